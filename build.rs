@@ -1,4 +1,18 @@
+use std::env;
+use std::path::PathBuf;
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_prost_build::compile_protos("proto/registry.proto")?;
+    // 同时输出文件描述符集，供 gRPC 反射服务使用
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    tonic_prost_build::configure()
+        .file_descriptor_set_path(out_dir.join("gateway_descriptor.bin"))
+        .compile_protos(
+            &[
+                "proto/registry.proto",
+                "proto/health.proto",
+                "proto/admin.proto",
+            ],
+            &["proto"],
+        )?;
     Ok(())
 }
@@ -1,17 +1,74 @@
+use arc_swap::ArcSwap;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 
+use crate::services::client::{ClientTlsSettings, Http2Settings};
+use crate::services::client_manager::EvictionPolicy;
 use crate::services::event::EventConfig;
+use crate::services::router::header_injection::HeaderInjectionPolicy;
+use crate::services::router::load_balance::LoadBalanceStrategy;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub security: SecurityConfig,
     pub router: RouterConfig,
     pub connection_pool: ConnectionPoolConfig,
+    // 反向连接和事件总线配置，缺省时使用默认值，可由环境变量单独覆盖
+    #[serde(default)]
     pub reverse_connection: ReverseConnectionConfig,
+    #[serde(default)]
     pub event: EventConfig,
     pub server: ServerConfig,
+    // Prometheus 指标端点配置
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    // 正向注册实例的主动健康检查配置
+    #[serde(default)]
+    pub health_check: HealthCheckConfig,
+    // 注册表快照持久化配置
+    #[serde(default)]
+    pub persistence: PersistenceConfig,
+    // 动态路由限流配置
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    // gRPC 消息压缩配置
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    // 浏览器客户端的 gRPC-Web 支持
+    #[serde(default)]
+    pub grpc_web: GrpcWebConfig,
+    // 请求/响应调试日志
+    #[serde(default)]
+    pub payload_log: PayloadLogConfig,
+    // 监听端和出站连接的 HTTP/2 keepalive 与流控窗口
+    #[serde(default)]
+    pub http2: Http2Config,
+    // 监听端 TLS 配置，未配置时使用明文 h2c
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+}
+
+// 运行中共享的配置句柄，所有克隆指向同一份配置，热更新时整体替换
+#[derive(Debug, Clone)]
+pub struct SharedConfig(Arc<ArcSwap<Config>>);
+
+impl SharedConfig {
+    pub fn new(config: Config) -> Self {
+        Self(Arc::new(ArcSwap::from_pointee(config)))
+    }
+
+    // 当前配置的快照，单个请求内应使用同一份快照
+    pub fn load(&self) -> Arc<Config> {
+        self.0.load_full()
+    }
+
+    pub fn store(&self, config: Config) {
+        self.0.store(Arc::new(config));
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,57 +77,890 @@ pub struct RouterConfig {
     pub request_timeout: u64,
     pub retry_attempts: u32,
     pub max_concurrent_requests: usize,
+    // 按服务覆盖的请求超时（秒），键为完整服务名，例如 "amwaybot.Foo"
+    #[serde(default)]
+    pub per_service_timeouts: HashMap<String, u64>,
+    // 单个请求/响应体的最大字节数
+    #[serde(default = "default_max_body_size")]
+    pub max_body_size: usize,
+    // 按服务覆盖的消息体大小上限（字节），键为完整服务名
+    #[serde(default)]
+    pub per_service_max_body_sizes: HashMap<String, usize>,
+    // 路由时使用完整服务名（"package.ServiceName"），默认只使用 "ServiceName"
+    #[serde(default)]
+    pub use_full_service_name: bool,
+    // 按标签路由没有匹配实例时，是否回退到任意健康实例（否则返回 NOT_FOUND）
+    #[serde(default = "default_label_route_fallback")]
+    pub label_route_fallback: bool,
+    // 会话亲和请求头（例如 "x-session-key"），请求携带该头时按其值一致性哈希选择实例
+    #[serde(default)]
+    pub affinity_header: Option<String>,
+    // 实例选择粘滞窗口（毫秒）：窗口内同一客户端的请求优先选择上次选中的健康实例，0 表示关闭；
+    // 携带亲和头的请求仍按亲和头选择
+    #[serde(default)]
+    pub stickiness_window_ms: u64,
+    // 标识客户端的请求头，未配置或请求未携带时按对端 IP 区分客户端
+    #[serde(default)]
+    pub stickiness_header: Option<String>,
+    // 负载均衡策略：round_robin、random、least_connections、first_healthy，正向和反向连接共用，支持热更新
+    #[serde(default)]
+    pub load_balance_strategy: LoadBalanceStrategy,
+    // 慢请求阈值（毫秒），转发耗时超过该值时输出 WARN 日志并计入慢请求指标，0 表示关闭
+    #[serde(default = "default_slow_request_threshold_ms")]
+    pub slow_request_threshold_ms: u64,
+    // 流量镜像：按采样率把一元请求复制一份发给影子实例，键为完整服务名，影子响应不会返回给调用方
+    #[serde(default)]
+    pub shadow_targets: HashMap<String, ShadowTarget>,
+    // 服务别名：旧完整服务名 -> 新完整服务名，服务改名后旧客户端仍可按旧名调用
+    #[serde(default)]
+    pub service_aliases: HashMap<String, String>,
+    // 命中别名时转发的方法路径是否改写为新服务名，否则保留调用方的原始路径
+    #[serde(default = "default_true")]
+    pub rewrite_aliased_path: bool,
+    // 为重试缓存请求体：不超过 retry_buffer_max_size 的请求体先读入内存，后端收到请求体后失败仍可重放到其他实例
+    #[serde(default)]
+    pub buffer_request_for_retry: bool,
+    // 重试缓存的请求体上限（字节），超过时按流式转发，请求体发出后不再重试
+    #[serde(default = "default_retry_buffer_max_size")]
+    pub retry_buffer_max_size: usize,
+    // 缓存小响应：不超过 response_buffer_max_size 的响应体读完后再返回并带上 content-length，供无法处理 Trailers-Only 或需要长度的客户端使用
+    #[serde(default)]
+    pub buffer_responses: bool,
+    // 缓存的响应体上限（字节），超过时按流式转发
+    #[serde(default = "default_response_buffer_max_size")]
+    pub response_buffer_max_size: usize,
+    // 除网关自身的服务外，动态路由不转发的服务：完整服务名或以 * 结尾的前缀
+    #[serde(default)]
+    pub reserved_services: Vec<String>,
+    // 按完整服务名注入转发请求的固定请求头（头名称 -> 值），调用方无需发送
+    #[serde(default)]
+    pub injected_headers: HashMap<String, HashMap<String, String>>,
+    // 注入的头与调用方发送的同名头冲突时覆盖（override）还是保留调用方的值（supplement）
+    #[serde(default)]
+    pub injected_header_policy: HeaderInjectionPolicy,
+    // 兜底后端地址：注册表中找不到的服务转发到该地址，用于分批迁移期间旧服务仍可访问
+    #[serde(default)]
+    pub default_backend: Option<String>,
+    // 允许对冲的方法路径（如 /post.PostService/GetPost，或以 * 结尾的前缀），只应列出只读、幂等的方法
+    #[serde(default)]
+    pub hedged_methods: Vec<String>,
+    // 对冲延迟（毫秒）：主请求在该时间内没有响应时向另一个健康实例再发一次，0 表示关闭
+    #[serde(default = "default_hedge_delay_ms")]
+    pub hedge_delay_ms: u64,
+    // 新实例的预热期（毫秒）：正向注册的新实例在预热期结束或首次主动健康检查通过前不参与路由，0 表示关闭
+    #[serde(default)]
+    pub initial_routing_delay_ms: u64,
+}
+
+// 请求头名称转为小写，空字符串视为未配置
+fn normalize_header(header: Option<&str>) -> Option<String> {
+    header
+        .map(|header| header.trim().to_ascii_lowercase())
+        .filter(|header| !header.is_empty())
+}
+
+// 影子实例及采样率（0.0 ~ 1.0）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShadowTarget {
+    pub address: String,
+    #[serde(default = "default_shadow_sample_rate")]
+    pub sample_rate: f64,
+}
+
+fn default_shadow_sample_rate() -> f64 {
+    1.0
+}
+
+// 环境变量中的格式为 address 或 address@sample_rate
+impl std::str::FromStr for ShadowTarget {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (address, sample_rate) = match value.rsplit_once('@') {
+            Some((address, rate)) => (
+                address,
+                rate.trim()
+                    .parse::<f64>()
+                    .map_err(|e| format!("invalid sample rate '{rate}': {e}"))?,
+            ),
+            None => (value, default_shadow_sample_rate()),
+        };
+        if address.trim().is_empty() {
+            return Err("missing shadow address".to_string());
+        }
+        Ok(Self {
+            address: address.trim().to_string(),
+            sample_rate,
+        })
+    }
+}
+
+impl RouterConfig {
+    // 规范化后的亲和头名称，未配置或为空时返回 None
+    pub fn affinity_header(&self) -> Option<String> {
+        normalize_header(self.affinity_header.as_deref())
+    }
+
+    // 规范化后的客户端标识头名称，未配置或为空时返回 None
+    pub fn stickiness_header(&self) -> Option<String> {
+        normalize_header(self.stickiness_header.as_deref())
+    }
+
+    // 兜底后端地址，未配置或为空时返回 None
+    pub fn default_backend(&self) -> Option<&str> {
+        self.default_backend
+            .as_deref()
+            .map(str::trim)
+            .filter(|address| !address.is_empty())
+    }
+
+    // 方法允许对冲时返回对冲延迟，未列入 hedged_methods 或延迟为 0 时返回 None
+    pub fn hedge_delay(&self, method_path: &str) -> Option<Duration> {
+        (self.hedge_delay_ms > 0
+            && self
+                .hedged_methods
+                .iter()
+                .any(|pattern| SecurityConfig::pattern_matches(pattern, method_path)))
+        .then(|| Duration::from_millis(self.hedge_delay_ms))
+    }
+
+    // 新实例的预热期，未开启时返回 None
+    pub fn initial_routing_delay(&self) -> Option<Duration> {
+        (self.initial_routing_delay_ms > 0)
+            .then(|| Duration::from_millis(self.initial_routing_delay_ms))
+    }
+
+    // 实例选择粘滞窗口，未开启时返回 None
+    pub fn stickiness_window(&self) -> Option<Duration> {
+        (self.stickiness_window_ms > 0).then(|| Duration::from_millis(self.stickiness_window_ms))
+    }
+
+    // 慢请求阈值，未开启时返回 None
+    pub fn slow_request_threshold(&self) -> Option<Duration> {
+        (self.slow_request_threshold_ms > 0)
+            .then(|| Duration::from_millis(self.slow_request_threshold_ms))
+    }
+}
+
+fn default_retry_buffer_max_size() -> usize {
+    64 * 1024 // 64KB
+}
+
+fn default_response_buffer_max_size() -> usize {
+    64 * 1024 // 64KB
+}
+
+fn default_hedge_delay_ms() -> u64 {
+    100
+}
+
+fn default_label_route_fallback() -> bool {
+    true
+}
+
+fn default_slow_request_threshold_ms() -> u64 {
+    1000
+}
+
+fn default_max_body_size() -> usize {
+    100 * 1024 * 1024 // 100MB
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectionPoolConfig {
     pub max_connections: usize,
+    // 每个后端地址最多建立的 HTTP/2 通道数，请求在这些通道间轮询
+    #[serde(default = "default_connections_per_address")]
+    pub connections_per_address: usize,
+    // 连接池满时的淘汰策略：lru（默认）、lfu 或 oldest
+    #[serde(default)]
+    pub eviction_policy: EvictionPolicy,
     pub connection_ttl: u64,
     pub idle_timeout: u64,
     pub cleanup_interval: u64,
+    // 连续失败多少次后熔断该后端地址
+    #[serde(default = "default_circuit_failure_threshold")]
+    pub circuit_failure_threshold: u32,
+    // 熔断后进入半开探测前的冷却时间（秒）
+    #[serde(default = "default_circuit_cooldown")]
+    pub circuit_cooldown: u64,
+    // 对 http 地址的后端也强制使用 TLS（https 地址总是使用 TLS）
+    #[serde(default)]
+    pub tls_enabled: bool,
+    // 校验后端证书的 CA 证书路径，未配置时使用内置根证书
+    #[serde(default)]
+    pub tls_ca_path: Option<String>,
+    // 连接后端时使用的客户端证书和私钥路径（mTLS）
+    #[serde(default)]
+    pub tls_client_cert_path: Option<String>,
+    #[serde(default)]
+    pub tls_client_key_path: Option<String>,
+    // 正向注册新实例时预先建立到其地址的连接
+    #[serde(default = "default_true")]
+    pub warmup_on_register: bool,
+}
+
+impl ConnectionPoolConfig {
+    // 读取证书文件构建后端出站 TLS 配置
+    pub fn client_tls(&self) -> Result<ClientTlsSettings, Box<dyn std::error::Error>> {
+        ClientTlsSettings::from_files(
+            self.tls_enabled,
+            self.tls_ca_path.as_deref(),
+            self.tls_client_cert_path.as_deref(),
+            self.tls_client_key_path.as_deref(),
+        )
+        .map_err(|e| format!("Failed to load backend TLS configuration: {e}").into())
+    }
+}
+
+fn default_connections_per_address() -> usize {
+    1
+}
+
+fn default_circuit_failure_threshold() -> u32 {
+    5
+}
+
+fn default_circuit_cooldown() -> u64 {
+    30
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReverseConnectionConfig {
+    #[serde(default = "default_reverse_heartbeat_timeout")]
     pub heartbeat_timeout: u64,
+    #[serde(default = "default_reverse_request_timeout")]
     pub request_timeout: u64,
+    #[serde(default = "default_reverse_cleanup_interval")]
     pub cleanup_interval: u64,
+    #[serde(default = "default_max_pending_requests")]
     pub max_pending_requests: usize,
+    // 流式响应两个数据块之间允许的最长间隔（秒），超过后放弃该响应
+    #[serde(default = "default_stream_idle_timeout")]
+    pub stream_idle_timeout: u64,
+    // 每个反向连接待发送消息队列的容量，队列满时新请求立即失败
+    #[serde(default = "default_request_channel_capacity")]
+    pub request_channel_capacity: usize,
+    // 是否用 gzip 压缩反向连接上转发的请求消息，并允许微服务返回 gzip 压缩的响应
+    #[serde(default)]
+    pub gzip_payload: bool,
+    // 网关主动向每个反向连接发送 Ping 的间隔（秒），0 表示关闭；
+    // 超过 heartbeat_timeout 的三分之一时按三分之一计算
+    #[serde(default = "default_ping_interval")]
+    pub ping_interval: u64,
+    // 微服务响应（包括组装后的流式响应）的大小上限（字节），0 表示只受 router.max_body_size 约束
+    #[serde(default)]
+    pub max_response_size: usize,
+    // 反向连接的最长存活时间（秒），超过后网关通知微服务重连，0 表示不限制
+    #[serde(default)]
+    pub max_connection_age: u64,
+    // 单个反向连接注册消息中允许声明的服务数量上限，0 表示不限制
+    #[serde(default = "default_max_services_per_connection")]
+    pub max_services_per_connection: usize,
+    // 同一调用方（token 校验得到的 subject）同时建立的反向连接数量上限，0 表示不限制
+    #[serde(default)]
+    pub max_connections_per_token: usize,
+    // 连接表、服务池与注册表一致性校对的间隔（秒），0 表示关闭
+    #[serde(default = "default_reconcile_interval")]
+    pub reconcile_interval: u64,
+    // 单个反向连接上同时进行的请求数上限，达到上限的连接不再被选中，0 表示不限制
+    #[serde(default)]
+    pub max_in_flight_per_connection: usize,
+    // 连接全部达到并发上限时请求排队等待名额的最长时间（毫秒），不超过请求本身的超时，0 表示不排队直接失败
+    #[serde(default)]
+    pub saturation_queue_wait_ms: u64,
+    // 每个服务同时排队的请求数上限，队列已满时新请求直接失败
+    #[serde(default = "default_saturation_queue_capacity")]
+    pub saturation_queue_capacity: usize,
+}
+
+impl Default for ReverseConnectionConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat_timeout: default_reverse_heartbeat_timeout(),
+            request_timeout: default_reverse_request_timeout(),
+            cleanup_interval: default_reverse_cleanup_interval(),
+            max_pending_requests: default_max_pending_requests(),
+            stream_idle_timeout: default_stream_idle_timeout(),
+            request_channel_capacity: default_request_channel_capacity(),
+            gzip_payload: false,
+            ping_interval: default_ping_interval(),
+            max_response_size: 0,
+            max_connection_age: 0,
+            max_services_per_connection: default_max_services_per_connection(),
+            max_connections_per_token: 0,
+            reconcile_interval: default_reconcile_interval(),
+            max_in_flight_per_connection: 0,
+            saturation_queue_wait_ms: 0,
+            saturation_queue_capacity: default_saturation_queue_capacity(),
+        }
+    }
+}
+
+fn default_reverse_heartbeat_timeout() -> u64 {
+    120
+}
+
+fn default_reverse_request_timeout() -> u64 {
+    30
+}
+
+fn default_reverse_cleanup_interval() -> u64 {
+    60
+}
+
+fn default_reconcile_interval() -> u64 {
+    300
+}
+
+fn default_max_pending_requests() -> usize {
+    1000
+}
+
+fn default_stream_idle_timeout() -> u64 {
+    30
+}
+
+fn default_ping_interval() -> u64 {
+    30
+}
+
+fn default_request_channel_capacity() -> usize {
+    1024
+}
+
+fn default_saturation_queue_capacity() -> usize {
+    100
+}
+
+fn default_max_services_per_connection() -> usize {
+    256
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
+    // 监听地址：host:port，或 unix:/path/to/socket 形式的 unix domain socket
     pub address: String,
     pub log_level: String,
+    // 日志输出格式，text 为人类可读格式，json 便于日志采集
+    #[serde(default)]
+    pub log_format: LogFormat,
+    // 停机时等待进行中请求完成的宽限期（秒），超过后仍在等待的反向请求以 UNAVAILABLE 失败
+    #[serde(default = "default_shutdown_grace_period")]
+    pub shutdown_grace_period: u64,
+    // 检查配置文件是否被修改的间隔（秒），0 表示只在收到 SIGHUP 时重新加载
+    #[serde(default = "default_config_reload_interval")]
+    pub config_reload_interval: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+fn default_shutdown_grace_period() -> u64 {
+    30
+}
+
+fn default_config_reload_interval() -> u64 {
+    5
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheckConfig {
+    // 是否周期性探测已注册地址的 grpc.health.v1.Health/Check
+    #[serde(default)]
+    pub enabled: bool,
+    // 探测间隔（秒）
+    #[serde(default = "default_health_check_interval")]
+    pub interval: u64,
+    // 单次探测超时（秒）
+    #[serde(default = "default_health_check_timeout")]
+    pub timeout: u64,
+    // 连续失败多少次后标记为 Unhealthy
+    #[serde(default = "default_health_check_failure_threshold")]
+    pub failure_threshold: u32,
+}
+
+fn default_health_check_interval() -> u64 {
+    10
+}
+
+fn default_health_check_timeout() -> u64 {
+    3
+}
+
+fn default_health_check_failure_threshold() -> u32 {
+    3
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: default_health_check_interval(),
+            timeout: default_health_check_timeout(),
+            failure_threshold: default_health_check_failure_threshold(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistenceConfig {
+    // 快照文件路径，未配置时不持久化注册表
+    #[serde(default)]
+    pub snapshot_path: Option<String>,
+    // 快照写入间隔（秒）
+    #[serde(default = "default_snapshot_interval")]
+    pub snapshot_interval: u64,
+    // 从快照恢复的实例在未收到心跳前保留的时间（秒）
+    #[serde(default = "default_snapshot_grace_period")]
+    pub snapshot_grace_period: u64,
+}
+
+fn default_snapshot_interval() -> u64 {
+    30
+}
+
+fn default_snapshot_grace_period() -> u64 {
+    30
+}
+
+impl Default for PersistenceConfig {
+    fn default() -> Self {
+        Self {
+            snapshot_path: None,
+            snapshot_interval: default_snapshot_interval(),
+            snapshot_grace_period: default_snapshot_grace_period(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressionConfig {
+    // 客户端声明接受 gzip 而后端返回未压缩的消息时，由网关压缩后返回
+    #[serde(default = "default_true")]
+    pub gzip_responses: bool,
+    // 小于该大小（字节）的消息不压缩
+    #[serde(default = "default_compression_min_size")]
+    pub min_size: usize,
+}
+
+fn default_compression_min_size() -> usize {
+    1024
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            gzip_responses: true,
+            min_size: default_compression_min_size(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    // 是否在动态路由入口按令牌桶限流
+    #[serde(default)]
+    pub enabled: bool,
+    // 每个桶每秒补充的请求数
+    #[serde(default = "default_rate_limit_requests_per_second")]
+    pub requests_per_second: f64,
+    // 桶容量，即允许的突发请求数
+    #[serde(default = "default_rate_limit_burst")]
+    pub burst: u32,
+    // 是否按调用方 token 区分桶
+    #[serde(default = "default_true")]
+    pub per_token: bool,
+    // 是否按目标服务区分桶
+    #[serde(default = "default_true")]
+    pub per_service: bool,
+    // 携带调用方 token 的请求头（metadata）
+    #[serde(default = "default_rate_limit_token_header")]
+    pub token_header: String,
+}
+
+fn default_rate_limit_requests_per_second() -> f64 {
+    100.0
+}
+
+fn default_rate_limit_burst() -> u32 {
+    200
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_rate_limit_token_header() -> String {
+    "x-api-key".to_string()
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            requests_per_second: default_rate_limit_requests_per_second(),
+            burst: default_rate_limit_burst(),
+            per_token: true,
+            per_service: true,
+            token_header: default_rate_limit_token_header(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    // 是否启用独立端口上的 /metrics 端点
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_metrics_address")]
+    pub address: String,
+}
+
+fn default_metrics_address() -> String {
+    "0.0.0.0:9090".to_string()
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: default_metrics_address(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcWebConfig {
+    // 是否接受 application/grpc-web(+proto) 和 grpc-web-text 请求
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    // 是否处理跨域预检请求，浏览器页面与网关不同源时需要开启
+    #[serde(default)]
+    pub cors_enabled: bool,
+    // 允许的来源，为空时允许任意来源
+    #[serde(default)]
+    pub cors_allowed_origins: Vec<String>,
+    // 预检结果的缓存时间（秒）
+    #[serde(default = "default_cors_max_age")]
+    pub cors_max_age: u64,
+}
+
+fn default_cors_max_age() -> u64 {
+    86400
+}
+
+impl Default for GrpcWebConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            cors_enabled: false,
+            cors_allowed_origins: Vec::new(),
+            cors_max_age: default_cors_max_age(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayloadLogConfig {
+    // 是否在动态路由入口和出口输出请求/响应日志（target 为 payload），仅用于排查问题
+    #[serde(default)]
+    pub enabled: bool,
+    // 值被替换为 [REDACTED] 的请求头/响应头，不区分大小写
+    #[serde(default = "default_payload_log_redact_headers")]
+    pub redact_headers: Vec<String>,
+    // 消息体预览的最大字节数，超出部分截断；0 表示不记录消息体
+    #[serde(default)]
+    pub max_payload_bytes: usize,
+    // 消息体预览的编码方式
+    #[serde(default)]
+    pub payload_encoding: PayloadEncoding,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+fn default_payload_log_redact_headers() -> Vec<String> {
+    ["authorization", "cookie", "set-cookie", "x-api-key"]
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+impl Default for PayloadLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            redact_headers: default_payload_log_redact_headers(),
+            max_payload_bytes: 0,
+            payload_encoding: PayloadEncoding::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Http2Config {
+    // 发送 HTTP/2 PING 的间隔（秒），连接空闲时同样发送；0 表示不发送
+    #[serde(default)]
+    pub http2_keepalive_interval: u64,
+    // 等待 PING 响应的超时时间（秒），超时后关闭连接
+    #[serde(default = "default_http2_keepalive_timeout")]
+    pub http2_keepalive_timeout: u64,
+    // 单个流的初始窗口大小（字节），0 表示使用默认值
+    #[serde(default)]
+    pub initial_stream_window: u32,
+    // 整个连接的初始窗口大小（字节），0 表示使用默认值
+    #[serde(default)]
+    pub initial_connection_window: u32,
+}
+
+fn default_http2_keepalive_timeout() -> u64 {
+    20
+}
+
+impl Default for Http2Config {
+    fn default() -> Self {
+        Self {
+            http2_keepalive_interval: 0,
+            http2_keepalive_timeout: default_http2_keepalive_timeout(),
+            initial_stream_window: 0,
+            initial_connection_window: 0,
+        }
+    }
+}
+
+impl Http2Config {
+    // 监听端和出站 Endpoint 共用的 HTTP/2 参数
+    pub fn settings(&self) -> Http2Settings {
+        let non_zero = |value: u32| (value > 0).then_some(value);
+        Http2Settings {
+            keepalive_interval: (self.http2_keepalive_interval > 0)
+                .then(|| Duration::from_secs(self.http2_keepalive_interval)),
+            keepalive_timeout: Some(Duration::from_secs(self.http2_keepalive_timeout)),
+            initial_stream_window: non_zero(self.initial_stream_window),
+            initial_connection_window: non_zero(self.initial_connection_window),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsConfig {
+    // PEM 格式的服务端证书路径
+    pub cert_path: String,
+    // PEM 格式的服务端私钥路径
+    pub key_path: String,
+    // 客户端 CA 证书路径，配置后要求并校验客户端证书（mTLS）
+    #[serde(default)]
+    pub client_ca_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SecurityConfig {
+    // 不限服务范围的 token，可以注册任意服务
     pub tokens: Vec<String>,
+    // 限定服务范围的 token -> 允许注册的服务名模式
+    // 模式为完整服务名、以 * 结尾的前缀（如 post.*）或单独的 *
+    #[serde(default)]
+    pub scoped_tokens: HashMap<String, Vec<String>>,
+    // 管理接口专用 token，与注册 token 相互独立
+    #[serde(default)]
+    pub admin_tokens: Vec<String>,
+    // 关闭注册服务所有 RPC 的 token 校验，仅用于本地开发；默认校验，未配置 token 时拒绝所有注册
+    #[serde(default)]
+    pub auth_disabled: bool,
+    // 注册和建立反向连接的审计记录除了输出到 audit 日志外，是否同时发布为 gateway.audit.registration 事件
+    #[serde(default)]
+    pub audit_events: bool,
+    // JWT 校验，启用后注册和建立反向连接可以使用签名的短期 JWT 代替静态 token
+    #[serde(default)]
+    pub jwt: JwtConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JwtConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    // HS256 共享密钥，为空时不接受 HS256 签名的 JWT
+    #[serde(default)]
+    pub hs256_secret: String,
+    // RS256 公钥所在的 JWKS 地址（http 或 https），为空时不接受 RS256 签名的 JWT
+    #[serde(default)]
+    pub jwks_url: String,
+    // 校验 https JWKS 地址证书的 CA 文件（PEM），网关不内置根证书
+    #[serde(default)]
+    pub jwks_ca_path: Option<String>,
+    // JWKS 缓存的刷新间隔（秒），遇到未知的 kid 时也会提前刷新
+    #[serde(default = "default_jwks_refresh_interval")]
+    pub jwks_refresh_interval: u64,
+    // 要求的 iss，为空时不校验
+    #[serde(default)]
+    pub issuer: String,
+    // 存放允许注册的服务名模式的 claim，可以是字符串数组或空格分隔的字符串
+    #[serde(default = "default_jwt_scope_claim")]
+    pub scope_claim: String,
+    // 校验 exp 和 nbf 时允许的时钟偏差（秒）
+    #[serde(default = "default_jwt_leeway")]
+    pub leeway: u64,
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hs256_secret: String::new(),
+            jwks_url: String::new(),
+            jwks_ca_path: None,
+            jwks_refresh_interval: default_jwks_refresh_interval(),
+            issuer: String::new(),
+            scope_claim: default_jwt_scope_claim(),
+            leeway: default_jwt_leeway(),
+        }
+    }
+}
+
+fn default_jwks_refresh_interval() -> u64 {
+    300
+}
+
+fn default_jwt_scope_claim() -> String {
+    "services".to_string()
+}
+
+fn default_jwt_leeway() -> u64 {
+    30
+}
+
+impl SecurityConfig {
+    pub(crate) fn pattern_matches(pattern: &str, service_name: &str) -> bool {
+        match pattern.strip_suffix('*') {
+            Some(prefix) => service_name.starts_with(prefix),
+            None => pattern == service_name,
+        }
+    }
 }
 
 // 环境变量配置结构
 #[derive(Debug, Deserialize)]
 struct EnvConfig {
     #[serde(default)]
-    grpc_security_tokens: Option<String>,
+    grpc_security_tokens: Option<String>,
+    #[serde(default)]
+    grpc_security_scoped_tokens: Option<String>,
+    #[serde(default)]
+    grpc_security_admin_tokens: Option<String>,
+    #[serde(default)]
+    grpc_security_auth_disabled: Option<bool>,
+    #[serde(default)]
+    grpc_security_audit_events: Option<bool>,
+    #[serde(default)]
+    grpc_security_jwt_enabled: Option<bool>,
+    #[serde(default)]
+    grpc_security_jwt_hs256_secret: Option<String>,
+    #[serde(default)]
+    grpc_security_jwt_jwks_url: Option<String>,
+    #[serde(default)]
+    grpc_security_jwt_jwks_ca_path: Option<String>,
+    #[serde(default)]
+    grpc_security_jwt_jwks_refresh_interval: Option<u64>,
+    #[serde(default)]
+    grpc_security_jwt_issuer: Option<String>,
+    #[serde(default)]
+    grpc_security_jwt_scope_claim: Option<String>,
+    #[serde(default)]
+    grpc_security_jwt_leeway: Option<u64>,
+    #[serde(default)]
+    grpc_router_heartbeat_timeout: Option<u64>,
+    #[serde(default)]
+    grpc_router_request_timeout: Option<u64>,
+    #[serde(default)]
+    grpc_router_retry_attempts: Option<u32>,
+    #[serde(default)]
+    grpc_router_max_concurrent_requests: Option<usize>,
+    #[serde(default)]
+    grpc_router_service_timeouts: Option<String>,
+    #[serde(default)]
+    grpc_router_use_full_service_name: Option<bool>,
+    #[serde(default)]
+    grpc_router_label_route_fallback: Option<bool>,
+    #[serde(default)]
+    grpc_router_affinity_header: Option<String>,
+    #[serde(default)]
+    grpc_router_stickiness_window_ms: Option<u64>,
+    #[serde(default)]
+    grpc_router_stickiness_header: Option<String>,
+    #[serde(default)]
+    grpc_router_load_balance_strategy: Option<LoadBalanceStrategy>,
+    #[serde(default)]
+    grpc_router_slow_request_threshold_ms: Option<u64>,
+    #[serde(default)]
+    grpc_router_max_body_size: Option<usize>,
     #[serde(default)]
-    grpc_router_heartbeat_timeout: Option<u64>,
+    grpc_router_buffer_request_for_retry: Option<bool>,
     #[serde(default)]
-    grpc_router_request_timeout: Option<u64>,
+    grpc_router_retry_buffer_max_size: Option<usize>,
     #[serde(default)]
-    grpc_router_retry_attempts: Option<u32>,
+    grpc_router_buffer_responses: Option<bool>,
     #[serde(default)]
-    grpc_router_max_concurrent_requests: Option<usize>,
+    grpc_router_response_buffer_max_size: Option<usize>,
+    #[serde(default)]
+    grpc_router_service_max_body_sizes: Option<String>,
+    #[serde(default)]
+    grpc_router_shadow_targets: Option<String>,
+    #[serde(default)]
+    grpc_router_service_aliases: Option<String>,
+    #[serde(default)]
+    grpc_router_reserved_services: Option<String>,
+    #[serde(default)]
+    grpc_router_injected_headers: Option<String>,
+    #[serde(default)]
+    grpc_router_injected_header_policy: Option<HeaderInjectionPolicy>,
+    #[serde(default)]
+    grpc_router_default_backend: Option<String>,
+    #[serde(default)]
+    grpc_router_hedged_methods: Option<String>,
+    #[serde(default)]
+    grpc_router_hedge_delay_ms: Option<u64>,
+    #[serde(default)]
+    grpc_router_initial_routing_delay_ms: Option<u64>,
+    #[serde(default)]
+    grpc_router_rewrite_aliased_path: Option<bool>,
     #[serde(default)]
     grpc_pool_max_connections: Option<usize>,
     #[serde(default)]
+    grpc_pool_connections_per_address: Option<usize>,
+    #[serde(default)]
+    grpc_pool_eviction_policy: Option<EvictionPolicy>,
+    #[serde(default)]
     grpc_pool_connection_ttl: Option<u64>,
     #[serde(default)]
     grpc_pool_idle_timeout: Option<u64>,
     #[serde(default)]
     grpc_pool_cleanup_interval: Option<u64>,
     #[serde(default)]
+    grpc_pool_circuit_failure_threshold: Option<u32>,
+    #[serde(default)]
+    grpc_pool_circuit_cooldown: Option<u64>,
+    #[serde(default)]
+    grpc_pool_tls_enabled: Option<bool>,
+    #[serde(default)]
+    grpc_pool_tls_ca_path: Option<String>,
+    #[serde(default)]
+    grpc_pool_tls_client_cert_path: Option<String>,
+    #[serde(default)]
+    grpc_pool_tls_client_key_path: Option<String>,
+    #[serde(default)]
+    grpc_pool_warmup_on_register: Option<bool>,
+    #[serde(default)]
     grpc_reverse_heartbeat_timeout: Option<u64>,
     #[serde(default)]
     grpc_reverse_request_timeout: Option<u64>,
@@ -79,18 +969,163 @@ struct EnvConfig {
     #[serde(default)]
     grpc_reverse_max_pending_requests: Option<usize>,
     #[serde(default)]
+    grpc_reverse_stream_idle_timeout: Option<u64>,
+    #[serde(default)]
+    grpc_reverse_ping_interval: Option<u64>,
+    #[serde(default)]
+    grpc_reverse_request_channel_capacity: Option<usize>,
+    #[serde(default)]
+    grpc_reverse_gzip_payload: Option<bool>,
+    #[serde(default)]
+    grpc_reverse_max_response_size: Option<usize>,
+    #[serde(default)]
+    grpc_reverse_max_connection_age: Option<u64>,
+    #[serde(default)]
+    grpc_reverse_max_services_per_connection: Option<usize>,
+    #[serde(default)]
+    grpc_reverse_max_connections_per_token: Option<usize>,
+    #[serde(default)]
+    grpc_reverse_reconcile_interval: Option<u64>,
+    #[serde(default)]
+    grpc_reverse_max_in_flight_per_connection: Option<usize>,
+    #[serde(default)]
+    grpc_reverse_saturation_queue_wait_ms: Option<u64>,
+    #[serde(default)]
+    grpc_reverse_saturation_queue_capacity: Option<usize>,
+    #[serde(default)]
     grpc_server_address: Option<String>,
     #[serde(default)]
     grpc_log_level: Option<String>,
+    #[serde(default)]
+    grpc_log_format: Option<LogFormat>,
+    #[serde(default)]
+    grpc_server_shutdown_grace_period: Option<u64>,
+    #[serde(default)]
+    grpc_server_config_reload_interval: Option<u64>,
+    #[serde(default)]
+    grpc_metrics_enabled: Option<bool>,
+    #[serde(default)]
+    grpc_metrics_address: Option<String>,
+    #[serde(default)]
+    grpc_web_enabled: Option<bool>,
+    #[serde(default)]
+    grpc_web_cors_enabled: Option<bool>,
+    #[serde(default)]
+    grpc_web_cors_allowed_origins: Option<String>,
+    #[serde(default)]
+    grpc_web_cors_max_age: Option<u64>,
+    #[serde(default)]
+    grpc_event_max_subscribers_per_type: Option<usize>,
+    #[serde(default)]
+    grpc_event_channel_capacity: Option<usize>,
+    #[serde(default)]
+    grpc_event_type_capacities: Option<String>,
+    #[serde(default)]
+    grpc_event_max_history: Option<usize>,
+    #[serde(default)]
+    grpc_event_ttl_seconds: Option<u64>,
+    #[serde(default)]
+    grpc_event_enable_metrics: Option<bool>,
+    #[serde(default)]
+    grpc_event_skip_lagged_events: Option<bool>,
+    #[serde(default)]
+    grpc_event_reliable_delivery: Option<bool>,
+    #[serde(default)]
+    grpc_event_reliable_queue_capacity: Option<usize>,
+    #[serde(default)]
+    grpc_event_subscriber_idle_ttl_seconds: Option<u64>,
+    #[serde(default)]
+    grpc_health_check_enabled: Option<bool>,
+    #[serde(default)]
+    grpc_health_check_interval: Option<u64>,
+    #[serde(default)]
+    grpc_health_check_timeout: Option<u64>,
+    #[serde(default)]
+    grpc_health_check_failure_threshold: Option<u32>,
+    #[serde(default)]
+    grpc_persistence_snapshot_path: Option<String>,
+    #[serde(default)]
+    grpc_persistence_snapshot_interval: Option<u64>,
+    #[serde(default)]
+    grpc_persistence_snapshot_grace_period: Option<u64>,
+    #[serde(default)]
+    grpc_rate_limit_enabled: Option<bool>,
+    #[serde(default)]
+    grpc_rate_limit_requests_per_second: Option<f64>,
+    #[serde(default)]
+    grpc_rate_limit_burst: Option<u32>,
+    #[serde(default)]
+    grpc_rate_limit_per_token: Option<bool>,
+    #[serde(default)]
+    grpc_rate_limit_per_service: Option<bool>,
+    #[serde(default)]
+    grpc_rate_limit_token_header: Option<String>,
+    #[serde(default)]
+    grpc_compression_gzip_responses: Option<bool>,
+    #[serde(default)]
+    grpc_compression_min_size: Option<usize>,
+    #[serde(default)]
+    grpc_payload_log_enabled: Option<bool>,
+    #[serde(default)]
+    grpc_payload_log_redact_headers: Option<String>,
+    #[serde(default)]
+    grpc_payload_log_max_payload_bytes: Option<usize>,
+    #[serde(default)]
+    grpc_payload_log_payload_encoding: Option<PayloadEncoding>,
+    #[serde(default)]
+    grpc_http2_keepalive_interval: Option<u64>,
+    #[serde(default)]
+    grpc_http2_keepalive_timeout: Option<u64>,
+    #[serde(default)]
+    grpc_http2_initial_stream_window: Option<u32>,
+    #[serde(default)]
+    grpc_http2_initial_connection_window: Option<u32>,
+    #[serde(default)]
+    grpc_tls_cert_path: Option<String>,
+    #[serde(default)]
+    grpc_tls_key_path: Option<String>,
+    #[serde(default)]
+    grpc_tls_client_ca_path: Option<String>,
 }
 
+// 默认配置文件路径
+pub const CONFIG_PATH: &str = "config.toml";
+
+// 修改后需要重启才能生效的配置项（JSON Pointer），其余配置项支持热更新
+const RESTART_REQUIRED_FIELDS: &[&str] = &[
+    "/security/auth_disabled",
+    "/security/jwt",
+    "/server",
+    "/tls",
+    "/metrics",
+    "/grpc_web",
+    "/http2",
+    "/event",
+    "/health_check",
+    "/persistence",
+    "/reverse_connection",
+    "/router/heartbeat_timeout",
+    "/router/max_concurrent_requests",
+    "/router/use_full_service_name",
+    "/router/affinity_header",
+    "/router/stickiness_window_ms",
+    "/router/stickiness_header",
+    "/router/service_aliases",
+    "/router/rewrite_aliased_path",
+    "/connection_pool/cleanup_interval",
+    "/connection_pool/tls_enabled",
+    "/connection_pool/tls_ca_path",
+    "/connection_pool/tls_client_cert_path",
+    "/connection_pool/tls_client_key_path",
+];
+
 impl Config {
     pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
         // 加载 .env 文件（如果存在）
         let _ = dotenvy::dotenv();
 
         // 从文件加载基础配置
-        let mut config = Self::load_from_file().unwrap_or_else(|_| Self::default());
+        let mut config = Self::load_from_file(CONFIG_PATH).unwrap_or_else(|_| Self::default());
 
         // 应用环境变量覆盖
         config.apply_env_overrides()?;
@@ -98,12 +1133,68 @@ impl Config {
         Ok(config)
     }
 
-    fn load_from_file() -> Result<Self, Box<dyn std::error::Error>> {
-        let config_str = fs::read_to_string("config.toml")?;
+    // 运行期间重新读取配置文件和环境变量
+    // 与启动时不同，文件存在但无法解析时返回错误，保留当前配置而不是退回默认值
+    pub fn reload_from(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        let mut config = if path.exists() {
+            Self::load_from_file(path)?
+        } else {
+            Self::default()
+        };
+        config.apply_env_overrides()?;
+        Ok(config)
+    }
+
+    fn load_from_file(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let config_str = fs::read_to_string(path)?;
         let config: Config = toml::from_str(&config_str)?;
         Ok(config)
     }
 
+    // 合并新配置中支持热更新的部分，需要重启的配置项保持当前值
+    pub fn with_live_fields_from(&self, new: Config) -> Config {
+        let mut merged = self.clone();
+        merged.security = SecurityConfig {
+            auth_disabled: self.security.auth_disabled,
+            jwt: self.security.jwt.clone(),
+            ..new.security
+        };
+        merged.rate_limit = new.rate_limit;
+        merged.router = RouterConfig {
+            heartbeat_timeout: self.router.heartbeat_timeout,
+            max_concurrent_requests: self.router.max_concurrent_requests,
+            use_full_service_name: self.router.use_full_service_name,
+            affinity_header: self.router.affinity_header.clone(),
+            stickiness_window_ms: self.router.stickiness_window_ms,
+            stickiness_header: self.router.stickiness_header.clone(),
+            service_aliases: self.router.service_aliases.clone(),
+            rewrite_aliased_path: self.router.rewrite_aliased_path,
+            ..new.router
+        };
+        merged.connection_pool = ConnectionPoolConfig {
+            cleanup_interval: self.connection_pool.cleanup_interval,
+            tls_enabled: self.connection_pool.tls_enabled,
+            tls_ca_path: self.connection_pool.tls_ca_path.clone(),
+            tls_client_cert_path: self.connection_pool.tls_client_cert_path.clone(),
+            tls_client_key_path: self.connection_pool.tls_client_key_path.clone(),
+            ..new.connection_pool
+        };
+        merged
+    }
+
+    // 与新配置相比发生变化、但需要重启才能生效的配置项
+    pub fn restart_required_changes(&self, new: &Config) -> Vec<&'static str> {
+        let (Ok(old), Ok(new)) = (serde_json::to_value(self), serde_json::to_value(new)) else {
+            return Vec::new();
+        };
+        RESTART_REQUIRED_FIELDS
+            .iter()
+            .copied()
+            .filter(|field| old.pointer(field) != new.pointer(field))
+            .collect()
+    }
+
     fn apply_env_overrides(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         let env_config: EnvConfig = envy::from_env()?;
 
@@ -115,6 +1206,46 @@ impl Config {
                 .filter(|s| !s.is_empty())
                 .collect();
         }
+        if let Some(val) = env_config.grpc_security_scoped_tokens {
+            self.security.scoped_tokens = Self::parse_scoped_tokens(&val)?;
+        }
+        if let Some(tokens_str) = env_config.grpc_security_admin_tokens {
+            self.security.admin_tokens = tokens_str
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+        if let Some(val) = env_config.grpc_security_auth_disabled {
+            self.security.auth_disabled = val;
+        }
+        if let Some(val) = env_config.grpc_security_audit_events {
+            self.security.audit_events = val;
+        }
+        if let Some(val) = env_config.grpc_security_jwt_enabled {
+            self.security.jwt.enabled = val;
+        }
+        if let Some(val) = env_config.grpc_security_jwt_hs256_secret {
+            self.security.jwt.hs256_secret = val;
+        }
+        if let Some(val) = env_config.grpc_security_jwt_jwks_url {
+            self.security.jwt.jwks_url = val;
+        }
+        if let Some(val) = env_config.grpc_security_jwt_jwks_ca_path {
+            self.security.jwt.jwks_ca_path = Some(val);
+        }
+        if let Some(val) = env_config.grpc_security_jwt_jwks_refresh_interval {
+            self.security.jwt.jwks_refresh_interval = val;
+        }
+        if let Some(val) = env_config.grpc_security_jwt_issuer {
+            self.security.jwt.issuer = val;
+        }
+        if let Some(val) = env_config.grpc_security_jwt_scope_claim {
+            self.security.jwt.scope_claim = val;
+        }
+        if let Some(val) = env_config.grpc_security_jwt_leeway {
+            self.security.jwt.leeway = val;
+        }
 
         // 路由配置覆盖
         if let Some(val) = env_config.grpc_router_heartbeat_timeout {
@@ -129,11 +1260,97 @@ impl Config {
         if let Some(val) = env_config.grpc_router_max_concurrent_requests {
             self.router.max_concurrent_requests = val;
         }
+        if let Some(val) = env_config.grpc_router_service_timeouts {
+            self.router.per_service_timeouts = Self::parse_service_overrides(&val)?;
+        }
+        if let Some(val) = env_config.grpc_router_use_full_service_name {
+            self.router.use_full_service_name = val;
+        }
+        if let Some(val) = env_config.grpc_router_label_route_fallback {
+            self.router.label_route_fallback = val;
+        }
+        if let Some(val) = env_config.grpc_router_affinity_header {
+            self.router.affinity_header = Some(val);
+        }
+        if let Some(val) = env_config.grpc_router_stickiness_window_ms {
+            self.router.stickiness_window_ms = val;
+        }
+        if let Some(val) = env_config.grpc_router_stickiness_header {
+            self.router.stickiness_header = Some(val);
+        }
+        if let Some(val) = env_config.grpc_router_load_balance_strategy {
+            self.router.load_balance_strategy = val;
+        }
+        if let Some(val) = env_config.grpc_router_slow_request_threshold_ms {
+            self.router.slow_request_threshold_ms = val;
+        }
+        if let Some(val) = env_config.grpc_router_max_body_size {
+            self.router.max_body_size = val;
+        }
+        if let Some(val) = env_config.grpc_router_buffer_request_for_retry {
+            self.router.buffer_request_for_retry = val;
+        }
+        if let Some(val) = env_config.grpc_router_retry_buffer_max_size {
+            self.router.retry_buffer_max_size = val;
+        }
+        if let Some(val) = env_config.grpc_router_buffer_responses {
+            self.router.buffer_responses = val;
+        }
+        if let Some(val) = env_config.grpc_router_response_buffer_max_size {
+            self.router.response_buffer_max_size = val;
+        }
+        if let Some(val) = env_config.grpc_router_service_max_body_sizes {
+            self.router.per_service_max_body_sizes = Self::parse_service_overrides(&val)?;
+        }
+        if let Some(val) = env_config.grpc_router_shadow_targets {
+            self.router.shadow_targets = Self::parse_service_overrides(&val)?;
+        }
+        if let Some(val) = env_config.grpc_router_service_aliases {
+            self.router.service_aliases = Self::parse_service_overrides(&val)?;
+        }
+        if let Some(val) = env_config.grpc_router_rewrite_aliased_path {
+            self.router.rewrite_aliased_path = val;
+        }
+        if let Some(val) = env_config.grpc_router_reserved_services {
+            self.router.reserved_services = val
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+        if let Some(val) = env_config.grpc_router_injected_headers {
+            self.router.injected_headers = Self::parse_injected_headers(&val)?;
+        }
+        if let Some(val) = env_config.grpc_router_injected_header_policy {
+            self.router.injected_header_policy = val;
+        }
+        if let Some(val) = env_config.grpc_router_default_backend {
+            self.router.default_backend = Some(val);
+        }
+        if let Some(val) = env_config.grpc_router_hedged_methods {
+            self.router.hedged_methods = val
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+        if let Some(val) = env_config.grpc_router_hedge_delay_ms {
+            self.router.hedge_delay_ms = val;
+        }
+        if let Some(val) = env_config.grpc_router_initial_routing_delay_ms {
+            self.router.initial_routing_delay_ms = val;
+        }
 
         // 连接池配置覆盖
         if let Some(val) = env_config.grpc_pool_max_connections {
             self.connection_pool.max_connections = val;
         }
+        if let Some(val) = env_config.grpc_pool_connections_per_address {
+            self.connection_pool.connections_per_address = val;
+        }
+        if let Some(val) = env_config.grpc_pool_eviction_policy {
+            self.connection_pool.eviction_policy = val;
+        }
         if let Some(val) = env_config.grpc_pool_connection_ttl {
             self.connection_pool.connection_ttl = val;
         }
@@ -143,6 +1360,27 @@ impl Config {
         if let Some(val) = env_config.grpc_pool_cleanup_interval {
             self.connection_pool.cleanup_interval = val;
         }
+        if let Some(val) = env_config.grpc_pool_circuit_failure_threshold {
+            self.connection_pool.circuit_failure_threshold = val;
+        }
+        if let Some(val) = env_config.grpc_pool_circuit_cooldown {
+            self.connection_pool.circuit_cooldown = val;
+        }
+        if let Some(val) = env_config.grpc_pool_tls_enabled {
+            self.connection_pool.tls_enabled = val;
+        }
+        if let Some(val) = env_config.grpc_pool_tls_ca_path {
+            self.connection_pool.tls_ca_path = Some(val);
+        }
+        if let Some(val) = env_config.grpc_pool_tls_client_cert_path {
+            self.connection_pool.tls_client_cert_path = Some(val);
+        }
+        if let Some(val) = env_config.grpc_pool_tls_client_key_path {
+            self.connection_pool.tls_client_key_path = Some(val);
+        }
+        if let Some(val) = env_config.grpc_pool_warmup_on_register {
+            self.connection_pool.warmup_on_register = val;
+        }
 
         // 反向连接配置覆盖
         if let Some(val) = env_config.grpc_reverse_heartbeat_timeout {
@@ -157,6 +1395,42 @@ impl Config {
         if let Some(val) = env_config.grpc_reverse_max_pending_requests {
             self.reverse_connection.max_pending_requests = val;
         }
+        if let Some(val) = env_config.grpc_reverse_stream_idle_timeout {
+            self.reverse_connection.stream_idle_timeout = val;
+        }
+        if let Some(val) = env_config.grpc_reverse_ping_interval {
+            self.reverse_connection.ping_interval = val;
+        }
+        if let Some(val) = env_config.grpc_reverse_request_channel_capacity {
+            self.reverse_connection.request_channel_capacity = val;
+        }
+        if let Some(val) = env_config.grpc_reverse_gzip_payload {
+            self.reverse_connection.gzip_payload = val;
+        }
+        if let Some(val) = env_config.grpc_reverse_max_response_size {
+            self.reverse_connection.max_response_size = val;
+        }
+        if let Some(val) = env_config.grpc_reverse_max_connection_age {
+            self.reverse_connection.max_connection_age = val;
+        }
+        if let Some(val) = env_config.grpc_reverse_max_services_per_connection {
+            self.reverse_connection.max_services_per_connection = val;
+        }
+        if let Some(val) = env_config.grpc_reverse_max_connections_per_token {
+            self.reverse_connection.max_connections_per_token = val;
+        }
+        if let Some(val) = env_config.grpc_reverse_reconcile_interval {
+            self.reverse_connection.reconcile_interval = val;
+        }
+        if let Some(val) = env_config.grpc_reverse_max_in_flight_per_connection {
+            self.reverse_connection.max_in_flight_per_connection = val;
+        }
+        if let Some(val) = env_config.grpc_reverse_saturation_queue_wait_ms {
+            self.reverse_connection.saturation_queue_wait_ms = val;
+        }
+        if let Some(val) = env_config.grpc_reverse_saturation_queue_capacity {
+            self.reverse_connection.saturation_queue_capacity = val;
+        }
 
         // 服务器配置覆盖
         if let Some(val) = env_config.grpc_server_address {
@@ -165,12 +1439,273 @@ impl Config {
         if let Some(val) = env_config.grpc_log_level {
             self.server.log_level = val;
         }
+        if let Some(val) = env_config.grpc_log_format {
+            self.server.log_format = val;
+        }
+        if let Some(val) = env_config.grpc_server_shutdown_grace_period {
+            self.server.shutdown_grace_period = val;
+        }
+        if let Some(val) = env_config.grpc_server_config_reload_interval {
+            self.server.config_reload_interval = val;
+        }
+
+        // 指标端点配置覆盖
+        if let Some(val) = env_config.grpc_metrics_enabled {
+            self.metrics.enabled = val;
+        }
+        if let Some(val) = env_config.grpc_metrics_address {
+            self.metrics.address = val;
+        }
+
+        // gRPC-Web 配置覆盖
+        if let Some(val) = env_config.grpc_web_enabled {
+            self.grpc_web.enabled = val;
+        }
+        if let Some(val) = env_config.grpc_web_cors_enabled {
+            self.grpc_web.cors_enabled = val;
+        }
+        if let Some(val) = env_config.grpc_web_cors_allowed_origins {
+            self.grpc_web.cors_allowed_origins = val
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+        if let Some(val) = env_config.grpc_web_cors_max_age {
+            self.grpc_web.cors_max_age = val;
+        }
+
+        // 事件总线配置覆盖
+        if let Some(val) = env_config.grpc_event_max_subscribers_per_type {
+            self.event.max_subscribers_per_type = val;
+        }
+        if let Some(val) = env_config.grpc_event_channel_capacity {
+            self.event.channel_capacity = val;
+        }
+        if let Some(val) = env_config.grpc_event_type_capacities {
+            self.event.event_type_capacities = Self::parse_service_overrides(&val)?;
+        }
+        if let Some(val) = env_config.grpc_event_max_history {
+            self.event.max_event_history = Some(val);
+        }
+        if let Some(val) = env_config.grpc_event_ttl_seconds {
+            self.event.event_ttl_seconds = Some(val);
+        }
+        if let Some(val) = env_config.grpc_event_enable_metrics {
+            self.event.enable_metrics = val;
+        }
+        if let Some(val) = env_config.grpc_event_skip_lagged_events {
+            self.event.skip_lagged_events = val;
+        }
+        if let Some(val) = env_config.grpc_event_reliable_delivery {
+            self.event.reliable_delivery = val;
+        }
+        if let Some(val) = env_config.grpc_event_reliable_queue_capacity {
+            self.event.reliable_queue_capacity = val;
+        }
+        if let Some(val) = env_config.grpc_event_subscriber_idle_ttl_seconds {
+            self.event.subscriber_idle_ttl_seconds = Some(val);
+        }
+
+        // 主动健康检查配置覆盖
+        if let Some(val) = env_config.grpc_health_check_enabled {
+            self.health_check.enabled = val;
+        }
+        if let Some(val) = env_config.grpc_health_check_interval {
+            self.health_check.interval = val;
+        }
+        if let Some(val) = env_config.grpc_health_check_timeout {
+            self.health_check.timeout = val;
+        }
+        if let Some(val) = env_config.grpc_health_check_failure_threshold {
+            self.health_check.failure_threshold = val;
+        }
+
+        // 注册表快照配置覆盖
+        if let Some(val) = env_config.grpc_persistence_snapshot_path {
+            self.persistence.snapshot_path = Some(val);
+        }
+        if let Some(val) = env_config.grpc_persistence_snapshot_interval {
+            self.persistence.snapshot_interval = val;
+        }
+        if let Some(val) = env_config.grpc_persistence_snapshot_grace_period {
+            self.persistence.snapshot_grace_period = val;
+        }
+
+        // 限流配置覆盖
+        if let Some(val) = env_config.grpc_rate_limit_enabled {
+            self.rate_limit.enabled = val;
+        }
+        if let Some(val) = env_config.grpc_rate_limit_requests_per_second {
+            self.rate_limit.requests_per_second = val;
+        }
+        if let Some(val) = env_config.grpc_rate_limit_burst {
+            self.rate_limit.burst = val;
+        }
+        if let Some(val) = env_config.grpc_rate_limit_per_token {
+            self.rate_limit.per_token = val;
+        }
+        if let Some(val) = env_config.grpc_rate_limit_per_service {
+            self.rate_limit.per_service = val;
+        }
+        if let Some(val) = env_config.grpc_rate_limit_token_header {
+            self.rate_limit.token_header = val;
+        }
+
+        // 压缩配置覆盖
+        if let Some(val) = env_config.grpc_compression_gzip_responses {
+            self.compression.gzip_responses = val;
+        }
+        if let Some(val) = env_config.grpc_compression_min_size {
+            self.compression.min_size = val;
+        }
+
+        // 请求/响应调试日志配置覆盖
+        if let Some(val) = env_config.grpc_payload_log_enabled {
+            self.payload_log.enabled = val;
+        }
+        if let Some(val) = env_config.grpc_payload_log_redact_headers {
+            self.payload_log.redact_headers = val
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+        if let Some(val) = env_config.grpc_payload_log_max_payload_bytes {
+            self.payload_log.max_payload_bytes = val;
+        }
+        if let Some(val) = env_config.grpc_payload_log_payload_encoding {
+            self.payload_log.payload_encoding = val;
+        }
+
+        // HTTP/2 keepalive 和窗口配置覆盖
+        if let Some(val) = env_config.grpc_http2_keepalive_interval {
+            self.http2.http2_keepalive_interval = val;
+        }
+        if let Some(val) = env_config.grpc_http2_keepalive_timeout {
+            self.http2.http2_keepalive_timeout = val;
+        }
+        if let Some(val) = env_config.grpc_http2_initial_stream_window {
+            self.http2.initial_stream_window = val;
+        }
+        if let Some(val) = env_config.grpc_http2_initial_connection_window {
+            self.http2.initial_connection_window = val;
+        }
+
+        // TLS 配置覆盖（证书和私钥需同时提供）
+        match (env_config.grpc_tls_cert_path, env_config.grpc_tls_key_path) {
+            (Some(cert_path), Some(key_path)) => {
+                self.tls = Some(TlsConfig {
+                    cert_path,
+                    key_path,
+                    client_ca_path: env_config.grpc_tls_client_ca_path,
+                });
+            }
+            (None, None) => {
+                if let (Some(tls), Some(ca)) =
+                    (self.tls.as_mut(), env_config.grpc_tls_client_ca_path)
+                {
+                    tls.client_ca_path = Some(ca);
+                }
+            }
+            _ => {
+                return Err("GRPC_TLS_CERT_PATH and GRPC_TLS_KEY_PATH must be set together".into());
+            }
+        }
 
         Ok(())
     }
 
+    // 解析 "service=值,service=值" 格式的按服务覆盖配置
+    fn parse_service_overrides<T>(
+        value: &str,
+    ) -> Result<HashMap<String, T>, Box<dyn std::error::Error>>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        let mut overrides = HashMap::new();
+        for entry in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (service, value) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid service override entry: {entry}"))?;
+            let value: T = value
+                .trim()
+                .parse()
+                .map_err(|e| format!("Invalid value for service {service}: {e}"))?;
+            overrides.insert(service.trim().to_string(), value);
+        }
+        Ok(overrides)
+    }
+
+    // 解析 token=pattern|pattern,token=pattern 格式的限定范围 token
+    // token 可能以 base64 填充符 = 结尾，因此按最后一个 = 拆分
+    fn parse_scoped_tokens(
+        value: &str,
+    ) -> Result<HashMap<String, Vec<String>>, Box<dyn std::error::Error>> {
+        let mut scoped_tokens = HashMap::new();
+        for entry in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (token, patterns) = entry
+                .rsplit_once('=')
+                .ok_or_else(|| format!("Invalid scoped token entry: {entry}"))?;
+            let patterns: Vec<String> = patterns
+                .split('|')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+            if token.trim().is_empty() || patterns.is_empty() {
+                return Err(format!("Invalid scoped token entry: {entry}").into());
+            }
+            scoped_tokens.insert(token.trim().to_string(), patterns);
+        }
+        Ok(scoped_tokens)
+    }
+
+    // 解析 service=header:value|header:value,service=header:value 格式的注入请求头，
+    // 头名称转为小写；值按第一个冒号拆分，可以包含冒号
+    fn parse_injected_headers(
+        value: &str,
+    ) -> Result<HashMap<String, HashMap<String, String>>, Box<dyn std::error::Error>> {
+        let mut injected = HashMap::new();
+        for entry in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (service, headers) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid injected header entry: {entry}"))?;
+            let mut parsed = HashMap::new();
+            for header in headers.split('|').map(str::trim).filter(|s| !s.is_empty()) {
+                let (name, value) = header
+                    .split_once(':')
+                    .ok_or_else(|| format!("Invalid injected header for {service}: {header}"))?;
+                parsed.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+            }
+            injected.insert(service.trim().to_string(), parsed);
+        }
+        Ok(injected)
+    }
+
     pub fn validate_token(&self, token: &str) -> bool {
-        self.security.tokens.contains(&token.to_string())
+        self.security.tokens.iter().any(|t| t == token)
+            || self.security.scoped_tokens.contains_key(token)
+    }
+
+    // 校验管理接口 token，注册用的 token 不能访问管理接口
+    pub fn validate_admin_token(&self, token: &str) -> bool {
+        !token.is_empty() && self.security.admin_tokens.iter().any(|t| t == token)
+    }
+
+    // 检查 token 是否允许注册指定服务，未限定范围的 token 允许所有服务
+    pub fn token_allows_service(&self, token: &str, service_name: &str) -> bool {
+        if self.security.tokens.iter().any(|t| t == token) {
+            return true;
+        }
+        self.security
+            .scoped_tokens
+            .get(token)
+            .is_some_and(|patterns| {
+                patterns
+                    .iter()
+                    .any(|pattern| SecurityConfig::pattern_matches(pattern, service_name))
+            })
     }
 
     // 获取路由配置的便利方法
@@ -181,6 +1716,50 @@ impl Config {
     pub fn request_timeout(&self) -> Duration {
         Duration::from_secs(self.router.request_timeout)
     }
+
+    pub fn shutdown_grace_period(&self) -> Duration {
+        Duration::from_secs(self.server.shutdown_grace_period)
+    }
+
+    // 为 0 时不轮询配置文件
+    pub fn config_reload_interval(&self) -> Option<Duration> {
+        (self.server.config_reload_interval > 0)
+            .then(|| Duration::from_secs(self.server.config_reload_interval))
+    }
+
+    pub fn health_check_interval(&self) -> Duration {
+        Duration::from_secs(self.health_check.interval)
+    }
+
+    pub fn health_check_timeout(&self) -> Duration {
+        Duration::from_secs(self.health_check.timeout)
+    }
+
+    // 获取指定服务的消息体大小上限，未单独配置时使用全局上限
+    pub fn max_body_size_for(&self, service_name: &str) -> usize {
+        self.router
+            .per_service_max_body_sizes
+            .get(service_name)
+            .copied()
+            .unwrap_or(self.router.max_body_size)
+    }
+
+    // 获取指定服务的影子实例，未配置或采样率不大于 0 时返回 None
+    pub fn shadow_target_for(&self, service_name: &str) -> Option<&ShadowTarget> {
+        self.router
+            .shadow_targets
+            .get(service_name)
+            .filter(|target| target.sample_rate > 0.0)
+    }
+
+    // 获取指定服务的请求超时，未单独配置时使用全局超时
+    pub fn request_timeout_for(&self, service_name: &str) -> Duration {
+        self.router
+            .per_service_timeouts
+            .get(service_name)
+            .map(|secs| Duration::from_secs(*secs))
+            .unwrap_or_else(|| self.request_timeout())
+    }
 }
 
 impl Default for Config {
@@ -188,30 +1767,75 @@ impl Default for Config {
         Self {
             security: SecurityConfig {
                 tokens: vec![], // 默认无 token，必须通过环境变量设置
+                scoped_tokens: HashMap::new(),
+                admin_tokens: vec![],
+                auth_disabled: false,
+                audit_events: false,
+                jwt: JwtConfig::default(),
             },
             router: RouterConfig {
                 heartbeat_timeout: 120,
                 request_timeout: 30,
                 retry_attempts: 3,
                 max_concurrent_requests: 1000,
+                per_service_timeouts: HashMap::new(),
+                max_body_size: default_max_body_size(),
+                per_service_max_body_sizes: HashMap::new(),
+                use_full_service_name: false,
+                label_route_fallback: default_label_route_fallback(),
+                affinity_header: None,
+                stickiness_window_ms: 0,
+                stickiness_header: None,
+                load_balance_strategy: LoadBalanceStrategy::default(),
+                slow_request_threshold_ms: default_slow_request_threshold_ms(),
+                shadow_targets: HashMap::new(),
+                service_aliases: HashMap::new(),
+                rewrite_aliased_path: true,
+                buffer_request_for_retry: false,
+                retry_buffer_max_size: default_retry_buffer_max_size(),
+                buffer_responses: false,
+                response_buffer_max_size: default_response_buffer_max_size(),
+                reserved_services: Vec::new(),
+                injected_headers: HashMap::new(),
+                injected_header_policy: HeaderInjectionPolicy::default(),
+                default_backend: None,
+                hedged_methods: Vec::new(),
+                hedge_delay_ms: default_hedge_delay_ms(),
+                initial_routing_delay_ms: 0,
             },
             connection_pool: ConnectionPoolConfig {
                 max_connections: 100,
+                connections_per_address: default_connections_per_address(),
+                eviction_policy: EvictionPolicy::default(),
                 connection_ttl: 300,
                 idle_timeout: 60,
                 cleanup_interval: 30,
+                circuit_failure_threshold: default_circuit_failure_threshold(),
+                circuit_cooldown: default_circuit_cooldown(),
+                tls_enabled: false,
+                tls_ca_path: None,
+                tls_client_cert_path: None,
+                tls_client_key_path: None,
+                warmup_on_register: true,
             },
-            reverse_connection: ReverseConnectionConfig {
-                heartbeat_timeout: 120,
-                request_timeout: 30,
-                cleanup_interval: 60,
-                max_pending_requests: 1000,
-            },
+            reverse_connection: ReverseConnectionConfig::default(),
             event: EventConfig::default(),
             server: ServerConfig {
                 address: "0.0.0.0:50051".to_string(),
                 log_level: "info".to_string(),
+                log_format: LogFormat::default(),
+                shutdown_grace_period: default_shutdown_grace_period(),
+                config_reload_interval: default_config_reload_interval(),
             },
+            metrics: MetricsConfig::default(),
+            health_check: HealthCheckConfig::default(),
+            persistence: PersistenceConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            compression: CompressionConfig::default(),
+            grpc_web: GrpcWebConfig::default(),
+            payload_log: PayloadLogConfig::default(),
+            http2: Http2Config::default(),
+            tls: None,
         }
     }
 }
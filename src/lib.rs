@@ -0,0 +1,15 @@
+pub mod registry {
+    tonic::include_proto!("registry");
+}
+pub mod health {
+    tonic::include_proto!("grpc.health.v1");
+}
+pub mod admin {
+    tonic::include_proto!("admin");
+}
+// 网关自身 proto 的文件描述符集，供 gRPC 反射服务使用
+pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("gateway_descriptor");
+pub mod config;
+pub mod logging;
+pub mod server;
+pub mod services;
@@ -0,0 +1,42 @@
+use tracing::Subscriber;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::config::{LogFormat, ServerConfig};
+
+// 设置了 RUST_LOG 时以其为准，否则使用配置中的 log_level
+pub fn env_filter(log_level: &str) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(log_level))
+}
+
+// 按配置构建日志订阅者，JSON 格式包含 target、当前 span 及其上层 span 的字段，
+// 时间戳为 RFC3339 格式的 UTC 时间
+pub fn build_subscriber<W>(
+    server: &ServerConfig,
+    make_writer: W,
+) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(env_filter(&server.log_level))
+        .with_target(true)
+        .with_writer(make_writer);
+
+    match server.log_format {
+        LogFormat::Text => Box::new(builder.finish()),
+        LogFormat::Json => Box::new(
+            builder
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .finish(),
+        ),
+    }
+}
+
+// 初始化全局日志订阅者，输出到标准输出
+pub fn init(server: &ServerConfig) -> Result<(), Box<dyn std::error::Error>> {
+    tracing::subscriber::set_global_default(build_subscriber(server, std::io::stdout))?;
+    Ok(())
+}
@@ -1,23 +1,18 @@
-pub mod registry {
-    tonic::include_proto!("registry");
-}
-pub mod config;
-pub mod server;
-pub mod services;
-
+use grpc_opizontas::config::Config;
+use grpc_opizontas::{logging, server};
 use jemallocator::Jemalloc;
-use tracing_subscriber::{EnvFilter, FmtSubscriber};
 #[global_allocator]
 static GLOBAL: Jemalloc = Jemalloc;
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // 先加载配置，日志格式和级别来自配置
+    let config = Config::load()?;
+
     // 初始化 tracing
-    let subscriber = FmtSubscriber::builder()
-        .with_env_filter(EnvFilter::from_default_env())
-        .finish();
-    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+    logging::init(&config.server)?;
+    tracing::info!("Security configuration loaded successfully");
 
     tracing::info!("Starting gateway server...");
-    server::start().await?;
+    server::start(config).await?;
     Ok(())
 }
@@ -1,34 +1,479 @@
-use crate::config::Config;
+use crate::admin::admin_server::AdminServer;
+use crate::config::{CONFIG_PATH, Config, GrpcWebConfig, TlsConfig};
+use crate::health::health_server::HealthServer;
 use crate::registry::registry_service_server::RegistryServiceServer;
-use crate::services::registry::MyRegistryService;
+use crate::services::admin::AdminService;
+use crate::services::config_watcher::ConfigWatcher;
+use crate::services::health::HealthService;
+use crate::services::metrics::{self, MetricsExporter, Readiness};
+use crate::services::registry::{
+    ActiveHealthChecker, JwtValidator, MyRegistryService, StaticTokenValidator,
+};
 use crate::services::router::DynamicRouter;
-use tonic::transport::Server;
+use futures::future::BoxFuture;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::service::Routes;
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
+use tonic_web::GrpcWebLayer;
+use tower_http::cors::{AllowHeaders, AllowOrigin, CorsLayer};
 
-pub async fn start() -> Result<(), Box<dyn std::error::Error>> {
-    let addr = "0.0.0.0:50051".parse()?;
+// unix domain socket 监听地址的前缀，例如 unix:/run/gateway.sock
+const UNIX_ADDRESS_PREFIX: &str = "unix:";
 
-    // 加载配置
-    let config = Config::load()?;
-    tracing::info!("Security configuration loaded successfully");
+// 宽限期结束、强制失败剩余请求后，等待这些错误响应发出的时间
+const FORCED_SHUTDOWN_FLUSH_PERIOD: Duration = Duration::from_millis(500);
+
+// 使用已加载的配置启动网关，收到 SIGTERM 或 SIGINT 后优雅停机
+pub async fn start(config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    run(config, shutdown_signal()).await
+}
+
+// 启动网关，shutdown 完成后开始优雅停机
+pub async fn run<F>(config: Config, shutdown: F) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let listen_address = ListenAddress::parse(&config.server.address)?;
+    serve(config, listen_address, shutdown).await
+}
+
+// 在调用方已经绑定的 TCP 监听器上启动网关，忽略配置中的监听地址；
+// 端口在启动前就已占用，不会在释放和重新绑定之间被其他进程抢走
+pub async fn run_with_listener<F>(
+    config: Config,
+    listener: tokio::net::TcpListener,
+    shutdown: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    serve(config, ListenAddress::Bound(listener), shutdown).await
+}
+
+// 在给定的监听端上启动网关，run 与 run_with_listener 共用
+async fn serve<F>(
+    config: Config,
+    listen_address: ListenAddress,
+    shutdown: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let grace_period = config.shutdown_grace_period();
+
+    // 提前校验后端出站 TLS 证书，避免首次转发时才发现配置错误
+    config.connection_pool.client_tls()?;
+
+    let builder = server_builder(&config)?;
+
+    if config.security.auth_disabled {
+        tracing::warn!(
+            "Token authentication is DISABLED: any client can register services and establish reverse connections. Never enable security.auth_disabled outside local development"
+        );
+    }
 
     // 创建服务实例
-    let registry_service = MyRegistryService::new(config.clone());
+    let mut registry_service = MyRegistryService::new(config.clone());
+    // 启用 JWT 时替换默认的 token 校验器，配置中的静态 token 仍然有效
+    if config.security.jwt.enabled {
+        let validator = JwtValidator::new(
+            &config.security.jwt,
+            StaticTokenValidator::new(registry_service.config.clone()),
+        )?;
+        registry_service = registry_service.with_token_validator(Arc::new(validator));
+        tracing::info!(
+            hs256 = !config.security.jwt.hs256_secret.is_empty(),
+            jwks_url = %config.security.jwt.jwks_url,
+            "JWT authentication enabled"
+        );
+    }
+    let registry_service = Arc::new(registry_service);
     let registry = registry_service.registry.clone();
     let reverse_manager = registry_service.reverse_connection_manager.clone();
 
-    // 创建动态路由器
-    let router = DynamicRouter::new(registry.clone(), config.clone(), reverse_manager);
+    // 创建动态路由器，与注册服务共享同一份可热更新的配置
+    let router = DynamicRouter::with_shared_config(
+        registry.clone(),
+        registry_service.config.clone(),
+        reverse_manager.clone(),
+    )?;
+    let client_manager = router.client_manager.clone();
+
+    // 后台任务（主动健康检查、配置热更新）在停机开始时一并关闭
+    let background_shutdown = tokio_util::sync::CancellationToken::new();
+
+    // 指标端点在排空期间继续运行，/readyz 返回 503，网关退出时才关闭
+    let metrics_shutdown = tokio_util::sync::CancellationToken::new();
+    let _metrics_guard = metrics_shutdown.clone().drop_guard();
+    let readiness = Readiness::default();
+
+    // 启动独立端口上的指标端点和存活/就绪探针
+    if config.metrics.enabled {
+        let metrics_addr = config.metrics.address.parse()?;
+        let exporter = MetricsExporter {
+            metrics: router.metrics.clone(),
+            registry: registry.clone(),
+            client_manager: router.client_manager.clone(),
+            reverse_manager: reverse_manager.clone(),
+            readiness: readiness.clone(),
+        };
+        let shutdown = metrics_shutdown.clone();
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve_metrics(metrics_addr, exporter, shutdown).await {
+                tracing::error!(error = %e, "Metrics endpoint failed");
+            }
+        });
+    }
+
+    // 周期性探测正向注册的后端，探测复用动态路由器的连接池
+    if config.health_check.enabled {
+        let checker = Arc::new(ActiveHealthChecker::new(
+            registry_service.clone(),
+            router.client_manager.clone(),
+            &config,
+        ));
+        checker.spawn(background_shutdown.clone());
+        tracing::info!(
+            interval_secs = config.health_check.interval,
+            failure_threshold = config.health_check.failure_threshold,
+            "Active health checking enabled"
+        );
+    }
+
+    // 正向注册新实例时预先建立连接，是否启用取自当前连接池配置
+    router.client_manager.spawn_registration_warmup(
+        registry_service.subscribe_registrations(),
+        background_shutdown.clone(),
+    );
 
-    tracing::info!("Gateway server listening on {} with registry service", addr);
+    // 配置文件修改或收到 SIGHUP 时热更新 token、路由、限流和连接池配置
+    ConfigWatcher::new(
+        CONFIG_PATH,
+        registry_service.config.clone(),
+        router.client_manager.clone(),
+        reverse_manager.clone(),
+    )
+    .spawn(config.config_reload_interval(), background_shutdown.clone());
+
+    // 创建健康检查服务
+    let health_service = HealthService::new(registry_service.clone());
+
+    // 管理服务只接受管理 token，未配置时拒绝所有请求
+    let admin_service = AdminService::new(registry_service.clone(), router.client_manager.clone());
+
+    // 反射只描述网关自身的服务，动态转发的服务没有描述符
+    let reflection = || {
+        tonic_reflection::server::Builder::configure()
+            .register_encoded_file_descriptor_set(crate::FILE_DESCRIPTOR_SET)
+    };
+    let reflection_v1 = reflection().build_v1()?;
+    let reflection_v1alpha = reflection().build_v1alpha()?;
+
+    // 注册服务、健康检查、管理服务和反射按服务名路由，其余所有请求交给动态路由器
+    let mut routes = Routes::builder();
+    routes
+        .add_service(RegistryServiceServer::from_arc(registry_service.clone()))
+        .add_service(HealthServer::new(health_service))
+        .add_service(AdminServer::new(admin_service))
+        .add_service(reflection_v1)
+        .add_service(reflection_v1alpha);
+    let mut routes = routes.routes();
+    let axum_router = std::mem::take(routes.axum_router_mut());
+    *routes.axum_router_mut() = axum_router.fallback_service(router);
+
+    tracing::info!(
+        "Gateway server listening on {} with registry service",
+        config.server.address
+    );
     tracing::info!("Dynamic routing enabled for all gRPC requests");
 
-    // 启动服务器，将动态路由器作为主要的服务处理器
-    // 注册服务请求会被动态路由器识别并转发到注册服务
-    Server::builder()
-        .add_service(tower::ServiceBuilder::new().service(router))
-        .add_service(RegistryServiceServer::new(registry_service))
-        .serve(addr)
-        .await?;
+    // 收到停机信号后：拒绝新请求和新反向连接，通知现有连接，等待进行中的请求完成
+    let (drain_started_tx, drain_started_rx) = tokio::sync::oneshot::channel();
+    let drain_manager = reverse_manager.clone();
+    let snapshot_service = registry_service.clone();
+    let snapshot_path = config.persistence.snapshot_path.clone();
+    let signal = async move {
+        shutdown.await;
+        tracing::info!(
+            grace_secs = grace_period.as_secs(),
+            "Shutdown signal received, draining in-flight requests"
+        );
+        drain_manager.begin_drain();
+        background_shutdown.cancel();
+
+        // 停机前写入最后一次注册表快照，先停止定期快照任务以免旧快照覆盖
+        snapshot_service.stop_snapshot_task().await;
+        if let Some(path) = &snapshot_path
+            && let Err(e) = snapshot_service.snapshot().save(path)
+        {
+            tracing::warn!(path = %path, error = %e, "Failed to write registry snapshot");
+        }
+
+        // 宽限期内未完成的反向请求直接失败，调用方收到 UNAVAILABLE 而不是连接被重置
+        let manager = drain_manager.clone();
+        tokio::spawn(async move {
+            if manager.wait_for_drain(grace_period).await {
+                tracing::info!("All in-flight requests completed");
+            } else {
+                manager.fail_pending_requests("Gateway shutdown deadline exceeded");
+            }
+            manager.close_all_connections();
+        });
 
+        let _ = drain_started_tx.send(());
+    };
+
+    // gRPC-Web 转换包在最外层，转发前统一变成原生 gRPC，正向和反向转发路径都无需感知
+    let (cors_layer, grpc_web_layer) = grpc_web_layers(&config.grpc_web)?;
+    let router = builder
+        .accept_http1(config.grpc_web.enabled)
+        .layer(tower::util::option_layer(cors_layer))
+        .layer(tower::util::option_layer(grpc_web_layer))
+        .add_routes(routes);
+
+    // socket 文件在守卫释放时删除，守卫先于 server 声明，server 结束后才释放
+    let _socket_guard;
+    let mut server: BoxFuture<'_, Result<(), tonic::transport::Error>> = match listen_address {
+        ListenAddress::Tcp(addr) => {
+            // 先绑定端口再标记就绪，与 serve_with_shutdown 的默认 TCP 参数一致
+            let incoming = TcpIncoming::bind(addr)
+                .map_err(|e| format!("Failed to bind {addr}: {e}"))?
+                .with_nodelay(Some(true));
+            Box::pin(router.serve_with_incoming_shutdown(incoming, signal))
+        }
+        ListenAddress::Bound(listener) => {
+            let incoming = TcpIncoming::from(listener).with_nodelay(Some(true));
+            Box::pin(router.serve_with_incoming_shutdown(incoming, signal))
+        }
+        ListenAddress::Unix(path) => {
+            let (incoming, guard) = bind_unix_socket(&path)?;
+            _socket_guard = guard;
+            Box::pin(router.serve_with_incoming_shutdown(incoming, signal))
+        }
+    };
+    readiness.mark_ready();
+
+    // 宽限期结束后留出很短的时间发出被强制失败的响应，之后仍未退出的连接直接放弃
+    let grace_deadline = async move {
+        if drain_started_rx.await.is_ok() {
+            tokio::time::sleep(grace_period + FORCED_SHUTDOWN_FLUSH_PERIOD).await;
+        } else {
+            std::future::pending::<()>().await;
+        }
+    };
+
+    tokio::select! {
+        result = &mut server => result?,
+        _ = grace_deadline => {
+            tracing::warn!("Shutdown grace period elapsed, forcing shutdown");
+        }
+    }
+
+    // 停止连接管理器的后台任务并等待其退出，仍未结束的任务不再等待
+    let background_tasks = async {
+        reverse_manager.shutdown().await;
+        client_manager.shutdown().await;
+    };
+    if tokio::time::timeout(FORCED_SHUTDOWN_FLUSH_PERIOD, background_tasks)
+        .await
+        .is_err()
+    {
+        tracing::warn!(
+            remaining_tasks =
+                reverse_manager.background_tasks() + client_manager.background_tasks(),
+            "Background tasks did not stop in time"
+        );
+    }
+
+    tracing::info!("Gateway server stopped");
     Ok(())
 }
+
+// 按配置创建监听端的 Server 构建器：TLS、HTTP/2 keepalive 和流控窗口
+pub fn server_builder(config: &Config) -> Result<Server, Box<dyn std::error::Error>> {
+    let mut builder = config.http2.settings().apply_to_server(Server::builder());
+    if let Some(tls) = &config.tls {
+        builder = builder.tls_config(load_tls_config(tls)?)?;
+        tracing::info!(
+            mtls = tls.client_ca_path.is_some(),
+            "TLS enabled for gateway listener"
+        );
+    }
+    if config.http2.http2_keepalive_interval > 0 {
+        tracing::info!(
+            interval_secs = config.http2.http2_keepalive_interval,
+            timeout_secs = config.http2.http2_keepalive_timeout,
+            "HTTP/2 keepalive enabled for gateway listener"
+        );
+    }
+    Ok(builder)
+}
+
+// 网关的监听地址：TCP 地址，或 unix:/path 形式的 unix domain socket，
+// 或者调用方已经绑定好的 TCP 监听器
+enum ListenAddress {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+    Bound(tokio::net::TcpListener),
+}
+
+impl ListenAddress {
+    fn parse(address: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match address.strip_prefix(UNIX_ADDRESS_PREFIX) {
+            Some("") => Err(format!("Missing socket path in address {address}").into()),
+            Some(path) => Ok(Self::Unix(PathBuf::from(path))),
+            None => Ok(Self::Tcp(address.parse()?)),
+        }
+    }
+}
+
+// 退出时删除监听的 socket 文件
+struct UnixSocketGuard(PathBuf);
+
+impl Drop for UnixSocketGuard {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.0)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            tracing::warn!(path = %self.0.display(), error = %e, "Failed to remove unix socket");
+        }
+    }
+}
+
+// 绑定 unix domain socket。上次未正常退出遗留的 socket 文件先删除；
+// 仍有进程在该 socket 上监听，或路径是普通文件时返回错误
+#[cfg(unix)]
+fn bind_unix_socket(
+    path: &Path,
+) -> Result<(tokio_stream::wrappers::UnixListenerStream, UnixSocketGuard), Box<dyn std::error::Error>>
+{
+    use std::os::unix::fs::FileTypeExt;
+
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(format!("{} exists and is not a unix socket", path.display()).into());
+        }
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(format!("{} is already in use", path.display()).into());
+        }
+        tracing::info!(path = %path.display(), "Removing stale unix socket");
+        std::fs::remove_file(path)?;
+    }
+
+    let listener = tokio::net::UnixListener::bind(path)
+        .map_err(|e| format!("Failed to bind unix socket {}: {e}", path.display()))?;
+    Ok((
+        tokio_stream::wrappers::UnixListenerStream::new(listener),
+        UnixSocketGuard(path.to_path_buf()),
+    ))
+}
+
+#[cfg(not(unix))]
+fn bind_unix_socket(
+    path: &Path,
+) -> Result<
+    (
+        futures::stream::Empty<Result<tokio::net::TcpStream, std::io::Error>>,
+        UnixSocketGuard,
+    ),
+    Box<dyn std::error::Error>,
+> {
+    Err(format!(
+        "Unix domain sockets are not supported on this platform: {}",
+        path.display()
+    )
+    .into())
+}
+
+// gRPC-Web 和跨域预检处理层，未启用的层为 None
+fn grpc_web_layers(
+    config: &GrpcWebConfig,
+) -> Result<(Option<CorsLayer>, Option<GrpcWebLayer>), Box<dyn std::error::Error>> {
+    if !config.enabled {
+        return Ok((None, None));
+    }
+    tracing::info!(cors = config.cors_enabled, "gRPC-Web enabled");
+
+    let cors = if config.cors_enabled {
+        let allow_origin = if config.cors_allowed_origins.is_empty() {
+            AllowOrigin::any()
+        } else {
+            let origins = config
+                .cors_allowed_origins
+                .iter()
+                .map(|origin| origin.parse())
+                .collect::<Result<Vec<http::HeaderValue>, _>>()?;
+            AllowOrigin::list(origins)
+        };
+        Some(
+            CorsLayer::new()
+                .allow_origin(allow_origin)
+                .allow_methods([http::Method::POST])
+                .allow_headers(AllowHeaders::mirror_request())
+                .expose_headers([
+                    http::HeaderName::from_static("grpc-status"),
+                    http::HeaderName::from_static("grpc-message"),
+                    http::HeaderName::from_static("grpc-status-details-bin"),
+                ])
+                .max_age(std::time::Duration::from_secs(config.cors_max_age)),
+        )
+    } else {
+        None
+    };
+
+    Ok((cors, Some(GrpcWebLayer::new())))
+}
+
+// 读取证书文件构建 TLS 配置，配置客户端 CA 时强制校验客户端证书
+fn load_tls_config(tls: &TlsConfig) -> Result<ServerTlsConfig, Box<dyn std::error::Error>> {
+    let cert = std::fs::read(&tls.cert_path)
+        .map_err(|e| format!("Failed to read TLS certificate {}: {e}", tls.cert_path))?;
+    let key = std::fs::read(&tls.key_path)
+        .map_err(|e| format!("Failed to read TLS key {}: {e}", tls.key_path))?;
+
+    let mut tls_config = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+
+    if let Some(client_ca_path) = &tls.client_ca_path {
+        let client_ca = std::fs::read(client_ca_path)
+            .map_err(|e| format!("Failed to read client CA {client_ca_path}: {e}"))?;
+        tls_config = tls_config.client_ca_root(Certificate::from_pem(client_ca));
+    }
+
+    Ok(tls_config)
+}
+
+// 等待 SIGTERM 或 SIGINT
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %e, "Failed to listen for SIGINT");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
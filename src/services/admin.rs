@@ -0,0 +1,324 @@
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tonic::{Request, Response, Status};
+
+use crate::admin::{
+    EventSubscriber, EventTypeSubscriptions, ForceUnregisterRequest, ForceUnregisterResponse,
+    GetConnectionStatsRequest, GetConnectionStatsResponse, GetServiceInstancesRequest,
+    GetServiceInstancesResponse, HealthStatus, ListEventSubscriptionsRequest,
+    ListEventSubscriptionsResponse, ListRegisteredServicesRequest, ListRegisteredServicesResponse,
+    ListReverseConnectionsRequest, ListReverseConnectionsResponse, ReverseConnectionInfo,
+    ServiceInstance, ServiceSummary, SetServiceHealthRequest, SetServiceHealthResponse,
+    SubscriptionFilter, admin_server::Admin,
+};
+use crate::services::client_manager::GrpcClientManager;
+use crate::services::connection;
+use crate::services::event::SubscriberInfo;
+use crate::services::registry::{MyRegistryService, ServiceHealthStatus, ServiceInfo};
+
+/// 运维管理服务 (admin.Admin)，只接受 `security.admin_tokens` 中的 token
+#[derive(Debug, Clone)]
+pub struct AdminService {
+    registry_service: Arc<MyRegistryService>,
+    client_manager: GrpcClientManager,
+}
+
+impl AdminService {
+    pub fn new(
+        registry_service: Arc<MyRegistryService>,
+        client_manager: GrpcClientManager,
+    ) -> Self {
+        Self {
+            registry_service,
+            client_manager,
+        }
+    }
+
+    fn authorize(&self, admin_token: &str) -> Result<(), Status> {
+        if self
+            .registry_service
+            .config
+            .load()
+            .validate_admin_token(admin_token)
+        {
+            Ok(())
+        } else {
+            Err(Status::unauthenticated("Invalid admin token"))
+        }
+    }
+
+    fn instance_count(&self, service_name: &str) -> Option<usize> {
+        self.registry_service
+            .registry
+            .get(service_name)
+            .map(|instances| instances.len())
+    }
+
+    fn to_proto_status(status: &ServiceHealthStatus) -> HealthStatus {
+        match status {
+            ServiceHealthStatus::Healthy => HealthStatus::Healthy,
+            ServiceHealthStatus::Unhealthy => HealthStatus::Unhealthy,
+            ServiceHealthStatus::Unknown => HealthStatus::Unknown,
+            ServiceHealthStatus::Draining => HealthStatus::Draining,
+            ServiceHealthStatus::Warming => HealthStatus::Warming,
+        }
+    }
+
+    fn to_instance(instance_id: &str, info: &ServiceInfo) -> ServiceInstance {
+        let last_heartbeat = info
+            .last_heartbeat
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs() as i64)
+            .unwrap_or_default();
+        ServiceInstance {
+            instance_id: instance_id.to_string(),
+            address: info.address.clone(),
+            health_status: Self::to_proto_status(&info.health_status) as i32,
+            last_heartbeat,
+            metadata: info.metadata.clone(),
+        }
+    }
+
+    fn to_connection_info(info: connection::ReverseConnectionInfo) -> ReverseConnectionInfo {
+        ReverseConnectionInfo {
+            connection_id: info.connection_id,
+            services: info.services,
+            connected_seconds: info.created_at.elapsed().as_secs(),
+            last_heartbeat_age_ms: info.last_heartbeat_age.as_millis() as u64,
+            weight: info.weight,
+            pooled_services: info.pooled_services,
+        }
+    }
+
+    fn to_event_subscriber(info: SubscriberInfo) -> EventSubscriber {
+        let age = |time: SystemTime| time.elapsed().map(|age| age.as_secs()).unwrap_or_default();
+        let mut filters: Vec<SubscriptionFilter> = info
+            .metadata_filters
+            .iter()
+            .map(|(event_type, filter)| SubscriptionFilter {
+                event_type: event_type.clone(),
+                metadata: filter.metadata.clone(),
+            })
+            .collect();
+        filters.sort_by(|a, b| a.event_type.cmp(&b.event_type));
+
+        EventSubscriber {
+            active_streams: info.active_streams() as u32,
+            subscriber_id: info.subscriber_id,
+            event_types: info.event_types,
+            subscribed_seconds: age(info.subscribed_at),
+            idle_seconds: age(info.last_active_at),
+            filters,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Admin for AdminService {
+    async fn list_registered_services(
+        &self,
+        request: Request<ListRegisteredServicesRequest>,
+    ) -> Result<Response<ListRegisteredServicesResponse>, Status> {
+        self.authorize(&request.into_inner().admin_token)?;
+
+        let mut services: Vec<ServiceSummary> = self
+            .registry_service
+            .registry
+            .iter()
+            .map(|entry| ServiceSummary {
+                service_name: entry.key().clone(),
+                instance_count: entry.value().len() as u32,
+                healthy_instance_count: entry
+                    .value()
+                    .iter()
+                    .filter(|instance| instance.health_status == ServiceHealthStatus::Healthy)
+                    .count() as u32,
+            })
+            .collect();
+        services.sort_by(|a, b| a.service_name.cmp(&b.service_name));
+
+        Ok(Response::new(ListRegisteredServicesResponse { services }))
+    }
+
+    async fn get_service_instances(
+        &self,
+        request: Request<GetServiceInstancesRequest>,
+    ) -> Result<Response<GetServiceInstancesResponse>, Status> {
+        let req = request.into_inner();
+        self.authorize(&req.admin_token)?;
+
+        let Some(entry) = self.registry_service.registry.get(&req.service_name) else {
+            return Err(Status::not_found(format!(
+                "Service '{}' not found",
+                req.service_name
+            )));
+        };
+        let instances = entry.clone();
+        drop(entry);
+
+        let mut instances: Vec<ServiceInstance> = instances
+            .iter()
+            .map(|instance| Self::to_instance(instance.key(), instance.value()))
+            .collect();
+        instances.sort_by(|a, b| (&a.address, &a.instance_id).cmp(&(&b.address, &b.instance_id)));
+
+        Ok(Response::new(GetServiceInstancesResponse { instances }))
+    }
+
+    async fn force_unregister(
+        &self,
+        request: Request<ForceUnregisterRequest>,
+    ) -> Result<Response<ForceUnregisterResponse>, Status> {
+        let req = request.into_inner();
+        self.authorize(&req.admin_token)?;
+
+        let removed = self.instance_count(&req.service_name).unwrap_or_default();
+        if !self.registry_service.unregister_service(&req.service_name) {
+            return Err(Status::not_found(format!(
+                "Service '{}' not found",
+                req.service_name
+            )));
+        }
+        tracing::warn!(
+            service_name = %req.service_name,
+            removed_instances = removed,
+            "Service force-unregistered via admin API"
+        );
+
+        Ok(Response::new(ForceUnregisterResponse {
+            removed_instances: removed as u32,
+        }))
+    }
+
+    async fn set_service_health(
+        &self,
+        request: Request<SetServiceHealthRequest>,
+    ) -> Result<Response<SetServiceHealthResponse>, Status> {
+        let req = request.into_inner();
+        self.authorize(&req.admin_token)?;
+
+        let status = match HealthStatus::try_from(req.health_status) {
+            Ok(HealthStatus::Healthy) => ServiceHealthStatus::Healthy,
+            Ok(HealthStatus::Unhealthy) => ServiceHealthStatus::Unhealthy,
+            Ok(HealthStatus::Draining) => ServiceHealthStatus::Draining,
+            Ok(HealthStatus::Unknown) => ServiceHealthStatus::Unknown,
+            // 预热状态只由注册流程设置，手动设置后没有预热期结束的时机
+            Ok(HealthStatus::Warming) => {
+                return Err(Status::invalid_argument(
+                    "Warming status cannot be set manually",
+                ));
+            }
+            Err(_) => {
+                return Err(Status::invalid_argument(format!(
+                    "Unknown health status {}",
+                    req.health_status
+                )));
+            }
+        };
+
+        let updated = self.instance_count(&req.service_name).unwrap_or_default();
+        if !self
+            .registry_service
+            .update_service_health(&req.service_name, status)
+        {
+            return Err(Status::not_found(format!(
+                "Service '{}' not found",
+                req.service_name
+            )));
+        }
+
+        Ok(Response::new(SetServiceHealthResponse {
+            updated_instances: updated as u32,
+        }))
+    }
+
+    async fn get_connection_stats(
+        &self,
+        request: Request<GetConnectionStatsRequest>,
+    ) -> Result<Response<GetConnectionStatsResponse>, Status> {
+        self.authorize(&request.into_inner().admin_token)?;
+
+        let stats = self
+            .registry_service
+            .reverse_connection_manager
+            .get_stats()
+            .await;
+
+        Ok(Response::new(GetConnectionStatsResponse {
+            active_connections: stats.active_connections as u64,
+            registered_services: stats.registered_services as u64,
+            pooled_connections: stats.pooled_connections as u64,
+            pending_requests: stats.pending_requests as u64,
+            forwarded_requests: stats.forwarded_requests,
+            timed_out_requests: stats.timed_out_requests,
+            failed_requests: stats.failed_requests,
+            connection_pool: self.client_manager.get_stats(),
+            pooled_addresses: self.client_manager.clients.len() as u64,
+        }))
+    }
+
+    async fn list_reverse_connections(
+        &self,
+        request: Request<ListReverseConnectionsRequest>,
+    ) -> Result<Response<ListReverseConnectionsResponse>, Status> {
+        let req = request.into_inner();
+        self.authorize(&req.admin_token)?;
+
+        let manager = &self.registry_service.reverse_connection_manager;
+        let connections = if req.connection_id.is_empty() {
+            manager.list_connections()
+        } else {
+            let info = manager
+                .get_connection_info(&req.connection_id)
+                .ok_or_else(|| {
+                    Status::not_found(format!(
+                        "Reverse connection '{}' not found",
+                        req.connection_id
+                    ))
+                })?;
+            vec![info]
+        };
+
+        Ok(Response::new(ListReverseConnectionsResponse {
+            connections: connections
+                .into_iter()
+                .map(Self::to_connection_info)
+                .collect(),
+        }))
+    }
+
+    async fn list_event_subscriptions(
+        &self,
+        request: Request<ListEventSubscriptionsRequest>,
+    ) -> Result<Response<ListEventSubscriptionsResponse>, Status> {
+        let req = request.into_inner();
+        self.authorize(&req.admin_token)?;
+
+        let event_bus = &self.registry_service.reverse_connection_manager.event_bus;
+        let matches_type =
+            |event_type: &str| req.event_type.is_empty() || req.event_type == event_type;
+
+        let mut subscribers: Vec<EventSubscriber> = event_bus
+            .get_subscribers()
+            .into_iter()
+            .filter(|info| info.event_types.iter().any(|et| matches_type(et)))
+            .map(Self::to_event_subscriber)
+            .collect();
+        subscribers.sort_by(|a, b| a.subscriber_id.cmp(&b.subscriber_id));
+
+        let event_types = event_bus
+            .subscriber_counts()
+            .into_iter()
+            .filter(|(event_type, _)| matches_type(event_type))
+            .map(|(event_type, count)| EventTypeSubscriptions {
+                event_type,
+                subscriber_count: count as u32,
+            })
+            .collect();
+
+        Ok(Response::new(ListEventSubscriptionsResponse {
+            subscribers,
+            event_types,
+        }))
+    }
+}
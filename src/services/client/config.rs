@@ -1,25 +1,46 @@
 use std::time::Duration;
 
+use super::http2::Http2Settings;
+use super::tls::ClientTlsSettings;
+
 /// 网关客户端配置
 #[derive(Debug, Clone)]
 pub struct GatewayClientConfig {
     /// 网关地址
     pub gateway_address: String,
+    /// 其他网关副本的地址，gateway_address 不可用时按顺序尝试
+    pub fallback_addresses: Vec<String>,
     /// 默认超时时间
     pub default_timeout: Duration,
     /// 连接超时时间
     pub connect_timeout: Duration,
     /// API 密钥
     pub api_key: String,
+    /// 出站 TLS 配置，https 地址自动启用
+    pub tls: ClientTlsSettings,
+    /// 到网关连接的 HTTP/2 keepalive 和窗口参数
+    pub http2: Http2Settings,
 }
 
 impl Default for GatewayClientConfig {
     fn default() -> Self {
         Self {
             gateway_address: "http://localhost:50051".to_string(),
+            fallback_addresses: Vec::new(),
             default_timeout: Duration::from_secs(30),
             connect_timeout: Duration::from_secs(10),
             api_key: String::new(),
+            tls: ClientTlsSettings::default(),
+            http2: Http2Settings::default(),
         }
     }
 }
+
+impl GatewayClientConfig {
+    /// 全部网关地址，gateway_address 在前
+    pub fn gateway_addresses(&self) -> Vec<&str> {
+        std::iter::once(self.gateway_address.as_str())
+            .chain(self.fallback_addresses.iter().map(String::as_str))
+            .collect()
+    }
+}
@@ -13,4 +13,6 @@ pub enum GatewayClientError {
     Timeout,
     #[error("Service not found: {0}")]
     ServiceNotFound(String),
+    #[error("Connection closed")]
+    ConnectionClosed,
 }
@@ -1,7 +1,7 @@
 use std::time::{Duration, SystemTime};
 use uuid::Uuid;
 
-use super::GatewayClientError;
+use super::{GatewayClientError, ReverseConnectionHandle};
 use crate::registry::{
     ConnectionMessage, EventMessage, SubscriptionRequest, connection_message::MessageType,
     subscription_request::Action,
@@ -12,6 +12,8 @@ use crate::services::gateway_client::GatewayClient;
 pub struct EventClient {
     gateway_client: GatewayClient,
     connection_id: String,
+    /// 设置后通过受监管的反向连接收发事件，订阅在重连后自动恢复
+    reverse_connection: Option<ReverseConnectionHandle>,
 }
 
 impl EventClient {
@@ -20,6 +22,7 @@ impl EventClient {
         Self {
             gateway_client,
             connection_id,
+            reverse_connection: None,
         }
     }
 
@@ -51,10 +54,31 @@ impl EventClient {
 
     /// 订阅事件类型
     pub async fn subscribe_events(&self, event_types: Vec<&str>) -> Result<(), GatewayClientError> {
+        self.subscribe_events_with_filter(event_types, std::collections::HashMap::new())
+            .await
+    }
+
+    /// 订阅事件类型，只接收 metadata 包含 `metadata_filter` 全部键值对的事件
+    pub async fn subscribe_events_with_filter(
+        &self,
+        event_types: Vec<&str>,
+        metadata_filter: std::collections::HashMap<String, String>,
+    ) -> Result<(), GatewayClientError> {
+        if let Some(handle) = &self.reverse_connection {
+            return handle
+                .subscribe_with_filter(
+                    event_types.iter().map(|s| s.to_string()).collect(),
+                    metadata_filter,
+                )
+                .await;
+        }
+
         let subscription = SubscriptionRequest {
             action: Action::Subscribe as i32,
             event_types: event_types.iter().map(|s| s.to_string()).collect(),
             subscriber_id: self.connection_id.clone(),
+            metadata_filter,
+            reliable: false,
         };
 
         let message = ConnectionMessage {
@@ -69,10 +93,18 @@ impl EventClient {
         &self,
         event_types: Vec<&str>,
     ) -> Result<(), GatewayClientError> {
+        if let Some(handle) = &self.reverse_connection {
+            return handle
+                .unsubscribe(event_types.iter().map(|s| s.to_string()).collect())
+                .await;
+        }
+
         let subscription = SubscriptionRequest {
             action: Action::Unsubscribe as i32,
             event_types: event_types.iter().map(|s| s.to_string()).collect(),
             subscriber_id: self.connection_id.clone(),
+            metadata_filter: std::collections::HashMap::new(),
+            reliable: false,
         };
 
         let message = ConnectionMessage {
@@ -93,6 +125,13 @@ impl EventClient {
             "Sending connection message"
         );
 
+        if let Some(handle) = &self.reverse_connection {
+            return match message.message_type {
+                Some(message_type) => handle.send(message_type).await,
+                None => Ok(()),
+            };
+        }
+
         // 创建 GatewayClient 的可变引用来发送消息
         // 注意：这里需要克隆 GatewayClient，因为它实现了 Clone trait
         let mut client = self.gateway_client.clone();
@@ -191,6 +230,7 @@ impl EventClient {
 pub struct EventClientBuilder {
     gateway_client: Option<GatewayClient>,
     connection_id: Option<String>,
+    reverse_connection: Option<ReverseConnectionHandle>,
 }
 
 impl EventClientBuilder {
@@ -198,6 +238,7 @@ impl EventClientBuilder {
         Self {
             gateway_client: None,
             connection_id: None,
+            reverse_connection: None,
         }
     }
 
@@ -211,13 +252,22 @@ impl EventClientBuilder {
         self
     }
 
+    /// 通过受监管的反向连接收发事件
+    pub fn with_reverse_connection(mut self, handle: ReverseConnectionHandle) -> Self {
+        self.reverse_connection = Some(handle);
+        self
+    }
+
     pub fn build(self) -> Result<EventClient, String> {
         let gateway_client = self.gateway_client.ok_or("Gateway client is required")?;
         let connection_id = self
             .connection_id
             .unwrap_or_else(|| Uuid::new_v4().to_string());
 
-        Ok(EventClient::new(gateway_client, connection_id))
+        Ok(EventClient {
+            reverse_connection: self.reverse_connection,
+            ..EventClient::new(gateway_client, connection_id)
+        })
     }
 }
 
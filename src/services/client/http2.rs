@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use tonic::transport::{Endpoint, Server};
+
+/// HTTP/2 连接参数，字段为 None 时使用 tonic 的默认值
+///
+/// 长时间空闲的连接（例如反向连接）可能被中间的代理或负载均衡器断开，
+/// 配置 `keepalive_interval` 后定期发送 HTTP/2 PING 保持连接；
+/// 大消息的流可以调大窗口，减少等待 WINDOW_UPDATE 的停顿。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Http2Settings {
+    /// 发送 HTTP/2 PING 的间隔，连接空闲时同样发送
+    pub keepalive_interval: Option<Duration>,
+    /// 等待 PING 响应的超时时间，超时后关闭连接
+    pub keepalive_timeout: Option<Duration>,
+    /// 单个流的初始窗口大小（字节）
+    pub initial_stream_window: Option<u32>,
+    /// 整个连接的初始窗口大小（字节）
+    pub initial_connection_window: Option<u32>,
+}
+
+impl Http2Settings {
+    /// 应用到出站连接的 Endpoint
+    pub fn apply_to_endpoint(&self, mut endpoint: Endpoint) -> Endpoint {
+        if let Some(interval) = self.keepalive_interval {
+            endpoint = endpoint
+                .http2_keep_alive_interval(interval)
+                .keep_alive_while_idle(true);
+        }
+        if let Some(timeout) = self.keepalive_timeout {
+            endpoint = endpoint.keep_alive_timeout(timeout);
+        }
+        endpoint
+            .initial_stream_window_size(self.initial_stream_window)
+            .initial_connection_window_size(self.initial_connection_window)
+    }
+
+    /// 应用到监听端的 Server 构建器
+    pub fn apply_to_server<L>(&self, mut server: Server<L>) -> Server<L> {
+        if self.keepalive_timeout.is_some() {
+            server = server.http2_keepalive_timeout(self.keepalive_timeout);
+        }
+        server
+            .http2_keepalive_interval(self.keepalive_interval)
+            .initial_stream_window_size(self.initial_stream_window)
+            .initial_connection_window_size(self.initial_connection_window)
+    }
+}
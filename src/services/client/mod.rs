@@ -2,8 +2,14 @@ pub mod config;
 pub mod error;
 pub mod event_client;
 pub mod generic;
+pub mod http2;
+pub mod reverse;
 pub(crate) mod streaming;
+pub mod tls;
 
 pub use config::*;
 pub use error::*;
 pub use event_client::*;
+pub use http2::*;
+pub use reverse::*;
+pub use tls::*;
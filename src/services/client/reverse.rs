@@ -0,0 +1,585 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::{mpsc, watch};
+use tokio_stream::StreamExt;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
+use tonic::Streaming;
+
+use super::{GatewayClientConfig, GatewayClientError};
+use crate::registry::{
+    ConnectionMessage, ConnectionRegister, ConnectionStatus, EventAck, ForwardResponse, Heartbeat,
+    Pong, SubscriptionRequest, connection_message::MessageType, connection_status::StatusType,
+    subscription_request::Action,
+};
+use crate::services::gateway_client::GatewayClient;
+
+/// 反向连接的连接状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// 首次连接中
+    Connecting,
+    /// 已注册到网关
+    Connected { connection_id: String },
+    /// 连接断开，等待第 attempt 次重连
+    Reconnecting { attempt: u32, error: String },
+    /// 已主动关闭
+    Closed,
+}
+
+/// 反向连接选项
+#[derive(Debug, Clone)]
+pub struct ReverseConnectionOptions {
+    /// 注册的服务名
+    pub services: Vec<String>,
+    /// 加权轮询权重
+    pub weight: u32,
+    /// 首次注册使用的连接 ID，为空时由网关分配
+    pub connection_id: Option<String>,
+    /// 心跳间隔
+    pub heartbeat_interval: Duration,
+    /// 重连退避的初始间隔
+    pub initial_backoff: Duration,
+    /// 重连退避的上限
+    pub max_backoff: Duration,
+}
+
+impl Default for ReverseConnectionOptions {
+    fn default() -> Self {
+        Self {
+            services: Vec::new(),
+            weight: 1,
+            connection_id: None,
+            heartbeat_interval: Duration::from_secs(30),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// 受监管的反向连接：流出错或关闭后按指数退避重新建立连接，
+/// 使用保存的连接 ID 重新注册，并恢复之前订阅的事件类型
+pub struct ReverseConnectionClient {
+    handle: ReverseConnectionHandle,
+    inbound: mpsc::Receiver<MessageType>,
+}
+
+impl ReverseConnectionClient {
+    /// 启动后台监管任务
+    pub fn spawn(config: GatewayClientConfig, options: ReverseConnectionOptions) -> Self {
+        let (outbound_tx, outbound_rx) = mpsc::channel(100);
+        let (inbound_tx, inbound_rx) = mpsc::channel(100);
+        let (state_tx, state_rx) = watch::channel(ConnectionState::Connecting);
+        let subscriptions = Arc::new(Mutex::new(BTreeMap::new()));
+        let shutdown = CancellationToken::new();
+
+        let supervisor = Supervisor {
+            config,
+            connection_id: options.connection_id.clone().filter(|id| !id.is_empty()),
+            next_address: 0,
+            options,
+            outbound_rx,
+            inbound_tx,
+            state_tx,
+            subscriptions: subscriptions.clone(),
+            shutdown: shutdown.clone(),
+        };
+        tokio::spawn(supervisor.run());
+
+        Self {
+            handle: ReverseConnectionHandle {
+                outbound_tx,
+                state_rx,
+                subscriptions,
+                shutdown,
+            },
+            inbound: inbound_rx,
+        }
+    }
+
+    /// 接收网关下发的消息（转发请求、取消请求等），连接关闭后返回 None
+    pub async fn next_message(&mut self) -> Option<MessageType> {
+        self.inbound.recv().await
+    }
+
+    /// 用于发送响应、订阅事件和观察连接状态的句柄
+    pub fn handle(&self) -> ReverseConnectionHandle {
+        self.handle.clone()
+    }
+}
+
+/// 反向连接句柄，可以在多个任务间共享；重连期间发送的消息会排队等待新连接
+#[derive(Debug, Clone)]
+pub struct ReverseConnectionHandle {
+    outbound_tx: mpsc::Sender<ConnectionMessage>,
+    state_rx: watch::Receiver<ConnectionState>,
+    subscriptions: Arc<Mutex<Subscriptions>>,
+    shutdown: CancellationToken,
+}
+
+impl ReverseConnectionHandle {
+    /// 当前连接状态
+    pub fn state(&self) -> ConnectionState {
+        self.state_rx.borrow().clone()
+    }
+
+    /// 连接状态变化的订阅端，可用于感知连接降级
+    pub fn state_changes(&self) -> watch::Receiver<ConnectionState> {
+        self.state_rx.clone()
+    }
+
+    /// 当前连接 ID，尚未注册成功时为 None
+    pub fn connection_id(&self) -> Option<String> {
+        match &*self.state_rx.borrow() {
+            ConnectionState::Connected { connection_id } => Some(connection_id.clone()),
+            _ => None,
+        }
+    }
+
+    /// 等待进入指定状态
+    pub async fn wait_for_state(
+        &self,
+        mut predicate: impl FnMut(&ConnectionState) -> bool,
+    ) -> ConnectionState {
+        let mut state_rx = self.state_rx.clone();
+        match state_rx.wait_for(|state| predicate(state)).await {
+            Ok(state) => state.clone(),
+            Err(_) => ConnectionState::Closed,
+        }
+    }
+
+    /// 发送转发请求的响应
+    pub async fn send_response(&self, response: ForwardResponse) -> Result<(), GatewayClientError> {
+        self.send(MessageType::Response(response)).await
+    }
+
+    /// 订阅事件类型，重连后自动恢复
+    pub async fn subscribe(&self, event_types: Vec<String>) -> Result<(), GatewayClientError> {
+        self.subscribe_with_filter(event_types, HashMap::new())
+            .await
+    }
+
+    /// 订阅事件类型，只接收 metadata 包含 `metadata_filter` 全部键值对的事件；
+    /// 重连后按原过滤条件恢复
+    pub async fn subscribe_with_filter(
+        &self,
+        event_types: Vec<String>,
+        metadata_filter: HashMap<String, String>,
+    ) -> Result<(), GatewayClientError> {
+        self.subscribe_with_options(event_types, metadata_filter, false)
+            .await
+    }
+
+    /// 以可靠投递方式订阅事件类型：网关保留事件直到调用 [`ack_events`](Self::ack_events) 确认，
+    /// 重连后重新发送未确认的事件，因此同一事件可能收到多次。需要网关开启 `event.reliable_delivery`
+    pub async fn subscribe_reliable(
+        &self,
+        event_types: Vec<String>,
+        metadata_filter: HashMap<String, String>,
+    ) -> Result<(), GatewayClientError> {
+        self.subscribe_with_options(event_types, metadata_filter, true)
+            .await
+    }
+
+    /// 确认已处理的可靠投递事件
+    pub async fn ack_events(&self, event_ids: Vec<String>) -> Result<(), GatewayClientError> {
+        self.send(MessageType::Ack(EventAck {
+            subscriber_id: String::new(),
+            event_ids,
+        }))
+        .await
+    }
+
+    async fn subscribe_with_options(
+        &self,
+        event_types: Vec<String>,
+        metadata_filter: HashMap<String, String>,
+        reliable: bool,
+    ) -> Result<(), GatewayClientError> {
+        {
+            let options = SubscriptionOptions {
+                metadata_filter: metadata_filter.clone().into_iter().collect(),
+                reliable,
+            };
+            let mut subscriptions = self.lock_subscriptions();
+            for event_type in &event_types {
+                subscriptions.insert(event_type.clone(), options.clone());
+            }
+        }
+        self.send(MessageType::Subscription(SubscriptionRequest {
+            action: Action::Subscribe as i32,
+            event_types,
+            subscriber_id: String::new(),
+            metadata_filter,
+            reliable,
+        }))
+        .await
+    }
+
+    /// 取消订阅事件类型
+    pub async fn unsubscribe(&self, event_types: Vec<String>) -> Result<(), GatewayClientError> {
+        {
+            let mut subscriptions = self.lock_subscriptions();
+            for event_type in &event_types {
+                subscriptions.remove(event_type);
+            }
+        }
+        self.send(MessageType::Subscription(SubscriptionRequest {
+            action: Action::Unsubscribe as i32,
+            event_types,
+            subscriber_id: String::new(),
+            metadata_filter: HashMap::new(),
+            reliable: false,
+        }))
+        .await
+    }
+
+    /// 当前订阅的事件类型
+    pub fn subscriptions(&self) -> Vec<String> {
+        self.lock_subscriptions().keys().cloned().collect()
+    }
+
+    /// 通过反向连接发送消息
+    pub async fn send(&self, message: MessageType) -> Result<(), GatewayClientError> {
+        self.outbound_tx
+            .send(ConnectionMessage {
+                message_type: Some(message),
+            })
+            .await
+            .map_err(|_| GatewayClientError::ConnectionClosed)
+    }
+
+    /// 通知网关断开并停止重连
+    pub fn close(&self) {
+        self.shutdown.cancel();
+    }
+
+    fn lock_subscriptions(&self) -> std::sync::MutexGuard<'_, Subscriptions> {
+        self.subscriptions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 订阅时指定的元数据过滤条件和投递方式
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct SubscriptionOptions {
+    metadata_filter: BTreeMap<String, String>,
+    reliable: bool,
+}
+
+/// 事件类型 -> 订阅选项
+type Subscriptions = BTreeMap<String, SubscriptionOptions>;
+
+/// 一次连接会话的结束原因
+enum SessionEnd {
+    /// 主动关闭或所有句柄都已释放
+    Closed,
+    /// 连接断开，established 表示断开前是否注册成功
+    Lost { established: bool, error: String },
+}
+
+struct Supervisor {
+    config: GatewayClientConfig,
+    options: ReverseConnectionOptions,
+    // 网关分配或首次指定的连接 ID，重连时沿用
+    connection_id: Option<String>,
+    // 下一次连接时首先尝试的网关地址位置
+    next_address: usize,
+    outbound_rx: mpsc::Receiver<ConnectionMessage>,
+    inbound_tx: mpsc::Sender<MessageType>,
+    state_tx: watch::Sender<ConnectionState>,
+    subscriptions: Arc<Mutex<Subscriptions>>,
+    shutdown: CancellationToken,
+}
+
+impl Supervisor {
+    async fn run(mut self) {
+        let mut attempt = 0u32;
+
+        loop {
+            let (established, error) = match self.run_session().await {
+                SessionEnd::Closed => break,
+                SessionEnd::Lost { established, error } => (established, error),
+            };
+
+            // 注册成功过的连接断开后从头开始退避
+            if established {
+                attempt = 0;
+            }
+            attempt += 1;
+            let backoff = self.backoff(attempt);
+            tracing::warn!(
+                connection_id = ?self.connection_id,
+                attempt,
+                backoff_ms = backoff.as_millis(),
+                error = %error,
+                "Reverse connection lost, reconnecting"
+            );
+            self.state_tx
+                .send_replace(ConnectionState::Reconnecting { attempt, error });
+
+            tokio::select! {
+                _ = self.shutdown.cancelled() => break,
+                _ = tokio::time::sleep(backoff) => {}
+            }
+        }
+
+        self.state_tx.send_replace(ConnectionState::Closed);
+        tracing::info!(connection_id = ?self.connection_id, "Reverse connection closed");
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.options
+            .initial_backoff
+            .saturating_mul(1u32 << attempt.saturating_sub(1).min(16))
+            .min(self.options.max_backoff)
+    }
+
+    // 建立一次连接并处理消息，直到连接断开或被关闭
+    async fn run_session(&mut self) -> SessionEnd {
+        let (stream_tx, mut inbound) = match self.connect().await {
+            Ok(session) => session,
+            Err(e) => {
+                return SessionEnd::Lost {
+                    established: false,
+                    error: e.to_string(),
+                };
+            }
+        };
+
+        // 第一条消息是网关分配的连接 ID
+        let connection_id = match inbound.next().await {
+            Some(Ok(ConnectionMessage {
+                message_type: Some(MessageType::Status(status)),
+            })) if status.status == StatusType::Connected as i32 => status.connection_id,
+            Some(Err(e)) => {
+                return SessionEnd::Lost {
+                    established: false,
+                    error: e.to_string(),
+                };
+            }
+            _ => {
+                return SessionEnd::Lost {
+                    established: false,
+                    error: "Gateway did not confirm the connection".to_string(),
+                };
+            }
+        };
+        self.connection_id = Some(connection_id.clone());
+
+        // 恢复之前订阅的事件类型，订阅选项相同的事件类型合并为一个订阅请求；
+        // 可靠订阅恢复后网关重新发送未确认的事件
+        let mut by_options: BTreeMap<SubscriptionOptions, Vec<String>> = BTreeMap::new();
+        for (event_type, options) in self
+            .subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+        {
+            by_options
+                .entry(options.clone())
+                .or_default()
+                .push(event_type.clone());
+        }
+        for (options, event_types) in by_options {
+            let resubscribe = ConnectionMessage {
+                message_type: Some(MessageType::Subscription(SubscriptionRequest {
+                    action: Action::Subscribe as i32,
+                    event_types,
+                    subscriber_id: connection_id.clone(),
+                    metadata_filter: options.metadata_filter.into_iter().collect(),
+                    reliable: options.reliable,
+                })),
+            };
+            if stream_tx.send(resubscribe).await.is_err() {
+                return SessionEnd::Lost {
+                    established: true,
+                    error: "Connection closed while restoring subscriptions".to_string(),
+                };
+            }
+        }
+
+        tracing::info!(connection_id = %connection_id, "Reverse connection registered");
+        self.state_tx.send_replace(ConnectionState::Connected {
+            connection_id: connection_id.clone(),
+        });
+
+        let mut heartbeat = tokio::time::interval(self.options.heartbeat_interval);
+        heartbeat.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = self.shutdown.cancelled() => {
+                    Self::send_disconnect(&stream_tx, &connection_id).await;
+                    return SessionEnd::Closed;
+                }
+                _ = heartbeat.tick() => {
+                    let message = ConnectionMessage {
+                        message_type: Some(MessageType::Heartbeat(Heartbeat {
+                            timestamp: unix_timestamp(),
+                            connection_id: connection_id.clone(),
+                        })),
+                    };
+                    if stream_tx.send(message).await.is_err() {
+                        return SessionEnd::Lost {
+                            established: true,
+                            error: "Connection closed while sending heartbeat".to_string(),
+                        };
+                    }
+                }
+                outbound = self.outbound_rx.recv() => {
+                    let Some(mut message) = outbound else {
+                        Self::send_disconnect(&stream_tx, &connection_id).await;
+                        return SessionEnd::Closed;
+                    };
+                    match &mut message.message_type {
+                        Some(MessageType::Subscription(subscription)) => {
+                            subscription.subscriber_id = connection_id.clone();
+                        }
+                        Some(MessageType::Ack(ack)) => ack.subscriber_id = connection_id.clone(),
+                        _ => {}
+                    }
+                    if stream_tx.send(message).await.is_err() {
+                        return SessionEnd::Lost {
+                            established: true,
+                            error: "Connection closed while sending message".to_string(),
+                        };
+                    }
+                }
+                message = inbound.next() => {
+                    match message {
+                        Some(Ok(ConnectionMessage { message_type: Some(MessageType::Ping(ping)) })) => {
+                            // 网关的保活探测由客户端直接回复，不交给调用方
+                            let pong = ConnectionMessage {
+                                message_type: Some(MessageType::Pong(Pong { nonce: ping.nonce })),
+                            };
+                            if stream_tx.send(pong).await.is_err() {
+                                return SessionEnd::Lost {
+                                    established: true,
+                                    error: "Connection closed while answering ping".to_string(),
+                                };
+                            }
+                        }
+                        Some(Ok(ConnectionMessage { message_type: Some(MessageType::Status(status)) }))
+                            if status.status == StatusType::Disconnected as i32 =>
+                        {
+                            // 网关停机或轮换连接时要求重连，结束本次会话后按退避重新注册
+                            return SessionEnd::Lost {
+                                established: true,
+                                error: status.message,
+                            };
+                        }
+                        Some(Ok(ConnectionMessage { message_type: Some(message_type) })) => {
+                            // 调用方不再接收消息时丢弃
+                            let _ = self.inbound_tx.send(message_type).await;
+                        }
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => {
+                            return SessionEnd::Lost {
+                                established: true,
+                                error: e.to_string(),
+                            };
+                        }
+                        None => {
+                            return SessionEnd::Lost {
+                                established: true,
+                                error: "Gateway closed the connection".to_string(),
+                            };
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // 从上次使用的网关之后的地址开始依次尝试，成功后下一次重连换到下一个地址，
+    // 某个网关副本不可用时连接迁移到其他副本
+    async fn connect(
+        &mut self,
+    ) -> Result<
+        (
+            mpsc::Sender<ConnectionMessage>,
+            Streaming<ConnectionMessage>,
+        ),
+        GatewayClientError,
+    > {
+        let address_count = self.config.gateway_addresses().len();
+        let mut last_error = None;
+        for _ in 0..address_count {
+            let index = self.next_address % address_count;
+            self.next_address = index + 1;
+            match self.connect_to(index).await {
+                Ok(session) => {
+                    tracing::debug!(
+                        gateway_address = self.config.gateway_addresses()[index],
+                        "Connected to gateway"
+                    );
+                    return Ok(session);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        gateway_address = self.config.gateway_addresses()[index],
+                        error = %e,
+                        "Failed to connect to gateway, trying next address"
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or(GatewayClientError::ConnectionClosed))
+    }
+
+    // 连接指定的网关并发送注册消息
+    async fn connect_to(
+        &self,
+        index: usize,
+    ) -> Result<
+        (
+            mpsc::Sender<ConnectionMessage>,
+            Streaming<ConnectionMessage>,
+        ),
+        GatewayClientError,
+    > {
+        let mut client = GatewayClient::connect_to(self.config.clone(), index).await?;
+
+        let (stream_tx, stream_rx) = mpsc::channel(100);
+        let register = ConnectionMessage {
+            message_type: Some(MessageType::Register(ConnectionRegister {
+                api_key: self.config.api_key.clone(),
+                services: self.options.services.clone(),
+                connection_id: self.connection_id.clone().unwrap_or_default(),
+                weight: self.options.weight,
+            })),
+        };
+        stream_tx
+            .send(register)
+            .await
+            .map_err(|_| GatewayClientError::ConnectionClosed)?;
+
+        let inbound = client
+            .client
+            .establish_connection(ReceiverStream::new(stream_rx))
+            .await?
+            .into_inner();
+        Ok((stream_tx, inbound))
+    }
+
+    async fn send_disconnect(stream_tx: &mpsc::Sender<ConnectionMessage>, connection_id: &str) {
+        let message = ConnectionMessage {
+            message_type: Some(MessageType::Status(ConnectionStatus {
+                connection_id: connection_id.to_string(),
+                status: StatusType::Disconnected as i32,
+                message: "Client closed the connection".to_string(),
+            })),
+        };
+        let _ = stream_tx.send(message).await;
+    }
+}
+
+fn unix_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
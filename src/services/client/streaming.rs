@@ -1,17 +1,60 @@
 use super::GatewayClientError;
-use crate::registry::{ForwardRequest, StreamingInfo, streaming_info::StreamType};
+use crate::registry::{ForwardRequest, ForwardResponse, StreamingInfo, streaming_info::StreamType};
 use crate::services::gateway_client::GatewayClient;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 use tokio_stream::{Stream, StreamExt};
 use tonic::Status;
 use uuid::Uuid;
 
+/// 一次调用发出的、尚未收到最后一个响应的请求 ID。
+/// 同一连接上可能还有其他调用的响应，每个请求在收到自己的最后一个响应后单独结束
+#[derive(Debug, Default)]
+pub(crate) struct PendingRequests {
+    ids: HashSet<String>,
+    // 调用方已发送完所有请求（半关闭），不会再有新的请求 ID
+    closed: bool,
+}
+
+impl PendingRequests {
+    /// 只包含一个请求且已半关闭，用于服务端流调用
+    fn single(request_id: &str) -> Self {
+        Self {
+            ids: HashSet::from([request_id.to_string()]),
+            closed: true,
+        }
+    }
+
+    /// 调用是否已结束：已半关闭且所有请求都收到了最后一个响应
+    fn is_complete(&self) -> bool {
+        self.closed && self.ids.is_empty()
+    }
+}
+
+/// 响应是否为该请求的最后一个响应：带 is_stream_end 或 is_final_chunk 标记、带错误，
+/// 或者不带任何流式标记（后端直接返回完整响应后结束）
+fn is_final_response(response: &ForwardResponse) -> bool {
+    if !response.error_message.is_empty() {
+        return true;
+    }
+    if let Some(info) = &response.response_stream_info
+        && info.is_streamed
+    {
+        return info.is_final_chunk;
+    }
+    response
+        .streaming_info
+        .as_ref()
+        .is_none_or(|info| info.is_stream_end)
+}
+
 /// 启动请求发送任务
 pub(crate) async fn spawn_request_sender<T>(
     client: &GatewayClient,
     requests: impl Stream<Item = T> + Send + Unpin + 'static,
     request_tx: mpsc::Sender<ForwardRequest>,
+    pending: Arc<Mutex<PendingRequests>>,
     service_name: &str,
     method_path: &str,
 ) where
@@ -49,6 +92,10 @@ pub(crate) async fn spawn_request_sender<T>(
             };
 
             sequence_number += 1;
+            // 先登记再发送，响应不会早于登记到达
+            lock_pending(&pending)
+                .ids
+                .insert(forward_request.request_id.clone());
             if request_tx.send(forward_request).await.is_err() {
                 break;
             }
@@ -72,50 +119,90 @@ pub(crate) async fn spawn_request_sender<T>(
             }),
         };
 
-        let _ = request_tx.send(end_request).await;
+        let sent = {
+            let mut pending = lock_pending(&pending);
+            pending.ids.insert(end_request.request_id.clone());
+            pending.closed = true;
+            end_request.request_id.clone()
+        };
+        if request_tx.send(end_request).await.is_err() {
+            lock_pending(&pending).ids.remove(&sent);
+        }
     });
 }
 
+fn lock_pending(pending: &Mutex<PendingRequests>) -> std::sync::MutexGuard<'_, PendingRequests> {
+    pending
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
 /// 启动响应处理任务
 pub(crate) async fn spawn_response_handler<R>(
-    mut inbound: impl Stream<Item = Result<crate::registry::ConnectionMessage, Status>>
+    inbound: impl Stream<Item = Result<crate::registry::ConnectionMessage, Status>>
     + Send
     + Unpin
     + 'static,
+    pending: Arc<Mutex<PendingRequests>>,
     response_tx: mpsc::Sender<Result<R, GatewayClientError>>,
 ) where
     R: prost::Message + Default + Send + 'static,
 {
-    tokio::spawn(async move {
-        while let Some(message_result) = inbound.next().await {
-            match message_result {
-                Ok(message) => {
-                    if let Some(crate::registry::connection_message::MessageType::Response(
-                        response,
-                    )) = message.message_type
-                    {
-                        let result =
-                            super::generic::deserialize_response_static::<R>(response.payload);
-
-                        if response_tx.send(result).await.is_err() {
-                            break;
-                        }
-
-                        // 检查流结束
-                        if let Some(streaming_info) = response.streaming_info
-                            && streaming_info.is_stream_end
-                        {
-                            break;
-                        }
-                    }
-                }
-                Err(e) => {
-                    let _ = response_tx.send(Err(GatewayClientError::Grpc(e))).await;
-                    break;
-                }
+    tokio::spawn(dispatch_responses(inbound, pending, response_tx));
+}
+
+/// 把属于本次调用的响应交给调用方，所有请求都结束后停止，即使连接仍然打开
+async fn dispatch_responses<R>(
+    mut inbound: impl Stream<Item = Result<crate::registry::ConnectionMessage, Status>> + Unpin,
+    pending: Arc<Mutex<PendingRequests>>,
+    response_tx: mpsc::Sender<Result<R, GatewayClientError>>,
+) where
+    R: prost::Message + Default,
+{
+    while let Some(message_result) = inbound.next().await {
+        let message = match message_result {
+            Ok(message) => message,
+            Err(e) => {
+                let _ = response_tx.send(Err(GatewayClientError::Grpc(e))).await;
+                return;
+            }
+        };
+        let Some(crate::registry::connection_message::MessageType::Response(response)) =
+            message.message_type
+        else {
+            continue;
+        };
+
+        let is_final = is_final_response(&response);
+        {
+            let mut pending = lock_pending(&pending);
+            if !pending.ids.contains(&response.request_id) {
+                tracing::debug!(
+                    request_id = %response.request_id,
+                    "Ignoring response for another request"
+                );
+                continue;
+            }
+            if is_final {
+                pending.ids.remove(&response.request_id);
             }
         }
-    });
+
+        let result = if response.error_message.is_empty() {
+            super::generic::deserialize_response_static::<R>(response.payload)
+        } else {
+            Err(GatewayClientError::Grpc(Status::unknown(
+                response.error_message,
+            )))
+        };
+        if response_tx.send(result).await.is_err() {
+            return;
+        }
+
+        if is_final && lock_pending(&pending).is_complete() {
+            return;
+        }
+    }
 }
 
 /// 发送客户端流请求
@@ -190,6 +277,7 @@ pub(crate) fn send_client_stream_requests<T>(
 pub(crate) fn send_server_stream_request<T>(
     client: &GatewayClient,
     request: T,
+    request_id: String,
     request_tx: mpsc::Sender<ForwardRequest>,
     service_name: &str,
     method_path: &str,
@@ -209,7 +297,7 @@ pub(crate) fn send_server_stream_request<T>(
         headers.insert("x-service-name".to_string(), service_name);
 
         let forward_request = ForwardRequest {
-            request_id: Uuid::new_v4().to_string(),
+            request_id,
             method_path,
             headers,
             payload,
@@ -228,39 +316,12 @@ pub(crate) fn send_server_stream_request<T>(
 
 /// 启动服务端流响应处理任务
 pub(crate) fn spawn_server_stream_handler<R>(
-    mut inbound: tonic::Streaming<crate::registry::ConnectionMessage>,
+    inbound: tonic::Streaming<crate::registry::ConnectionMessage>,
+    request_id: &str,
     response_tx: mpsc::Sender<Result<R, GatewayClientError>>,
 ) where
     R: prost::Message + Default + Send + 'static,
 {
-    tokio::spawn(async move {
-        while let Some(message_result) = inbound.next().await {
-            let message = match message_result {
-                Ok(msg) => msg,
-                Err(e) => {
-                    let _ = response_tx.send(Err(GatewayClientError::Grpc(e))).await;
-                    break;
-                }
-            };
-
-            let Some(crate::registry::connection_message::MessageType::Response(response)) =
-                message.message_type
-            else {
-                continue;
-            };
-
-            let result = super::generic::deserialize_response_static::<R>(response.payload);
-
-            if response_tx.send(result).await.is_err() {
-                break;
-            }
-
-            // 检查流结束标记
-            if let Some(streaming_info) = response.streaming_info
-                && streaming_info.is_stream_end
-            {
-                break;
-            }
-        }
-    });
+    let pending = Arc::new(Mutex::new(PendingRequests::single(request_id)));
+    tokio::spawn(dispatch_responses(inbound, pending, response_tx));
 }
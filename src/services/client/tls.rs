@@ -0,0 +1,86 @@
+use std::io;
+
+use tonic::transport::{Certificate, ClientTlsConfig, Endpoint, Identity, Uri};
+
+/// 出站连接 TLS 配置
+///
+/// `https://` 地址总是使用 TLS；`enabled` 为 true 时 `http://` 地址也按 TLS 连接。
+#[derive(Debug, Clone, Default)]
+pub struct ClientTlsSettings {
+    /// 对非 https 地址也强制启用 TLS
+    pub enabled: bool,
+    /// 校验服务端证书的 CA 证书（PEM），未设置时使用 tonic 启用的根证书
+    pub ca_certificate: Option<Vec<u8>>,
+    /// 客户端证书和私钥（PEM），用于 mTLS
+    pub identity: Option<(Vec<u8>, Vec<u8>)>,
+    /// 覆盖证书校验使用的域名，默认取地址中的主机名
+    pub domain_name: Option<String>,
+}
+
+impl ClientTlsSettings {
+    /// 从 PEM 文件加载 CA 和客户端证书
+    pub fn from_files(
+        enabled: bool,
+        ca_path: Option<&str>,
+        client_cert_path: Option<&str>,
+        client_key_path: Option<&str>,
+    ) -> io::Result<Self> {
+        let ca_certificate = ca_path.map(std::fs::read).transpose()?;
+        let identity = match (client_cert_path, client_key_path) {
+            (Some(cert), Some(key)) => Some((std::fs::read(cert)?, std::fs::read(key)?)),
+            (None, None) => None,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Client certificate and key must be set together",
+                ));
+            }
+        };
+
+        Ok(Self {
+            enabled,
+            ca_certificate,
+            identity,
+            domain_name: None,
+        })
+    }
+
+    /// 地址是否需要使用 TLS
+    pub fn applies_to(&self, uri: &Uri) -> bool {
+        self.enabled || uri.scheme_str() == Some("https")
+    }
+
+    /// 构建 tonic 客户端 TLS 配置
+    pub fn client_tls_config(&self) -> ClientTlsConfig {
+        let mut tls = ClientTlsConfig::new();
+        tls = match &self.ca_certificate {
+            Some(ca) => tls.ca_certificate(Certificate::from_pem(ca)),
+            None => tls.with_enabled_roots(),
+        };
+        if let Some((cert, key)) = &self.identity {
+            tls = tls.identity(Identity::from_pem(cert, key));
+        }
+        if let Some(domain_name) = &self.domain_name {
+            tls = tls.domain_name(domain_name.clone());
+        }
+        tls
+    }
+
+    /// 为地址创建 Endpoint，需要时附加 TLS 配置
+    pub fn endpoint(&self, uri: Uri) -> Result<Endpoint, tonic::transport::Error> {
+        if !self.applies_to(&uri) {
+            return Ok(Endpoint::from(uri));
+        }
+
+        // tonic 只对 https 地址执行 TLS 握手，强制启用时改写协议
+        let endpoint = if uri.scheme_str() == Some("https") {
+            Endpoint::from(uri)
+        } else {
+            let authority = uri.authority().map(|a| a.as_str()).unwrap_or_default();
+            let path = uri.path_and_query().map(|p| p.as_str()).unwrap_or_default();
+            Endpoint::from_shared(format!("https://{authority}{path}"))?
+        };
+
+        endpoint.tls_config(self.client_tls_config())
+    }
+}
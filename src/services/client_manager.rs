@@ -1,45 +1,103 @@
+use arc_swap::ArcSwap;
 use dashmap::DashMap;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
 use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
 use tokio::time::interval;
+use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
-use tonic::transport::{Channel, Endpoint, Uri};
+use tonic::transport::{Channel, Uri};
+
+use super::client::{ClientTlsSettings, Http2Settings};
+use super::router::load_balance::InFlight;
+use super::router::sticky::StickyInstances;
+
+// 连接池满时选择淘汰连接的策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EvictionPolicy {
+    // 最久未使用
+    #[default]
+    Lru,
+    // 使用次数最少，次数相同时淘汰最久未使用的
+    Lfu,
+    // 最早创建
+    Oldest,
+}
 
 // 连接池配置
 #[derive(Debug, Clone)]
 pub struct ConnectionPoolConfig {
+    // 最多缓存的后端地址数
     pub max_connections: usize,
+    // 每个地址最多建立的通道数，请求在这些通道间轮询
+    pub connections_per_address: usize,
+    pub eviction_policy: EvictionPolicy,
     pub connection_ttl: Duration,
     pub idle_timeout: Duration,
     pub cleanup_interval: Duration,
+    pub circuit_failure_threshold: u32,
+    pub circuit_cooldown: Duration,
+    pub tls: ClientTlsSettings, // 后端出站 TLS
+    pub http2: Http2Settings,   // 后端连接的 HTTP/2 keepalive 和窗口参数
+    // 正向注册新实例时预先建立到其地址的连接
+    pub warmup_on_register: bool,
 }
 
 impl Default for ConnectionPoolConfig {
     fn default() -> Self {
         Self {
             max_connections: 100,
+            connections_per_address: 1,
+            eviction_policy: EvictionPolicy::default(),
             connection_ttl: Duration::from_secs(300), // 5分钟
             idle_timeout: Duration::from_secs(60),    // 1分钟
             cleanup_interval: Duration::from_secs(30), // 30秒清理一次
+            circuit_failure_threshold: 5,
+            circuit_cooldown: Duration::from_secs(30),
+            tls: ClientTlsSettings::default(),
+            http2: Http2Settings::default(),
+            warmup_on_register: true,
         }
     }
 }
 
-// 连接元数据
+// 单个地址的连接元数据，通道数不超过 connections_per_address
 #[derive(Debug, Clone)]
 pub struct ConnectionMetadata {
-    pub channel: Channel,
+    pub channels: Vec<Channel>,
     pub created_at: Instant,
     pub last_used: Instant,
     pub use_count: u64,
 }
 
 impl ConnectionMetadata {
+    pub fn new(channel: Channel) -> Self {
+        let now = Instant::now();
+        Self {
+            channels: vec![channel],
+            created_at: now,
+            last_used: now,
+            use_count: 0,
+        }
+    }
+
     pub fn touch(&mut self) {
         self.last_used = Instant::now();
         self.use_count += 1;
     }
 
+    // 按使用次数轮询选择通道
+    pub fn next_channel(&mut self) -> Channel {
+        let index = (self.use_count % self.channels.len() as u64) as usize;
+        self.touch();
+        self.channels[index].clone()
+    }
+
     pub fn is_expired(&self, config: &ConnectionPoolConfig) -> bool {
         let now = Instant::now();
         now.duration_since(self.created_at) > config.connection_ttl
@@ -49,12 +107,101 @@ impl ConnectionMetadata {
 
 pub type ClientPool = Arc<DashMap<String, ConnectionMetadata>>;
 
+// 熔断器状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+impl CircuitState {
+    // 用于统计输出的数值编码
+    fn as_stat(self) -> u64 {
+        match self {
+            CircuitState::Closed => 0,
+            CircuitState::Open => 1,
+            CircuitState::HalfOpen => 2,
+        }
+    }
+}
+
+// 单个后端地址的熔断器
+#[derive(Debug, Clone)]
+pub struct BreakerState {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    // 最近一次熔断或发出半开探测的时间
+    pub opened_at: Option<Instant>,
+}
+
+impl Default for BreakerState {
+    fn default() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+        }
+    }
+}
+
+// 连接池快照，供容量规划使用；年龄和空闲时长按地址统计，连接池为空时均为 0
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PoolSnapshot {
+    // 缓存的后端地址数
+    pub addresses: usize,
+    // 所有地址的通道总数
+    pub channels: usize,
+    // 距 created_at 的时长
+    pub min_age: Duration,
+    pub max_age: Duration,
+    pub avg_age: Duration,
+    // 当前缓存的连接累计被取用的次数
+    pub total_use_count: u64,
+    // 距 last_used 的时长
+    pub max_idle: Duration,
+    pub avg_idle: Duration,
+}
+
+// 熔断期间快速失败返回的错误
+#[derive(Debug, thiserror::Error)]
+#[error("Circuit open for {address}")]
+pub struct CircuitOpenError {
+    pub address: String,
+}
+
+// 正在进行的一次连接建立，错误转为字符串以便在等待者之间共享
+type PendingConnect = Shared<BoxFuture<'static, Result<Channel, String>>>;
+
+// 按地址记录正在建立的连接，同一地址的并发请求等待同一次建立，而不是各自拨号
+#[derive(Default)]
+struct PendingConnects(DashMap<String, PendingConnect>);
+
+impl fmt::Debug for PendingConnects {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PendingConnects")
+            .field("addresses", &self.0.len())
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GrpcClientManager {
     pub clients: ClientPool,
-    pub config: ConnectionPoolConfig,
-    pub stats: Arc<DashMap<String, u64>>, // 连接统计
+    // 连接池配置，支持运行中热更新
+    config: Arc<ArcSwap<ConnectionPoolConfig>>,
+    pub stats: Arc<DashMap<String, u64>>,             // 连接统计
+    pub breakers: Arc<DashMap<String, BreakerState>>, // 按地址的熔断器
+    // 正向转发负载均衡的轮询游标
+    pub(crate) next_instance: Arc<AtomicUsize>,
+    // 每个后端地址上进行中的请求数，供 least_connections 策略使用
+    pub(crate) in_flight: Arc<InFlight>,
+    // 实例选择粘滞：客户端 -> 上次选中的实例地址
+    pub(crate) sticky: Arc<StickyInstances>,
+    pending_connects: Arc<PendingConnects>,
     task_tracker: Arc<TaskTracker>,
+    // 停机信号，触发后清理任务退出
+    shutdown: CancellationToken,
 }
 
 impl Default for GrpcClientManager {
@@ -67,9 +214,15 @@ impl GrpcClientManager {
     pub fn new(config: ConnectionPoolConfig) -> Self {
         let manager = Self {
             clients: Arc::new(DashMap::new()),
-            config: config.clone(),
+            config: Arc::new(ArcSwap::from_pointee(config)),
             stats: Arc::new(DashMap::new()),
+            breakers: Arc::new(DashMap::new()),
+            next_instance: Arc::default(),
+            in_flight: Arc::default(),
+            sticky: Arc::default(),
+            pending_connects: Arc::new(PendingConnects::default()),
             task_tracker: Arc::new(TaskTracker::new()),
+            shutdown: CancellationToken::new(),
         };
 
         // 启动清理任务
@@ -78,21 +231,32 @@ impl GrpcClientManager {
         manager
     }
 
+    // 当前连接池配置
+    pub fn config(&self) -> Arc<ConnectionPoolConfig> {
+        self.config.load_full()
+    }
+
+    // 替换连接池配置，新的上限和超时对后续的获取与清理立即生效，
+    // 已缓存的连接不会因此断开
+    pub fn update_config(&self, config: ConnectionPoolConfig) {
+        self.config.store(Arc::new(config));
+    }
+
     pub async fn get_or_create_client(
         &self,
         address: &str,
     ) -> Result<Channel, Box<dyn std::error::Error + Send + Sync>> {
-        // 如果达到最大连接数限制，移除最老的连接
-        if self.clients.len() >= self.config.max_connections {
-            self.evict_oldest_connection().await;
-        }
+        // 熔断中的地址直接失败，不再承担连接超时的代价
+        self.check_circuit(address)?;
 
-        // 尝试从缓存获取并更新使用时间
+        // 尝试从缓存获取并更新使用时间，通道数未达到上限时继续建立新通道
+        let connections_per_address = self.config.load().connections_per_address.max(1);
         if let Some(mut entry) = self.clients.get_mut(address) {
-            if !entry.is_expired(&self.config) {
-                entry.touch();
-                self.increment_stat("cache_hits");
-                return Ok(entry.channel.clone());
+            if !entry.is_expired(&self.config.load()) {
+                if entry.channels.len() >= connections_per_address {
+                    self.increment_stat("cache_hits");
+                    return Ok(entry.next_channel());
+                }
             } else {
                 // 连接已过期，移除它
                 drop(entry);
@@ -102,30 +266,283 @@ impl GrpcClientManager {
 
         self.increment_stat("cache_misses");
 
+        // 同一地址已有连接正在建立时等待它完成，不再重复拨号
+        let connect = self.pending_connect(address);
+        let result = connect.clone().await;
+        // 建立结束后由任一等待者移除，之后的请求重新经过缓存判断
+        self.pending_connects
+            .0
+            .remove_if(address, |_, pending| pending.ptr_eq(&connect));
+        result.map_err(Into::into)
+    }
+
+    // 取得地址上正在进行的连接建立，没有时发起一次。缓存写入和失败计数在共享的
+    // 建立过程中完成，无论有多少个等待者都只执行一次
+    fn pending_connect(&self, address: &str) -> PendingConnect {
+        let mut coalesced = true;
+        let connect = self
+            .pending_connects
+            .0
+            .entry(address.to_string())
+            .or_insert_with(|| {
+                coalesced = false;
+                let manager = self.clone();
+                let address = address.to_string();
+                async move {
+                    manager
+                        .connect_and_cache(&address)
+                        .await
+                        .map_err(|e| e.to_string())
+                }
+                .boxed()
+                .shared()
+            })
+            .clone();
+        if coalesced {
+            self.increment_stat("connects_coalesced");
+        }
+        connect
+    }
+
+    // 建立新连接并加入缓存
+    async fn connect_and_cache(
+        &self,
+        address: &str,
+    ) -> Result<Channel, Box<dyn std::error::Error + Send + Sync>> {
+        let connections_per_address = self.config.load().connections_per_address.max(1);
+
+        // 需要缓存新地址且达到最大连接数限制时，按淘汰策略移除一个地址的连接
+        if !self.clients.contains_key(address)
+            && self.clients.len() >= self.config.load().max_connections
+        {
+            self.evict_connection().await;
+        }
+
         // 创建新的客户端连接
+        let channel = match self.connect(address).await {
+            Ok(channel) => channel,
+            Err(e) => {
+                self.record_failure(address);
+                return Err(e);
+            }
+        };
+
+        // 将新连接加入缓存，建立期间并发请求已经补满的地址不再追加
+        let channel_count = {
+            let mut entry = self
+                .clients
+                .entry(address.to_string())
+                .and_modify(|entry| {
+                    if entry.channels.len() < connections_per_address {
+                        entry.channels.push(channel.clone());
+                    }
+                })
+                .or_insert_with(|| ConnectionMetadata::new(channel.clone()));
+            entry.touch();
+            entry.channels.len()
+        };
+        self.increment_stat("connections_created");
+
+        tracing::info!(
+            address = %address,
+            channel_count,
+            total_clients = self.clients.len(),
+            "Created new gRPC client connection"
+        );
+        Ok(channel)
+    }
+
+    // 预先建立到指定地址的连接并加入连接池，首个请求不再承担握手延迟。
+    // 已缓存、熔断中或连接失败的地址跳过，连接池达到 max_connections 后不再预热，
+    // 也不会为此驱逐已有连接；返回新建立的连接数
+    pub async fn warmup(&self, addresses: &[String]) -> usize {
+        let config = self.config.load();
+        let capacity = config.max_connections.saturating_sub(self.clients.len());
+
+        let mut pending: Vec<&str> = Vec::new();
+        for address in addresses {
+            let address = address.as_str();
+            let cached = self
+                .clients
+                .get(address)
+                .is_some_and(|entry| !entry.is_expired(&config));
+            let circuit_open = self
+                .breakers
+                .get(address)
+                .is_some_and(|breaker| breaker.state != CircuitState::Closed);
+            if cached || circuit_open || pending.contains(&address) {
+                continue;
+            }
+            if pending.len() >= capacity {
+                tracing::debug!(
+                    address = %address,
+                    max_connections = config.max_connections,
+                    "Connection pool full, skipping warmup"
+                );
+                break;
+            }
+            pending.push(address);
+        }
+
+        let results =
+            futures::future::join_all(pending.iter().map(|address| self.connect(address))).await;
+
+        let mut warmed = 0;
+        for (address, result) in pending.into_iter().zip(results) {
+            match result {
+                Ok(channel) => {
+                    // 预热期间被请求抢先建立的连接保持不变
+                    if self.clients.contains_key(address)
+                        || self.clients.len() >= self.config.load().max_connections
+                    {
+                        continue;
+                    }
+                    self.clients
+                        .insert(address.to_string(), ConnectionMetadata::new(channel));
+                    self.increment_stat("connections_warmed");
+                    warmed += 1;
+                    tracing::info!(address = %address, total_clients = self.clients.len(), "Pre-established gRPC client connection");
+                }
+                Err(e) => {
+                    // 后端可能在注册之后才开始监听，预热失败不计入熔断
+                    tracing::warn!(address = %address, error = %e, "Connection warmup failed");
+                }
+            }
+        }
+
+        warmed
+    }
+
+    // 正向注册新实例时预热其地址，直到 shutdown 触发
+    pub fn spawn_registration_warmup(
+        &self,
+        mut registrations: broadcast::Receiver<String>,
+        shutdown: CancellationToken,
+    ) {
+        let manager = self.clone();
+        self.task_tracker.spawn(async move {
+            loop {
+                let address = tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = manager.shutdown.cancelled() => break,
+                    received = registrations.recv() => match received {
+                        Ok(address) => address,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            tracing::debug!(skipped, "Skipped registration warmups");
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                };
+
+                if manager.config.load().warmup_on_register {
+                    manager.warmup(&[address]).await;
+                }
+            }
+        });
+    }
+
+    // 按当前 TLS 配置建立到地址的连接
+    async fn connect(
+        &self,
+        address: &str,
+    ) -> Result<Channel, Box<dyn std::error::Error + Send + Sync>> {
         let uri: Uri = address
             .parse()
             .map_err(|e| format!("Invalid URI {address}: {e}"))?;
-        let endpoint = Endpoint::from(uri);
+        let config = self.config.load_full();
+        let endpoint = config
+            .tls
+            .endpoint(uri)
+            .map_err(|e| format!("Invalid TLS configuration for {address}: {e}"))?;
+        let endpoint = config.http2.apply_to_endpoint(endpoint);
 
-        let channel = endpoint
+        endpoint
             .connect()
             .await
-            .map_err(|e| format!("Failed to connect to {address}: {e}"))?;
+            .map_err(|e| format!("Failed to connect to {address}: {e}").into())
+    }
 
-        // 将新连接加入缓存
-        let now = Instant::now();
-        let metadata = ConnectionMetadata {
-            channel: channel.clone(),
-            created_at: now,
-            last_used: now,
-            use_count: 0,
+    // 检查地址的熔断状态，冷却结束后放行一个半开探测请求
+    fn check_circuit(&self, address: &str) -> Result<(), CircuitOpenError> {
+        let Some(mut breaker) = self.breakers.get_mut(address) else {
+            return Ok(());
         };
-        self.clients.insert(address.to_string(), metadata);
-        self.increment_stat("connections_created");
 
-        tracing::info!(address = %address, total_clients = self.clients.len(), "Created new gRPC client connection");
-        Ok(channel)
+        if breaker.state == CircuitState::Closed {
+            return Ok(());
+        }
+
+        let cooled_down = breaker
+            .opened_at
+            .is_none_or(|opened_at| opened_at.elapsed() >= self.config.load().circuit_cooldown);
+        if !cooled_down {
+            drop(breaker);
+            self.increment_stat("circuit_rejected");
+            return Err(CircuitOpenError {
+                address: address.to_string(),
+            });
+        }
+
+        // 半开探测期间其余请求继续快速失败，探测迟迟没有结果时再次放行
+        breaker.state = CircuitState::HalfOpen;
+        breaker.opened_at = Some(Instant::now());
+        drop(breaker);
+        tracing::info!(address = %address, "Circuit half-open, probing backend");
+        Ok(())
+    }
+
+    // 记录一次成功请求，关闭熔断器
+    pub fn record_success(&self, address: &str) {
+        if let Some((_, breaker)) = self.breakers.remove(address)
+            && breaker.state != CircuitState::Closed
+        {
+            tracing::info!(address = %address, "Circuit closed, backend recovered");
+        }
+    }
+
+    // 记录一次失败，连续失败达到阈值或半开探测失败时熔断
+    pub fn record_failure(&self, address: &str) {
+        let threshold = self.config.load().circuit_failure_threshold;
+        if threshold == 0 {
+            return;
+        }
+
+        let mut breaker = self.breakers.entry(address.to_string()).or_default();
+        breaker.consecutive_failures = breaker.consecutive_failures.saturating_add(1);
+
+        let should_open = breaker.state == CircuitState::HalfOpen
+            || (breaker.state == CircuitState::Closed && breaker.consecutive_failures >= threshold);
+        if !should_open {
+            return;
+        }
+
+        breaker.state = CircuitState::Open;
+        breaker.opened_at = Some(Instant::now());
+        let failures = breaker.consecutive_failures;
+        drop(breaker);
+
+        // 熔断时丢弃缓存的连接，恢复后重新建立
+        self.clients.remove(address);
+        self.increment_stat("circuit_opened");
+        tracing::warn!(
+            address = %address,
+            consecutive_failures = failures,
+            cooldown_secs = self.config.load().circuit_cooldown.as_secs(),
+            "Circuit opened for backend"
+        );
+    }
+
+    // 所有地址缓存的通道总数
+    pub fn channel_count(&self) -> usize {
+        self.clients
+            .iter()
+            .map(|entry| entry.value().channels.len())
+            .sum()
+    }
+
+    pub fn get_breaker_state(&self, address: &str) -> Option<BreakerState> {
+        self.breakers.get(address).map(|breaker| breaker.clone())
     }
 
     pub async fn remove_client(&self, address: &str) {
@@ -139,14 +556,64 @@ impl GrpcClientManager {
         let count = self.clients.len();
         self.clients.clear();
         self.stats.clear();
+        self.breakers.clear();
         tracing::info!(cleared_count = count, "Cleared gRPC client connections");
     }
 
+    // 连接统计，同时包含每个地址的熔断状态（0=关闭，1=熔断，2=半开）和连续失败次数
     pub fn get_stats(&self) -> std::collections::HashMap<String, u64> {
-        self.stats
+        let mut stats: std::collections::HashMap<String, u64> = self
+            .stats
             .iter()
             .map(|entry| (entry.key().clone(), *entry.value()))
-            .collect()
+            .collect();
+
+        for entry in self.breakers.iter() {
+            let breaker = entry.value();
+            stats.insert(
+                format!("circuit_state:{}", entry.key()),
+                breaker.state.as_stat(),
+            );
+            stats.insert(
+                format!("circuit_failures:{}", entry.key()),
+                u64::from(breaker.consecutive_failures),
+            );
+        }
+
+        stats
+    }
+
+    // 遍历连接池生成快照，逐个分片读取，不持有整个连接池的锁
+    pub fn detailed_stats(&self) -> PoolSnapshot {
+        let now = Instant::now();
+        let mut snapshot = PoolSnapshot {
+            min_age: Duration::MAX,
+            ..Default::default()
+        };
+        let mut total_age = Duration::ZERO;
+        let mut total_idle = Duration::ZERO;
+
+        for entry in self.clients.iter() {
+            let metadata = entry.value();
+            let age = now.saturating_duration_since(metadata.created_at);
+            let idle = now.saturating_duration_since(metadata.last_used);
+
+            snapshot.addresses += 1;
+            snapshot.channels += metadata.channels.len();
+            snapshot.total_use_count += metadata.use_count;
+            snapshot.min_age = snapshot.min_age.min(age);
+            snapshot.max_age = snapshot.max_age.max(age);
+            snapshot.max_idle = snapshot.max_idle.max(idle);
+            total_age += age;
+            total_idle += idle;
+        }
+
+        if snapshot.addresses == 0 {
+            return PoolSnapshot::default();
+        }
+        snapshot.avg_age = total_age / snapshot.addresses as u32;
+        snapshot.avg_idle = total_idle / snapshot.addresses as u32;
+        snapshot
     }
 
     fn increment_stat(&self, key: &str) {
@@ -156,40 +623,62 @@ impl GrpcClientManager {
             .or_insert(1);
     }
 
-    async fn evict_oldest_connection(&self) {
-        let mut oldest_key: Option<String> = None;
-        let mut oldest_time = Instant::now();
+    // 按淘汰策略选出一个地址并移除其连接；逐个分片遍历，不持有整个连接池的锁
+    async fn evict_connection(&self) {
+        let policy = self.config.load().eviction_policy;
+        let mut victim: Option<(String, (u64, Instant))> = None;
 
         for entry in self.clients.iter() {
-            if entry.value().created_at < oldest_time {
-                oldest_time = entry.value().created_at;
-                oldest_key = Some(entry.key().clone());
+            let metadata = entry.value();
+            let rank = match policy {
+                EvictionPolicy::Lru => (0, metadata.last_used),
+                EvictionPolicy::Lfu => (metadata.use_count, metadata.last_used),
+                EvictionPolicy::Oldest => (0, metadata.created_at),
+            };
+            if victim.as_ref().is_none_or(|(_, lowest)| rank < *lowest) {
+                victim = Some((entry.key().clone(), rank));
             }
         }
 
-        if let Some(key) = oldest_key
+        if let Some((key, _)) = victim
             && self.clients.remove(&key).is_some()
         {
             self.increment_stat("connections_evicted");
-            tracing::debug!(evicted_connection = %key, "Evicted oldest connection");
+            tracing::debug!(evicted_connection = %key, policy = ?policy, "Evicted pooled connection");
         }
     }
 
+    // 停止清理和预热任务并等待它们退出，可以重复调用
+    pub async fn shutdown(&self) {
+        self.shutdown.cancel();
+        self.task_tracker.close();
+        self.task_tracker.wait().await;
+    }
+
+    // 仍在运行的后台任务数量
+    pub fn background_tasks(&self) -> usize {
+        self.task_tracker.len()
+    }
+
     fn start_cleanup_task(&self) {
         let clients = self.clients.clone();
         let config = self.config.clone();
         let stats = self.stats.clone();
+        let shutdown = self.shutdown.clone();
 
         self.task_tracker.spawn(async move {
-            let mut cleanup_interval = interval(config.cleanup_interval);
+            let mut cleanup_interval = interval(config.load().cleanup_interval);
             cleanup_interval.tick().await; // 跳过第一个tick
 
             loop {
-                cleanup_interval.tick().await;
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = cleanup_interval.tick() => {}
+                }
 
                 let mut expired_keys = Vec::new();
                 for entry in clients.iter() {
-                    if entry.value().is_expired(&config) {
+                    if entry.value().is_expired(&config.load()) {
                         expired_keys.push(entry.key().clone());
                     }
                 }
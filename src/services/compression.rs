@@ -0,0 +1,232 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use http_body::{Body, Frame};
+use std::io::{Read, Write};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+pub const GRPC_ENCODING: &str = "grpc-encoding";
+pub const GRPC_ACCEPT_ENCODING: &str = "grpc-accept-encoding";
+pub const GZIP: &str = "gzip";
+
+// gRPC 消息帧头：1 字节压缩标志 + 4 字节大端长度
+const FRAME_HEADER_LEN: usize = 5;
+
+// grpc-accept-encoding 是否包含 gzip
+pub fn accepts_gzip(accept_encoding: Option<&str>) -> bool {
+    accept_encoding.is_some_and(|value| {
+        value
+            .split(',')
+            .any(|encoding| encoding.trim().eq_ignore_ascii_case(GZIP))
+    })
+}
+
+// grpc-encoding 是否为 gzip
+pub fn is_gzip(encoding: Option<&str>) -> bool {
+    encoding.is_some_and(|value| value.trim().eq_ignore_ascii_case(GZIP))
+}
+
+// 未压缩：没有 grpc-encoding 或为 identity
+pub fn is_identity(encoding: Option<&str>) -> bool {
+    encoding.is_none_or(|value| value.trim().eq_ignore_ascii_case("identity"))
+}
+
+// 在 grpc-accept-encoding 中追加 gzip
+pub fn with_gzip_accepted(accept_encoding: Option<&str>) -> String {
+    match accept_encoding {
+        Some(value) if accepts_gzip(Some(value)) => value.to_string(),
+        Some(value) if !value.trim().is_empty() => format!("{value},{GZIP}"),
+        _ => GZIP.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameTransform {
+    // 压缩不小于 min_size 的未压缩消息，更小的消息保持原样
+    Compress { min_size: usize },
+    // 解压所有已压缩的消息
+    Decompress,
+}
+
+// 逐帧改写 gRPC 消息的压缩状态；输入可以在任意位置切分，
+// 不完整的帧缓存到下一次输入，帧头不合法时其余数据原样透传
+#[derive(Debug)]
+pub struct FrameRecoder {
+    transform: FrameTransform,
+    buffer: BytesMut,
+    passthrough: bool,
+}
+
+impl FrameRecoder {
+    pub fn new(transform: FrameTransform) -> Self {
+        Self {
+            transform,
+            buffer: BytesMut::new(),
+            passthrough: false,
+        }
+    }
+
+    // 输入一段数据，返回其中已经完整的帧改写后的结果
+    pub fn push(&mut self, data: &[u8]) -> std::io::Result<Bytes> {
+        if self.passthrough {
+            return Ok(Bytes::copy_from_slice(data));
+        }
+
+        self.buffer.extend_from_slice(data);
+        let mut output = BytesMut::new();
+        while self.buffer.len() >= FRAME_HEADER_LEN {
+            let flag = self.buffer[0];
+            if flag > 1 {
+                // 不是 gRPC 消息帧，不再尝试解析
+                self.passthrough = true;
+                output.extend_from_slice(&self.buffer.split());
+                break;
+            }
+
+            let len = u32::from_be_bytes([
+                self.buffer[1],
+                self.buffer[2],
+                self.buffer[3],
+                self.buffer[4],
+            ]) as usize;
+            if self.buffer.len() < FRAME_HEADER_LEN + len {
+                break;
+            }
+
+            self.buffer.advance(FRAME_HEADER_LEN);
+            let message = self.buffer.split_to(len);
+            self.write_frame(flag == 1, &message, &mut output)?;
+        }
+
+        Ok(output.freeze())
+    }
+
+    // 输入结束，返回缓存中不完整的数据（原样）
+    pub fn finish(&mut self) -> Bytes {
+        self.buffer.split().freeze()
+    }
+
+    fn write_frame(
+        &self,
+        compressed: bool,
+        message: &[u8],
+        output: &mut BytesMut,
+    ) -> std::io::Result<()> {
+        let (compressed, message) = match self.transform {
+            FrameTransform::Compress { min_size } if !compressed && message.len() >= min_size => {
+                (true, gzip(message)?)
+            }
+            FrameTransform::Decompress if compressed => (false, gunzip(message)?),
+            _ => (compressed, message.to_vec()),
+        };
+
+        output.reserve(FRAME_HEADER_LEN + message.len());
+        output.put_u8(compressed as u8);
+        output.put_u32(message.len() as u32);
+        output.extend_from_slice(&message);
+        Ok(())
+    }
+}
+
+// 改写完整消息体
+pub fn recode(payload: &[u8], transform: FrameTransform) -> std::io::Result<Vec<u8>> {
+    let mut recoder = FrameRecoder::new(transform);
+    let mut output = recoder.push(payload)?.to_vec();
+    output.extend_from_slice(&recoder.finish());
+    Ok(output)
+}
+
+fn gzip(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+fn gunzip(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut decoder = GzDecoder::new(data);
+    let mut output = Vec::new();
+    decoder.read_to_end(&mut output)?;
+    Ok(output)
+}
+
+// 流式改写响应体中的 gRPC 消息帧
+pub struct RecodedBody<B> {
+    inner: Pin<Box<B>>,
+    recoder: FrameRecoder,
+    // 不完整数据之后待发送的 trailers
+    pending_trailers: Option<http::HeaderMap>,
+    finished: bool,
+}
+
+impl<B> RecodedBody<B> {
+    pub fn new(inner: B, transform: FrameTransform) -> Self {
+        Self {
+            inner: Box::pin(inner),
+            recoder: FrameRecoder::new(transform),
+            pending_trailers: None,
+            finished: false,
+        }
+    }
+}
+
+impl<B> Body for RecodedBody<B>
+where
+    B: Body<Data = Bytes>,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Data = Bytes;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if let Some(trailers) = self.pending_trailers.take() {
+            return Poll::Ready(Some(Ok(Frame::trailers(trailers))));
+        }
+        if self.finished {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            match std::task::ready!(self.inner.as_mut().poll_frame(cx)) {
+                Some(Ok(frame)) => match frame.into_data() {
+                    Ok(data) => {
+                        let recoded = self.recoder.push(&data)?;
+                        // 只收到半个帧时继续读取
+                        if !recoded.is_empty() {
+                            return Poll::Ready(Some(Ok(Frame::data(recoded))));
+                        }
+                    }
+                    Err(frame) => {
+                        let Ok(trailers) = frame.into_trailers() else {
+                            continue;
+                        };
+                        self.finished = true;
+                        let rest = self.recoder.finish();
+                        if rest.is_empty() {
+                            return Poll::Ready(Some(Ok(Frame::trailers(trailers))));
+                        }
+                        self.pending_trailers = Some(trailers);
+                        return Poll::Ready(Some(Ok(Frame::data(rest))));
+                    }
+                },
+                Some(Err(e)) => return Poll::Ready(Some(Err(e.into()))),
+                None => {
+                    self.finished = true;
+                    let rest = self.recoder.finish();
+                    if rest.is_empty() {
+                        return Poll::Ready(None);
+                    }
+                    return Poll::Ready(Some(Ok(Frame::data(rest))));
+                }
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.finished && self.pending_trailers.is_none()
+    }
+}
@@ -0,0 +1,171 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::client_manager::GrpcClientManager;
+use super::connection::ReverseConnectionManager;
+use super::router::DynamicRouter;
+use crate::config::{Config, SharedConfig};
+
+// 配置热更新：配置文件修改时间变化或收到 SIGHUP 时重新读取配置文件和环境变量，
+// 把 token、路由超时、限流和连接池参数应用到运行中的服务
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    path: PathBuf,
+    config: SharedConfig,
+    client_manager: GrpcClientManager,
+    reverse_manager: Arc<ReverseConnectionManager>,
+    // 上次加载时配置文件的修改时间
+    last_modified: Mutex<Option<SystemTime>>,
+}
+
+impl ConfigWatcher {
+    pub fn new(
+        path: impl Into<PathBuf>,
+        config: SharedConfig,
+        client_manager: GrpcClientManager,
+        reverse_manager: Arc<ReverseConnectionManager>,
+    ) -> Self {
+        let path = path.into();
+        let last_modified = Mutex::new(Self::modified_time(&path));
+        Self {
+            path,
+            config,
+            client_manager,
+            reverse_manager,
+            last_modified,
+        }
+    }
+
+    // 重新加载配置，返回发生变化但需要重启才能生效的配置项
+    // 配置文件无法解析时返回错误，运行中的配置保持不变
+    pub fn reload(&self) -> Result<Vec<&'static str>, Box<dyn std::error::Error>> {
+        let new = Config::reload_from(&self.path)?;
+        let current = self.config.load();
+
+        let restart_required = current.restart_required_changes(&new);
+        for field in &restart_required {
+            tracing::warn!(
+                field = %field,
+                "Configuration change requires a restart to take effect"
+            );
+        }
+
+        let merged = current.with_live_fields_from(new);
+        // 出站证书无法读取时保留当前配置，不降级为默认 TLS 设置
+        self.client_manager
+            .update_config(DynamicRouter::connection_pool_config(&merged)?);
+        // 正向转发每次请求都读取配置，反向连接的负载均衡策略需要单独写入管理器
+        self.reverse_manager
+            .set_load_balance_strategy(merged.router.load_balance_strategy);
+        self.config.store(merged);
+
+        tracing::info!(path = %self.path.display(), "Configuration reloaded");
+        Ok(restart_required)
+    }
+
+    // 配置文件修改时间变化时重新加载，返回是否执行了加载
+    pub fn reload_if_modified(&self) -> bool {
+        let modified = Self::modified_time(&self.path);
+        {
+            let mut last_modified = self.last_modified.lock().unwrap_or_else(|e| e.into_inner());
+            if *last_modified == modified {
+                return false;
+            }
+            *last_modified = modified;
+        }
+
+        if let Err(e) = self.reload() {
+            tracing::error!(
+                path = %self.path.display(),
+                error = %e,
+                "Failed to reload configuration, keeping current settings"
+            );
+        }
+        true
+    }
+
+    // 启动后台任务：定期检查修改时间，Unix 上同时响应 SIGHUP
+    // poll_interval 为 None 时只响应 SIGHUP
+    pub fn spawn(
+        self,
+        poll_interval: Option<Duration>,
+        shutdown: CancellationToken,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = poll_interval.map(|period| {
+                let mut interval = tokio::time::interval(period);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                interval
+            });
+            let mut hangup = HangupSignal::new();
+
+            loop {
+                let tick = async {
+                    match &mut interval {
+                        Some(interval) => {
+                            interval.tick().await;
+                        }
+                        None => std::future::pending::<()>().await,
+                    }
+                };
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = tick => {
+                        self.reload_if_modified();
+                    }
+                    _ = hangup.recv() => {
+                        tracing::info!("SIGHUP received, reloading configuration");
+                        if let Err(e) = self.reload() {
+                            tracing::error!(
+                                error = %e,
+                                "Failed to reload configuration, keeping current settings"
+                            );
+                        }
+                    }
+                }
+            }
+            tracing::debug!("Config watcher stopped");
+        })
+    }
+
+    fn modified_time(path: &std::path::Path) -> Option<SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+}
+
+// SIGHUP 监听，非 Unix 平台或注册失败时永不触发
+struct HangupSignal {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl HangupSignal {
+    fn new() -> Self {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{SignalKind, signal};
+            let signal = signal(SignalKind::hangup())
+                .inspect_err(|e| tracing::warn!(error = %e, "Failed to listen for SIGHUP"))
+                .ok();
+            Self { signal }
+        }
+        #[cfg(not(unix))]
+        {
+            Self {}
+        }
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = &mut self.signal {
+            signal.recv().await;
+            return;
+        }
+        std::future::pending::<()>().await
+    }
+}
@@ -1,16 +1,21 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use dashmap::DashMap;
-use tokio::sync::RwLock;
 
-use crate::services::registry::types::{ServiceInstances, ServiceRegistry};
 use super::{
+    connection::ReverseConnection,
     manager::ReverseConnectionManager,
     service_pool::ServicePool,
-    types::PendingRequest,
-    connection::ReverseConnection,
+    types::{PendingRequests, REQUEST_TIMEOUT_EVENT, StreamingResponseHandler},
+};
+use crate::registry::{
+    ConnectionMessage, ConnectionStatus, connection_message::MessageType,
+    connection_status::StatusType,
 };
+use crate::services::event::EventBus;
+use crate::services::registry::types::{ServiceInstances, ServiceRegistry};
 
 impl ReverseConnectionManager {
     // 启动清理任务
@@ -18,22 +23,40 @@ impl ReverseConnectionManager {
         let connections_by_service = self.connections_by_service.clone();
         let connections_by_id = self.connections_by_id.clone();
         let pending_requests = self.pending_requests.clone();
+        let streaming_handlers = self.streaming_handlers.clone();
+        let stream_idle_timeout = self.config.stream_idle_timeout;
         let heartbeat_timeout = self.config.heartbeat_timeout;
-        let request_timeout = self.config.request_timeout;
+        let request_timeout = self.config.max_request_timeout();
         let cleanup_interval = self.config.cleanup_interval;
+        let max_connection_age = self.config.max_connection_age;
         let service_registry = self.service_registry.clone();
+        let event_bus = self.event_bus.clone();
+        let shutdown = self.shutdown.clone();
 
         self.task_tracker.spawn(async move {
             let mut interval = tokio::time::interval(cleanup_interval);
             loop {
-                interval.tick().await;
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = interval.tick() => {}
+                }
                 Self::cleanup_expired_connections(
                     &connections_by_service,
                     &connections_by_id,
                     service_registry.clone(),
                     heartbeat_timeout,
                 );
-                Self::cleanup_expired_requests(&pending_requests, request_timeout).await;
+                if let Some(max_age) = max_connection_age {
+                    Self::rotate_aged_connections(
+                        &connections_by_service,
+                        &connections_by_id,
+                        service_registry.clone(),
+                        max_age,
+                    );
+                }
+                Self::cleanup_expired_requests(&pending_requests, &event_bus, request_timeout);
+                Self::cleanup_stale_streams(&streaming_handlers, &event_bus, stream_idle_timeout);
+                event_bus.evict_stale_subscribers().await;
             }
         });
     }
@@ -56,84 +79,200 @@ impl ReverseConnectionManager {
         }
 
         for connection in expired_connections {
-            let connection_id = connection.connection_id.clone();
-            let services = connection.services.clone();
-
             tracing::warn!(
-                connection_id = %connection_id,
-                services_count = services.len(),
+                connection_id = %connection.connection_id,
+                services_count = connection.services.len(),
                 "Removing expired reverse connection"
             );
+            Self::remove_connection(
+                connections_by_service,
+                connections_by_id,
+                service_registry.as_ref(),
+                &connection,
+            );
+        }
+    }
+
+    // 轮换存活超过最长时间的连接：注销连接后发送 Disconnected 状态让微服务重连。
+    // 每个连接的期限按连接ID增加最多十分之一的偏移，同时建立的连接不会同时重连
+    fn rotate_aged_connections(
+        connections_by_service: &Arc<DashMap<String, ServicePool>>,
+        connections_by_id: &Arc<DashMap<String, ReverseConnection>>,
+        service_registry: Option<ServiceRegistry>,
+        max_age: Duration,
+    ) {
+        let now = Instant::now();
+        let aged_connections: Vec<ReverseConnection> = connections_by_id
+            .iter()
+            .filter(|entry| {
+                let connection = entry.value();
+                now.duration_since(connection.created_at)
+                    > rotation_age(max_age, &connection.connection_id)
+            })
+            .map(|entry| entry.value().clone())
+            .collect();
+
+        for connection in aged_connections {
+            // 先注销，轮换后不再有新请求分配到该连接
+            if !Self::remove_connection(
+                connections_by_service,
+                connections_by_id,
+                service_registry.as_ref(),
+                &connection,
+            ) {
+                continue;
+            }
+            tracing::info!(
+                connection_id = %connection.connection_id,
+                connection_age_secs = now.duration_since(connection.created_at).as_secs(),
+                "Rotating reverse connection that reached max age"
+            );
+
+            let status = ConnectionMessage {
+                message_type: Some(MessageType::Status(ConnectionStatus {
+                    connection_id: connection.connection_id.clone(),
+                    status: StatusType::Disconnected as i32,
+                    message: "Connection reached max age, please reconnect".to_string(),
+                })),
+            };
+            if let Err(e) = connection.enqueue(status) {
+                tracing::debug!(
+                    connection_id = %connection.connection_id,
+                    error = %e,
+                    "Failed to notify aged reverse connection"
+                );
+            }
+        }
+    }
+
+    // 从连接表、服务池和注册表中移除连接；连接ID已被新连接重新注册时保留新连接
+    fn remove_connection(
+        connections_by_service: &Arc<DashMap<String, ServicePool>>,
+        connections_by_id: &Arc<DashMap<String, ReverseConnection>>,
+        service_registry: Option<&ServiceRegistry>,
+        connection: &ReverseConnection,
+    ) -> bool {
+        let connection_id = &connection.connection_id;
+        let is_same = |_: &String, current: &ReverseConnection| {
+            current
+                .request_sender
+                .same_channel(&connection.request_sender)
+        };
+        if connections_by_id
+            .remove_if(connection_id, is_same)
+            .is_none()
+        {
+            return false;
+        }
+
+        for service in &connection.services {
+            if let Some(pool_entry) = connections_by_service.get(service) {
+                let pool = pool_entry.clone();
+                drop(pool_entry);
 
-            connections_by_id.remove(&connection_id);
-            for service in &services {
-                if let Some(pool_entry) = connections_by_service.get(service) {
-                    let pool = pool_entry.clone();
-                    drop(pool_entry);
-
-                    if pool.remove_connection(&connection_id).is_some() {
-                        tracing::debug!(
-                            service_name = %service,
-                            connection_id = %connection_id,
-                            "Removed expired reverse connection instance from service pool"
-                        );
-                    }
-
-                    if pool.is_empty() {
-                        connections_by_service.remove_if(service, |_, p| p.is_empty());
-                        tracing::debug!(
-                            service_name = %service,
-                            "Service pool empty after removing expired connections, removed mapping"
-                        );
-                    }
+                if pool.remove_connection(connection_id).is_some() {
+                    tracing::debug!(
+                        service_name = %service,
+                        connection_id = %connection_id,
+                        "Removed reverse connection instance from service pool"
+                    );
                 }
 
-                if let Some(ref registry) = service_registry {
-                    if let Some(instances_guard) = registry.get(service) {
-                        let instances = instances_guard.clone();
-                        drop(instances_guard);
-
-                        if instances.remove(&connection_id).is_some() {
-                            tracing::debug!(
-                                service_name = %service,
-                                connection_id = %connection_id,
-                                "Removed expired service instance from registry"
-                            );
-                        }
-
-                        if instances.is_empty() {
-                            registry.remove_if(service, |_, v: &ServiceInstances| v.is_empty());
-                        }
-                    }
+                if pool.is_empty() {
+                    connections_by_service.remove_if(service, |_, p| p.is_empty());
+                    tracing::debug!(
+                        service_name = %service,
+                        "Service pool empty after removing connection, removed mapping"
+                    );
+                }
+            }
+
+            if let Some(registry) = service_registry
+                && let Some(instances_guard) = registry.get(service)
+            {
+                let instances = instances_guard.clone();
+                drop(instances_guard);
+
+                if instances.remove(connection_id).is_some() {
+                    tracing::debug!(
+                        service_name = %service,
+                        connection_id = %connection_id,
+                        "Removed service instance from registry"
+                    );
+                }
+
+                if instances.is_empty() {
+                    registry.remove_if(service, |_, v: &ServiceInstances| v.is_empty());
                 }
             }
         }
+
+        true
     }
 
     // 清理过期请求
-    async fn cleanup_expired_requests(
-        pending_requests: &Arc<RwLock<DashMap<String, PendingRequest>>>,
+    fn cleanup_expired_requests(
+        pending_requests: &PendingRequests,
+        event_bus: &EventBus,
         timeout: Duration,
     ) {
-        let pending_requests_guard = pending_requests.read().await;
+        // 收集过期请求后逐个移除
+        for request_id in pending_requests.expired(Instant::now(), timeout) {
+            if pending_requests.remove(&request_id).is_some() {
+                tracing::warn!(request_id = %request_id, "Removing expired pending request");
+                Self::publish_request_event(
+                    event_bus,
+                    REQUEST_TIMEOUT_EVENT,
+                    &request_id,
+                    "Pending request expired",
+                );
+            }
+        }
+    }
+
+    // 清理长时间没有收到新数据块的流式响应（例如微服务在流中途崩溃），并通知等待方
+    fn cleanup_stale_streams(
+        streaming_handlers: &DashMap<String, StreamingResponseHandler>,
+        event_bus: &EventBus,
+        idle_timeout: Duration,
+    ) {
         let now = Instant::now();
-        let mut expired_requests = Vec::new();
 
-        // 收集过期请求
-        for entry in pending_requests_guard.iter() {
-            let request_id = entry.key();
-            let request = entry.value();
+        let stale_streams: Vec<String> = streaming_handlers
+            .iter()
+            .filter(|entry| now.duration_since(entry.value().last_chunk_at) > idle_timeout)
+            .map(|entry| entry.key().clone())
+            .collect();
 
-            if now.duration_since(request.created_at) > timeout {
-                expired_requests.push(request_id.clone());
-            }
-        }
+        for request_id in stale_streams {
+            let Some((_id, handler)) = streaming_handlers.remove(&request_id) else {
+                continue;
+            };
 
-        // 移除过期请求
-        for request_id in expired_requests {
-            if let Some((_id, _request)) = pending_requests_guard.remove(&request_id) {
-                tracing::warn!(request_id = %request_id, "Removing expired pending request");
+            tracing::warn!(
+                request_id = %request_id,
+                received_chunks = handler.received_chunks(),
+                stream_age_ms = now.duration_since(handler.created_at).as_millis(),
+                idle_timeout_ms = idle_timeout.as_millis(),
+                "Removing stale streaming response"
+            );
+
+            let message = format!(
+                "Streaming response timed out after {} chunks",
+                handler.received_chunks()
+            );
+            Self::publish_request_event(event_bus, REQUEST_TIMEOUT_EVENT, &request_id, &message);
+            if !handler.fail(message) {
+                tracing::debug!(request_id = %request_id, "Client already gone for stale stream");
             }
         }
     }
-}
\ No newline at end of file
+}
+
+// 按连接ID在最长存活时间上增加 0 到十分之一的固定偏移
+fn rotation_age(max_age: Duration, connection_id: &str) -> Duration {
+    let mut hasher = DefaultHasher::new();
+    connection_id.hash(&mut hasher);
+    let spread_ms = (max_age / 10).as_millis() as u64;
+    max_age + Duration::from_millis(hasher.finish() % (spread_ms + 1))
+}
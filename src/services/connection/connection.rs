@@ -1,6 +1,10 @@
 use std::time::{Duration, Instant};
-use tokio::sync::mpsc;
+use tokio::sync::mpsc::{
+    self,
+    error::{SendTimeoutError, TrySendError},
+};
 
+use super::types::ReverseRequestError;
 use crate::registry::ConnectionMessage;
 
 // 反向连接元数据
@@ -11,8 +15,10 @@ pub struct ReverseConnection {
     pub created_at: Instant,
     pub last_heartbeat: Instant,
     pub is_active: bool,
-    // 用于向微服务发送请求的发送端
-    pub request_sender: mpsc::UnboundedSender<ConnectionMessage>,
+    // 加权轮询使用的权重，默认为 1
+    pub weight: u32,
+    // 用于向微服务发送请求的发送端，队列有界，微服务处理不过来时由发送方感知背压
+    pub request_sender: mpsc::Sender<ConnectionMessage>,
 }
 
 impl ReverseConnection {
@@ -23,4 +29,35 @@ impl ReverseConnection {
     pub fn is_expired(&self, timeout: Duration) -> bool {
         Instant::now().duration_since(self.last_heartbeat) > timeout
     }
+
+    // 非阻塞地将消息放入发送队列，队列已满时立即失败而不是等待
+    pub fn enqueue(&self, message: ConnectionMessage) -> Result<(), ReverseRequestError> {
+        self.request_sender.try_send(message).map_err(|e| match e {
+            TrySendError::Full(_) => ReverseRequestError::QueueFull {
+                capacity: self.request_sender.max_capacity(),
+            },
+            TrySendError::Closed(_) => {
+                ReverseRequestError::Failed("Failed to send request to microservice".to_string())
+            }
+        })
+    }
+
+    // 等待队列空位，超过 timeout 仍未放入时按队列已满处理
+    pub async fn enqueue_timeout(
+        &self,
+        message: ConnectionMessage,
+        timeout: Duration,
+    ) -> Result<(), ReverseRequestError> {
+        self.request_sender
+            .send_timeout(message, timeout)
+            .await
+            .map_err(|e| match e {
+                SendTimeoutError::Timeout(_) => ReverseRequestError::QueueFull {
+                    capacity: self.request_sender.max_capacity(),
+                },
+                SendTimeoutError::Closed(_) => ReverseRequestError::Failed(
+                    "Failed to send request to microservice".to_string(),
+                ),
+            })
+    }
 }
@@ -0,0 +1,123 @@
+use std::future::Future;
+use std::time::Duration;
+
+use tokio_util::task::task_tracker::TrackedFuture;
+
+use super::manager::ReverseConnectionManager;
+use crate::registry::{
+    ConnectionMessage, ConnectionStatus, connection_message::MessageType,
+    connection_status::StatusType,
+};
+
+impl ReverseConnectionManager {
+    // 是否已进入停机排空阶段
+    pub fn is_draining(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
+
+    // 跟踪一个进行中的转发请求，排空时会等待其完成
+    pub fn track_request<F: Future>(&self, future: F) -> TrackedFuture<F> {
+        self.task_tracker.track_future(future)
+    }
+
+    // 开始排空：停止后台清理任务，并通知所有反向连接网关即将关闭
+    pub fn begin_drain(&self) {
+        if self.shutdown.is_cancelled() {
+            return;
+        }
+        self.shutdown.cancel();
+
+        let mut notified = 0;
+        for entry in self.connections_by_id.iter() {
+            let connection = entry.value();
+            let status = ConnectionMessage {
+                message_type: Some(MessageType::Status(ConnectionStatus {
+                    connection_id: connection.connection_id.clone(),
+                    status: StatusType::Disconnected as i32,
+                    message: "Gateway is shutting down, please reconnect elsewhere".to_string(),
+                })),
+            };
+            if connection.enqueue(status).is_ok() {
+                notified += 1;
+            }
+        }
+
+        tracing::info!(
+            notified_connections = notified,
+            "Reverse connection manager draining"
+        );
+    }
+
+    // 等待进行中的请求完成，超过宽限期返回 false
+    pub async fn wait_for_drain(&self, grace_period: Duration) -> bool {
+        self.task_tracker.close();
+        let drained = tokio::time::timeout(grace_period, self.task_tracker.wait())
+            .await
+            .is_ok();
+
+        if !drained {
+            tracing::warn!(
+                remaining_tasks = self.task_tracker.len(),
+                grace_secs = grace_period.as_secs(),
+                "Grace period elapsed with requests still in flight"
+            );
+        }
+        drained
+    }
+
+    // 停止后台清理和保活任务，并等待它们以及仍被跟踪的请求结束，可以重复调用
+    pub async fn shutdown(&self) {
+        self.shutdown.cancel();
+        self.task_tracker.close();
+        self.task_tracker.wait().await;
+    }
+
+    // 仍在运行的后台任务和被跟踪的请求数量
+    pub fn background_tasks(&self) -> usize {
+        self.task_tracker.len()
+    }
+
+    // 宽限期结束后强制结束仍在等待响应的请求（包括未结束的流式响应），
+    // 调用方收到 UNAVAILABLE，不再等待不响应的微服务；返回被结束的请求数
+    pub fn fail_pending_requests(&self, reason: &str) -> usize {
+        let mut failed = 0;
+        for request in self.pending_requests.drain() {
+            if request
+                .response_sender
+                .send(Err(reason.to_string()))
+                .is_ok()
+            {
+                failed += 1;
+            }
+        }
+
+        let request_ids: Vec<String> = self
+            .streaming_handlers
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect();
+        for request_id in request_ids {
+            if let Some((_id, handler)) = self.streaming_handlers.remove(&request_id)
+                && handler.fail(reason.to_string())
+            {
+                failed += 1;
+            }
+        }
+
+        if failed > 0 {
+            tracing::warn!(
+                failed_requests = failed,
+                "Force-failed pending reverse requests after shutdown grace period"
+            );
+        }
+        failed
+    }
+
+    // 释放所有反向连接，使出站流结束
+    pub fn close_all_connections(&self) {
+        let count = self.connections_by_id.len();
+        self.connections_by_id.clear();
+        self.connections_by_service.clear();
+        tracing::info!(closed_connections = count, "Closed all reverse connections");
+    }
+}
@@ -1,16 +1,142 @@
 use std::collections::HashMap;
-use std::time::Instant;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
+use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
+use tokio::sync::{mpsc, oneshot};
 use uuid::Uuid;
 
-use crate::registry::{
-    ConnectionMessage, ForwardRequest, ForwardResponse, StreamingInfo,
-    connection_message::MessageType,
-};
 use super::{
+    connection::ReverseConnection,
     manager::ReverseConnectionManager,
-    types::{PendingRequest, StreamingResponseHandler},
+    response_builder::ForwardResponseBuilder,
+    saturation_queue::ConnectionSlot,
+    types::{
+        ForwardResponseError, PendingRequest, PendingRequests, REQUEST_DROPPED_EVENT,
+        RequestCounters, ReverseRequestError, StreamingResponseHandler,
+    },
+};
+use crate::registry::{
+    ConnectionMessage, EventMessage, ForwardRequest, ForwardResponse, RequestCancel,
+    ResponseStreamInfo, StreamingInfo, connection_message::MessageType,
+};
+use crate::services::compression::{
+    self, FrameRecoder, FrameTransform, GRPC_ACCEPT_ENCODING, GRPC_ENCODING,
 };
+use crate::services::event::EventBus;
+use crate::services::metrics;
+use crate::services::router::deadline;
+
+// 转发请求的取消守卫：调用方在收到响应前断开（外层 future 被丢弃）时，
+// 通知微服务取消请求，并立即清理等待表，而不是等到超时清理
+#[derive(Debug)]
+struct CancelOnDrop {
+    request_id: String,
+    connection: ReverseConnection,
+    pending_requests: Arc<PendingRequests>,
+    streaming_handlers: Arc<DashMap<String, StreamingResponseHandler>>,
+    completed: bool,
+    // 请求结束前占用连接的名额，计入连接的进行中请求数
+    _slot: ConnectionSlot,
+}
+
+impl CancelOnDrop {
+    // 请求已正常结束（收到响应、超时或出错），不再需要取消
+    fn complete(mut self) {
+        self.completed = true;
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+
+        let message = ConnectionMessage {
+            message_type: Some(MessageType::Cancel(RequestCancel {
+                request_id: self.request_id.clone(),
+                reason: "Client disconnected".to_string(),
+            })),
+        };
+        if let Err(e) = self.connection.enqueue(message) {
+            tracing::debug!(
+                request_id = %self.request_id,
+                connection_id = %self.connection.connection_id,
+                error = %e,
+                "Cancellation could not be sent to microservice"
+            );
+        }
+
+        self.pending_requests.remove(&self.request_id);
+        self.streaming_handlers.remove(&self.request_id);
+
+        tracing::info!(
+            request_id = %self.request_id,
+            connection_id = %self.connection.connection_id,
+            "Cancelled reverse request after client disconnect"
+        );
+    }
+}
+
+// 发往微服务的一元请求，incremental 为 false 时流式响应组装成完整响应后返回
+struct UnaryRequest<'a> {
+    request_id: &'a str,
+    service_name: &'a str,
+    method_path: &'a str,
+    headers: HashMap<String, String>,
+    payload: Vec<u8>,
+    incremental: bool,
+    client_id: Option<&'a str>,
+}
+
+// 等待中请求的响应接收端，增量转发时另有后续数据块的接收端
+struct PendingResponse {
+    response_receiver: oneshot::Receiver<Result<ForwardResponse, String>>,
+    chunk_receiver: Option<mpsc::UnboundedReceiver<Result<ForwardResponse, String>>>,
+}
+
+// 反向转发的响应：一元响应，或者流式响应的第一个数据块及其后续数据块
+#[derive(Debug)]
+pub struct ReverseResponse {
+    pub head: ForwardResponse,
+    pub chunks: Option<ResponseChunks>,
+    // 处理该请求的反向连接
+    pub connection_id: String,
+}
+
+// 流式响应中第一个数据块之后的数据块，按序号依次产出，最后一个数据块之后结束；
+// 在此之前被丢弃（调用方断开）时通知微服务取消请求
+#[derive(Debug)]
+pub struct ResponseChunks {
+    receiver: mpsc::UnboundedReceiver<Result<ForwardResponse, String>>,
+    cancel_guard: Option<CancelOnDrop>,
+}
+
+impl futures::Stream for ResponseChunks {
+    type Item = Result<ForwardResponse, String>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = std::task::ready!(self.receiver.poll_recv(cx));
+        if let Some(Ok(chunk)) = &item
+            && is_final_chunk(chunk)
+            && let Some(cancel_guard) = self.cancel_guard.take()
+        {
+            cancel_guard.complete();
+        }
+        Poll::Ready(item)
+    }
+}
+
+fn is_final_chunk(response: &ForwardResponse) -> bool {
+    response
+        .response_stream_info
+        .as_ref()
+        .is_some_and(|info| info.is_final_chunk)
+}
 
 impl ReverseConnectionManager {
     // 发送请求到微服务并等待响应
@@ -28,39 +154,184 @@ impl ReverseConnectionManager {
     }
 
     // 流式发送请求到微服务并等待响应
+    // 请求体按帧转发为带序列号的 ForwardRequest 分块，网关不再缓存完整请求体；
+    // 流式响应在第一个数据块到达时返回，后续数据块从 ReverseResponse::chunks 读取
     pub async fn send_request_stream<B>(
         &self,
         service_name: &str,
         method_path: &str,
         headers: HashMap<String, String>,
         body: B,
-    ) -> Result<ForwardResponse, String>
+        client_id: Option<&str>,
+    ) -> Result<ReverseResponse, ReverseRequestError>
     where
         B: http_body::Body<Data = bytes::Bytes> + Send + 'static,
         B::Error: Into<Box<dyn std::error::Error + Send + Sync>> + std::fmt::Debug,
     {
-        use http_body_util::BodyExt;
+        let mut headers = headers;
+        let max_body_size = self.max_body_size_for(service_name, method_path);
+        // 请求体边读边累计大小，超过上限时立即停止读取，不会先把整个请求体读入内存
+        let mut body = LimitedBodyReader::new(body, max_body_size)?;
 
-        // 对于现有的API兼容性，仍然需要收集body
-        // 但这里可以添加大小限制来避免无限内存使用
-        let collected = body
-            .collect()
-            .await
-            .map_err(|e| format!("Failed to collect request body: {e:?}"))?;
-        let payload = collected.to_bytes().to_vec();
+        // 预读两帧：只有一帧（或为空）的请求体仍按一元请求发送，保持与现有微服务的兼容
+        let request_id = Uuid::new_v4().to_string();
+        let Some(first_chunk) = body.next_chunk().await? else {
+            return self
+                .send_unary(UnaryRequest {
+                    request_id: &request_id,
+                    service_name,
+                    method_path,
+                    headers,
+                    payload: Vec::new(),
+                    incremental: true,
+                    client_id,
+                })
+                .await;
+        };
+        let Some(second_chunk) = body.next_chunk().await? else {
+            return self
+                .send_unary(UnaryRequest {
+                    request_id: &request_id,
+                    service_name,
+                    method_path,
+                    headers,
+                    payload: first_chunk.to_vec(),
+                    incremental: true,
+                    client_id,
+                })
+                .await;
+        };
+
+        let (connection, slot) = self
+            .acquire_connection(&request_id, service_name, method_path, &headers, client_id)
+            .await?;
+        let queued = slot.queued;
+        let pending = self
+            .register_pending_request(
+                &request_id,
+                true,
+                self.max_response_size_for(service_name, method_path),
+            )
+            .await?;
+        let cancel_guard = self.cancel_on_drop(&request_id, &connection, slot);
+        let mut recoder = self.request_recoder(&mut headers);
+
+        let request_timeout =
+            self.request_deadline(service_name, method_path, &mut headers, queued);
+        let mut sequence_number = 0i64;
+        let mut current_chunk = first_chunk;
+        let mut next_chunk = Some(second_chunk);
+
+        loop {
+            // 总是持有下一帧，以便在最后一帧上标记 is_stream_end
+            let is_stream_end = next_chunk.is_none();
+
+            // 分块边界与消息帧无关，不完整的帧留到下一个分块
+            let payload = match recoder.as_mut() {
+                Some(recoder) => {
+                    let mut payload = recoder.push(&current_chunk).map(|data| data.to_vec());
+                    if is_stream_end && let Ok(payload) = payload.as_mut() {
+                        payload.extend_from_slice(&recoder.finish());
+                    }
+                    match payload {
+                        Ok(payload) => payload,
+                        Err(e) => {
+                            self.remove_pending_request(&request_id).await;
+                            // 微服务尚未收到任何分块，无需发送取消
+                            if sequence_number == 0 {
+                                cancel_guard.complete();
+                            }
+                            return Err(ReverseRequestError::Body(format!(
+                                "Failed to compress request payload: {e}"
+                            )));
+                        }
+                    }
+                }
+                None => current_chunk.to_vec(),
+            };
+
+            let forward_request = ForwardRequest {
+                request_id: request_id.clone(),
+                method_path: method_path.to_string(),
+                headers: headers.clone(),
+                payload,
+                timeout_seconds: Self::timeout_seconds(request_timeout),
+                streaming_info: Some(StreamingInfo {
+                    stream_type: crate::registry::streaming_info::StreamType::ClientStreaming
+                        as i32,
+                    is_stream_end,
+                    sequence_number,
+                    chunk_size: current_chunk.len() as i32,
+                }),
+            };
+            let message = ConnectionMessage {
+                message_type: Some(MessageType::Request(forward_request)),
+            };
+
+            // 第一个分块在队列满时立即失败；请求已经发出后，后续分块等待队列空位，
+            // 把背压传递给请求体的读取
+            let sent = if sequence_number == 0 {
+                connection.enqueue(message)
+            } else {
+                connection.enqueue_timeout(message, request_timeout).await
+            };
+
+            if let Err(e) = sent {
+                tracing::error!(
+                    service_name = %service_name,
+                    method_path = %method_path,
+                    request_id = %request_id,
+                    connection_id = %connection.connection_id,
+                    sequence_number,
+                    error = %e,
+                    "Failed to send request chunk to microservice"
+                );
+                RequestCounters::incr(&self.request_counters.failed);
+                self.remove_pending_request(&request_id).await;
+                // 微服务尚未收到任何分块，无需发送取消
+                if sequence_number == 0 {
+                    cancel_guard.complete();
+                }
+                return Err(e);
+            }
+            if sequence_number == 0 {
+                RequestCounters::incr(&self.request_counters.forwarded);
+            }
+
+            let Some(chunk) = next_chunk.take() else {
+                break;
+            };
+            current_chunk = chunk;
+            sequence_number += 1;
 
-        // 如果payload太大，可以在这里添加大小检查
-        const MAX_BODY_SIZE: usize = 100 * 1024 * 1024; // 100MB
-        if payload.len() > MAX_BODY_SIZE {
-            return Err(format!(
-                "Request body too large: {} bytes (max: {} bytes)",
-                payload.len(),
-                MAX_BODY_SIZE
-            ));
+            next_chunk = match body.next_chunk().await {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    self.remove_pending_request(&request_id).await;
+                    return Err(e);
+                }
+            };
         }
 
-        self.send_request(service_name, method_path, headers, payload)
-            .await
+        tracing::debug!(
+            service_name = %service_name,
+            method_path = %method_path,
+            request_id = %request_id,
+            connection_id = %connection.connection_id,
+            chunk_count = sequence_number + 1,
+            payload_size = body.total_size(),
+            "Streamed request body via reverse connection"
+        );
+
+        self.finish_request(
+            request_id,
+            service_name,
+            method_path,
+            request_timeout,
+            pending,
+            cancel_guard,
+        )
+        .await
     }
 
     // 使用指定的请求ID发送请求到微服务并等待响应
@@ -72,41 +343,75 @@ impl ReverseConnectionManager {
         headers: HashMap<String, String>,
         payload: Vec<u8>,
     ) -> Result<ForwardResponse, String> {
-        // 获取连接
-        let connection = self
-            .get_connection_for_service(service_name)
-            .ok_or_else(|| {
-                tracing::error!(
-                    service_name = %service_name,
-                    method_path = %method_path,
-                    request_id = %request_id,
-                    "No reverse connection found for service"
-                );
-                format!("No reverse connection found for service: {service_name}")
-            })?;
+        let started_at = Instant::now();
+        let payload_size = payload.len() as u64;
+        let result = self
+            .send_unary(UnaryRequest {
+                request_id,
+                service_name,
+                method_path,
+                headers,
+                payload,
+                incremental: false,
+                client_id: None,
+            })
+            .await
+            .map(|response| response.head)
+            .map_err(|e| e.to_string());
+
+        metrics::log_slow_request(
+            self.config.slow_request_threshold,
+            service_name,
+            method_path,
+            started_at.elapsed(),
+            payload_size,
+        );
+        result
+    }
+
+    // 以一元请求发送完整消息体，保留失败原因供调用方映射状态码
+    async fn send_unary(
+        &self,
+        request: UnaryRequest<'_>,
+    ) -> Result<ReverseResponse, ReverseRequestError> {
+        let UnaryRequest {
+            request_id,
+            service_name,
+            method_path,
+            mut headers,
+            mut payload,
+            incremental,
+            client_id,
+        } = request;
+        // 获取连接，请求结束前占用该连接的一个名额
+        let (connection, slot) = self
+            .acquire_connection(request_id, service_name, method_path, &headers, client_id)
+            .await?;
+        let request_timeout =
+            self.request_deadline(service_name, method_path, &mut headers, slot.queued);
+
+        if self.request_recoder(&mut headers).is_some() {
+            payload = compression::recode(
+                &payload,
+                FrameTransform::Compress {
+                    min_size: self.config.gzip_min_size,
+                },
+            )
+            .map_err(|e| format!("Failed to compress request payload: {e}"))?;
+        }
 
         // 使用传入的请求ID
         let request_id = request_id.to_string();
         let payload_size = payload.len();
 
-        // 创建响应通道
-        let (response_sender, response_receiver) = tokio::sync::oneshot::channel();
-
         // 存储等待中的请求
-        {
-            let pending_requests = self.pending_requests.read().await;
-            if pending_requests.len() >= self.config.max_pending_requests {
-                return Err("Too many pending requests".to_string());
-            }
-            pending_requests.insert(
-                request_id.clone(),
-                PendingRequest {
-                    request_id: request_id.clone(),
-                    created_at: Instant::now(),
-                    response_sender,
-                },
-            );
-        }
+        let pending = self
+            .register_pending_request(
+                &request_id,
+                incremental,
+                self.max_response_size_for(service_name, method_path),
+            )
+            .await?;
 
         // 构建转发请求
         let forward_request = ForwardRequest {
@@ -114,7 +419,7 @@ impl ReverseConnectionManager {
             method_path: method_path.to_string(),
             headers,
             payload,
-            timeout_seconds: self.config.request_timeout.as_secs() as i32,
+            timeout_seconds: Self::timeout_seconds(request_timeout),
             streaming_info: Some(StreamingInfo {
                 stream_type: crate::registry::streaming_info::StreamType::Unary as i32,
                 is_stream_end: true,
@@ -136,26 +441,439 @@ impl ReverseConnectionManager {
             "Sending request via reverse connection"
         );
 
-        // 发送请求到微服务
-        if connection.request_sender.send(message).is_err() {
+        // 发送请求到微服务，队列已满时立即失败，不在网关堆积请求
+        if let Err(e) = connection.enqueue(message) {
+            RequestCounters::incr(&self.request_counters.failed);
             // 移除等待中的请求
-            let pending_requests = self.pending_requests.read().await;
-            pending_requests.remove(&request_id);
+            self.remove_pending_request(&request_id).await;
 
             tracing::error!(
                 service_name = %service_name,
                 method_path = %method_path,
                 request_id = %request_id,
                 connection_id = %connection.connection_id,
-                "Failed to send request to microservice - connection channel closed"
+                error = %e,
+                "Failed to send request to microservice"
             );
 
-            return Err("Failed to send request to microservice".to_string());
+            return Err(e);
+        }
+        RequestCounters::incr(&self.request_counters.forwarded);
+
+        let cancel_guard = self.cancel_on_drop(&request_id, &connection, slot);
+        self.finish_request(
+            request_id,
+            service_name,
+            method_path,
+            request_timeout,
+            pending,
+            cancel_guard,
+        )
+        .await
+    }
+
+    // 等待响应；增量转发的流式响应尚未结束时，取消守卫随后续数据块一起交给调用方
+    async fn finish_request(
+        &self,
+        request_id: String,
+        service_name: &str,
+        method_path: &str,
+        request_timeout: Duration,
+        pending: PendingResponse,
+        cancel_guard: CancelOnDrop,
+    ) -> Result<ReverseResponse, ReverseRequestError> {
+        let head = match self
+            .wait_for_response(
+                request_id,
+                service_name,
+                method_path,
+                request_timeout,
+                pending.response_receiver,
+            )
+            .await
+        {
+            Ok(head) => head,
+            Err(e) => {
+                cancel_guard.complete();
+                return Err(e);
+            }
+        };
+
+        let connection_id = cancel_guard.connection.connection_id.clone();
+        let chunks = match pending.chunk_receiver {
+            Some(receiver) if Self::is_streaming_response(&head) && !is_final_chunk(&head) => {
+                Some(ResponseChunks {
+                    receiver,
+                    cancel_guard: Some(cancel_guard),
+                })
+            }
+            _ => {
+                cancel_guard.complete();
+                None
+            }
+        };
+        Ok(ReverseResponse {
+            head,
+            chunks,
+            connection_id,
+        })
+    }
+
+    // 开启 gzip_payload 时声明微服务可以返回 gzip 压缩的响应；
+    // 请求未压缩时改为 gzip 编码并返回用于压缩消息帧的改写器，客户端已压缩的请求原样转发
+    fn request_recoder(&self, headers: &mut HashMap<String, String>) -> Option<FrameRecoder> {
+        if !self.config.gzip_payload {
+            return None;
+        }
+
+        let accept_encoding =
+            compression::with_gzip_accepted(headers.get(GRPC_ACCEPT_ENCODING).map(String::as_str));
+        headers.insert(GRPC_ACCEPT_ENCODING.to_string(), accept_encoding);
+
+        if !compression::is_identity(headers.get(GRPC_ENCODING).map(String::as_str)) {
+            return None;
         }
+        headers.insert(GRPC_ENCODING.to_string(), compression::GZIP.to_string());
+        Some(FrameRecoder::new(FrameTransform::Compress {
+            min_size: self.config.gzip_min_size,
+        }))
+    }
 
-        // 等待响应（带超时）
-        match tokio::time::timeout(self.config.request_timeout, response_receiver).await {
-            Ok(Ok(response)) => {
+    fn cancel_on_drop(
+        &self,
+        request_id: &str,
+        connection: &ReverseConnection,
+        slot: ConnectionSlot,
+    ) -> CancelOnDrop {
+        CancelOnDrop {
+            request_id: request_id.to_string(),
+            connection: connection.clone(),
+            pending_requests: self.pending_requests.clone(),
+            streaming_handlers: self.streaming_handlers.clone(),
+            completed: false,
+            _slot: slot,
+        }
+    }
+
+    // 获取请求超时：优先匹配方法路径中的完整服务名，其次匹配解析出的服务名
+    fn request_timeout_for(&self, service_name: &str, method_path: &str) -> Duration {
+        let full_service_name = method_path
+            .trim_start_matches('/')
+            .split('/')
+            .next()
+            .unwrap_or_default();
+
+        if self.config.service_timeouts.contains_key(full_service_name) {
+            self.config.request_timeout_for(full_service_name)
+        } else {
+            self.config.request_timeout_for(service_name)
+        }
+    }
+
+    // 调用方在 grpc-timeout 中声明的截止时间比配置的超时更短时以调用方为准
+    fn effective_request_timeout(
+        &self,
+        service_name: &str,
+        method_path: &str,
+        headers: &HashMap<String, String>,
+    ) -> Duration {
+        let client_timeout = headers
+            .get(deadline::GRPC_TIMEOUT)
+            .and_then(|value| deadline::parse_grpc_timeout(value));
+        deadline::effective_timeout(
+            client_timeout,
+            self.request_timeout_for(service_name, method_path),
+        )
+    }
+
+    // 本次请求的超时：生效的超时扣除排队等待连接名额的时间（queued），
+    // 并写回 grpc-timeout 传给微服务
+    fn request_deadline(
+        &self,
+        service_name: &str,
+        method_path: &str,
+        headers: &mut HashMap<String, String>,
+        queued: Duration,
+    ) -> Duration {
+        let request_timeout = self
+            .effective_request_timeout(service_name, method_path, headers)
+            .saturating_sub(queued);
+        headers.insert(
+            deadline::GRPC_TIMEOUT.to_string(),
+            deadline::format_grpc_timeout(request_timeout),
+        );
+        request_timeout
+    }
+
+    // ForwardRequest.timeout_seconds 按秒向上取整，避免不足一秒的截止时间变成 0
+    fn timeout_seconds(timeout: Duration) -> i32 {
+        timeout.as_millis().div_ceil(1000).min(i32::MAX as u128) as i32
+    }
+
+    // 获取消息体大小上限，查找顺序与请求超时一致
+    pub(crate) fn max_body_size_for(&self, service_name: &str, method_path: &str) -> usize {
+        let full_service_name = method_path
+            .trim_start_matches('/')
+            .split('/')
+            .next()
+            .unwrap_or_default();
+
+        if self
+            .config
+            .service_max_body_sizes
+            .contains_key(full_service_name)
+        {
+            self.config.max_body_size_for(full_service_name)
+        } else {
+            self.config.max_body_size_for(service_name)
+        }
+    }
+
+    // 获取响应大小上限：配置了 max_response_size 时取其与消息体上限中较小者
+    pub(crate) fn max_response_size_for(&self, service_name: &str, method_path: &str) -> usize {
+        let max_body_size = self.max_body_size_for(service_name, method_path);
+        match self.config.max_response_size {
+            0 => max_body_size,
+            limit => limit.min(max_body_size),
+        }
+    }
+
+    // 为请求选择反向连接并占用一个名额。连接全部达到并发上限时，开启了排队则按先后顺序
+    // 等待名额，最长等待 saturation_queue_wait（不超过请求本身的超时），否则立即失败
+    async fn acquire_connection(
+        &self,
+        request_id: &str,
+        service_name: &str,
+        method_path: &str,
+        headers: &HashMap<String, String>,
+        client_id: Option<&str>,
+    ) -> Result<(ReverseConnection, ConnectionSlot), ReverseRequestError> {
+        let result = match self.config.saturation_queue_wait {
+            Some(queue_wait) => {
+                self.wait_for_connection(
+                    request_id,
+                    service_name,
+                    method_path,
+                    headers,
+                    client_id,
+                    queue_wait,
+                )
+                .await
+            }
+            None => self.try_acquire_connection(
+                request_id,
+                service_name,
+                method_path,
+                headers,
+                client_id,
+            ),
+        };
+
+        if let Err(ReverseRequestError::ConnectionsSaturated { limit }) = &result {
+            tracing::warn!(
+                service_name = %service_name,
+                method_path = %method_path,
+                request_id = %request_id,
+                limit,
+                "All reverse connections for service are at their in-flight request limit"
+            );
+        }
+        result
+    }
+
+    // 没有请求排队时先直接选择连接，连接全部达到上限再排队等待名额：只有队首的请求尝试选择连接，
+    // 其余请求在名额释放、新连接注册或队首离开时被唤醒后重新检查
+    async fn wait_for_connection(
+        &self,
+        request_id: &str,
+        service_name: &str,
+        method_path: &str,
+        headers: &HashMap<String, String>,
+        client_id: Option<&str>,
+        queue_wait: Duration,
+    ) -> Result<(ReverseConnection, ConnectionSlot), ReverseRequestError> {
+        let saturated = || ReverseRequestError::ConnectionsSaturated {
+            limit: self.config.max_in_flight_per_connection.unwrap_or_default(),
+        };
+        let queued_at = tokio::time::Instant::now();
+        let deadline = queued_at
+            + queue_wait.min(self.effective_request_timeout(service_name, method_path, headers));
+        // 已有请求排队时新请求排在队尾，不越过它们直接选择连接
+        if !self.saturation_queue.has_waiters(service_name) {
+            match self.try_acquire_connection(
+                request_id,
+                service_name,
+                method_path,
+                headers,
+                client_id,
+            ) {
+                Err(ReverseRequestError::ConnectionsSaturated { .. }) => {}
+                result => return result,
+            }
+        }
+
+        let Some(ticket) = self
+            .saturation_queue
+            .join(service_name, self.config.saturation_queue_capacity)
+        else {
+            return Err(saturated());
+        };
+        tracing::debug!(
+            service_name = %service_name,
+            request_id = %request_id,
+            "Queued request until a reverse connection slot frees up"
+        );
+
+        loop {
+            let changed = ticket.changed();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+
+            if tokio::time::Instant::now() >= deadline {
+                return Err(saturated());
+            }
+            if ticket.is_head() {
+                match self.try_acquire_connection(
+                    request_id,
+                    service_name,
+                    method_path,
+                    headers,
+                    client_id,
+                ) {
+                    Err(ReverseRequestError::ConnectionsSaturated { .. }) => {}
+                    result => {
+                        return result.map(|(connection, mut slot)| {
+                            slot.queued = queued_at.elapsed();
+                            (connection, slot)
+                        });
+                    }
+                }
+            }
+            if tokio::time::timeout_at(deadline, changed).await.is_err() {
+                return Err(saturated());
+            }
+        }
+    }
+
+    // 为服务选择反向连接，配置了亲和头且请求携带该头时按其值一致性哈希；
+    // 开启粘滞时，窗口内同一客户端优先沿用上次选中的连接。client_id 为空时（微服务之间的请求）
+    // 按 stickiness_header 请求头识别客户端。选中的连接同时计入一个进行中的请求，
+    // 配置了单连接并发上限时，所有可用连接都已达到上限返回 ConnectionsSaturated
+    fn try_acquire_connection(
+        &self,
+        request_id: &str,
+        service_name: &str,
+        method_path: &str,
+        headers: &HashMap<String, String>,
+        client_id: Option<&str>,
+    ) -> Result<(ReverseConnection, ConnectionSlot), ReverseRequestError> {
+        let header_value = |header: &Option<String>| {
+            header
+                .as_ref()
+                .and_then(|header| headers.get(header))
+                .map(String::as_str)
+                .filter(|value| !value.is_empty())
+        };
+        let affinity_key = header_value(&self.config.affinity_header);
+        let sticky = self
+            .config
+            .stickiness_window
+            .zip(client_id.or_else(|| header_value(&self.config.stickiness_header)))
+            .filter(|_| affinity_key.is_none());
+
+        let limit = self.config.max_in_flight_per_connection;
+        let saturated = || ReverseRequestError::ConnectionsSaturated {
+            limit: limit.unwrap_or_default(),
+        };
+
+        let Some(connection) = self.get_connection_for_client(
+            service_name,
+            affinity_key,
+            sticky.map(|(_, client_id)| client_id),
+        ) else {
+            // 选择时跳过了达到上限的连接，仍有可用连接说明它们都已达到上限
+            if limit.is_some() && self.has_reverse_connection(service_name) {
+                return Err(saturated());
+            }
+            tracing::error!(
+                service_name = %service_name,
+                method_path = %method_path,
+                request_id = %request_id,
+                "No reverse connection found for service"
+            );
+            return Err(format!("No reverse connection found for service: {service_name}").into());
+        };
+
+        // 选择与计数之间其他请求可能占满了该连接
+        let in_flight = match limit {
+            Some(limit) => self
+                .in_flight
+                .try_acquire(&connection.connection_id, limit)
+                .ok_or_else(saturated)?,
+            None => self.in_flight.acquire(&connection.connection_id),
+        };
+        if let Some((window, client_id)) = sticky {
+            self.sticky
+                .record(client_id, service_name, &connection.connection_id, window);
+        }
+        let slot = ConnectionSlot::new(
+            in_flight,
+            &self.saturation_queue,
+            connection.services.clone(),
+        );
+        Ok((connection, slot))
+    }
+
+    // 登记等待中的请求，返回响应接收端；incremental 时另外返回流式响应后续数据块的接收端
+    async fn register_pending_request(
+        &self,
+        request_id: &str,
+        incremental: bool,
+        max_response_size: usize,
+    ) -> Result<PendingResponse, String> {
+        let (response_sender, response_receiver) = oneshot::channel();
+        let (chunk_sender, chunk_receiver) = if incremental {
+            let (sender, receiver) = mpsc::unbounded_channel();
+            (Some(sender), Some(receiver))
+        } else {
+            (None, None)
+        };
+
+        let request = PendingRequest {
+            request_id: request_id.to_string(),
+            created_at: Instant::now(),
+            response_sender,
+            chunk_sender,
+            max_response_size,
+        };
+        if !self
+            .pending_requests
+            .try_insert(request, self.config.max_pending_requests)
+        {
+            return Err("Too many pending requests".to_string());
+        }
+
+        Ok(PendingResponse {
+            response_receiver,
+            chunk_receiver,
+        })
+    }
+
+    async fn remove_pending_request(&self, request_id: &str) {
+        self.pending_requests.remove(request_id);
+    }
+
+    // 等待响应（带超时）
+    async fn wait_for_response(
+        &self,
+        request_id: String,
+        service_name: &str,
+        method_path: &str,
+        request_timeout: Duration,
+        response_receiver: oneshot::Receiver<Result<ForwardResponse, String>>,
+    ) -> Result<ForwardResponse, ReverseRequestError> {
+        match tokio::time::timeout(request_timeout, response_receiver).await {
+            Ok(Ok(Ok(response))) => {
                 tracing::debug!(
                     service_name = %service_name,
                     method_path = %method_path,
@@ -166,10 +884,22 @@ impl ReverseConnectionManager {
                 );
                 Ok(response)
             }
+            Ok(Ok(Err(message))) => {
+                RequestCounters::incr(&self.request_counters.failed);
+                tracing::error!(
+                    service_name = %service_name,
+                    method_path = %method_path,
+                    request_id = %request_id,
+                    error = %message,
+                    "Reverse request abandoned by gateway"
+                );
+
+                Err(message.into())
+            }
             Ok(Err(_)) => {
+                RequestCounters::incr(&self.request_counters.failed);
                 // 移除等待中的请求
-                let pending_requests = self.pending_requests.read().await;
-                pending_requests.remove(&request_id);
+                self.remove_pending_request(&request_id).await;
 
                 tracing::error!(
                     service_name = %service_name,
@@ -178,22 +908,22 @@ impl ReverseConnectionManager {
                     "Response channel closed - microservice disconnected unexpectedly"
                 );
 
-                Err("Response channel closed".to_string())
+                Err("Response channel closed".to_string().into())
             }
             Err(_) => {
+                RequestCounters::incr(&self.request_counters.timed_out);
                 // 移除等待中的请求
-                let pending_requests = self.pending_requests.read().await;
-                pending_requests.remove(&request_id);
+                self.remove_pending_request(&request_id).await;
 
                 tracing::error!(
                     service_name = %service_name,
                     method_path = %method_path,
                     request_id = %request_id,
-                    timeout_ms = self.config.request_timeout.as_millis(),
+                    timeout_ms = request_timeout.as_millis(),
                     "Request timeout - microservice did not respond in time"
                 );
 
-                Err("Request timeout".to_string())
+                Err(ReverseRequestError::Timeout)
             }
         }
     }
@@ -205,118 +935,291 @@ impl ReverseConnectionManager {
             self.handle_streaming_response(response).await;
         } else {
             // 处理常规响应
-            let pending_requests = self.pending_requests.read().await;
-            if let Some((_id, pending)) = pending_requests.remove(&response.request_id) {
-                if pending.response_sender.send(response).is_err() {
+            if let Some(pending) = self.pending_requests.remove(&response.request_id) {
+                let response = if response.payload.len() > pending.max_response_size {
+                    tracing::warn!(
+                        request_id = %pending.request_id,
+                        size = response.payload.len(),
+                        limit = pending.max_response_size,
+                        "Response exceeds max response size"
+                    );
+                    Self::response_too_large(
+                        pending.request_id.clone(),
+                        response.payload.len(),
+                        pending.max_response_size,
+                    )
+                } else {
+                    response
+                };
+                if pending.response_sender.send(Ok(response)).is_err() {
                     tracing::warn!(request_id = %pending.request_id, "Failed to send response to waiting client");
                 }
             } else {
                 tracing::warn!(request_id = %response.request_id, "Received response for unknown request");
+                Self::publish_request_event(
+                    &self.event_bus,
+                    REQUEST_DROPPED_EVENT,
+                    &response.request_id,
+                    "Response for unknown request",
+                );
             }
         }
     }
 
-    // 处理流式响应
-    async fn handle_streaming_response(&self, response: ForwardResponse) {
-        let stream_info = match response.response_stream_info.as_ref() {
-            Some(info) => info,
-            None => {
-                tracing::error!(request_id = %response.request_id, "Missing stream info in streaming response");
-                return;
-            }
+    // 处理流式响应：数据块可能乱序到达，先缓存；增量转发时按序号发出已经连续的数据块，
+    // 否则在最后一个数据块到达且中间没有缺口时组装成完整响应
+    async fn handle_streaming_response(&self, mut response: ForwardResponse) {
+        let Some(stream_info) = response.response_stream_info else {
+            tracing::error!(request_id = %response.request_id, "Missing stream info in streaming response");
+            return;
         };
 
-        let streaming_handlers = self.streaming_handlers.write().await;
+        let streaming_handlers = &self.streaming_handlers;
 
-        // 检查是否已有处理器
-        if !streaming_handlers.contains_key(&response.request_id) {
-            // 这是一个新的流式响应，需要从 pending_requests 中获取 sender
-            let pending_requests = self.pending_requests.read().await;
-            if let Some((_id, pending)) = pending_requests.remove(&response.request_id) {
-                let handler = StreamingResponseHandler {
+        // 检查是否已有处理器；条目锁保证同一请求只会从 pending_requests 取出一次
+        let mut handler = match streaming_handlers.entry(response.request_id.clone()) {
+            Entry::Occupied(entry) => entry.into_ref(),
+            Entry::Vacant(entry) => {
+                // 这是一个新的流式响应，需要从 pending_requests 中获取 sender
+                let Some(pending) = self.pending_requests.remove(&response.request_id) else {
+                    tracing::warn!(request_id = %response.request_id, "No pending request found for streaming response");
+                    Self::publish_request_event(
+                        &self.event_bus,
+                        REQUEST_DROPPED_EVENT,
+                        &response.request_id,
+                        "Streaming response for unknown request",
+                    );
+                    return;
+                };
+                let now = Instant::now();
+                entry.insert(StreamingResponseHandler {
                     request_id: response.request_id.clone(),
                     chunks: std::collections::BTreeMap::new(),
-                    next_expected_chunk: 0,
-                    is_complete: false,
+                    final_chunk: None,
                     total_size: stream_info.total_size,
-                    response_sender: pending.response_sender,
-                };
-                streaming_handlers.insert(response.request_id.clone(), handler);
-            } else {
-                tracing::warn!(request_id = %response.request_id, "No pending request found for streaming response");
-                return;
+                    created_at: now,
+                    last_chunk_at: now,
+                    response_sender: Some(pending.response_sender),
+                    chunk_sender: pending.chunk_sender,
+                    next_index: 0,
+                    contiguous: 0,
+                    max_response_size: pending.max_response_size,
+                    received_size: 0,
+                })
+            }
+        };
+
+        if stream_info.chunk_index < 0 {
+            tracing::warn!(
+                request_id = %response.request_id,
+                chunk_index = stream_info.chunk_index,
+                "Ignoring streaming chunk with negative index"
+            );
+            return;
+        }
+        if stream_info.chunk_index < handler.next_index {
+            tracing::warn!(
+                request_id = %response.request_id,
+                chunk_index = stream_info.chunk_index,
+                "Ignoring streaming chunk that was already forwarded"
+            );
+            return;
+        }
+
+        // 添加数据块，payload 直接移入缓存；累计大小超过上限时放弃整个响应并释放已缓存的数据块
+        let payload = std::mem::take(&mut response.payload);
+        let replaced_size = handler
+            .chunks
+            .get(&stream_info.chunk_index)
+            .map_or(0, Vec::len);
+        handler.received_size = handler.received_size - replaced_size + payload.len();
+        if handler.received_size > handler.max_response_size {
+            let request_id = handler.request_id.clone();
+            drop(handler);
+            if let Some((_id, handler)) = streaming_handlers.remove(&request_id) {
+                Self::reject_oversized_stream(handler);
             }
+            return;
         }
+        if handler
+            .chunks
+            .insert(stream_info.chunk_index, payload)
+            .is_some()
+        {
+            tracing::warn!(
+                request_id = %response.request_id,
+                chunk_index = stream_info.chunk_index,
+                "Duplicate streaming chunk, keeping the latest"
+            );
+        }
+        handler.last_chunk_at = Instant::now();
+        let arrived = if stream_info.is_final_chunk {
+            handler.final_chunk = Some(response);
+            None
+        } else {
+            Some(response)
+        };
 
-        let mut handler = match streaming_handlers.get_mut(&response.request_id) {
-            Some(h) => h,
-            None => return,
+        if handler.chunk_sender.is_some() {
+            if Self::forward_ready_chunks(&mut handler, arrived) {
+                let request_id = handler.request_id.clone();
+                drop(handler);
+                streaming_handlers.remove(&request_id);
+            }
+            return;
+        }
+
+        handler.advance_contiguous();
+        if !handler.is_complete() {
+            return;
+        }
+
+        // 先释放表项引用，否则在同一分片上 remove 会死锁
+        let request_id = handler.request_id.clone();
+        drop(handler);
+        let Some((_id, mut handler)) = streaming_handlers.remove(&request_id) else {
+            return;
+        };
+        let (Some(final_index), Some(final_chunk)) =
+            (handler.final_chunk_index(), handler.final_chunk.take())
+        else {
+            return;
         };
 
-        // 添加数据块
-        handler
-            .chunks
-            .insert(stream_info.chunk_index, response.payload.clone());
+        // 最后一个数据块之后的序号不属于本次响应
+        let mut complete_payload = Vec::new();
+        for (_index, chunk) in handler.chunks.range(..=final_index) {
+            complete_payload.extend_from_slice(chunk);
+        }
+        if handler.chunks.range(final_index + 1..).next().is_some() {
+            tracing::warn!(request_id = %request_id, final_index, "Dropping streaming chunks after the final chunk");
+        }
 
-        // 检查是否可以组装完整响应
-        if stream_info.is_final_chunk {
-            handler.is_complete = true;
+        // 使用最后一个数据块的状态码和响应头构建完整响应
+        let complete_response = ForwardResponse {
+            payload: complete_payload,
+            response_stream_info: None, // 清除流式信息，因为这是最终的完整响应
+            ..final_chunk
+        };
+
+        // 发送完整响应
+        if let Some(response_sender) = handler.response_sender.take()
+            && response_sender.send(Ok(complete_response)).is_err()
+        {
+            tracing::warn!(request_id = %request_id, "Failed to send complete streaming response to waiting client");
         }
+    }
 
-        // 尝试组装完整的响应
-        if handler.is_complete {
-            let mut complete_payload = Vec::new();
-            for chunk_index in 0..=stream_info.chunk_index {
-                if let Some(chunk_data) = handler.chunks.remove(&chunk_index) {
-                    complete_payload.extend(chunk_data);
-                } else {
-                    tracing::error!(request_id = %response.request_id, chunk_index, "Missing chunk in streaming response");
-                    return;
-                }
+    // 流式响应超过大小上限：尚未返回响应头时以 RESOURCE_EXHAUSTED 作为完整响应，
+    // 否则作为最后一个数据块发出，其状态头成为 trailers
+    fn reject_oversized_stream(mut handler: StreamingResponseHandler) {
+        tracing::warn!(
+            request_id = %handler.request_id,
+            size = handler.received_size,
+            limit = handler.max_response_size,
+            buffered_chunks = handler.chunks.len(),
+            "Streaming response exceeds max response size, dropping buffered chunks"
+        );
+        let mut response = Self::response_too_large(
+            handler.request_id.clone(),
+            handler.received_size,
+            handler.max_response_size,
+        );
+        let delivered = match handler.response_sender.take() {
+            Some(response_sender) => response_sender.send(Ok(response)).is_ok(),
+            None => {
+                response.response_stream_info = Some(ResponseStreamInfo {
+                    is_streamed: true,
+                    chunk_index: handler.next_index,
+                    is_final_chunk: true,
+                    ..Default::default()
+                });
+                handler
+                    .chunk_sender
+                    .as_ref()
+                    .is_some_and(|chunk_sender| chunk_sender.send(Ok(response)).is_ok())
             }
+        };
+        if !delivered {
+            tracing::warn!(request_id = %handler.request_id, "Failed to notify client of oversized streaming response");
+        }
+    }
 
-            // 创建完整的响应
-            let complete_response = ForwardResponse {
-                request_id: response.request_id.clone(),
-                status_code: response.status_code,
-                headers: response.headers,
-                payload: complete_payload,
-                error_message: response.error_message,
-                streaming_info: response.streaming_info,
-                response_stream_info: None, // 清除流式信息，因为这是最终的完整响应
+    // 响应超过大小上限时返回给调用方的 RESOURCE_EXHAUSTED 响应
+    fn response_too_large(request_id: String, size: usize, limit: usize) -> ForwardResponse {
+        ForwardResponseBuilder::grpc_error(
+            request_id,
+            tonic::Code::ResourceExhausted,
+            format!("Response body too large: {size} bytes (max: {limit} bytes)"),
+        )
+    }
+
+    // 按序号发出已经连续的数据块：第一个数据块通过响应通道，其余通过数据块通道。
+    // 第一个和最后一个数据块保留状态码与响应头，返回 true 表示流已结束或等待方已断开
+    fn forward_ready_chunks(
+        handler: &mut StreamingResponseHandler,
+        mut arrived: Option<ForwardResponse>,
+    ) -> bool {
+        while let Some(payload) = handler.chunks.remove(&handler.next_index) {
+            let index = handler.next_index;
+            handler.next_index += 1;
+
+            let is_final = handler.final_chunk_index() == Some(index);
+            let chunk = if is_final {
+                handler.final_chunk.take()
+            } else if arrived
+                .as_ref()
+                .and_then(|response| response.response_stream_info.as_ref())
+                .is_some_and(|info| info.chunk_index == index)
+            {
+                arrived.take()
+            } else {
+                None
+            };
+            let chunk = ForwardResponse {
+                payload,
+                ..chunk.unwrap_or_else(|| ForwardResponse {
+                    request_id: handler.request_id.clone(),
+                    response_stream_info: Some(ResponseStreamInfo {
+                        is_streamed: true,
+                        chunk_index: index,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
             };
 
-            // 发送完整响应
-            if let Some((_id, handler)) = streaming_handlers.remove(&response.request_id) {
-                if handler.response_sender.send(complete_response).is_err() {
-                    tracing::warn!(request_id = %response.request_id, "Failed to send complete streaming response to waiting client");
-                }
+            let delivered = match handler.response_sender.take() {
+                Some(response_sender) => response_sender.send(Ok(chunk)).is_ok(),
+                None => handler
+                    .chunk_sender
+                    .as_ref()
+                    .is_some_and(|chunk_sender| chunk_sender.send(Ok(chunk)).is_ok()),
+            };
+            if !delivered {
+                tracing::warn!(request_id = %handler.request_id, chunk_index = index, "Client gone, dropping streaming response");
+                return true;
+            }
+            if is_final {
+                return true;
             }
         }
+
+        false
     }
 
-    // 创建流式响应块
+    // 创建流式响应块，chunk_index 为负数时失败
     pub fn create_response_chunk(
         request_id: String,
         chunk_data: Vec<u8>,
         chunk_index: i64,
         is_final: bool,
         total_size: Option<i64>,
-    ) -> ForwardResponse {
-        let chunk_size = chunk_data.len() as i32;
-        ForwardResponse {
-            request_id,
-            status_code: 200,
-            payload: chunk_data,
-            response_stream_info: Some(crate::registry::ResponseStreamInfo {
-                is_streamed: true,
-                chunk_index,
-                is_final_chunk: is_final,
-                chunk_size,
-                total_size,
-            }),
-            ..Default::default()
-        }
+    ) -> Result<ForwardResponse, ForwardResponseError> {
+        ForwardResponseBuilder::new(request_id)
+            .payload(chunk_data)
+            .chunk(chunk_index, is_final, total_size)
+            .build()
     }
 
     // 检查响应是否为流式响应
@@ -329,10 +1232,7 @@ impl ReverseConnectionManager {
     }
 
     // 处理事件消息
-    pub async fn handle_event_message(
-        &self,
-        event: crate::registry::EventMessage,
-    ) -> Result<usize, String> {
+    pub async fn handle_event_message(&self, event: EventMessage) -> Result<usize, String> {
         match self.event_bus.publish_event(event).await {
             Ok(subscriber_count) => Ok(subscriber_count),
             Err(err) => {
@@ -342,14 +1242,42 @@ impl ReverseConnectionManager {
         }
     }
 
-    // 处理订阅请求
+    // 发布请求被丢弃或超时的事件，没有订阅者时直接忽略
+    pub(crate) fn publish_request_event(
+        event_bus: &EventBus,
+        event_type: &str,
+        request_id: &str,
+        reason: &str,
+    ) {
+        let event = EventMessage {
+            event_type: event_type.to_string(),
+            publisher_id: "gateway".to_string(),
+            metadata: HashMap::from([
+                ("request_id".to_string(), request_id.to_string()),
+                ("reason".to_string(), reason.to_string()),
+            ]),
+            ..Default::default()
+        };
+
+        if let Err(e) = event_bus.publish_event_sync(event) {
+            tracing::debug!(
+                event_type = %event_type,
+                request_id = %request_id,
+                error = %e,
+                "Request event not delivered"
+            );
+        }
+    }
+
+    // 处理订阅请求，owner 为发出请求的连接所属的调用方
     pub async fn handle_subscription_request(
         &self,
         subscription: crate::registry::SubscriptionRequest,
+        owner: &str,
     ) -> Result<(), String> {
         match self
             .event_bus
-            .handle_subscription_request(subscription)
+            .handle_subscription_request(subscription, owner)
             .await
         {
             Ok(()) => Ok(()),
@@ -360,18 +1288,54 @@ impl ReverseConnectionManager {
         }
     }
 
+    // 把可靠订阅者队列中的事件推送到连接上，直到连接关闭或该订阅者开始新的投递会话。
+    // 推送出去但未确认的事件留在队列中，下次订阅时重新发送
+    pub fn spawn_reliable_delivery(
+        &self,
+        subscriber_id: &str,
+        owner: &str,
+        outbound_tx: mpsc::Sender<Result<ConnectionMessage, tonic::Status>>,
+    ) -> Result<(), String> {
+        let events = self
+            .event_bus
+            .reliable_stream(subscriber_id, owner)
+            .map_err(|err| format!("Failed to start reliable delivery: {err}"))?;
+        let subscriber_id = subscriber_id.to_string();
+        tokio::spawn(async move {
+            tokio::pin!(events);
+            loop {
+                let event = tokio::select! {
+                    _ = outbound_tx.closed() => break,
+                    event = tokio_stream::StreamExt::next(&mut events) => match event {
+                        Some(event) => event,
+                        None => break,
+                    },
+                };
+                let message = ConnectionMessage {
+                    message_type: Some(MessageType::Event(event)),
+                };
+                if outbound_tx.send(Ok(message)).await.is_err() {
+                    break;
+                }
+            }
+            tracing::debug!(subscriber_id = %subscriber_id, "Reliable event delivery stopped");
+        });
+        Ok(())
+    }
+
     // 为连接创建事件流
     pub fn create_event_stream_for_connection(
         &self,
         connection_id: &str,
         event_type: &str,
-    ) -> Result<
-        impl tokio_stream::Stream<Item = Result<crate::registry::EventMessage, tonic::Status>>,
-        String,
-    > {
+    ) -> Result<impl tokio_stream::Stream<Item = Result<EventMessage, tonic::Status>>, String> {
+        // 使用连接订阅时指定的元数据过滤条件
+        let filter = self
+            .event_bus
+            .get_subscriber_filter(connection_id, event_type);
         match self
             .event_bus
-            .subscribe_event_type(event_type, connection_id)
+            .subscribe_with_filter(event_type, connection_id, false, filter)
         {
             Ok(stream) => Ok(stream),
             Err(err) => {
@@ -395,3 +1359,61 @@ impl ReverseConnectionManager {
         );
     }
 }
+
+// 按上限读取请求体：声明的大小（Content-Length）已超过上限时不读取任何数据，
+// 否则每读到一帧就累计大小，超过上限的那一帧之后不再读取
+struct LimitedBodyReader<B> {
+    body: std::pin::Pin<Box<B>>,
+    total_size: usize,
+    limit: usize,
+}
+
+impl<B> LimitedBodyReader<B>
+where
+    B: http_body::Body<Data = bytes::Bytes>,
+    B::Error: std::fmt::Debug,
+{
+    fn new(body: B, limit: usize) -> Result<Self, ReverseRequestError> {
+        let declared = body.size_hint().lower();
+        if declared > limit as u64 {
+            return Err(ReverseRequestError::BodyTooLarge {
+                size: usize::try_from(declared).unwrap_or(usize::MAX),
+                limit,
+            });
+        }
+        Ok(Self {
+            body: Box::pin(body),
+            total_size: 0,
+            limit,
+        })
+    }
+
+    // 读取下一个非空数据帧，忽略 trailers
+    async fn next_chunk(&mut self) -> Result<Option<bytes::Bytes>, ReverseRequestError> {
+        use http_body_util::BodyExt;
+
+        while let Some(frame) = self.body.frame().await {
+            let frame = frame.map_err(|e| {
+                ReverseRequestError::Body(format!("Failed to read request body: {e:?}"))
+            })?;
+            if let Ok(data) = frame.into_data()
+                && !data.is_empty()
+            {
+                self.total_size += data.len();
+                if self.total_size > self.limit {
+                    return Err(ReverseRequestError::BodyTooLarge {
+                        size: self.total_size,
+                        limit: self.limit,
+                    });
+                }
+                return Ok(Some(data));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn total_size(&self) -> usize {
+        self.total_size
+    }
+}
@@ -0,0 +1,68 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::manager::ReverseConnectionManager;
+use crate::registry::{ConnectionMessage, Ping, Pong, connection_message::MessageType};
+
+impl ReverseConnectionManager {
+    // 启动保活探测任务：定期向每个反向连接发送 Ping，
+    // 收到 Pong 与收到心跳一样刷新连接的存活时间，不依赖微服务自己的心跳定时器
+    pub(super) fn start_ping_task(&self) {
+        let Some(ping_interval) = self.config.effective_ping_interval() else {
+            return;
+        };
+        let connections_by_id = self.connections_by_id.clone();
+        let shutdown = self.shutdown.clone();
+
+        self.task_tracker.spawn(async move {
+            // 第一次探测在一个间隔之后，刚注册的连接无需立即探测
+            let mut interval = tokio::time::interval_at(
+                tokio::time::Instant::now() + ping_interval,
+                ping_interval,
+            );
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut nonce = 0u64;
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = interval.tick() => {}
+                }
+                nonce += 1;
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|elapsed| elapsed.as_secs() as i64)
+                    .unwrap_or_default();
+
+                let connections: Vec<_> = connections_by_id
+                    .iter()
+                    .map(|entry| entry.value().clone())
+                    .collect();
+                for connection in connections {
+                    let ping = ConnectionMessage {
+                        message_type: Some(MessageType::Ping(Ping { nonce, timestamp })),
+                    };
+                    // 发送队列已满说明连接仍在处理消息，跳过本轮探测
+                    if let Err(e) = connection.enqueue(ping) {
+                        tracing::debug!(
+                            connection_id = %connection.connection_id,
+                            error = %e,
+                            "Skipped keepalive ping"
+                        );
+                    }
+                }
+            }
+        });
+    }
+
+    // 处理微服务回复的 Pong，连接 ID 取自所在的连接流
+    pub async fn handle_pong(&self, connection_id: &str, pong: Pong) {
+        if !self.connections_by_id.contains_key(connection_id) {
+            return;
+        }
+        tracing::trace!(
+            connection_id = %connection_id,
+            nonce = pong.nonce,
+            "Received keepalive pong"
+        );
+        self.update_heartbeat(connection_id).await;
+    }
+}
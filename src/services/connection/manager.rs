@@ -1,17 +1,26 @@
-use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
+use dashmap::{DashMap, DashSet};
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
 use std::time::{Instant, SystemTime};
-use tokio::sync::{RwLock, mpsc};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
 
 use crate::registry::ConnectionMessage;
 use crate::services::event::{EventBus, EventConfig};
-use crate::services::registry::types::{ServiceInstances, ServiceRegistry};
+use crate::services::registry::types::{ServiceHealthStatus, ServiceInstances, ServiceRegistry};
+use crate::services::router::load_balance::{InFlight, LoadBalanceStrategy, SharedStrategy};
+use crate::services::router::sticky::StickyInstances;
 
 use super::{
     connection::ReverseConnection,
-    service_pool::ServicePool,
-    types::{PendingRequest, ReverseConnectionConfig, StreamingResponseHandler},
+    saturation_queue::SaturationQueue,
+    service_pool::{InstanceStates, ServicePool},
+    types::{
+        ConnectionStats, PendingRequests, RepairCounters, RequestCounters, ReverseConnectionConfig,
+        ReverseConnectionInfo, StreamingResponseHandler,
+    },
 };
 
 // 反向连接管理器
@@ -22,15 +31,31 @@ pub struct ReverseConnectionManager {
     // 连接ID -> 反向连接映射
     pub(crate) connections_by_id: Arc<DashMap<String, ReverseConnection>>,
     // 等待响应的请求
-    pub(crate) pending_requests: Arc<RwLock<DashMap<String, PendingRequest>>>,
+    pub(crate) pending_requests: Arc<PendingRequests>,
     // 流式响应处理器
-    pub(crate) streaming_handlers: Arc<RwLock<DashMap<String, StreamingResponseHandler>>>,
+    pub(crate) streaming_handlers: Arc<DashMap<String, StreamingResponseHandler>>,
+    // 转发请求计数
+    pub(crate) request_counters: Arc<RequestCounters>,
+    // 一致性校对的修复计数
+    pub(crate) repair_counters: Arc<RepairCounters>,
+    // 本网关建立过的连接 ID，一致性校对据此区分反向连接实例与正向注册的实例
+    pub(crate) known_connection_ids: Arc<DashSet<String>>,
     // 主服务注册表的引用，用于同步清理
     pub(crate) service_registry: Option<ServiceRegistry>,
     // 事件总线
     pub event_bus: Arc<EventBus>,
     pub(crate) config: ReverseConnectionConfig,
     pub(crate) task_tracker: Arc<TaskTracker>,
+    // 停机信号，触发后停止后台任务并拒绝新连接
+    pub(crate) shutdown: CancellationToken,
+    // 负载均衡策略，配置热更新时替换
+    pub(crate) load_balance_strategy: Arc<SharedStrategy>,
+    // 每个连接上进行中的请求数，least_connections 策略按此选择
+    pub(crate) in_flight: Arc<InFlight>,
+    // 实例选择粘滞：客户端 -> 上次选中的连接 ID
+    pub(crate) sticky: Arc<StickyInstances>,
+    // 连接全部达到并发上限时排队等待名额的请求
+    pub(crate) saturation_queue: Arc<SaturationQueue>,
 }
 
 impl Default for ReverseConnectionManager {
@@ -77,16 +102,26 @@ impl ReverseConnectionManager {
         let manager = Self {
             connections_by_service: Arc::new(DashMap::new()),
             connections_by_id: Arc::new(DashMap::new()),
-            pending_requests: Arc::new(RwLock::new(DashMap::new())),
-            streaming_handlers: Arc::new(RwLock::new(DashMap::new())),
+            pending_requests: Arc::new(PendingRequests::default()),
+            streaming_handlers: Arc::new(DashMap::new()),
+            request_counters: Arc::new(RequestCounters::default()),
+            repair_counters: Arc::new(RepairCounters::default()),
+            known_connection_ids: Arc::new(DashSet::new()),
             service_registry,
             event_bus: Arc::new(EventBus::new(event_config)),
             config: config.clone(),
             task_tracker: Arc::new(TaskTracker::new()),
+            shutdown: CancellationToken::new(),
+            load_balance_strategy: Arc::new(SharedStrategy::new(config.load_balance_strategy)),
+            in_flight: Arc::new(InFlight::default()),
+            sticky: Arc::new(StickyInstances::default()),
+            saturation_queue: Arc::new(SaturationQueue::default()),
         };
 
-        // 启动清理任务
+        // 启动清理任务、保活探测任务和一致性校对任务
         manager.start_cleanup_task();
+        manager.start_ping_task();
+        manager.start_reconcile_task();
 
         manager
     }
@@ -96,7 +131,8 @@ impl ReverseConnectionManager {
         &self,
         connection_id: String,
         services: Vec<String>,
-        request_sender: mpsc::UnboundedSender<ConnectionMessage>,
+        weight: u32,
+        request_sender: mpsc::Sender<ConnectionMessage>,
     ) -> Result<(), String> {
         let now = Instant::now();
         let new_connection = ReverseConnection {
@@ -105,9 +141,23 @@ impl ReverseConnectionManager {
             created_at: now,
             last_heartbeat: now,
             is_active: true,
+            weight: weight.max(1),
             request_sender,
         };
 
+        self.known_connection_ids.insert(connection_id.clone());
+
+        // 持有连接ID条目的锁完成整个替换，同一ID的并发注册依次执行；
+        // 服务池按连接ID覆盖，旧连接只需从新连接不再提供的服务中移除，
+        // 不会误删刚加入服务池的新连接
+        let (guard, old_connection) = match self.connections_by_id.entry(connection_id.clone()) {
+            Entry::Occupied(mut entry) => {
+                let old_connection = entry.insert(new_connection.clone());
+                (entry.into_ref(), Some(old_connection))
+            }
+            Entry::Vacant(entry) => (entry.insert(new_connection.clone()), None),
+        };
+
         for service in &services {
             let pool = self
                 .connections_by_service
@@ -126,6 +176,7 @@ impl ReverseConnectionManager {
                     tracing::info!(
                         service_name = %service,
                         connection_id = %connection_id,
+                        weight = new_connection.weight,
                         pool_size = pool.len(),
                         "Registered reverse connection instance for service"
                     );
@@ -133,95 +184,143 @@ impl ReverseConnectionManager {
             }
         }
 
-        if let Some(old_connection) = self
-            .connections_by_id
-            .insert(connection_id.clone(), new_connection.clone())
-        {
+        let stale_services: Vec<String> = old_connection
+            .iter()
+            .flat_map(|old| old.services.iter())
+            .filter(|service| !services.contains(service))
+            .cloned()
+            .collect();
+        for service in &stale_services {
+            self.detach_from_pool(service, &connection_id);
+        }
+        drop(guard);
+        // 新连接带来新的名额，唤醒其承载服务的排队请求
+        for service in &services {
+            self.saturation_queue.notify(service);
+        }
+
+        if old_connection.is_some() {
             tracing::info!(
-                new_connection_id = %connection_id,
-                old_connection_id = %old_connection.connection_id,
+                connection_id = %connection_id,
+                stale_services = ?stale_services,
                 "Replaced existing reverse connection with identical connection_id"
             );
-            self.detach_connection(&old_connection);
+        }
+        // 注册表实例在释放连接锁后再清理，避免与按注册表遍历连接的路径交叉加锁
+        for service in &stale_services {
+            self.remove_service_registry_instance(service, &connection_id);
         }
 
         Ok(())
     }
 
-    fn detach_connection(&self, connection: &ReverseConnection) {
-        for service in &connection.services {
-            if let Some(pool_entry) = self.connections_by_service.get(service) {
-                let pool = pool_entry.clone();
-                drop(pool_entry);
-
-                if pool.remove_connection(&connection.connection_id).is_some() {
-                    tracing::info!(
-                        service_name = %service,
-                        connection_id = %connection.connection_id,
-                        "Detached reverse connection instance from service pool"
-                    );
-                }
+    fn detach_from_pool(&self, service: &str, connection_id: &str) {
+        if let Some(pool_entry) = self.connections_by_service.get(service) {
+            let pool = pool_entry.clone();
+            drop(pool_entry);
 
-                if pool.is_empty() {
-                    self.connections_by_service
-                        .remove_if(service, |_, p| p.is_empty());
-                    tracing::debug!(
-                        service_name = %service,
-                        connection_id = %connection.connection_id,
-                        "Service pool empty after detaching connection, removed mapping"
-                    );
-                }
+            if pool.remove_connection(connection_id).is_some() {
+                tracing::info!(
+                    service_name = %service,
+                    connection_id = %connection_id,
+                    "Detached reverse connection instance from service pool"
+                );
             }
 
-            self.remove_service_registry_instance(service, &connection.connection_id);
+            if pool.is_empty() {
+                self.connections_by_service
+                    .remove_if(service, |_, p| p.is_empty());
+                tracing::debug!(
+                    service_name = %service,
+                    connection_id = %connection_id,
+                    "Service pool empty after detaching connection, removed mapping"
+                );
+            }
         }
     }
 
     fn remove_service_registry_instance(&self, service_name: &str, instance_id: &str) {
-        if let Some(ref service_registry) = self.service_registry {
-            if let Some(instances_guard) = service_registry.get(service_name) {
-                let instances = instances_guard.clone();
-                drop(instances_guard);
+        if let Some(ref service_registry) = self.service_registry
+            && let Some(instances_guard) = service_registry.get(service_name)
+        {
+            let instances = instances_guard.clone();
+            drop(instances_guard);
 
-                if instances.remove(instance_id).is_some() {
-                    tracing::info!(
-                        service_name = %service_name,
-                        instance_id = %instance_id,
-                        "Removed service instance from registry"
-                    );
-                }
+            if instances.remove(instance_id).is_some() {
+                tracing::info!(
+                    service_name = %service_name,
+                    instance_id = %instance_id,
+                    "Removed service instance from registry"
+                );
+            }
 
-                if instances.is_empty() {
-                    service_registry
-                        .remove_if(service_name, |_, v: &ServiceInstances| v.is_empty());
-                    tracing::debug!(
-                        service_name = %service_name,
-                        "Service registry entry empty, removed service"
-                    );
-                }
+            if instances.is_empty() {
+                service_registry.remove_if(service_name, |_, v: &ServiceInstances| v.is_empty());
+                tracing::debug!(
+                    service_name = %service_name,
+                    "Service registry entry empty, removed service"
+                );
             }
         }
     }
 
     // 注销反向连接
     pub async fn unregister_connection(&self, connection_id: &str) {
-        if let Some((_id, connection)) = self.connections_by_id.remove(connection_id) {
-            self.detach_connection(&connection);
-            tracing::info!(
-                connection_id = %connection_id,
-                services = ?connection.services,
-                "Unregistered reverse connection and cleaned up service mappings"
-            );
+        self.remove_connection_if(connection_id, |_| true);
+    }
+
+    // 连接流结束时注销：只移除仍使用该请求通道的连接，同一连接ID已经由新的流重新注册时保留新连接。
+    // 返回 false 表示连接ID已属于新的流，调用方不应再清理该ID的其他状态
+    pub async fn release_connection(
+        &self,
+        connection_id: &str,
+        request_sender: &mpsc::WeakSender<ConnectionMessage>,
+    ) -> bool {
+        let mut replaced = false;
+        self.remove_connection_if(connection_id, |connection| {
+            // 本连接仍在表中时通道必然存活，升级失败说明表中已是其他通道
+            replaced = !request_sender
+                .upgrade()
+                .is_some_and(|sender| sender.same_channel(&connection.request_sender));
+            !replaced
+        });
+        !replaced
+    }
+
+    fn remove_connection_if(
+        &self,
+        connection_id: &str,
+        predicate: impl FnOnce(&ReverseConnection) -> bool,
+    ) {
+        // 与注册相同，持有连接ID条目的锁从服务池移除，避免与同ID的重新注册交错
+        let Entry::Occupied(entry) = self.connections_by_id.entry(connection_id.to_string()) else {
+            return;
+        };
+        if !predicate(entry.get()) {
+            return;
         }
+        let connection = entry.get().clone();
+        for service in &connection.services {
+            self.detach_from_pool(service, connection_id);
+        }
+        entry.remove();
+        for service in &connection.services {
+            self.remove_service_registry_instance(service, connection_id);
+        }
+        tracing::info!(
+            connection_id = %connection_id,
+            services = ?connection.services,
+            "Unregistered reverse connection and cleaned up service mappings"
+        );
     }
 
-    // 更新心跳
+    // 更新心跳。只按连接ID查找，不再把服务名解析为该服务的某个连接，
+    // 否则知道服务名的客户端就能刷新其他客户端连接的心跳
     pub async fn update_heartbeat(&self, connection_id: &str) {
         // 检查连接ID格式并记录诊断信息
         let is_valid_uuid = Self::is_valid_connection_id(connection_id);
         let is_empty = connection_id.is_empty();
 
-        // 首先尝试按连接ID查找
         if let Some(mut connection) = self.connections_by_id.get_mut(connection_id) {
             let now = std::time::Instant::now();
             let old_heartbeat = connection.last_heartbeat;
@@ -249,50 +348,6 @@ impl ReverseConnectionManager {
             return;
         }
 
-        // 如果按连接ID找不到，尝试按服务名查找（兼容错误的客户端）
-        if let Some(pool_ref) = self.connections_by_service.get(connection_id) {
-            let pool = pool_ref.clone();
-            drop(pool_ref);
-
-            if let Some(actual_connection) = pool.next_connection(self.config.heartbeat_timeout) {
-                let actual_connection_id = actual_connection.connection_id.clone();
-
-                if let Some(mut connection) = self.connections_by_id.get_mut(&actual_connection_id)
-                {
-                    let now = std::time::Instant::now();
-                    let old_heartbeat = connection.last_heartbeat;
-                    connection.update_heartbeat();
-                    let services = connection.services.clone();
-
-                    // 同时更新 connections_by_service 中的副本
-                    for service_name in &services {
-                        if let Some(service_pool) = self.connections_by_service.get(service_name) {
-                            service_pool.update_connection(&actual_connection_id, |conn| {
-                                conn.last_heartbeat = now;
-                            });
-                        }
-                    }
-
-                    tracing::error!(
-                        received_id = %connection_id,
-                        actual_connection_id = %actual_connection_id,
-                        services = ?services,
-                        is_valid_uuid = %is_valid_uuid,
-                        old_heartbeat_elapsed_ms = %old_heartbeat.elapsed().as_millis(),
-                        new_heartbeat_set = %now.elapsed().as_millis(),
-                        "CLIENT ERROR: Using service name as heartbeat ID! Client must use connection_id: '{}' for heartbeat, not service name: '{}'. Heartbeat updated in both mappings.",
-                        actual_connection_id,
-                        connection_id
-                    );
-
-                    // 同时更新服务注册表中对应服务的心跳时间戳
-                    self.update_service_registry_heartbeat(&actual_connection_id, &services)
-                        .await;
-                    return;
-                }
-            }
-        }
-
         // 提供详细的错误诊断和客户端指导
         if is_empty {
             tracing::error!(
@@ -342,11 +397,34 @@ impl ReverseConnectionManager {
 
     // 获取服务的反向连接 - 增强状态验证和诊断
     pub fn get_connection_for_service(&self, service_name: &str) -> Option<ReverseConnection> {
+        self.get_connection_for_key(service_name, None)
+    }
+
+    // 按亲和键获取服务的反向连接，同一个键始终选中同一个连接
+    pub fn get_connection_for_key(
+        &self,
+        service_name: &str,
+        affinity_key: Option<&str>,
+    ) -> Option<ReverseConnection> {
+        self.get_connection_for_client(service_name, affinity_key, None)
+    }
+
+    // 获取服务的反向连接，client_id 在粘滞窗口内选中过的连接仍然可用时优先沿用
+    pub fn get_connection_for_client(
+        &self,
+        service_name: &str,
+        affinity_key: Option<&str>,
+        client_id: Option<&str>,
+    ) -> Option<ReverseConnection> {
+        let preferred = client_id.and_then(|client_id| self.sticky.get(client_id, service_name));
+        let preferred = preferred.as_deref();
         if let Some(pool_ref) = self.connections_by_service.get(service_name) {
             let pool = pool_ref.clone();
             drop(pool_ref);
 
-            if let Some(conn) = pool.next_connection(self.config.heartbeat_timeout) {
+            if let Some(conn) =
+                self.select_routable_connection(service_name, &pool, affinity_key, preferred)
+            {
                 let last_heartbeat_ago = conn.last_heartbeat.elapsed();
 
                 tracing::debug!(
@@ -374,7 +452,8 @@ impl ReverseConnectionManager {
             );
         }
 
-        let result = self.find_connection_by_hierarchical_name(service_name);
+        let result =
+            self.find_connection_by_hierarchical_name(service_name, affinity_key, preferred);
 
         if result.is_none() {
             self.cleanup_orphaned_service_registry_entry(service_name);
@@ -383,6 +462,77 @@ impl ReverseConnectionManager {
         result
     }
 
+    // 等待微服务响应的请求数
+    pub async fn pending_request_count(&self) -> usize {
+        self.pending_requests.len()
+    }
+
+    // 连接与请求统计
+    pub async fn get_stats(&self) -> ConnectionStats {
+        let counters = &self.request_counters;
+        let repairs = &self.repair_counters;
+        ConnectionStats {
+            active_connections: self.connections_by_id.len(),
+            registered_services: self.connections_by_service.len(),
+            pooled_connections: self
+                .connections_by_service
+                .iter()
+                .map(|pool| pool.value().len())
+                .sum(),
+            pending_requests: self.pending_request_count().await,
+            forwarded_requests: counters.forwarded.load(Ordering::Relaxed),
+            timed_out_requests: counters.timed_out.load(Ordering::Relaxed),
+            failed_requests: counters.failed.load(Ordering::Relaxed),
+            detached_pool_connections: repairs.detached_pool_connections.load(Ordering::Relaxed),
+            removed_registry_instances: repairs.removed_registry_instances.load(Ordering::Relaxed),
+        }
+    }
+
+    // 查询单个反向连接的诊断信息
+    pub fn get_connection_info(&self, connection_id: &str) -> Option<ReverseConnectionInfo> {
+        let connection = self.connections_by_id.get(connection_id)?.clone();
+        Some(self.connection_info(connection))
+    }
+
+    // 列出所有反向连接的诊断信息，按连接 ID 排序
+    pub fn list_connections(&self) -> Vec<ReverseConnectionInfo> {
+        // 先复制连接再查询服务池，避免同时持有两张表的锁
+        let connections: Vec<ReverseConnection> = self
+            .connections_by_id
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        let mut infos: Vec<ReverseConnectionInfo> = connections
+            .into_iter()
+            .map(|connection| self.connection_info(connection))
+            .collect();
+        infos.sort_by(|a, b| a.connection_id.cmp(&b.connection_id));
+        infos
+    }
+
+    fn connection_info(&self, connection: ReverseConnection) -> ReverseConnectionInfo {
+        let mut pooled_services: Vec<String> = self
+            .connections_by_service
+            .iter()
+            .filter(|pool| pool.value().contains(&connection.connection_id))
+            .map(|pool| pool.key().clone())
+            .collect();
+        pooled_services.sort();
+        ReverseConnectionInfo {
+            last_heartbeat_age: connection.last_heartbeat.elapsed(),
+            connection_id: connection.connection_id,
+            services: connection.services,
+            created_at: connection.created_at,
+            weight: connection.weight,
+            pooled_services,
+        }
+    }
+
+    // 正在组装的流式响应数
+    pub async fn streaming_response_count(&self) -> usize {
+        self.streaming_handlers.len()
+    }
+
     // 检查是否存在可用的反向连接
     pub fn has_reverse_connection(&self, service_name: &str) -> bool {
         if self.has_direct_reverse_connection(service_name) {
@@ -401,10 +551,7 @@ impl ReverseConnectionManager {
             let pool = pool_ref.clone();
             drop(pool_ref);
 
-            if pool
-                .next_connection(self.config.heartbeat_timeout)
-                .is_some()
-            {
+            if self.has_routable_connection(service_name, &pool) {
                 return true;
             }
 
@@ -427,10 +574,7 @@ impl ReverseConnectionManager {
                 let pool = pool_ref.clone();
                 drop(pool_ref);
 
-                if pool
-                    .next_connection(self.config.heartbeat_timeout)
-                    .is_some()
-                {
+                if self.has_routable_connection(&parent_name, &pool) {
                     tracing::debug!(
                         requested_service = %service_name,
                         matched_service = %parent_name,
@@ -447,20 +591,103 @@ impl ReverseConnectionManager {
         false
     }
 
+    // 从服务池选择连接，跳过注册表中被标记为 Unhealthy 或 Draining 的实例（实例ID即连接ID）
+    // 和进行中请求数已达上限的连接；
+    // 服务在注册表中的实例全部不可用时（例如运维手动标记整个服务不健康），不选择任何连接
+    fn select_routable_connection(
+        &self,
+        service_name: &str,
+        pool: &ServicePool,
+        affinity_key: Option<&str>,
+        preferred: Option<&str>,
+    ) -> Option<ReverseConnection> {
+        let mut states = self.instance_states(service_name)?;
+        if let Some(limit) = self.config.max_in_flight_per_connection {
+            states.excluded.extend(
+                pool.connection_ids()
+                    .into_iter()
+                    .filter(|id| self.in_flight.count(id) >= limit),
+            );
+        }
+        pool.select_connection(
+            self.config.heartbeat_timeout,
+            affinity_key,
+            preferred,
+            &states,
+            self.load_balance_strategy.load(),
+            &self.in_flight,
+        )
+    }
+
+    // 服务池中是否有可以选择的连接；只检查不选择，不推进负载均衡的轮询游标
+    fn has_routable_connection(&self, service_name: &str, pool: &ServicePool) -> bool {
+        self.instance_states(service_name).is_some_and(|states| {
+            pool.has_available_connection(self.config.heartbeat_timeout, &states)
+        })
+    }
+
+    // 注册表中被标记为 Unhealthy 或 Draining 的实例和各实例的故障转移层级，
+    // 全部实例都不可用时返回 None
+    fn instance_states(&self, service_name: &str) -> Option<InstanceStates> {
+        let mut states = InstanceStates::default();
+        if let Some(ref service_registry) = self.service_registry
+            && let Some(instances_guard) = service_registry.get(service_name)
+        {
+            let instances = instances_guard.clone();
+            drop(instances_guard);
+
+            for instance in instances.iter() {
+                let info = instance.value();
+                if matches!(
+                    info.health_status,
+                    ServiceHealthStatus::Unhealthy | ServiceHealthStatus::Draining
+                ) {
+                    states.excluded.insert(instance.key().clone());
+                }
+                let tier = info.tier();
+                if tier > 0 {
+                    states.tiers.insert(instance.key().clone(), tier);
+                }
+            }
+            if !instances.is_empty() && states.excluded.len() == instances.len() {
+                tracing::debug!(
+                    service_name = %service_name,
+                    "All registry instances unavailable, skipping reverse connections"
+                );
+                return None;
+            }
+        }
+        Some(states)
+    }
+
+    // 当前的负载均衡策略
+    pub fn load_balance_strategy(&self) -> LoadBalanceStrategy {
+        self.load_balance_strategy.load()
+    }
+
+    // 替换负载均衡策略，之后选择的连接立即按新策略
+    pub fn set_load_balance_strategy(&self, strategy: LoadBalanceStrategy) {
+        self.load_balance_strategy.store(strategy);
+    }
+
+    // 连接上进行中的请求数
+    pub fn in_flight_requests(&self, connection_id: &str) -> usize {
+        self.in_flight.count(connection_id)
+    }
+
     // 清理孤立的服务注册表条目（没有对应反向连接的服务）
     fn cleanup_orphaned_service_registry_entry(&self, service_name: &str) {
-        if let Some(ref service_registry) = self.service_registry {
-            if service_registry
+        if let Some(ref service_registry) = self.service_registry
+            && service_registry
                 .remove_if(service_name, |_, instances: &ServiceInstances| {
                     instances.is_empty()
                 })
                 .is_some()
-            {
-                tracing::warn!(
-                    service_name = %service_name,
-                    "CONSISTENCY FIX: Removed orphaned service from registry (no valid reverse connections remaining)"
-                );
-            }
+        {
+            tracing::warn!(
+                service_name = %service_name,
+                "CONSISTENCY FIX: Removed orphaned service from registry (no valid reverse connections remaining)"
+            );
         }
     }
 
@@ -468,6 +695,8 @@ impl ReverseConnectionManager {
     fn find_connection_by_hierarchical_name(
         &self,
         service_name: &str,
+        affinity_key: Option<&str>,
+        preferred: Option<&str>,
     ) -> Option<ReverseConnection> {
         // 将服务名按 '.' 分割，从最长的父级开始尝试
         let parts: Vec<&str> = service_name.split('.').collect();
@@ -486,7 +715,9 @@ impl ReverseConnectionManager {
                 let pool = pool_ref.clone();
                 drop(pool_ref);
 
-                if let Some(conn) = pool.next_connection(self.config.heartbeat_timeout) {
+                if let Some(conn) =
+                    self.select_routable_connection(&parent_name, &pool, affinity_key, preferred)
+                {
                     tracing::info!(
                         requested_service = %service_name,
                         matched_service = %parent_name,
@@ -506,10 +737,9 @@ impl ReverseConnectionManager {
         );
         None
     }
-
-
 }
 
+// 未调用 shutdown 时的兜底：不等待任务结束
 impl Drop for ReverseConnectionManager {
     fn drop(&mut self) {
         self.task_tracker.close();
@@ -1,10 +1,18 @@
 pub mod cleanup;
+#[allow(clippy::module_inception)]
 pub mod connection;
+pub mod drain;
 pub mod handler;
+pub mod keepalive;
 pub mod manager;
+pub mod reconcile;
+pub mod response_builder;
+pub mod saturation_queue;
 pub mod service_pool;
 pub mod types;
 
 pub use connection::*;
+pub use handler::{ResponseChunks, ReverseResponse};
 pub use manager::*;
+pub use response_builder::ForwardResponseBuilder;
 pub use types::*;
@@ -0,0 +1,179 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use dashmap::{DashMap, DashSet};
+
+use super::{
+    connection::ReverseConnection,
+    manager::ReverseConnectionManager,
+    service_pool::ServicePool,
+    types::{ReconcileReport, RepairCounters},
+};
+use crate::services::registry::types::{ServiceInstances, ServiceRegistry};
+
+impl ReverseConnectionManager {
+    // 启动一致性校对任务：定期比对连接表、服务池和注册表，修复各处清理遗漏的映射
+    pub(super) fn start_reconcile_task(&self) {
+        let Some(reconcile_interval) = self.config.reconcile_interval else {
+            return;
+        };
+        let connections_by_service = self.connections_by_service.clone();
+        let connections_by_id = self.connections_by_id.clone();
+        let known_connection_ids = self.known_connection_ids.clone();
+        let repair_counters = self.repair_counters.clone();
+        let service_registry = self.service_registry.clone();
+        let shutdown = self.shutdown.clone();
+
+        self.task_tracker.spawn(async move {
+            // 第一次校对在一个间隔之后，启动时各映射必然一致
+            let mut interval = tokio::time::interval_at(
+                tokio::time::Instant::now() + reconcile_interval,
+                reconcile_interval,
+            );
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = interval.tick() => {}
+                }
+                Self::reconcile_mappings(
+                    &connections_by_service,
+                    &connections_by_id,
+                    service_registry.as_ref(),
+                    &known_connection_ids,
+                    &repair_counters,
+                );
+            }
+        });
+    }
+
+    // 立即执行一次一致性校对，返回本次修复的数量
+    pub fn reconcile(&self) -> ReconcileReport {
+        Self::reconcile_mappings(
+            &self.connections_by_service,
+            &self.connections_by_id,
+            self.service_registry.as_ref(),
+            &self.known_connection_ids,
+            &self.repair_counters,
+        )
+    }
+
+    fn reconcile_mappings(
+        connections_by_service: &Arc<DashMap<String, ServicePool>>,
+        connections_by_id: &Arc<DashMap<String, ReverseConnection>>,
+        service_registry: Option<&ServiceRegistry>,
+        known_connection_ids: &DashSet<String>,
+        repair_counters: &RepairCounters,
+    ) -> ReconcileReport {
+        let report = ReconcileReport {
+            detached_pool_connections: Self::detach_unknown_pool_connections(
+                connections_by_service,
+                connections_by_id,
+            ),
+            removed_registry_instances: service_registry
+                .map(|registry| {
+                    Self::remove_disconnected_instances(
+                        registry,
+                        connections_by_id,
+                        known_connection_ids,
+                    )
+                })
+                .unwrap_or_default(),
+        };
+
+        RepairCounters::add(
+            &repair_counters.detached_pool_connections,
+            report.detached_pool_connections,
+        );
+        RepairCounters::add(
+            &repair_counters.removed_registry_instances,
+            report.removed_registry_instances,
+        );
+        if !report.is_empty() {
+            tracing::warn!(
+                detached_pool_connections = report.detached_pool_connections,
+                removed_registry_instances = report.removed_registry_instances,
+                "CONSISTENCY FIX: Reconciled reverse connection mappings"
+            );
+        }
+        report
+    }
+
+    // 服务池中存在、连接表中已不存在的连接不会再收到心跳，也不会被清理任务找到，从服务池中摘除
+    fn detach_unknown_pool_connections(
+        connections_by_service: &DashMap<String, ServicePool>,
+        connections_by_id: &DashMap<String, ReverseConnection>,
+    ) -> usize {
+        // 先复制服务池再查询连接表：注册连接时持有连接表条目的锁写入服务池，
+        // 同时持有两张表的锁会与之交叉加锁
+        let pools: Vec<(String, ServicePool)> = connections_by_service
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+
+        let mut detached = 0;
+        for (service, pool) in pools {
+            for connection_id in pool.connection_ids() {
+                if connections_by_id.contains_key(&connection_id) {
+                    continue;
+                }
+                if pool.remove_connection(&connection_id).is_some() {
+                    detached += 1;
+                    tracing::warn!(
+                        service_name = %service,
+                        connection_id = %connection_id,
+                        "CONSISTENCY FIX: Detached unknown connection from service pool"
+                    );
+                }
+            }
+            if pool.is_empty() {
+                connections_by_service.remove_if(&service, |_, p| p.is_empty());
+            }
+        }
+        detached
+    }
+
+    // 以反向连接 ID 登记、但连接已经不存在的注册表实例不会被选中，却仍计入服务的实例和健康状态，
+    // 将其移除。只处理本网关建立过的连接 ID，正向注册的实例由心跳过期清理
+    fn remove_disconnected_instances(
+        service_registry: &ServiceRegistry,
+        connections_by_id: &DashMap<String, ReverseConnection>,
+        known_connection_ids: &DashSet<String>,
+    ) -> usize {
+        let services: Vec<(String, ServiceInstances)> = service_registry
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+
+        let mut removed = 0;
+        let mut registered_ids = HashSet::new();
+        for (service, instances) in services {
+            let instance_ids: Vec<String> =
+                instances.iter().map(|entry| entry.key().clone()).collect();
+            for instance_id in instance_ids {
+                if !known_connection_ids.contains(&instance_id)
+                    || connections_by_id.contains_key(&instance_id)
+                {
+                    registered_ids.insert(instance_id);
+                    continue;
+                }
+                if instances.remove(&instance_id).is_some() {
+                    removed += 1;
+                    tracing::warn!(
+                        service_name = %service,
+                        instance_id = %instance_id,
+                        "CONSISTENCY FIX: Removed registry instance without a live reverse connection"
+                    );
+                }
+            }
+            if instances.is_empty() {
+                service_registry.remove_if(&service, |_, v: &ServiceInstances| v.is_empty());
+            }
+        }
+
+        // 已断开且不再出现在注册表中的连接 ID 无需继续跟踪
+        known_connection_ids
+            .retain(|id| connections_by_id.contains_key(id) || registered_ids.contains(id));
+        removed
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use super::types::ForwardResponseError;
+use crate::registry::{ForwardResponse, ResponseStreamInfo, StreamingInfo};
+
+// ForwardResponse 构建器，build 时检查字段之间的约束：
+// 流式数据块必须带有 is_streamed 为 true 的 response_stream_info，且 chunk_size 与数据块长度一致；
+// 错误响应必须带有非空的 error_message 和表示失败的状态码
+#[derive(Debug, Clone)]
+pub struct ForwardResponseBuilder {
+    response: ForwardResponse,
+    is_error: bool,
+}
+
+impl ForwardResponseBuilder {
+    // 状态码默认为 200
+    pub fn new(request_id: impl Into<String>) -> Self {
+        Self {
+            response: ForwardResponse {
+                request_id: request_id.into(),
+                status_code: 200,
+                ..Default::default()
+            },
+            is_error: false,
+        }
+    }
+
+    // 以 gRPC 状态返回的错误：HTTP 状态码为 200，状态放在 grpc-status 和 grpc-message 中。
+    // 字段组合固定且总能通过 build 的检查，因此直接返回响应
+    pub fn grpc_error(
+        request_id: impl Into<String>,
+        code: tonic::Code,
+        message: impl Into<String>,
+    ) -> ForwardResponse {
+        let headers = HashMap::from([
+            ("content-type".to_string(), "application/grpc".to_string()),
+            ("grpc-status".to_string(), (code as i32).to_string()),
+            ("grpc-message".to_string(), message.into()),
+        ]);
+        Self::new(request_id).headers(headers).response
+    }
+
+    // 状态码为 500 的错误响应，原样带回请求的流式信息；message 为空时使用默认说明，
+    // 保证错误响应带有 error_message
+    pub fn internal_error(
+        request_id: impl Into<String>,
+        message: impl Into<String>,
+        streaming_info: Option<StreamingInfo>,
+    ) -> ForwardResponse {
+        let mut message = message.into();
+        if message.is_empty() {
+            message = "Internal error".to_string();
+        }
+        let mut builder = Self::new(request_id).streaming_info(streaming_info);
+        builder.response.status_code = 500;
+        builder.response.error_message = message;
+        builder.response
+    }
+
+    pub fn status_code(mut self, status_code: i32) -> Self {
+        self.response.status_code = status_code;
+        self
+    }
+
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.response.headers.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.response.headers.extend(headers);
+        self
+    }
+
+    pub fn payload(mut self, payload: Vec<u8>) -> Self {
+        self.response.payload = payload;
+        self
+    }
+
+    // 错误响应：status_code 为 gRPC 状态码（0-16）或 HTTP 状态码
+    pub fn error(mut self, status_code: i32, message: impl Into<String>) -> Self {
+        self.response.status_code = status_code;
+        self.response.error_message = message.into();
+        self.is_error = true;
+        self
+    }
+
+    // 原样带回请求的流式信息
+    pub fn streaming_info(mut self, streaming_info: Option<StreamingInfo>) -> Self {
+        self.response.streaming_info = streaming_info;
+        self
+    }
+
+    // 流式响应的一个数据块，chunk_size 在 build 时按数据块长度填写
+    pub fn chunk(
+        mut self,
+        chunk_index: i64,
+        is_final_chunk: bool,
+        total_size: Option<i64>,
+    ) -> Self {
+        self.response.response_stream_info = Some(ResponseStreamInfo {
+            is_streamed: true,
+            chunk_index,
+            is_final_chunk,
+            chunk_size: 0,
+            total_size,
+        });
+        self
+    }
+
+    // 直接指定响应体流式传输信息，build 时按同样的约束检查
+    pub fn response_stream_info(mut self, info: ResponseStreamInfo) -> Self {
+        self.response.response_stream_info = Some(info);
+        self
+    }
+
+    pub fn build(mut self) -> Result<ForwardResponse, ForwardResponseError> {
+        let status_code = self.response.status_code;
+        if !matches!(status_code, 0..=16 | 100..=599) {
+            return Err(ForwardResponseError::InvalidStatusCode(status_code));
+        }
+
+        let is_success = matches!(status_code, 0 | 200..=299);
+        if self.is_error && self.response.error_message.is_empty() {
+            return Err(ForwardResponseError::MissingErrorMessage);
+        }
+        if is_success && !self.response.error_message.is_empty() {
+            return Err(ForwardResponseError::ErrorWithSuccessStatus(status_code));
+        }
+
+        let payload_size = self.response.payload.len();
+        if let Some(info) = self.response.response_stream_info.as_mut() {
+            if !info.is_streamed {
+                return Err(ForwardResponseError::NotStreamed);
+            }
+            if info.chunk_index < 0 {
+                return Err(ForwardResponseError::InvalidChunkIndex(info.chunk_index));
+            }
+            if info.chunk_size == 0 {
+                info.chunk_size = payload_size as i32;
+            } else if info.chunk_size as usize != payload_size {
+                return Err(ForwardResponseError::ChunkSizeMismatch {
+                    chunk_size: info.chunk_size,
+                    payload_size,
+                });
+            }
+        }
+
+        Ok(self.response)
+    }
+}
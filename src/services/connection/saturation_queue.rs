@@ -0,0 +1,144 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use dashmap::DashMap;
+use tokio::sync::Notify;
+use tokio::sync::futures::OwnedNotified;
+
+use crate::services::router::load_balance::InFlightGuard;
+
+// 服务的连接全部达到并发上限时排队等待名额的请求，每个服务一个先进先出队列。
+// 只有队首的请求尝试选择连接，该服务的名额释放、新连接注册或队首变化时唤醒该服务的所有等待者
+#[derive(Debug, Default)]
+pub(crate) struct SaturationQueue {
+    services: DashMap<String, ServiceQueue>,
+    next_ticket: AtomicU64,
+}
+
+// 单个服务的排队请求及其唤醒通知
+#[derive(Debug, Default)]
+struct ServiceQueue {
+    waiters: VecDeque<u64>,
+    changed: Arc<Notify>,
+}
+
+impl SaturationQueue {
+    // 服务是否有请求正在排队，有时新请求也要排队，不能越过队列直接选择连接
+    pub(crate) fn has_waiters(&self, service_name: &str) -> bool {
+        self.services
+            .get(service_name)
+            .is_some_and(|queue| !queue.waiters.is_empty())
+    }
+
+    // 加入服务的队列，队列已满时返回 None
+    pub(crate) fn join(
+        self: &Arc<Self>,
+        service_name: &str,
+        capacity: usize,
+    ) -> Option<QueueTicket> {
+        let mut queue = self.services.entry(service_name.to_string()).or_default();
+        if queue.waiters.len() >= capacity {
+            return None;
+        }
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+        queue.waiters.push_back(ticket);
+        Some(QueueTicket {
+            queue: self.clone(),
+            service_name: service_name.to_string(),
+            ticket,
+        })
+    }
+
+    // 唤醒服务的所有等待者，服务没有请求排队时什么也不做
+    pub(crate) fn notify(&self, service_name: &str) {
+        if let Some(queue) = self.services.get(service_name) {
+            queue.changed.notify_waiters();
+        }
+    }
+}
+
+// 排队中的请求，丢弃时（选中连接、等待超时或调用方断开）离开队列
+#[derive(Debug)]
+pub(crate) struct QueueTicket {
+    queue: Arc<SaturationQueue>,
+    service_name: String,
+    ticket: u64,
+}
+
+impl QueueTicket {
+    pub(crate) fn is_head(&self) -> bool {
+        self.queue
+            .services
+            .get(&self.service_name)
+            .is_some_and(|queue| queue.waiters.front() == Some(&self.ticket))
+    }
+
+    // 等待所在服务的队列变化。先创建等待再检查状态，检查之后发出的通知不会丢失
+    pub(crate) fn changed(&self) -> OwnedNotified {
+        // 持有排队号期间服务的队列不会被移除
+        let changed = self
+            .queue
+            .services
+            .entry(self.service_name.clone())
+            .or_default()
+            .changed
+            .clone();
+        changed.notified_owned()
+    }
+}
+
+impl Drop for QueueTicket {
+    fn drop(&mut self) {
+        if let Some(mut queue) = self.queue.services.get_mut(&self.service_name) {
+            queue.waiters.retain(|ticket| *ticket != self.ticket);
+            // 队首可能已经变化，唤醒剩下的等待者
+            queue.changed.notify_waiters();
+        }
+        self.queue
+            .services
+            .remove_if(&self.service_name, |_, queue| queue.waiters.is_empty());
+    }
+}
+
+// 请求占用的连接名额。字段按声明顺序释放：先扣除进行中的请求数，再唤醒排队的请求
+#[derive(Debug)]
+pub(crate) struct ConnectionSlot {
+    // 排队等待名额的时间，没有排队时为零
+    pub(crate) queued: Duration,
+    _in_flight: InFlightGuard,
+    _release: SlotRelease,
+}
+
+impl ConnectionSlot {
+    // services 为名额所在连接承载的服务，释放名额时只唤醒这些服务的排队请求
+    pub(crate) fn new(
+        in_flight: InFlightGuard,
+        queue: &Arc<SaturationQueue>,
+        services: Vec<String>,
+    ) -> Self {
+        Self {
+            queued: Duration::ZERO,
+            _in_flight: in_flight,
+            _release: SlotRelease {
+                queue: queue.clone(),
+                services,
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SlotRelease {
+    queue: Arc<SaturationQueue>,
+    services: Vec<String>,
+}
+
+impl Drop for SlotRelease {
+    fn drop(&mut self) {
+        for service in &self.services {
+            self.queue.notify(service);
+        }
+    }
+}
@@ -1,9 +1,26 @@
 use dashmap::DashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::AtomicUsize;
 use std::time::Duration;
 
 use super::connection::ReverseConnection;
+use crate::services::router::affinity;
+use crate::services::router::load_balance::{self, InFlight, LoadBalanceStrategy};
+
+// 注册表中以连接 ID 登记的实例状态：不可用的实例，以及各实例的故障转移层级
+#[derive(Debug, Default)]
+pub(crate) struct InstanceStates {
+    pub(crate) excluded: HashSet<String>,
+    pub(crate) tiers: HashMap<String, u32>,
+}
+
+impl InstanceStates {
+    // 未在注册表中登记层级的连接属于主层级 0
+    fn tier(&self, connection_id: &str) -> u32 {
+        self.tiers.get(connection_id).copied().unwrap_or(0)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub(crate) struct ServicePool {
@@ -19,7 +36,10 @@ impl ServicePool {
         }
     }
 
-    pub(crate) fn add_connection(&self, connection: ReverseConnection) -> Option<ReverseConnection> {
+    pub(crate) fn add_connection(
+        &self,
+        connection: ReverseConnection,
+    ) -> Option<ReverseConnection> {
         self.connections
             .insert(connection.connection_id.clone(), connection)
     }
@@ -28,7 +48,19 @@ impl ServicePool {
         self.connections.remove(connection_id).map(|(_, conn)| conn)
     }
 
-    pub(crate) fn next_connection(&self, timeout: Duration) -> Option<ReverseConnection> {
+    pub(crate) fn connection_ids(&self) -> Vec<String> {
+        self.connections
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect()
+    }
+
+    pub(crate) fn contains(&self, connection_id: &str) -> bool {
+        self.connections.contains_key(connection_id)
+    }
+
+    // 清理过期连接并返回仍然活跃的连接
+    fn active_connections(&self, timeout: Duration) -> Vec<ReverseConnection> {
         let mut active = Vec::new();
         let mut expired_ids = Vec::new();
 
@@ -45,12 +77,63 @@ impl ServicePool {
             self.connections.remove(&id);
         }
 
-        if active.is_empty() {
-            return None;
+        active
+    }
+
+    // 按亲和键选择连接：同一个键始终落在同一个连接上，没有亲和键时按负载均衡策略选择；
+    // preferred（粘滞窗口内上次选中的连接）仍然可用时直接沿用；
+    // excluded 中的连接（注册表中不健康的实例）不参与选择，
+    // 其余连接中只在故障转移层级最低的一层里选择
+    pub(crate) fn select_connection(
+        &self,
+        timeout: Duration,
+        affinity_key: Option<&str>,
+        preferred: Option<&str>,
+        states: &InstanceStates,
+        strategy: LoadBalanceStrategy,
+        in_flight: &InFlight,
+    ) -> Option<ReverseConnection> {
+        let mut active = self.active_connections(timeout);
+        active.retain(|conn| !states.excluded.contains(&conn.connection_id));
+        let lowest_tier = active
+            .iter()
+            .map(|conn| states.tier(&conn.connection_id))
+            .min();
+        active.retain(|conn| Some(states.tier(&conn.connection_id)) == lowest_tier);
+
+        if let Some(index) = preferred
+            .filter(|_| affinity_key.is_none())
+            .and_then(|preferred| {
+                active
+                    .iter()
+                    .position(|conn| conn.connection_id == preferred)
+            })
+        {
+            return Some(active.swap_remove(index));
         }
 
-        let idx = self.cursor.fetch_add(1, Ordering::Relaxed);
-        Some(active[idx % active.len()].clone())
+        match affinity_key {
+            Some(key) => affinity::rank(key, &mut active, connection_key),
+            None => load_balance::order(
+                strategy,
+                &mut active,
+                &self.cursor,
+                in_flight,
+                connection_key,
+            ),
+        }
+        active.into_iter().next()
+    }
+
+    // 是否有不在 excluded 中的活跃连接
+    pub(crate) fn has_available_connection(
+        &self,
+        timeout: Duration,
+        states: &InstanceStates,
+    ) -> bool {
+        self.active_connections(timeout)
+            .iter()
+            .any(|conn| !states.excluded.contains(&conn.connection_id))
     }
 
     pub(crate) fn is_empty(&self) -> bool {
@@ -69,4 +152,9 @@ impl ServicePool {
             update_fn(entry.value_mut());
         }
     }
-}
\ No newline at end of file
+}
+
+// 负载均衡和会话亲和使用的连接键与权重
+fn connection_key(conn: &ReverseConnection) -> (&str, u32) {
+    (conn.connection_id.as_str(), conn.weight)
+}
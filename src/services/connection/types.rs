@@ -1,14 +1,101 @@
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
-use tokio::sync::oneshot;
+use tokio::sync::{mpsc, oneshot};
 
 use crate::registry::ForwardResponse;
+use crate::services::router::load_balance::LoadBalanceStrategy;
+
+// 网关丢弃反向请求时发布的事件类型，元数据中携带 request_id 和 reason
+pub const REQUEST_DROPPED_EVENT: &str = "gateway.request.dropped";
+pub const REQUEST_TIMEOUT_EVENT: &str = "gateway.request.timeout";
+
+// 发给等待方的结果，网关侧放弃请求时携带错误原因
+pub type ResponseSender = oneshot::Sender<Result<ForwardResponse, String>>;
+
+// 增量转发流式响应时，第一个数据块之后按序号依次发送的数据块
+pub type ChunkSender = mpsc::UnboundedSender<Result<ForwardResponse, String>>;
 
 // 等待中的请求
 #[derive(Debug)]
 pub struct PendingRequest {
     pub request_id: String,
     pub created_at: Instant,
-    pub response_sender: oneshot::Sender<ForwardResponse>,
+    pub response_sender: ResponseSender,
+    // 设置后流式响应的数据块到达即转发，否则组装成完整响应
+    pub chunk_sender: Option<ChunkSender>,
+    // 响应大小上限，流式响应按已收到的数据块累计
+    pub max_response_size: usize,
+}
+
+// 等待响应的请求表：DashMap 自身按分片加锁，无需外层读写锁；
+// 容量用原子计数在插入前预留，并发登记时也不会超过上限
+#[derive(Debug, Default)]
+pub struct PendingRequests {
+    entries: DashMap<String, PendingRequest>,
+    count: AtomicUsize,
+}
+
+impl PendingRequests {
+    // 已达到容量上限时返回 false，请求不会被登记
+    pub fn try_insert(&self, request: PendingRequest, capacity: usize) -> bool {
+        let reserved = self
+            .count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < capacity).then_some(count + 1)
+            })
+            .is_ok();
+        if !reserved {
+            return false;
+        }
+
+        // 同一请求ID重复登记时覆盖旧请求，归还多预留的名额
+        if self
+            .entries
+            .insert(request.request_id.clone(), request)
+            .is_some()
+        {
+            self.count.fetch_sub(1, Ordering::AcqRel);
+        }
+        true
+    }
+
+    pub fn remove(&self, request_id: &str) -> Option<PendingRequest> {
+        let (_id, request) = self.entries.remove(request_id)?;
+        self.count.fetch_sub(1, Ordering::AcqRel);
+        Some(request)
+    }
+
+    pub fn len(&self) -> usize {
+        self.count.load(Ordering::Acquire)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 取出全部等待中的请求
+    pub fn drain(&self) -> Vec<PendingRequest> {
+        let request_ids: Vec<String> = self
+            .entries
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect();
+        request_ids
+            .iter()
+            .filter_map(|request_id| self.remove(request_id))
+            .collect()
+    }
+
+    // 登记时间早于 timeout 之前的请求ID
+    pub fn expired(&self, now: Instant, timeout: Duration) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|entry| now.duration_since(entry.value().created_at) > timeout)
+            .map(|entry| entry.key().clone())
+            .collect()
+    }
 }
 
 // 流式响应处理器
@@ -16,10 +103,61 @@ pub struct PendingRequest {
 pub struct StreamingResponseHandler {
     pub request_id: String,
     pub chunks: std::collections::BTreeMap<i64, Vec<u8>>, // chunk_index -> data
-    pub next_expected_chunk: i64,
-    pub is_complete: bool,
+    // 最后一个数据块（不含 payload），携带最终的状态码和响应头
+    pub final_chunk: Option<ForwardResponse>,
     pub total_size: Option<i64>,
-    pub response_sender: oneshot::Sender<ForwardResponse>,
+    pub created_at: Instant,
+    // 最近一次收到数据块的时间，超过 stream_idle_timeout 未更新视为流已中断
+    pub last_chunk_at: Instant,
+    // 完整响应或增量转发的第一个数据块发出后为 None
+    pub response_sender: Option<ResponseSender>,
+    // 增量转发时后续数据块的发送端
+    pub chunk_sender: Option<ChunkSender>,
+    // 增量转发中下一个待发送的序号，之前的数据块已经发出
+    pub next_index: i64,
+    // 缓存模式下从 0 开始连续到齐的数据块数，之前的序号全部已收到
+    pub contiguous: i64,
+    pub max_response_size: usize,
+    // 已收到的数据块累计大小，包括已经增量转发的数据块
+    pub received_size: usize,
+}
+
+impl StreamingResponseHandler {
+    // 最后一个数据块的序号，尚未收到时为 None
+    pub fn final_chunk_index(&self) -> Option<i64> {
+        self.final_chunk
+            .as_ref()
+            .and_then(|chunk| chunk.response_stream_info.as_ref())
+            .map(|info| info.chunk_index)
+    }
+
+    // 已收到的数据块数，包括已经增量转发的数据块
+    pub fn received_chunks(&self) -> usize {
+        self.chunks.len() + self.next_index as usize
+    }
+
+    // 流中断时通知等待方：第一个数据块尚未发出时通过响应通道，否则通过数据块通道
+    pub fn fail(self, message: String) -> bool {
+        match self.response_sender {
+            Some(sender) => sender.send(Err(message)).is_ok(),
+            None => self
+                .chunk_sender
+                .is_some_and(|sender| sender.send(Err(message)).is_ok()),
+        }
+    }
+
+    // 数据块缓存后推进连续到齐的前缀，每个序号只推进一次
+    pub fn advance_contiguous(&mut self) {
+        while self.chunks.contains_key(&self.contiguous) {
+            self.contiguous += 1;
+        }
+    }
+
+    // 已收到最后一个数据块，且 0..=final 的数据块全部到齐
+    pub fn is_complete(&self) -> bool {
+        self.final_chunk_index()
+            .is_some_and(|final_index| self.contiguous > final_index)
+    }
 }
 
 // 反向连接管理器配置
@@ -29,6 +167,84 @@ pub struct ReverseConnectionConfig {
     pub request_timeout: Duration,
     pub cleanup_interval: Duration,
     pub max_pending_requests: usize,
+    // 流式响应两个数据块之间允许的最长间隔
+    pub stream_idle_timeout: Duration,
+    // 每个反向连接待发送消息队列的容量
+    pub request_channel_capacity: usize,
+    // 按服务覆盖的请求超时，键为完整服务名
+    pub service_timeouts: HashMap<String, Duration>,
+    // 请求/响应体大小上限（字节）
+    pub max_body_size: usize,
+    // 按服务覆盖的消息体大小上限，键为完整服务名
+    pub service_max_body_sizes: HashMap<String, usize>,
+    // 服务间请求解析服务名时是否使用完整服务名，与路由器保持一致
+    pub use_full_service_name: bool,
+    // 是否用 gzip 压缩转发给微服务的请求消息
+    pub gzip_payload: bool,
+    // 小于该大小的消息不压缩
+    pub gzip_min_size: usize,
+    // 会话亲和请求头（小写），请求携带该头时按其值一致性哈希选择连接
+    pub affinity_header: Option<String>,
+    // 实例选择粘滞窗口，窗口内同一客户端的请求优先选择上次选中的连接
+    pub stickiness_window: Option<Duration>,
+    // 标识客户端的请求头（小写），网关入口请求未识别出客户端时使用
+    pub stickiness_header: Option<String>,
+    // 网关主动发送 Ping 的间隔，为零时不发送
+    pub ping_interval: Duration,
+    // 慢请求阈值，微服务之间的请求耗时超过该值时输出 WARN 日志
+    pub slow_request_threshold: Option<Duration>,
+    // 响应大小上限（字节），为零时只受 max_body_size 约束
+    pub max_response_size: usize,
+    // 服务别名与是否改写转发路径，与路由器保持一致
+    pub service_aliases: HashMap<String, String>,
+    pub rewrite_aliased_path: bool,
+    // 连接最长存活时间，超过后由清理任务轮换
+    pub max_connection_age: Option<Duration>,
+    // 启动时的负载均衡策略，运行中可通过 set_load_balance_strategy 替换
+    pub load_balance_strategy: LoadBalanceStrategy,
+    // 连接表、服务池与注册表一致性校对的间隔，为 None 时不启动校对任务
+    pub reconcile_interval: Option<Duration>,
+    // 单个连接上同时进行的请求数上限，为 None 时不限制
+    pub max_in_flight_per_connection: Option<usize>,
+    // 连接全部达到并发上限时排队等待名额的最长时间，为 None 时不排队
+    pub saturation_queue_wait: Option<Duration>,
+    // 每个服务同时排队的请求数上限
+    pub saturation_queue_capacity: usize,
+}
+
+impl ReverseConnectionConfig {
+    // 获取指定服务的请求超时，未单独配置时使用全局超时
+    pub fn request_timeout_for(&self, service_name: &str) -> Duration {
+        self.service_timeouts
+            .get(service_name)
+            .copied()
+            .unwrap_or(self.request_timeout)
+    }
+
+    // 获取指定服务的消息体大小上限，未单独配置时使用全局上限
+    pub fn max_body_size_for(&self, service_name: &str) -> usize {
+        self.service_max_body_sizes
+            .get(service_name)
+            .copied()
+            .unwrap_or(self.max_body_size)
+    }
+
+    // 实际使用的 Ping 间隔，不超过心跳超时的三分之一，保证超时前至少有两次探测机会
+    pub fn effective_ping_interval(&self) -> Option<Duration> {
+        if self.ping_interval.is_zero() {
+            return None;
+        }
+        Some(self.ping_interval.min(self.heartbeat_timeout / 3))
+            .filter(|interval| !interval.is_zero())
+    }
+
+    // 所有请求中最长的超时，用于清理等待中的请求
+    pub fn max_request_timeout(&self) -> Duration {
+        self.service_timeouts
+            .values()
+            .copied()
+            .fold(self.request_timeout, Duration::max)
+    }
 }
 
 impl Default for ReverseConnectionConfig {
@@ -38,13 +254,153 @@ impl Default for ReverseConnectionConfig {
             request_timeout: Duration::from_secs(30),
             cleanup_interval: Duration::from_secs(60),
             max_pending_requests: 1000,
+            stream_idle_timeout: Duration::from_secs(30),
+            request_channel_capacity: 1024,
+            service_timeouts: HashMap::new(),
+            max_body_size: 100 * 1024 * 1024, // 100MB
+            service_max_body_sizes: HashMap::new(),
+            use_full_service_name: false,
+            gzip_payload: false,
+            gzip_min_size: 1024,
+            affinity_header: None,
+            stickiness_window: None,
+            stickiness_header: None,
+            ping_interval: Duration::from_secs(30),
+            slow_request_threshold: Some(Duration::from_secs(1)),
+            max_response_size: 0,
+            service_aliases: HashMap::new(),
+            rewrite_aliased_path: true,
+            max_connection_age: None,
+            load_balance_strategy: LoadBalanceStrategy::default(),
+            reconcile_interval: Some(Duration::from_secs(300)),
+            max_in_flight_per_connection: None,
+            saturation_queue_wait: None,
+            saturation_queue_capacity: 100,
         }
     }
 }
 
+// 反向转发请求失败的原因
+#[derive(Debug, thiserror::Error)]
+pub enum ReverseRequestError {
+    #[error("Request body too large: {size} bytes (max: {limit} bytes)")]
+    BodyTooLarge { size: usize, limit: usize },
+    #[error(
+        "Connection request queue is full ({capacity} messages), microservice is not keeping up"
+    )]
+    QueueFull { capacity: usize },
+    #[error(
+        "All reverse connections for the service are at their in-flight request limit ({limit})"
+    )]
+    ConnectionsSaturated { limit: usize },
+    #[error("Request timeout")]
+    Timeout,
+    // 读取或压缩请求体失败
+    #[error("{0}")]
+    Body(String),
+    #[error("{0}")]
+    Failed(String),
+}
+
+// ForwardResponseBuilder 拒绝的字段组合
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ForwardResponseError {
+    #[error("Invalid status code: {0}")]
+    InvalidStatusCode(i32),
+    #[error("Error response requires a non-empty error message")]
+    MissingErrorMessage,
+    #[error("Response with error message has a success status code: {0}")]
+    ErrorWithSuccessStatus(i32),
+    #[error("Response stream info must be marked as streamed")]
+    NotStreamed,
+    #[error("Invalid chunk index: {0}")]
+    InvalidChunkIndex(i64),
+    #[error("Chunk size {chunk_size} does not match payload size {payload_size}")]
+    ChunkSizeMismatch {
+        chunk_size: i32,
+        payload_size: usize,
+    },
+}
+
+impl From<String> for ReverseRequestError {
+    fn from(message: String) -> Self {
+        ReverseRequestError::Failed(message)
+    }
+}
+
+// 反向转发请求计数
+#[derive(Debug, Default)]
+pub struct RequestCounters {
+    // 已发送给微服务的请求
+    pub forwarded: AtomicU64,
+    // 微服务未在超时时间内响应的请求
+    pub timed_out: AtomicU64,
+    // 发送失败或被网关放弃的请求
+    pub failed: AtomicU64,
+}
+
+impl RequestCounters {
+    pub(crate) fn incr(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// 一致性校对累计修复的数量
+#[derive(Debug, Default)]
+pub struct RepairCounters {
+    // 从服务池中摘除的、连接表中已不存在的连接
+    pub detached_pool_connections: AtomicU64,
+    // 从注册表中移除的、没有存活反向连接的实例
+    pub removed_registry_instances: AtomicU64,
+}
+
+impl RepairCounters {
+    pub(crate) fn add(counter: &AtomicU64, value: usize) {
+        counter.fetch_add(value as u64, Ordering::Relaxed);
+    }
+}
+
+// 单次一致性校对的修复结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReconcileReport {
+    pub detached_pool_connections: usize,
+    pub removed_registry_instances: usize,
+}
+
+impl ReconcileReport {
+    // 本次校对没有发现不一致
+    pub fn is_empty(&self) -> bool {
+        self.detached_pool_connections == 0 && self.removed_registry_instances == 0
+    }
+}
+
 // 连接统计信息
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ConnectionStats {
     pub active_connections: usize,
+    // 存在服务池的服务数
     pub registered_services: usize,
+    // 所有服务池中的连接数，一个连接提供多个服务时分别计数
+    pub pooled_connections: usize,
+    pub pending_requests: usize,
+    pub forwarded_requests: u64,
+    pub timed_out_requests: u64,
+    pub failed_requests: u64,
+    // 一致性校对累计修复的服务池连接和注册表实例
+    pub detached_pool_connections: u64,
+    pub removed_registry_instances: u64,
+}
+
+// 反向连接的诊断信息，不包含发送端
+#[derive(Debug, Clone)]
+pub struct ReverseConnectionInfo {
+    pub connection_id: String,
+    // 建立连接时登记的服务
+    pub services: Vec<String>,
+    pub created_at: Instant,
+    // 距最后一次心跳的时间
+    pub last_heartbeat_age: Duration,
+    pub weight: u32,
+    // 服务池中实际包含该连接的服务，与 services 不一致说明映射已失去同步
+    pub pooled_services: Vec<String>,
 }
@@ -1,16 +1,46 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::pin::Pin;
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::task::{Context, Poll};
+use std::time::{Instant, SystemTime};
 
 use dashmap::DashMap;
 use tokio::sync::broadcast;
 use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
 use tokio_stream::{Stream, StreamExt};
 use tonic::Status;
 use uuid::Uuid;
 
-use super::types::{EventConfig, EventError, EventStats, SubscriberInfo};
+use super::reliable::ReliableQueues;
+use super::types::{EventConfig, EventError, EventFilter, EventStats, SubscriberInfo};
 use crate::registry::{EventMessage, SubscriptionRequest};
 
+/// 历史缓冲中的事件及其入队时间
+#[derive(Debug, Clone)]
+struct BufferedEvent {
+    event: EventMessage,
+    buffered_at: Instant,
+}
+
+/// 订阅流，持有订阅的存活标记，流被丢弃时标记随之失效
+struct SubscriptionStream<S> {
+    inner: Pin<Box<S>>,
+    _alive: Arc<()>,
+}
+
+impl<S: Stream> Stream for SubscriptionStream<S> {
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
 /// 基于 Tokio broadcast 的事件总线
 #[derive(Debug)]
 pub struct EventBus {
@@ -18,6 +48,10 @@ pub struct EventBus {
     channels: Arc<DashMap<String, broadcast::Sender<EventMessage>>>,
     /// 订阅者信息 (订阅者ID -> 订阅信息)
     subscribers: Arc<DashMap<String, SubscriberInfo>>,
+    /// 事件历史 (事件类型 -> 有界环形缓冲)
+    history: Arc<DashMap<String, VecDeque<BufferedEvent>>>,
+    /// 可靠投递订阅者的队列
+    reliable: ReliableQueues,
     /// 事件统计
     stats: Arc<std::sync::Mutex<EventStats>>,
     /// 配置
@@ -30,13 +64,20 @@ impl EventBus {
         Self {
             channels: Arc::new(DashMap::new()),
             subscribers: Arc::new(DashMap::new()),
+            history: Arc::new(DashMap::new()),
+            reliable: ReliableQueues::new(config.reliable_queue_capacity),
             stats: Arc::new(std::sync::Mutex::new(EventStats::default())),
             config,
         }
     }
 
     /// 发布事件到指定事件类型的所有订阅者
-    pub async fn publish_event(&self, mut event: EventMessage) -> Result<usize, EventError> {
+    pub async fn publish_event(&self, event: EventMessage) -> Result<usize, EventError> {
+        self.publish_event_sync(event)
+    }
+
+    /// 同步发布事件，供不在异步上下文中的调用方使用
+    pub fn publish_event_sync(&self, mut event: EventMessage) -> Result<usize, EventError> {
         // 验证事件类型
         if event.event_type.is_empty() {
             return Err(EventError::InvalidEventType {
@@ -60,15 +101,46 @@ impl EventBus {
         // 获取或创建该事件类型的广播通道
         let sender = self.get_or_create_channel(&event.event_type);
 
+        // 写入历史与广播在同一把锁内完成，保证回放订阅者不会漏掉或重复事件
+        let send_result = match self.history_capacity() {
+            Some(capacity) => {
+                let mut history = self.history.entry(event.event_type.clone()).or_default();
+                self.prune_history(&mut history);
+                history.push_back(BufferedEvent {
+                    event: event.clone(),
+                    buffered_at: Instant::now(),
+                });
+                while history.len() > capacity {
+                    history.pop_front();
+                }
+                sender.send(event.clone())
+            }
+            None => sender.send(event.clone()),
+        };
+
+        // 可靠订阅者不经过广播通道，事件放入各自的队列
+        let reliable = self.reliable.enqueue(&event);
+        if reliable.overflowed > 0
+            && self.config.enable_metrics
+            && let Ok(mut stats) = self.stats.lock()
+        {
+            stats.delivery_failures += reliable.overflowed as u64;
+        }
+        let send_result = match send_result {
+            Ok(count) => Ok(count + reliable.subscribers),
+            Err(_) if reliable.subscribers > 0 => Ok(reliable.subscribers),
+            Err(e) => Err(e),
+        };
+
         // 发送事件，返回订阅者数量
-        match sender.send(event.clone()) {
+        match send_result {
             Ok(subscriber_count) => {
                 // 更新统计信息
-                if self.config.enable_metrics {
-                    if let Ok(mut stats) = self.stats.lock() {
-                        stats.events_published += 1;
-                        stats.events_delivered += subscriber_count as u64;
-                    }
+                if self.config.enable_metrics
+                    && let Ok(mut stats) = self.stats.lock()
+                {
+                    stats.events_published += 1;
+                    stats.events_delivered += subscriber_count as u64;
                 }
 
                 tracing::debug!(
@@ -100,6 +172,29 @@ impl EventBus {
         &self,
         event_type: &str,
         subscriber_id: &str,
+    ) -> Result<impl Stream<Item = Result<EventMessage, Status>>, EventError> {
+        self.subscribe_with_replay(event_type, subscriber_id, false)
+    }
+
+    /// 订阅指定事件类型，`replay` 为 true 时先回放历史缓冲中的事件再接收实时事件
+    pub fn subscribe_with_replay(
+        &self,
+        event_type: &str,
+        subscriber_id: &str,
+        replay: bool,
+    ) -> Result<impl Stream<Item = Result<EventMessage, Status>>, EventError> {
+        self.subscribe_with_filter(event_type, subscriber_id, replay, EventFilter::default())
+    }
+
+    /// 订阅指定事件类型，只接收满足 `filter` 的事件（包括回放的历史事件）
+    ///
+    /// 广播通道把事件发给该类型的所有订阅流，过滤在每个订阅流内进行
+    pub fn subscribe_with_filter(
+        &self,
+        event_type: &str,
+        subscriber_id: &str,
+        replay: bool,
+        filter: EventFilter,
     ) -> Result<impl Stream<Item = Result<EventMessage, Status>>, EventError> {
         // 检查订阅者限制
         let current_subscriber_count = self.get_subscriber_count_for_type(event_type);
@@ -111,48 +206,127 @@ impl EventBus {
 
         // 获取或创建广播通道
         let sender = self.get_or_create_channel(event_type);
-        let receiver = sender.subscribe();
 
-        // 更新订阅者信息
+        // 订阅与读取历史快照在同一把锁内完成，与发布端互斥
+        let (receiver, replayed) = match (replay, self.history_capacity()) {
+            (true, Some(_)) => {
+                let mut history = self.history.entry(event_type.to_string()).or_default();
+                self.prune_history(&mut history);
+                let receiver = sender.subscribe();
+                let replayed: Vec<EventMessage> = history
+                    .iter()
+                    .filter(|buffered| filter.matches(&buffered.event))
+                    .map(|buffered| buffered.event.clone())
+                    .collect();
+                (receiver, replayed)
+            }
+            _ => (sender.subscribe(), Vec::new()),
+        };
+
+        // 更新订阅者信息，清理任务通过存活标记发现已被丢弃的订阅流
         self.update_subscriber_info(subscriber_id, event_type);
+        let alive = Arc::new(());
+        if let Some(mut info) = self.subscribers.get_mut(subscriber_id) {
+            info.streams
+                .entry(event_type.to_string())
+                .or_default()
+                .push(Arc::downgrade(&alive));
+        }
 
         // 更新统计信息
-        if self.config.enable_metrics {
-            if let Ok(mut stats) = self.stats.lock() {
-                stats.total_subscribers += 1;
-            }
+        if self.config.enable_metrics
+            && let Ok(mut stats) = self.stats.lock()
+        {
+            stats.total_subscribers += 1;
         }
 
         tracing::info!(
             event_type = %event_type,
             subscriber_id = %subscriber_id,
+            replayed_events = replayed.len(),
+            metadata_filter = ?filter.metadata,
             "New subscription created"
         );
 
-        // 返回转换后的流
-        Ok(BroadcastStream::new(receiver).map(|result| {
-            result.map_err(|_err| {
-                // BroadcastStreamRecvError 不提供错误详细信息，使用通用错误
-                Status::internal("Event stream error")
-            })
-        }))
+        // 返回转换后的流，回放的历史事件排在实时事件之前
+        let stats = self.stats.clone();
+        let enable_metrics = self.config.enable_metrics;
+        let skip_lagged = self.config.skip_lagged_events;
+        let event_type = event_type.to_string();
+        let subscriber_id = subscriber_id.to_string();
+        let live = BroadcastStream::new(receiver).filter_map(move |result| match result {
+            Ok(event) if filter.matches(&event) => Some(Ok(event)),
+            Ok(_) => None,
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                // 订阅者消费过慢，通道中最早的事件已被覆盖
+                if enable_metrics && let Ok(mut stats) = stats.lock() {
+                    stats.events_lagged += skipped;
+                    stats.delivery_failures += skipped;
+                }
+                tracing::warn!(
+                    event_type = %event_type,
+                    subscriber_id = %subscriber_id,
+                    skipped_events = skipped,
+                    skip_lagged,
+                    "Event subscriber lagged behind"
+                );
+                if skip_lagged {
+                    None
+                } else {
+                    Some(Err(Status::resource_exhausted(format!(
+                        "Event subscriber lagged behind by {skipped} events"
+                    ))))
+                }
+            }
+        });
+        Ok(SubscriptionStream {
+            inner: Box::pin(tokio_stream::iter(replayed.into_iter().map(Ok)).chain(live)),
+            _alive: alive,
+        })
     }
 
-    /// 处理订阅请求，返回是否成功
+    /// 处理订阅请求，返回是否成功。owner 标识发出请求的调用方（如反向连接的 token subject），
+    /// 可靠订阅者的 ID 只能由首次登记它的调用方继续使用
     pub async fn handle_subscription_request(
         &self,
         request: SubscriptionRequest,
+        owner: &str,
     ) -> Result<(), EventError> {
+        if !self.reliable.is_owned_by(&request.subscriber_id, owner) {
+            return Err(self.not_owned(&request.subscriber_id));
+        }
         match request.action() {
             crate::registry::subscription_request::Action::Subscribe => {
-                // 订阅事件 - 只记录订阅信息，实际的流创建在连接处理时进行
+                // 订阅事件 - 只记录订阅信息和过滤条件，实际的流创建在连接处理时进行
+                let filter = EventFilter::metadata(request.metadata_filter.clone());
+                let reliable = request.reliable && self.config.reliable_delivery;
+                if reliable
+                    && !self.reliable.register(
+                        &request.subscriber_id,
+                        owner,
+                        &request.event_types,
+                        &filter,
+                    )
+                {
+                    return Err(self.not_owned(&request.subscriber_id));
+                }
                 for event_type in &request.event_types {
                     self.update_subscriber_info(&request.subscriber_id, event_type);
+                    self.set_subscriber_filter(&request.subscriber_id, event_type, filter.clone());
+                }
+
+                if !reliable && request.reliable {
+                    tracing::warn!(
+                        subscriber_id = %request.subscriber_id,
+                        "Reliable delivery is disabled, falling back to a regular subscription"
+                    );
                 }
 
                 tracing::info!(
                     subscriber_id = %request.subscriber_id,
                     event_types = ?request.event_types,
+                    metadata_filter = ?request.metadata_filter,
+                    reliable,
                     "Subscribed to events"
                 );
             }
@@ -181,13 +355,103 @@ impl EventBus {
         }
     }
 
+    /// 获取订阅者对指定事件类型的过滤条件，未指定时返回空条件
+    pub fn get_subscriber_filter(&self, subscriber_id: &str, event_type: &str) -> EventFilter {
+        self.subscribers
+            .get(subscriber_id)
+            .and_then(|info| info.metadata_filters.get(event_type).cloned())
+            .unwrap_or_default()
+    }
+
+    /// 是否接受可靠投递订阅
+    pub fn reliable_delivery_enabled(&self) -> bool {
+        self.config.reliable_delivery
+    }
+
+    /// 为可靠订阅者开始新的投递会话，返回的事件流先重发所有未确认的事件。
+    /// 订阅者再次调用时之前的事件流结束；取消全部可靠订阅后事件流结束
+    pub fn reliable_stream(
+        &self,
+        subscriber_id: &str,
+        owner: &str,
+    ) -> Result<impl Stream<Item = EventMessage> + use<>, EventError> {
+        if !self.config.reliable_delivery {
+            return Err(EventError::ReliableDeliveryDisabled);
+        }
+        if !self.reliable.is_owned_by(subscriber_id, owner) {
+            return Err(self.not_owned(subscriber_id));
+        }
+        let stream = self.reliable.stream(subscriber_id, owner).ok_or_else(|| {
+            EventError::NotReliableSubscriber {
+                subscriber_id: subscriber_id.to_string(),
+            }
+        })?;
+        tracing::info!(
+            subscriber_id = %subscriber_id,
+            pending_events = self.reliable.pending(subscriber_id),
+            "Started reliable event delivery"
+        );
+        Ok(stream)
+    }
+
+    /// 确认可靠订阅者已处理的事件，返回从队列中移除的数量。
+    /// 订阅者由其他调用方登记时忽略确认
+    pub fn ack_events(&self, subscriber_id: &str, owner: &str, event_ids: &[String]) -> usize {
+        if !self.reliable.is_owned_by(subscriber_id, owner) {
+            self.not_owned(subscriber_id);
+            return 0;
+        }
+        if let Some(mut info) = self.subscribers.get_mut(subscriber_id) {
+            info.last_active_at = SystemTime::now();
+        }
+        let acked = self.reliable.ack(subscriber_id, owner, event_ids);
+        if acked > 0
+            && self.config.enable_metrics
+            && let Ok(mut stats) = self.stats.lock()
+        {
+            stats.events_acked += acked as u64;
+        }
+        tracing::debug!(
+            subscriber_id = %subscriber_id,
+            requested = event_ids.len(),
+            acked,
+            "Acknowledged reliable events"
+        );
+        acked
+    }
+
+    // 拒绝使用其他调用方登记的可靠订阅者 ID
+    fn not_owned(&self, subscriber_id: &str) -> EventError {
+        tracing::warn!(
+            subscriber_id = %subscriber_id,
+            "Rejected use of a reliable subscriber registered by another caller"
+        );
+        EventError::SubscriberNotOwned {
+            subscriber_id: subscriber_id.to_string(),
+        }
+    }
+
+    /// 可靠订阅者尚未确认的事件数量
+    pub fn pending_reliable_events(&self, subscriber_id: &str) -> usize {
+        self.reliable.pending(subscriber_id)
+    }
+
     /// 取消订阅
     pub async fn unsubscribe(&self, subscriber_id: &str, event_types: &[String]) {
+        // 可靠订阅的队列随之删除，未确认的事件不再保留
+        self.reliable.unregister(subscriber_id, event_types);
+
         // 更新订阅者信息
         if let Some(mut subscriber_info) = self.subscribers.get_mut(subscriber_id) {
             subscriber_info
                 .event_types
                 .retain(|et| !event_types.contains(et));
+            subscriber_info
+                .metadata_filters
+                .retain(|et, _| !event_types.contains(et));
+            subscriber_info
+                .streams
+                .retain(|et, _| !event_types.contains(et));
 
             if subscriber_info.event_types.is_empty() {
                 drop(subscriber_info);
@@ -196,23 +460,23 @@ impl EventBus {
         }
 
         // 更新统计信息
-        if self.config.enable_metrics {
-            if let Ok(mut stats) = self.stats.lock() {
-                stats.total_subscribers = stats.total_subscribers.saturating_sub(event_types.len());
-            }
+        if self.config.enable_metrics
+            && let Ok(mut stats) = self.stats.lock()
+        {
+            stats.total_subscribers = stats.total_subscribers.saturating_sub(event_types.len());
         }
     }
 
-    /// 移除订阅者的所有订阅
+    /// 移除订阅者的所有订阅。可靠订阅者的队列保留，重新订阅后继续投递未确认的事件
     pub async fn remove_subscriber(&self, subscriber_id: &str) {
         if let Some((_, subscriber_info)) = self.subscribers.remove(subscriber_id) {
             let event_count = subscriber_info.event_types.len();
 
             // 更新统计信息
-            if self.config.enable_metrics {
-                if let Ok(mut stats) = self.stats.lock() {
-                    stats.total_subscribers = stats.total_subscribers.saturating_sub(event_count);
-                }
+            if self.config.enable_metrics
+                && let Ok(mut stats) = self.stats.lock()
+            {
+                stats.total_subscribers = stats.total_subscribers.saturating_sub(event_count);
             }
 
             tracing::info!(
@@ -242,6 +506,42 @@ impl EventBus {
             .collect()
     }
 
+    /// 历史缓冲容量，未配置或为 0 时不保留历史
+    fn history_capacity(&self) -> Option<usize> {
+        self.config
+            .max_event_history
+            .filter(|&capacity| capacity > 0)
+    }
+
+    /// 丢弃超过 TTL 的历史事件
+    fn prune_history(&self, history: &mut VecDeque<BufferedEvent>) {
+        let Some(ttl) = self.config.event_ttl() else {
+            return;
+        };
+        while history
+            .front()
+            .is_some_and(|buffered| buffered.buffered_at.elapsed() > ttl)
+        {
+            history.pop_front();
+        }
+    }
+
+    /// 每个事件类型的订阅者数量，按事件类型排序
+    pub fn subscriber_counts(&self) -> BTreeMap<String, usize> {
+        let event_types: BTreeSet<String> = self
+            .subscribers
+            .iter()
+            .flat_map(|entry| entry.value().event_types.clone())
+            .collect();
+        event_types
+            .into_iter()
+            .map(|event_type| {
+                let count = self.get_subscriber_count_for_type(&event_type);
+                (event_type, count)
+            })
+            .collect()
+    }
+
     /// 获取指定事件类型的订阅者数量
     fn get_subscriber_count_for_type(&self, event_type: &str) -> usize {
         self.subscribers
@@ -255,19 +555,21 @@ impl EventBus {
         if let Some(sender) = self.channels.get(event_type) {
             sender.clone()
         } else {
-            let (sender, _) = broadcast::channel(self.config.channel_capacity);
+            // 容量在创建时确定，之后修改配置需要重建通道
+            let capacity = self.config.channel_capacity_for(event_type);
+            let (sender, _) = broadcast::channel(capacity);
             self.channels.insert(event_type.to_string(), sender.clone());
 
             // 更新统计信息
-            if self.config.enable_metrics {
-                if let Ok(mut stats) = self.stats.lock() {
-                    stats.active_event_types = self.channels.len();
-                }
+            if self.config.enable_metrics
+                && let Ok(mut stats) = self.stats.lock()
+            {
+                stats.active_event_types = self.channels.len();
             }
 
             tracing::debug!(
                 event_type = %event_type,
-                capacity = %self.config.channel_capacity,
+                capacity = %capacity,
                 "Created new broadcast channel for event type"
             );
 
@@ -283,15 +585,104 @@ impl EventBus {
                 if !info.event_types.contains(&event_type.to_string()) {
                     info.event_types.push(event_type.to_string());
                 }
+                info.last_active_at = SystemTime::now();
             })
             .or_insert_with(|| SubscriberInfo {
                 subscriber_id: subscriber_id.to_string(),
                 event_types: vec![event_type.to_string()],
                 subscribed_at: SystemTime::now(),
+                last_active_at: SystemTime::now(),
                 events_received: 0,
+                metadata_filters: HashMap::new(),
+                streams: HashMap::new(),
             });
     }
 
+    /// 记录订阅者对事件类型的过滤条件，空条件表示接收所有事件
+    fn set_subscriber_filter(&self, subscriber_id: &str, event_type: &str, filter: EventFilter) {
+        if let Some(mut info) = self.subscribers.get_mut(subscriber_id) {
+            if filter.is_empty() {
+                info.metadata_filters.remove(event_type);
+            } else {
+                info.metadata_filters.insert(event_type.to_string(), filter);
+            }
+        }
+    }
+
+    /// 清理失效的订阅，释放其占用的订阅名额，返回清理的订阅数量（按事件类型计）：
+    /// 订阅流全部被丢弃的事件类型从订阅者中移除；没有存活订阅流、
+    /// 且超过 `subscriber_idle_ttl_seconds` 没有活动的订阅者整体移除。
+    /// 与 `remove_subscriber` 相同，可靠订阅者的队列保留
+    pub async fn evict_stale_subscribers(&self) -> usize {
+        let idle_ttl = self.config.subscriber_idle_ttl();
+        let is_idle = |info: &SubscriberInfo| {
+            info.streams.is_empty()
+                && idle_ttl.is_some_and(|ttl| {
+                    info.last_active_at
+                        .elapsed()
+                        .is_ok_and(|elapsed| elapsed > ttl)
+                })
+        };
+
+        let mut evicted = 0;
+        let mut to_remove = Vec::new();
+        for mut entry in self.subscribers.iter_mut() {
+            let info = entry.value_mut();
+            for streams in info.streams.values_mut() {
+                streams.retain(|alive| alive.strong_count() > 0);
+            }
+            // 所有订阅流都已被丢弃的事件类型
+            let dropped: Vec<String> = info
+                .streams
+                .iter()
+                .filter(|(_, streams)| streams.is_empty())
+                .map(|(event_type, _)| event_type.clone())
+                .collect();
+            for event_type in &dropped {
+                info.streams.remove(event_type);
+                info.metadata_filters.remove(event_type);
+                info.event_types.retain(|et| et != event_type);
+            }
+            evicted += dropped.len();
+            if !dropped.is_empty() {
+                tracing::info!(
+                    subscriber_id = %info.subscriber_id,
+                    event_types = ?dropped,
+                    "Evicted subscriptions whose event streams were dropped"
+                );
+            }
+
+            if info.event_types.is_empty() || is_idle(info) {
+                to_remove.push(entry.key().clone());
+            }
+        }
+
+        for subscriber_id in to_remove {
+            let Some((_, info)) = self.subscribers.remove_if(&subscriber_id, |_, info| {
+                info.event_types.is_empty() || is_idle(info)
+            }) else {
+                continue;
+            };
+            if !info.event_types.is_empty() {
+                evicted += info.event_types.len();
+                tracing::info!(
+                    subscriber_id = %subscriber_id,
+                    event_types = ?info.event_types,
+                    "Evicted idle event subscriber"
+                );
+            }
+        }
+
+        if evicted > 0
+            && self.config.enable_metrics
+            && let Ok(mut stats) = self.stats.lock()
+        {
+            stats.total_subscribers = stats.total_subscribers.saturating_sub(evicted);
+            stats.subscriptions_evicted += evicted as u64;
+        }
+        evicted
+    }
+
     /// 清理不活跃的通道
     pub async fn cleanup_inactive_channels(&self) {
         let mut to_remove = Vec::new();
@@ -321,6 +712,8 @@ impl Clone for EventBus {
         Self {
             channels: self.channels.clone(),
             subscribers: self.subscribers.clone(),
+            history: self.history.clone(),
+            reliable: self.reliable.clone(),
             stats: self.stats.clone(),
             config: self.config.clone(),
         }
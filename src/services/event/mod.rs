@@ -1,5 +1,7 @@
 pub mod event_bus;
+pub mod reliable;
 pub mod types;
 
 pub use event_bus::EventBus;
+pub use reliable::ReliableQueues;
 pub use types::*;
@@ -0,0 +1,217 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use tokio::sync::watch;
+use tokio_stream::Stream;
+
+use super::types::EventFilter;
+use crate::registry::EventMessage;
+
+/// 等待确认的事件，记录最近一次投递所在的会话
+#[derive(Debug)]
+struct PendingEvent {
+    event: EventMessage,
+    delivered_in: Option<u64>,
+}
+
+/// 单个可靠订阅者的队列，断开期间保留，直到取消全部订阅
+#[derive(Debug)]
+struct ReliableQueue {
+    /// 首次登记该订阅者的调用方，只有它可以续订、确认或取消
+    owner: String,
+    /// 事件类型 -> 元数据过滤条件
+    filters: HashMap<String, EventFilter>,
+    /// 尚未确认的事件，按发布顺序排列
+    pending: VecDeque<PendingEvent>,
+    /// 当前投递会话，每次重新订阅加一，旧会话的事件流随之结束
+    session: u64,
+    /// 队列或会话变化时通知事件流
+    changed: watch::Sender<()>,
+}
+
+impl ReliableQueue {
+    fn new(owner: &str) -> Self {
+        Self {
+            owner: owner.to_string(),
+            filters: HashMap::new(),
+            pending: VecDeque::new(),
+            session: 0,
+            changed: watch::Sender::new(()),
+        }
+    }
+}
+
+/// 入队结果
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EnqueueOutcome {
+    /// 接收该事件的可靠订阅者数量
+    pub subscribers: usize,
+    /// 因队列已满被丢弃的最早未确认事件数量
+    pub overflowed: usize,
+}
+
+/// 可靠投递的订阅者队列：每个订阅者一个有界队列，事件在确认前一直保留，
+/// 新的投递会话从最早的未确认事件开始重新发送
+#[derive(Debug, Clone)]
+pub struct ReliableQueues {
+    queues: Arc<DashMap<String, ReliableQueue>>,
+    capacity: usize,
+}
+
+impl ReliableQueues {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            queues: Arc::new(DashMap::new()),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// 订阅者 ID 是否可以由 owner 使用：尚未登记或由 owner 登记时返回 true
+    pub fn is_owned_by(&self, subscriber_id: &str, owner: &str) -> bool {
+        self.queues
+            .get(subscriber_id)
+            .is_none_or(|queue| queue.owner == owner)
+    }
+
+    /// 登记订阅者对事件类型的可靠订阅，同一事件类型以最新的过滤条件为准。
+    /// 订阅者已由其他调用方登记时不做修改并返回 false
+    pub fn register(
+        &self,
+        subscriber_id: &str,
+        owner: &str,
+        event_types: &[String],
+        filter: &EventFilter,
+    ) -> bool {
+        let mut queue = self
+            .queues
+            .entry(subscriber_id.to_string())
+            .or_insert_with(|| ReliableQueue::new(owner));
+        if queue.owner != owner {
+            return false;
+        }
+        for event_type in event_types {
+            queue.filters.insert(event_type.clone(), filter.clone());
+        }
+        true
+    }
+
+    /// 取消事件类型的可靠订阅，没有剩余订阅时删除队列并结束事件流
+    pub fn unregister(&self, subscriber_id: &str, event_types: &[String]) {
+        let removed = self
+            .queues
+            .remove_if_mut(subscriber_id, |_, queue| {
+                queue
+                    .filters
+                    .retain(|event_type, _| !event_types.contains(event_type));
+                queue.filters.is_empty()
+            })
+            .is_some();
+        if removed {
+            tracing::info!(subscriber_id = %subscriber_id, "Removed reliable event queue");
+        }
+    }
+
+    /// 把事件放入所有匹配的订阅者队列，队列已满时丢弃最早的未确认事件
+    pub fn enqueue(&self, event: &EventMessage) -> EnqueueOutcome {
+        let mut outcome = EnqueueOutcome::default();
+        for mut entry in self.queues.iter_mut() {
+            let subscriber_id = entry.key().clone();
+            let queue = entry.value_mut();
+            if !queue
+                .filters
+                .get(&event.event_type)
+                .is_some_and(|filter| filter.matches(event))
+            {
+                continue;
+            }
+
+            if queue.pending.len() >= self.capacity {
+                queue.pending.pop_front();
+                outcome.overflowed += 1;
+                tracing::warn!(
+                    subscriber_id = %subscriber_id,
+                    capacity = self.capacity,
+                    "Reliable event queue full, dropped the oldest unacknowledged event"
+                );
+            }
+            queue.pending.push_back(PendingEvent {
+                event: event.clone(),
+                delivered_in: None,
+            });
+            queue.changed.send_replace(());
+            outcome.subscribers += 1;
+        }
+        outcome
+    }
+
+    /// 确认事件，返回从队列中移除的数量；只接受登记该订阅者的调用方的确认
+    pub fn ack(&self, subscriber_id: &str, owner: &str, event_ids: &[String]) -> usize {
+        let Some(mut queue) = self
+            .queues
+            .get_mut(subscriber_id)
+            .filter(|queue| queue.owner == owner)
+        else {
+            return 0;
+        };
+        let before = queue.pending.len();
+        queue
+            .pending
+            .retain(|pending| !event_ids.contains(&pending.event.event_id));
+        before - queue.pending.len()
+    }
+
+    /// 订阅者尚未确认的事件数量
+    pub fn pending(&self, subscriber_id: &str) -> usize {
+        self.queues
+            .get(subscriber_id)
+            .map(|queue| queue.pending.len())
+            .unwrap_or(0)
+    }
+
+    /// 开始新的投递会话：先重发所有未确认的事件，再按发布顺序投递新事件。
+    /// 同一订阅者之前的事件流在新会话开始后结束。订阅者被删除或不属于 owner 时返回 None
+    pub fn stream(
+        &self,
+        subscriber_id: &str,
+        owner: &str,
+    ) -> Option<impl Stream<Item = EventMessage> + use<>> {
+        let (session, changed) = {
+            let mut queue = self
+                .queues
+                .get_mut(subscriber_id)
+                .filter(|queue| queue.owner == owner)?;
+            queue.session += 1;
+            queue.changed.send_replace(());
+            (queue.session, queue.changed.subscribe())
+        };
+
+        let state = (self.queues.clone(), subscriber_id.to_string(), changed);
+        Some(futures::stream::unfold(
+            state,
+            move |(queues, subscriber_id, mut changed)| async move {
+                loop {
+                    // 先标记已读再检查队列，检查之后的入队一定会唤醒下面的等待
+                    changed.borrow_and_update();
+                    {
+                        let mut queue = queues.get_mut(&subscriber_id)?;
+                        if queue.session != session {
+                            return None;
+                        }
+                        if let Some(pending) = queue
+                            .pending
+                            .iter_mut()
+                            .find(|pending| pending.delivered_in != Some(session))
+                        {
+                            pending.delivered_in = Some(session);
+                            let event = pending.event.clone();
+                            drop(queue);
+                            return Some((event, (queues, subscriber_id, changed)));
+                        }
+                    }
+                    changed.changed().await.ok()?;
+                }
+            },
+        ))
+    }
+}
@@ -1,20 +1,37 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Weak;
 use std::time::Duration;
 use thiserror::Error;
 
-/// 事件总线配置
+use crate::registry::EventMessage;
+
+/// 事件总线配置，缺省的字段使用默认值
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct EventConfig {
     /// 每个事件类型的最大订阅者数量
     pub max_subscribers_per_type: usize,
     /// 广播通道容量
     pub channel_capacity: usize,
+    /// 按事件类型覆盖广播通道容量，未列出的类型使用 `channel_capacity`。
+    /// 容量只在创建通道时读取，通道已存在后修改不会生效
+    pub event_type_capacities: HashMap<String, usize>,
     /// 事件历史保留大小 (可选功能)
     pub max_event_history: Option<usize>,
     /// 事件 TTL 秒数 (可选功能)
     pub event_ttl_seconds: Option<u64>,
     /// 是否启用事件统计
     pub enable_metrics: bool,
+    /// 订阅者落后超过通道容量时跳过丢失的事件继续接收，否则以错误结束订阅流
+    pub skip_lagged_events: bool,
+    /// 是否接受可靠投递订阅（至少一次，需要订阅者确认），关闭时可靠订阅按普通订阅处理
+    pub reliable_delivery: bool,
+    /// 每个可靠订阅者最多保留的未确认事件数，超出时丢弃最早的事件
+    pub reliable_queue_capacity: usize,
+    /// 没有存活订阅流的订阅者超过该秒数没有活动（订阅、确认）时被清理，
+    /// 未设置时只清理订阅流已被丢弃的订阅
+    pub subscriber_idle_ttl_seconds: Option<u64>,
 }
 
 impl EventConfig {
@@ -22,6 +39,20 @@ impl EventConfig {
     pub fn event_ttl(&self) -> Option<Duration> {
         self.event_ttl_seconds.map(Duration::from_secs)
     }
+
+    /// 获取订阅者空闲清理时长
+    pub fn subscriber_idle_ttl(&self) -> Option<Duration> {
+        self.subscriber_idle_ttl_seconds.map(Duration::from_secs)
+    }
+
+    /// 获取事件类型的广播通道容量
+    pub fn channel_capacity_for(&self, event_type: &str) -> usize {
+        self.event_type_capacities
+            .get(event_type)
+            .copied()
+            .unwrap_or(self.channel_capacity)
+            .max(1)
+    }
 }
 
 impl Default for EventConfig {
@@ -29,9 +60,14 @@ impl Default for EventConfig {
         Self {
             max_subscribers_per_type: 1000,
             channel_capacity: 1024,
+            event_type_capacities: HashMap::new(),
             max_event_history: None,
             event_ttl_seconds: None,
             enable_metrics: true,
+            skip_lagged_events: false,
+            reliable_delivery: false,
+            reliable_queue_capacity: 1024,
+            subscriber_idle_ttl_seconds: None,
         }
     }
 }
@@ -51,6 +87,15 @@ pub enum EventError {
     #[error("Subscriber limit exceeded for event type: {event_type}")]
     SubscriberLimitExceeded { event_type: String },
 
+    #[error("Reliable delivery is disabled")]
+    ReliableDeliveryDisabled,
+
+    #[error("No reliable subscription for subscriber: {subscriber_id}")]
+    NotReliableSubscriber { subscriber_id: String },
+
+    #[error("Subscriber {subscriber_id} is registered by another caller")]
+    SubscriberNotOwned { subscriber_id: String },
+
     #[error("Broadcast receiver error: {0}")]
     BroadcastError(#[from] tokio::sync::broadcast::error::RecvError),
 
@@ -69,8 +114,14 @@ pub struct EventStats {
     pub events_published: u64,
     /// 已投递的事件总数
     pub events_delivered: u64,
-    /// 失败的投递数量
+    /// 失败的投递数量（包括订阅者落后而丢失的事件）
     pub delivery_failures: u64,
+    /// 订阅者落后于通道容量而丢失的事件数量
+    pub events_lagged: u64,
+    /// 可靠订阅者确认的事件数量
+    pub events_acked: u64,
+    /// 因订阅流被丢弃或长时间不活动而清理的订阅数量（按事件类型计）
+    pub subscriptions_evicted: u64,
 }
 
 /// 订阅者信息
@@ -82,6 +133,50 @@ pub struct SubscriberInfo {
     pub event_types: Vec<String>,
     /// 订阅时间
     pub subscribed_at: std::time::SystemTime,
+    /// 最近一次活动（订阅、确认事件）的时间
+    pub last_active_at: std::time::SystemTime,
     /// 接收到的事件数量
     pub events_received: u64,
+    /// 事件类型 -> 订阅时指定的元数据过滤条件，未指定过滤条件的事件类型不在其中
+    pub metadata_filters: HashMap<String, EventFilter>,
+    /// 事件类型 -> 订阅流的存活标记，订阅流被丢弃后对应的标记失效；
+    /// 只记录订阅信息、不创建订阅流的订阅不在其中
+    pub(crate) streams: HashMap<String, Vec<Weak<()>>>,
+}
+
+impl SubscriberInfo {
+    /// 仍然存活的订阅流数量
+    pub fn active_streams(&self) -> usize {
+        self.streams
+            .values()
+            .flatten()
+            .filter(|alive| alive.strong_count() > 0)
+            .count()
+    }
+}
+
+/// 订阅者的事件过滤条件
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    /// 事件 metadata 需要包含的全部键值对，为空时接收所有事件
+    pub metadata: HashMap<String, String>,
+}
+
+impl EventFilter {
+    /// 按元数据键值对过滤
+    pub fn metadata(metadata: HashMap<String, String>) -> Self {
+        Self { metadata }
+    }
+
+    /// 是否没有任何过滤条件
+    pub fn is_empty(&self) -> bool {
+        self.metadata.is_empty()
+    }
+
+    /// 事件是否满足过滤条件
+    pub fn matches(&self, event: &EventMessage) -> bool {
+        self.metadata
+            .iter()
+            .all(|(key, value)| event.metadata.get(key) == Some(value))
+    }
 }
@@ -1,14 +1,16 @@
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::{Stream, StreamExt};
 use tonic::transport::{Channel, Endpoint};
 use uuid::Uuid;
 
+use super::client::streaming::PendingRequests;
 use super::client::{GatewayClientConfig, GatewayClientError};
 use crate::registry::{
-    ForwardRequest, StreamingInfo, registry_service_client::RegistryServiceClient,
-    streaming_info::StreamType,
+    ForwardRequest, ListServicesRequest, StreamingInfo,
+    registry_service_client::RegistryServiceClient, streaming_info::StreamType,
 };
 
 /// 网关客户端
@@ -16,19 +18,57 @@ use crate::registry::{
 pub struct GatewayClient {
     pub(crate) config: GatewayClientConfig,
     pub(crate) client: RegistryServiceClient<Channel>,
+    // 当前连接的网关地址在 gateway_addresses 中的位置
+    pub(crate) address_index: usize,
 }
 
 impl GatewayClient {
-    /// 创建新的网关客户端
+    /// 创建新的网关客户端，按顺序尝试 gateway_address 和 fallback_addresses，
+    /// 使用第一个连接成功的网关
     pub async fn new(config: GatewayClientConfig) -> Result<Self, GatewayClientError> {
-        let endpoint = Endpoint::from_shared(config.gateway_address.clone())?
+        let mut last_error = None;
+        for index in 0..config.gateway_addresses().len() {
+            match Self::connect_to(config.clone(), index).await {
+                Ok(client) => return Ok(client),
+                Err(e) => {
+                    tracing::warn!(
+                        gateway_address = config.gateway_addresses()[index],
+                        error = %e,
+                        "Failed to connect to gateway, trying next address"
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or(GatewayClientError::ConnectionClosed))
+    }
+
+    /// 只连接 gateway_addresses 中第 index 个网关
+    pub(crate) async fn connect_to(
+        config: GatewayClientConfig,
+        index: usize,
+    ) -> Result<Self, GatewayClientError> {
+        let address = config.gateway_addresses()[index].to_string();
+        let uri = Endpoint::from_shared(address)?.uri().clone();
+        let endpoint = config
+            .http2
+            .apply_to_endpoint(config.tls.endpoint(uri)?)
             .connect_timeout(config.connect_timeout)
             .timeout(config.default_timeout);
 
         let channel = endpoint.connect().await?;
         let client = RegistryServiceClient::new(channel);
 
-        Ok(Self { config, client })
+        Ok(Self {
+            config,
+            client,
+            address_index: index,
+        })
+    }
+
+    /// 当前连接的网关地址
+    pub fn gateway_address(&self) -> &str {
+        self.config.gateway_addresses()[self.address_index]
     }
 
     /// 便捷的创建方法，使用默认配置
@@ -160,11 +200,13 @@ impl GatewayClient {
     {
         let (response_tx, response_rx) = mpsc::channel(100);
         let (request_tx, request_rx) = mpsc::channel(1);
+        let request_id = Uuid::new_v4().to_string();
 
         // 发送单个请求
         super::client::streaming::send_server_stream_request(
             self,
             request,
+            request_id.clone(),
             request_tx,
             service_name,
             method_path,
@@ -181,8 +223,8 @@ impl GatewayClient {
         let response = self.client.establish_connection(request_stream).await?;
         let inbound = response.into_inner();
 
-        // 启动响应流处理任务
-        super::client::streaming::spawn_server_stream_handler(inbound, response_tx);
+        // 启动响应流处理任务，收到该请求的最后一个响应后结束，不依赖连接关闭
+        super::client::streaming::spawn_server_stream_handler(inbound, &request_id, response_tx);
 
         Ok(ReceiverStream::new(response_rx))
     }
@@ -215,6 +257,7 @@ impl GatewayClient {
     {
         let (response_tx, response_rx) = mpsc::channel(100);
         let (request_tx, request_rx) = mpsc::channel(100);
+        let pending = Arc::new(Mutex::new(PendingRequests::default()));
 
         // 创建请求流
         let request_stream = ReceiverStream::new(request_rx);
@@ -236,13 +279,14 @@ impl GatewayClient {
             self,
             requests,
             request_tx,
+            pending.clone(),
             service_name,
             method_path,
         )
         .await;
 
-        // 启动响应处理任务
-        super::client::streaming::spawn_response_handler(inbound, response_tx).await;
+        // 启动响应处理任务，请求半关闭且每个请求都收到最后一个响应后结束
+        super::client::streaming::spawn_response_handler(inbound, pending, response_tx).await;
 
         Ok(ReceiverStream::new(response_rx))
     }
@@ -276,9 +320,11 @@ impl GatewayClient {
     pub async fn list_healthy_services(
         &mut self,
     ) -> Result<HashMap<String, String>, GatewayClientError> {
-        // 这里可以实现一个获取服务列表的方法
-        // 需要扩展 proto 定义以支持服务发现
-        todo!("Service discovery not yet implemented")
+        let request = ListServicesRequest {
+            api_key: self.config.api_key.clone(),
+        };
+        let response = self.client.list_services(request).await?;
+        Ok(response.into_inner().services)
     }
 
     /// 检查服务是否可用
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+use crate::health::{
+    HealthCheckRequest, HealthCheckResponse, health_check_response::ServingStatus,
+    health_server::Health,
+};
+use crate::services::registry::MyRegistryService;
+
+/// 标准 gRPC 健康检查服务 (grpc.health.v1.Health)
+#[derive(Debug, Clone)]
+pub struct HealthService {
+    registry_service: Arc<MyRegistryService>,
+}
+
+impl HealthService {
+    pub fn new(registry_service: Arc<MyRegistryService>) -> Self {
+        Self { registry_service }
+    }
+
+    /// 计算服务当前状态，空服务名表示网关自身
+    pub fn service_status(&self, service_name: &str) -> ServingStatus {
+        if service_name.is_empty() {
+            return ServingStatus::Serving;
+        }
+
+        // 未注册或没有健康实例的服务都返回 NOT_SERVING
+        if self
+            .registry_service
+            .get_healthy_services()
+            .contains_key(service_name)
+        {
+            ServingStatus::Serving
+        } else {
+            ServingStatus::NotServing
+        }
+    }
+
+    fn status_response(status: ServingStatus) -> HealthCheckResponse {
+        HealthCheckResponse {
+            status: status as i32,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Health for HealthService {
+    type WatchStream = ReceiverStream<Result<HealthCheckResponse, Status>>;
+
+    async fn check(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<HealthCheckResponse>, Status> {
+        let service_name = request.into_inner().service;
+        let status = self.service_status(&service_name);
+
+        tracing::debug!(service_name = %service_name, status = ?status, "Health check");
+
+        Ok(Response::new(Self::status_response(status)))
+    }
+
+    async fn watch(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let service_name = request.into_inner().service;
+        let (tx, rx) = mpsc::channel(16);
+        let mut changes = self.registry_service.subscribe_health_changes();
+        let health = self.clone();
+
+        tokio::spawn(async move {
+            // 先推送当前状态
+            let mut last_status = health.service_status(&service_name);
+            if tx
+                .send(Ok(Self::status_response(last_status)))
+                .await
+                .is_err()
+            {
+                return;
+            }
+
+            loop {
+                let changed = tokio::select! {
+                    _ = tx.closed() => break,
+                    changed = changes.recv() => changed,
+                };
+
+                match changed {
+                    Ok(changed_service) if changed_service != service_name => continue,
+                    // 落后时无法确定变更的服务，直接重新计算状态
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+
+                let status = health.service_status(&service_name);
+                if status == last_status {
+                    continue;
+                }
+
+                tracing::debug!(
+                    service_name = %service_name,
+                    old_status = ?last_status,
+                    new_status = ?status,
+                    "Health status transition"
+                );
+                last_status = status;
+
+                if tx.send(Ok(Self::status_response(status))).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
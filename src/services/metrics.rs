@@ -0,0 +1,724 @@
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use dashmap::DashMap;
+use http_body::{Body, Frame};
+use http_body_util::Full;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use super::client_manager::GrpcClientManager;
+use super::connection::ReverseConnectionManager;
+use super::registry::ServiceRegistry;
+
+// 转发延迟直方图的桶上界（秒）
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+// 请求体大小直方图的桶上界（字节）
+const PAYLOAD_BUCKETS: [u64; 10] = [
+    256,
+    1024,
+    4 * 1024,
+    16 * 1024,
+    64 * 1024,
+    256 * 1024,
+    1024 * 1024,
+    4 * 1024 * 1024,
+    16 * 1024 * 1024,
+    64 * 1024 * 1024,
+];
+
+// 转发路径
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForwardRoute {
+    Direct,
+    Reverse,
+}
+
+impl ForwardRoute {
+    fn as_label(self) -> &'static str {
+        match self {
+            ForwardRoute::Direct => "direct",
+            ForwardRoute::Reverse => "reverse",
+        }
+    }
+}
+
+// 单条转发路径的计数和延迟直方图
+#[derive(Debug, Default)]
+struct ForwardStats {
+    success: AtomicU64,
+    failure: AtomicU64,
+    // 失败请求按 RouterError::kind 分类计数
+    errors: DashMap<&'static str, AtomicU64>,
+    // 各桶独立计数，输出时再累加
+    buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    latency_sum_micros: AtomicU64,
+    payload_buckets: [AtomicU64; PAYLOAD_BUCKETS.len()],
+    payload_sum_bytes: AtomicU64,
+    payload_count: AtomicU64,
+    // 耗时超过慢请求阈值的请求数
+    slow: AtomicU64,
+}
+
+impl ForwardStats {
+    fn record(&self, latency: Duration, error: Option<&'static str>) {
+        match error {
+            None => {
+                self.success.fetch_add(1, Ordering::Relaxed);
+            }
+            Some(kind) => {
+                self.failure.fetch_add(1, Ordering::Relaxed);
+                self.errors
+                    .entry(kind)
+                    .or_default()
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let secs = latency.as_secs_f64();
+        if let Some(index) = LATENCY_BUCKETS.iter().position(|&bound| secs <= bound) {
+            self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        }
+        self.latency_sum_micros
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn record_payload(&self, size: u64) {
+        if let Some(index) = PAYLOAD_BUCKETS.iter().position(|&bound| size <= bound) {
+            self.payload_buckets[index].fetch_add(1, Ordering::Relaxed);
+        }
+        self.payload_sum_bytes.fetch_add(size, Ordering::Relaxed);
+        self.payload_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn count(&self) -> u64 {
+        self.success.load(Ordering::Relaxed) + self.failure.load(Ordering::Relaxed)
+    }
+}
+
+// 网关转发指标，由动态路由器在每次转发后记录
+#[derive(Debug, Default)]
+pub struct GatewayMetrics {
+    direct: ForwardStats,
+    reverse: ForwardStats,
+}
+
+impl GatewayMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // error 为失败时的错误种类（RouterError::kind），成功时为 None
+    pub fn record_forward(
+        &self,
+        route: ForwardRoute,
+        latency: Duration,
+        error: Option<&'static str>,
+    ) {
+        self.stats(route).record(latency, error);
+    }
+
+    pub fn record_payload(&self, route: ForwardRoute, size: u64) {
+        self.stats(route).record_payload(size);
+    }
+
+    pub fn record_slow(&self, route: ForwardRoute) {
+        self.stats(route).slow.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // 某条转发路径已完成的请求数
+    pub fn forwarded_requests(&self, route: ForwardRoute) -> u64 {
+        self.stats(route).count()
+    }
+
+    // 某条转发路径上某种错误的失败请求数
+    pub fn forward_errors(&self, route: ForwardRoute, kind: &str) -> u64 {
+        self.stats(route)
+            .errors
+            .get(kind)
+            .map_or(0, |count| count.load(Ordering::Relaxed))
+    }
+
+    // 某条转发路径上的慢请求数
+    pub fn slow_requests(&self, route: ForwardRoute) -> u64 {
+        self.stats(route).slow.load(Ordering::Relaxed)
+    }
+
+    fn stats(&self, route: ForwardRoute) -> &ForwardStats {
+        match route {
+            ForwardRoute::Direct => &self.direct,
+            ForwardRoute::Reverse => &self.reverse,
+        }
+    }
+}
+
+// 转发耗时超过慢请求阈值时输出 WARN 日志，返回是否为慢请求
+pub fn log_slow_request(
+    threshold: Option<Duration>,
+    service_name: &str,
+    method_path: &str,
+    elapsed: Duration,
+    payload_size: u64,
+) -> bool {
+    match threshold {
+        Some(threshold) if elapsed >= threshold => {
+            tracing::warn!(
+                service_name = %service_name,
+                method_path = %method_path,
+                duration_ms = elapsed.as_millis(),
+                payload_size = payload_size,
+                threshold_ms = threshold.as_millis(),
+                "Slow request"
+            );
+            true
+        }
+        _ => false,
+    }
+}
+
+// 统计已读取的请求体字节数，供请求体大小直方图和慢请求日志使用
+pub struct CountingBody<B> {
+    inner: Pin<Box<B>>,
+    counted: Arc<AtomicU64>,
+}
+
+impl<B> CountingBody<B> {
+    pub fn new(inner: B, counted: Arc<AtomicU64>) -> Self {
+        Self {
+            inner: Box::pin(inner),
+            counted,
+        }
+    }
+}
+
+impl<B> Body for CountingBody<B>
+where
+    B: Body<Data = bytes::Bytes>,
+{
+    type Data = bytes::Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let frame = self.inner.as_mut().poll_frame(cx);
+        if let Poll::Ready(Some(Ok(frame))) = &frame
+            && let Some(data) = frame.data_ref()
+        {
+            self.counted.fetch_add(data.len() as u64, Ordering::Relaxed);
+        }
+        frame
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// 汇总网关各组件状态并编码为 Prometheus 文本格式
+#[derive(Debug, Clone)]
+pub struct MetricsExporter {
+    pub metrics: Arc<GatewayMetrics>,
+    pub registry: ServiceRegistry,
+    pub client_manager: GrpcClientManager,
+    pub reverse_manager: Arc<ReverseConnectionManager>,
+    pub readiness: Readiness,
+}
+
+// 网关是否可以接收流量：配置加载成功、gRPC 监听端口绑定后置为就绪
+#[derive(Debug, Clone, Default)]
+pub struct Readiness(Arc<AtomicBool>);
+
+impl Readiness {
+    pub fn mark_ready(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+impl MetricsExporter {
+    // 已就绪且未进入停机排空时才接收新流量
+    pub fn is_ready(&self) -> bool {
+        self.readiness.is_ready() && !self.reverse_manager.is_draining()
+    }
+
+    pub async fn render(&self) -> String {
+        let mut out = String::new();
+
+        // 反向连接与等待中的请求
+        let reverse_stats = self.reverse_manager.get_stats().await;
+        write_gauge(
+            &mut out,
+            "gateway_reverse_connections_active",
+            "Active reverse connections",
+            reverse_stats.active_connections as u64,
+        );
+        write_gauge(
+            &mut out,
+            "gateway_pending_requests",
+            "Requests waiting for a reverse connection response",
+            reverse_stats.pending_requests as u64,
+        );
+        write_header(
+            &mut out,
+            "gateway_reverse_requests_total",
+            "Requests sent over reverse connections by outcome",
+            "counter",
+        );
+        for (outcome, value) in [
+            ("forwarded", reverse_stats.forwarded_requests),
+            ("timed_out", reverse_stats.timed_out_requests),
+            ("failed", reverse_stats.failed_requests),
+        ] {
+            let _ = writeln!(
+                out,
+                "gateway_reverse_requests_total{{outcome=\"{outcome}\"}} {value}"
+            );
+        }
+        write_header(
+            &mut out,
+            "gateway_reconcile_repairs_total",
+            "Inconsistencies repaired by the reverse connection reconciler",
+            "counter",
+        );
+        for (kind, value) in [
+            ("pool_connection", reverse_stats.detached_pool_connections),
+            (
+                "registry_instance",
+                reverse_stats.removed_registry_instances,
+            ),
+        ] {
+            let _ = writeln!(
+                out,
+                "gateway_reconcile_repairs_total{{kind=\"{kind}\"}} {value}"
+            );
+        }
+
+        // 每个反向连接提供的服务数和心跳间隔，便于发现连接与服务映射异常
+        let connections = self.reverse_manager.list_connections();
+        write_header(
+            &mut out,
+            "gateway_reverse_connection_services",
+            "Services registered by each reverse connection",
+            "gauge",
+        );
+        for info in &connections {
+            let _ = writeln!(
+                out,
+                "gateway_reverse_connection_services{{connection_id=\"{}\"}} {}",
+                escape_label(&info.connection_id),
+                info.services.len()
+            );
+        }
+        write_header(
+            &mut out,
+            "gateway_reverse_connection_heartbeat_age_seconds",
+            "Seconds since the last heartbeat of each reverse connection",
+            "gauge",
+        );
+        for info in &connections {
+            let _ = writeln!(
+                out,
+                "gateway_reverse_connection_heartbeat_age_seconds{{connection_id=\"{}\"}} {:.3}",
+                escape_label(&info.connection_id),
+                info.last_heartbeat_age.as_secs_f64()
+            );
+        }
+
+        // 服务注册表
+        write_gauge(
+            &mut out,
+            "gateway_registered_services",
+            "Registered service names",
+            self.registry.len() as u64,
+        );
+        let instances: usize = self.registry.iter().map(|entry| entry.value().len()).sum();
+        write_gauge(
+            &mut out,
+            "gateway_registered_instances",
+            "Registered service instances",
+            instances as u64,
+        );
+
+        self.render_forwarding(&mut out);
+        self.render_client_pool(&mut out);
+        self.render_event_bus(&mut out);
+
+        out
+    }
+
+    fn render_forwarding(&self, out: &mut String) {
+        let routes = [ForwardRoute::Direct, ForwardRoute::Reverse];
+
+        write_header(
+            out,
+            "gateway_forwarded_requests_total",
+            "Forwarded requests by route and outcome",
+            "counter",
+        );
+        for route in routes {
+            let stats = self.metrics.stats(route);
+            for (outcome, counter) in [("success", &stats.success), ("error", &stats.failure)] {
+                let _ = writeln!(
+                    out,
+                    "gateway_forwarded_requests_total{{route=\"{}\",outcome=\"{outcome}\"}} {}",
+                    route.as_label(),
+                    counter.load(Ordering::Relaxed)
+                );
+            }
+        }
+
+        write_header(
+            out,
+            "gateway_forward_errors_total",
+            "Failed forwarded requests by route and error reason",
+            "counter",
+        );
+        for route in routes {
+            let mut errors: Vec<_> = self
+                .metrics
+                .stats(route)
+                .errors
+                .iter()
+                .map(|entry| (*entry.key(), entry.value().load(Ordering::Relaxed)))
+                .collect();
+            errors.sort_unstable();
+            for (reason, count) in errors {
+                let _ = writeln!(
+                    out,
+                    "gateway_forward_errors_total{{route=\"{}\",reason=\"{reason}\"}} {count}",
+                    route.as_label()
+                );
+            }
+        }
+
+        write_header(
+            out,
+            "gateway_forward_latency_seconds",
+            "Latency until response headers are received",
+            "histogram",
+        );
+        for route in routes {
+            let stats = self.metrics.stats(route);
+            let label = route.as_label();
+            let mut cumulative = 0;
+            for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&stats.buckets) {
+                cumulative += bucket.load(Ordering::Relaxed);
+                let _ = writeln!(
+                    out,
+                    "gateway_forward_latency_seconds_bucket{{route=\"{label}\",le=\"{bound}\"}} {cumulative}"
+                );
+            }
+            let count = stats.count();
+            let sum = stats.latency_sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+            let _ = writeln!(
+                out,
+                "gateway_forward_latency_seconds_bucket{{route=\"{label}\",le=\"+Inf\"}} {count}"
+            );
+            let _ = writeln!(
+                out,
+                "gateway_forward_latency_seconds_sum{{route=\"{label}\"}} {sum}"
+            );
+            let _ = writeln!(
+                out,
+                "gateway_forward_latency_seconds_count{{route=\"{label}\"}} {count}"
+            );
+        }
+
+        write_header(
+            out,
+            "gateway_request_payload_bytes",
+            "Request body size received before the response headers",
+            "histogram",
+        );
+        for route in routes {
+            let stats = self.metrics.stats(route);
+            let label = route.as_label();
+            let mut cumulative = 0;
+            for (bound, bucket) in PAYLOAD_BUCKETS.iter().zip(&stats.payload_buckets) {
+                cumulative += bucket.load(Ordering::Relaxed);
+                let _ = writeln!(
+                    out,
+                    "gateway_request_payload_bytes_bucket{{route=\"{label}\",le=\"{bound}\"}} {cumulative}"
+                );
+            }
+            let count = stats.payload_count.load(Ordering::Relaxed);
+            let _ = writeln!(
+                out,
+                "gateway_request_payload_bytes_bucket{{route=\"{label}\",le=\"+Inf\"}} {count}"
+            );
+            let _ = writeln!(
+                out,
+                "gateway_request_payload_bytes_sum{{route=\"{label}\"}} {}",
+                stats.payload_sum_bytes.load(Ordering::Relaxed)
+            );
+            let _ = writeln!(
+                out,
+                "gateway_request_payload_bytes_count{{route=\"{label}\"}} {count}"
+            );
+        }
+
+        write_header(
+            out,
+            "gateway_slow_requests_total",
+            "Forwarded requests slower than router.slow_request_threshold_ms",
+            "counter",
+        );
+        for route in routes {
+            let _ = writeln!(
+                out,
+                "gateway_slow_requests_total{{route=\"{}\"}} {}",
+                route.as_label(),
+                self.metrics.slow_requests(route)
+            );
+        }
+    }
+
+    fn render_client_pool(&self, out: &mut String) {
+        let snapshot = self.client_manager.detailed_stats();
+        write_gauge(
+            out,
+            "gateway_client_pool_connections",
+            "Cached backend channels",
+            snapshot.channels as u64,
+        );
+        write_gauge(
+            out,
+            "gateway_client_pool_addresses",
+            "Backend addresses with cached channels",
+            snapshot.addresses as u64,
+        );
+        write_gauge(
+            out,
+            "gateway_client_pool_channel_uses",
+            "Times the currently cached channels have been handed out",
+            snapshot.total_use_count,
+        );
+        for (name, help, values) in [
+            (
+                "gateway_client_pool_connection_age_seconds",
+                "Age of cached backend connections per address",
+                [
+                    ("min", snapshot.min_age),
+                    ("max", snapshot.max_age),
+                    ("avg", snapshot.avg_age),
+                ]
+                .as_slice(),
+            ),
+            (
+                "gateway_client_pool_idle_seconds",
+                "Time since cached backend connections were last used",
+                [("max", snapshot.max_idle), ("avg", snapshot.avg_idle)].as_slice(),
+            ),
+        ] {
+            write_header(out, name, help, "gauge");
+            for (stat, value) in values {
+                let _ = writeln!(out, "{name}{{stat=\"{stat}\"}} {:.3}", value.as_secs_f64());
+            }
+        }
+
+        let mut stats: Vec<_> = self.client_manager.get_stats().into_iter().collect();
+        stats.sort();
+
+        write_header(
+            out,
+            "gateway_client_pool_events_total",
+            "Backend connection pool events such as cache_hits and cache_misses",
+            "counter",
+        );
+        for (key, value) in stats.iter().filter(|(key, _)| !key.contains(':')) {
+            let _ = writeln!(
+                out,
+                "gateway_client_pool_events_total{{event=\"{}\"}} {value}",
+                escape_label(key)
+            );
+        }
+
+        // 按地址的熔断器状态
+        for (prefix, name, help) in [
+            (
+                "circuit_state:",
+                "gateway_backend_circuit_state",
+                "Circuit breaker state per backend (0=closed, 1=open, 2=half-open)",
+            ),
+            (
+                "circuit_failures:",
+                "gateway_backend_circuit_failures",
+                "Consecutive failures per backend",
+            ),
+        ] {
+            write_header(out, name, help, "gauge");
+            for (key, value) in &stats {
+                if let Some(address) = key.strip_prefix(prefix) {
+                    let _ = writeln!(
+                        out,
+                        "{name}{{address=\"{}\"}} {value}",
+                        escape_label(address)
+                    );
+                }
+            }
+        }
+    }
+
+    fn render_event_bus(&self, out: &mut String) {
+        let stats = self.reverse_manager.event_bus.get_stats();
+
+        write_gauge(
+            out,
+            "gateway_event_types_active",
+            "Event types with an open channel",
+            stats.active_event_types as u64,
+        );
+        write_gauge(
+            out,
+            "gateway_event_subscribers",
+            "Event bus subscribers",
+            stats.total_subscribers as u64,
+        );
+        write_counter(
+            out,
+            "gateway_events_published_total",
+            "Events published on the event bus",
+            stats.events_published,
+        );
+        write_counter(
+            out,
+            "gateway_events_delivered_total",
+            "Events delivered to subscribers",
+            stats.events_delivered,
+        );
+        write_counter(
+            out,
+            "gateway_event_delivery_failures_total",
+            "Failed event deliveries",
+            stats.delivery_failures,
+        );
+        write_counter(
+            out,
+            "gateway_events_lagged_total",
+            "Events missed by subscribers that fell behind the channel capacity",
+            stats.events_lagged,
+        );
+        write_counter(
+            out,
+            "gateway_event_subscriptions_evicted_total",
+            "Event subscriptions evicted after their stream was dropped or they went idle",
+            stats.subscriptions_evicted,
+        );
+    }
+}
+
+fn write_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn write_gauge(out: &mut String, name: &str, help: &str, value: u64) {
+    write_header(out, name, help, "gauge");
+    let _ = writeln!(out, "{name} {value}");
+}
+
+fn write_counter(out: &mut String, name: &str, help: &str, value: u64) {
+    write_header(out, name, help, "counter");
+    let _ = writeln!(out, "{name} {value}");
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// 在独立端口上提供 /metrics 和 /livez、/readyz 探针，shutdown 触发后停止接受新连接
+pub async fn serve_metrics(
+    addr: SocketAddr,
+    exporter: MetricsExporter,
+    shutdown: CancellationToken,
+) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    tracing::info!(address = %addr, "Metrics endpoint listening");
+
+    loop {
+        let (stream, _) = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to accept metrics connection");
+                    continue;
+                }
+            },
+        };
+
+        let exporter = exporter.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |req: hyper::Request<hyper::body::Incoming>| {
+                let exporter = exporter.clone();
+                async move { Ok::<_, Infallible>(handle_metrics_request(&exporter, &req).await) }
+            });
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                tracing::debug!(error = %e, "Metrics connection closed with error");
+            }
+        });
+    }
+
+    Ok(())
+}
+
+async fn handle_metrics_request<B>(
+    exporter: &MetricsExporter,
+    req: &hyper::Request<B>,
+) -> hyper::Response<Full<bytes::Bytes>> {
+    if req.method() != hyper::Method::GET {
+        return probe_response(hyper::StatusCode::NOT_FOUND);
+    }
+
+    match req.uri().path() {
+        "/metrics" => hyper::Response::builder()
+            .header("content-type", "text/plain; version=0.0.4")
+            .body(Full::from(exporter.render().await))
+            .expect("Failed to build response"),
+        // 进程在运行即存活
+        "/livez" => probe_response(hyper::StatusCode::OK),
+        // 启动完成前和停机排空期间返回 503，编排系统据此摘除流量
+        "/readyz" if exporter.is_ready() => probe_response(hyper::StatusCode::OK),
+        "/readyz" => probe_response(hyper::StatusCode::SERVICE_UNAVAILABLE),
+        _ => probe_response(hyper::StatusCode::NOT_FOUND),
+    }
+}
+
+fn probe_response(status: hyper::StatusCode) -> hyper::Response<Full<bytes::Bytes>> {
+    let body = match status {
+        hyper::StatusCode::OK => Full::from("ok"),
+        hyper::StatusCode::NOT_FOUND => Full::default(),
+        _ => Full::from("not ready"),
+    };
+    hyper::Response::builder()
+        .status(status)
+        .body(body)
+        .expect("Failed to build response")
+}
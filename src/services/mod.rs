@@ -1,8 +1,13 @@
+pub mod admin;
 pub mod client;
 pub mod client_manager;
+pub mod compression;
+pub mod config_watcher;
 pub mod connection;
 pub mod event;
 pub mod gateway_client;
+pub mod health;
+pub mod metrics;
 pub mod registry;
 pub mod router;
 
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::SystemTime;
+
+use tonic::Status;
+
+use crate::registry::EventMessage;
+use crate::services::event::EventBus;
+
+// 审计记录作为事件发布时使用的事件类型
+pub const REGISTRATION_AUDIT_EVENT: &str = "gateway.audit.registration";
+
+// 被审计的注册操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Register,
+    EstablishConnection,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Register => "register",
+            Self::EstablishConnection => "establish_connection",
+        }
+    }
+}
+
+// 一次注册或建立反向连接的尝试
+#[derive(Debug, Clone)]
+pub struct RegistrationAudit<'a> {
+    pub action: AuditAction,
+    pub peer: Option<SocketAddr>,
+    pub token: &'a str,
+    pub services: &'a [String],
+}
+
+impl RegistrationAudit<'_> {
+    // 输出 target 为 audit 的结构化日志，publish 为 true 时同时发布审计事件
+    pub fn emit(&self, result: Result<(), &Status>, event_bus: &EventBus, publish: bool) {
+        let peer = self
+            .peer
+            .map(|peer| peer.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        let token_id = token_id(self.token);
+        let timestamp = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let (outcome, reason) = match result {
+            Ok(()) => ("success", String::new()),
+            Err(status) => (code_name(status.code()), status.message().to_string()),
+        };
+
+        match result {
+            Ok(()) => tracing::info!(
+                target: "audit",
+                action = self.action.as_str(),
+                peer = %peer,
+                token_id = %token_id,
+                services = ?self.services,
+                outcome,
+                timestamp,
+                "Registration attempt"
+            ),
+            Err(_) => tracing::warn!(
+                target: "audit",
+                action = self.action.as_str(),
+                peer = %peer,
+                token_id = %token_id,
+                services = ?self.services,
+                outcome,
+                reason = %reason,
+                timestamp,
+                "Registration attempt rejected"
+            ),
+        }
+
+        if !publish {
+            return;
+        }
+        let event = EventMessage {
+            event_type: REGISTRATION_AUDIT_EVENT.to_string(),
+            publisher_id: "gateway".to_string(),
+            timestamp,
+            metadata: HashMap::from([
+                ("action".to_string(), self.action.as_str().to_string()),
+                ("peer".to_string(), peer),
+                ("token_id".to_string(), token_id),
+                ("services".to_string(), self.services.join(",")),
+                ("outcome".to_string(), outcome.to_string()),
+                ("reason".to_string(), reason),
+            ]),
+            ..Default::default()
+        };
+        if let Err(e) = event_bus.publish_event_sync(event) {
+            tracing::debug!(error = %e, "Audit event not delivered");
+        }
+    }
+}
+
+// token 的指纹：SHA-256 的前 8 个字节（十六进制），日志中不出现 token 原文；空 token 记为 "none"
+pub fn token_id(token: &str) -> String {
+    if token.is_empty() {
+        return "none".to_string();
+    }
+    let digest = ring::digest::digest(&ring::digest::SHA256, token.as_bytes());
+    digest.as_ref()[..8]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn code_name(code: tonic::Code) -> &'static str {
+    match code {
+        tonic::Code::Unauthenticated => "unauthenticated",
+        tonic::Code::PermissionDenied => "permission_denied",
+        tonic::Code::InvalidArgument => "invalid_argument",
+        tonic::Code::Unavailable => "unavailable",
+        tonic::Code::Aborted => "aborted",
+        _ => "error",
+    }
+}
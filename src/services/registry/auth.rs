@@ -0,0 +1,102 @@
+use std::fmt;
+
+use thiserror::Error;
+use tonic::Status;
+
+use super::audit::token_id;
+use crate::config::SharedConfig;
+
+// 校验通过的 token 信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenClaims {
+    // 调用方标识，用于日志和按调用方统计反向连接数，不应包含 token 原文
+    pub subject: String,
+}
+
+// token 校验失败的原因
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    #[error("Invalid token")]
+    InvalidToken,
+
+    #[error("Token is not allowed to register service '{service}'")]
+    ServiceNotAllowed { service: String },
+
+    // 外部认证服务不可用等临时错误
+    #[error("Token validation unavailable: {0}")]
+    Unavailable(String),
+}
+
+impl From<AuthError> for Status {
+    fn from(error: AuthError) -> Self {
+        match error {
+            AuthError::InvalidToken => Status::unauthenticated(error.to_string()),
+            AuthError::ServiceNotAllowed { .. } => Status::permission_denied(error.to_string()),
+            AuthError::Unavailable(_) => Status::unavailable(error.to_string()),
+        }
+    }
+}
+
+// 注册、建立反向连接和查询服务时使用的 token 校验器。
+// service 为 Some 时还需检查 token 是否允许注册该服务；
+// 接入外部认证服务或 JWT/OIDC 时实现该 trait 并通过 MyRegistryService::with_token_validator 替换
+#[tonic::async_trait]
+pub trait TokenValidator: Send + Sync + fmt::Debug {
+    async fn validate(&self, token: &str, service: Option<&str>) -> Result<TokenClaims, AuthError>;
+
+    // 校验 token 并检查是否允许注册全部服务，任一服务越界即返回错误。
+    // 默认逐个服务调用 validate；校验开销大的实现（如验签）应覆盖为只校验一次 token
+    async fn validate_services(
+        &self,
+        token: &str,
+        services: &[String],
+    ) -> Result<TokenClaims, AuthError> {
+        let claims = self.validate(token, None).await?;
+        for service in services {
+            self.validate(token, Some(service)).await?;
+        }
+        Ok(claims)
+    }
+}
+
+// 默认校验器：使用配置中的 security.tokens 和 security.scoped_tokens，热更新后立即生效
+#[derive(Debug, Clone)]
+pub struct StaticTokenValidator {
+    config: SharedConfig,
+}
+
+impl StaticTokenValidator {
+    pub fn new(config: SharedConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[tonic::async_trait]
+impl TokenValidator for StaticTokenValidator {
+    async fn validate(&self, token: &str, service: Option<&str>) -> Result<TokenClaims, AuthError> {
+        let services = service.map(str::to_string);
+        self.validate_services(token, services.as_slice()).await
+    }
+
+    async fn validate_services(
+        &self,
+        token: &str,
+        services: &[String],
+    ) -> Result<TokenClaims, AuthError> {
+        let config = self.config.load();
+        if !config.validate_token(token) {
+            return Err(AuthError::InvalidToken);
+        }
+        if let Some(service) = services
+            .iter()
+            .find(|service| !config.token_allows_service(token, service))
+        {
+            return Err(AuthError::ServiceNotAllowed {
+                service: service.clone(),
+            });
+        }
+        Ok(TokenClaims {
+            subject: token_id(token),
+        })
+    }
+}
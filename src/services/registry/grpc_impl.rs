@@ -1,4 +1,5 @@
 use dashmap::DashMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::SystemTime;
 use tokio::sync::mpsc;
@@ -7,13 +8,18 @@ use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status, Streaming};
 use uuid::Uuid;
 
+use super::audit::{AuditAction, RegistrationAudit, token_id};
+use super::auth::{AuthError, TokenClaims};
+use super::limits::TokenConnectionGuard;
 use super::service::MyRegistryService;
-use super::types::{ServiceHealthStatus, ServiceInfo};
+use super::types::{SERVICE_REGISTERED_EVENT, ServiceHealthStatus, ServiceInfo};
 use crate::registry::{
-    ConnectionMessage, ConnectionStatus, RegisterRequest, RegisterResponse,
+    ConnectionMessage, ConnectionStatus, DrainInstanceRequest, DrainInstanceResponse,
+    ListServicesRequest, ListServicesResponse, Pong, RegisterRequest, RegisterResponse,
     connection_message::MessageType, connection_status::StatusType,
-    registry_service_server::RegistryService,
+    registry_service_server::RegistryService, subscription_request::Action,
 };
+use crate::services::connection::ForwardResponseBuilder;
 
 // 为结构体实现 gRPC 服务 trait
 #[tonic::async_trait]
@@ -23,23 +29,53 @@ impl RegistryService for MyRegistryService {
         &self,
         request: Request<RegisterRequest>,
     ) -> Result<Response<RegisterResponse>, Status> {
+        let peer = request.remote_addr();
         let req = request.into_inner();
 
-        // 验证 Token
-        if !self.config.validate_token(&req.api_key) {
-            return Err(Status::unauthenticated("Invalid token"));
-        }
+        let authorized = self.authorize(&req.api_key, &req.services).await;
+        self.audit_registration(
+            AuditAction::Register,
+            peer,
+            &req.api_key,
+            &req.services,
+            authorized.as_ref().map(|_| ()),
+        );
+        authorized?;
+
+        // 没有携带实例 ID 时沿用该地址已有的实例，重复注册（心跳）不会产生新实例
+        let instance_id = if !req.instance_id.is_empty() {
+            req.instance_id.clone()
+        } else {
+            self.find_instance_id(&req.services, &req.address)
+                .unwrap_or_else(|| Uuid::new_v4().to_string())
+        };
 
+        // 反向连接登记的实例由连接状态决定能否路由，不经过预热
+        let warmup = self
+            .config
+            .load()
+            .router
+            .initial_routing_delay()
+            .filter(|_| {
+                !self
+                    .reverse_connection_manager
+                    .connections_by_id
+                    .contains_key(&instance_id)
+            });
+
+        let mut registered_new_instance = false;
         let service_info = ServiceInfo {
             address: req.address.clone(),
             last_heartbeat: SystemTime::now(),
             health_status: ServiceHealthStatus::Healthy,
+            metadata: req.metadata,
         };
 
         for service_name in req.services {
             tracing::info!(
                 service_name = %service_name,
                 address = %req.address,
+                instance_id = %instance_id,
                 "Registering service"
             );
             let instances = self
@@ -47,10 +83,35 @@ impl RegistryService for MyRegistryService {
                 .entry(service_name.clone())
                 .or_insert_with(|| Arc::new(DashMap::new()))
                 .clone();
+            let previous_health = Self::aggregate_health(&self.registry, &service_name);
+
+            let existing_status = instances
+                .get(&instance_id)
+                .map(|existing| existing.health_status.clone());
+            let mut instance_info = service_info.clone();
+            match existing_status {
+                // 排空中或预热中的实例重新注册（心跳）时保持原状态，直到被显式恢复或预热结束
+                Some(status @ (ServiceHealthStatus::Draining | ServiceHealthStatus::Warming)) => {
+                    instance_info.health_status = status
+                }
+                // 新实例在预热期内不参与路由
+                None if warmup.is_some() => {
+                    instance_info.health_status = ServiceHealthStatus::Warming;
+                }
+                _ => {}
+            }
 
-            let instance_id = req.address.clone();
-
-            match instances.insert(instance_id.clone(), service_info.clone()) {
+            match instances.insert(instance_id.clone(), instance_info) {
+                // 同一实例 ID 换了地址（例如副本迁移）时覆盖旧地址，不产生新实例
+                Some(previous) if previous.address != req.address => {
+                    tracing::warn!(
+                        service_name = %service_name,
+                        instance_id = %instance_id,
+                        previous_address = %previous.address,
+                        address = %req.address,
+                        "Service instance address changed"
+                    );
+                }
                 Some(_) => {
                     tracing::info!(
                         service_name = %service_name,
@@ -62,25 +123,113 @@ impl RegistryService for MyRegistryService {
                     tracing::info!(
                         service_name = %service_name,
                         address = %req.address,
+                        instance_id = %instance_id,
+                        instances = instances.len(),
                         "Registered new service instance"
                     );
+                    registered_new_instance = true;
+                    if let Some(delay) = warmup {
+                        self.schedule_warmup_end(&service_name, &instance_id, delay);
+                    }
+                    Self::publish_lifecycle_event(
+                        &self.reverse_connection_manager.event_bus,
+                        SERVICE_REGISTERED_EVENT,
+                        &service_name,
+                        &req.address,
+                    );
                 }
             }
+            // 新实例或从 Unhealthy 恢复的实例可能改变服务的整体健康状态，普通心跳不通知
+            if Self::aggregate_health(&self.registry, &service_name) != previous_health {
+                self.notify_health_change(&service_name);
+            }
+        }
+
+        if registered_new_instance {
+            self.notify_registration(&req.address);
         }
 
         let reply = RegisterResponse {
             success: true,
             message: "Registration successful".into(),
+            instance_id,
         };
 
         Ok(Response::new(reply))
     }
 
+    // 查询当前健康的服务列表
+    async fn list_services(
+        &self,
+        request: Request<ListServicesRequest>,
+    ) -> Result<Response<ListServicesResponse>, Status> {
+        let req = request.into_inner();
+
+        self.authorize(&req.api_key, &[]).await?;
+
+        let services = self.get_healthy_services();
+        tracing::debug!(service_count = services.len(), "Listing healthy services");
+
+        Ok(Response::new(ListServicesResponse { services }))
+    }
+
+    // 将实例标记为排空或恢复，排空中的实例不再被动态路由选中
+    async fn drain_instance(
+        &self,
+        request: Request<DrainInstanceRequest>,
+    ) -> Result<Response<DrainInstanceResponse>, Status> {
+        let req = request.into_inner();
+
+        // 限定范围的 token 只能排空或恢复允许注册的服务的实例
+        self.authorize(&req.api_key, std::slice::from_ref(&req.service_name))
+            .await?;
+
+        let status = if req.draining {
+            ServiceHealthStatus::Draining
+        } else {
+            ServiceHealthStatus::Healthy
+        };
+        if !self.set_instance_health(&req.service_name, &req.instance_id, status) {
+            return Err(Status::not_found(format!(
+                "Instance '{}' of service '{}' not found",
+                req.instance_id, req.service_name
+            )));
+        }
+
+        let message = if req.draining {
+            "Instance draining"
+        } else {
+            "Instance restored"
+        };
+        Ok(Response::new(DrainInstanceResponse {
+            success: true,
+            message: message.into(),
+        }))
+    }
+
     // 建立反向连接的双向流
     async fn establish_connection(
         &self,
         request: Request<Streaming<ConnectionMessage>>,
     ) -> Result<Response<ReceiverStream<Result<ConnectionMessage, Status>>>, Status> {
+        let peer = request.remote_addr();
+        // 在读到注册消息之前被拒绝的连接没有 token 和服务列表
+        let reject = |status: Status| {
+            self.audit_registration(
+                AuditAction::EstablishConnection,
+                peer,
+                "",
+                &[],
+                Err(&status),
+            );
+            status
+        };
+
+        // 停机排空期间拒绝新的反向连接
+        if self.reverse_connection_manager.is_draining() {
+            return Err(reject(Status::unavailable("Gateway is shutting down")));
+        }
+
         let mut inbound = request.into_inner();
         let (outbound_tx, outbound_rx) = mpsc::channel(100);
 
@@ -89,55 +238,110 @@ impl RegistryService for MyRegistryService {
             Some(Ok(msg)) => msg,
             Some(Err(e)) => {
                 tracing::error!(error = %e, "Error receiving first connection message");
-                return Err(Status::internal("Failed to receive connection message"));
+                return Err(reject(Status::internal(
+                    "Failed to receive connection message",
+                )));
             }
             None => {
                 tracing::error!("Connection stream closed immediately");
-                return Err(Status::aborted("Connection stream closed"));
+                return Err(reject(Status::aborted("Connection stream closed")));
             }
         };
 
         // 处理连接注册
-        let (connection_id, services) = match first_message.message_type {
-            Some(MessageType::Register(register)) => {
-                // 验证 Token
-                if !self.config.validate_token(&register.api_key) {
-                    return Err(Status::unauthenticated("Invalid token"));
-                }
+        let (api_key, subject, connection_id, services, weight, connection_guard) =
+            match first_message.message_type {
+                Some(MessageType::Register(register)) => {
+                    let claims = match self.authorize(&register.api_key, &register.services).await {
+                        Ok(claims) => claims,
+                        Err(status) => {
+                            self.audit_registration(
+                                AuditAction::EstablishConnection,
+                                peer,
+                                &register.api_key,
+                                &register.services,
+                                Err(&status),
+                            );
+                            return Err(status);
+                        }
+                    };
+
+                    let connection_guard =
+                        match self.check_connection_limits(&claims, &register.services) {
+                            Ok(guard) => guard,
+                            Err(status) => {
+                                self.audit_registration(
+                                    AuditAction::EstablishConnection,
+                                    peer,
+                                    &register.api_key,
+                                    &register.services,
+                                    Err(&status),
+                                );
+                                return Err(status);
+                            }
+                        };
 
-                let connection_id = if register.connection_id.is_empty() {
-                    Uuid::new_v4().to_string()
-                } else {
-                    register.connection_id
-                };
+                    let connection_id = if register.connection_id.is_empty() {
+                        Uuid::new_v4().to_string()
+                    } else {
+                        register.connection_id
+                    };
 
-                tracing::info!(
-                    connection_id = %connection_id,
-                    services = ?register.services,
-                    "Establishing reverse connection"
-                );
+                    tracing::info!(
+                        connection_id = %connection_id,
+                        services = ?register.services,
+                        weight = register.weight,
+                        "Establishing reverse connection"
+                    );
 
-                (connection_id, register.services)
-            }
-            _ => {
-                return Err(Status::invalid_argument(
-                    "First message must be a connection register",
-                ));
-            }
-        };
+                    (
+                        register.api_key,
+                        claims.subject,
+                        connection_id,
+                        register.services,
+                        register.weight,
+                        connection_guard,
+                    )
+                }
+                _ => {
+                    return Err(reject(Status::invalid_argument(
+                        "First message must be a connection register",
+                    )));
+                }
+            };
 
-        // 创建请求发送通道
-        let (request_tx, mut request_rx) = mpsc::unbounded_channel();
+        // 创建请求发送通道，容量有限，微服务消费过慢时新请求快速失败
+        let (request_tx, mut request_rx) = mpsc::channel(
+            self.reverse_connection_manager
+                .config
+                .request_channel_capacity,
+        );
 
-        // 注册反向连接
+        // 注册反向连接；入站流结束时凭通道判断连接是否已被同ID的新流替换
+        let request_sender = request_tx.downgrade();
         if let Err(e) = self
             .reverse_connection_manager
-            .register_connection(connection_id.clone(), services.clone(), request_tx)
+            .register_connection(connection_id.clone(), services.clone(), weight, request_tx)
             .await
         {
             tracing::error!(error = %e, "Failed to register reverse connection");
-            return Err(Status::internal("Failed to register connection"));
+            let status = Status::internal("Failed to register connection");
+            self.audit_registration(
+                AuditAction::EstablishConnection,
+                peer,
+                &api_key,
+                &services,
+                Err(&status),
+            );
+            return Err(status);
         }
+        self.audit_registration(
+            AuditAction::EstablishConnection,
+            peer,
+            &api_key,
+            &services,
+            Ok(()),
+        );
 
         // 发送连接确认 - 明确指导客户端使用连接ID
         let status_msg = ConnectionMessage {
@@ -165,10 +369,14 @@ impl RegistryService for MyRegistryService {
             inbound,
             (*reverse_manager).clone(),
             connection_id_clone,
+            request_sender,
             outbound_tx_for_inbound,
+            subject,
+            connection_guard,
         );
 
-        // 处理出站消息的任务
+        // 处理出站消息的任务：出站流写满时暂停从请求队列取消息，
+        // 使请求队列被填满，背压最终反映到 enqueue 的失败上
         let outbound_tx_clone = outbound_tx.clone();
         tokio::spawn(async move {
             while let Some(message) = request_rx.recv().await {
@@ -185,11 +393,98 @@ impl RegistryService for MyRegistryService {
 }
 
 impl MyRegistryService {
+    // 所有注册服务 RPC 共用的 token 校验，每次请求只校验一次 token；限定范围的 token 只能操作
+    // 允许的服务，任一服务越界则拒绝整个请求。auth_disabled 时跳过全部校验，以 token 指纹作为调用方标识
+    async fn authorize(&self, token: &str, services: &[String]) -> Result<TokenClaims, Status> {
+        if self.config.load().security.auth_disabled {
+            return Ok(TokenClaims {
+                subject: token_id(token),
+            });
+        }
+        let claims = self
+            .token_validator
+            .validate_services(token, services)
+            .await
+            .inspect_err(|e| {
+                if let AuthError::ServiceNotAllowed { service } = e {
+                    tracing::warn!(service_name = %service, "Token is not allowed to register service");
+                }
+            })?;
+        tracing::debug!(subject = %claims.subject, "Token validated");
+        Ok(claims)
+    }
+
+    // 检查反向连接的服务数量和调用方的连接数上限，通过时占用一个连接名额。
+    // 上限按 token 的 subject 统计，同一调用方换发的新 token 共用名额
+    fn check_connection_limits(
+        &self,
+        claims: &TokenClaims,
+        services: &[String],
+    ) -> Result<TokenConnectionGuard, Status> {
+        let config = self.config.load();
+        let max_services = config.reverse_connection.max_services_per_connection;
+        if max_services > 0 && services.len() > max_services {
+            tracing::warn!(
+                services = services.len(),
+                max_services,
+                "Reverse connection declares too many services"
+            );
+            return Err(Status::permission_denied(format!(
+                "A connection may declare at most {max_services} services, got {}",
+                services.len()
+            )));
+        }
+
+        let max_connections = config.reverse_connection.max_connections_per_token;
+        self.token_connections
+            .try_acquire(&claims.subject, max_connections)
+            .ok_or_else(|| {
+                tracing::warn!(
+                    subject = %claims.subject,
+                    max_connections,
+                    "Token reached its reverse connection limit"
+                );
+                Status::resource_exhausted(format!(
+                    "Token already has {max_connections} active reverse connections"
+                ))
+            })
+    }
+
+    // 当前由该调用方（TokenClaims::subject）建立的反向连接数
+    pub fn subject_connection_count(&self, subject: &str) -> usize {
+        self.token_connections.count(subject)
+    }
+
+    // 输出注册审计记录，开启 security.audit_events 时同时发布审计事件
+    fn audit_registration(
+        &self,
+        action: AuditAction,
+        peer: Option<SocketAddr>,
+        token: &str,
+        services: &[String],
+        result: Result<(), &Status>,
+    ) {
+        RegistrationAudit {
+            action,
+            peer,
+            token,
+            services,
+        }
+        .emit(
+            result,
+            &self.reverse_connection_manager.event_bus,
+            self.config.load().security.audit_events,
+        );
+    }
+
     fn spawn_inbound_message_handler(
         mut inbound: Streaming<ConnectionMessage>,
         reverse_manager: crate::services::connection::ReverseConnectionManager,
         connection_id: String,
+        request_sender: mpsc::WeakSender<ConnectionMessage>,
         outbound_tx: mpsc::Sender<Result<ConnectionMessage, Status>>,
+        subject: String,
+        connection_guard: TokenConnectionGuard,
     ) {
         tokio::spawn(async move {
             while let Some(message_result) = inbound.next().await {
@@ -199,6 +494,8 @@ impl MyRegistryService {
                             let should_break = Self::handle_message_type(
                                 message_type,
                                 &reverse_manager,
+                                &connection_id,
+                                &subject,
                                 &outbound_tx,
                             )
                             .await;
@@ -214,17 +511,26 @@ impl MyRegistryService {
                 }
             }
 
-            reverse_manager.unregister_connection(&connection_id).await;
-            reverse_manager
-                .cleanup_connection_subscriptions(&connection_id)
-                .await;
+            if reverse_manager
+                .release_connection(&connection_id, &request_sender)
+                .await
+            {
+                reverse_manager
+                    .cleanup_connection_subscriptions(&connection_id)
+                    .await;
+            }
+            // 流结束后释放 token 的连接名额
+            drop(connection_guard);
             tracing::info!(connection_id = %connection_id, "Reverse connection closed");
         });
     }
 
+    // subject 为连接所属的调用方，可靠订阅者的 ID 只能由首次登记它的调用方使用
     async fn handle_message_type(
         message_type: MessageType,
         reverse_manager: &crate::services::connection::ReverseConnectionManager,
+        connection_id: &str,
+        subject: &str,
         outbound_tx: &mpsc::Sender<Result<ConnectionMessage, Status>>,
     ) -> bool {
         match message_type {
@@ -234,12 +540,43 @@ impl MyRegistryService {
             }
             MessageType::Heartbeat(heartbeat) => {
                 tracing::debug!(
+                    connection_id = %connection_id,
                     heartbeat_connection_id = %heartbeat.connection_id,
                     "Received heartbeat message"
                 );
-                reverse_manager
-                    .update_heartbeat(&heartbeat.connection_id)
-                    .await;
+                // 心跳只能刷新所在连接流自己的连接，携带其他连接ID或服务名的心跳直接拒绝
+                if heartbeat.connection_id != connection_id {
+                    tracing::warn!(
+                        connection_id = %connection_id,
+                        heartbeat_connection_id = %heartbeat.connection_id,
+                        "Rejected heartbeat with a connection_id not belonging to this stream"
+                    );
+                    let status = ConnectionMessage {
+                        message_type: Some(MessageType::Status(ConnectionStatus {
+                            connection_id: connection_id.to_string(),
+                            status: StatusType::Error as i32,
+                            message: format!(
+                                "Heartbeat rejected: connection_id '{}' does not belong to this connection, use '{}'",
+                                heartbeat.connection_id, connection_id
+                            ),
+                        })),
+                    };
+                    let _ = outbound_tx.send(Ok(status)).await;
+                    return false;
+                }
+                reverse_manager.update_heartbeat(connection_id).await;
+                false
+            }
+            MessageType::Pong(pong) => {
+                reverse_manager.handle_pong(connection_id, pong).await;
+                false
+            }
+            MessageType::Ping(ping) => {
+                // 微服务也可以探测网关，直接回复
+                let pong = ConnectionMessage {
+                    message_type: Some(MessageType::Pong(Pong { nonce: ping.nonce })),
+                };
+                let _ = outbound_tx.send(Ok(pong)).await;
                 false
             }
             MessageType::Status(status) => {
@@ -265,6 +602,13 @@ impl MyRegistryService {
                 tracing::warn!("Unexpected register message in established connection");
                 false
             }
+            MessageType::Cancel(cancel) => {
+                tracing::warn!(
+                    request_id = %cancel.request_id,
+                    "Unexpected cancel message from microservice"
+                );
+                false
+            }
             MessageType::Event(event) => {
                 // 处理事件发布
                 tracing::debug!(
@@ -292,19 +636,47 @@ impl MyRegistryService {
                     "Received subscription request"
                 );
 
+                // 可靠订阅登记后开始新的投递会话，先重发之前未确认的事件
+                let reliable = subscription.reliable
+                    && subscription.action() == Action::Subscribe
+                    && reverse_manager.event_bus.reliable_delivery_enabled();
+                let subscriber_id = subscription.subscriber_id.clone();
                 tokio::spawn({
                     let reverse_manager = (*reverse_manager).clone();
+                    let outbound_tx = outbound_tx.clone();
+                    let subject = subject.to_string();
                     async move {
                         if let Err(err) = reverse_manager
-                            .handle_subscription_request(subscription)
+                            .handle_subscription_request(subscription, &subject)
                             .await
                         {
                             tracing::error!(error = %err, "Failed to handle subscription request");
+                            return;
+                        }
+                        if reliable
+                            && let Err(err) = reverse_manager.spawn_reliable_delivery(
+                                &subscriber_id,
+                                &subject,
+                                outbound_tx,
+                            )
+                        {
+                            tracing::error!(error = %err, "Failed to deliver reliable events");
                         }
                     }
                 });
                 false
             }
+            MessageType::Ack(ack) => {
+                let subscriber_id = if ack.subscriber_id.is_empty() {
+                    connection_id
+                } else {
+                    ack.subscriber_id.as_str()
+                };
+                reverse_manager
+                    .event_bus
+                    .ack_events(subscriber_id, subject, &ack.event_ids);
+                false
+            }
         }
     }
 
@@ -349,15 +721,13 @@ impl MyRegistryService {
             }
             Err(e) => {
                 tracing::error!(error = %e, "Failed to handle service request");
-                let error_response = crate::registry::ForwardResponse {
-                    request_id,
-                    status_code: 500,
-                    headers: std::collections::HashMap::new(),
-                    payload: Vec::new(),
-                    error_message: e,
-                    streaming_info,
-                    response_stream_info: None,
+                let message = if e.is_empty() {
+                    "Failed to handle service request".to_string()
+                } else {
+                    e
                 };
+                let error_response =
+                    ForwardResponseBuilder::internal_error(request_id, message, streaming_info);
                 let error_msg = ConnectionMessage {
                     message_type: Some(MessageType::Response(error_response)),
                 };
@@ -0,0 +1,142 @@
+use dashmap::DashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::service::MyRegistryService;
+use super::types::ServiceHealthStatus;
+use crate::config::Config;
+use crate::health::health_client::HealthClient;
+use crate::health::{HealthCheckRequest, health_check_response::ServingStatus};
+use crate::services::client_manager::GrpcClientManager;
+
+// 主动探测正向注册实例的 grpc.health.v1.Health/Check，
+// 连续失败达到阈值后标记为 Unhealthy，探测恢复后重新标记为 Healthy；
+// 预热中的实例首次探测成功即结束预热
+#[derive(Debug)]
+pub struct ActiveHealthChecker {
+    registry_service: Arc<MyRegistryService>,
+    client_manager: GrpcClientManager,
+    interval: Duration,
+    timeout: Duration,
+    failure_threshold: u32,
+    // 按地址记录的连续失败次数
+    failures: DashMap<String, u32>,
+}
+
+impl ActiveHealthChecker {
+    pub fn new(
+        registry_service: Arc<MyRegistryService>,
+        client_manager: GrpcClientManager,
+        config: &Config,
+    ) -> Self {
+        Self {
+            registry_service,
+            client_manager,
+            interval: config.health_check_interval(),
+            timeout: config.health_check_timeout(),
+            failure_threshold: config.health_check.failure_threshold.max(1),
+            failures: DashMap::new(),
+        }
+    }
+
+    // 启动周期性探测任务，shutdown 触发后退出
+    pub fn spawn(self: Arc<Self>, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = interval.tick() => self.check_all().await,
+                }
+            }
+            tracing::debug!("Active health checker stopped");
+        })
+    }
+
+    // 并发探测所有已注册地址，同一地址只探测一次
+    pub async fn check_all(&self) {
+        let addresses: HashSet<String> = self
+            .registry_service
+            .registry
+            .iter()
+            .flat_map(|service_entry| {
+                service_entry
+                    .value()
+                    .iter()
+                    .map(|instance| instance.value().address.clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        // 已经注销的地址不再保留失败计数
+        self.failures
+            .retain(|address, _| addresses.contains(address));
+
+        let probes = addresses.into_iter().map(|address| async move {
+            let healthy = self.probe(&address).await;
+            (address, healthy)
+        });
+        for (address, healthy) in futures::future::join_all(probes).await {
+            self.record(&address, healthy);
+        }
+    }
+
+    // 探测单个地址，未实现健康检查服务的后端只要可达即视为健康
+    async fn probe(&self, address: &str) -> bool {
+        let channel = match self.client_manager.get_or_create_client(address).await {
+            Ok(channel) => channel,
+            Err(e) => {
+                tracing::debug!(address = %address, error = %e, "Health probe failed to connect");
+                return false;
+            }
+        };
+
+        let request = HealthCheckRequest {
+            service: String::new(),
+        };
+        let result =
+            tokio::time::timeout(self.timeout, HealthClient::new(channel).check(request)).await;
+
+        let healthy = match result {
+            Ok(Ok(response)) => response.into_inner().status == ServingStatus::Serving as i32,
+            Ok(Err(status)) if status.code() == tonic::Code::Unimplemented => true,
+            Ok(Err(status)) => {
+                tracing::debug!(address = %address, status = %status, "Health probe failed");
+                false
+            }
+            Err(_) => {
+                tracing::debug!(address = %address, "Health probe timed out");
+                false
+            }
+        };
+        if healthy {
+            self.client_manager.record_success(address);
+        }
+        healthy
+    }
+
+    fn record(&self, address: &str, healthy: bool) {
+        let status = if healthy {
+            self.failures.remove(address);
+            ServiceHealthStatus::Healthy
+        } else {
+            let mut failures = self.failures.entry(address.to_string()).or_insert(0);
+            *failures += 1;
+            if *failures < self.failure_threshold {
+                return;
+            }
+            tracing::warn!(
+                address = %address,
+                consecutive_failures = *failures,
+                "Backend failed consecutive health checks"
+            );
+            ServiceHealthStatus::Unhealthy
+        };
+
+        self.registry_service.set_address_health(address, status);
+    }
+}
@@ -0,0 +1,296 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Empty, Limited};
+use hyper_util::rt::TokioIo;
+use serde::Deserialize;
+use tokio::net::TcpStream;
+use tokio::sync::{Mutex, RwLock};
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName};
+use tokio_rustls::rustls::{ClientConfig, RootCertStore, crypto};
+
+use super::auth::AuthError;
+use super::jwt::BASE64_URL;
+
+// 拉取 JWKS 的超时时间
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// JWKS 响应体的大小上限
+const MAX_JWKS_SIZE: usize = 1024 * 1024;
+
+// 两次拉取之间的最短间隔，携带伪造 kid 的请求或认证服务故障时不会反复拉取
+const MIN_FETCH_INTERVAL: Duration = Duration::from_secs(10);
+
+// JWKS 中的 RSA 公钥，n 和 e 为大端字节
+#[derive(Debug, Clone)]
+pub(crate) struct RsaKey {
+    pub kid: Option<String>,
+    pub n: Vec<u8>,
+    pub e: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct JwkSet {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Deserialize)]
+struct Jwk {
+    kty: String,
+    #[serde(default)]
+    kid: Option<String>,
+    #[serde(default)]
+    alg: Option<String>,
+    #[serde(default, rename = "use")]
+    key_use: Option<String>,
+    #[serde(default)]
+    n: Option<String>,
+    #[serde(default)]
+    e: Option<String>,
+}
+
+#[derive(Default)]
+struct CachedKeys {
+    keys: Vec<RsaKey>,
+    // 最近一次成功拉取的时间
+    fetched_at: Option<Instant>,
+    // 最近一次尝试拉取的时间，无论成功与否
+    attempted_at: Option<Instant>,
+}
+
+// 按 kid 缓存 JWKS 中的 RS256 公钥，缓存过期或遇到未知 kid 时重新拉取
+pub(crate) struct JwksCache {
+    uri: http::Uri,
+    tls: Option<TlsConnector>,
+    refresh_interval: Duration,
+    cached: RwLock<CachedKeys>,
+    // 同一时间只有一个请求去拉取，其余请求等待后直接使用新结果
+    refresh_lock: Mutex<()>,
+}
+
+impl fmt::Debug for JwksCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwksCache")
+            .field("uri", &self.uri)
+            .field("refresh_interval", &self.refresh_interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl JwksCache {
+    // https 地址必须提供 CA 文件
+    pub fn new(
+        url: &str,
+        ca_path: Option<&str>,
+        refresh_interval: Duration,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let uri: http::Uri = url
+            .parse()
+            .map_err(|e| format!("Invalid JWKS URL {url}: {e}"))?;
+        if uri.host().is_none() {
+            return Err(format!("Missing host in JWKS URL {url}").into());
+        }
+        let tls = match uri.scheme_str() {
+            Some("http") => None,
+            Some("https") => {
+                let ca_path = ca_path.ok_or_else(|| {
+                    format!("security.jwt.jwks_ca_path is required for https JWKS URL {url}")
+                })?;
+                Some(Self::tls_connector(ca_path)?)
+            }
+            _ => return Err(format!("Unsupported JWKS URL scheme: {url}").into()),
+        };
+
+        Ok(Self {
+            uri,
+            tls,
+            refresh_interval,
+            cached: RwLock::new(CachedKeys::default()),
+            refresh_lock: Mutex::new(()),
+        })
+    }
+
+    fn tls_connector(ca_path: &str) -> Result<TlsConnector, Box<dyn std::error::Error>> {
+        let pem =
+            std::fs::read(ca_path).map_err(|e| format!("Failed to read JWKS CA {ca_path}: {e}"))?;
+        let mut roots = RootCertStore::empty();
+        for cert in CertificateDer::pem_slice_iter(&pem) {
+            roots.add(cert.map_err(|e| format!("Invalid certificate in {ca_path}: {e}"))?)?;
+        }
+        if roots.is_empty() {
+            return Err(format!("No certificates found in {ca_path}").into());
+        }
+
+        let config =
+            ClientConfig::builder_with_provider(Arc::new(crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()?
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+        Ok(TlsConnector::from(Arc::new(config)))
+    }
+
+    // 查找 kid 对应的公钥；token 没有 kid 时只有 JWKS 中恰好一个密钥才能使用
+    pub async fn key(&self, kid: Option<&str>) -> Result<RsaKey, AuthError> {
+        if let Some(key) = self.lookup(kid, Some(self.refresh_interval)).await {
+            return Ok(key);
+        }
+
+        let _refreshing = self.refresh_lock.lock().await;
+        // 等待期间其它请求可能已经刷新
+        if let Some(key) = self.lookup(kid, Some(self.refresh_interval)).await {
+            return Ok(key);
+        }
+
+        let recently_attempted = self
+            .cached
+            .read()
+            .await
+            .attempted_at
+            .is_some_and(|at| at.elapsed() < MIN_FETCH_INTERVAL);
+        let fetch_error = if recently_attempted {
+            None
+        } else {
+            let result = self.fetch().await;
+            let mut cached = self.cached.write().await;
+            cached.attempted_at = Some(Instant::now());
+            match result {
+                Ok(keys) => {
+                    tracing::debug!(url = %self.uri, keys = keys.len(), "Refreshed JWKS");
+                    cached.keys = keys;
+                    cached.fetched_at = cached.attempted_at;
+                    None
+                }
+                Err(e) => {
+                    tracing::warn!(url = %self.uri, error = %e, "Failed to fetch JWKS");
+                    Some(e)
+                }
+            }
+        };
+
+        // 拉取失败时继续使用已缓存的密钥
+        match (self.lookup(kid, None).await, fetch_error) {
+            (Some(key), _) => Ok(key),
+            (None, Some(e)) => Err(AuthError::Unavailable(format!("Failed to fetch JWKS: {e}"))),
+            (None, None) => Err(AuthError::InvalidToken),
+        }
+    }
+
+    // max_age 为 None 时忽略缓存是否过期
+    async fn lookup(&self, kid: Option<&str>, max_age: Option<Duration>) -> Option<RsaKey> {
+        let cached = self.cached.read().await;
+        let fetched_at = cached.fetched_at?;
+        if max_age.is_some_and(|max_age| fetched_at.elapsed() > max_age) {
+            return None;
+        }
+        match kid {
+            Some(kid) => cached
+                .keys
+                .iter()
+                .find(|key| key.kid.as_deref() == Some(kid))
+                .cloned(),
+            None if cached.keys.len() == 1 => cached.keys.first().cloned(),
+            None => None,
+        }
+    }
+
+    async fn fetch(&self) -> Result<Vec<RsaKey>, String> {
+        let body = tokio::time::timeout(FETCH_TIMEOUT, self.get())
+            .await
+            .map_err(|_| "request timed out".to_string())??;
+        parse_jwks(&body)
+    }
+
+    async fn get(&self) -> Result<Bytes, String> {
+        let host = self.uri.host().unwrap_or_default();
+        let port = self
+            .uri
+            .port_u16()
+            .unwrap_or(if self.tls.is_some() { 443 } else { 80 });
+        let stream = TcpStream::connect((host, port))
+            .await
+            .map_err(|e| format!("failed to connect: {e}"))?;
+
+        match &self.tls {
+            Some(connector) => {
+                let server_name = ServerName::try_from(host.to_string())
+                    .map_err(|e| format!("invalid server name {host}: {e}"))?;
+                let stream = connector
+                    .connect(server_name, stream)
+                    .await
+                    .map_err(|e| format!("TLS handshake failed: {e}"))?;
+                self.send(TokioIo::new(stream)).await
+            }
+            None => self.send(TokioIo::new(stream)).await,
+        }
+    }
+
+    async fn send<I>(&self, io: I) -> Result<Bytes, String>
+    where
+        I: hyper::rt::Read + hyper::rt::Write + Unpin + Send + 'static,
+    {
+        let (mut sender, connection) = hyper::client::conn::http1::handshake(io)
+            .await
+            .map_err(|e| format!("HTTP handshake failed: {e}"))?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                tracing::debug!(error = %e, "JWKS connection closed with error");
+            }
+        });
+
+        let path = self
+            .uri
+            .path_and_query()
+            .map(|path| path.as_str())
+            .unwrap_or("/");
+        let authority = self
+            .uri
+            .authority()
+            .map(|authority| authority.as_str())
+            .unwrap_or_default();
+        let request = http::Request::get(path)
+            .header(http::header::HOST, authority)
+            .header(http::header::ACCEPT, "application/json")
+            .body(Empty::<Bytes>::new())
+            .map_err(|e| e.to_string())?;
+        let response = sender
+            .send_request(request)
+            .await
+            .map_err(|e| format!("request failed: {e}"))?;
+        if !response.status().is_success() {
+            return Err(format!("unexpected status {}", response.status()));
+        }
+
+        Limited::new(response.into_body(), MAX_JWKS_SIZE)
+            .collect()
+            .await
+            .map(|collected| collected.to_bytes())
+            .map_err(|e| format!("failed to read response: {e}"))
+    }
+}
+
+// 只保留可用于 RS256 签名校验的 RSA 密钥
+fn parse_jwks(body: &[u8]) -> Result<Vec<RsaKey>, String> {
+    let set: JwkSet = serde_json::from_slice(body).map_err(|e| format!("invalid JWKS: {e}"))?;
+    let keys = set
+        .keys
+        .into_iter()
+        .filter(|jwk| jwk.kty == "RSA")
+        .filter(|jwk| {
+            jwk.key_use
+                .as_deref()
+                .is_none_or(|key_use| key_use == "sig")
+        })
+        .filter(|jwk| jwk.alg.as_deref().is_none_or(|alg| alg == "RS256"))
+        .filter_map(|jwk| {
+            let n = BASE64_URL.decode(jwk.n?).ok()?;
+            let e = BASE64_URL.decode(jwk.e?).ok()?;
+            Some(RsaKey { kid: jwk.kid, n, e })
+        })
+        .collect();
+    Ok(keys)
+}
@@ -0,0 +1,208 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use base64::engine::DecodePaddingMode;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use ring::{hmac, signature};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::auth::{AuthError, StaticTokenValidator, TokenClaims, TokenValidator};
+use super::jwks::JwksCache;
+use crate::config::{JwtConfig, SecurityConfig};
+
+// JWT 各段使用的 base64url 编码，解码时兼容带填充的写法
+pub(crate) const BASE64_URL: GeneralPurpose = GeneralPurpose::new(
+    &base64::alphabet::URL_SAFE,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+#[derive(Deserialize)]
+struct JwtHeader {
+    alg: String,
+    #[serde(default)]
+    kid: Option<String>,
+}
+
+// JWT 校验器：HS256 使用共享密钥，RS256 使用 JWKS 中的公钥。
+// 配置中的静态 token 交给 fallback 校验，便于逐步迁移到 JWT
+#[derive(Debug)]
+pub struct JwtValidator {
+    hs256_key: Option<hmac::Key>,
+    jwks: Option<JwksCache>,
+    issuer: Option<String>,
+    scope_claim: String,
+    leeway: u64,
+    fallback: StaticTokenValidator,
+}
+
+impl JwtValidator {
+    pub fn new(
+        config: &JwtConfig,
+        fallback: StaticTokenValidator,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let hs256_key = (!config.hs256_secret.is_empty())
+            .then(|| hmac::Key::new(hmac::HMAC_SHA256, config.hs256_secret.as_bytes()));
+        let jwks = if config.jwks_url.is_empty() {
+            None
+        } else {
+            Some(JwksCache::new(
+                &config.jwks_url,
+                config.jwks_ca_path.as_deref(),
+                Duration::from_secs(config.jwks_refresh_interval),
+            )?)
+        };
+        if hs256_key.is_none() && jwks.is_none() {
+            return Err(
+                "security.jwt requires either hs256_secret or jwks_url to verify tokens".into(),
+            );
+        }
+
+        Ok(Self {
+            hs256_key,
+            jwks,
+            issuer: (!config.issuer.is_empty()).then(|| config.issuer.clone()),
+            scope_claim: config.scope_claim.clone(),
+            leeway: config.leeway,
+            fallback,
+        })
+    }
+
+    // 校验签名、exp、nbf 和 iss，返回 JWT 的 claims
+    async fn verify(&self, token: &str) -> Result<serde_json::Map<String, Value>, AuthError> {
+        let mut parts = token.split('.');
+        let (Some(header), Some(payload), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(reject("malformed token"));
+        };
+        let signing_input = &token.as_bytes()[..header.len() + 1 + payload.len()];
+        let header: JwtHeader = decode_json(header)?;
+        let signature = BASE64_URL
+            .decode(signature)
+            .map_err(|_| reject("malformed signature"))?;
+
+        match header.alg.as_str() {
+            "HS256" => {
+                let key = self
+                    .hs256_key
+                    .as_ref()
+                    .ok_or_else(|| reject("HS256 is not enabled"))?;
+                hmac::verify(key, signing_input, &signature)
+                    .map_err(|_| reject("invalid signature"))?;
+            }
+            "RS256" => {
+                let jwks = self
+                    .jwks
+                    .as_ref()
+                    .ok_or_else(|| reject("RS256 is not enabled"))?;
+                let key = jwks.key(header.kid.as_deref()).await?;
+                signature::RsaPublicKeyComponents {
+                    n: &key.n,
+                    e: &key.e,
+                }
+                .verify(
+                    &signature::RSA_PKCS1_2048_8192_SHA256,
+                    signing_input,
+                    &signature,
+                )
+                .map_err(|_| reject("invalid signature"))?;
+            }
+            _ => return Err(reject("unsupported algorithm")),
+        }
+
+        let claims: serde_json::Map<String, Value> = decode_json(payload)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        // 只接受有过期时间的短期 token
+        let exp = claims
+            .get("exp")
+            .and_then(numeric_date)
+            .ok_or_else(|| reject("missing exp"))?;
+        if now > exp.saturating_add(self.leeway) {
+            return Err(reject("token expired"));
+        }
+        if let Some(nbf) = claims.get("nbf") {
+            let nbf = numeric_date(nbf).ok_or_else(|| reject("malformed nbf"))?;
+            if now.saturating_add(self.leeway) < nbf {
+                return Err(reject("token not yet valid"));
+            }
+        }
+        if let Some(issuer) = &self.issuer
+            && claims.get("iss").and_then(Value::as_str) != Some(issuer)
+        {
+            return Err(reject("issuer mismatch"));
+        }
+        Ok(claims)
+    }
+
+    // 允许注册的服务名模式，claim 可以是字符串数组或空格分隔的字符串
+    fn scopes<'a>(&self, claims: &'a serde_json::Map<String, Value>) -> Vec<&'a str> {
+        match claims.get(&self.scope_claim) {
+            Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).collect(),
+            Some(Value::String(value)) => value.split_whitespace().collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl TokenValidator for JwtValidator {
+    async fn validate(&self, token: &str, service: Option<&str>) -> Result<TokenClaims, AuthError> {
+        let services = service.map(str::to_string);
+        self.validate_services(token, services.as_slice()).await
+    }
+
+    // 每次注册只验签一次，再用同一份 claims 检查全部服务
+    async fn validate_services(
+        &self,
+        token: &str,
+        services: &[String],
+    ) -> Result<TokenClaims, AuthError> {
+        match self.fallback.validate_services(token, services).await {
+            Err(AuthError::InvalidToken) => {}
+            result => return result,
+        }
+
+        let claims = self.verify(token).await?;
+        // 连接名额和可靠队列的归属都按 subject 区分，没有 sub 的 token 无法区分调用方
+        let subject = claims
+            .get("sub")
+            .and_then(Value::as_str)
+            .filter(|sub| !sub.is_empty())
+            .ok_or_else(|| reject("missing sub"))?
+            .to_string();
+        let scopes = self.scopes(&claims);
+        if let Some(service) = services.iter().find(|service| {
+            !scopes
+                .iter()
+                .any(|pattern| SecurityConfig::pattern_matches(pattern, service))
+        }) {
+            return Err(AuthError::ServiceNotAllowed {
+                service: service.clone(),
+            });
+        }
+        Ok(TokenClaims { subject })
+    }
+}
+
+fn reject(reason: &str) -> AuthError {
+    tracing::debug!(reason, "JWT rejected");
+    AuthError::InvalidToken
+}
+
+fn decode_json<T: serde::de::DeserializeOwned>(segment: &str) -> Result<T, AuthError> {
+    let bytes = BASE64_URL
+        .decode(segment)
+        .map_err(|_| reject("malformed segment"))?;
+    serde_json::from_slice(&bytes).map_err(|_| reject("malformed JSON"))
+}
+
+// exp、nbf 为秒级时间戳，允许带小数
+fn numeric_date(value: &Value) -> Option<u64> {
+    value
+        .as_u64()
+        .or_else(|| value.as_f64().filter(|v| *v >= 0.0).map(|v| v as u64))
+}
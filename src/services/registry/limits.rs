@@ -0,0 +1,45 @@
+use dashmap::DashMap;
+use std::sync::Arc;
+
+// 按调用方统计的反向连接数，键为 TokenClaims::subject，不保存 token 原文
+#[derive(Debug, Default)]
+pub struct TokenConnections {
+    counts: Arc<DashMap<String, usize>>,
+}
+
+impl TokenConnections {
+    // 占用调用方的一个连接名额，已达到上限时返回 None；limit 为 0 表示不限制
+    pub fn try_acquire(&self, subject: &str, limit: usize) -> Option<TokenConnectionGuard> {
+        let key = subject.to_string();
+        let mut count = self.counts.entry(key.clone()).or_insert(0);
+        if limit > 0 && *count >= limit {
+            return None;
+        }
+        *count += 1;
+        Some(TokenConnectionGuard {
+            counts: self.counts.clone(),
+            key,
+        })
+    }
+
+    // 调用方当前占用的连接数
+    pub fn count(&self, subject: &str) -> usize {
+        self.counts.get(subject).map(|count| *count).unwrap_or(0)
+    }
+}
+
+// 连接名额，反向连接的流结束时释放
+#[derive(Debug)]
+pub struct TokenConnectionGuard {
+    counts: Arc<DashMap<String, usize>>,
+    key: String,
+}
+
+impl Drop for TokenConnectionGuard {
+    fn drop(&mut self) {
+        self.counts.remove_if_mut(&self.key, |_, count| {
+            *count = count.saturating_sub(1);
+            *count == 0
+        });
+    }
+}
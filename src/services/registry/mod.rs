@@ -4,11 +4,33 @@
 //! - `types`: Data structures and type definitions
 //! - `service`: Core service logic and methods
 //! - `grpc_impl`: gRPC trait implementation
+//! - `health_check`: Active health probing of registered backends
+//! - `snapshot`: Registry snapshot persistence for restart recovery
+//! - `audit`: Audit records for registration and reverse connection attempts
+//! - `limits`: Per-caller (token subject) reverse connection accounting
+//! - `auth`: Pluggable token validation
+//! - `jwt`: JWT bearer token validation (HS256 / RS256 via JWKS)
+//! - `jwks`: JWKS key cache used by the JWT validator
 
+pub mod audit;
+pub mod auth;
 pub mod grpc_impl;
+pub mod health_check;
+mod jwks;
+pub mod jwt;
+pub mod limits;
 pub mod service;
+pub mod snapshot;
 pub mod types;
 
 // Re-export public types for easier access
+pub use audit::REGISTRATION_AUDIT_EVENT;
+pub use auth::{AuthError, StaticTokenValidator, TokenClaims, TokenValidator};
+pub use health_check::ActiveHealthChecker;
+pub use jwt::JwtValidator;
 pub use service::MyRegistryService;
-pub use types::{ServiceHealthStatus, ServiceInfo, ServiceRegistry};
+pub use snapshot::{InstanceSnapshot, RegistrySnapshot};
+pub use types::{
+    SERVICE_EXPIRED_EVENT, SERVICE_REGISTERED_EVENT, SERVICE_UNREGISTERED_EVENT,
+    ServiceHealthStatus, ServiceInfo, ServiceRegistry, TIER_METADATA_KEY,
+};
@@ -2,69 +2,219 @@ use dashmap::DashMap;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
+use tokio::sync::{broadcast, watch};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
 
-use super::types::{ServiceHealthStatus, ServiceInfo, ServiceInstances, ServiceRegistry};
-use crate::config::Config;
+use super::auth::{StaticTokenValidator, TokenValidator};
+use super::limits::TokenConnections;
+use super::snapshot::RegistrySnapshot;
+use super::types::{
+    HealthWatchers, SERVICE_EXPIRED_EVENT, SERVICE_UNREGISTERED_EVENT, ServiceHealthStatus,
+    ServiceInfo, ServiceInstances, ServiceRegistry,
+};
+use crate::config::{Config, SharedConfig};
+use crate::registry::EventMessage;
 use crate::services::connection::{ReverseConnectionConfig, ReverseConnectionManager};
+use crate::services::event::EventBus;
+use crate::services::router::extractor::{extract_service_name, resolve_alias};
 
 // 定义的服务实现
 #[derive(Debug)]
 pub struct MyRegistryService {
     pub registry: ServiceRegistry,
-    pub config: Config,
+    // 运行中的配置，热更新后 token 校验等立即使用新值
+    pub config: SharedConfig,
     pub reverse_connection_manager: Arc<ReverseConnectionManager>,
+    // 服务健康状态变更通知（携带服务名）
+    health_notifier: broadcast::Sender<String>,
+    // 按服务的整体健康状态通道，供 watch_health 订阅
+    health_watchers: HealthWatchers,
+    // 正向注册新实例的通知（携带实例地址）
+    registration_notifier: broadcast::Sender<String>,
+    // 每个 token 当前建立的反向连接数
+    pub(crate) token_connections: TokenConnections,
+    // 注册、建立反向连接和查询服务时的 token 校验器
+    pub(crate) token_validator: Arc<dyn TokenValidator>,
+    // 定期快照任务及其停止信号，停机写最后一次快照前先停止
+    snapshot_tracker: TaskTracker,
+    snapshot_shutdown: CancellationToken,
 }
 
 impl MyRegistryService {
     pub fn new(config: Config) -> Self {
-        // 创建反向连接管理器配置
-        let reverse_config = ReverseConnectionConfig {
-            heartbeat_timeout: Duration::from_secs(config.reverse_connection.heartbeat_timeout),
-            request_timeout: Duration::from_secs(config.reverse_connection.request_timeout),
-            cleanup_interval: Duration::from_secs(config.reverse_connection.cleanup_interval),
-            max_pending_requests: config.reverse_connection.max_pending_requests,
-        };
-
+        let reverse_config = Self::reverse_connection_config(&config);
         let registry: ServiceRegistry = Arc::new(DashMap::new());
         let event_config = config.event.clone();
+        let (health_notifier, _) = broadcast::channel(256);
+        let (registration_notifier, _) = broadcast::channel(256);
 
+        let snapshot_path = config.persistence.snapshot_path.clone();
+        let heartbeat_timeout = config.heartbeat_timeout();
+        let config = SharedConfig::new(config);
         let service = Self {
             registry: registry.clone(),
+            token_validator: Arc::new(StaticTokenValidator::new(config.clone())),
             config,
             reverse_connection_manager: Arc::new(ReverseConnectionManager::new(
                 reverse_config,
                 Some(registry),
                 event_config,
             )),
+            health_notifier,
+            health_watchers: Arc::new(DashMap::new()),
+            registration_notifier,
+            token_connections: TokenConnections::default(),
+            snapshot_tracker: TaskTracker::new(),
+            snapshot_shutdown: CancellationToken::new(),
         };
 
+        // 从快照恢复正向注册的服务，并定期写入新快照
+        if let Some(path) = snapshot_path {
+            service.restore_from_snapshot(&path);
+            service.start_snapshot_task(path);
+        }
+
         // 启动定期清理任务
         let registry_clone = service.registry.clone();
-        let heartbeat_timeout = service.config.heartbeat_timeout();
+        let event_bus = service.reverse_connection_manager.event_bus.clone();
+        let health_watchers = service.health_watchers.clone();
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(heartbeat_timeout);
             loop {
                 interval.tick().await;
                 tracing::debug!("Executing service expiration check...");
-                Self::cleanup_expired_services(&registry_clone, heartbeat_timeout).await;
+                Self::cleanup_expired_services(
+                    &registry_clone,
+                    &event_bus,
+                    &health_watchers,
+                    heartbeat_timeout,
+                )
+                .await;
             }
         });
 
         service
     }
 
+    // 替换默认的 token 校验器，例如接入外部认证服务或 JWT 校验
+    pub fn with_token_validator(mut self, validator: Arc<dyn TokenValidator>) -> Self {
+        self.token_validator = validator;
+        self
+    }
+
+    // 由网关配置生成反向连接管理器配置，秒数转换为时长，并带上路由相关的设置
+    pub fn reverse_connection_config(config: &Config) -> ReverseConnectionConfig {
+        ReverseConnectionConfig {
+            heartbeat_timeout: Duration::from_secs(config.reverse_connection.heartbeat_timeout),
+            request_timeout: Duration::from_secs(config.reverse_connection.request_timeout),
+            cleanup_interval: Duration::from_secs(config.reverse_connection.cleanup_interval),
+            max_pending_requests: config.reverse_connection.max_pending_requests,
+            stream_idle_timeout: Duration::from_secs(config.reverse_connection.stream_idle_timeout),
+            request_channel_capacity: config.reverse_connection.request_channel_capacity.max(1),
+            service_timeouts: config
+                .router
+                .per_service_timeouts
+                .iter()
+                .map(|(service, secs)| (service.clone(), Duration::from_secs(*secs)))
+                .collect(),
+            max_body_size: config.router.max_body_size,
+            service_max_body_sizes: config.router.per_service_max_body_sizes.clone(),
+            use_full_service_name: config.router.use_full_service_name,
+            gzip_payload: config.reverse_connection.gzip_payload,
+            gzip_min_size: config.compression.min_size,
+            affinity_header: config.router.affinity_header(),
+            stickiness_window: config.router.stickiness_window(),
+            stickiness_header: config.router.stickiness_header(),
+            ping_interval: Duration::from_secs(config.reverse_connection.ping_interval),
+            slow_request_threshold: config.router.slow_request_threshold(),
+            max_response_size: config.reverse_connection.max_response_size,
+            service_aliases: config.router.service_aliases.clone(),
+            rewrite_aliased_path: config.router.rewrite_aliased_path,
+            max_connection_age: (config.reverse_connection.max_connection_age > 0)
+                .then(|| Duration::from_secs(config.reverse_connection.max_connection_age)),
+            load_balance_strategy: config.router.load_balance_strategy,
+            reconcile_interval: (config.reverse_connection.reconcile_interval > 0)
+                .then(|| Duration::from_secs(config.reverse_connection.reconcile_interval)),
+            max_in_flight_per_connection: (config.reverse_connection.max_in_flight_per_connection
+                > 0)
+            .then_some(config.reverse_connection.max_in_flight_per_connection),
+            saturation_queue_wait: (config.reverse_connection.saturation_queue_wait_ms > 0)
+                .then(|| Duration::from_millis(config.reverse_connection.saturation_queue_wait_ms)),
+            saturation_queue_capacity: config.reverse_connection.saturation_queue_capacity,
+        }
+    }
+
+    fn restore_from_snapshot(&self, path: &str) {
+        let grace_period =
+            Duration::from_secs(self.config.load().persistence.snapshot_grace_period);
+        match RegistrySnapshot::load(path) {
+            Ok(snapshot) => {
+                let restored = self.restore_snapshot(snapshot, grace_period);
+                tracing::info!(
+                    path = %path,
+                    restored_instances = restored,
+                    grace_secs = grace_period.as_secs(),
+                    "Restored service registry from snapshot, instances unverified until next heartbeat"
+                );
+            }
+            Err(e) => {
+                tracing::warn!(path = %path, error = %e, "Failed to load registry snapshot");
+            }
+        }
+    }
+
+    fn start_snapshot_task(&self, path: String) {
+        let registry = self.registry.clone();
+        let reverse_manager = self.reverse_connection_manager.clone();
+        let snapshot_interval =
+            Duration::from_secs(self.config.load().persistence.snapshot_interval);
+        let shutdown = self.snapshot_shutdown.clone();
+        self.snapshot_tracker.spawn(async move {
+            let mut interval = tokio::time::interval(snapshot_interval);
+            interval.tick().await;
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = interval.tick() => {}
+                }
+                let snapshot = RegistrySnapshot::capture(&registry, &reverse_manager);
+                if let Err(e) = snapshot.save(&path) {
+                    tracing::warn!(path = %path, error = %e, "Failed to write registry snapshot");
+                }
+            }
+        });
+    }
+
+    // 停止定期快照任务并等待正在进行的写入完成，之后的快照写入不会再被覆盖
+    pub async fn stop_snapshot_task(&self) {
+        self.snapshot_shutdown.cancel();
+        self.snapshot_tracker.close();
+        self.snapshot_tracker.wait().await;
+    }
+
     // 处理通过反向连接接收到的服务请求
     pub async fn handle_service_request(
         reverse_manager: Arc<ReverseConnectionManager>,
         request: crate::registry::ForwardRequest,
     ) -> Result<crate::registry::ForwardResponse, String> {
-        // 从方法路径中提取服务名
-        let service_name = Self::extract_service_name(&request.method_path)?;
+        // 从方法路径中提取服务名，规则与动态路由器一致，包括服务别名
+        let config = &reverse_manager.config;
+        let aliased_path = resolve_alias(&request.method_path, &config.service_aliases);
+        let service_name = extract_service_name(
+            aliased_path.as_deref().unwrap_or(&request.method_path),
+            config.use_full_service_name,
+        )
+        .map_err(|e| e.to_string())?;
+        let method_path = match aliased_path {
+            Some(aliased_path) if config.rewrite_aliased_path => aliased_path,
+            _ => request.method_path,
+        };
 
         tracing::info!(
             request_id = %request.request_id,
             service_name = %service_name,
-            method_path = %request.method_path,
+            method_path = %method_path,
             "Forwarding request to target service"
         );
 
@@ -73,35 +223,47 @@ impl MyRegistryService {
             .send_request_with_id(
                 &request.request_id,
                 &service_name,
-                &request.method_path,
+                &method_path,
                 request.headers,
                 request.payload,
             )
             .await
     }
 
-    // 从方法路径中提取服务名
-    fn extract_service_name(method_path: &str) -> Result<String, String> {
-        let parts: Vec<&str> = method_path.trim_start_matches('/').split('/').collect();
-        if parts.is_empty() {
-            return Err("Invalid method path format".to_string());
-        }
-
-        let service_part = parts[0];
-        let service_parts: Vec<&str> = service_part.split('.').collect();
+    // 发布注册表生命周期事件，没有订阅者时直接忽略
+    pub(crate) fn publish_lifecycle_event(
+        event_bus: &EventBus,
+        event_type: &str,
+        service_name: &str,
+        address: &str,
+    ) {
+        let event = EventMessage {
+            event_type: event_type.to_string(),
+            publisher_id: "registry".to_string(),
+            metadata: HashMap::from([
+                ("service_name".to_string(), service_name.to_string()),
+                ("address".to_string(), address.to_string()),
+            ]),
+            ..Default::default()
+        };
 
-        if service_parts.len() < 2 {
-            return Err("Invalid service path format".to_string());
+        if let Err(e) = event_bus.publish_event_sync(event) {
+            tracing::debug!(
+                event_type = %event_type,
+                service_name = %service_name,
+                error = %e,
+                "Registry lifecycle event not delivered"
+            );
         }
-
-        // 返回完整的服务名称（包含包名），而不是只返回第一部分
-        // 例如：从 "amwaybot.RecommendationService" 返回 "amwaybot.RecommendationService"
-        // 而不是只返回 "amwaybot"
-        Ok(service_part.to_string())
     }
 
     // 清理过期的服务实例
-    async fn cleanup_expired_services(registry: &ServiceRegistry, timeout: Duration) {
+    async fn cleanup_expired_services(
+        registry: &ServiceRegistry,
+        event_bus: &EventBus,
+        health_watchers: &HealthWatchers,
+        timeout: Duration,
+    ) {
         let now = SystemTime::now();
         let mut expired_instances = Vec::new();
 
@@ -148,12 +310,18 @@ impl MyRegistryService {
                 let instances = service_entry.clone();
                 drop(service_entry);
 
-                if instances.remove(&instance_id).is_some() {
+                if let Some((_, service_info)) = instances.remove(&instance_id) {
                     tracing::info!(
                         service_name = %service_name,
                         instance_id = %instance_id,
                         "Removed expired service instance from registry"
                     );
+                    Self::publish_lifecycle_event(
+                        event_bus,
+                        SERVICE_EXPIRED_EVENT,
+                        &service_name,
+                        &service_info.address,
+                    );
                 }
 
                 if instances.is_empty() {
@@ -165,6 +333,7 @@ impl MyRegistryService {
                         "All instances expired, removed service from registry"
                     );
                 }
+                Self::publish_health(registry, health_watchers, &service_name);
             }
         }
     }
@@ -188,6 +357,90 @@ impl MyRegistryService {
         healthy
     }
 
+    // 订阅服务健康状态变更通知
+    pub fn subscribe_health_changes(&self) -> broadcast::Receiver<String> {
+        self.health_notifier.subscribe()
+    }
+
+    // 订阅服务的整体健康状态：有健康实例时为 Healthy，实例状态一致时为该状态，否则为 Unhealthy；
+    // 未注册的服务为 Unknown。服务被完全移除时推送 Unknown 并关闭通道
+    pub fn watch_health(&self, service_name: &str) -> watch::Receiver<ServiceHealthStatus> {
+        self.health_watchers
+            .entry(service_name.to_string())
+            .or_insert_with(|| {
+                watch::channel(Self::aggregate_health(&self.registry, service_name)).0
+            })
+            .subscribe()
+    }
+
+    pub(crate) fn aggregate_health(
+        registry: &ServiceRegistry,
+        service_name: &str,
+    ) -> ServiceHealthStatus {
+        let Some(instances) = registry.get(service_name).map(|entry| entry.clone()) else {
+            return ServiceHealthStatus::Unknown;
+        };
+        let mut statuses = instances
+            .iter()
+            .map(|instance| instance.value().health_status.clone());
+        let Some(first) = statuses.next() else {
+            return ServiceHealthStatus::Unknown;
+        };
+        let mut aggregate = first;
+        for status in statuses {
+            if status == ServiceHealthStatus::Healthy {
+                return ServiceHealthStatus::Healthy;
+            }
+            if status != aggregate && aggregate != ServiceHealthStatus::Healthy {
+                aggregate = ServiceHealthStatus::Unhealthy;
+            }
+        }
+        aggregate
+    }
+
+    // 重新计算服务的整体健康状态，发生变化时通知 watch_health 的订阅者
+    fn publish_health(registry: &ServiceRegistry, watchers: &HealthWatchers, service_name: &str) {
+        if !registry.contains_key(service_name) {
+            if let Some((_, sender)) = watchers.remove(service_name) {
+                sender.send_replace(ServiceHealthStatus::Unknown);
+            }
+            return;
+        }
+
+        let status = Self::aggregate_health(registry, service_name);
+        if let Some(sender) = watchers.get(service_name) {
+            sender.send_if_modified(|current| {
+                if *current == status {
+                    return false;
+                }
+                tracing::debug!(
+                    service_name = %service_name,
+                    old_status = ?current,
+                    new_status = ?status,
+                    "Service health transition"
+                );
+                *current = status;
+                true
+            });
+        }
+    }
+
+    pub(crate) fn notify_health_change(&self, service_name: &str) {
+        // 没有订阅者时发送失败是正常的
+        let _ = self.health_notifier.send(service_name.to_string());
+        Self::publish_health(&self.registry, &self.health_watchers, service_name);
+    }
+
+    // 订阅正向注册新实例的通知
+    pub fn subscribe_registrations(&self) -> broadcast::Receiver<String> {
+        self.registration_notifier.subscribe()
+    }
+
+    pub(crate) fn notify_registration(&self, address: &str) {
+        // 没有订阅者时发送失败是正常的
+        let _ = self.registration_notifier.send(address.to_string());
+    }
+
     // 获取服务信息（返回第一个实例）
     pub fn get_service_info(&self, service_name: &str) -> Option<ServiceInfo> {
         self.registry
@@ -213,6 +466,7 @@ impl MyRegistryService {
                     new_status = ?status,
                     "Updated health status for all service instances"
                 );
+                self.notify_health_change(service_name);
             }
             updated
         } else {
@@ -220,14 +474,153 @@ impl MyRegistryService {
         }
     }
 
+    // 查找指定地址在这些服务下已注册的实例 ID，用于没有携带实例 ID 的重复注册（心跳）
+    pub(crate) fn find_instance_id(
+        &self,
+        service_names: &[String],
+        address: &str,
+    ) -> Option<String> {
+        service_names.iter().find_map(|service_name| {
+            let instances = self.registry.get(service_name)?.clone();
+            instances
+                .iter()
+                .find(|instance| instance.value().address == address)
+                .map(|instance| instance.key().clone())
+        })
+    }
+
+    // 按实例 ID 查找实例，找不到时按注册地址查找
+    fn resolve_instance_id(instances: &ServiceInstances, instance_id: &str) -> Option<String> {
+        if instances.contains_key(instance_id) {
+            return Some(instance_id.to_string());
+        }
+        instances
+            .iter()
+            .find(|instance| instance.value().address == instance_id)
+            .map(|instance| instance.key().clone())
+    }
+
+    // 更新单个实例（实例 ID 或注册地址）的健康状态，实例不存在时返回 false
+    pub fn set_instance_health(
+        &self,
+        service_name: &str,
+        instance_id: &str,
+        status: ServiceHealthStatus,
+    ) -> bool {
+        let Some(service_entry) = self.registry.get(service_name) else {
+            return false;
+        };
+        let instances = service_entry.clone();
+        drop(service_entry);
+
+        let Some(mut instance) = Self::resolve_instance_id(&instances, instance_id)
+            .and_then(|instance_id| instances.get_mut(&instance_id))
+        else {
+            return false;
+        };
+        instance.health_status = status.clone();
+        drop(instance);
+
+        tracing::info!(
+            service_name = %service_name,
+            instance_id = %instance_id,
+            new_status = ?status,
+            "Updated health status for service instance"
+        );
+        self.notify_health_change(service_name);
+        true
+    }
+
+    // 预热期结束后将仍处于 Warming 的实例标记为 Healthy，期间已被健康检查或运维改变状态的实例不受影响
+    pub(crate) fn schedule_warmup_end(
+        &self,
+        service_name: &str,
+        instance_id: &str,
+        delay: Duration,
+    ) {
+        let registry = self.registry.clone();
+        let health_notifier = self.health_notifier.clone();
+        let health_watchers = self.health_watchers.clone();
+        let service_name = service_name.to_string();
+        let instance_id = instance_id.to_string();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            let Some(instances) = registry.get(&service_name).map(|entry| entry.clone()) else {
+                return;
+            };
+            let warmed = instances.get_mut(&instance_id).is_some_and(|mut instance| {
+                if instance.health_status != ServiceHealthStatus::Warming {
+                    return false;
+                }
+                instance.health_status = ServiceHealthStatus::Healthy;
+                true
+            });
+            if !warmed {
+                return;
+            }
+
+            tracing::info!(
+                service_name = %service_name,
+                instance_id = %instance_id,
+                "Service instance warmup period elapsed, now routable"
+            );
+            let _ = health_notifier.send(service_name.clone());
+            Self::publish_health(&registry, &health_watchers, &service_name);
+        });
+    }
+
+    // 更新指定地址的所有实例健康状态，返回状态发生变化的实例数
+    pub(crate) fn set_address_health(&self, address: &str, status: ServiceHealthStatus) -> usize {
+        let mut changed_services = Vec::new();
+
+        for service_entry in self.registry.iter() {
+            for mut instance in service_entry.value().iter_mut() {
+                let info = instance.value_mut();
+                // 排空中的实例由运维显式控制，不被探测结果覆盖
+                if info.address == address
+                    && info.health_status != status
+                    && info.health_status != ServiceHealthStatus::Draining
+                {
+                    info.health_status = status.clone();
+                    changed_services.push(service_entry.key().clone());
+                }
+            }
+        }
+
+        for service_name in &changed_services {
+            tracing::info!(
+                service_name = %service_name,
+                address = %address,
+                new_status = ?status,
+                "Updated health status for service instance"
+            );
+            self.notify_health_change(service_name);
+        }
+        changed_services.len()
+    }
+
     // 注销服务（移除全部实例）
     pub fn unregister_service(&self, service_name: &str) -> bool {
         if let Some((_name, instances)) = self.registry.remove(service_name) {
+            let addresses: Vec<String> = instances
+                .iter()
+                .map(|instance| instance.value().address.clone())
+                .collect();
             instances.clear();
             tracing::info!(
                 service_name = %service_name,
                 "Unregistered service and cleared all instances"
             );
+
+            for address in addresses {
+                Self::publish_lifecycle_event(
+                    &self.reverse_connection_manager.event_bus,
+                    SERVICE_UNREGISTERED_EVENT,
+                    service_name,
+                    &address,
+                );
+            }
+            self.notify_health_change(service_name);
             true
         } else {
             false
@@ -0,0 +1,144 @@
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use super::service::MyRegistryService;
+use super::types::{ServiceHealthStatus, ServiceInfo, ServiceRegistry};
+use crate::services::connection::ReverseConnectionManager;
+
+// 注册表快照，只包含通过 Register 注册的正向连接实例
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegistrySnapshot {
+    // 服务名 -> 实例列表
+    pub services: BTreeMap<String, Vec<InstanceSnapshot>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceSnapshot {
+    pub instance_id: String,
+    pub address: String,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    // 排空状态由运维显式设置，重启后保留
+    #[serde(default)]
+    pub draining: bool,
+}
+
+impl RegistrySnapshot {
+    // 采集注册表快照，反向连接无法恢复，不写入快照
+    pub fn capture(registry: &ServiceRegistry, reverse_manager: &ReverseConnectionManager) -> Self {
+        let mut services = BTreeMap::new();
+
+        for service_entry in registry.iter() {
+            let instances: Vec<InstanceSnapshot> = service_entry
+                .value()
+                .iter()
+                .filter(|instance| {
+                    !reverse_manager
+                        .connections_by_id
+                        .contains_key(instance.key())
+                })
+                .map(|instance| InstanceSnapshot {
+                    instance_id: instance.key().clone(),
+                    address: instance.value().address.clone(),
+                    metadata: instance.value().metadata.clone(),
+                    draining: instance.value().health_status == ServiceHealthStatus::Draining,
+                })
+                .collect();
+
+            if !instances.is_empty() {
+                services.insert(service_entry.key().clone(), instances);
+            }
+        }
+
+        Self { services }
+    }
+
+    // 写入文件，先写临时文件再重命名，避免进程中断留下半个文件；
+    // 每次写入使用不同的临时文件，并发写入时不会互相截断
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        static NEXT_TMP: AtomicU64 = AtomicU64::new(0);
+
+        let path = path.as_ref();
+        let json = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+
+        let tmp_path = path.with_extension(format!(
+            "{}.{}.tmp",
+            std::process::id(),
+            NEXT_TMP.fetch_add(1, Ordering::Relaxed)
+        ));
+        if let Err(e) = std::fs::write(&tmp_path, json) {
+            let _ = std::fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+        std::fs::rename(&tmp_path, path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&tmp_path);
+        })
+    }
+
+    // 读取快照文件，文件不存在时返回空快照
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let json = match std::fs::read(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        serde_json::from_slice(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl MyRegistryService {
+    // 当前注册表的快照
+    pub fn snapshot(&self) -> RegistrySnapshot {
+        RegistrySnapshot::capture(&self.registry, &self.reverse_connection_manager)
+    }
+
+    // 从快照恢复实例，返回恢复的实例数
+    //
+    // 恢复的实例立即参与路由，但心跳时间被回拨到只剩 grace_period，
+    // 未在宽限期内重新注册的实例会被过期清理任务移除
+    pub fn restore_snapshot(&self, snapshot: RegistrySnapshot, grace_period: Duration) -> usize {
+        let unverified_age = self
+            .config
+            .load()
+            .heartbeat_timeout()
+            .saturating_sub(grace_period);
+        let last_heartbeat = SystemTime::now()
+            .checked_sub(unverified_age)
+            .unwrap_or_else(SystemTime::now);
+
+        let mut restored = 0;
+        for (service_name, instances) in snapshot.services {
+            let entry = self
+                .registry
+                .entry(service_name)
+                .or_insert_with(|| Arc::new(DashMap::new()))
+                .clone();
+
+            for instance in instances {
+                let health_status = if instance.draining {
+                    ServiceHealthStatus::Draining
+                } else {
+                    ServiceHealthStatus::Healthy
+                };
+                // 已经重新注册的实例以最新注册信息为准
+                entry
+                    .entry(instance.instance_id)
+                    .or_insert_with(|| ServiceInfo {
+                        address: instance.address,
+                        last_heartbeat,
+                        health_status,
+                        metadata: instance.metadata,
+                    });
+                restored += 1;
+            }
+        }
+
+        restored
+    }
+}
@@ -1,6 +1,8 @@
 use dashmap::DashMap;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::SystemTime;
+use tokio::sync::watch;
 
 // 服务注册信息
 #[derive(Debug, Clone)]
@@ -8,6 +10,21 @@ pub struct ServiceInfo {
     pub address: String,
     pub last_heartbeat: SystemTime,
     pub health_status: ServiceHealthStatus,
+    // 注册时上报的实例标签
+    pub metadata: HashMap<String, String>,
+}
+
+// 实例元数据中的故障转移层级，例如 {"tier": "1"}
+pub const TIER_METADATA_KEY: &str = "tier";
+
+impl ServiceInfo {
+    // 故障转移层级：未设置或无法解析时为 0（主层级），数值越小越优先
+    pub fn tier(&self) -> u32 {
+        self.metadata
+            .get(TIER_METADATA_KEY)
+            .and_then(|tier| tier.trim().parse().ok())
+            .unwrap_or(0)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -15,9 +32,21 @@ pub enum ServiceHealthStatus {
     Healthy,
     Unhealthy,
     Unknown,
+    // 排空中：不再接收新请求，但保留在注册表中直到显式恢复或心跳过期
+    Draining,
+    // 预热中：新注册的实例在预热期结束或首次主动健康检查通过前不参与路由
+    Warming,
 }
 
 pub type ServiceInstances = Arc<DashMap<String, ServiceInfo>>;
 
 // 定义增强的服务注册表（服务名 -> 服务实例集合）
 pub type ServiceRegistry = Arc<DashMap<String, ServiceInstances>>;
+
+// 服务整体健康状态的 watch 通道（服务名 -> 发送端）
+pub type HealthWatchers = Arc<DashMap<String, watch::Sender<ServiceHealthStatus>>>;
+
+// 注册表生命周期事件类型，元数据中携带 service_name 和 address
+pub const SERVICE_REGISTERED_EVENT: &str = "registry.service.registered";
+pub const SERVICE_UNREGISTERED_EVENT: &str = "registry.service.unregistered";
+pub const SERVICE_EXPIRED_EVENT: &str = "registry.service.expired";
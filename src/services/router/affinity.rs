@@ -0,0 +1,45 @@
+// 会话亲和：按请求头的值对候选实例做 rendezvous (HRW) 哈希，
+// 每个实例的得分只取决于键和实例本身，增删一个实例只会重新映射原本落在该实例上的键
+
+// 64 位 FNV-1a，保证不同网关进程对同一个键得到相同的结果
+fn fnv1a(bytes: &[u8], mut hash: u64) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+// splitmix64 的混合步骤，打散 FNV 输出的低位
+fn mix(mut hash: u64) -> u64 {
+    hash ^= hash >> 30;
+    hash = hash.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash ^= hash >> 27;
+    hash = hash.wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+// 键与实例的得分，权重越大得分越高的概率越大（加权 rendezvous 哈希）
+pub fn score(key: &str, instance_id: &str, weight: u32) -> f64 {
+    let hash = fnv1a(key.as_bytes(), 0xcbf2_9ce4_8422_2325);
+    let hash = mix(fnv1a(instance_id.as_bytes(), fnv1a(&[0xff], hash)));
+    // 取高 53 位映射到 (0, 1)
+    let unit = ((hash >> 11) as f64 + 0.5) / (1u64 << 53) as f64;
+    f64::from(weight.max(1)) / -unit.ln()
+}
+
+// 按得分从高到低排列候选实例，排在第一位的即为键对应的实例
+pub fn rank<T>(key: &str, candidates: &mut [T], id: impl Fn(&T) -> (&str, u32)) {
+    candidates.sort_by_cached_key(|candidate| {
+        let (instance_id, weight) = id(candidate);
+        std::cmp::Reverse(score(key, instance_id, weight).to_bits())
+    });
+}
+
+// 从请求头中取出亲和键，未配置亲和头或请求未携带时返回 None
+pub fn affinity_key<'a>(headers: &'a http::HeaderMap, header: Option<&str>) -> Option<&'a str> {
+    headers
+        .get(header?)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+}
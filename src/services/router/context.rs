@@ -0,0 +1,122 @@
+use http::{HeaderMap, HeaderValue};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+// 请求 ID 头：调用方传入时沿用，否则由网关生成；转发给后端并写入响应头
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// 调用方传入的请求 ID 超过该长度时不采用，避免日志被超长的值污染
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+// 一次路由请求的上下文：在 DynamicRouter::call 开始时创建并沿转发链路传递，
+// 请求 ID、服务名、耗时、尝试次数和最终选中的后端都记录在这里
+#[derive(Debug)]
+pub struct RequestContext {
+    pub request_id: String,
+    // 解析出的服务名，解析之前为空
+    pub service_name: String,
+    // 转发使用的方法路径（服务别名改写之后）
+    pub method_path: String,
+    pub started_at: Instant,
+    // 开启实例选择粘滞时的客户端标识（请求头或对端 IP）
+    pub client_id: Option<String>,
+    // 请求 span，日志携带 request_id、service_name 和 method_path 字段
+    span: tracing::Span,
+    attempts: AtomicU32,
+    // 最近一次尝试的后端：正向转发为实例地址，反向连接为连接 ID
+    backend: Mutex<Option<String>>,
+}
+
+impl RequestContext {
+    // 为已知服务创建上下文，生成新的请求 ID
+    pub fn new(service_name: &str, method_path: &str) -> Self {
+        let mut context = Self::with_request_id(Uuid::new_v4().to_string(), method_path);
+        context.resolve(service_name, method_path);
+        context
+    }
+
+    // 入站请求的上下文：优先使用请求头中的 x-request-id，服务名在解析后通过 resolve 填入
+    pub fn from_headers(headers: &HeaderMap, method_path: &str) -> Self {
+        let request_id = headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+            .map(str::to_string)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        Self::with_request_id(request_id, method_path)
+    }
+
+    fn with_request_id(request_id: String, method_path: &str) -> Self {
+        let span = tracing::info_span!(
+            "request",
+            request_id = %request_id,
+            service_name = tracing::field::Empty,
+            method_path = %method_path,
+        );
+        Self {
+            request_id,
+            service_name: String::new(),
+            method_path: method_path.to_string(),
+            started_at: Instant::now(),
+            client_id: None,
+            span,
+            attempts: AtomicU32::new(0),
+            backend: Mutex::new(None),
+        }
+    }
+
+    // 记录解析出的服务名和实际转发的路径
+    pub fn resolve(&mut self, service_name: &str, method_path: &str) {
+        self.service_name = service_name.to_string();
+        self.span.record("service_name", service_name);
+        if self.method_path != method_path {
+            self.method_path = method_path.to_string();
+            self.span.record("method_path", method_path);
+        }
+    }
+
+    pub fn span(&self) -> &tracing::Span {
+        &self.span
+    }
+
+    // 记录一次发往 backend 的尝试
+    pub fn record_attempt(&self, backend: &str) {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+        self.record_backend(backend);
+    }
+
+    // 记录最终采用的后端，对冲请求由先返回的一方决定
+    pub fn record_backend(&self, backend: &str) {
+        *self.backend.lock().unwrap_or_else(|e| e.into_inner()) = Some(backend.to_string());
+    }
+
+    // 发往后端的尝试次数，包括第一次
+    pub fn attempts(&self) -> u32 {
+        self.attempts.load(Ordering::Relaxed)
+    }
+
+    pub fn retries(&self) -> u32 {
+        self.attempts().saturating_sub(1)
+    }
+
+    pub fn backend(&self) -> Option<String> {
+        self.backend
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    // 把请求 ID 写入请求头或响应头
+    pub fn insert_header(&self, headers: &mut HeaderMap) {
+        if let Ok(value) = HeaderValue::from_str(&self.request_id) {
+            headers.insert(REQUEST_ID_HEADER, value);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+// gRPC 调用方的截止时间，格式为最多 8 位数字加单位，例如 100m、5S、1H
+pub const GRPC_TIMEOUT: &str = "grpc-timeout";
+
+const MAX_DIGITS: usize = 8;
+const MAX_VALUE: u64 = 99_999_999;
+
+// 解析 grpc-timeout 头，格式不合法时返回 None
+pub fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let unit = value.chars().last()?;
+    let digits = &value[..value.len() - unit.len_utf8()];
+    if digits.is_empty() || digits.len() > MAX_DIGITS || !digits.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let amount: u64 = digits.parse().ok()?;
+
+    match unit {
+        'H' => Some(Duration::from_secs(amount * 3600)),
+        'M' => Some(Duration::from_secs(amount * 60)),
+        'S' => Some(Duration::from_secs(amount)),
+        'm' => Some(Duration::from_millis(amount)),
+        'u' => Some(Duration::from_micros(amount)),
+        'n' => Some(Duration::from_nanos(amount)),
+        _ => None,
+    }
+}
+
+// 格式化为 grpc-timeout 头，选择能用 8 位数字表示的最小单位，向下取整
+pub fn format_grpc_timeout(timeout: Duration) -> String {
+    let nanos = timeout.as_nanos();
+    let units: [(u128, char); 6] = [
+        (1, 'n'),
+        (1_000, 'u'),
+        (1_000_000, 'm'),
+        (1_000_000_000, 'S'),
+        (60_000_000_000, 'M'),
+        (3_600_000_000_000, 'H'),
+    ];
+    for (scale, unit) in units {
+        let amount = nanos / scale;
+        if amount <= u128::from(MAX_VALUE) {
+            return format!("{amount}{unit}");
+        }
+    }
+    format!("{MAX_VALUE}H")
+}
+
+// 取调用方截止时间与网关配置超时中较小的一个
+pub fn effective_timeout(client_timeout: Option<Duration>, configured: Duration) -> Duration {
+    client_timeout.map_or(configured, |client| client.min(configured))
+}
+
+// 从请求头中读取调用方的截止时间
+pub fn client_timeout(headers: &http::HeaderMap) -> Option<Duration> {
+    headers
+        .get(GRPC_TIMEOUT)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_grpc_timeout)
+}
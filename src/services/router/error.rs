@@ -1,3 +1,4 @@
+use std::time::Duration;
 use thiserror::Error;
 
 // 定义路由错误类型
@@ -9,6 +10,72 @@ pub enum RouterError {
     ServiceUnavailable(String),
     #[error("Invalid path: {0}")]
     InvalidPath(String),
-    #[error("Forwarding error: {0}")]
-    ForwardingError(String),
+    // 连接后端失败或连接在请求过程中断开
+    #[error("Backend connection error: {0}")]
+    ConnectError(String),
+    // 后端在超时时间或调用方截止时间内没有响应
+    #[error("Request timed out: {0}")]
+    Timeout(String),
+    // 读取、改写请求体或构建转发请求失败
+    #[error("Request body error: {0}")]
+    BodyError(String),
+    // 后端返回了网关无法透传的响应，按 code 返回给调用方
+    #[error("Backend error ({}): {message}", code.description())]
+    BackendError { code: tonic::Code, message: String },
+    #[error("Resource exhausted: {0}")]
+    ResourceExhausted(String),
+    #[error("Reserved service: {0}")]
+    ReservedService(String),
+    #[error("Rate limit exceeded, retry after {}ms", retry_after.as_millis())]
+    RateLimited { retry_after: Duration },
+}
+
+impl RouterError {
+    // 对应的 gRPC 状态码
+    pub fn grpc_code(&self) -> tonic::Code {
+        match self {
+            RouterError::ServiceNotFound(_) => tonic::Code::NotFound,
+            RouterError::ServiceUnavailable(_) => tonic::Code::Unavailable,
+            RouterError::InvalidPath(_) => tonic::Code::InvalidArgument,
+            // 与连接失败一致按 UNAVAILABLE 返回，调用方可以按可重试错误处理
+            RouterError::ConnectError(_) | RouterError::Timeout(_) => tonic::Code::Unavailable,
+            RouterError::BodyError(_) => tonic::Code::Internal,
+            RouterError::BackendError { code, .. } => *code,
+            RouterError::ResourceExhausted(_) => tonic::Code::ResourceExhausted,
+            RouterError::ReservedService(_) => tonic::Code::Unimplemented,
+            RouterError::RateLimited { .. } => tonic::Code::ResourceExhausted,
+        }
+    }
+
+    // 错误种类，作为 google.rpc.ErrorInfo 的 reason 返回给调用方，也是转发错误指标的 reason 标签
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RouterError::ServiceNotFound(_) => "SERVICE_NOT_FOUND",
+            RouterError::ServiceUnavailable(_) => "SERVICE_UNAVAILABLE",
+            RouterError::InvalidPath(_) => "INVALID_PATH",
+            RouterError::ConnectError(_) => "CONNECT_ERROR",
+            RouterError::Timeout(_) => "TIMEOUT",
+            RouterError::BodyError(_) => "BODY_ERROR",
+            RouterError::BackendError { .. } => "BACKEND_ERROR",
+            RouterError::ResourceExhausted(_) => "RESOURCE_EXHAUSTED",
+            RouterError::ReservedService(_) => "RESERVED_SERVICE",
+            RouterError::RateLimited { .. } => "RATE_LIMITED",
+        }
+    }
+
+    // 返回给调用方的错误信息
+    pub fn message(&self) -> String {
+        match self {
+            RouterError::ServiceNotFound(msg)
+            | RouterError::ServiceUnavailable(msg)
+            | RouterError::InvalidPath(msg)
+            | RouterError::ConnectError(msg)
+            | RouterError::Timeout(msg)
+            | RouterError::BodyError(msg)
+            | RouterError::BackendError { message: msg, .. }
+            | RouterError::ResourceExhausted(msg)
+            | RouterError::ReservedService(msg) => msg.clone(),
+            RouterError::RateLimited { .. } => self.to_string(),
+        }
+    }
 }
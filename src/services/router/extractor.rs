@@ -1,8 +1,20 @@
 use super::error::RouterError;
+use crate::config::SecurityConfig;
+use std::collections::HashMap;
 use std::path::Path;
 
-// 增强的服务名解析，支持多种格式
-pub fn extract_service_name(path: &str) -> Result<String, RouterError> {
+// 网关自身提供的服务，动态路由从不把它们当作后端服务转发
+pub const GATEWAY_SERVICES: &[&str] = &[
+    "registry.RegistryService",
+    "grpc.health.v1.Health",
+    "admin.Admin",
+    "grpc.reflection.*",
+];
+
+// 从 "/package.ServiceName/Method" 中解析服务名
+// use_full_name 为 true 时返回 "package.ServiceName"，否则只返回 "ServiceName"
+// 注册表和反向连接的服务名需按同一规则登记才能匹配
+pub fn extract_service_name(path: &str, use_full_name: bool) -> Result<String, RouterError> {
     if path.is_empty() || !path.starts_with('/') {
         return Err(RouterError::InvalidPath(
             "Path must start with '/'".to_string(),
@@ -31,7 +43,9 @@ pub fn extract_service_name(path: &str) -> Result<String, RouterError> {
         .ok_or_else(|| RouterError::InvalidPath("Invalid UTF-8 in service path".to_string()))?;
 
     // 支持多种服务名格式
-    let service_name = if service_path.contains('.') {
+    let service_name = if use_full_name {
+        service_path
+    } else if service_path.contains('.') {
         // 标准格式: "package.ServiceName" -> "ServiceName"
         service_path.split('.').next_back().unwrap_or(service_path)
     } else {
@@ -45,3 +59,42 @@ pub fn extract_service_name(path: &str) -> Result<String, RouterError> {
 
     Ok(service_name.to_string())
 }
+
+// 路径中的完整服务名 "package.ServiceName"
+pub fn full_service_name(path: &str) -> &str {
+    path.trim_start_matches('/')
+        .split('/')
+        .next()
+        .unwrap_or_default()
+}
+
+// 路径是否指向网关自身的服务或配置中保留的服务，按完整服务名匹配
+pub fn is_reserved_path(path: &str, reserved: &[String]) -> bool {
+    let service = full_service_name(path);
+    GATEWAY_SERVICES
+        .iter()
+        .copied()
+        .chain(reserved.iter().map(String::as_str))
+        .any(|pattern| SecurityConfig::pattern_matches(pattern, service))
+}
+
+// 按别名把 "/old.Service/Method" 改写为 "/new.Service/Method"，服务没有别名时返回 None
+pub fn resolve_alias(path: &str, aliases: &HashMap<String, String>) -> Option<String> {
+    if aliases.is_empty() {
+        return None;
+    }
+    let (service, method) = path.strip_prefix('/')?.split_once('/')?;
+    let target = aliases.get(service)?;
+    Some(format!("/{target}/{method}"))
+}
+
+// 替换 URI 中的路径，保留 scheme、authority 和查询参数
+pub fn rewrite_path(uri: &http::Uri, path: &str) -> Option<http::Uri> {
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path.to_string(),
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse().ok()?);
+    http::Uri::from_parts(parts).ok()
+}
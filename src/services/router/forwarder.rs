@@ -1,25 +1,776 @@
+use super::affinity;
+use super::context::RequestContext;
+use super::deadline;
 use super::error::RouterError;
+use super::load_balance::{self, InFlightBody, LoadBalanceStrategy};
 use crate::config::Config;
-use crate::services::client_manager::GrpcClientManager;
-use http_body::Body;
+use crate::services::client_manager::{CircuitOpenError, GrpcClientManager};
+use crate::services::compression::{self, FrameTransform, GRPC_ACCEPT_ENCODING, GRPC_ENCODING};
+use crate::services::registry::{ServiceHealthStatus, ServiceInfo, ServiceRegistry};
+use http_body::{Body, Frame};
 use http_body_util::BodyExt;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 use tower::util::ServiceExt;
 
-// 转发 gRPC 请求到目标服务
+// 重试退避的初始间隔与上限
+const RETRY_BACKOFF_BASE: Duration = Duration::from_millis(50);
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(2);
+
+// 缓存请求体时等待下一帧的最长时间，超过后视为客户端流式调用
+const RETRY_BUFFER_IDLE_TIMEOUT: Duration = Duration::from_millis(50);
+
+// 缓存响应体时等待下一帧的最长时间，超过后视为服务端流式响应
+const RESPONSE_BUFFER_IDLE_TIMEOUT: Duration = Duration::from_millis(50);
+
+type ForwardResponse = http::Response<
+    http_body_util::combinators::UnsyncBoxBody<
+        bytes::Bytes,
+        Box<dyn std::error::Error + Send + Sync>,
+    >,
+>;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+type BoxedRequestBody = http_body_util::combinators::UnsyncBoxBody<bytes::Bytes, BoxError>;
+
+// 单次转发失败，retryable 表示是否可以安全地换实例重试
+struct AttemptError {
+    error: RouterError,
+    retryable: bool,
+}
+
+// 可在多次尝试间共享的请求体，记录是否已经向后端发送过数据
+struct SharedBody<B> {
+    inner: Arc<Mutex<Pin<Box<B>>>>,
+    streamed: Arc<AtomicBool>,
+}
+
+impl<B> Clone for SharedBody<B> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            streamed: self.streamed.clone(),
+        }
+    }
+}
+
+impl<B> SharedBody<B> {
+    fn new(body: B) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Box::pin(body))),
+            streamed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn has_streamed(&self) -> bool {
+        self.streamed.load(Ordering::Acquire)
+    }
+}
+
+impl<B> Body for SharedBody<B>
+where
+    B: Body<Data = bytes::Bytes>,
+{
+    type Data = bytes::Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let frame = inner.as_mut().poll_frame(cx);
+        if let Poll::Ready(Some(Ok(_))) = &frame {
+            self.streamed.store(true, Ordering::Release);
+        }
+        frame
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner
+            .lock()
+            .map(|inner| inner.is_end_stream())
+            .unwrap_or(false)
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner
+            .lock()
+            .map(|inner| inner.size_hint())
+            .unwrap_or_default()
+    }
+}
+
+// 转发使用的请求体：缓存在内存中的请求体每次尝试都完整重放，流式请求体只能发送一次
+enum RequestBody {
+    Buffered(bytes::Bytes),
+    Streaming(SharedBody<BoxedRequestBody>),
+}
+
+impl RequestBody {
+    // 开启重试缓存时读取请求体，不超过 limit 时完整缓存；
+    // 超过时已读取的部分与剩余请求体拼接后流式转发
+    async fn new<B>(body: B, buffer_limit: Option<usize>) -> Result<Self, RouterError>
+    where
+        B: Body<Data = bytes::Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        let mut body = body.map_err(Into::into).boxed_unsync();
+        let Some(limit) = buffer_limit else {
+            return Ok(Self::Streaming(SharedBody::new(body)));
+        };
+        // 声明的长度已经超过上限时不再读取
+        if body.size_hint().lower() > limit as u64 {
+            return Ok(Self::Streaming(SharedBody::new(body)));
+        }
+
+        let mut buffered = bytes::BytesMut::new();
+        loop {
+            // 客户端流和双向流调用可能在收到响应前不再发送数据，等待超时后改为流式转发
+            let frame = match tokio::time::timeout(RETRY_BUFFER_IDLE_TIMEOUT, body.frame()).await {
+                Ok(None) => break,
+                Ok(Some(frame)) => frame.map_err(|e| {
+                    RouterError::BodyError(format!("Failed to read request body: {e}"))
+                })?,
+                Err(_) => return Ok(Self::streaming_after(buffered, None, body)),
+            };
+            match frame.into_data() {
+                Ok(data) if buffered.len() + data.len() <= limit => {
+                    buffered.extend_from_slice(&data);
+                }
+                Ok(data) => {
+                    return Ok(Self::streaming_after(
+                        buffered,
+                        Some(Frame::data(data)),
+                        body,
+                    ));
+                }
+                // 请求 trailers 无法随缓存重放，按流式转发
+                Err(frame) => return Ok(Self::streaming_after(buffered, Some(frame), body)),
+            }
+        }
+        Ok(Self::Buffered(buffered.freeze()))
+    }
+
+    // 放弃缓存：先发送已读取的数据和当前帧，再继续转发剩余请求体
+    fn streaming_after(
+        buffered: bytes::BytesMut,
+        frame: Option<Frame<bytes::Bytes>>,
+        rest: BoxedRequestBody,
+    ) -> Self {
+        let prefix = (!buffered.is_empty())
+            .then(|| Frame::data(buffered.freeze()))
+            .into_iter()
+            .chain(frame)
+            .map(Ok);
+        let body = futures::StreamExt::chain(
+            futures::stream::iter(prefix),
+            http_body_util::BodyStream::new(rest),
+        );
+        Self::Streaming(SharedBody::new(
+            http_body_util::StreamBody::new(body).boxed_unsync(),
+        ))
+    }
+
+    // 本次尝试发送的请求体
+    fn attempt(&self) -> BoxedRequestBody {
+        match self {
+            Self::Buffered(data) => http_body_util::Full::new(data.clone())
+                .map_err(|never| match never {})
+                .boxed_unsync(),
+            Self::Streaming(body) => body.clone().boxed_unsync(),
+        }
+    }
+
+    // 只有缓存的请求体可以同时发给两个实例
+    fn can_hedge(&self) -> bool {
+        matches!(self, Self::Buffered(_))
+    }
+
+    // 流式请求体一旦发出就不能安全重放
+    fn can_replay(&self) -> bool {
+        match self {
+            Self::Buffered(_) => true,
+            Self::Streaming(body) => !body.has_streamed(),
+        }
+    }
+}
+
+// 缓存在内存中的完整响应体：先发送数据再发送 trailers。
+// 长度确定，HTTP/2 服务端据此设置 content-length
+struct BufferedBody {
+    data: Option<bytes::Bytes>,
+    trailers: Option<http::HeaderMap>,
+}
+
+impl Body for BufferedBody {
+    type Data = bytes::Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if let Some(data) = self.data.take() {
+            return Poll::Ready(Some(Ok(Frame::data(data))));
+        }
+        Poll::Ready(
+            self.trailers
+                .take()
+                .map(|trailers| Ok(Frame::trailers(trailers))),
+        )
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.data.is_none() && self.trailers.is_none()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        http_body::SizeHint::with_exact(self.data.as_ref().map_or(0, |data| data.len() as u64))
+    }
+}
+
+// 开启响应缓存时读取响应体：不超过 limit 且及时结束的响应完整缓存，trailers 随后发出；
+// 超过上限或 50ms 内没有读到下一帧（服务端流）时，已读取的部分与剩余响应体拼接后流式转发
+async fn buffer_response(response: ForwardResponse, limit: usize) -> ForwardResponse {
+    let (parts, mut body) = response.into_parts();
+    let mut buffered = bytes::BytesMut::new();
+    let mut trailers = None;
+    loop {
+        let frame = match tokio::time::timeout(RESPONSE_BUFFER_IDLE_TIMEOUT, body.frame()).await {
+            Ok(None) => break,
+            Ok(Some(frame)) => frame,
+            Err(_) => return streamed_response(parts, buffered, None, body),
+        };
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => return streamed_response(parts, buffered, Some(Err(e)), body),
+        };
+        match frame.into_data() {
+            Ok(data) if trailers.is_none() && buffered.len() + data.len() <= limit => {
+                buffered.extend_from_slice(&data);
+            }
+            Ok(data) => {
+                return streamed_response(parts, buffered, Some(Ok(Frame::data(data))), body);
+            }
+            Err(frame) => {
+                if let Ok(frame_trailers) = frame.into_trailers() {
+                    trailers = Some(frame_trailers);
+                }
+            }
+        }
+    }
+
+    let body = BufferedBody {
+        data: (!buffered.is_empty()).then(|| buffered.freeze()),
+        trailers,
+    };
+    http::Response::from_parts(parts, http_body_util::combinators::UnsyncBoxBody::new(body))
+}
+
+// 放弃缓存：先发送已读取的数据和当前帧，再继续转发剩余响应体
+fn streamed_response(
+    parts: http::response::Parts,
+    buffered: bytes::BytesMut,
+    frame: Option<Result<Frame<bytes::Bytes>, BoxError>>,
+    rest: http_body_util::combinators::UnsyncBoxBody<bytes::Bytes, BoxError>,
+) -> ForwardResponse {
+    let prefix = (!buffered.is_empty())
+        .then(|| Ok(Frame::data(buffered.freeze())))
+        .into_iter()
+        .chain(frame);
+    let body = futures::StreamExt::chain(
+        futures::stream::iter(prefix),
+        http_body_util::BodyStream::new(rest),
+    );
+    http::Response::from_parts(parts, http_body_util::StreamBody::new(body).boxed_unsync())
+}
+
+// 限制后端响应体大小，超出时丢弃剩余数据并以 RESOURCE_EXHAUSTED trailers 结束响应
+struct LimitedBody<B> {
+    inner: Pin<Box<B>>,
+    received: usize,
+    limit: usize,
+    exhausted: bool,
+}
+
+impl<B> LimitedBody<B> {
+    fn new(inner: B, limit: usize) -> Self {
+        Self {
+            inner: Box::pin(inner),
+            received: 0,
+            limit,
+            exhausted: false,
+        }
+    }
+}
+
+impl<B> Body for LimitedBody<B>
+where
+    B: Body<Data = bytes::Bytes>,
+{
+    type Data = bytes::Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if self.exhausted {
+            return Poll::Ready(None);
+        }
+
+        let frame = std::task::ready!(self.inner.as_mut().poll_frame(cx));
+        if let Some(Ok(data)) = &frame
+            && let Some(data) = data.data_ref()
+        {
+            self.received += data.len();
+            if self.received > self.limit {
+                self.exhausted = true;
+                tracing::warn!(
+                    limit = self.limit,
+                    received = self.received,
+                    "Backend response exceeded max body size"
+                );
+
+                let mut trailers = http::HeaderMap::new();
+                trailers.insert("grpc-status", http::HeaderValue::from_static("8"));
+                if let Ok(message) = http::HeaderValue::from_str(&format!(
+                    "Response body too large (max: {} bytes)",
+                    self.limit
+                )) {
+                    trailers.insert("grpc-message", message);
+                }
+                return Poll::Ready(Some(Ok(Frame::trailers(trailers))));
+            }
+        }
+
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.exhausted || self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        if self.exhausted {
+            http_body::SizeHint::with_exact(0)
+        } else {
+            self.inner.size_hint()
+        }
+    }
+}
+
+// 以此为前缀的请求头作为路由标签，例如 "x-route-version: v2" 对应标签 version=v2
+pub const ROUTE_LABEL_HEADER_PREFIX: &str = "x-route-";
+
+// 从请求头中提取路由标签
+pub fn route_labels(headers: &http::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let label = name.as_str().strip_prefix(ROUTE_LABEL_HEADER_PREFIX)?;
+            let value = value.to_str().ok()?;
+            (!label.is_empty()).then(|| (label.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+// 一次转发中选择实例的条件，各次尝试之间不变
+#[derive(Debug, Clone, Copy)]
+pub struct InstanceSelector<'a> {
+    pub service_name: &'a str,
+    pub labels: &'a [(String, String)],
+    // 没有实例匹配全部标签时是否退回到任意健康实例
+    pub fallback: bool,
+    pub affinity_key: Option<&'a str>,
+    pub strategy: LoadBalanceStrategy,
+}
+
+// 按标签选择健康实例：只考虑元数据匹配全部标签的实例，
+// 没有匹配实例时根据 fallback 决定是否退回到任意健康实例；
+// 没有会话亲和时使用 client_manager 上的轮询游标和进行中请求数按策略分摊
+pub fn select_instance(
+    registry: &ServiceRegistry,
+    client_manager: &GrpcClientManager,
+    selector: &InstanceSelector<'_>,
+    failed_addrs: &[String],
+) -> Option<String> {
+    let mut candidates = candidate_instances(
+        registry,
+        selector.service_name,
+        selector.labels,
+        selector.fallback,
+    );
+
+    // 会话亲和：按哈希得分排序，同一个键总是先选中同一个实例，重试时依次选择得分次高的实例
+    if let Some(key) = selector.affinity_key {
+        affinity::rank(key, &mut candidates, |addr| (addr.as_str(), 1));
+    } else {
+        load_balance::order(
+            selector.strategy,
+            &mut candidates,
+            &client_manager.next_instance,
+            &client_manager.in_flight,
+            |addr| (addr.as_str(), 1),
+        );
+    }
+
+    // 所有候选实例都失败过时退回到任意候选实例，单实例部署仍可重试瞬时故障
+    candidates
+        .iter()
+        .find(|addr| !failed_addrs.contains(addr))
+        .or_else(|| candidates.first())
+        .cloned()
+}
+
+// 粘滞窗口内上次为客户端选中的实例，仍然健康、匹配标签且本次请求未失败过时才沿用
+fn sticky_instance(
+    registry: &ServiceRegistry,
+    client_manager: &GrpcClientManager,
+    selector: &InstanceSelector<'_>,
+    failed_addrs: &[String],
+    client_id: &str,
+) -> Option<String> {
+    let previous = client_manager
+        .sticky
+        .get(client_id, selector.service_name)?;
+    (!failed_addrs.contains(&previous)
+        && candidate_instances(
+            registry,
+            selector.service_name,
+            selector.labels,
+            selector.fallback,
+        )
+        .contains(&previous))
+    .then_some(previous)
+}
+
+// 可以选择的健康实例地址：元数据匹配全部标签的实例，
+// 没有匹配实例时根据 fallback 决定是否退回到任意健康实例；
+// 其中只保留故障转移层级最低的实例，低层级没有健康实例时才使用高层级
+fn candidate_instances(
+    registry: &ServiceRegistry,
+    service_name: &str,
+    labels: &[(String, String)],
+    fallback: bool,
+) -> Vec<String> {
+    let Some(instances) = registry
+        .get(service_name)
+        .map(|instances| instances.clone())
+    else {
+        return Vec::new();
+    };
+
+    let healthy: Vec<_> = instances
+        .iter()
+        .filter(|instance| instance.value().health_status == ServiceHealthStatus::Healthy)
+        .map(|instance| instance.value().clone())
+        .collect();
+
+    let matching: Vec<_> = healthy
+        .iter()
+        .filter(|info| {
+            labels
+                .iter()
+                .all(|(key, value)| info.metadata.get(key) == Some(value))
+        })
+        .cloned()
+        .collect();
+
+    let candidates = if matching.is_empty() && fallback {
+        healthy
+    } else {
+        matching
+    };
+    let lowest_tier = candidates.iter().map(ServiceInfo::tier).min();
+    candidates
+        .into_iter()
+        .filter(|info| Some(info.tier()) == lowest_tier)
+        .map(|info| info.address)
+        .collect()
+}
+
+// 注册表中没有该服务时使用的兜底后端；服务已注册但没有可用实例时不兜底
+fn default_backend(
+    registry: &ServiceRegistry,
+    config: &Config,
+    service_name: &str,
+) -> Option<String> {
+    let address = config.router.default_backend()?;
+    if registry.contains_key(service_name) {
+        return None;
+    }
+    tracing::debug!(
+        service_name = %service_name,
+        default_backend = %address,
+        "Service not registered, forwarding to default backend"
+    );
+    Some(address.to_string())
+}
+
+// 第 attempt 次重试前的退避时间（指数增长）
+fn retry_backoff(attempt: u32) -> Duration {
+    RETRY_BACKOFF_BASE
+        .saturating_mul(1u32 << attempt.saturating_sub(1).min(16))
+        .min(RETRY_BACKOFF_MAX)
+}
+
+// 转发 gRPC 请求到服务的健康实例，对连接失败和超时进行带退避的重试
 pub async fn forward_request<B>(
+    registry: &ServiceRegistry,
+    client_manager: &GrpcClientManager,
+    config: &Config,
+    context: &RequestContext,
+    req: http::Request<B>,
+) -> Result<ForwardResponse, RouterError>
+where
+    B: Body<Data = bytes::Bytes> + Send + 'static,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>> + std::fmt::Debug,
+{
+    let service_name = context.service_name.as_str();
+    let (mut parts, body) = req.into_parts();
+    // 后端与网关日志使用同一个请求 ID
+    context.insert_header(&mut parts.headers);
+    // 对冲需要把同一个请求体发给两个实例，允许对冲的方法同样缓存请求体
+    let hedge_delay = config.router.hedge_delay(&context.method_path);
+    let buffer_limit = (config.router.buffer_request_for_retry || hedge_delay.is_some())
+        .then_some(config.router.retry_buffer_max_size);
+    let body = RequestBody::new(body, buffer_limit).await?;
+    let max_retries = config.router.retry_attempts;
+    let mut failed_addrs: Vec<String> = Vec::new();
+    let mut attempt = 0;
+    let labels = route_labels(&parts.headers);
+    let affinity_header = config.router.affinity_header();
+    let affinity_key = affinity::affinity_key(&parts.headers, affinity_header.as_deref());
+    // 调用方的截止时间从收到请求时开始计算，重试共用同一个截止时间
+    let client_deadline = deadline::client_timeout(&parts.headers).map(|t| Instant::now() + t);
+
+    let selector = InstanceSelector {
+        service_name,
+        labels: &labels,
+        fallback: config.router.label_route_fallback,
+        affinity_key,
+        strategy: config.router.load_balance_strategy,
+    };
+    let request = AttemptContext {
+        client_manager,
+        config,
+        parts: &parts,
+        body: &body,
+        client_deadline,
+    };
+
+    // 实例选择粘滞只对没有亲和键的请求生效
+    let sticky = config
+        .router
+        .stickiness_window()
+        .zip(context.client_id.as_deref())
+        .filter(|_| affinity_key.is_none());
+
+    loop {
+        // 每次尝试都重新从注册表解析实例，避免重试已失效的实例
+        let sticky_target = sticky.and_then(|(_, client_id)| {
+            sticky_instance(
+                registry,
+                client_manager,
+                &selector,
+                &failed_addrs,
+                client_id,
+            )
+        });
+        let Some(target_addr) = sticky_target
+            .or_else(|| select_instance(registry, client_manager, &selector, &failed_addrs))
+            .or_else(|| default_backend(registry, config, service_name))
+        else {
+            // 服务已注册但没有健康实例时返回 UNAVAILABLE，调用方可以稍后重试
+            if let Some(instances) = registry.get(service_name) {
+                let has_healthy = instances
+                    .iter()
+                    .any(|instance| instance.value().health_status == ServiceHealthStatus::Healthy);
+                if !has_healthy {
+                    return Err(RouterError::ServiceUnavailable(format!(
+                        "Service '{service_name}' has no healthy instances"
+                    )));
+                }
+            }
+            if !labels.is_empty() && registry.contains_key(service_name) {
+                let labels = labels
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                return Err(RouterError::ServiceNotFound(format!(
+                    "No instance of service '{service_name}' matches labels [{labels}]"
+                )));
+            }
+            return Err(RouterError::ServiceNotFound(format!(
+                "Service '{service_name}' not found in registry"
+            )));
+        };
+
+        if let Some((window, client_id)) = sticky {
+            client_manager
+                .sticky
+                .record(client_id, service_name, &target_addr, window);
+        }
+        context.record_attempt(&target_addr);
+        let result = match hedge_delay.filter(|_| body.can_hedge()) {
+            Some(delay) => {
+                // 对冲请求发往本次请求尚未失败过的另一个实例
+                let hedge_target = || {
+                    let mut excluded = failed_addrs.clone();
+                    excluded.push(target_addr.clone());
+                    select_instance(registry, client_manager, &selector, &excluded)
+                        .filter(|addr| *addr != target_addr)
+                };
+                hedged_attempt(&request, context, &target_addr, hedge_target, delay).await
+            }
+            None => request.forward(&target_addr).await,
+        };
+        let error = match result {
+            Ok(response) => return Ok(response),
+            Err(error) => error,
+        };
+
+        // 已经发送过且没有缓存的请求体不能安全重放
+        if !error.retryable || !body.can_replay() || attempt >= max_retries {
+            return Err(error.error);
+        }
+
+        attempt += 1;
+        let backoff = retry_backoff(attempt);
+        // 退避结束时调用方已经放弃的请求不再重试
+        if client_deadline.is_some_and(|deadline| Instant::now() + backoff >= deadline) {
+            return Err(error.error);
+        }
+        tracing::warn!(
+            service_name = %service_name,
+            target_addr = %target_addr,
+            attempt = attempt,
+            max_retries = max_retries,
+            backoff_ms = backoff.as_millis(),
+            error = %error.error,
+            "Transient forwarding failure, retrying"
+        );
+
+        if !failed_addrs.contains(&target_addr) {
+            failed_addrs.push(target_addr);
+        }
+        tokio::time::sleep(backoff).await;
+    }
+}
+
+// 本次尝试的请求：复制请求头，请求体按缓存或流式方式提供
+fn attempt_request(
+    parts: &http::request::Parts,
+    body: &RequestBody,
+) -> http::Request<BoxedRequestBody> {
+    let mut attempt_req = http::Request::new(body.attempt());
+    *attempt_req.method_mut() = parts.method.clone();
+    *attempt_req.uri_mut() = parts.uri.clone();
+    *attempt_req.version_mut() = parts.version;
+    *attempt_req.headers_mut() = parts.headers.clone();
+    attempt_req
+}
+
+// 一次转发中各次尝试共用的请求：请求头、可重放的请求体和调用方的截止时间
+struct AttemptContext<'a> {
+    client_manager: &'a GrpcClientManager,
+    config: &'a Config,
+    parts: &'a http::request::Parts,
+    body: &'a RequestBody,
+    client_deadline: Option<Instant>,
+}
+
+impl AttemptContext<'_> {
+    // 向指定实例发出一次尝试
+    async fn forward(&self, target_addr: &str) -> Result<ForwardResponse, AttemptError> {
+        forward_attempt(
+            self.client_manager,
+            self.config,
+            attempt_request(self.parts, self.body),
+            target_addr,
+            self.client_deadline,
+        )
+        .await
+    }
+}
+
+// 对冲转发：主请求在 delay 内没有响应时，向另一个健康实例发出相同的请求，
+// 采用先成功返回的响应，未完成的另一个请求随之取消；两个请求都失败时返回后失败的错误
+async fn hedged_attempt(
+    request: &AttemptContext<'_>,
+    context: &RequestContext,
+    primary_addr: &str,
+    hedge_target: impl FnOnce() -> Option<String>,
+    delay: Duration,
+) -> Result<ForwardResponse, AttemptError> {
+    let mut primary = std::pin::pin!(request.forward(primary_addr));
+    tokio::select! {
+        result = &mut primary => return result,
+        _ = tokio::time::sleep(delay) => {}
+    }
+    let Some(hedge_addr) = hedge_target() else {
+        return primary.await;
+    };
+    tracing::debug!(
+        primary_addr = %primary_addr,
+        hedge_addr = %hedge_addr,
+        delay_ms = delay.as_millis(),
+        "Primary attempt is slow, sending hedged request"
+    );
+
+    context.record_attempt(&hedge_addr);
+    let mut hedge = std::pin::pin!(request.forward(&hedge_addr));
+    tokio::select! {
+        result = &mut primary => match result {
+            Ok(response) => {
+                context.record_backend(primary_addr);
+                Ok(response)
+            }
+            Err(_) => hedge.await,
+        },
+        result = &mut hedge => match result {
+            Ok(response) => Ok(response),
+            Err(_) => {
+                let result = primary.await;
+                context.record_backend(primary_addr);
+                result
+            }
+        },
+    }
+}
+
+fn is_timeout_expired(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(error);
+    while let Some(error) = source {
+        if error.is::<tonic::TimeoutExpired>() {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+fn is_grpc_response(headers: &http::HeaderMap) -> bool {
+    headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/grpc"))
+}
+
+// 单次转发到指定实例
+async fn forward_attempt<B>(
     client_manager: &GrpcClientManager,
     config: &Config,
     req: http::Request<B>,
     target_addr: &str,
-) -> Result<
-    http::Response<
-        http_body_util::combinators::UnsyncBoxBody<
-            bytes::Bytes,
-            Box<dyn std::error::Error + Send + Sync>,
-        >,
-    >,
-    RouterError,
->
+    client_deadline: Option<Instant>,
+) -> Result<ForwardResponse, AttemptError>
 where
     B: Body<Data = bytes::Bytes> + Send + 'static,
     B::Error: Into<Box<dyn std::error::Error + Send + Sync>> + std::fmt::Debug,
@@ -28,6 +779,28 @@ where
     let method = req.method().as_str().to_string();
     let uri = req.uri().to_string();
 
+    // 按完整服务名查找超时覆盖，未配置时使用全局超时
+    let full_service_name = req
+        .uri()
+        .path()
+        .trim_start_matches('/')
+        .split('/')
+        .next()
+        .unwrap_or_default();
+    // 调用方的剩余时间比配置的超时更短时以调用方为准
+    let configured_timeout = config.request_timeout_for(full_service_name);
+    let request_timeout = deadline::effective_timeout(
+        client_deadline.map(|deadline| deadline.saturating_duration_since(Instant::now())),
+        configured_timeout,
+    );
+    let limited_by_client = request_timeout < configured_timeout;
+    let max_body_size = config.max_body_size_for(full_service_name);
+    let client_accepts_gzip = compression::accepts_gzip(
+        req.headers()
+            .get(GRPC_ACCEPT_ENCODING)
+            .and_then(|v| v.to_str().ok()),
+    );
+
     tracing::debug!(
         target_addr = %target_addr,
         method = %method,
@@ -35,17 +808,32 @@ where
         "Starting request forwarding"
     );
 
+    // 从发出请求到响应体结束都计为该实例上进行中的请求
+    let in_flight = client_manager.in_flight.acquire(target_addr);
+
     // 获取或创建客户端连接
     let channel = client_manager
         .get_or_create_client(target_addr)
         .await
         .map_err(|e| {
+            // 熔断中的后端快速失败为 UNAVAILABLE
+            if let Some(open) = e.downcast_ref::<CircuitOpenError>() {
+                tracing::warn!(target_addr = %target_addr, "Backend circuit open, failing fast");
+                return AttemptError {
+                    error: RouterError::ServiceUnavailable(open.to_string()),
+                    retryable: true,
+                };
+            }
+
             tracing::error!(
                 target_addr = %target_addr,
                 error = %e,
                 "Failed to get or create gRPC client connection"
             );
-            RouterError::ForwardingError(format!("Failed to get client: {e}"))
+            AttemptError {
+                error: RouterError::ConnectError(format!("Failed to get client: {e}")),
+                retryable: true,
+            }
         })?;
 
     // 直接构建新的请求，不收集请求体
@@ -57,29 +845,55 @@ where
         .uri(parts.uri)
         .version(parts.version);
 
-    // 复制头部
+    // 复制头部，grpc-timeout 替换为剩余时间
     for (name, value) in parts.headers.iter() {
-        new_req = new_req.header(name, value);
+        if name != deadline::GRPC_TIMEOUT {
+            new_req = new_req.header(name, value);
+        }
     }
+    new_req = new_req.header(
+        deadline::GRPC_TIMEOUT,
+        deadline::format_grpc_timeout(request_timeout),
+    );
 
     let new_req = new_req
         .body(tonic::body::Body::new(body))
-        .map_err(|e| RouterError::ForwardingError(format!("Failed to build request: {e}")))?;
+        .map_err(|e| AttemptError {
+            error: RouterError::BodyError(format!("Failed to build request: {e}")),
+            retryable: false,
+        })?;
+
+    let timed_out = || {
+        tracing::error!(
+                target_addr = %target_addr,
+            timeout_ms = request_timeout.as_millis(),
+            method = %method,
+            uri = %uri,
+            "Request forwarding timeout"
+        );
+        // 调用方截止时间到期不代表后端故障，不计入熔断也不重试
+        if limited_by_client {
+            return AttemptError {
+                error: RouterError::Timeout("Client deadline exceeded".to_string()),
+                retryable: false,
+            };
+        }
+        client_manager.record_failure(target_addr);
+        AttemptError {
+            error: RouterError::Timeout("Request timeout".to_string()),
+            retryable: true,
+        }
+    };
 
     // 发送请求到目标服务（带超时）
-    let response = tokio::time::timeout(config.request_timeout(), channel.clone().oneshot(new_req))
+    let response = tokio::time::timeout(request_timeout, channel.clone().oneshot(new_req))
         .await
-        .map_err(|_| {
-            tracing::error!(
-                target_addr = %target_addr,
-                timeout_ms = config.request_timeout().as_millis(),
-                method = %method,
-                uri = %uri,
-                "Request forwarding timeout"
-            );
-            RouterError::ForwardingError("Request timeout".to_string())
-        })?
+        .map_err(|_| timed_out())?
         .map_err(|e| {
+            // 通道按转发的 grpc-timeout 自行超时，可能略早于上面的计时器
+            if is_timeout_expired(&e) {
+                return timed_out();
+            }
             tracing::error!(
                 target_addr = %target_addr,
                 method = %method,
@@ -87,9 +901,16 @@ where
                 error = %e,
                 "Failed to forward request to target service"
             );
-            RouterError::ForwardingError(format!("Failed to forward request: {e}"))
+            client_manager.record_failure(target_addr);
+            AttemptError {
+                error: RouterError::ConnectError(format!("Failed to forward request: {e}")),
+                retryable: true,
+            }
         })?;
 
+    // 后端已经响应（包括应用层 gRPC 错误），视为地址可用
+    client_manager.record_success(target_addr);
+
     // 直接转换响应体，不收集响应体
     let (parts, body) = response.into_parts();
 
@@ -102,14 +923,47 @@ where
         response_builder = response_builder.header(name, value);
     }
 
+    // 客户端接受 gzip 而后端没有压缩时由网关压缩；后端已选择的编码原样透传
+    let gzip_response = config.compression.gzip_responses
+        && client_accepts_gzip
+        && is_grpc_response(&parts.headers)
+        && compression::is_identity(
+            parts
+                .headers
+                .get(GRPC_ENCODING)
+                .and_then(|v| v.to_str().ok()),
+        );
+
     // 使用 UnsyncBoxBody 来避免 Sync 约束
-    let boxed_body = http_body_util::combinators::UnsyncBoxBody::new(
-        body.map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) }),
-    );
+    let limited_body = InFlightBody::new(LimitedBody::new(body, max_body_size), in_flight);
+    let boxed_body = if gzip_response {
+        response_builder = response_builder.header(GRPC_ENCODING, compression::GZIP);
+        http_body_util::combinators::UnsyncBoxBody::new(compression::RecodedBody::new(
+            limited_body,
+            FrameTransform::Compress {
+                min_size: config.compression.min_size,
+            },
+        ))
+    } else {
+        http_body_util::combinators::UnsyncBoxBody::new(
+            limited_body.map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) }),
+        )
+    };
 
     let final_response = response_builder
         .body(boxed_body)
-        .map_err(|e| RouterError::ForwardingError(format!("Failed to build response: {e}")))?;
+        .map_err(|e| AttemptError {
+            error: RouterError::BackendError {
+                code: tonic::Code::Internal,
+                message: format!("Failed to build response: {e}"),
+            },
+            retryable: false,
+        })?;
+    let final_response = if config.router.buffer_responses {
+        buffer_response(final_response, config.router.response_buffer_max_size).await
+    } else {
+        final_response
+    };
 
     tracing::debug!(
         target_addr = %target_addr,
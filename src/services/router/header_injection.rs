@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+use http::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+
+// 注入的请求头与调用方已发送的同名头冲突时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeaderInjectionPolicy {
+    // 使用配置的值覆盖调用方发送的值
+    #[default]
+    Override,
+    // 只补充调用方没有发送的头，已有的值保持不变
+    Supplement,
+}
+
+// 把为服务配置的固定请求头写入转发请求。
+// 注入的值通常是密钥，标记为敏感值，日志中只出现头名称
+pub fn inject_headers(
+    headers: &mut HeaderMap,
+    injected: &HashMap<String, String>,
+    policy: HeaderInjectionPolicy,
+    service_name: &str,
+) {
+    let mut injected_names = Vec::new();
+    for (header, value) in injected {
+        let (Ok(name), Ok(mut value)) = (
+            HeaderName::try_from(header.as_str()),
+            HeaderValue::try_from(value.as_str()),
+        ) else {
+            tracing::warn!(
+                service_name = %service_name,
+                header = %header,
+                "Skipping invalid injected header"
+            );
+            continue;
+        };
+        if policy == HeaderInjectionPolicy::Supplement && headers.contains_key(&name) {
+            continue;
+        }
+        value.set_sensitive(true);
+        headers.insert(name, value);
+        injected_names.push(header);
+    }
+    if !injected_names.is_empty() {
+        tracing::debug!(
+            service_name = %service_name,
+            headers = ?injected_names,
+            "Injected static request headers"
+        );
+    }
+}
@@ -0,0 +1,203 @@
+// 负载均衡：正向转发和反向连接按同一个策略在候选实例中选择，
+// 候选实例排序后排在第一位的即为本次选中的实例，其余依次作为重试时的备选
+use dashmap::DashMap;
+use http_body::{Body, Frame};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadBalanceStrategy {
+    // 按权重轮询
+    #[default]
+    RoundRobin,
+    // 按权重随机
+    Random,
+    // 进行中请求数（按权重折算）最少的实例，相同时轮询
+    LeastConnections,
+    // 总是选择排序后的第一个可用实例，其余实例只在重试时使用
+    FirstHealthy,
+}
+
+impl LoadBalanceStrategy {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Random,
+            2 => Self::LeastConnections,
+            3 => Self::FirstHealthy,
+            _ => Self::RoundRobin,
+        }
+    }
+}
+
+// 运行中可替换的策略，配置热更新时写入
+#[derive(Debug, Default)]
+pub struct SharedStrategy(AtomicU8);
+
+impl SharedStrategy {
+    pub fn new(strategy: LoadBalanceStrategy) -> Self {
+        Self(AtomicU8::new(strategy as u8))
+    }
+
+    pub fn load(&self) -> LoadBalanceStrategy {
+        LoadBalanceStrategy::from_u8(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn store(&self, strategy: LoadBalanceStrategy) {
+        self.0.store(strategy as u8, Ordering::Relaxed);
+    }
+}
+
+// 每个实例上进行中的请求数，键为实例地址或连接 ID
+#[derive(Debug, Default)]
+pub struct InFlight {
+    counts: DashMap<String, usize>,
+}
+
+impl InFlight {
+    pub fn count(&self, key: &str) -> usize {
+        self.counts.get(key).map(|count| *count).unwrap_or(0)
+    }
+
+    // 计入一个进行中的请求，守卫释放时扣除
+    pub fn acquire(self: &Arc<Self>, key: &str) -> InFlightGuard {
+        *self.counts.entry(key.to_string()).or_insert(0) += 1;
+        InFlightGuard {
+            in_flight: self.clone(),
+            key: key.to_string(),
+        }
+    }
+
+    // 进行中的请求数低于 limit 时计入一个请求，检查与计数在同一把锁内完成，并发请求不会超过上限
+    pub fn try_acquire(self: &Arc<Self>, key: &str, limit: usize) -> Option<InFlightGuard> {
+        let mut count = self.counts.entry(key.to_string()).or_insert(0);
+        if *count >= limit {
+            return None;
+        }
+        *count += 1;
+        drop(count);
+        Some(InFlightGuard {
+            in_flight: self.clone(),
+            key: key.to_string(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct InFlightGuard {
+    in_flight: Arc<InFlight>,
+    key: String,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if let Some(mut count) = self.in_flight.counts.get_mut(&self.key) {
+            *count = count.saturating_sub(1);
+        }
+        self.in_flight
+            .counts
+            .remove_if(&self.key, |_, count| *count == 0);
+    }
+}
+
+// 响应体结束（或被丢弃）前请求一直计为进行中
+pub struct InFlightBody<B> {
+    inner: Pin<Box<B>>,
+    _guard: InFlightGuard,
+}
+
+impl<B> InFlightBody<B> {
+    pub fn new(inner: B, guard: InFlightGuard) -> Self {
+        Self {
+            inner: Box::pin(inner),
+            _guard: guard,
+        }
+    }
+}
+
+impl<B> Body for InFlightBody<B>
+where
+    B: Body,
+{
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        self.inner.as_mut().poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// 按策略排列候选实例；id 返回实例的键（地址或连接 ID）和权重，cursor 为轮询游标
+pub fn order<T>(
+    strategy: LoadBalanceStrategy,
+    candidates: &mut [T],
+    cursor: &AtomicUsize,
+    in_flight: &InFlight,
+    id: impl Fn(&T) -> (&str, u32),
+) {
+    if candidates.is_empty() {
+        return;
+    }
+    // 注册表和服务池的遍历顺序不固定，先按键排序使轮询和 first_healthy 的结果可预期
+    candidates.sort_by(|a, b| id(a).0.cmp(id(b).0));
+
+    match strategy {
+        LoadBalanceStrategy::FirstHealthy => {}
+        LoadBalanceStrategy::RoundRobin => {
+            let point = cursor.fetch_add(1, Ordering::Relaxed);
+            let index = weighted_index(candidates, point, &id);
+            candidates.rotate_left(index);
+        }
+        LoadBalanceStrategy::Random => {
+            let point = RandomState::new().hash_one(cursor.fetch_add(1, Ordering::Relaxed));
+            let index = weighted_index(candidates, point as usize, &id);
+            candidates.rotate_left(index);
+        }
+        LoadBalanceStrategy::LeastConnections => {
+            // 先轮询再稳定排序，进行中请求数相同的实例轮流被选中
+            let start = cursor.fetch_add(1, Ordering::Relaxed) % candidates.len();
+            candidates.rotate_left(start);
+            // 比较 count / weight，交叉相乘避免浮点
+            candidates.sort_by(|a, b| {
+                let (a_key, a_weight) = id(a);
+                let (b_key, b_weight) = id(b);
+                let a_load = in_flight.count(a_key) as u64 * u64::from(b_weight.max(1));
+                let b_load = in_flight.count(b_key) as u64 * u64::from(a_weight.max(1));
+                a_load.cmp(&b_load)
+            });
+        }
+    }
+}
+
+// 总权重区间内的落点对应的实例下标
+fn weighted_index<T>(candidates: &[T], point: usize, id: &impl Fn(&T) -> (&str, u32)) -> usize {
+    let total_weight: usize = candidates
+        .iter()
+        .map(|candidate| id(candidate).1.max(1) as usize)
+        .sum();
+    let mut point = point % total_weight;
+    for (index, candidate) in candidates.iter().enumerate() {
+        let weight = id(candidate).1.max(1) as usize;
+        if point < weight {
+            return index;
+        }
+        point -= weight;
+    }
+    0
+}
@@ -0,0 +1,39 @@
+use base64::Engine;
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use http::{HeaderName, HeaderValue};
+
+// gRPC 二进制元数据的请求头后缀，值在 HTTP/2 上以 base64 传输
+pub const BINARY_HEADER_SUFFIX: &str = "-bin";
+
+// 按 gRPC 规范输出不带填充的 base64，解码时填充可有可无
+const BINARY_ENGINE: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+pub fn is_binary(name: &str) -> bool {
+    name.ends_with(BINARY_HEADER_SUFFIX)
+}
+
+// 把请求头的值转换为反向连接上传递的字符串：-bin 头已经是 base64 时原样保留，
+// 否则对原始字节做 base64 编码；其它头只接受合法的字符串值
+pub fn encode_value(name: &HeaderName, value: &HeaderValue) -> Option<String> {
+    match value.to_str() {
+        Ok(value) => Some(value.to_string()),
+        Err(_) if is_binary(name.as_str()) => Some(BINARY_ENGINE.encode(value.as_bytes())),
+        Err(_) => None,
+    }
+}
+
+// 把微服务返回的响应头转换为 HTTP 头的值：-bin 头先按 base64 解码校验，
+// 再以不带填充的 base64 写回；无法解码或不是合法头值时返回 None
+pub fn decode_value(name: &str, value: &str) -> Option<HeaderValue> {
+    if is_binary(name) {
+        let bytes = BINARY_ENGINE.decode(value).ok()?;
+        return HeaderValue::from_str(&BINARY_ENGINE.encode(bytes)).ok();
+    }
+    HeaderValue::from_str(value).ok()
+}
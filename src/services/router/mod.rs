@@ -1,30 +1,62 @@
+pub mod affinity;
+pub mod context;
+pub mod deadline;
 pub mod error;
 pub mod extractor;
 pub mod forwarder;
+pub mod header_injection;
+pub mod load_balance;
+pub mod metadata;
+pub mod payload_log;
+pub mod rate_limit;
 pub mod response;
+pub mod shadow;
+pub mod sticky;
+pub mod stream_body;
+pub mod trace_context;
 
+pub use context::RequestContext;
 pub use error::RouterError;
 
 use super::client_manager::GrpcClientManager;
-use super::connection::ReverseConnectionManager;
-use crate::config::Config;
-use crate::services::registry::{ServiceHealthStatus, ServiceRegistry};
+use super::connection::{ReverseConnectionManager, ReverseRequestError};
+use super::metrics::{self, CountingBody, ForwardRoute, GatewayMetrics};
+use crate::config::{CompressionConfig, Config, SharedConfig};
+use crate::registry::ForwardResponse;
+use crate::services::compression::{
+    self, FrameTransform, GRPC_ACCEPT_ENCODING, GRPC_ENCODING, RecodedBody,
+};
+use crate::services::registry::ServiceRegistry;
 use futures::future::BoxFuture;
 use http_body::Body;
 use http_body_util::BodyExt;
+use payload_log::{Direction, PreviewBody};
+use shadow::{MirrorBody, ShadowRequest};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::task::{Context, Poll};
 use std::time::Duration;
+use tokio::sync::Semaphore;
 use tonic::server::NamedService;
 use tower::Service;
+use tracing::Instrument;
+
+// 并发已满时等待空闲许可的最长时间
+const PERMIT_ACQUIRE_TIMEOUT: Duration = Duration::from_millis(50);
 
 // 定义动态路由服务
 #[derive(Debug, Clone)]
 pub struct DynamicRouter {
     pub registry: ServiceRegistry,
     pub client_manager: GrpcClientManager,
-    pub config: Config,
+    // 运行中的配置，每个请求使用调用时的快照
+    pub config: SharedConfig,
     pub reverse_manager: std::sync::Arc<ReverseConnectionManager>,
+    pub metrics: std::sync::Arc<GatewayMetrics>,
+    // 进行中请求数上限，对应 router.max_concurrent_requests
+    concurrency_limiter: std::sync::Arc<Semaphore>,
+    // 令牌桶限流器，是否启用及速率取自当前配置
+    rate_limiter: rate_limit::RateLimiter,
 }
 
 impl DynamicRouter {
@@ -32,28 +64,90 @@ impl DynamicRouter {
         registry: ServiceRegistry,
         config: Config,
         reverse_manager: std::sync::Arc<ReverseConnectionManager>,
-    ) -> Self {
-        // 使用配置创建连接管理器
-        let connection_pool_config = crate::services::client_manager::ConnectionPoolConfig {
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_shared_config(registry, SharedConfig::new(config), reverse_manager)
+    }
+
+    // 使用与注册服务共享的配置句柄创建路由器，热更新后路由参数随之生效。
+    // 出站 TLS 证书无法读取时返回错误
+    pub fn with_shared_config(
+        registry: ServiceRegistry,
+        shared_config: SharedConfig,
+        reverse_manager: std::sync::Arc<ReverseConnectionManager>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let config = shared_config.load();
+
+        // 0 表示不限制并发
+        let max_concurrent_requests = match config.router.max_concurrent_requests {
+            0 => Semaphore::MAX_PERMITS,
+            limit => limit,
+        };
+
+        Ok(Self {
+            registry,
+            client_manager: GrpcClientManager::new(Self::connection_pool_config(&config)?),
+            concurrency_limiter: std::sync::Arc::new(Semaphore::new(max_concurrent_requests)),
+            rate_limiter: rate_limit::RateLimiter::new(),
+            config: shared_config,
+            reverse_manager,
+            metrics: std::sync::Arc::new(GatewayMetrics::new()),
+        })
+    }
+
+    // 由网关配置生成连接池配置。证书读取失败时返回错误，不退回到不带自定义证书的 TLS
+    pub fn connection_pool_config(
+        config: &Config,
+    ) -> Result<crate::services::client_manager::ConnectionPoolConfig, Box<dyn std::error::Error>>
+    {
+        let tls = config.connection_pool.client_tls()?;
+
+        Ok(crate::services::client_manager::ConnectionPoolConfig {
             max_connections: config.connection_pool.max_connections,
+            connections_per_address: config.connection_pool.connections_per_address,
+            eviction_policy: config.connection_pool.eviction_policy,
             connection_ttl: Duration::from_secs(config.connection_pool.connection_ttl),
             idle_timeout: Duration::from_secs(config.connection_pool.idle_timeout),
             cleanup_interval: Duration::from_secs(config.connection_pool.cleanup_interval),
-        };
+            circuit_failure_threshold: config.connection_pool.circuit_failure_threshold,
+            circuit_cooldown: Duration::from_secs(config.connection_pool.circuit_cooldown),
+            tls,
+            http2: config.http2.settings(),
+            warmup_on_register: config.connection_pool.warmup_on_register,
+        })
+    }
 
-        Self {
-            registry,
-            client_manager: GrpcClientManager::new(connection_pool_config),
-            config,
-            reverse_manager,
+    // 按客户端声明的 grpc-accept-encoding 调整反向连接响应的压缩方式：
+    // 客户端不接受 gzip 时解压，客户端接受而微服务未压缩时压缩，其余情况原样透传。
+    // 返回需要对消息帧做的改写，响应头中的 grpc-encoding 已经相应更新
+    fn negotiate_response_encoding(
+        response: &mut ForwardResponse,
+        client_accepts_gzip: bool,
+        compression_config: &CompressionConfig,
+    ) -> Option<FrameTransform> {
+        let encoding = response.headers.get(GRPC_ENCODING).map(String::as_str);
+        if compression::is_gzip(encoding) && !client_accepts_gzip {
+            response.headers.remove(GRPC_ENCODING);
+            Some(FrameTransform::Decompress)
+        } else if compression::is_identity(encoding)
+            && client_accepts_gzip
+            && compression_config.gzip_responses
+        {
+            response
+                .headers
+                .insert(GRPC_ENCODING.to_string(), compression::GZIP.to_string());
+            Some(FrameTransform::Compress {
+                min_size: compression_config.min_size,
+            })
+        } else {
+            None
         }
     }
 
     // 通过反向连接转发请求（流式版本）
     async fn forward_via_reverse_connection<B>(
         reverse_manager: &std::sync::Arc<ReverseConnectionManager>,
-        service_name: &str,
-        method_path: &str,
+        compression_config: &CompressionConfig,
+        context: &RequestContext,
         req: http::Request<B>,
     ) -> Result<
         http::Response<
@@ -62,58 +156,204 @@ impl DynamicRouter {
                 Box<dyn std::error::Error + Send + Sync>,
             >,
         >,
-        String,
+        RouterError,
     >
     where
         B: Body<Data = bytes::Bytes> + Send + 'static,
         B::Error: Into<Box<dyn std::error::Error + Send + Sync>> + std::fmt::Debug,
     {
-        // 分解请求
-        let (parts, body) = req.into_parts();
+        let service_name = context.service_name.as_str();
+        let method_path = context.method_path.as_str();
 
-        // 收集请求头
+        // 分解请求，微服务收到与网关日志相同的请求 ID
+        let (mut parts, body) = req.into_parts();
+        context.insert_header(&mut parts.headers);
+        let client_accepts_gzip = compression::accepts_gzip(
+            parts
+                .headers
+                .get(GRPC_ACCEPT_ENCODING)
+                .and_then(|v| v.to_str().ok()),
+        );
+
+        // 收集请求头，traceparent/tracestate 等 ASCII 头原样转发，
+        // -bin 二进制元数据中无法作为字符串传递的值以 base64 编码
         let mut headers = HashMap::new();
         for (name, value) in parts.headers.iter() {
-            if let Ok(value_str) = value.to_str() {
-                headers.insert(name.to_string(), value_str.to_string());
+            match metadata::encode_value(name, value) {
+                Some(value) => {
+                    headers.insert(name.to_string(), value);
+                }
+                None => {
+                    tracing::warn!(
+                        service_name = %service_name,
+                        header = %name,
+                        "Dropping non-UTF8 header value on reverse connection hop"
+                    );
+                }
             }
         }
 
-        // 使用流式处理请求体
-        let forward_response = reverse_manager
-            .send_request_stream(service_name, method_path, headers, body)
-            .await?;
+        // 网关 span 记录入站 trace-id，便于把网关日志与上下游调用链关联
+        let trace_context = trace_context::TraceContext::extract(&parts.headers);
+        let span = tracing::info_span!(
+            "reverse_forward",
+            service_name = %service_name,
+            method_path = %method_path,
+            trace_id = tracing::field::Empty,
+        );
+        if let Some(trace_id) = trace_context.trace_id() {
+            span.record("trace_id", trace_id);
+        }
 
-        // 构建 HTTP 响应
-        let mut response_builder =
-            http::Response::builder().status(forward_response.status_code as u16);
+        // 使用流式处理请求体，流式响应在第一个数据块到达时即开始返回
+        let reverse_response = reverse_manager
+            .send_request_stream(
+                service_name,
+                method_path,
+                headers,
+                body,
+                context.client_id.as_deref(),
+            )
+            .instrument(span)
+            .await
+            .map_err(|e| match e {
+                ReverseRequestError::BodyTooLarge { .. }
+                | ReverseRequestError::QueueFull { .. }
+                | ReverseRequestError::ConnectionsSaturated { .. } => {
+                    RouterError::ResourceExhausted(e.to_string())
+                }
+                ReverseRequestError::Timeout => RouterError::Timeout(e.to_string()),
+                ReverseRequestError::Body(message) => RouterError::BodyError(message),
+                // 发送失败、连接断开或请求被排空放弃
+                ReverseRequestError::Failed(message) => RouterError::ConnectError(message),
+            })?;
+        context.record_attempt(&reverse_response.connection_id);
 
-        // 检查是否为流式响应
-        let is_streaming = ReverseConnectionManager::is_streaming_response(&forward_response);
+        let mut forward_response = reverse_response.head;
 
-        // 添加响应头
-        for (name, value) in forward_response.headers {
-            response_builder = response_builder.header(name, value);
+        // 响应体同样受大小上限约束，流式响应在转发过程中累计检查
+        let max_body_size = reverse_manager.max_response_size_for(service_name, method_path);
+        if forward_response.payload.len() > max_body_size {
+            return Err(RouterError::ResourceExhausted(format!(
+                "Response body too large: {} bytes (max: {max_body_size} bytes)",
+                forward_response.payload.len()
+            )));
         }
+        let transform = Self::negotiate_response_encoding(
+            &mut forward_response,
+            client_accepts_gzip,
+            compression_config,
+        );
 
-        // 创建响应体（支持流式响应）
-        let response_body = if is_streaming {
-            // 对于流式响应，直接使用payload，因为它们已经在reverse_connection_manager中被组装了
-            http_body_util::combinators::UnsyncBoxBody::new(
-                http_body_util::Full::new(bytes::Bytes::from(forward_response.payload))
-                    .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) }),
-            )
-        } else {
-            // 常规响应
-            http_body_util::combinators::UnsyncBoxBody::new(
-                http_body_util::Full::new(bytes::Bytes::from(forward_response.payload))
-                    .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) }),
-            )
+        // 构建 HTTP 响应：gRPC 响应的 HTTP 状态总是 200，微服务的状态通过 grpc-status 返回
+        let mut response_builder = http::Response::builder().status(http::StatusCode::OK);
+
+        let response_body = match reverse_response.chunks {
+            Some(chunks) => {
+                // 流式响应：响应头取自第一个数据块，grpc-status 等状态头随最后一个数据块作为 trailers 发出
+                for name in stream_body::TRAILER_HEADERS {
+                    forward_response.headers.remove(name);
+                }
+                forward_response
+                    .headers
+                    .entry("content-type".to_string())
+                    .or_insert_with(|| "application/grpc".to_string());
+
+                let body = stream_body::ReverseStreamBody::new(
+                    forward_response.payload,
+                    chunks,
+                    max_body_size,
+                );
+                match transform {
+                    Some(transform) => http_body_util::combinators::UnsyncBoxBody::new(
+                        RecodedBody::new(body, transform),
+                    ),
+                    None => http_body_util::combinators::UnsyncBoxBody::new(body),
+                }
+            }
+            None => {
+                // 一元响应，或者第一个数据块就是最后一个数据块的流式响应：
+                // 状态头在消息之后作为 trailers 发出，没有消息时同时放在响应头中
+                let status_headers = response::reverse_status_headers(&forward_response);
+                for name in stream_body::TRAILER_HEADERS {
+                    forward_response.headers.remove(name);
+                }
+                forward_response
+                    .headers
+                    .entry("content-type".to_string())
+                    .or_insert_with(|| "application/grpc".to_string());
+
+                // 微服务返回错误时以 Trailers-Only 响应结束：状态只放在响应头中，
+                // 响应体为空，错误响应附带的消息不再发给调用方
+                let failed = status_headers
+                    .get("grpc-status")
+                    .is_some_and(|status| status != "0");
+                if failed {
+                    if !forward_response.payload.is_empty() {
+                        tracing::debug!(
+                            payload_size = forward_response.payload.len(),
+                            "Discarding payload of failed reverse response"
+                        );
+                    }
+                    for (name, value) in &status_headers {
+                        response_builder = response_builder.header(name, value);
+                    }
+                    http_body_util::combinators::UnsyncBoxBody::new(
+                        http_body_util::Empty::new().map_err(|never| match never {}),
+                    )
+                } else {
+                    let payload = match transform {
+                        Some(transform) => {
+                            compression::recode(&forward_response.payload, transform).map_err(
+                                |e| RouterError::BackendError {
+                                    code: tonic::Code::Internal,
+                                    message: format!("Failed to recode response payload: {e}"),
+                                },
+                            )?
+                        }
+                        None => forward_response.payload,
+                    };
+                    if payload.is_empty() {
+                        for (name, value) in &status_headers {
+                            response_builder = response_builder.header(name, value);
+                        }
+                    }
+                    let mut frames = Vec::with_capacity(2);
+                    if !payload.is_empty() {
+                        frames.push(Ok(http_body::Frame::data(bytes::Bytes::from(payload))));
+                    }
+                    frames.push(Ok(http_body::Frame::trailers(status_headers)));
+                    http_body_util::combinators::UnsyncBoxBody::new(
+                        http_body_util::StreamBody::new(futures::stream::iter(frames)),
+                    )
+                }
+            }
         };
 
-        response_builder
-            .body(response_body)
-            .map_err(|e| format!("Failed to build response: {e}"))
+        // 添加响应头，-bin 头按 base64 解码校验，无效的头丢弃而不是让整个响应失败
+        for (name, value) in forward_response.headers {
+            match metadata::decode_value(&name, &value) {
+                Some(value) => response_builder = response_builder.header(name, value),
+                None => {
+                    tracing::warn!(
+                        service_name = %service_name,
+                        header = %name,
+                        "Dropping invalid response header from reverse connection"
+                    );
+                }
+            }
+        }
+
+        let mut response =
+            response_builder
+                .body(response_body)
+                .map_err(|e| RouterError::BackendError {
+                    code: tonic::Code::Internal,
+                    message: format!("Failed to build response: {e}"),
+                })?;
+        trace_context.restore(response.headers_mut());
+
+        Ok(response)
     }
 }
 
@@ -135,26 +375,192 @@ where
         Poll::Ready(Ok(()))
     }
 
-    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
         let registry = self.registry.clone();
         let client_manager = self.client_manager.clone();
-        let config = self.config.clone();
+        let config = self.config.load();
         let reverse_manager = self.reverse_manager.clone();
+        let metrics = self.metrics.clone();
+        let concurrency_limiter = self.concurrency_limiter.clone();
+        let rate_limiter = self.rate_limiter.clone();
 
-        Box::pin(async move {
+        // 请求上下文在最开始创建，之后的日志都带有请求 ID，所有响应（包括错误响应）都返回该 ID
+        let mut context = RequestContext::from_headers(req.headers(), req.uri().path());
+        if config.router.stickiness_window().is_some() {
+            context.client_id =
+                sticky::client_id(&req, config.router.stickiness_header().as_deref());
+        }
+        let span = context.span().clone();
+        let request_id = context.request_id.clone();
+        let payload_log_config = config.clone();
+
+        // 跟踪进行中的请求，停机时等待其完成
+        let request_tracker = reverse_manager.clone();
+        let routed = async move {
             let path = req.uri().path().to_string();
 
+            // 停机排空期间不再接受新请求
+            if reverse_manager.is_draining() {
+                tracing::debug!(path = %path, "Rejecting request during shutdown drain");
+                return Ok(response::create_error_response(
+                    &RouterError::ServiceUnavailable("Gateway is shutting down".to_string()),
+                ));
+            }
+
+            // 服务别名：按新服务名查找实例，rewrite_aliased_path 时转发的路径也改写为新服务名
+            let aliased_path = extractor::resolve_alias(&path, &config.router.service_aliases);
+            let lookup_path = aliased_path.clone().unwrap_or_else(|| path.clone());
+
+            // 网关自身的服务（注册、健康检查、管理、反射）和保留服务不转发，
+            // 即使有后端以同名服务注册也不会被选中
+            if let Some(reserved) = [&path, &lookup_path].into_iter().find(|candidate| {
+                extractor::is_reserved_path(candidate, &config.router.reserved_services)
+            }) {
+                tracing::warn!(path = %path, "Rejecting request for a reserved service");
+                return Ok(response::create_error_response(
+                    &RouterError::ReservedService(format!(
+                        "Service '{}' is reserved by the gateway and cannot be forwarded",
+                        extractor::full_service_name(reserved)
+                    )),
+                ));
+            }
+
+            let path = match aliased_path {
+                Some(aliased_path) if config.router.rewrite_aliased_path => {
+                    match extractor::rewrite_path(req.uri(), &aliased_path) {
+                        Some(uri) => {
+                            tracing::debug!(path = %path, rewritten_path = %aliased_path, "Rewriting aliased service path");
+                            *req.uri_mut() = uri;
+                            aliased_path
+                        }
+                        None => {
+                            tracing::warn!(path = %path, alias = %aliased_path, "Invalid service alias, forwarding the original path");
+                            path
+                        }
+                    }
+                }
+                _ => path,
+            };
+
             // 解析服务名（改进的错误处理）
-            let service_name = match extractor::extract_service_name(&path) {
+            let service_name = match extractor::extract_service_name(
+                &lookup_path,
+                config.router.use_full_service_name,
+            ) {
                 Ok(name) => name,
                 Err(e) => {
                     tracing::warn!(path = %path, error = %e, "Invalid gRPC path");
                     return Ok(response::create_error_response(&e));
                 }
             };
+            context.resolve(&service_name, &path);
+            if config.payload_log.enabled {
+                payload_log::log_request(
+                    &config.payload_log,
+                    &context.request_id,
+                    &path,
+                    req.headers(),
+                );
+            }
+
+            // 限流在占用并发许可之前进行，被限流的请求不影响其他调用方
+            if config.rate_limit.enabled {
+                let key = rate_limiter.bucket_key(&config.rate_limit, req.headers(), &service_name);
+                if let Err(retry_after) = rate_limiter.try_acquire(&config.rate_limit, &key) {
+                    tracing::warn!(
+                        service_name = %service_name,
+                        path = %path,
+                        retry_after_ms = retry_after.as_millis(),
+                        "Rejecting request: rate limit exceeded"
+                    );
+                    return Ok(response::create_error_response(&RouterError::RateLimited {
+                        retry_after,
+                    }));
+                }
+            }
+
+            // 许可随 future 完成或被丢弃时释放
+            let _permit = match tokio::time::timeout(
+                PERMIT_ACQUIRE_TIMEOUT,
+                concurrency_limiter.acquire_owned(),
+            )
+            .await
+            {
+                Ok(Ok(permit)) => permit,
+                _ => {
+                    tracing::warn!(path = %path, "Rejecting request: too many concurrent requests");
+                    return Ok(response::create_error_response(
+                        &RouterError::ResourceExhausted("Too many concurrent requests".to_string()),
+                    ));
+                }
+            };
+
+            // 注入为服务配置的固定请求头，正向、反向转发和流量镜像都会带上；
+            // 在请求日志和限流之后进行，两者只看到调用方发送的头
+            let full_service_name = extractor::full_service_name(&path);
+            if let Some(injected) = config.router.injected_headers.get(full_service_name) {
+                header_injection::inject_headers(
+                    req.headers_mut(),
+                    injected,
+                    config.router.injected_header_policy,
+                    full_service_name,
+                );
+            }
+
+            // 统计请求体大小，慢请求日志和请求体大小直方图使用
+            let payload_size = std::sync::Arc::new(AtomicU64::new(0));
+            let req = req.map(|body| {
+                PreviewBody::new(
+                    CountingBody::new(body, payload_size.clone()),
+                    &config.payload_log,
+                    Direction::Request,
+                    &context.request_id,
+                )
+            });
+            // 流量镜像：按采样率复制一元请求发给影子实例，主请求照常转发
+            let mut primary_status = None;
+            let req = match config.shadow_target_for(full_service_name) {
+                Some(target) if shadow::should_mirror(target.sample_rate) => {
+                    let (parts, body) = req.into_parts();
+                    let (body, mirrored_body) =
+                        MirrorBody::new(body, config.max_body_size_for(full_service_name));
+                    let (status_sender, status_receiver) = tokio::sync::oneshot::channel();
+                    ShadowRequest {
+                        target: target.clone(),
+                        service_name: service_name.clone(),
+                        uri: parts.uri.clone(),
+                        headers: parts.headers.clone(),
+                        timeout: config.request_timeout_for(full_service_name),
+                    }
+                    .spawn(
+                        client_manager.clone(),
+                        mirrored_body,
+                        status_receiver,
+                    );
+                    primary_status = Some(status_sender);
+                    http::Request::from_parts(parts, body)
+                }
+                _ => req.map(MirrorBody::passthrough),
+            };
+            let slow_request_threshold = config.router.slow_request_threshold();
+            let record = |route: ForwardRoute, error: Option<&RouterError>| {
+                let elapsed = context.elapsed();
+                let payload_size = payload_size.load(Ordering::Relaxed);
+                metrics.record_forward(route, elapsed, error.map(RouterError::kind));
+                metrics.record_payload(route, payload_size);
+                if metrics::log_slow_request(
+                    slow_request_threshold,
+                    &service_name,
+                    &path,
+                    elapsed,
+                    payload_size,
+                ) {
+                    metrics.record_slow(route);
+                }
+            };
 
             // 检查是否有反向连接可用
-            if reverse_manager.has_reverse_connection(&service_name) {
+            let response = if reverse_manager.has_reverse_connection(&service_name) {
                 // 使用反向连接转发请求
                 tracing::info!(
                     service_name = %service_name,
@@ -162,106 +568,101 @@ where
                     "Using reverse connection for request forwarding"
                 );
 
-                match Self::forward_via_reverse_connection(
+                let result = Self::forward_via_reverse_connection(
                     &reverse_manager,
-                    &service_name,
-                    &path,
+                    &config.compression,
+                    &context,
                     req,
                 )
-                .await
-                {
+                .await;
+                record(ForwardRoute::Reverse, result.as_ref().err());
+
+                match result {
                     Ok(response) => {
                         tracing::debug!(
-                            service_name = %service_name,
-                            path = %path,
+                            connection_id = context.backend().as_deref(),
                             status = %response.status(),
+                            elapsed_ms = context.elapsed().as_millis(),
                             "Request forwarded successfully via reverse connection"
                         );
-                        Ok(response)
+                        response
                     }
                     Err(e) => {
                         tracing::error!(
-                            service_name = %service_name,
-                            path = %path,
+                            connection_id = context.backend().as_deref(),
+                            elapsed_ms = context.elapsed().as_millis(),
                             error = %e,
                             "Failed to forward request via reverse connection"
                         );
-                        Ok(response::create_error_response(
-                            &RouterError::ForwardingError(e),
-                        ))
+                        response::create_error_response(&e)
                     }
                 }
             } else {
-                // 使用传统的正向连接转发请求
-                // 从注册表查找目标地址（使用 DashMap）
-                let target_addr = if let Some(instances_guard) = registry.get(&service_name) {
-                    let instances = instances_guard.clone();
-                    drop(instances_guard);
-
-                    instances
-                        .iter()
-                        .find(|instance| {
-                            instance.value().health_status == ServiceHealthStatus::Healthy
-                        })
-                        .map(|instance| instance.value().address.clone())
-                } else {
-                    None
-                };
+                // 使用传统的正向连接转发请求，实例选择与重试由转发器负责
                 tracing::debug!(
                     service_name = %service_name,
                     path = %path,
-                    target_addr = ?target_addr,
                     "Using traditional forward connection"
                 );
 
-                match target_addr {
-                    Some(ref addr) => {
-                        tracing::info!(
-                            service_name = %service_name,
-                            target_addr = %addr,
-                            path = %path,
-                            "Forwarding request to healthy service instance"
-                        );
+                let result =
+                    forwarder::forward_request(&registry, &client_manager, &config, &context, req)
+                        .await;
+                record(ForwardRoute::Direct, result.as_ref().err());
 
-                        // 转发请求到目标服务
-                        match forwarder::forward_request(&client_manager, &config, req, addr).await
-                        {
-                            Ok(response) => {
-                                tracing::debug!(
-                                    service_name = %service_name,
-                                    target_addr = %addr,
-                                    status = %response.status(),
-                                    "Request forwarded successfully"
-                                );
-                                Ok(response)
-                            }
-                            Err(e) => {
-                                tracing::error!(
-                                    service_name = %service_name,
-                                    target_addr = %addr,
-                                    path = %path,
-                                    error = %e,
-                                    "Failed to forward request to target service"
-                                );
-                                Ok(response::create_error_response(&e))
-                            }
-                        }
+                match result {
+                    Ok(response) => {
+                        tracing::debug!(
+                            target_addr = context.backend().as_deref(),
+                            attempts = context.attempts(),
+                            status = %response.status(),
+                            elapsed_ms = context.elapsed().as_millis(),
+                            "Request forwarded successfully"
+                        );
+                        response
                     }
-                    None => {
-                        // 服务未注册
-                        tracing::warn!(
-                            service_name = %service_name,
-                            path = %path,
-                            "Service not found or no healthy instances available in registry"
+                    Err(e) => {
+                        tracing::error!(
+                            target_addr = context.backend().as_deref(),
+                            attempts = context.attempts(),
+                            elapsed_ms = context.elapsed().as_millis(),
+                            error = %e,
+                            "Failed to forward request to target service"
                         );
-                        let error = RouterError::ServiceNotFound(format!(
-                            "Service '{service_name}' not found in registry"
-                        ));
-                        Ok(response::create_error_response(&error))
+                        response::create_error_response(&e)
                     }
                 }
-            }
-        })
+            };
+            Ok::<_, Self::Error>(shadow::observe_primary(response, primary_status))
+        };
+
+        Box::pin(
+            request_tracker.track_request(
+                async move {
+                    let mut response = routed.await?;
+                    if let Ok(value) = http::HeaderValue::from_str(&request_id) {
+                        response
+                            .headers_mut()
+                            .insert(context::REQUEST_ID_HEADER, value);
+                    }
+                    let payload_log = &payload_log_config.payload_log;
+                    if !payload_log.enabled {
+                        return Ok(response);
+                    }
+                    payload_log::log_response(
+                        payload_log,
+                        &request_id,
+                        response.status(),
+                        response.headers(),
+                    );
+                    Ok(response.map(|body| {
+                        PreviewBody::new(body, payload_log, Direction::Response, &request_id)
+                            .boxed_unsync()
+                    }))
+                }
+                .instrument(span),
+            ),
+        )
     }
 }
 
@@ -0,0 +1,205 @@
+use crate::config::{PayloadEncoding, PayloadLogConfig};
+use base64::Engine;
+use bytes::Bytes;
+use http::HeaderMap;
+use http_body::{Body, Frame};
+use std::fmt::Write;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+// 请求/响应调试日志的 target，可以用 RUST_LOG=payload=info 单独采集
+pub const PAYLOAD_LOG_TARGET: &str = "payload";
+
+const REDACTED: &str = "[REDACTED]";
+
+// 消息体的方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Request,
+    Response,
+}
+
+impl Direction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Direction::Request => "request",
+            Direction::Response => "response",
+        }
+    }
+}
+
+// 输出请求的方法路径和请求头
+pub fn log_request(
+    config: &PayloadLogConfig,
+    request_id: &str,
+    method_path: &str,
+    headers: &HeaderMap,
+) {
+    tracing::info!(
+        target: PAYLOAD_LOG_TARGET,
+        request_id = %request_id,
+        direction = Direction::Request.as_str(),
+        method_path = %method_path,
+        headers = %format_headers(headers, &config.redact_headers),
+        "Request"
+    );
+}
+
+// 输出响应的 HTTP 状态、grpc-status 和响应头
+pub fn log_response(
+    config: &PayloadLogConfig,
+    request_id: &str,
+    status: http::StatusCode,
+    headers: &HeaderMap,
+) {
+    tracing::info!(
+        target: PAYLOAD_LOG_TARGET,
+        request_id = %request_id,
+        direction = Direction::Response.as_str(),
+        status = status.as_u16(),
+        grpc_status = headers.get("grpc-status").and_then(|v| v.to_str().ok()),
+        headers = %format_headers(headers, &config.redact_headers),
+        "Response"
+    );
+}
+
+// 拼接为 name: value 列表，redact 中的头和标记为敏感的值（如注入的请求头）只保留名称
+fn format_headers(headers: &HeaderMap, redact: &[String]) -> String {
+    let mut out = String::new();
+    for (name, value) in headers {
+        let value = if value.is_sensitive()
+            || redact
+                .iter()
+                .any(|redacted| name.as_str().eq_ignore_ascii_case(redacted))
+        {
+            REDACTED
+        } else {
+            value.to_str().unwrap_or("<binary>")
+        };
+        if !out.is_empty() {
+            out.push_str(", ");
+        }
+        let _ = write!(out, "{name}: {value}");
+    }
+    out
+}
+
+fn encode(payload: &[u8], encoding: PayloadEncoding) -> String {
+    match encoding {
+        PayloadEncoding::Hex => payload.iter().fold(String::new(), |mut out, byte| {
+            let _ = write!(out, "{byte:02x}");
+            out
+        }),
+        PayloadEncoding::Base64 => base64::engine::general_purpose::STANDARD.encode(payload),
+    }
+}
+
+// 记录消息体的前 limit 个字节，消息体结束或被丢弃时输出一条日志
+struct Preview {
+    direction: Direction,
+    request_id: String,
+    encoding: PayloadEncoding,
+    limit: usize,
+    captured: Vec<u8>,
+    total: u64,
+    complete: bool,
+}
+
+impl Drop for Preview {
+    fn drop(&mut self) {
+        tracing::info!(
+            target: PAYLOAD_LOG_TARGET,
+            request_id = %self.request_id,
+            direction = self.direction.as_str(),
+            size = self.total,
+            truncated = self.total > self.captured.len() as u64,
+            complete = self.complete,
+            preview = %encode(&self.captured, self.encoding),
+            "Payload"
+        );
+    }
+}
+
+// 透传消息体并按配置截取预览
+pub struct PreviewBody<B> {
+    inner: Pin<Box<B>>,
+    preview: Option<Preview>,
+}
+
+impl<B> PreviewBody<B> {
+    // 未开启调试日志或不记录消息体时只透传
+    pub fn passthrough(inner: B) -> Self {
+        Self {
+            inner: Box::pin(inner),
+            preview: None,
+        }
+    }
+
+    pub fn new(
+        inner: B,
+        config: &PayloadLogConfig,
+        direction: Direction,
+        request_id: &str,
+    ) -> Self {
+        if !config.enabled || config.max_payload_bytes == 0 {
+            return Self::passthrough(inner);
+        }
+        Self {
+            inner: Box::pin(inner),
+            preview: Some(Preview {
+                direction,
+                request_id: request_id.to_string(),
+                encoding: config.payload_encoding,
+                limit: config.max_payload_bytes,
+                captured: Vec::new(),
+                total: 0,
+                complete: false,
+            }),
+        }
+    }
+}
+
+impl<B> Body for PreviewBody<B>
+where
+    B: Body<Data = Bytes>,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let frame = std::task::ready!(self.inner.as_mut().poll_frame(cx));
+        let end_stream = self.inner.is_end_stream();
+        if let Some(preview) = self.preview.as_mut() {
+            match &frame {
+                Some(Ok(frame)) => {
+                    if let Some(data) = frame.data_ref() {
+                        preview.total += data.len() as u64;
+                        let remaining = preview.limit.saturating_sub(preview.captured.len());
+                        preview
+                            .captured
+                            .extend_from_slice(&data[..remaining.min(data.len())]);
+                    }
+                    preview.complete = end_stream;
+                }
+                Some(Err(_)) => {}
+                None => preview.complete = true,
+            }
+            // 消息体结束后立即输出，不等待调用方丢弃
+            if preview.complete {
+                self.preview = None;
+            }
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,96 @@
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::config::RateLimitConfig;
+
+// 桶数量超过该值时清理已经补满的桶，避免大量一次性 token 占用内存
+const PRUNE_THRESHOLD: usize = 10_000;
+
+// 补充速率下限，避免配置为 0 时等待时间无穷大
+const MIN_REQUESTS_PER_SECOND: f64 = 0.001;
+
+// 未携带 token 的请求共用的桶键
+const ANONYMOUS_TOKEN: &str = "anonymous";
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+// 令牌桶限流器，桶保存在共享的 DashMap 中，路由器的所有克隆共用同一份状态
+// 速率和容量每次从当前配置读取，配置热更新后立即生效
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    buckets: Arc<DashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 根据配置拼出桶键，token 和服务都不区分时所有请求共用一个桶
+    pub fn bucket_key(
+        &self,
+        config: &RateLimitConfig,
+        headers: &http::HeaderMap,
+        service_name: &str,
+    ) -> String {
+        let token = config.per_token.then(|| {
+            headers
+                .get(config.token_header.as_str())
+                .and_then(|v| v.to_str().ok())
+                .filter(|v| !v.is_empty())
+                .unwrap_or(ANONYMOUS_TOKEN)
+        });
+        let service = config.per_service.then_some(service_name);
+
+        match (token, service) {
+            (Some(token), Some(service)) => format!("{token}|{service}"),
+            (Some(token), None) => token.to_string(),
+            (None, Some(service)) => format!("|{service}"),
+            (None, None) => String::new(),
+        }
+    }
+
+    // 取走一个令牌；桶已空时返回需要等待的时间
+    pub fn try_acquire(&self, config: &RateLimitConfig, key: &str) -> Result<(), Duration> {
+        let rate = config.requests_per_second.max(MIN_REQUESTS_PER_SECOND);
+        let burst = config.burst.max(1) as f64;
+
+        if self.buckets.len() > PRUNE_THRESHOLD {
+            self.prune_full_buckets(rate, burst);
+        }
+
+        let now = Instant::now();
+        let mut bucket = self
+            .buckets
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucket {
+                tokens: burst,
+                last_refill: now,
+            });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+
+    // 移除已经补满的桶，它们与新建的桶没有区别
+    fn prune_full_buckets(&self, rate: f64, burst: f64) {
+        let now = Instant::now();
+        self.buckets.retain(|_, bucket| {
+            let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+            bucket.tokens + elapsed * rate < burst
+        });
+    }
+}
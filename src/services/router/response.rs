@@ -1,5 +1,129 @@
 use super::error::RouterError;
-use http_body_util::{BodyExt, Empty};
+use super::stream_body::TRAILER_HEADERS;
+use crate::registry::ForwardResponse;
+use bytes::Bytes;
+use http_body::Frame;
+use http_body_util::{BodyExt, Empty, StreamBody};
+use prost::Message;
+use std::collections::HashMap;
+
+// ErrorInfo 中标识网关的 domain
+pub const ERROR_DOMAIN: &str = "gateway.opizontas";
+const ERROR_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.ErrorInfo";
+
+// google.rpc.Status，序列化后作为 grpc-status-details-bin 返回
+#[derive(Clone, PartialEq, Message)]
+pub struct RpcStatus {
+    #[prost(int32, tag = "1")]
+    pub code: i32,
+    #[prost(string, tag = "2")]
+    pub message: String,
+    #[prost(message, repeated, tag = "3")]
+    pub details: Vec<RpcAny>,
+}
+
+// google.protobuf.Any
+#[derive(Clone, PartialEq, Message)]
+pub struct RpcAny {
+    #[prost(string, tag = "1")]
+    pub type_url: String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub value: Vec<u8>,
+}
+
+// google.rpc.ErrorInfo，reason 为 RouterError 的错误种类
+#[derive(Clone, PartialEq, Message)]
+pub struct ErrorInfo {
+    #[prost(string, tag = "1")]
+    pub reason: String,
+    #[prost(string, tag = "2")]
+    pub domain: String,
+    #[prost(map = "string, string", tag = "3")]
+    pub metadata: HashMap<String, String>,
+}
+
+impl RpcStatus {
+    // 取出 details 中的 ErrorInfo
+    pub fn error_info(&self) -> Option<ErrorInfo> {
+        self.details
+            .iter()
+            .find(|detail| detail.type_url == ERROR_INFO_TYPE_URL)
+            .and_then(|detail| ErrorInfo::decode(detail.value.as_slice()).ok())
+    }
+}
+
+// 将路由错误转换为带详情的 gRPC 状态
+pub fn error_status(error: &RouterError) -> tonic::Status {
+    let code = error.grpc_code();
+    let message = error.message();
+
+    let mut metadata = HashMap::new();
+    if let RouterError::RateLimited { retry_after } = error {
+        metadata.insert(
+            "retry_after_ms".to_string(),
+            retry_after.as_millis().max(1).to_string(),
+        );
+    }
+    let error_info = ErrorInfo {
+        reason: error.kind().to_string(),
+        domain: ERROR_DOMAIN.to_string(),
+        metadata,
+    };
+    let details = RpcStatus {
+        code: code as i32,
+        message: message.clone(),
+        details: vec![RpcAny {
+            type_url: ERROR_INFO_TYPE_URL.to_string(),
+            value: error_info.encode_to_vec(),
+        }],
+    };
+
+    tonic::Status::with_details(code, message, Bytes::from(details.encode_to_vec()))
+}
+
+// 反向连接响应的 status_code 对应的 gRPC 状态码：0-16 为 gRPC 状态码，
+// 其余按 HTTP 状态码映射（与 gRPC 规范中 HTTP 到 gRPC 的映射一致）；
+// 成功状态附带 error_message 时视为失败
+pub fn reverse_status_code(status_code: i32, error_message: &str) -> tonic::Code {
+    let code = match status_code {
+        0..=16 => tonic::Code::from_i32(status_code),
+        200..=299 => tonic::Code::Ok,
+        400 => tonic::Code::Internal,
+        401 => tonic::Code::Unauthenticated,
+        403 => tonic::Code::PermissionDenied,
+        404 => tonic::Code::Unimplemented,
+        429 | 502 | 503 | 504 => tonic::Code::Unavailable,
+        _ => tonic::Code::Unknown,
+    };
+    if code == tonic::Code::Ok && !error_message.is_empty() {
+        return tonic::Code::Unknown;
+    }
+    code
+}
+
+// 反向连接响应的 gRPC 状态头：微服务在 headers 中给出 grpc-status 时原样透传，
+// 否则由 status_code 与 error_message 生成
+pub fn reverse_status_headers(response: &ForwardResponse) -> http::HeaderMap {
+    let mut headers = http::HeaderMap::new();
+    if response.headers.contains_key("grpc-status") {
+        for name in TRAILER_HEADERS {
+            if let Some(value) = response.headers.get(name)
+                && let Ok(value) = http::HeaderValue::from_str(value)
+            {
+                headers.insert(name, value);
+            }
+        }
+        return headers;
+    }
+
+    let code = reverse_status_code(response.status_code, &response.error_message);
+    let status = tonic::Status::new(code, response.error_message.clone());
+    if let Err(e) = status.add_header(&mut headers) {
+        tracing::warn!(error = %e, "Failed to encode reverse response status");
+        headers.insert("grpc-status", http::HeaderValue::from(code as i32));
+    }
+    headers
+}
 
 // 创建错误响应
 pub fn create_error_response(
@@ -10,25 +134,45 @@ pub fn create_error_response(
         Box<dyn std::error::Error + Send + Sync>,
     >,
 > {
-    let (grpc_status, message) = match error {
-        RouterError::ServiceNotFound(msg) => ("5", msg.as_str()), // NOT_FOUND
-        RouterError::ServiceUnavailable(msg) => ("14", msg.as_str()), // UNAVAILABLE
-        RouterError::InvalidPath(msg) => ("3", msg.as_str()),     // INVALID_ARGUMENT
-        RouterError::ForwardingError(msg) => ("14", msg.as_str()), // UNAVAILABLE
-    };
+    let status = error_status(error);
 
-    tracing::error!(status = ?grpc_status, message = %message, "Creating error response");
+    tracing::error!(status = ?status.code(), message = %status.message(), "Creating error response");
 
-    // 使用 Result 处理而不是 unwrap()
-    match http::Response::builder()
+    // grpc-status、grpc-message 与 grpc-status-details-bin 由 tonic 负责编码
+    let mut status_headers = http::HeaderMap::new();
+    if let Err(e) = status.add_header(&mut status_headers) {
+        tracing::error!("Failed to encode error status: {}", e);
+        status_headers.insert("grpc-status", http::HeaderValue::from(status.code() as i32));
+    }
+
+    let mut builder = http::Response::builder()
         .status(200) // HTTP status is always 200 for gRPC
-        .header("grpc-status", grpc_status)
-        .header("grpc-message", message)
-        .header("content-type", "application/grpc")
-        .body(http_body_util::combinators::UnsyncBoxBody::new(
-            Empty::new()
-                .map_err(|never| -> Box<dyn std::error::Error + Send + Sync> { match never {} }),
-        )) {
+        .header("content-type", "application/grpc");
+
+    // 状态同时放在响应头中，兼容按 Trailers-Only 响应处理的客户端
+    for (name, value) in &status_headers {
+        builder = builder.header(name, value);
+    }
+
+    // 限流时告知调用方多久后重试：retry-after 为秒（向上取整），
+    // grpc-retry-pushback-ms 供启用了重试策略的 gRPC 客户端使用
+    if let RouterError::RateLimited { retry_after } = error {
+        let millis = retry_after.as_millis().max(1);
+        builder = builder
+            .header("retry-after", millis.div_ceil(1000).to_string())
+            .header("grpc-retry-pushback-ms", millis.to_string());
+    }
+
+    // 响应体为空，状态作为 trailers 发出，流式调用的客户端从 trailers 读取状态
+    let body = StreamBody::new(futures::stream::once(futures::future::ready(Ok::<
+        _,
+        Box<dyn std::error::Error + Send + Sync>,
+    >(
+        Frame::trailers(status_headers),
+    ))));
+
+    // 使用 Result 处理而不是 unwrap()
+    match builder.body(http_body_util::combinators::UnsyncBoxBody::new(body)) {
         Ok(response) => response,
         Err(e) => {
             tracing::error!("Failed to create error response: {}", e);
@@ -0,0 +1,301 @@
+use super::deadline;
+use crate::config::ShadowTarget;
+use crate::services::client_manager::GrpcClientManager;
+use bytes::{Bytes, BytesMut};
+use http_body::{Body, Frame};
+use http_body_util::{BodyExt, Full};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tower::util::ServiceExt;
+
+// gRPC 消息帧头：1 字节压缩标志 + 4 字节大端长度
+const GRPC_FRAME_HEADER_LEN: usize = 5;
+
+// 采样计数，所有镜像服务共用
+static SAMPLE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// 按采样率决定本次请求是否镜像：计数乘以采样率每跨过一个整数镜像一次，长期比例等于采样率
+pub fn should_mirror(sample_rate: f64) -> bool {
+    if sample_rate >= 1.0 {
+        return true;
+    }
+    if sample_rate.is_nan() || sample_rate <= 0.0 {
+        return false;
+    }
+    let count = SAMPLE_COUNTER.fetch_add(1, Ordering::Relaxed) as f64;
+    ((count + 1.0) * sample_rate).floor() > (count * sample_rate).floor()
+}
+
+// 请求体恰好是一个完整的 gRPC 消息，即一元请求
+fn is_single_message(payload: &[u8]) -> bool {
+    if payload.len() < GRPC_FRAME_HEADER_LEN {
+        return false;
+    }
+    let len = u32::from_be_bytes([payload[1], payload[2], payload[3], payload[4]]) as usize;
+    payload.len() == GRPC_FRAME_HEADER_LEN + len
+}
+
+// 转发给主实例时顺带复制请求体，请求体完整结束后交给镜像任务；
+// 超过上限或读取出错时放弃镜像，不影响主请求
+pub struct MirrorBody<B> {
+    inner: Pin<Box<B>>,
+    buffer: BytesMut,
+    limit: usize,
+    sender: Option<oneshot::Sender<Bytes>>,
+}
+
+impl<B> MirrorBody<B> {
+    // 不镜像的请求只透传
+    pub fn passthrough(inner: B) -> Self {
+        Self {
+            inner: Box::pin(inner),
+            buffer: BytesMut::new(),
+            limit: 0,
+            sender: None,
+        }
+    }
+
+    pub fn new(inner: B, limit: usize) -> (Self, oneshot::Receiver<Bytes>) {
+        let (sender, receiver) = oneshot::channel();
+        let body = Self {
+            inner: Box::pin(inner),
+            buffer: BytesMut::new(),
+            limit,
+            sender: Some(sender),
+        };
+        (body, receiver)
+    }
+
+    fn finish(&mut self) {
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.send(std::mem::take(&mut self.buffer).freeze());
+        }
+    }
+}
+
+impl<B> Body for MirrorBody<B>
+where
+    B: Body<Data = Bytes>,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let frame = std::task::ready!(self.inner.as_mut().poll_frame(cx));
+        match &frame {
+            Some(Ok(frame)) => {
+                if let Some(data) = frame.data_ref()
+                    && self.sender.is_some()
+                {
+                    if self.buffer.len() + data.len() > self.limit {
+                        self.sender = None;
+                        self.buffer = BytesMut::new();
+                    } else {
+                        self.buffer.extend_from_slice(data);
+                    }
+                }
+                // 调用方可能不再轮询已经结束的请求体
+                if self.inner.is_end_stream() {
+                    self.finish();
+                }
+            }
+            Some(Err(_)) => self.sender = None,
+            None => self.finish(),
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// 记录主响应的 grpc-status：出现在响应头时直接发送，否则从 trailers 中取得
+pub struct StatusTap<B> {
+    inner: Pin<Box<B>>,
+    sender: Option<oneshot::Sender<String>>,
+}
+
+impl<B> StatusTap<B> {
+    pub fn new(inner: B, sender: oneshot::Sender<String>) -> Self {
+        Self {
+            inner: Box::pin(inner),
+            sender: Some(sender),
+        }
+    }
+}
+
+impl<B> Body for StatusTap<B>
+where
+    B: Body<Data = Bytes>,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let frame = std::task::ready!(self.inner.as_mut().poll_frame(cx));
+        if let Some(Ok(frame)) = &frame
+            && let Some(status) = frame.trailers_ref().and_then(grpc_status)
+            && let Some(sender) = self.sender.take()
+        {
+            let _ = sender.send(status);
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+pub fn grpc_status(headers: &http::HeaderMap) -> Option<String> {
+    headers
+        .get("grpc-status")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+// 镜像请求所需的信息，在主请求转发前复制
+pub struct ShadowRequest {
+    pub target: ShadowTarget,
+    pub service_name: String,
+    pub uri: http::Uri,
+    pub headers: http::HeaderMap,
+    pub timeout: Duration,
+}
+
+impl ShadowRequest {
+    // 后台发送镜像请求：等待主请求体结束，只镜像一元请求，影子响应只用于和主响应比较状态码
+    pub fn spawn(
+        self,
+        client_manager: GrpcClientManager,
+        body: oneshot::Receiver<Bytes>,
+        primary_status: oneshot::Receiver<String>,
+    ) {
+        tokio::spawn(async move {
+            let Ok(payload) = body.await else {
+                tracing::debug!(
+                    service_name = %self.service_name,
+                    "Primary request body incomplete, skipping shadow request"
+                );
+                return;
+            };
+            if !is_single_message(&payload) {
+                tracing::debug!(
+                    service_name = %self.service_name,
+                    "Request is not unary, skipping shadow request"
+                );
+                return;
+            }
+
+            let shadow_status = self.send(&client_manager, payload).await;
+            let primary_status = tokio::time::timeout(self.timeout, primary_status)
+                .await
+                .ok()
+                .and_then(Result::ok);
+
+            match (primary_status, shadow_status) {
+                (Some(primary), Ok(shadow)) if primary == shadow => {
+                    tracing::debug!(
+                        service_name = %self.service_name,
+                        path = %self.uri.path(),
+                        shadow_address = %self.target.address,
+                        grpc_status = %primary,
+                        "Shadow response status matches primary"
+                    );
+                }
+                (primary, shadow) => {
+                    tracing::warn!(
+                        service_name = %self.service_name,
+                        path = %self.uri.path(),
+                        shadow_address = %self.target.address,
+                        primary_status = primary.as_deref().unwrap_or("unknown"),
+                        shadow_status = %shadow.unwrap_or_else(|e| e),
+                        "Shadow response status mismatch"
+                    );
+                }
+            }
+        });
+    }
+
+    // 发送到影子实例，返回其 grpc-status，失败时返回错误描述
+    async fn send(
+        &self,
+        client_manager: &GrpcClientManager,
+        payload: Bytes,
+    ) -> Result<String, String> {
+        let channel = client_manager
+            .get_or_create_client(&self.target.address)
+            .await
+            .map_err(|e| format!("connect failed: {e}"))?;
+
+        let mut builder = http::Request::builder()
+            .method(http::Method::POST)
+            .uri(self.uri.clone());
+        for (name, value) in self.headers.iter() {
+            if name != deadline::GRPC_TIMEOUT {
+                builder = builder.header(name, value);
+            }
+        }
+        let request = builder
+            .header(
+                deadline::GRPC_TIMEOUT,
+                deadline::format_grpc_timeout(self.timeout),
+            )
+            .body(tonic::body::Body::new(Full::new(payload)))
+            .map_err(|e| format!("invalid request: {e}"))?;
+
+        let response = tokio::time::timeout(self.timeout, channel.oneshot(request))
+            .await
+            .map_err(|_| "timeout".to_string())?
+            .map_err(|e| format!("request failed: {e}"))?;
+        if let Some(status) = grpc_status(response.headers()) {
+            return Ok(status);
+        }
+
+        let collected = tokio::time::timeout(self.timeout, response.into_body().collect())
+            .await
+            .map_err(|_| "timeout".to_string())?
+            .map_err(|e| format!("response failed: {e}"))?;
+        Ok(collected
+            .trailers()
+            .and_then(grpc_status)
+            .unwrap_or_else(|| "unknown".to_string()))
+    }
+}
+
+type RouterResponse = http::Response<
+    http_body_util::combinators::UnsyncBoxBody<Bytes, Box<dyn std::error::Error + Send + Sync>>,
+>;
+
+// 把主响应的状态码交给镜像任务比较，没有镜像时原样返回
+pub fn observe_primary(
+    response: RouterResponse,
+    sender: Option<oneshot::Sender<String>>,
+) -> RouterResponse {
+    let Some(sender) = sender else {
+        return response;
+    };
+    if let Some(status) = grpc_status(response.headers()) {
+        let _ = sender.send(status);
+        return response;
+    }
+    response.map(|body| StatusTap::new(body, sender).boxed_unsync())
+}
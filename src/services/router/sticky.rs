@@ -0,0 +1,59 @@
+use dashmap::DashMap;
+use std::time::{Duration, Instant};
+use tonic::transport::server::TcpConnectInfo;
+
+// 实例选择粘滞：窗口内同一客户端对同一服务的请求优先选择上次选中的实例，
+// 该实例不健康或已下线时按正常的负载均衡重新选择。
+// 与会话亲和不同，粘滞只是短时间内的偏好，窗口过期后重新参与负载均衡
+
+// 条目数超过该值时，写入前先清理已过期的条目
+const PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Default)]
+pub struct StickyInstances {
+    // (客户端标识, 服务名) -> (上次选中的实例, 过期时间)
+    entries: DashMap<(String, String), (String, Instant)>,
+}
+
+impl StickyInstances {
+    // 窗口内上次为该客户端选中的实例，正向转发为实例地址，反向连接为连接 ID
+    pub fn get(&self, client_id: &str, service_name: &str) -> Option<String> {
+        let key = (client_id.to_string(), service_name.to_string());
+        let now = Instant::now();
+        if let Some(entry) = self.entries.get(&key)
+            && entry.1 > now
+        {
+            return Some(entry.0.clone());
+        }
+        self.entries
+            .remove_if(&key, |_, (_, expires)| *expires <= now);
+        None
+    }
+
+    // 记录本次选中的实例，窗口从现在重新开始计算
+    pub fn record(&self, client_id: &str, service_name: &str, instance: &str, window: Duration) {
+        let now = Instant::now();
+        if self.entries.len() >= PRUNE_THRESHOLD {
+            self.entries.retain(|_, (_, expires)| *expires > now);
+        }
+        self.entries.insert(
+            (client_id.to_string(), service_name.to_string()),
+            (instance.to_string(), now + window),
+        );
+    }
+}
+
+// 请求的客户端标识：优先取 header 指定的请求头，否则取 TCP 对端的 IP
+pub fn client_id<B>(req: &http::Request<B>, header: Option<&str>) -> Option<String> {
+    if let Some(value) = header
+        .and_then(|header| req.headers().get(header))
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+    {
+        return Some(value.to_string());
+    }
+    req.extensions()
+        .get::<TcpConnectInfo>()
+        .and_then(TcpConnectInfo::remote_addr)
+        .map(|addr| addr.ip().to_string())
+}
@@ -0,0 +1,101 @@
+use bytes::Bytes;
+use futures::Stream;
+use http_body::{Body, Frame};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use super::response;
+use crate::services::connection::ResponseChunks;
+
+// 只在流末尾发送的 gRPC 状态头
+pub const TRAILER_HEADERS: [&str; 3] = ["grpc-status", "grpc-message", "grpc-status-details-bin"];
+
+// 反向连接流式响应的响应体：先发出第一个数据块，再按序号发出后续数据块，
+// 最后一个数据块的状态（grpc-status 等状态头，或由 status_code 生成）作为 trailers 发出
+pub struct ReverseStreamBody {
+    head: Option<Bytes>,
+    chunks: ResponseChunks,
+    // 已发出的字节数，超过 max_body_size 时中止
+    sent: usize,
+    max_body_size: usize,
+    pending_trailers: Option<http::HeaderMap>,
+    finished: bool,
+}
+
+impl ReverseStreamBody {
+    pub fn new(head: Vec<u8>, chunks: ResponseChunks, max_body_size: usize) -> Self {
+        Self {
+            sent: head.len(),
+            head: Some(Bytes::from(head)),
+            chunks,
+            max_body_size,
+            pending_trailers: None,
+            finished: false,
+        }
+    }
+}
+
+impl Body for ReverseStreamBody {
+    type Data = Bytes;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if let Some(head) = self.head.take()
+            && !head.is_empty()
+        {
+            return Poll::Ready(Some(Ok(Frame::data(head))));
+        }
+
+        loop {
+            if let Some(trailers) = self.pending_trailers.take() {
+                self.finished = true;
+                return Poll::Ready(Some(Ok(Frame::trailers(trailers))));
+            }
+            if self.finished {
+                return Poll::Ready(None);
+            }
+
+            let chunk = match std::task::ready!(Pin::new(&mut self.chunks).poll_next(cx)) {
+                Some(Ok(chunk)) => chunk,
+                Some(Err(message)) => {
+                    self.finished = true;
+                    return Poll::Ready(Some(Err(message.into())));
+                }
+                None => {
+                    self.finished = true;
+                    return Poll::Ready(Some(Err(
+                        "Streaming response ended before the final chunk".into(),
+                    )));
+                }
+            };
+
+            self.sent += chunk.payload.len();
+            if self.sent > self.max_body_size {
+                self.finished = true;
+                return Poll::Ready(Some(Err(format!(
+                    "Response body too large: {} bytes (max: {} bytes)",
+                    self.sent, self.max_body_size
+                )
+                .into())));
+            }
+
+            if chunk
+                .response_stream_info
+                .as_ref()
+                .is_some_and(|info| info.is_final_chunk)
+            {
+                self.pending_trailers = Some(response::reverse_status_headers(&chunk));
+            }
+            if !chunk.payload.is_empty() {
+                return Poll::Ready(Some(Ok(Frame::data(Bytes::from(chunk.payload)))));
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.finished && self.pending_trailers.is_none() && self.head.is_none()
+    }
+}
@@ -0,0 +1,47 @@
+use http::{HeaderMap, HeaderName, HeaderValue};
+
+// W3C Trace Context 请求头，经反向连接转发时原样保留
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+pub const TRACESTATE_HEADER: &str = "tracestate";
+
+const TRACE_CONTEXT_HEADERS: [&str; 2] = [TRACEPARENT_HEADER, TRACESTATE_HEADER];
+
+// 入站请求携带的 trace context
+#[derive(Debug, Clone, Default)]
+pub struct TraceContext {
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl TraceContext {
+    // 从入站请求头中提取 traceparent 和 tracestate
+    pub fn extract(headers: &HeaderMap) -> Self {
+        let headers = TRACE_CONTEXT_HEADERS
+            .iter()
+            .filter_map(|name| {
+                headers
+                    .get(*name)
+                    .map(|value| (HeaderName::from_static(name), value.clone()))
+            })
+            .collect();
+        Self { headers }
+    }
+
+    // traceparent 中的 trace-id，格式为 version-traceid-parentid-flags
+    pub fn trace_id(&self) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name == TRACEPARENT_HEADER)
+            .and_then(|(_, value)| value.to_str().ok())
+            .and_then(|traceparent| traceparent.split('-').nth(1))
+            .filter(|trace_id| trace_id.len() == 32)
+    }
+
+    // 微服务响应未携带 trace context 时，把入站值写回响应头
+    pub fn restore(&self, response_headers: &mut HeaderMap) {
+        for (name, value) in &self.headers {
+            if !response_headers.contains_key(name) {
+                response_headers.insert(name.clone(), value.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::service::Routes;
+use tonic::{Request, Response, Status};
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::health::health_server::{Health, HealthServer};
+use grpc_opizontas::health::{
+    HealthCheckRequest, HealthCheckResponse, health_check_response::ServingStatus,
+};
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::services::client_manager::GrpcClientManager;
+use grpc_opizontas::services::registry::ActiveHealthChecker;
+use grpc_opizontas::services::{MyRegistryService, ServiceHealthStatus};
+
+use common::unused_addr;
+
+const TOKEN: &str = "active-health-token";
+const SERVICE: &str = "probe.ProbeService";
+
+// 可切换健康状态的后端
+#[derive(Clone)]
+struct FlakyHealth {
+    serving: Arc<AtomicBool>,
+}
+
+#[tonic::async_trait]
+impl Health for FlakyHealth {
+    type WatchStream = ReceiverStream<Result<HealthCheckResponse, Status>>;
+
+    async fn check(
+        &self,
+        _request: Request<HealthCheckRequest>,
+    ) -> Result<Response<HealthCheckResponse>, Status> {
+        if self.serving.load(Ordering::SeqCst) {
+            Ok(Response::new(HealthCheckResponse {
+                status: ServingStatus::Serving as i32,
+            }))
+        } else {
+            Err(Status::unavailable("backend failing"))
+        }
+    }
+
+    async fn watch(
+        &self,
+        _request: Request<HealthCheckRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        Err(Status::unimplemented("watch not supported"))
+    }
+}
+
+fn instance_status(registry_service: &MyRegistryService) -> ServiceHealthStatus {
+    registry_service
+        .get_service_info(SERVICE)
+        .expect("Service should stay registered")
+        .health_status
+}
+
+#[tokio::test]
+async fn test_failing_backend_transitions_health_status() {
+    let serving = Arc::new(AtomicBool::new(true));
+    let (listener, backend_addr) = common::bind().await;
+    let backend = HealthServer::new(FlakyHealth {
+        serving: serving.clone(),
+    });
+    common::serve_routes(listener, Routes::new(backend));
+
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.health_check.failure_threshold = 2;
+
+    let registry_service = Arc::new(MyRegistryService::new(config.clone()));
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: format!("http://{backend_addr}"),
+            services: vec![SERVICE.to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+
+    let checker = ActiveHealthChecker::new(
+        registry_service.clone(),
+        GrpcClientManager::default(),
+        &config,
+    );
+
+    checker.check_all().await;
+    assert_eq!(
+        instance_status(&registry_service),
+        ServiceHealthStatus::Healthy
+    );
+
+    // 第一次失败未达到阈值，状态保持不变
+    serving.store(false, Ordering::SeqCst);
+    checker.check_all().await;
+    assert_eq!(
+        instance_status(&registry_service),
+        ServiceHealthStatus::Healthy
+    );
+
+    // 连续失败达到阈值后标记为 Unhealthy，健康检查服务同步变为 NOT_SERVING
+    checker.check_all().await;
+    assert_eq!(
+        instance_status(&registry_service),
+        ServiceHealthStatus::Unhealthy
+    );
+    assert!(
+        !registry_service
+            .get_healthy_services()
+            .contains_key(SERVICE)
+    );
+
+    // 恢复后一次成功即重新标记为 Healthy
+    serving.store(true, Ordering::SeqCst);
+    checker.check_all().await;
+    assert_eq!(
+        instance_status(&registry_service),
+        ServiceHealthStatus::Healthy
+    );
+}
+
+#[tokio::test]
+async fn test_unreachable_backend_marked_unhealthy() {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.health_check.failure_threshold = 1;
+
+    let registry_service = Arc::new(MyRegistryService::new(config.clone()));
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: format!("http://{}", unused_addr()),
+            services: vec![SERVICE.to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+
+    let checker = ActiveHealthChecker::new(
+        registry_service.clone(),
+        GrpcClientManager::default(),
+        &config,
+    );
+    checker.check_all().await;
+
+    // 探测失败只改变健康状态，不会移除实例
+    assert_eq!(
+        instance_status(&registry_service),
+        ServiceHealthStatus::Unhealthy
+    );
+}
@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::sync::mpsc;
+use tonic::{Code, Request};
+
+use grpc_opizontas::admin::{
+    EventTypeSubscriptions, ForceUnregisterRequest, GetConnectionStatsRequest,
+    GetServiceInstancesRequest, HealthStatus, ListEventSubscriptionsRequest,
+    ListRegisteredServicesRequest, ListReverseConnectionsRequest, SetServiceHealthRequest,
+    admin_server::Admin,
+};
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::registry::{RegisterRequest, SubscriptionRequest, subscription_request};
+use grpc_opizontas::services::admin::AdminService;
+use grpc_opizontas::services::client_manager::GrpcClientManager;
+use grpc_opizontas::services::{MyRegistryService, ServiceHealthStatus};
+
+const TOKEN: &str = "admin-test-token";
+const ADMIN_TOKEN: &str = "admin-test-admin-token";
+// 进程内订阅时代表调用方的标识
+const OWNER: &str = "admin-test-owner";
+
+// 注册 OrderService 的两个实例和 UserService 的一个实例
+async fn setup() -> (Arc<MyRegistryService>, AdminService) {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.security.admin_tokens = vec![ADMIN_TOKEN.to_string()];
+
+    let registry_service = Arc::new(MyRegistryService::new(config));
+    for (address, services, metadata) in [
+        (
+            "http://127.0.0.1:50201",
+            vec!["OrderService", "UserService"],
+            HashMap::from([("version".to_string(), "v1".to_string())]),
+        ),
+        (
+            "http://127.0.0.1:50202",
+            vec!["OrderService"],
+            HashMap::new(),
+        ),
+    ] {
+        registry_service
+            .register(Request::new(RegisterRequest {
+                api_key: TOKEN.to_string(),
+                address: address.to_string(),
+                services: services.into_iter().map(str::to_string).collect(),
+                metadata,
+                ..Default::default()
+            }))
+            .await
+            .expect("Failed to register service");
+    }
+
+    let admin = AdminService::new(registry_service.clone(), GrpcClientManager::default());
+    (registry_service, admin)
+}
+
+#[tokio::test]
+async fn test_admin_rpcs_require_admin_token() {
+    let (_registry_service, admin) = setup().await;
+
+    // 注册用的 token 不能访问管理接口
+    for token in [TOKEN, "", "wrong-token"] {
+        let status = admin
+            .list_registered_services(Request::new(ListRegisteredServicesRequest {
+                admin_token: token.to_string(),
+            }))
+            .await
+            .expect_err("Admin RPC accepted a non-admin token");
+        assert_eq!(status.code(), Code::Unauthenticated);
+    }
+
+    let status = admin
+        .force_unregister(Request::new(ForceUnregisterRequest {
+            admin_token: TOKEN.to_string(),
+            service_name: "OrderService".to_string(),
+        }))
+        .await
+        .expect_err("Admin RPC accepted a non-admin token");
+    assert_eq!(status.code(), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn test_list_registered_services_and_instances() {
+    let (registry_service, admin) = setup().await;
+    assert!(registry_service.set_instance_health(
+        "OrderService",
+        "http://127.0.0.1:50202",
+        ServiceHealthStatus::Draining,
+    ));
+
+    let services = admin
+        .list_registered_services(Request::new(ListRegisteredServicesRequest {
+            admin_token: ADMIN_TOKEN.to_string(),
+        }))
+        .await
+        .expect("Failed to list services")
+        .into_inner()
+        .services;
+    let summary: Vec<_> = services
+        .iter()
+        .map(|s| {
+            (
+                s.service_name.as_str(),
+                s.instance_count,
+                s.healthy_instance_count,
+            )
+        })
+        .collect();
+    assert_eq!(summary, vec![("OrderService", 2, 1), ("UserService", 1, 1)]);
+
+    let instances = admin
+        .get_service_instances(Request::new(GetServiceInstancesRequest {
+            admin_token: ADMIN_TOKEN.to_string(),
+            service_name: "OrderService".to_string(),
+        }))
+        .await
+        .expect("Failed to get instances")
+        .into_inner()
+        .instances;
+    assert_eq!(instances.len(), 2);
+    assert_eq!(instances[0].address, "http://127.0.0.1:50201");
+    assert_eq!(instances[0].health_status, HealthStatus::Healthy as i32);
+    assert_eq!(instances[0].metadata.get("version").unwrap(), "v1");
+    assert!(instances[0].last_heartbeat > 0);
+    assert_eq!(instances[1].health_status, HealthStatus::Draining as i32);
+
+    let status = admin
+        .get_service_instances(Request::new(GetServiceInstancesRequest {
+            admin_token: ADMIN_TOKEN.to_string(),
+            service_name: "MissingService".to_string(),
+        }))
+        .await
+        .expect_err("Unknown service returned instances");
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_set_service_health_and_force_unregister() {
+    let (registry_service, admin) = setup().await;
+
+    let response = admin
+        .set_service_health(Request::new(SetServiceHealthRequest {
+            admin_token: ADMIN_TOKEN.to_string(),
+            service_name: "OrderService".to_string(),
+            health_status: HealthStatus::Unhealthy as i32,
+        }))
+        .await
+        .expect("Failed to set health")
+        .into_inner();
+    assert_eq!(response.updated_instances, 2);
+    let healthy = registry_service.get_healthy_services();
+    assert!(!healthy.contains_key("OrderService"));
+    assert!(healthy.contains_key("UserService"));
+
+    let status = admin
+        .set_service_health(Request::new(SetServiceHealthRequest {
+            admin_token: ADMIN_TOKEN.to_string(),
+            service_name: "OrderService".to_string(),
+            health_status: 42,
+        }))
+        .await
+        .expect_err("Invalid health status accepted");
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let response = admin
+        .force_unregister(Request::new(ForceUnregisterRequest {
+            admin_token: ADMIN_TOKEN.to_string(),
+            service_name: "OrderService".to_string(),
+        }))
+        .await
+        .expect("Failed to unregister")
+        .into_inner();
+    assert_eq!(response.removed_instances, 2);
+    assert!(!registry_service.registry.contains_key("OrderService"));
+
+    let status = admin
+        .force_unregister(Request::new(ForceUnregisterRequest {
+            admin_token: ADMIN_TOKEN.to_string(),
+            service_name: "OrderService".to_string(),
+        }))
+        .await
+        .expect_err("Unregistered service twice");
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_get_connection_stats() {
+    let (registry_service, admin) = setup().await;
+    registry_service
+        .reverse_connection_manager
+        .register_connection(
+            "admin-conn".to_string(),
+            vec!["ReverseService".to_string()],
+            1,
+            mpsc::channel(16).0,
+        )
+        .await
+        .expect("Failed to register reverse connection");
+
+    let stats = admin
+        .get_connection_stats(Request::new(GetConnectionStatsRequest {
+            admin_token: ADMIN_TOKEN.to_string(),
+        }))
+        .await
+        .expect("Failed to get stats")
+        .into_inner();
+    assert_eq!(stats.active_connections, 1);
+    assert_eq!(stats.registered_services, 1);
+    assert_eq!(stats.pending_requests, 0);
+    assert_eq!(stats.pooled_addresses, 0);
+}
+
+#[tokio::test]
+async fn test_list_reverse_connections() {
+    let (registry_service, admin) = setup().await;
+    let manager = &registry_service.reverse_connection_manager;
+    for (connection_id, services, weight) in [
+        ("conn-b", vec!["OrderService", "UserService"], 3),
+        ("conn-a", vec!["OrderService"], 1),
+    ] {
+        manager
+            .register_connection(
+                connection_id.to_string(),
+                services.into_iter().map(str::to_string).collect(),
+                weight,
+                mpsc::channel(16).0,
+            )
+            .await
+            .expect("Failed to register reverse connection");
+    }
+
+    let connections = manager.list_connections();
+    let ids: Vec<&str> = connections
+        .iter()
+        .map(|info| info.connection_id.as_str())
+        .collect();
+    assert_eq!(ids, ["conn-a", "conn-b"]);
+    assert_eq!(connections[1].services, ["OrderService", "UserService"]);
+    assert_eq!(
+        connections[1].pooled_services,
+        ["OrderService", "UserService"]
+    );
+    assert_eq!(connections[1].weight, 3);
+    assert!(manager.get_connection_info("OrderService").is_none());
+
+    // 重新注册后服务池成员随之变化
+    manager
+        .register_connection(
+            "conn-b".to_string(),
+            vec!["UserService".to_string()],
+            3,
+            mpsc::channel(16).0,
+        )
+        .await
+        .expect("Failed to re-register reverse connection");
+    let info = manager
+        .get_connection_info("conn-b")
+        .expect("Connection missing after re-registration");
+    assert_eq!(info.pooled_services, ["UserService"]);
+
+    let response = admin
+        .list_reverse_connections(Request::new(ListReverseConnectionsRequest {
+            admin_token: ADMIN_TOKEN.to_string(),
+            connection_id: String::new(),
+        }))
+        .await
+        .expect("Failed to list reverse connections")
+        .into_inner();
+    assert_eq!(response.connections.len(), 2);
+    assert_eq!(response.connections[0].connection_id, "conn-a");
+    assert_eq!(response.connections[0].pooled_services, ["OrderService"]);
+    assert_eq!(response.connections[1].services, ["UserService"]);
+    assert!(response.connections[1].last_heartbeat_age_ms < 1000);
+
+    let status = admin
+        .list_reverse_connections(Request::new(ListReverseConnectionsRequest {
+            admin_token: ADMIN_TOKEN.to_string(),
+            connection_id: "missing-conn".to_string(),
+        }))
+        .await
+        .expect_err("Unknown connection id accepted");
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_list_event_subscriptions() {
+    let (registry_service, admin) = setup().await;
+    let event_bus = &registry_service.reverse_connection_manager.event_bus;
+    let _order_stream = event_bus
+        .subscribe_event_type("order.created", "billing")
+        .expect("Failed to subscribe billing");
+    let _shipping_stream = event_bus
+        .subscribe_event_type("order.created", "shipping")
+        .expect("Failed to subscribe shipping");
+    event_bus
+        .handle_subscription_request(
+            SubscriptionRequest {
+                action: subscription_request::Action::Subscribe as i32,
+                event_types: vec!["order.created".to_string(), "user.deleted".to_string()],
+                subscriber_id: "audit".to_string(),
+                metadata_filter: HashMap::from([("tenant_id".to_string(), "acme".to_string())]),
+                reliable: false,
+            },
+            OWNER,
+        )
+        .await
+        .expect("Failed to subscribe audit");
+
+    let list = |event_type: &str| {
+        admin.list_event_subscriptions(Request::new(ListEventSubscriptionsRequest {
+            admin_token: ADMIN_TOKEN.to_string(),
+            event_type: event_type.to_string(),
+        }))
+    };
+
+    let snapshot = list("")
+        .await
+        .expect("Failed to list subscriptions")
+        .into_inner();
+    let ids: Vec<&str> = snapshot
+        .subscribers
+        .iter()
+        .map(|subscriber| subscriber.subscriber_id.as_str())
+        .collect();
+    assert_eq!(ids, ["audit", "billing", "shipping"]);
+    assert_eq!(
+        snapshot.event_types,
+        [
+            EventTypeSubscriptions {
+                event_type: "order.created".to_string(),
+                subscriber_count: 3,
+            },
+            EventTypeSubscriptions {
+                event_type: "user.deleted".to_string(),
+                subscriber_count: 1,
+            },
+        ]
+    );
+
+    // 只登记了订阅、没有订阅流的订阅者收不到实时事件
+    let audit = &snapshot.subscribers[0];
+    assert_eq!(audit.event_types, ["order.created", "user.deleted"]);
+    assert_eq!(audit.active_streams, 0);
+    assert!(audit.subscribed_seconds < 5);
+    let filters: Vec<&str> = audit
+        .filters
+        .iter()
+        .map(|filter| filter.event_type.as_str())
+        .collect();
+    assert_eq!(filters, ["order.created", "user.deleted"]);
+    assert_eq!(audit.filters[0].metadata["tenant_id"], "acme");
+    assert_eq!(snapshot.subscribers[1].active_streams, 1);
+    assert!(snapshot.subscribers[1].filters.is_empty());
+
+    let snapshot = list("user.deleted")
+        .await
+        .expect("Failed to list subscriptions")
+        .into_inner();
+    assert_eq!(snapshot.subscribers.len(), 1);
+    assert_eq!(snapshot.subscribers[0].subscriber_id, "audit");
+    assert_eq!(snapshot.event_types.len(), 1);
+}
@@ -0,0 +1,164 @@
+use std::io::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::timeout;
+use tokio_stream::StreamExt;
+use tonic::transport::server::TcpConnectInfo;
+use tonic::{Code, Request};
+
+use grpc_opizontas::config::{Config, LogFormat};
+use grpc_opizontas::logging;
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::registry::REGISTRATION_AUDIT_EVENT;
+use grpc_opizontas::services::registry::audit;
+
+const TOKEN: &str = "audit-token";
+const BAD_TOKEN: &str = "stolen-token";
+const PEER: &str = "10.1.2.3:40000";
+
+// 收集日志输出的缓冲区
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn registry_service(audit_events: bool) -> MyRegistryService {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.security.audit_events = audit_events;
+    MyRegistryService::new(config)
+}
+
+// 模拟 tonic 服务端为请求附带的连接信息
+fn register_request(token: &str) -> Request<RegisterRequest> {
+    let mut request = Request::new(RegisterRequest {
+        api_key: token.to_string(),
+        address: "http://127.0.0.1:50500".to_string(),
+        services: vec!["audit.TestService".to_string()],
+        ..Default::default()
+    });
+    request.extensions_mut().insert(TcpConnectInfo {
+        local_addr: None,
+        remote_addr: Some(PEER.parse::<SocketAddr>().unwrap()),
+    });
+    request
+}
+
+#[tokio::test]
+async fn test_rejected_registration_publishes_audit_event() {
+    let registry_service = registry_service(true);
+    let mut audit_events = registry_service
+        .reverse_connection_manager
+        .event_bus
+        .subscribe_event_type(REGISTRATION_AUDIT_EVENT, "auditor")
+        .expect("Failed to subscribe");
+
+    let status = registry_service
+        .register(register_request(BAD_TOKEN))
+        .await
+        .expect_err("Registration with an unknown token should fail");
+    assert_eq!(status.code(), Code::Unauthenticated);
+
+    let event = timeout(Duration::from_secs(1), audit_events.next())
+        .await
+        .expect("Timeout waiting for audit event")
+        .expect("Stream ended unexpectedly")
+        .expect("Event stream error");
+    let field = |key: &str| event.metadata.get(key).map(String::as_str);
+    assert_eq!(field("action"), Some("register"));
+    assert_eq!(field("peer"), Some(PEER));
+    assert_eq!(field("outcome"), Some("unauthenticated"));
+    assert_eq!(field("services"), Some("audit.TestService"));
+    assert_eq!(field("token_id"), Some(audit::token_id(BAD_TOKEN).as_str()));
+    assert!(
+        event
+            .metadata
+            .values()
+            .all(|value| !value.contains(BAD_TOKEN)),
+        "Audit event must not contain the raw token: {:?}",
+        event.metadata
+    );
+
+    // 成功的注册同样被记录
+    registry_service
+        .register(register_request(TOKEN))
+        .await
+        .expect("Registration should succeed");
+    let event = timeout(Duration::from_secs(1), audit_events.next())
+        .await
+        .expect("Timeout waiting for audit event")
+        .expect("Stream ended unexpectedly")
+        .expect("Event stream error");
+    assert_eq!(
+        event.metadata.get("outcome").map(String::as_str),
+        Some("success")
+    );
+}
+
+#[tokio::test]
+async fn test_audit_events_are_not_published_by_default() {
+    let registry_service = registry_service(false);
+    let mut audit_events = registry_service
+        .reverse_connection_manager
+        .event_bus
+        .subscribe_event_type(REGISTRATION_AUDIT_EVENT, "auditor")
+        .expect("Failed to subscribe");
+
+    registry_service
+        .register(register_request(BAD_TOKEN))
+        .await
+        .expect_err("Registration with an unknown token should fail");
+
+    assert!(
+        timeout(Duration::from_millis(200), audit_events.next())
+            .await
+            .is_err(),
+        "No audit event expected when audit_events is disabled"
+    );
+}
+
+#[tokio::test]
+async fn test_rejected_registration_writes_audit_log() {
+    let mut config = Config::default();
+    config.server.log_format = LogFormat::Json;
+    config.server.log_level = "info".to_string();
+
+    let buffer = SharedBuffer::default();
+    let writer = buffer.clone();
+    let subscriber = logging::build_subscriber(&config.server, move || writer.clone());
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    registry_service(false)
+        .register(register_request(BAD_TOKEN))
+        .await
+        .expect_err("Registration with an unknown token should fail");
+
+    let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    assert!(
+        !output.contains(BAD_TOKEN),
+        "Logs must not contain the raw token"
+    );
+    let record = output
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .find(|record| record["target"] == "audit")
+        .expect("No audit record written");
+    assert_eq!(record["level"], "WARN");
+    assert_eq!(record["fields"]["action"], "register");
+    assert_eq!(record["fields"]["peer"], PEER);
+    assert_eq!(record["fields"]["outcome"], "unauthenticated");
+    assert_eq!(record["fields"]["token_id"], audit::token_id(BAD_TOKEN));
+    assert!(record["fields"]["timestamp"].as_i64().unwrap_or(0) > 0);
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio_stream::StreamExt;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Code, Request};
+
+use grpc_opizontas::config::{Config, SecurityConfig};
+use grpc_opizontas::registry::registry_service_client::RegistryServiceClient;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::registry::{
+    ConnectionMessage, ConnectionRegister, DrainInstanceRequest, ListServicesRequest,
+    RegisterRequest, connection_message::MessageType,
+};
+use grpc_opizontas::services::MyRegistryService;
+
+fn register_request(token: &str) -> Request<RegisterRequest> {
+    Request::new(RegisterRequest {
+        api_key: token.to_string(),
+        address: "http://127.0.0.1:50500".to_string(),
+        services: vec!["auth.AuthService".to_string()],
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_auth_enabled_by_default() {
+    assert!(!Config::default().security.auth_disabled);
+    let security: SecurityConfig = toml::from_str(r#"tokens = []"#).unwrap();
+    assert!(!security.auth_disabled);
+}
+
+#[tokio::test]
+async fn test_enabled_auth_rejects_registration_without_tokens() {
+    // 未配置任何 token 时拒绝所有注册
+    let registry_service = MyRegistryService::new(Config::default());
+    for token in ["", "any-token"] {
+        let status = registry_service
+            .register(register_request(token))
+            .await
+            .expect_err("Registration accepted without configured tokens");
+        assert_eq!(status.code(), Code::Unauthenticated);
+    }
+    assert!(
+        registry_service
+            .get_service_info("auth.AuthService")
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_disabled_auth_accepts_any_token() {
+    let mut config = Config::default();
+    config.security.auth_disabled = true;
+    config
+        .security
+        .scoped_tokens
+        .insert("scoped-token".to_string(), vec!["billing.*".to_string()]);
+    let registry_service = MyRegistryService::new(config);
+
+    // 服务范围同样不再校验
+    for token in ["", "scoped-token"] {
+        registry_service
+            .register(register_request(token))
+            .await
+            .expect("Registration rejected with auth disabled");
+    }
+    assert!(
+        registry_service
+            .get_service_info("auth.AuthService")
+            .is_some()
+    );
+
+    // 查询和排空接口同样不校验 token
+    let services = registry_service
+        .list_services(Request::new(ListServicesRequest {
+            api_key: String::new(),
+        }))
+        .await
+        .expect("ListServices rejected with auth disabled")
+        .into_inner()
+        .services;
+    assert!(services.contains_key("auth.AuthService"));
+
+    // 同一地址重复注册返回已有实例的 ID
+    let instance_id = registry_service
+        .register(register_request(""))
+        .await
+        .expect("Registration rejected with auth disabled")
+        .into_inner()
+        .instance_id;
+    registry_service
+        .drain_instance(Request::new(DrainInstanceRequest {
+            api_key: String::new(),
+            service_name: "auth.AuthService".to_string(),
+            instance_id,
+            draining: true,
+        }))
+        .await
+        .expect("DrainInstance rejected with auth disabled");
+}
+
+// 启动网关并以指定 token 建立反向连接，返回建立结果
+async fn establish_reverse_connection(auth_disabled: bool) -> Result<ConnectionMessage, Code> {
+    let mut config = Config::default();
+    config.security.auth_disabled = auth_disabled;
+
+    let (gateway_addr, shutdown_tx, gateway) = common::spawn_gateway(config).await;
+
+    let channel = common::connect(gateway_addr).await;
+    let mut registry_client = RegistryServiceClient::new(channel);
+
+    let (reverse_tx, reverse_rx) = mpsc::channel(4);
+    reverse_tx
+        .send(ConnectionMessage {
+            message_type: Some(MessageType::Register(ConnectionRegister {
+                api_key: String::new(),
+                services: vec!["auth.AuthService".to_string()],
+                connection_id: String::new(),
+                weight: 1,
+            })),
+        })
+        .await
+        .unwrap();
+    let result = match registry_client
+        .establish_connection(ReceiverStream::new(reverse_rx))
+        .await
+    {
+        Ok(response) => {
+            let mut inbound = response.into_inner();
+            let first = tokio::time::timeout(Duration::from_secs(1), inbound.next())
+                .await
+                .expect("Timeout waiting for connection status")
+                .expect("Connection stream closed")
+                .expect("Connection stream failed");
+            Ok(first)
+        }
+        Err(status) => Err(status.code()),
+    };
+
+    drop(reverse_tx);
+    let _ = shutdown_tx.send(());
+    gateway
+        .await
+        .expect("Gateway task panicked")
+        .expect("Gateway failed");
+    result
+}
+
+#[tokio::test]
+async fn test_reverse_connection_auth_modes() {
+    assert_eq!(
+        establish_reverse_connection(false).await.unwrap_err(),
+        Code::Unauthenticated
+    );
+
+    let message = establish_reverse_connection(true)
+        .await
+        .expect("Reverse connection rejected with auth disabled");
+    assert!(matches!(message.message_type, Some(MessageType::Status(_))));
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::ConnectionMessage;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::connection::ReverseConnectionManager;
+use grpc_opizontas::services::router::DynamicRouter;
+
+use common::grpc_status;
+
+const SERVICE: &str = "QueueService";
+
+// 注册只有一个队列空位的反向连接，返回的接收端不被消费，模拟处理不过来的微服务
+async fn register_slow_connection(
+    manager: &ReverseConnectionManager,
+) -> mpsc::Receiver<ConnectionMessage> {
+    let (request_tx, request_rx) = mpsc::channel(1);
+    manager
+        .register_connection(
+            "queue-conn".to_string(),
+            vec![SERVICE.to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+    request_rx
+}
+
+#[tokio::test]
+async fn test_full_queue_fails_fast() {
+    let registry_service = MyRegistryService::new(Config::default());
+    let manager = registry_service.reverse_connection_manager.clone();
+    let mut request_rx = register_slow_connection(&manager).await;
+
+    // 第一个请求占满队列并等待响应
+    let first = tokio::spawn({
+        let manager = manager.clone();
+        async move {
+            manager
+                .send_request(
+                    SERVICE,
+                    "/queue.QueueService/Call",
+                    HashMap::new(),
+                    Vec::new(),
+                )
+                .await
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(manager.pending_request_count().await, 1);
+
+    // 队列已满时立即失败，不占用等待表
+    let error = timeout(
+        Duration::from_millis(200),
+        manager.send_request(
+            SERVICE,
+            "/queue.QueueService/Call",
+            HashMap::new(),
+            Vec::new(),
+        ),
+    )
+    .await
+    .expect("Send on a full queue should not block")
+    .expect_err("Send on a full queue should fail");
+    assert!(error.contains("queue is full"), "unexpected error: {error}");
+    assert_eq!(manager.pending_request_count().await, 1);
+
+    // 微服务取走消息后队列恢复可用
+    request_rx.recv().await.expect("Request channel closed");
+    let second = tokio::spawn({
+        let manager = manager.clone();
+        async move {
+            manager
+                .send_request(
+                    SERVICE,
+                    "/queue.QueueService/Call",
+                    HashMap::new(),
+                    Vec::new(),
+                )
+                .await
+        }
+    });
+    timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for request")
+        .expect("Request channel closed");
+
+    first.abort();
+    second.abort();
+}
+
+#[tokio::test]
+async fn test_router_returns_resource_exhausted_when_queue_full() {
+    let config = Config::default();
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+    let _request_rx = register_slow_connection(&reverse_manager).await;
+
+    let call = || {
+        http::Request::builder()
+            .method("POST")
+            .uri("/queue.QueueService/Call")
+            .header("content-type", "application/grpc")
+            .body(Full::new(Bytes::from_static(b"payload")))
+            .unwrap()
+    };
+
+    let first = tokio::spawn(router.clone().oneshot(call()));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let response = timeout(Duration::from_millis(500), router.clone().oneshot(call()))
+        .await
+        .expect("Request on a full queue should not block")
+        .unwrap();
+    assert_eq!(grpc_status(&response), Some("8"));
+
+    first.abort();
+}
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use bytes::Bytes;
+use http::HeaderValue;
+use http_body_util::Full;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tonic::metadata::MetadataMap;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::{ForwardResponse, connection_message::MessageType};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::router::DynamicRouter;
+
+// 不是合法 UTF-8 的原始字节
+const BINARY_VALUE: &[u8] = &[0xff, 0x80, 0xfe, b'g', 0xc3, 0x28];
+
+#[tokio::test]
+async fn test_binary_metadata_round_trips_over_reverse_connection() {
+    let config = Config::default();
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "binary-conn".to_string(),
+            vec!["BinaryService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    let request = http::Request::builder()
+        .method("POST")
+        .uri("/binary.BinaryService/Echo")
+        .header("content-type", "application/grpc")
+        .header("custom-bin", HeaderValue::from_bytes(BINARY_VALUE).unwrap())
+        .header("plain", "text")
+        .body(Full::new(Bytes::new()))
+        .unwrap();
+    let forwarding = tokio::spawn(router.oneshot(request));
+
+    let message = timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for forwarded request")
+        .expect("Request channel closed");
+    let Some(MessageType::Request(forward_request)) = message.message_type else {
+        panic!("Expected a forwarded request");
+    };
+
+    // 微服务收到 base64 编码的二进制元数据，普通头不受影响
+    let encoded = forward_request
+        .headers
+        .get("custom-bin")
+        .expect("Binary header was dropped")
+        .clone();
+    assert_eq!(STANDARD_NO_PAD.decode(&encoded).unwrap(), BINARY_VALUE);
+    assert_eq!(
+        forward_request.headers.get("plain").map(String::as_str),
+        Some("text")
+    );
+
+    // 微服务原样回传二进制元数据，另带一个带填充的值和一个无效值
+    let mut response_headers = std::collections::HashMap::new();
+    response_headers.insert("custom-bin".to_string(), encoded);
+    response_headers.insert("padded-bin".to_string(), "AQI=".to_string());
+    response_headers.insert("broken-bin".to_string(), "not base64!".to_string());
+    reverse_manager
+        .handle_response(ForwardResponse {
+            request_id: forward_request.request_id,
+            status_code: 0,
+            headers: response_headers,
+            ..Default::default()
+        })
+        .await;
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Timeout waiting for response")
+        .expect("Router task panicked")
+        .expect("Router returned an error");
+
+    // 调用方按 gRPC 元数据解码后得到原始字节
+    let metadata = MetadataMap::from_headers(response.headers().clone());
+    let custom = metadata
+        .get_bin("custom-bin")
+        .expect("Binary header missing from response");
+    assert_eq!(custom.to_bytes().unwrap().as_ref(), BINARY_VALUE);
+    let padded = metadata
+        .get_bin("padded-bin")
+        .expect("Padded binary header missing from response");
+    assert_eq!(padded.to_bytes().unwrap().as_ref(), &[1, 2]);
+    assert!(response.headers().get("broken-bin").is_none());
+}
@@ -0,0 +1,253 @@
+mod common;
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use http_body::{Body, Frame, SizeHint};
+use http_body_util::{BodyExt, Full};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::{ForwardResponse, connection_message::MessageType};
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::{MyRegistryService, ServiceHealthStatus, ServiceInfo};
+
+use common::{grpc_request, grpc_status};
+
+const LIMIT: usize = 1024;
+
+#[tokio::test]
+async fn test_reverse_request_body_limit() {
+    let mut config = Config::default();
+    config
+        .router
+        .per_service_max_body_sizes
+        .insert("limit.UploadService".to_string(), LIMIT);
+
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "limit-conn".to_string(),
+            vec!["UploadService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    // 恰好等于上限的请求体正常转发
+    let forwarding = tokio::spawn(
+        router
+            .clone()
+            .oneshot(grpc_request("/limit.UploadService/Upload", vec![0; LIMIT])),
+    );
+    let message = timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for forwarded request")
+        .expect("Request channel closed");
+    let Some(MessageType::Request(forward_request)) = message.message_type else {
+        panic!("Expected a forwarded request");
+    };
+    assert_eq!(forward_request.payload.len(), LIMIT);
+    reverse_manager
+        .handle_response(ForwardResponse {
+            request_id: forward_request.request_id,
+            status_code: 200,
+            ..Default::default()
+        })
+        .await;
+    let response = forwarding.await.unwrap().unwrap();
+    assert_eq!(grpc_status(&response), Some("0"));
+
+    // 超出上限一个字节返回 RESOURCE_EXHAUSTED，且不会转发给微服务
+    let response = router
+        .oneshot(grpc_request(
+            "/limit.UploadService/Upload",
+            vec![0; LIMIT + 1],
+        ))
+        .await
+        .unwrap();
+    assert_eq!(grpc_status(&response), Some("8"));
+    assert!(request_rx.try_recv().is_err());
+}
+
+// 启动按方法返回不同大小响应体的后端
+async fn start_backend() -> SocketAddr {
+    let (listener, addr) = common::bind().await;
+    let backend = tower::service_fn(|req: http::Request<_>| async move {
+        let size = if req.uri().path().ends_with("/Big") {
+            LIMIT + 1
+        } else {
+            LIMIT
+        };
+        let response = common::grpc_ok()
+            .body(Full::new(Bytes::from(vec![0u8; size])))
+            .expect("Failed to build response");
+        Ok::<_, Infallible>(response)
+    });
+
+    common::serve_fallback(listener, backend);
+    addr
+}
+
+#[tokio::test]
+async fn test_forward_response_body_limit() {
+    let backend_addr = start_backend().await;
+
+    let mut config = Config::default();
+    config.router.max_body_size = LIMIT;
+
+    let registry_service = MyRegistryService::new(config.clone());
+    let address = format!("http://{backend_addr}");
+    let instances = Arc::new(DashMap::new());
+    instances.insert(
+        address.clone(),
+        ServiceInfo {
+            address,
+            last_heartbeat: SystemTime::now(),
+            health_status: ServiceHealthStatus::Healthy,
+            metadata: Default::default(),
+        },
+    );
+    registry_service
+        .registry
+        .insert("DownloadService".to_string(), instances);
+
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    // 恰好等于上限的响应体完整返回
+    let response = router
+        .clone()
+        .oneshot(grpc_request("/limit.DownloadService/Small", Vec::new()))
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body.len(), LIMIT);
+
+    // 超出上限时以 RESOURCE_EXHAUSTED trailers 结束
+    let response = router
+        .oneshot(grpc_request("/limit.DownloadService/Big", Vec::new()))
+        .await
+        .unwrap();
+    let collected = response.into_body().collect().await.unwrap();
+    let trailers = collected.trailers().cloned().expect("Missing trailers");
+    assert_eq!(
+        trailers.get("grpc-status").and_then(|v| v.to_str().ok()),
+        Some("8")
+    );
+}
+
+const CHUNK: usize = 256;
+
+// 不断产生数据块的请求体，记录被读取的块数；hint 为声明的请求体大小
+struct EndlessBody {
+    polled: Arc<AtomicUsize>,
+    hint: Option<u64>,
+}
+
+impl Body for EndlessBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        self.polled.fetch_add(1, Ordering::SeqCst);
+        Poll::Ready(Some(Ok(Frame::data(Bytes::from(vec![0u8; CHUNK])))))
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match self.hint {
+            Some(size) => SizeHint::with_exact(size),
+            None => SizeHint::default(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_streamed_request_body_aborts_at_limit() {
+    let mut config = Config::default();
+    config.router.max_body_size = LIMIT;
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+    let (request_tx, mut request_rx) = mpsc::channel(64);
+    reverse_manager
+        .register_connection(
+            "endless-conn".to_string(),
+            vec!["UploadService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    let endless_request = |hint| {
+        let polled = Arc::new(AtomicUsize::new(0));
+        let request = http::Request::builder()
+            .method("POST")
+            .uri("/limit.UploadService/Upload")
+            .header("content-type", "application/grpc")
+            .body(EndlessBody {
+                polled: polled.clone(),
+                hint,
+            })
+            .unwrap();
+        (request, polled)
+    };
+
+    // 没有声明大小的请求体读到超过上限的那一块就停止，不会一直读下去
+    let (request, polled) = endless_request(None);
+    let response = timeout(Duration::from_secs(1), router.clone().oneshot(request))
+        .await
+        .expect("Oversized stream was not aborted")
+        .unwrap();
+    assert_eq!(grpc_status(&response), Some("8"));
+    assert_eq!(polled.load(Ordering::SeqCst), LIMIT / CHUNK + 1);
+
+    // 已经发给微服务的分块不超过上限，且没有分块被标记为请求结束
+    let mut forwarded = 0;
+    while let Ok(message) = request_rx.try_recv() {
+        if let Some(MessageType::Request(request)) = message.message_type {
+            forwarded += request.payload.len();
+            let streaming_info = request.streaming_info.expect("Missing streaming info");
+            assert!(!streaming_info.is_stream_end);
+        }
+    }
+    assert!(forwarded <= LIMIT);
+
+    // 声明的大小已经超过上限时不读取请求体
+    let (request, polled) = endless_request(Some(LIMIT as u64 + 1));
+    let response = router.oneshot(request).await.unwrap();
+    assert_eq!(grpc_status(&response), Some("8"));
+    assert_eq!(polled.load(Ordering::SeqCst), 0);
+}
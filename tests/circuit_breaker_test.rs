@@ -0,0 +1,144 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use dashmap::DashMap;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::services::client_manager::{
+    CircuitOpenError, CircuitState, ConnectionPoolConfig, GrpcClientManager,
+};
+use grpc_opizontas::services::router::{RequestContext, RouterError, forwarder};
+use grpc_opizontas::services::{ServiceHealthStatus, ServiceInfo, ServiceRegistry};
+
+use common::unused_addr;
+
+const COOLDOWN: Duration = Duration::from_millis(300);
+
+fn manager() -> GrpcClientManager {
+    GrpcClientManager::new(ConnectionPoolConfig {
+        circuit_failure_threshold: 3,
+        circuit_cooldown: COOLDOWN,
+        ..Default::default()
+    })
+}
+
+fn circuit_state(manager: &GrpcClientManager, address: &str) -> CircuitState {
+    manager
+        .get_breaker_state(address)
+        .map(|breaker| breaker.state)
+        .unwrap_or(CircuitState::Closed)
+}
+
+// 在之前连接失败的地址上启动后端，模拟后端恢复
+async fn start_backend(addr: SocketAddr) {
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .expect("Failed to bind backend address");
+    common::serve_health(listener);
+}
+
+#[tokio::test]
+async fn test_circuit_opens_after_consecutive_connect_failures() {
+    let manager = manager();
+    let address = format!("http://{}", unused_addr());
+
+    for _ in 0..3 {
+        let err = manager
+            .get_or_create_client(&address)
+            .await
+            .expect_err("Connect should fail");
+        assert!(!err.is::<CircuitOpenError>());
+    }
+    assert_eq!(circuit_state(&manager, &address), CircuitState::Open);
+
+    // 熔断期间快速失败
+    let started = Instant::now();
+    let err = manager
+        .get_or_create_client(&address)
+        .await
+        .expect_err("Circuit should be open");
+    assert!(err.is::<CircuitOpenError>());
+    assert!(started.elapsed() < Duration::from_millis(50));
+
+    let stats = manager.get_stats();
+    assert_eq!(stats.get(&format!("circuit_state:{address}")), Some(&1));
+    assert_eq!(stats.get(&format!("circuit_failures:{address}")), Some(&3));
+    assert_eq!(stats.get("circuit_opened"), Some(&1));
+    assert_eq!(stats.get("circuit_rejected"), Some(&1));
+}
+
+#[tokio::test]
+async fn test_circuit_recovers_after_cooldown() {
+    let manager = manager();
+    let addr = unused_addr();
+    let address = format!("http://{addr}");
+
+    for _ in 0..3 {
+        let _ = manager.get_or_create_client(&address).await;
+    }
+    assert_eq!(circuit_state(&manager, &address), CircuitState::Open);
+
+    // 冷却后半开探测仍然失败，重新熔断
+    tokio::time::sleep(COOLDOWN).await;
+    let err = manager
+        .get_or_create_client(&address)
+        .await
+        .expect_err("Probe should fail");
+    assert!(!err.is::<CircuitOpenError>());
+    assert_eq!(circuit_state(&manager, &address), CircuitState::Open);
+
+    // 后端恢复后，下一次探测成功并关闭熔断器
+    start_backend(addr).await;
+    tokio::time::sleep(COOLDOWN).await;
+    manager
+        .get_or_create_client(&address)
+        .await
+        .expect("Probe should succeed once backend is up");
+    manager.record_success(&address);
+
+    assert_eq!(circuit_state(&manager, &address), CircuitState::Closed);
+    assert!(
+        !manager
+            .get_stats()
+            .contains_key(&format!("circuit_state:{address}"))
+    );
+}
+
+#[tokio::test]
+async fn test_open_circuit_maps_to_service_unavailable() {
+    let manager = manager();
+    let addr = unused_addr();
+    let address = format!("http://{addr}");
+    for _ in 0..3 {
+        let _ = manager.get_or_create_client(&address).await;
+    }
+
+    let instances = Arc::new(DashMap::new());
+    instances.insert(
+        address.clone(),
+        ServiceInfo {
+            address: address.clone(),
+            last_heartbeat: SystemTime::now(),
+            health_status: ServiceHealthStatus::Healthy,
+            metadata: Default::default(),
+        },
+    );
+    let registry: ServiceRegistry = Arc::new(DashMap::new());
+    registry.insert("breaker.Service".to_string(), instances);
+
+    let mut config = Config::default();
+    config.router.retry_attempts = 0;
+
+    let request = http::Request::builder()
+        .method("POST")
+        .uri("/breaker.Service/Call")
+        .body(http_body_util::Empty::<bytes::Bytes>::new())
+        .expect("Failed to build request");
+    let context = RequestContext::new("breaker.Service", "/breaker.Service/Call");
+    let result = forwarder::forward_request(&registry, &manager, &config, &context, request).await;
+
+    assert!(matches!(result, Err(RouterError::ServiceUnavailable(_))));
+}
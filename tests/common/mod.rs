@@ -0,0 +1,226 @@
+// 集成测试共用的辅助函数，每个测试文件只用到其中一部分
+#![allow(dead_code)]
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::TcpListenerStream;
+use tokio_util::sync::CancellationToken;
+use tonic::service::{AxumBody, Routes};
+use tonic::transport::{Channel, Server};
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::health::health_server::HealthServer;
+use grpc_opizontas::registry::registry_service_server::{RegistryService, RegistryServiceServer};
+use grpc_opizontas::registry::{RegisterRequest, RegisterResponse};
+use grpc_opizontas::server;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::health::HealthService;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+// 没有进程监听的本地地址，用于模拟宕机的后端，
+// 或者只能通过配置传入地址、由被测代码自己绑定的监听端（例如指标端点）
+pub fn unused_addr() -> SocketAddr {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("Failed to allocate local port")
+}
+
+// 绑定本地随机端口。监听器直接交给服务器使用，端口不会在释放和重新绑定之间被抢走，
+// 服务器开始接受连接之前的连接也会在队列中等待，不需要启动后再等待一段时间
+pub async fn bind() -> (TcpListener, SocketAddr) {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind local port");
+    let addr = listener.local_addr().expect("Failed to read local address");
+    (listener, addr)
+}
+
+// 在已绑定的监听器上启动 gRPC 服务
+pub fn serve_routes(listener: TcpListener, routes: Routes) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        Server::builder()
+            .add_routes(routes)
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .expect("Backend server failed");
+    })
+}
+
+// 在已绑定的监听器上启动后端，所有请求都交给 backend 处理
+pub fn serve_fallback<S, B>(listener: TcpListener, backend: S) -> JoinHandle<()>
+where
+    S: tower::Service<http::Request<AxumBody>, Response = http::Response<B>, Error = Infallible>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    S::Future: Send + 'static,
+    B: http_body::Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    let mut routes = Routes::default();
+    let router = std::mem::take(routes.axum_router_mut());
+    *routes.axum_router_mut() = router.fallback_service(backend);
+    serve_routes(listener, routes)
+}
+
+// 直接返回空 gRPC 响应的后端响应构建器，grpc-status 为 0
+pub fn grpc_ok() -> http::response::Builder {
+    http::Response::builder()
+        .header("content-type", "application/grpc")
+        .header("grpc-status", "0")
+}
+
+// 发往 path 的 gRPC 请求
+pub fn grpc_request(path: &str, body: impl Into<Bytes>) -> http::Request<Full<Bytes>> {
+    http::Request::builder()
+        .method("POST")
+        .uri(path)
+        .header("content-type", "application/grpc")
+        .body(Full::new(body.into()))
+        .unwrap()
+}
+
+// 响应头中的 grpc-status
+pub fn grpc_status<B>(response: &http::Response<B>) -> Option<&str> {
+    response
+        .headers()
+        .get("grpc-status")
+        .and_then(|v| v.to_str().ok())
+}
+
+// 在已绑定的监听器上启动只提供健康检查服务的后端
+pub fn serve_health(listener: TcpListener) -> JoinHandle<()> {
+    let registry_service = Arc::new(MyRegistryService::new(Config::default()));
+    serve_routes(
+        listener,
+        Routes::new(HealthServer::new(HealthService::new(registry_service))),
+    )
+}
+
+// 启动只提供健康检查服务的后端，返回监听地址
+pub async fn start_health_backend() -> SocketAddr {
+    let (listener, addr) = bind().await;
+    serve_health(listener);
+    addr
+}
+
+// 把 address 注册为 services 的实例
+pub async fn register(
+    registry_service: &MyRegistryService,
+    token: &str,
+    address: &str,
+    services: &[&str],
+) -> RegisterResponse {
+    registry_service
+        .register(tonic::Request::new(RegisterRequest {
+            api_key: token.to_string(),
+            address: address.to_string(),
+            services: services.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service")
+        .into_inner()
+}
+
+// 启动在响应头 x-backend 中返回自身地址的后端
+pub async fn start_addr_backend() -> SocketAddr {
+    let (listener, addr) = bind().await;
+    let backend = tower::service_fn(move |_req: http::Request<AxumBody>| async move {
+        let response = grpc_ok()
+            .header("x-backend", addr.to_string())
+            .body(Full::new(Bytes::new()))
+            .expect("Failed to build response");
+        Ok::<_, Infallible>(response)
+    });
+    serve_fallback(listener, backend);
+    addr
+}
+
+// 启动只提供注册服务的网关，返回监听地址
+pub async fn serve_registry(registry_service: Arc<MyRegistryService>) -> SocketAddr {
+    let (listener, addr) = bind().await;
+    serve_routes(
+        listener,
+        Routes::new(RegistryServiceServer::from_arc(registry_service)),
+    );
+    addr
+}
+
+// 在本地随机端口上启动网关，返回监听地址、停机信号的发送端和网关任务；
+// 通过发送端发出信号后网关开始优雅停机，只丢弃发送端不会停机
+pub async fn spawn_gateway(
+    config: Config,
+) -> (
+    SocketAddr,
+    oneshot::Sender<()>,
+    JoinHandle<Result<(), String>>,
+) {
+    let (listener, addr) = bind().await;
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let gateway = tokio::spawn(async move {
+        server::run_with_listener(config, listener, async move {
+            if shutdown_rx.await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        })
+        .await
+        .map_err(|e| e.to_string())
+    });
+    (addr, shutdown_tx, gateway)
+}
+
+// 建立到本地服务器的明文 gRPC 通道
+pub async fn connect(addr: SocketAddr) -> Channel {
+    Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .expect("Failed to connect to server")
+}
+
+// 转发 TCP 连接的代理，kill 断开当前所有连接但继续接受新连接
+pub struct KillableProxy {
+    pub addr: SocketAddr,
+    connections: Arc<Mutex<CancellationToken>>,
+}
+
+impl KillableProxy {
+    pub async fn start(upstream: SocketAddr) -> Self {
+        let (listener, addr) = bind().await;
+        let connections = Arc::new(Mutex::new(CancellationToken::new()));
+
+        let current = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((mut inbound, _)) = listener.accept().await {
+                let token = current.lock().unwrap().clone();
+                tokio::spawn(async move {
+                    let Ok(mut outbound) = tokio::net::TcpStream::connect(upstream).await else {
+                        return;
+                    };
+                    tokio::select! {
+                        _ = token.cancelled() => {}
+                        _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound) => {}
+                    }
+                });
+            }
+        });
+
+        Self { addr, connections }
+    }
+
+    pub fn kill_connections(&self) {
+        let mut token = self.connections.lock().unwrap();
+        token.cancel();
+        *token = CancellationToken::new();
+    }
+}
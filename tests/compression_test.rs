@@ -0,0 +1,242 @@
+mod common;
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use http_body::Frame;
+use http_body_util::{BodyExt, Full, StreamBody};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tonic::Request;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::registry::{ForwardResponse, connection_message::MessageType};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::compression::{FrameTransform, recode};
+use grpc_opizontas::services::router::DynamicRouter;
+
+const TOKEN: &str = "compression-token";
+
+// 重复度高的大消息，压缩后明显变小
+fn large_message() -> Vec<u8> {
+    b"opizontas-gateway-".repeat(512)
+}
+
+fn grpc_frame(compressed: bool, payload: &[u8]) -> Bytes {
+    let mut frame = BytesMut::with_capacity(5 + payload.len());
+    frame.put_u8(compressed as u8);
+    frame.put_u32(payload.len() as u32);
+    frame.put_slice(payload);
+    frame.freeze()
+}
+
+// 解析单个 gRPC 消息帧，返回 (是否压缩, 内容)
+fn parse_frame(body: &[u8]) -> (bool, Vec<u8>) {
+    let len = u32::from_be_bytes(body[1..5].try_into().unwrap()) as usize;
+    assert_eq!(body.len(), 5 + len, "expected exactly one frame");
+    (body[0] == 1, body[5..].to_vec())
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn gunzip(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    GzDecoder::new(data).read_to_end(&mut output).unwrap();
+    output
+}
+
+type BackendBody =
+    StreamBody<tokio_stream::Iter<std::vec::IntoIter<Result<Frame<Bytes>, Infallible>>>>;
+
+// 不支持压缩的后端：总是返回未压缩的大消息
+async fn uncompressed_backend<B>(
+    _req: http::Request<B>,
+) -> Result<http::Response<BackendBody>, Infallible> {
+    let mut trailers = http::HeaderMap::new();
+    trailers.insert("grpc-status", "0".parse().unwrap());
+    let frames = vec![
+        Ok(Frame::data(grpc_frame(false, &large_message()))),
+        Ok(Frame::trailers(trailers)),
+    ];
+    let response = http::Response::builder()
+        .header("content-type", "application/grpc")
+        .body(StreamBody::new(tokio_stream::iter(frames)))
+        .expect("Failed to build response");
+    Ok(response)
+}
+
+async fn pooled_router() -> DynamicRouter {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+
+    let (listener, backend_addr) = common::bind().await;
+    common::serve_fallback(listener, tower::service_fn(uncompressed_backend));
+
+    let registry_service = MyRegistryService::new(config.clone());
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: format!("http://{backend_addr}"),
+            services: vec!["PayloadService".to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+
+    DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router")
+}
+
+fn grpc_request(accept_gzip: bool, body: Bytes) -> http::Request<Full<Bytes>> {
+    let mut builder = http::Request::builder()
+        .method("POST")
+        .uri("/payload.PayloadService/Get")
+        .header("content-type", "application/grpc");
+    if accept_gzip {
+        builder = builder.header("grpc-accept-encoding", "identity,deflate,gzip");
+    }
+    builder.body(Full::new(body)).unwrap()
+}
+
+#[test]
+fn test_recode_round_trip_keeps_small_messages() {
+    let small = b"tiny".to_vec();
+    let mut payload = grpc_frame(false, &small).to_vec();
+    payload.extend_from_slice(&grpc_frame(false, &large_message()));
+
+    let compressed = recode(&payload, FrameTransform::Compress { min_size: 1024 }).unwrap();
+    assert!(compressed.len() < payload.len());
+    // 小消息不压缩
+    assert_eq!(&compressed[..9], &grpc_frame(false, &small)[..]);
+    assert_eq!(compressed[9], 1);
+
+    let restored = recode(&compressed, FrameTransform::Decompress).unwrap();
+    assert_eq!(restored, payload);
+
+    // 不是 gRPC 消息帧的数据原样透传
+    let raw = b"not a grpc frame".to_vec();
+    assert_eq!(
+        recode(&raw, FrameTransform::Compress { min_size: 0 }).unwrap(),
+        raw
+    );
+}
+
+#[tokio::test]
+async fn test_pooled_response_compressed_when_client_accepts_gzip() {
+    let router = pooled_router().await;
+
+    let response = router
+        .clone()
+        .oneshot(grpc_request(true, grpc_frame(false, b"request")))
+        .await
+        .unwrap();
+    assert_eq!(response.headers().get("grpc-encoding").unwrap(), "gzip");
+    let collected = response.into_body().collect().await.unwrap();
+    let trailers = collected.trailers().cloned().expect("Missing trailers");
+    assert_eq!(trailers.get("grpc-status").unwrap(), "0");
+    let (compressed, message) = parse_frame(&collected.to_bytes());
+    assert!(compressed);
+    assert!(message.len() < large_message().len());
+    assert_eq!(gunzip(&message), large_message());
+
+    // 未声明 gzip 的客户端收到原始消息
+    let response = router
+        .oneshot(grpc_request(false, grpc_frame(false, b"request")))
+        .await
+        .unwrap();
+    assert!(!response.headers().contains_key("grpc-encoding"));
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(parse_frame(&body), (false, large_message()));
+}
+
+#[tokio::test]
+async fn test_reverse_payload_gzip_round_trip() {
+    let mut config = Config::default();
+    config.reverse_connection.gzip_payload = true;
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "gzip-conn".to_string(),
+            vec!["PayloadService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    // 客户端发送未压缩的请求，且不接受压缩的响应
+    let forwarding = tokio::spawn(
+        router
+            .clone()
+            .oneshot(grpc_request(false, grpc_frame(false, &large_message()))),
+    );
+
+    // 微服务收到 gzip 压缩的消息和对应的请求头
+    let message = timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for forwarded request")
+        .expect("Request channel closed");
+    let Some(MessageType::Request(forward_request)) = message.message_type else {
+        panic!("Expected a forwarded request");
+    };
+    assert_eq!(
+        forward_request
+            .headers
+            .get("grpc-encoding")
+            .map(String::as_str),
+        Some("gzip")
+    );
+    assert!(forward_request.headers["grpc-accept-encoding"].contains("gzip"));
+    let (compressed, message) = parse_frame(&forward_request.payload);
+    assert!(compressed);
+    assert_eq!(gunzip(&message), large_message());
+
+    // 微服务返回 gzip 压缩的响应，网关为不支持压缩的客户端解压
+    reverse_manager
+        .handle_response(ForwardResponse {
+            request_id: forward_request.request_id,
+            status_code: 200,
+            headers: HashMap::from([
+                ("content-type".to_string(), "application/grpc".to_string()),
+                ("grpc-encoding".to_string(), "gzip".to_string()),
+            ]),
+            payload: grpc_frame(true, &gzip(&large_message())).to_vec(),
+            ..Default::default()
+        })
+        .await;
+
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Timeout waiting for response")
+        .expect("Router task panicked")
+        .expect("Router returned an error");
+    assert!(!response.headers().contains_key("grpc-encoding"));
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(parse_frame(&body), (false, large_message()));
+}
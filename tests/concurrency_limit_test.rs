@@ -0,0 +1,100 @@
+mod common;
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::router::DynamicRouter;
+
+use common::grpc_status;
+
+const LIMIT: usize = 2;
+const FLOOD: usize = 5;
+
+#[tokio::test]
+async fn test_requests_beyond_limit_are_rejected() {
+    let mut config = Config::default();
+    config.router.max_concurrent_requests = LIMIT;
+
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    // 微服务收到请求后不响应，使请求一直占用许可
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "limit-conn".to_string(),
+            vec!["LimitService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    let mut in_flight = Vec::new();
+    for _ in 0..FLOOD {
+        let request = http::Request::builder()
+            .method("POST")
+            .uri("/limit.LimitService/Call")
+            .header("content-type", "application/grpc")
+            .body(Full::new(Bytes::from_static(b"payload")))
+            .unwrap();
+        in_flight.push(tokio::spawn(router.clone().oneshot(request)));
+    }
+
+    // 只有 LIMIT 个请求到达微服务
+    for _ in 0..LIMIT {
+        timeout(Duration::from_secs(1), request_rx.recv())
+            .await
+            .expect("Timeout waiting for forwarded request")
+            .expect("Request channel closed");
+    }
+
+    let mut rejected = 0;
+    for handle in in_flight {
+        if let Ok(result) = timeout(Duration::from_millis(300), handle).await {
+            let response = result.unwrap().unwrap();
+            assert_eq!(grpc_status(&response), Some("8"));
+            rejected += 1;
+        }
+    }
+    assert_eq!(rejected, FLOOD - LIMIT);
+    assert!(request_rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_permit_released_after_request_completes() {
+    let mut config = Config::default();
+    config.router.max_concurrent_requests = 1;
+
+    let registry_service = MyRegistryService::new(config.clone());
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    // 未注册的服务立即返回 NOT_FOUND，随后许可应已归还
+    for _ in 0..3 {
+        let request = http::Request::builder()
+            .method("POST")
+            .uri("/limit.Missing/Call")
+            .body(Full::new(Bytes::new()))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(grpc_status(&response), Some("5"));
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::services::MyRegistryService;
+
+// 环境变量是进程级状态，所有依赖环境变量的断言放在同一个测试中
+#[tokio::test]
+async fn test_env_overrides_reverse_connection_and_event_settings() {
+    let overrides = [
+        ("GRPC_REVERSE_HEARTBEAT_TIMEOUT", "45"),
+        ("GRPC_REVERSE_REQUEST_TIMEOUT", "7"),
+        ("GRPC_REVERSE_CLEANUP_INTERVAL", "15"),
+        ("GRPC_REVERSE_MAX_PENDING_REQUESTS", "64"),
+        ("GRPC_EVENT_CHANNEL_CAPACITY", "32"),
+        ("GRPC_EVENT_MAX_HISTORY", "10"),
+        ("GRPC_EVENT_ENABLE_METRICS", "false"),
+    ];
+    for (key, value) in overrides {
+        // SAFETY: 本测试文件只有这一个测试，不存在并发读写环境变量
+        unsafe { std::env::set_var(key, value) };
+    }
+
+    let missing = std::env::temp_dir().join("grpc_opizontas_missing_config_env.toml");
+    let config = Config::reload_from(&missing).expect("Failed to load config");
+    assert_eq!(config.reverse_connection.heartbeat_timeout, 45);
+    assert_eq!(config.reverse_connection.request_timeout, 7);
+    assert_eq!(config.reverse_connection.cleanup_interval, 15);
+    assert_eq!(config.reverse_connection.max_pending_requests, 64);
+    assert_eq!(config.event.channel_capacity, 32);
+    assert_eq!(config.event.max_event_history, Some(10));
+    assert!(!config.event.enable_metrics);
+    // 未覆盖的字段保持默认值
+    assert_eq!(config.event.max_subscribers_per_type, 1000);
+
+    let reverse_config = MyRegistryService::reverse_connection_config(&config);
+    assert_eq!(reverse_config.heartbeat_timeout, Duration::from_secs(45));
+    assert_eq!(reverse_config.request_timeout, Duration::from_secs(7));
+    assert_eq!(reverse_config.cleanup_interval, Duration::from_secs(15));
+    assert_eq!(reverse_config.max_pending_requests, 64);
+
+    for (key, _) in overrides {
+        // SAFETY: 同上
+        unsafe { std::env::remove_var(key) };
+    }
+}
+
+#[test]
+fn test_missing_reverse_connection_and_event_sections_use_defaults() {
+    let mut value = toml::Value::try_from(Config::default()).unwrap();
+    let table = value.as_table_mut().unwrap();
+    table.remove("reverse_connection");
+    table.remove("event");
+    let mut reverse = toml::Table::new();
+    reverse.insert("heartbeat_timeout".to_string(), toml::Value::Integer(90));
+    table.insert(
+        "reverse_connection".to_string(),
+        toml::Value::Table(reverse),
+    );
+
+    let config: Config = toml::from_str(&toml::to_string(&value).unwrap()).unwrap();
+    assert_eq!(config.reverse_connection.heartbeat_timeout, 90);
+    assert_eq!(config.reverse_connection.request_timeout, 30);
+    assert_eq!(config.reverse_connection.max_pending_requests, 1000);
+    assert_eq!(config.event.channel_capacity, 1024);
+    assert!(config.event.enable_metrics);
+}
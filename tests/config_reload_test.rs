@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::client_manager::GrpcClientManager;
+use grpc_opizontas::services::config_watcher::ConfigWatcher;
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::router::load_balance::LoadBalanceStrategy;
+
+fn config_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("grpc_opizontas_{name}_{}.toml", std::process::id()))
+}
+
+// 以默认配置为基础生成完整的配置文件
+fn config_toml(address: &str, token: &str, max_connections: usize) -> String {
+    let mut config = Config::default();
+    config.server.address = address.to_string();
+    config.security.tokens = vec![token.to_string()];
+    config.connection_pool.max_connections = max_connections;
+    toml::to_string(&config).unwrap()
+}
+
+#[tokio::test]
+async fn test_reload_applies_tokens_and_reports_restart_fields() {
+    let path = config_path("reload");
+    std::fs::write(&path, config_toml("0.0.0.0:50051", "token-a", 10)).unwrap();
+
+    let config = Config::reload_from(&path).unwrap();
+    let registry_service = MyRegistryService::new(config.clone());
+    let client_manager = GrpcClientManager::new(
+        DynamicRouter::connection_pool_config(&config)
+            .expect("Failed to load connection pool config"),
+    );
+    let watcher = ConfigWatcher::new(
+        &path,
+        registry_service.config.clone(),
+        client_manager.clone(),
+        registry_service.reverse_connection_manager.clone(),
+    );
+    assert!(registry_service.config.load().validate_token("token-a"));
+
+    std::fs::write(&path, config_toml("0.0.0.0:60061", "token-b", 42)).unwrap();
+    let restart_required = watcher.reload().expect("Reload should succeed");
+
+    // 新 token 立即生效，旧 token 失效
+    let reloaded = registry_service.config.load();
+    assert!(reloaded.validate_token("token-b"));
+    assert!(!reloaded.validate_token("token-a"));
+    assert_eq!(client_manager.config().max_connections, 42);
+
+    // 监听地址需要重启，保持原值
+    assert_eq!(restart_required, vec!["/server"]);
+    assert_eq!(reloaded.server.address, "0.0.0.0:50051");
+
+    // 解析失败时保留当前配置
+    std::fs::write(&path, "[security\ntokens = ").unwrap();
+    assert!(watcher.reload().is_err());
+    assert!(registry_service.config.load().validate_token("token-b"));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn test_reload_swaps_load_balance_strategy() {
+    let path = config_path("load_balance");
+    std::fs::write(&path, config_toml("0.0.0.0:50051", "token-a", 10)).unwrap();
+
+    let config = Config::reload_from(&path).unwrap();
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let watcher = ConfigWatcher::new(
+        &path,
+        registry_service.config.clone(),
+        GrpcClientManager::new(
+            DynamicRouter::connection_pool_config(&config)
+                .expect("Failed to load connection pool config"),
+        ),
+        reverse_manager.clone(),
+    );
+    assert_eq!(
+        reverse_manager.load_balance_strategy(),
+        LoadBalanceStrategy::RoundRobin
+    );
+
+    let mut config: Config = toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    config.router.load_balance_strategy = LoadBalanceStrategy::LeastConnections;
+    std::fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let restart_required = watcher.reload().expect("Reload should succeed");
+
+    // 策略无需重启，正向转发和反向连接同时切换
+    assert!(restart_required.is_empty());
+    assert_eq!(
+        registry_service.config.load().router.load_balance_strategy,
+        LoadBalanceStrategy::LeastConnections
+    );
+    assert_eq!(
+        reverse_manager.load_balance_strategy(),
+        LoadBalanceStrategy::LeastConnections
+    );
+
+    let _ = std::fs::remove_file(&path);
+}
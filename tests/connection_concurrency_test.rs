@@ -0,0 +1,281 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::connection_message::MessageType;
+use grpc_opizontas::registry::{ConnectionMessage, ForwardRequest, ForwardResponse};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::connection::ReverseConnectionManager;
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::router::load_balance::LoadBalanceStrategy;
+
+use common::grpc_status;
+
+const SERVICE: &str = "BusyService";
+
+fn request() -> http::Request<Full<Bytes>> {
+    request_to("/busy.BusyService/Call", None)
+}
+
+fn request_to(path: &str, grpc_timeout: Option<&str>) -> http::Request<Full<Bytes>> {
+    let mut builder = http::Request::builder()
+        .method("POST")
+        .uri(path)
+        .header("content-type", "application/grpc");
+    if let Some(grpc_timeout) = grpc_timeout {
+        builder = builder.header("grpc-timeout", grpc_timeout);
+    }
+    builder.body(Full::new(Bytes::new())).unwrap()
+}
+
+async fn next_request(requests: &mut mpsc::Receiver<ConnectionMessage>) -> ForwardRequest {
+    let message = timeout(Duration::from_secs(1), requests.recv())
+        .await
+        .expect("Timeout waiting for forwarded request")
+        .expect("Request channel closed");
+    match message.message_type {
+        Some(MessageType::Request(request)) => request,
+        other => panic!("Unexpected message: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_saturated_connection_is_skipped() {
+    let mut config = Config::default();
+    config.router.load_balance_strategy = LoadBalanceStrategy::FirstHealthy;
+    config.reverse_connection.max_in_flight_per_connection = 1;
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    let mut receivers = Vec::new();
+    for connection_id in ["busy-conn-1", "busy-conn-2"] {
+        let (request_tx, request_rx) = mpsc::channel(16);
+        reverse_manager
+            .register_connection(
+                connection_id.to_string(),
+                vec![SERVICE.to_string()],
+                1,
+                request_tx,
+            )
+            .await
+            .expect("Failed to register connection");
+        receivers.push(request_rx);
+    }
+    let [mut first_rx, mut second_rx] = receivers.try_into().unwrap();
+
+    // first_healthy 总是选择第一个连接，它还没有响应时请求转向第二个连接
+    let first_call = tokio::spawn(router.clone().oneshot(request()));
+    let first_request = next_request(&mut first_rx).await;
+    assert_eq!(reverse_manager.in_flight_requests("busy-conn-1"), 1);
+
+    let second_call = tokio::spawn(router.clone().oneshot(request()));
+    let second_request = next_request(&mut second_rx).await;
+    assert_eq!(reverse_manager.in_flight_requests("busy-conn-2"), 1);
+
+    // 两个连接都达到上限
+    let response = router.clone().oneshot(request()).await.unwrap();
+    assert_eq!(grpc_status(&response), Some("8"));
+    assert!(first_rx.try_recv().is_err());
+    assert!(second_rx.try_recv().is_err());
+
+    // 微服务响应后名额释放，新请求回到第一个连接
+    for (request, call) in [(first_request, first_call), (second_request, second_call)] {
+        reverse_manager
+            .handle_response(ForwardResponse {
+                request_id: request.request_id,
+                status_code: 0,
+                ..Default::default()
+            })
+            .await;
+        let response = timeout(Duration::from_secs(1), call)
+            .await
+            .expect("Timeout waiting for response")
+            .unwrap()
+            .unwrap();
+        assert_eq!(grpc_status(&response), Some("0"));
+    }
+    assert_eq!(reverse_manager.in_flight_requests("busy-conn-1"), 0);
+
+    let third_call = tokio::spawn(router.clone().oneshot(request()));
+    let third_request = next_request(&mut first_rx).await;
+    reverse_manager
+        .handle_response(ForwardResponse {
+            request_id: third_request.request_id,
+            status_code: 0,
+            ..Default::default()
+        })
+        .await;
+    let response = third_call.await.unwrap().unwrap();
+    assert_eq!(grpc_status(&response), Some("0"));
+}
+
+// 只有一个连接、并发上限为 1 且开启排队的网关
+async fn queued_router(
+    queue_wait_ms: u64,
+) -> (
+    DynamicRouter,
+    Arc<ReverseConnectionManager>,
+    mpsc::Receiver<ConnectionMessage>,
+) {
+    let mut config = Config::default();
+    config.reverse_connection.max_in_flight_per_connection = 1;
+    config.reverse_connection.saturation_queue_wait_ms = queue_wait_ms;
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+    let (request_tx, request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "queued-conn".to_string(),
+            vec![SERVICE.to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+    (router, reverse_manager, request_rx)
+}
+
+async fn respond(reverse_manager: &ReverseConnectionManager, request: ForwardRequest) {
+    reverse_manager
+        .handle_response(ForwardResponse {
+            request_id: request.request_id,
+            status_code: 0,
+            ..Default::default()
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn test_queued_requests_proceed_in_order_when_slot_frees() {
+    let (router, reverse_manager, mut requests) = queued_router(2_000).await;
+
+    let first_call = tokio::spawn(
+        router
+            .clone()
+            .oneshot(request_to("/busy.BusyService/First", None)),
+    );
+    let first = next_request(&mut requests).await;
+
+    // 连接已达到上限，后面两个请求按到达顺序排队
+    let mut queued_calls = Vec::new();
+    for method in ["Second", "Third"] {
+        let path = format!("/busy.BusyService/{method}");
+        queued_calls.push(tokio::spawn(
+            router.clone().oneshot(request_to(&path, None)),
+        ));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert!(requests.try_recv().is_err());
+
+    respond(&reverse_manager, first).await;
+    let second = next_request(&mut requests).await;
+    assert_eq!(second.method_path, "/busy.BusyService/Second");
+    assert!(requests.try_recv().is_err());
+
+    respond(&reverse_manager, second).await;
+    let third = next_request(&mut requests).await;
+    assert_eq!(third.method_path, "/busy.BusyService/Third");
+    respond(&reverse_manager, third).await;
+
+    for call in std::iter::once(first_call).chain(queued_calls) {
+        let response = timeout(Duration::from_secs(1), call)
+            .await
+            .expect("Timeout waiting for response")
+            .unwrap()
+            .unwrap();
+        assert_eq!(grpc_status(&response), Some("0"));
+    }
+}
+
+#[tokio::test]
+async fn test_queued_request_fails_after_wait_deadline() {
+    let (router, _reverse_manager, mut requests) = queued_router(200).await;
+    let _held_call = tokio::spawn(router.clone().oneshot(request()));
+    let _held = next_request(&mut requests).await;
+
+    let started = Instant::now();
+    let response = router.clone().oneshot(request()).await.unwrap();
+    assert_eq!(grpc_status(&response), Some("8"));
+    assert!(started.elapsed() >= Duration::from_millis(200));
+
+    // 调用方的截止时间比排队时间更短时以调用方为准
+    let (router, reverse_manager, mut requests) = queued_router(5_000).await;
+    let _held_call = tokio::spawn(router.clone().oneshot(request()));
+    let _held = next_request(&mut requests).await;
+
+    let started = Instant::now();
+    let response = router
+        .clone()
+        .oneshot(request_to("/busy.BusyService/Call", Some("100m")))
+        .await
+        .unwrap();
+    assert_eq!(grpc_status(&response), Some("8"));
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert!(requests.try_recv().is_err());
+    assert_eq!(reverse_manager.in_flight_requests("queued-conn"), 1);
+}
+
+#[tokio::test]
+async fn test_slot_freed_by_other_service_wakes_queued_request() {
+    let (router, reverse_manager, mut requests) = queued_router(2_000).await;
+    let _held_call = tokio::spawn(router.clone().oneshot(request()));
+    let _held = next_request(&mut requests).await;
+
+    let (request_tx, mut shared_requests) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "shared-conn".to_string(),
+            vec![SERVICE.to_string(), "OtherService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    // 两个连接都被占满：queued-conn 处理 BusyService，shared-conn 处理 OtherService
+    let other_call = tokio::spawn(
+        router
+            .clone()
+            .oneshot(request_to("/other.OtherService/Call", None)),
+    );
+    let other = next_request(&mut shared_requests).await;
+
+    let queued_call = tokio::spawn(router.clone().oneshot(request()));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(shared_requests.try_recv().is_err());
+
+    // OtherService 的请求释放了共享连接的名额，排队的 BusyService 请求被唤醒
+    respond(&reverse_manager, other).await;
+    let queued = next_request(&mut shared_requests).await;
+    assert_eq!(queued.method_path, "/busy.BusyService/Call");
+    respond(&reverse_manager, queued).await;
+
+    for call in [other_call, queued_call] {
+        let response = timeout(Duration::from_secs(1), call)
+            .await
+            .expect("Timeout waiting for response")
+            .unwrap()
+            .unwrap();
+        assert_eq!(grpc_status(&response), Some("0"));
+    }
+}
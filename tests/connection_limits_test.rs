@@ -0,0 +1,195 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tokio_stream::StreamExt;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::Channel;
+use tonic::{Code, Status};
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::registry_service_client::RegistryServiceClient;
+use grpc_opizontas::registry::{
+    ConnectionMessage, ConnectionRegister, connection_message::MessageType,
+    connection_status::StatusType,
+};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::registry::audit::token_id;
+use grpc_opizontas::services::registry::{AuthError, TokenClaims, TokenValidator};
+
+const TOKEN: &str = "limits-token";
+const OTHER_TOKEN: &str = "limits-other-token";
+const SUBJECT: &str = "limits-deployment";
+
+// 模拟为同一调用方换发的短期 token：任意以 limits-rotated- 开头的 token 都属于 SUBJECT
+#[derive(Debug)]
+struct RotatingTokenValidator;
+
+#[tonic::async_trait]
+impl TokenValidator for RotatingTokenValidator {
+    async fn validate(
+        &self,
+        token: &str,
+        _service: Option<&str>,
+    ) -> Result<TokenClaims, AuthError> {
+        if !token.starts_with("limits-rotated-") {
+            return Err(AuthError::InvalidToken);
+        }
+        Ok(TokenClaims {
+            subject: SUBJECT.to_string(),
+        })
+    }
+}
+
+fn limits_config(configure: impl FnOnce(&mut Config)) -> Config {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string(), OTHER_TOKEN.to_string()];
+    configure(&mut config);
+    config
+}
+
+// 启动只提供注册服务的网关，返回服务实例以便查询连接计数
+async fn start_gateway(
+    registry_service: MyRegistryService,
+) -> (Arc<MyRegistryService>, RegistryServiceClient<Channel>) {
+    let registry_service = Arc::new(registry_service);
+    let addr = common::serve_registry(registry_service.clone()).await;
+
+    let channel = common::connect(addr).await;
+    (registry_service, RegistryServiceClient::new(channel))
+}
+
+// 建立反向连接并等待连接确认，返回保持流打开的发送端
+async fn connect(
+    client: &mut RegistryServiceClient<Channel>,
+    token: &str,
+    services: Vec<String>,
+) -> Result<mpsc::Sender<ConnectionMessage>, Status> {
+    let (reverse_tx, reverse_rx) = mpsc::channel(4);
+    reverse_tx
+        .send(ConnectionMessage {
+            message_type: Some(MessageType::Register(ConnectionRegister {
+                api_key: token.to_string(),
+                services,
+                connection_id: String::new(),
+                weight: 1,
+            })),
+        })
+        .await
+        .unwrap();
+    let mut inbound = client
+        .establish_connection(ReceiverStream::new(reverse_rx))
+        .await?
+        .into_inner();
+
+    let message = timeout(Duration::from_secs(1), inbound.next())
+        .await
+        .expect("Timeout waiting for connection status")
+        .expect("Connection stream closed")
+        .expect("Connection stream failed");
+    let Some(MessageType::Status(status)) = message.message_type else {
+        panic!("Expected a connection status");
+    };
+    assert_eq!(status.status, StatusType::Connected as i32);
+
+    // 保持响应流被读取，连接关闭时任务随之结束
+    tokio::spawn(async move { while inbound.next().await.is_some() {} });
+    Ok(reverse_tx)
+}
+
+#[tokio::test]
+async fn test_too_many_services_rejected() {
+    let (_registry_service, mut client) =
+        start_gateway(MyRegistryService::new(limits_config(|config| {
+            config.reverse_connection.max_services_per_connection = 2
+        })))
+        .await;
+
+    let services = |count: usize| (0..count).map(|i| format!("LimitService{i}")).collect();
+    let status = connect(&mut client, TOKEN, services(3))
+        .await
+        .expect_err("Connection declaring too many services should be rejected");
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    // 不超过上限的连接正常建立
+    connect(&mut client, TOKEN, services(2))
+        .await
+        .expect("Connection within the service limit should succeed");
+}
+
+#[tokio::test]
+async fn test_per_token_connection_limit_released_on_disconnect() {
+    let (registry_service, mut client) =
+        start_gateway(MyRegistryService::new(limits_config(|config| {
+            config.reverse_connection.max_connections_per_token = 2
+        })))
+        .await;
+    let services = || vec!["LimitService".to_string()];
+
+    let first = connect(&mut client, TOKEN, services())
+        .await
+        .expect("First connection should succeed");
+    let _second = connect(&mut client, TOKEN, services())
+        .await
+        .expect("Second connection should succeed");
+    assert_eq!(
+        registry_service.subject_connection_count(&token_id(TOKEN)),
+        2
+    );
+
+    let status = connect(&mut client, TOKEN, services())
+        .await
+        .expect_err("Connection over the per-token limit should be rejected");
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert_eq!(
+        registry_service.subject_connection_count(&token_id(TOKEN)),
+        2
+    );
+
+    // 其它 token 不受影响
+    let _other = connect(&mut client, OTHER_TOKEN, services())
+        .await
+        .expect("Another token should have its own limit");
+
+    // 关闭一个连接后名额释放，可以重新建立
+    drop(first);
+    timeout(Duration::from_secs(1), async {
+        while registry_service.subject_connection_count(&token_id(TOKEN)) != 1 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("Connection count was not decremented after disconnect");
+    let _third = connect(&mut client, TOKEN, services())
+        .await
+        .expect("Connection should succeed after a slot was released");
+    assert_eq!(
+        registry_service.subject_connection_count(&token_id(TOKEN)),
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_connection_limit_shared_by_tokens_of_same_subject() {
+    let registry_service = MyRegistryService::new(limits_config(|config| {
+        config.reverse_connection.max_connections_per_token = 1;
+    }))
+    .with_token_validator(Arc::new(RotatingTokenValidator));
+    let (registry_service, mut client) = start_gateway(registry_service).await;
+    let services = || vec!["LimitService".to_string()];
+
+    let _first = connect(&mut client, "limits-rotated-1", services())
+        .await
+        .expect("First connection should succeed");
+    assert_eq!(registry_service.subject_connection_count(SUBJECT), 1);
+
+    // 换发的新 token 不会获得独立的名额
+    let status = connect(&mut client, "limits-rotated-2", services())
+        .await
+        .expect_err("A fresh token for the same subject should share the limit");
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert_eq!(registry_service.subject_connection_count(SUBJECT), 1);
+}
@@ -0,0 +1,250 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use tokio::sync::Barrier;
+
+use grpc_opizontas::health::{HealthCheckRequest, health_client::HealthClient};
+use grpc_opizontas::services::client_manager::{
+    ConnectionPoolConfig, EvictionPolicy, GrpcClientManager,
+};
+
+use common::unused_addr;
+
+// 统计 TCP 连接数的转发代理，每个通道对应一个 TCP 连接
+async fn start_counting_proxy(upstream: SocketAddr) -> (String, Arc<AtomicUsize>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind proxy");
+    let addr = listener.local_addr().unwrap();
+    let accepted = Arc::new(AtomicUsize::new(0));
+
+    let counter = accepted.clone();
+    tokio::spawn(async move {
+        while let Ok((mut inbound, _)) = listener.accept().await {
+            counter.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(async move {
+                if let Ok(mut outbound) = tokio::net::TcpStream::connect(upstream).await {
+                    let _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await;
+                }
+            });
+        }
+    });
+
+    (format!("http://{addr}"), accepted)
+}
+
+#[tokio::test]
+async fn test_channels_per_address_are_round_robined() {
+    let backend = common::start_health_backend().await;
+    let (address, accepted) = start_counting_proxy(backend).await;
+    let manager = GrpcClientManager::new(ConnectionPoolConfig {
+        connections_per_address: 3,
+        ..Default::default()
+    });
+
+    // 前三次调用各自建立一个新通道
+    let mut channels = Vec::new();
+    for _ in 0..3 {
+        channels.push(
+            manager
+                .get_or_create_client(&address)
+                .await
+                .expect("Failed to get client"),
+        );
+    }
+    for channel in &channels {
+        HealthClient::new(channel.clone())
+            .check(HealthCheckRequest::default())
+            .await
+            .expect("Health check failed");
+    }
+    assert_eq!(accepted.load(Ordering::SeqCst), 3);
+    assert_eq!(manager.clients.len(), 1);
+    assert_eq!(manager.channel_count(), 3);
+
+    // 之后在已有通道间轮询，不再建立新连接
+    for _ in 0..6 {
+        let channel = manager
+            .get_or_create_client(&address)
+            .await
+            .expect("Failed to get client");
+        HealthClient::new(channel)
+            .check(HealthCheckRequest::default())
+            .await
+            .expect("Health check failed");
+    }
+    assert_eq!(accepted.load(Ordering::SeqCst), 3);
+
+    let stats = manager.get_stats();
+    assert_eq!(stats.get("connections_created"), Some(&3));
+    assert_eq!(stats.get("cache_hits"), Some(&6));
+    let metadata = manager.clients.get(&address).unwrap();
+    assert_eq!(metadata.use_count, 9);
+}
+
+#[tokio::test]
+async fn test_single_channel_per_address_by_default() {
+    let backend = common::start_health_backend().await;
+    let (address, accepted) = start_counting_proxy(backend).await;
+    let manager = GrpcClientManager::default();
+
+    for _ in 0..3 {
+        let channel = manager
+            .get_or_create_client(&address)
+            .await
+            .expect("Failed to get client");
+        HealthClient::new(channel)
+            .check(HealthCheckRequest::default())
+            .await
+            .expect("Health check failed");
+    }
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    assert_eq!(manager.channel_count(), 1);
+}
+
+#[tokio::test]
+async fn test_concurrent_requests_share_one_connect() {
+    const REQUESTS: usize = 50;
+    let backend = common::start_health_backend().await;
+    let (address, accepted) = start_counting_proxy(backend).await;
+    let manager = GrpcClientManager::default();
+
+    // 50 个请求同时获取一个尚未连接的地址，只拨号一次
+    let barrier = Arc::new(Barrier::new(REQUESTS));
+    let tasks: Vec<_> = (0..REQUESTS)
+        .map(|_| {
+            let manager = manager.clone();
+            let address = address.clone();
+            let barrier = barrier.clone();
+            tokio::spawn(async move {
+                barrier.wait().await;
+                manager.get_or_create_client(&address).await
+            })
+        })
+        .collect();
+    for task in tasks {
+        let channel = task
+            .await
+            .expect("Request task panicked")
+            .expect("Failed to get client");
+        HealthClient::new(channel)
+            .check(HealthCheckRequest::default())
+            .await
+            .expect("Health check failed");
+    }
+
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    assert_eq!(manager.channel_count(), 1);
+    let stats = manager.get_stats();
+    assert_eq!(stats.get("connections_created"), Some(&1));
+    assert_eq!(
+        stats.get("connects_coalesced").copied().unwrap_or_default()
+            + stats.get("cache_hits").copied().unwrap_or_default(),
+        REQUESTS as u64 - 1
+    );
+}
+
+#[tokio::test]
+async fn test_concurrent_failed_connect_counts_once() {
+    let address = format!("http://{}", unused_addr());
+    let manager = GrpcClientManager::default();
+
+    // 同时失败的请求共享一次连接尝试，只计一次熔断失败
+    let results =
+        futures::future::join_all((0..10).map(|_| manager.get_or_create_client(&address))).await;
+    assert!(results.iter().all(Result::is_err));
+    let breaker = manager.get_breaker_state(&address).unwrap();
+    assert_eq!(breaker.consecutive_failures, 1);
+
+    // 失败后不保留建立中的记录，下一次请求重新拨号
+    assert!(manager.get_or_create_client(&address).await.is_err());
+    let breaker = manager.get_breaker_state(&address).unwrap();
+    assert_eq!(breaker.consecutive_failures, 2);
+}
+
+// 依次建立 first、second，反复使用 first 后加入第三个地址触发淘汰，
+// 返回 first 是否被淘汰
+async fn hot_connection_evicted(policy: EvictionPolicy) -> bool {
+    let manager = GrpcClientManager::new(ConnectionPoolConfig {
+        max_connections: 2,
+        eviction_policy: policy,
+        ..Default::default()
+    });
+    let first = format!("http://{}", common::start_health_backend().await);
+    let second = format!("http://{}", common::start_health_backend().await);
+    let third = format!("http://{}", common::start_health_backend().await);
+
+    manager.get_or_create_client(&first).await.unwrap();
+    manager.get_or_create_client(&second).await.unwrap();
+    for _ in 0..5 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        manager.get_or_create_client(&first).await.unwrap();
+    }
+    // 缓存命中不会触发淘汰
+    assert_eq!(manager.clients.len(), 2);
+
+    manager.get_or_create_client(&third).await.unwrap();
+    assert_eq!(manager.clients.len(), 2);
+    assert!(manager.clients.contains_key(&third));
+    assert_eq!(manager.get_stats().get("connections_evicted"), Some(&1));
+
+    // 两者只淘汰其一
+    let first_evicted = !manager.clients.contains_key(&first);
+    assert_eq!(first_evicted, manager.clients.contains_key(&second));
+    first_evicted
+}
+
+#[tokio::test]
+async fn test_eviction_keeps_frequently_used_connection() {
+    // 频繁使用的 first 保留，未再使用的 second 被淘汰
+    assert!(!hot_connection_evicted(EvictionPolicy::Lru).await);
+    assert!(!hot_connection_evicted(EvictionPolicy::Lfu).await);
+    // 按创建时间淘汰时，最早建立的 first 即使仍在使用也会被淘汰
+    assert!(hot_connection_evicted(EvictionPolicy::Oldest).await);
+}
+
+#[tokio::test]
+async fn test_detailed_stats_snapshot() {
+    let backend = common::start_health_backend().await;
+    let (busy, _) = start_counting_proxy(backend).await;
+    let (quiet, _) = start_counting_proxy(backend).await;
+    let manager = GrpcClientManager::new(ConnectionPoolConfig {
+        connections_per_address: 2,
+        ..Default::default()
+    });
+    assert_eq!(manager.detailed_stats(), Default::default());
+
+    // quiet 先建立并只使用一次，busy 稍后建立并使用多次
+    manager
+        .get_or_create_client(&quiet)
+        .await
+        .expect("Failed to get client");
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    for _ in 0..4 {
+        manager
+            .get_or_create_client(&busy)
+            .await
+            .expect("Failed to get client");
+    }
+
+    let snapshot = manager.detailed_stats();
+    assert_eq!(snapshot.addresses, 2);
+    assert_eq!(snapshot.channels, 3);
+    assert_eq!(snapshot.total_use_count, 5);
+    assert!(snapshot.max_age >= Duration::from_millis(200));
+    assert!(snapshot.min_age < Duration::from_millis(200));
+    assert!(snapshot.min_age <= snapshot.avg_age && snapshot.avg_age <= snapshot.max_age);
+    // quiet 建立后一直空闲，busy 刚刚使用过
+    assert!(snapshot.max_idle >= Duration::from_millis(200));
+    assert!(snapshot.avg_idle <= snapshot.max_idle);
+    assert!(snapshot.max_idle <= snapshot.max_age);
+
+    // 原有的计数统计保持不变
+    let stats = manager.get_stats();
+    assert_eq!(stats.get("connections_created"), Some(&3));
+    assert_eq!(stats.get("cache_hits"), Some(&2));
+}
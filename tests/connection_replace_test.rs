@@ -0,0 +1,92 @@
+use tokio::sync::mpsc;
+
+use grpc_opizontas::services::connection::ReverseConnectionManager;
+
+const CONNECTION_ID: &str = "replace-conn";
+const SERVICES: [&str; 3] = [
+    "replace.AlphaService",
+    "replace.BetaService",
+    "replace.GammaService",
+];
+
+// 按序号选取服务子集，保证每个子集至少包含一个服务
+fn services_for(round: usize) -> Vec<String> {
+    let mask = round % 7 + 1;
+    SERVICES
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| mask & (1 << index) != 0)
+        .map(|(_, service)| service.to_string())
+        .collect()
+}
+
+async fn register(manager: &ReverseConnectionManager, services: Vec<String>) {
+    let (request_tx, _request_rx) = mpsc::channel(16);
+    manager
+        .register_connection(CONNECTION_ID.to_string(), services, 1, request_tx)
+        .await
+        .expect("Failed to register connection");
+}
+
+// 服务池与连接表一致：连接提供的服务都能选到它，其余服务没有残留
+fn assert_pools_consistent(manager: &ReverseConnectionManager) {
+    let current = SERVICES
+        .iter()
+        .find_map(|service| manager.get_connection_for_service(service))
+        .expect("Connection missing from every service pool");
+    assert_eq!(current.connection_id, CONNECTION_ID);
+
+    for service in SERVICES {
+        let provided = current.services.iter().any(|s| s == service);
+        assert_eq!(
+            manager.has_reverse_connection(service),
+            provided,
+            "{service}: pool state does not match services {:?}",
+            current.services
+        );
+        if provided {
+            let selected = manager
+                .get_connection_for_service(service)
+                .expect("Expected connection in pool");
+            assert_eq!(selected.services, current.services);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_sequential_reregister_keeps_connection() {
+    let manager = ReverseConnectionManager::default();
+
+    register(&manager, services_for(6)).await;
+    register(&manager, services_for(6)).await;
+    assert_pools_consistent(&manager);
+
+    // 缩小服务集合后，旧服务池中不再保留该连接
+    register(&manager, services_for(0)).await;
+    assert_pools_consistent(&manager);
+    assert!(manager.has_reverse_connection(SERVICES[0]));
+    assert!(!manager.has_reverse_connection(SERVICES[1]));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_reregister_keeps_pools_consistent() {
+    let manager = ReverseConnectionManager::default();
+
+    for _ in 0..20 {
+        let tasks: Vec<_> = (0..16)
+            .map(|round| {
+                let manager = manager.clone();
+                tokio::spawn(async move { register(&manager, services_for(round)).await })
+            })
+            .collect();
+        for task in tasks {
+            task.await.expect("Register task panicked");
+        }
+        assert_pools_consistent(&manager);
+    }
+
+    manager.unregister_connection(CONNECTION_ID).await;
+    for service in SERVICES {
+        assert!(!manager.has_reverse_connection(service));
+    }
+}
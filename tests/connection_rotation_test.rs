@@ -0,0 +1,122 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::connection_message::MessageType;
+use grpc_opizontas::registry::connection_status::StatusType;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::client::{
+    ConnectionState, GatewayClientConfig, ReverseConnectionClient, ReverseConnectionOptions,
+};
+use grpc_opizontas::services::connection::{ReverseConnectionConfig, ReverseConnectionManager};
+use grpc_opizontas::services::event::EventConfig;
+
+const TOKEN: &str = "rotation-token";
+
+#[tokio::test]
+async fn test_aged_connection_is_notified_and_unregistered() {
+    let config = ReverseConnectionConfig {
+        cleanup_interval: Duration::from_millis(50),
+        ping_interval: Duration::ZERO,
+        max_connection_age: Some(Duration::from_millis(300)),
+        ..Default::default()
+    };
+    let manager = ReverseConnectionManager::new(config, None, EventConfig::default());
+
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    manager
+        .register_connection(
+            "aged".to_string(),
+            vec!["rotation.OrderService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    // 未到期前连接保持可用
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert!(manager.has_reverse_connection("rotation.OrderService"));
+
+    let message = timeout(Duration::from_secs(2), request_rx.recv())
+        .await
+        .expect("Timeout waiting for rotation notice")
+        .expect("Request channel closed");
+    let Some(MessageType::Status(status)) = message.message_type else {
+        panic!("Expected a status message, got {message:?}");
+    };
+    assert_eq!(status.status, StatusType::Disconnected as i32);
+    assert_eq!(status.connection_id, "aged");
+
+    assert!(!manager.has_reverse_connection("rotation.OrderService"));
+    assert!(manager.get_connection_info("aged").is_none());
+    // 连接已从管理器中释放，请求通道随之关闭
+    assert!(
+        timeout(Duration::from_secs(1), request_rx.recv())
+            .await
+            .expect("Request channel still open")
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_client_reconnects_after_rotation() {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.reverse_connection.cleanup_interval = 1;
+    config.reverse_connection.max_connection_age = 1;
+    let registry_service = Arc::new(MyRegistryService::new(config));
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+
+    let addr = common::serve_registry(registry_service.clone()).await;
+
+    let client = ReverseConnectionClient::spawn(
+        GatewayClientConfig {
+            gateway_address: format!("http://{addr}"),
+            api_key: TOKEN.to_string(),
+            ..Default::default()
+        },
+        ReverseConnectionOptions {
+            services: vec!["rotation.PaymentService".to_string()],
+            ..Default::default()
+        },
+    );
+    let handle = client.handle();
+    timeout(
+        Duration::from_secs(5),
+        handle.wait_for_state(|state| matches!(state, ConnectionState::Connected { .. })),
+    )
+    .await
+    .expect("Timeout waiting for connection");
+    let connection_id = handle.connection_id().expect("Missing connection id");
+
+    // 网关轮换连接后客户端按原连接ID重新注册
+    timeout(
+        Duration::from_secs(5),
+        handle.wait_for_state(|state| matches!(state, ConnectionState::Reconnecting { .. })),
+    )
+    .await
+    .expect("Timeout waiting for rotation");
+    timeout(
+        Duration::from_secs(5),
+        handle.wait_for_state(|state| matches!(state, ConnectionState::Connected { .. })),
+    )
+    .await
+    .expect("Timeout waiting for reconnection");
+    assert_eq!(handle.connection_id(), Some(connection_id.clone()));
+
+    // 旧流关闭时不会注销同一ID的新连接
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(reverse_manager.has_reverse_connection("rotation.PaymentService"));
+    assert!(
+        reverse_manager
+            .get_connection_info(&connection_id)
+            .is_some()
+    );
+    handle.close();
+}
@@ -0,0 +1,214 @@
+mod common;
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tonic::Request;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::connection_message::MessageType;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::router::deadline::{
+    effective_timeout, format_grpc_timeout, parse_grpc_timeout,
+};
+
+use common::grpc_status;
+
+const TOKEN: &str = "deadline-token";
+
+fn request(path: &str, grpc_timeout: &str) -> http::Request<Full<Bytes>> {
+    http::Request::builder()
+        .method("POST")
+        .uri(path)
+        .header("content-type", "application/grpc")
+        .header("grpc-timeout", grpc_timeout)
+        .body(Full::new(Bytes::from_static(b"payload")))
+        .unwrap()
+}
+
+#[test]
+fn test_parse_grpc_timeout_units() {
+    assert_eq!(parse_grpc_timeout("1H"), Some(Duration::from_secs(3600)));
+    assert_eq!(parse_grpc_timeout("2M"), Some(Duration::from_secs(120)));
+    assert_eq!(parse_grpc_timeout("5S"), Some(Duration::from_secs(5)));
+    assert_eq!(parse_grpc_timeout("100m"), Some(Duration::from_millis(100)));
+    assert_eq!(parse_grpc_timeout("250u"), Some(Duration::from_micros(250)));
+    assert_eq!(parse_grpc_timeout("10n"), Some(Duration::from_nanos(10)));
+    assert_eq!(
+        parse_grpc_timeout("99999999S"),
+        Some(Duration::from_secs(99_999_999))
+    );
+
+    // 缺少数字、超过 8 位数字、未知单位或非整数都不合法
+    for invalid in ["", "m", "123456789S", "10x", "-1S", "1.5S", "S10"] {
+        assert_eq!(parse_grpc_timeout(invalid), None, "{invalid:?}");
+    }
+}
+
+#[test]
+fn test_format_grpc_timeout_round_trips() {
+    assert_eq!(format_grpc_timeout(Duration::from_millis(50)), "50000000n");
+    assert_eq!(format_grpc_timeout(Duration::from_millis(1500)), "1500000u");
+    assert_eq!(
+        format_grpc_timeout(Duration::from_secs(3 * 3600)),
+        "10800000m"
+    );
+
+    for timeout in [
+        Duration::from_nanos(7),
+        Duration::from_millis(250),
+        Duration::from_secs(30),
+        Duration::from_secs(86_400),
+    ] {
+        assert_eq!(
+            parse_grpc_timeout(&format_grpc_timeout(timeout)),
+            Some(timeout)
+        );
+    }
+}
+
+#[test]
+fn test_effective_timeout_is_the_smaller_value() {
+    let configured = Duration::from_secs(30);
+    assert_eq!(
+        effective_timeout(Some(Duration::from_millis(100)), configured),
+        Duration::from_millis(100)
+    );
+    assert_eq!(
+        effective_timeout(Some(Duration::from_secs(60)), configured),
+        configured
+    );
+    assert_eq!(effective_timeout(None, configured), configured);
+}
+
+// 把收到的 grpc-timeout 发给测试，然后一直不响应的后端
+async fn start_slow_backend() -> (SocketAddr, mpsc::UnboundedReceiver<Option<String>>) {
+    let (listener, addr) = common::bind().await;
+    let (seen_tx, seen_rx) = mpsc::unbounded_channel();
+    let backend = tower::service_fn(move |req: http::Request<_>| {
+        let seen_tx = seen_tx.clone();
+        async move {
+            let grpc_timeout = req
+                .headers()
+                .get("grpc-timeout")
+                .and_then(|v: &http::HeaderValue| v.to_str().ok())
+                .map(str::to_string);
+            let _ = seen_tx.send(grpc_timeout);
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            let response = common::grpc_ok()
+                .body(Full::new(Bytes::new()))
+                .expect("Failed to build response");
+            Ok::<_, Infallible>(response)
+        }
+    });
+
+    common::serve_fallback(listener, backend);
+    (addr, seen_rx)
+}
+
+#[tokio::test]
+async fn test_forward_call_uses_client_deadline() {
+    let (backend_addr, mut seen) = start_slow_backend().await;
+
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    let registry_service = MyRegistryService::new(config.clone());
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: format!("http://{backend_addr}"),
+            services: vec!["SlowService".to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    let started = Instant::now();
+    let response = router
+        .oneshot(request("/deadline.SlowService/Call", "300m"))
+        .await
+        .unwrap();
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(250), "{elapsed:?}");
+    assert!(elapsed < Duration::from_secs(2), "{elapsed:?}");
+    assert_eq!(grpc_status(&response), Some("14"));
+
+    // 后端收到的是剩余时间，不超过调用方的截止时间；截止时间到期后不重试
+    let forwarded = seen.recv().await.unwrap().expect("Missing grpc-timeout");
+    let forwarded = parse_grpc_timeout(&forwarded).expect("Invalid grpc-timeout");
+    assert!(forwarded <= Duration::from_millis(300), "{forwarded:?}");
+    assert!(forwarded > Duration::from_millis(200), "{forwarded:?}");
+    assert!(seen.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_reverse_call_uses_client_deadline() {
+    let config = Config::default();
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "deadline-conn".to_string(),
+            vec!["ReverseSlowService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    let started = Instant::now();
+    let call = tokio::spawn(
+        router
+            .clone()
+            .oneshot(request("/deadline.ReverseSlowService/Call", "200m")),
+    );
+
+    // 微服务收到的 grpc-timeout 为调用方的截止时间，timeout_seconds 向上取整为 1 秒
+    let message = timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for forwarded request")
+        .expect("Request channel closed");
+    let Some(MessageType::Request(forwarded)) = message.message_type else {
+        panic!("Expected a forward request");
+    };
+    assert_eq!(
+        forwarded
+            .headers
+            .get("grpc-timeout")
+            .and_then(|v| parse_grpc_timeout(v)),
+        Some(Duration::from_millis(200))
+    );
+    assert_eq!(forwarded.timeout_seconds, 1);
+
+    // 微服务不响应，网关在调用方截止时间而不是配置的 30 秒后放弃
+    let response = timeout(Duration::from_secs(2), call)
+        .await
+        .expect("Gateway ignored the client deadline")
+        .unwrap()
+        .unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(180));
+    assert_eq!(grpc_status(&response), Some("14"));
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tokio::sync::mpsc;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::router::DynamicRouter;
+
+use common::grpc_request;
+
+// 启动一个把收到的请求路径发回测试的兜底后端
+async fn start_backend() -> (SocketAddr, mpsc::UnboundedReceiver<String>) {
+    let (listener, addr) = common::bind().await;
+    let (paths_tx, paths_rx) = mpsc::unbounded_channel();
+    let backend = tower::service_fn(move |req: http::Request<_>| {
+        let _ = paths_tx.send(req.uri().path().to_string());
+        async move {
+            let response = common::grpc_ok()
+                .body(Full::new(Bytes::new()))
+                .expect("Failed to build response");
+            Ok::<_, Infallible>(response)
+        }
+    });
+
+    common::serve_fallback(listener, backend);
+    (addr, paths_rx)
+}
+
+fn router(config: Config) -> DynamicRouter {
+    let registry_service = MyRegistryService::new(config.clone());
+    DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router")
+}
+
+#[tokio::test]
+async fn test_unknown_service_forwarded_to_default_backend() {
+    let (backend_addr, mut backend_paths) = start_backend().await;
+
+    // 未配置兜底后端时，未注册的服务返回 NOT_FOUND
+    let response = router(Config::default())
+        .oneshot(grpc_request("/legacy.LegacyService/Call", Bytes::new()))
+        .await
+        .unwrap();
+    assert_eq!(
+        response.headers().get("grpc-status").unwrap(),
+        &(tonic::Code::NotFound as i32).to_string()
+    );
+
+    let mut config = Config::default();
+    config.router.default_backend = Some(format!("http://{backend_addr}"));
+    let response = router(config)
+        .oneshot(grpc_request("/legacy.LegacyService/Call", Bytes::new()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(response.headers().get("grpc-status").unwrap(), "0");
+
+    let path = tokio::time::timeout(Duration::from_secs(1), backend_paths.recv())
+        .await
+        .expect("Timeout waiting for the default backend")
+        .expect("Default backend did not receive a request");
+    assert_eq!(path, "/legacy.LegacyService/Call");
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tonic::{Code, Request};
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::DrainInstanceRequest;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::{MyRegistryService, ServiceHealthStatus};
+
+const TOKEN: &str = "drain-test-token";
+const SERVICE: &str = "DrainService";
+
+fn drain_request(instance_id: &str, draining: bool) -> Request<DrainInstanceRequest> {
+    Request::new(DrainInstanceRequest {
+        api_key: TOKEN.to_string(),
+        service_name: SERVICE.to_string(),
+        instance_id: instance_id.to_string(),
+        draining,
+    })
+}
+
+fn instance_status(registry_service: &MyRegistryService, address: &str) -> ServiceHealthStatus {
+    registry_service
+        .registry
+        .get(SERVICE)
+        .and_then(|instances| {
+            instances
+                .iter()
+                .find(|instance| instance.address == address)
+                .map(|instance| instance.health_status.clone())
+        })
+        .expect("Instance should stay registered")
+}
+
+async fn forwarded_backend(router: &DynamicRouter) -> String {
+    let request = http::Request::builder()
+        .method("POST")
+        .uri(format!("/drain.{SERVICE}/Call"))
+        .header("content-type", "application/grpc")
+        .body(Full::new(Bytes::new()))
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    response
+        .headers()
+        .get("x-backend")
+        .and_then(|v| v.to_str().ok())
+        .expect("Request was not forwarded")
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_draining_instance_receives_no_new_requests() {
+    let kept_addr = common::start_addr_backend().await;
+    let drained_addr = common::start_addr_backend().await;
+
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+
+    let registry_service = Arc::new(MyRegistryService::new(config.clone()));
+    let drained = format!("http://{drained_addr}");
+    common::register(
+        &registry_service,
+        TOKEN,
+        &format!("http://{kept_addr}"),
+        &[SERVICE],
+    )
+    .await;
+    common::register(&registry_service, TOKEN, &drained, &[SERVICE]).await;
+
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    registry_service
+        .drain_instance(drain_request(&drained, true))
+        .await
+        .expect("Failed to drain instance");
+
+    for _ in 0..5 {
+        assert_eq!(forwarded_backend(&router).await, kept_addr.to_string());
+    }
+
+    // 排空中的实例继续心跳时仍保留在注册表且保持 Draining
+    common::register(&registry_service, TOKEN, &drained, &[SERVICE]).await;
+    assert_eq!(
+        instance_status(&registry_service, &drained),
+        ServiceHealthStatus::Draining
+    );
+    assert_eq!(forwarded_backend(&router).await, kept_addr.to_string());
+
+    // 恢复后重新参与实例选择
+    registry_service
+        .drain_instance(drain_request(&drained, false))
+        .await
+        .expect("Failed to restore instance");
+    assert_eq!(
+        instance_status(&registry_service, &drained),
+        ServiceHealthStatus::Healthy
+    );
+}
+
+#[tokio::test]
+async fn test_drain_instance_rejects_invalid_requests() {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    let registry_service = MyRegistryService::new(config);
+    common::register(
+        &registry_service,
+        TOKEN,
+        "http://127.0.0.1:50200",
+        &[SERVICE],
+    )
+    .await;
+
+    let status = registry_service
+        .drain_instance(drain_request("http://127.0.0.1:50201", true))
+        .await
+        .expect_err("Unknown instance should be rejected");
+    assert_eq!(status.code(), Code::NotFound);
+
+    let mut request = drain_request("http://127.0.0.1:50200", true);
+    request.get_mut().api_key = "wrong-token".to_string();
+    let status = registry_service
+        .drain_instance(request)
+        .await
+        .expect_err("Invalid token should be rejected");
+    assert_eq!(status.code(), Code::Unauthenticated);
+    assert_eq!(
+        instance_status(&registry_service, "http://127.0.0.1:50200"),
+        ServiceHealthStatus::Healthy
+    );
+}
@@ -0,0 +1,215 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use http_body::Frame;
+use http_body_util::{Full, StreamBody};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::connection_message::MessageType;
+use grpc_opizontas::services::client_manager::GrpcClientManager;
+use grpc_opizontas::services::metrics::ForwardRoute;
+use grpc_opizontas::services::router::response::create_error_response;
+use grpc_opizontas::services::router::{DynamicRouter, RequestContext, RouterError, forwarder};
+use grpc_opizontas::services::{
+    MyRegistryService, ServiceHealthStatus, ServiceInfo, ServiceRegistry,
+};
+
+use common::{grpc_status, unused_addr};
+
+const SERVICE: &str = "FailingService";
+const PATH: &str = "/errors.FailingService/Call";
+
+fn registry_with(addr: SocketAddr) -> ServiceRegistry {
+    let address = format!("http://{addr}");
+    let instances = Arc::new(DashMap::new());
+    instances.insert(
+        address.clone(),
+        ServiceInfo {
+            address,
+            last_heartbeat: SystemTime::now(),
+            health_status: ServiceHealthStatus::Healthy,
+            metadata: Default::default(),
+        },
+    );
+    let registry: ServiceRegistry = Arc::new(DashMap::new());
+    registry.insert(SERVICE.to_string(), instances);
+    registry
+}
+
+fn request(grpc_timeout: Option<&str>) -> http::Request<Full<Bytes>> {
+    let mut builder = http::Request::builder()
+        .method("POST")
+        .uri(PATH)
+        .header("content-type", "application/grpc");
+    if let Some(grpc_timeout) = grpc_timeout {
+        builder = builder.header("grpc-timeout", grpc_timeout);
+    }
+    builder.body(Full::new(Bytes::new())).unwrap()
+}
+
+// 启动一个接受连接但从不响应的后端。
+// 不使用 tonic 服务端，它会按 grpc-timeout 自行取消请求，与网关的超时竞争
+async fn start_stalled_backend() -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind stalled backend");
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            connections.push(stream);
+        }
+    });
+    addr
+}
+
+async fn forward<B>(
+    registry: &ServiceRegistry,
+    config: &Config,
+    req: http::Request<B>,
+) -> RouterError
+where
+    B: http_body::Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>> + std::fmt::Debug,
+{
+    let context = RequestContext::new(SERVICE, PATH);
+    forwarder::forward_request(
+        registry,
+        &GrpcClientManager::default(),
+        config,
+        &context,
+        req,
+    )
+    .await
+    .expect_err("Forwarding should fail")
+}
+
+#[tokio::test]
+async fn test_unreachable_backend_is_connect_error() {
+    let mut config = Config::default();
+    config.router.retry_attempts = 0;
+    let registry = registry_with(unused_addr());
+
+    let error = forward(&registry, &config, request(None)).await;
+    assert!(matches!(error, RouterError::ConnectError(_)), "{error:?}");
+    assert_eq!(error.grpc_code(), tonic::Code::Unavailable);
+    assert_eq!(error.kind(), "CONNECT_ERROR");
+
+    // 经过动态路由时按错误种类计入指标
+    let reverse_manager = MyRegistryService::new(config.clone()).reverse_connection_manager;
+    let router =
+        DynamicRouter::new(registry, config, reverse_manager).expect("Failed to create router");
+    let metrics = router.metrics.clone();
+    let response = router.oneshot(request(None)).await.unwrap();
+    assert_eq!(grpc_status(&response), Some("14"));
+    assert_eq!(
+        metrics.forward_errors(ForwardRoute::Direct, "CONNECT_ERROR"),
+        1
+    );
+    assert_eq!(metrics.forward_errors(ForwardRoute::Direct, "TIMEOUT"), 0);
+}
+
+#[tokio::test]
+async fn test_stalled_backend_is_timeout() {
+    let backend_addr = start_stalled_backend().await;
+    let registry = registry_with(backend_addr);
+
+    let error = forward(&registry, &Config::default(), request(Some("100m"))).await;
+    assert!(matches!(error, RouterError::Timeout(_)), "{error:?}");
+    assert_eq!(error.grpc_code(), tonic::Code::Unavailable);
+    assert_eq!(error.kind(), "TIMEOUT");
+}
+
+#[tokio::test]
+async fn test_failed_request_body_is_body_error() {
+    // 缓存请求体以便重试时会先读取请求体，读取失败时还没有选择实例
+    let mut config = Config::default();
+    config.router.buffer_request_for_retry = true;
+    let registry = registry_with(unused_addr());
+    let body = StreamBody::new(futures::stream::iter([Err::<Frame<Bytes>, _>(
+        std::io::Error::other("client reset the stream"),
+    )]));
+    let req = http::Request::builder()
+        .method("POST")
+        .uri(PATH)
+        .header("content-type", "application/grpc")
+        .body(body)
+        .unwrap();
+
+    let error = forward(&registry, &config, req).await;
+    assert!(matches!(error, RouterError::BodyError(_)), "{error:?}");
+    assert_eq!(error.grpc_code(), tonic::Code::Internal);
+    assert_eq!(error.kind(), "BODY_ERROR");
+}
+
+#[tokio::test]
+async fn test_backend_error_keeps_its_code() {
+    let error = RouterError::BackendError {
+        code: tonic::Code::DataLoss,
+        message: "Failed to recode response payload".to_string(),
+    };
+    assert_eq!(error.grpc_code(), tonic::Code::DataLoss);
+    assert_eq!(error.kind(), "BACKEND_ERROR");
+    assert!(
+        error
+            .to_string()
+            .contains("Failed to recode response payload")
+    );
+
+    let response = create_error_response(&error);
+    assert_eq!(
+        grpc_status(&response),
+        Some((tonic::Code::DataLoss as i32).to_string().as_str())
+    );
+}
+
+#[tokio::test]
+async fn test_reverse_timeout_is_labelled_in_metrics() {
+    let config = Config::default();
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+    let metrics = router.metrics.clone();
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "failing-conn".to_string(),
+            vec![SERVICE.to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    // 微服务收到请求但不响应，在调用方截止时间到期后失败
+    let call = tokio::spawn(router.oneshot(request(Some("100m"))));
+    let message = timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for forwarded request")
+        .expect("Request channel closed");
+    assert!(matches!(
+        message.message_type,
+        Some(MessageType::Request(_))
+    ));
+
+    let response = timeout(Duration::from_secs(2), call)
+        .await
+        .expect("Gateway ignored the client deadline")
+        .unwrap()
+        .unwrap();
+    assert_eq!(grpc_status(&response), Some("14"));
+    assert_eq!(metrics.forward_errors(ForwardRoute::Reverse, "TIMEOUT"), 1);
+}
@@ -0,0 +1,373 @@
+mod common;
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body::{Body, Frame};
+use http_body_util::{BodyExt, Full, StreamBody};
+use prost::Message;
+use tokio::sync::oneshot;
+use tokio::time::timeout;
+use tonic::transport::Channel;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::registry_service_client::RegistryServiceClient;
+use grpc_opizontas::registry::{ForwardResponse, RegisterRequest, connection_message::MessageType};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::client::{
+    ConnectionState, GatewayClientConfig, ReverseConnectionClient, ReverseConnectionOptions,
+};
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::router::response::{ERROR_DOMAIN, RpcStatus, reverse_status_code};
+
+const PATH: &str = "/missing.MissingService/Watch";
+
+#[tokio::test]
+async fn test_error_status_is_sent_in_trailers() {
+    let config = Config::default();
+    let registry_service = MyRegistryService::new(config.clone());
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    let request = http::Request::builder()
+        .method("POST")
+        .uri(PATH)
+        .header("content-type", "application/grpc")
+        .body(Full::new(Bytes::new()))
+        .unwrap();
+    let response = router.oneshot(request).await.expect("Router failed");
+    assert_eq!(response.status(), http::StatusCode::OK);
+
+    let collected = response
+        .into_body()
+        .collect()
+        .await
+        .expect("Failed to read body");
+    let trailers = collected.trailers().cloned().expect("Missing trailers");
+    assert!(collected.to_bytes().is_empty());
+    assert_eq!(trailers.get("grpc-status").unwrap(), "5");
+    assert!(trailers.contains_key("grpc-message"));
+
+    // grpc-status-details-bin 为 base64 编码的 google.rpc.Status
+    let details = tonic::Status::from_header_map(&trailers).expect("Invalid status trailers");
+    let status = RpcStatus::decode(details.details()).expect("Invalid status details");
+    assert_eq!(status.code, tonic::Code::NotFound as i32);
+    let info = status.error_info().expect("Missing ErrorInfo");
+    assert_eq!(info.reason, "SERVICE_NOT_FOUND");
+    assert_eq!(info.domain, ERROR_DOMAIN);
+}
+
+#[tokio::test]
+async fn test_streaming_client_receives_error_status() {
+    let config = Config::default();
+
+    let (gateway_addr, shutdown_tx, _gateway) = common::spawn_gateway(config).await;
+
+    let channel = common::connect(gateway_addr).await;
+    let mut client = tonic::client::Grpc::new(channel);
+    client.ready().await.expect("Gateway not ready");
+
+    let status = client
+        .server_streaming(
+            tonic::Request::new(()),
+            http::uri::PathAndQuery::from_static(PATH),
+            tonic_prost::ProstCodec::<(), ()>::default(),
+        )
+        .await
+        .expect_err("Call to an unregistered service succeeded");
+    assert_eq!(status.code(), tonic::Code::NotFound);
+
+    let details = RpcStatus::decode(status.details()).expect("Invalid status details");
+    assert_eq!(
+        details.error_info().map(|info| info.reason),
+        Some("SERVICE_NOT_FOUND".to_string())
+    );
+
+    let _ = shutdown_tx.send(());
+}
+
+const TOKEN: &str = "error-status-token";
+
+// 启动网关并返回其地址和已连接的通道
+async fn start_gateway() -> (SocketAddr, Channel, oneshot::Sender<()>) {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+
+    let (gateway_addr, shutdown_tx, _gateway) = common::spawn_gateway(config).await;
+
+    let channel = common::connect(gateway_addr).await;
+    (gateway_addr, channel, shutdown_tx)
+}
+
+async fn call_unary(channel: Channel, path: &'static str) -> tonic::Status {
+    let mut client = tonic::client::Grpc::new(channel);
+    client.ready().await.expect("Gateway not ready");
+    client
+        .unary(
+            tonic::Request::new(()),
+            http::uri::PathAndQuery::from_static(path),
+            tonic_prost::ProstCodec::<(), ()>::default(),
+        )
+        .await
+        .expect_err("Call succeeded")
+}
+
+// 后端按方法返回不同的 gRPC 错误：Missing 为 Trailers-Only 响应，Invalid 先返回消息再以 trailers 结束
+async fn start_failing_backend() -> SocketAddr {
+    let (listener, addr) = common::bind().await;
+    let backend = tower::service_fn(|req: http::Request<_>| async move {
+        let builder = http::Response::builder().header("content-type", "application/grpc");
+        let frames: Vec<Result<Frame<Bytes>, Infallible>> =
+            if req.uri().path().ends_with("/Missing") {
+                vec![]
+            } else {
+                let mut trailers = http::HeaderMap::new();
+                trailers.insert("grpc-status", http::HeaderValue::from_static("3"));
+                trailers.insert("grpc-message", http::HeaderValue::from_static("bad id"));
+                vec![
+                    Ok(Frame::data(Bytes::from_static(b"\0\0\0\0\0"))),
+                    Ok(Frame::trailers(trailers)),
+                ]
+            };
+        let builder = if frames.is_empty() {
+            builder
+                .header("grpc-status", "5")
+                .header("grpc-message", "order 42 not found")
+        } else {
+            builder
+        };
+        let response = builder
+            .body(StreamBody::new(futures::stream::iter(frames)))
+            .expect("Failed to build response");
+        Ok::<_, Infallible>(response)
+    });
+
+    common::serve_fallback(listener, backend);
+    addr
+}
+
+#[tokio::test]
+async fn test_backend_error_status_passes_through() {
+    let backend_addr = start_failing_backend().await;
+    let (_, channel, shutdown_tx) = start_gateway().await;
+
+    RegistryServiceClient::new(channel.clone())
+        .register(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: format!("http://{backend_addr}"),
+            services: vec!["OrderService".to_string()],
+            ..Default::default()
+        })
+        .await
+        .expect("Failed to register backend");
+
+    let status = call_unary(channel.clone(), "/order.OrderService/Missing").await;
+    assert_eq!(status.code(), tonic::Code::NotFound);
+    assert_eq!(status.message(), "order 42 not found");
+
+    let status = call_unary(channel, "/order.OrderService/Invalid").await;
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    assert_eq!(status.message(), "bad id");
+
+    let _ = shutdown_tx.send(());
+}
+
+#[tokio::test]
+async fn test_reverse_status_code_maps_to_grpc_status() {
+    let (gateway_addr, channel, shutdown_tx) = start_gateway().await;
+
+    let mut client = ReverseConnectionClient::spawn(
+        GatewayClientConfig {
+            gateway_address: format!("http://{gateway_addr}"),
+            api_key: TOKEN.to_string(),
+            ..Default::default()
+        },
+        ReverseConnectionOptions {
+            services: vec!["ProfileService".to_string()],
+            ..Default::default()
+        },
+    );
+    let handle = client.handle();
+    timeout(
+        Duration::from_secs(5),
+        handle.wait_for_state(|state| matches!(state, ConnectionState::Connected { .. })),
+    )
+    .await
+    .expect("Timeout waiting for reverse connection");
+
+    // 微服务以 status_code 和 error_message 返回错误
+    let responder = tokio::spawn(async move {
+        for response in [
+            ForwardResponse {
+                status_code: 5,
+                error_message: "profile 7 not found".to_string(),
+                ..Default::default()
+            },
+            ForwardResponse {
+                status_code: 200,
+                headers: HashMap::from([
+                    ("grpc-status".to_string(), "7".to_string()),
+                    ("grpc-message".to_string(), "not your profile".to_string()),
+                ]),
+                ..Default::default()
+            },
+        ] {
+            let Some(MessageType::Request(request)) = client.next_message().await else {
+                panic!("Expected a forwarded request");
+            };
+            handle
+                .send_response(ForwardResponse {
+                    request_id: request.request_id,
+                    ..response
+                })
+                .await
+                .expect("Failed to send response");
+        }
+        client
+    });
+
+    let status = call_unary(channel.clone(), "/profile.ProfileService/Get").await;
+    assert_eq!(status.code(), tonic::Code::NotFound);
+    assert_eq!(status.message(), "profile 7 not found");
+
+    // 微服务自带的 grpc-status 原样透传
+    let status = call_unary(channel, "/profile.ProfileService/Get").await;
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    assert_eq!(status.message(), "not your profile");
+
+    responder.await.expect("Responder panicked");
+    let _ = shutdown_tx.send(());
+}
+
+#[tokio::test]
+async fn test_reverse_error_is_trailers_only() {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+    let (request_tx, mut request_rx) = tokio::sync::mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "boom-conn".to_string(),
+            vec!["BoomService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    let request = http::Request::builder()
+        .method("POST")
+        .uri("/boom.BoomService/Explode")
+        .header("content-type", "application/grpc")
+        .body(Full::new(Bytes::new()))
+        .unwrap();
+    let forwarding = tokio::spawn(router.oneshot(request));
+    let message = timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for forwarded request")
+        .expect("Request channel closed");
+    let Some(MessageType::Request(forward_request)) = message.message_type else {
+        panic!("Expected a forwarded request");
+    };
+
+    // 失败响应附带的消息不会发给调用方
+    reverse_manager
+        .handle_response(ForwardResponse {
+            request_id: forward_request.request_id,
+            status_code: 13,
+            error_message: "boom".to_string(),
+            payload: b"\0\0\0\0\0".to_vec(),
+            ..Default::default()
+        })
+        .await;
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Timeout waiting for response")
+        .expect("Router task panicked")
+        .expect("Router returned an error");
+
+    // 状态只在响应头中，响应体为空且没有 trailers
+    assert_eq!(response.status(), http::StatusCode::OK);
+    let headers = response.headers();
+    assert_eq!(headers.get("content-type").unwrap(), "application/grpc");
+    assert_eq!(headers.get("grpc-status").unwrap(), "13");
+    assert_eq!(headers.get("grpc-message").unwrap(), "boom");
+    assert!(response.body().is_end_stream());
+    let body = response.into_body().collect().await.expect("Body failed");
+    assert!(body.trailers().is_none());
+    assert!(body.to_bytes().is_empty());
+}
+
+#[tokio::test]
+async fn test_reverse_internal_error_reaches_client() {
+    let (gateway_addr, channel, shutdown_tx) = start_gateway().await;
+
+    let mut client = ReverseConnectionClient::spawn(
+        GatewayClientConfig {
+            gateway_address: format!("http://{gateway_addr}"),
+            api_key: TOKEN.to_string(),
+            ..Default::default()
+        },
+        ReverseConnectionOptions {
+            services: vec!["BoomService".to_string()],
+            ..Default::default()
+        },
+    );
+    let handle = client.handle();
+    timeout(
+        Duration::from_secs(5),
+        handle.wait_for_state(|state| matches!(state, ConnectionState::Connected { .. })),
+    )
+    .await
+    .expect("Timeout waiting for reverse connection");
+
+    let responder = tokio::spawn(async move {
+        let Some(MessageType::Request(request)) = client.next_message().await else {
+            panic!("Expected a forwarded request");
+        };
+        handle
+            .send_response(ForwardResponse {
+                request_id: request.request_id,
+                status_code: 13,
+                error_message: "boom".to_string(),
+                ..Default::default()
+            })
+            .await
+            .expect("Failed to send response");
+        client
+    });
+
+    let status = call_unary(channel, "/boom.BoomService/Explode").await;
+    assert_eq!(status.code(), tonic::Code::Internal);
+    assert_eq!(status.message(), "boom");
+
+    responder.await.expect("Responder panicked");
+    let _ = shutdown_tx.send(());
+}
+
+#[test]
+fn test_reverse_status_code_mapping() {
+    assert_eq!(reverse_status_code(0, ""), tonic::Code::Ok);
+    assert_eq!(reverse_status_code(200, ""), tonic::Code::Ok);
+    assert_eq!(reverse_status_code(3, "bad"), tonic::Code::InvalidArgument);
+    assert_eq!(reverse_status_code(503, ""), tonic::Code::Unavailable);
+    assert_eq!(reverse_status_code(401, ""), tonic::Code::Unauthenticated);
+    assert_eq!(reverse_status_code(500, "boom"), tonic::Code::Unknown);
+    // 成功状态附带错误信息时视为失败
+    assert_eq!(reverse_status_code(200, "boom"), tonic::Code::Unknown);
+}
@@ -3,7 +3,10 @@ use tokio::time::timeout;
 use tokio_stream::StreamExt;
 
 use grpc_opizontas::registry::{EventMessage, SubscriptionRequest, subscription_request::Action};
-use grpc_opizontas::services::event::{EventBus, EventConfig};
+use grpc_opizontas::services::event::{EventBus, EventConfig, EventFilter};
+
+// 进程内订阅时代表调用方的标识
+const OWNER: &str = "test-owner";
 
 #[tokio::test]
 async fn test_event_publish_subscribe() {
@@ -11,9 +14,14 @@ async fn test_event_publish_subscribe() {
     let config = EventConfig {
         max_subscribers_per_type: 10,
         channel_capacity: 100,
+        event_type_capacities: Default::default(),
         max_event_history: None,
         event_ttl_seconds: None,
         enable_metrics: true,
+        skip_lagged_events: false,
+        reliable_delivery: false,
+        reliable_queue_capacity: 1024,
+        subscriber_idle_ttl_seconds: None,
     };
 
     let event_bus = EventBus::new(config);
@@ -117,10 +125,12 @@ async fn test_subscription_management() {
         action: Action::Subscribe as i32,
         event_types: vec!["test.sub1".to_string(), "test.sub2".to_string()],
         subscriber_id: subscriber_id.to_string(),
+        metadata_filter: std::collections::HashMap::new(),
+        reliable: false,
     };
 
     let result = event_bus
-        .handle_subscription_request(subscription_request)
+        .handle_subscription_request(subscription_request, OWNER)
         .await;
     assert!(result.is_ok());
 
@@ -135,10 +145,12 @@ async fn test_subscription_management() {
         action: Action::Unsubscribe as i32,
         event_types: vec!["test.sub1".to_string()],
         subscriber_id: subscriber_id.to_string(),
+        metadata_filter: std::collections::HashMap::new(),
+        reliable: false,
     };
 
     let result = event_bus
-        .handle_subscription_request(unsubscribe_request)
+        .handle_subscription_request(unsubscribe_request, OWNER)
         .await;
     assert!(result.is_ok());
 
@@ -200,3 +212,409 @@ async fn test_no_subscribers() {
     let result = event_bus.publish_event(test_event).await;
     assert!(result.is_err()); // 应该返回错误，因为没有订阅者
 }
+
+fn replay_event(event_id: &str, event_type: &str) -> EventMessage {
+    EventMessage {
+        event_id: event_id.to_string(),
+        event_type: event_type.to_string(),
+        publisher_id: "replay-publisher".to_string(),
+        payload: event_id.as_bytes().to_vec(),
+        timestamp: 0,
+        metadata: std::collections::HashMap::new(),
+    }
+}
+
+#[tokio::test]
+async fn test_subscribe_with_replay() {
+    let event_bus = EventBus::new(EventConfig {
+        max_event_history: Some(10),
+        ..EventConfig::default()
+    });
+    let event_type = "replay.test";
+
+    // 订阅前发布的事件没有订阅者，但仍会进入历史缓冲
+    for event_id in ["replay-1", "replay-2", "replay-3"] {
+        let result = event_bus
+            .publish_event(replay_event(event_id, event_type))
+            .await;
+        assert!(result.is_err());
+    }
+
+    let mut stream = event_bus
+        .subscribe_with_replay(event_type, "replay-subscriber", true)
+        .expect("Failed to subscribe with replay");
+
+    let publish_result = event_bus
+        .publish_event(replay_event("replay-4", event_type))
+        .await;
+    assert_eq!(publish_result.unwrap(), 1);
+
+    for expected in ["replay-1", "replay-2", "replay-3", "replay-4"] {
+        let event = timeout(Duration::from_secs(1), stream.next())
+            .await
+            .expect("Timeout waiting for event")
+            .expect("Stream ended unexpectedly")
+            .expect("Event stream error");
+        assert_eq!(event.event_id, expected);
+    }
+
+    // 默认订阅不回放历史
+    let mut live_only = event_bus
+        .subscribe_event_type(event_type, "live-subscriber")
+        .expect("Failed to subscribe");
+    assert!(
+        timeout(Duration::from_millis(100), live_only.next())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_replay_history_is_bounded_and_expires() {
+    let event_bus = EventBus::new(EventConfig {
+        max_event_history: Some(2),
+        event_ttl_seconds: Some(1),
+        ..EventConfig::default()
+    });
+    let event_type = "replay.bounded";
+
+    for event_id in ["bounded-1", "bounded-2", "bounded-3"] {
+        let _ = event_bus
+            .publish_event(replay_event(event_id, event_type))
+            .await;
+    }
+
+    // 只保留最近的 2 个事件
+    let mut stream = event_bus
+        .subscribe_with_replay(event_type, "bounded-subscriber", true)
+        .expect("Failed to subscribe with replay");
+    for expected in ["bounded-2", "bounded-3"] {
+        let event = timeout(Duration::from_secs(1), stream.next())
+            .await
+            .expect("Timeout waiting for event")
+            .expect("Stream ended unexpectedly")
+            .expect("Event stream error");
+        assert_eq!(event.event_id, expected);
+    }
+
+    // 超过 TTL 的事件不再回放
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let mut expired = event_bus
+        .subscribe_with_replay(event_type, "expired-subscriber", true)
+        .expect("Failed to subscribe with replay");
+    assert!(
+        timeout(Duration::from_millis(100), expired.next())
+            .await
+            .is_err()
+    );
+}
+
+// 通道容量为 4 的事件总线
+fn lagging_event_bus(skip_lagged_events: bool) -> EventBus {
+    EventBus::new(EventConfig {
+        channel_capacity: 4,
+        skip_lagged_events,
+        ..Default::default()
+    })
+}
+
+// 订阅者暂停消费时连续发布 10 个事件
+async fn publish_burst(event_bus: &EventBus, event_type: &str) {
+    for i in 0..10 {
+        let _ = event_bus
+            .publish_event(replay_event(&format!("lag-{i}"), event_type))
+            .await;
+    }
+}
+
+async fn next_event(
+    stream: &mut (impl tokio_stream::Stream<Item = Result<EventMessage, tonic::Status>> + Unpin),
+) -> Result<EventMessage, tonic::Status> {
+    timeout(Duration::from_secs(1), stream.next())
+        .await
+        .expect("Timeout waiting for event")
+        .expect("Stream ended unexpectedly")
+}
+
+#[tokio::test]
+async fn test_lagged_subscriber_skips_missed_events() {
+    let event_bus = lagging_event_bus(true);
+    let mut stream = Box::pin(
+        event_bus
+            .subscribe_event_type("lag.event", "slow-subscriber")
+            .expect("Failed to subscribe"),
+    );
+    publish_burst(&event_bus, "lag.event").await;
+
+    // 被覆盖的 6 个事件跳过，订阅继续接收剩余事件和之后的新事件
+    for i in 6..10 {
+        let event = next_event(&mut stream).await.expect("Event stream error");
+        assert_eq!(event.event_id, format!("lag-{i}"));
+    }
+    let _ = event_bus
+        .publish_event(replay_event("lag-after", "lag.event"))
+        .await;
+    let event = next_event(&mut stream).await.expect("Event stream error");
+    assert_eq!(event.event_id, "lag-after");
+
+    let stats = event_bus.get_stats();
+    assert_eq!(stats.events_lagged, 6);
+    assert_eq!(stats.delivery_failures, 6);
+}
+
+#[tokio::test]
+async fn test_lagged_subscriber_reports_error_by_default() {
+    let event_bus = lagging_event_bus(false);
+    let mut stream = Box::pin(
+        event_bus
+            .subscribe_event_type("lag.event", "slow-subscriber")
+            .expect("Failed to subscribe"),
+    );
+    publish_burst(&event_bus, "lag.event").await;
+
+    let status = next_event(&mut stream)
+        .await
+        .expect_err("Lag should be reported to the subscriber");
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+    assert!(status.message().contains("6 events"));
+    assert_eq!(event_bus.get_stats().events_lagged, 6);
+}
+
+#[tokio::test]
+async fn test_event_type_capacity_override() {
+    let event_bus = EventBus::new(EventConfig {
+        channel_capacity: 4,
+        event_type_capacities: [("busy.event".to_string(), 16)].into(),
+        ..Default::default()
+    });
+    let mut busy = Box::pin(
+        event_bus
+            .subscribe_event_type("busy.event", "busy-subscriber")
+            .expect("Failed to subscribe"),
+    );
+    let mut default = Box::pin(
+        event_bus
+            .subscribe_event_type("lag.event", "default-subscriber")
+            .expect("Failed to subscribe"),
+    );
+    publish_burst(&event_bus, "busy.event").await;
+    publish_burst(&event_bus, "lag.event").await;
+
+    // 容量为 16 的类型容纳全部 10 个事件
+    for i in 0..10 {
+        let event = next_event(&mut busy).await.expect("Event stream error");
+        assert_eq!(event.event_id, format!("lag-{i}"));
+    }
+
+    // 使用全局容量 4 的类型落后 6 个事件
+    let status = next_event(&mut default)
+        .await
+        .expect_err("Lag should be reported to the subscriber");
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+    assert_eq!(event_bus.get_stats().events_lagged, 6);
+}
+
+fn tenant_event(event_id: &str, event_type: &str, tenant_id: Option<&str>) -> EventMessage {
+    EventMessage {
+        event_id: event_id.to_string(),
+        event_type: event_type.to_string(),
+        metadata: tenant_id
+            .map(|tenant_id| {
+                [
+                    ("tenant_id".to_string(), tenant_id.to_string()),
+                    ("region".to_string(), "eu".to_string()),
+                ]
+                .into()
+            })
+            .unwrap_or_default(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_metadata_filtered_subscription() {
+    let event_bus = EventBus::new(EventConfig::default());
+    let event_type = "order.created";
+
+    let mut filtered = event_bus
+        .subscribe_with_filter(
+            event_type,
+            "acme-subscriber",
+            false,
+            EventFilter::metadata([("tenant_id".to_string(), "acme".to_string())].into()),
+        )
+        .expect("Failed to subscribe with filter");
+    let mut unfiltered = event_bus
+        .subscribe_event_type(event_type, "all-subscriber")
+        .expect("Failed to subscribe");
+
+    for (event_id, tenant_id) in [
+        ("order-1", Some("acme")),
+        ("order-2", Some("globex")),
+        ("order-3", None),
+        ("order-4", Some("acme")),
+    ] {
+        event_bus
+            .publish_event(tenant_event(event_id, event_type, tenant_id))
+            .await
+            .expect("Failed to publish event");
+    }
+
+    // 过滤订阅者只收到 tenant_id=acme 的事件，其余键不影响匹配
+    for expected in ["order-1", "order-4"] {
+        let event = timeout(Duration::from_secs(1), filtered.next())
+            .await
+            .expect("Timeout waiting for event")
+            .expect("Stream ended unexpectedly")
+            .expect("Event stream error");
+        assert_eq!(event.event_id, expected);
+        assert_eq!(event.metadata["tenant_id"], "acme");
+    }
+    assert!(
+        timeout(Duration::from_millis(100), filtered.next())
+            .await
+            .is_err()
+    );
+
+    // 未指定过滤条件的订阅者收到全部事件
+    for expected in ["order-1", "order-2", "order-3", "order-4"] {
+        let event = timeout(Duration::from_secs(1), unfiltered.next())
+            .await
+            .expect("Timeout waiting for event")
+            .expect("Stream ended unexpectedly")
+            .expect("Event stream error");
+        assert_eq!(event.event_id, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_subscription_request_records_metadata_filter() {
+    let event_bus = EventBus::new(EventConfig::default());
+    let subscriber_id = "filtered-connection";
+    let metadata_filter: std::collections::HashMap<String, String> =
+        [("tenant_id".to_string(), "acme".to_string())].into();
+
+    event_bus
+        .handle_subscription_request(
+            SubscriptionRequest {
+                action: Action::Subscribe as i32,
+                event_types: vec!["order.created".to_string()],
+                subscriber_id: subscriber_id.to_string(),
+                metadata_filter: metadata_filter.clone(),
+                reliable: false,
+            },
+            OWNER,
+        )
+        .await
+        .expect("Failed to subscribe");
+    assert_eq!(
+        event_bus.get_subscriber_filter(subscriber_id, "order.created"),
+        EventFilter::metadata(metadata_filter)
+    );
+    assert!(
+        event_bus
+            .get_subscriber_filter(subscriber_id, "order.cancelled")
+            .is_empty()
+    );
+
+    event_bus
+        .handle_subscription_request(
+            SubscriptionRequest {
+                action: Action::Unsubscribe as i32,
+                event_types: vec!["order.created".to_string()],
+                subscriber_id: subscriber_id.to_string(),
+                metadata_filter: Default::default(),
+                reliable: false,
+            },
+            OWNER,
+        )
+        .await
+        .expect("Failed to unsubscribe");
+    assert!(
+        event_bus
+            .get_subscriber_filter(subscriber_id, "order.created")
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_dropped_stream_frees_subscriber_slot() {
+    let event_bus = EventBus::new(EventConfig {
+        max_subscribers_per_type: 1,
+        ..EventConfig::default()
+    });
+    let event_type = "slot.test";
+
+    let stream = event_bus
+        .subscribe_event_type(event_type, "subscriber-a")
+        .expect("Failed to subscribe subscriber-a");
+    assert!(
+        event_bus
+            .subscribe_event_type(event_type, "subscriber-b")
+            .is_err()
+    );
+
+    // 订阅流仍然存活时不清理
+    assert_eq!(event_bus.evict_stale_subscribers().await, 0);
+
+    // 订阅方断开后订阅流被丢弃，清理后名额被释放
+    drop(stream);
+    assert_eq!(event_bus.evict_stale_subscribers().await, 1);
+    assert!(
+        event_bus
+            .get_subscriber_event_types("subscriber-a")
+            .is_empty()
+    );
+
+    let mut stream = event_bus
+        .subscribe_event_type(event_type, "subscriber-b")
+        .expect("Failed to subscribe after eviction");
+    event_bus
+        .publish_event(replay_event("slot-1", event_type))
+        .await
+        .expect("Failed to publish event");
+    assert_eq!(next_event(&mut stream).await.unwrap().event_id, "slot-1");
+
+    let stats = event_bus.get_stats();
+    assert_eq!(stats.subscriptions_evicted, 1);
+    assert_eq!(stats.total_subscribers, 1);
+}
+
+#[tokio::test]
+async fn test_idle_subscriber_is_evicted_after_ttl() {
+    let event_bus = EventBus::new(EventConfig {
+        subscriber_idle_ttl_seconds: Some(0),
+        ..EventConfig::default()
+    });
+
+    // 只登记了订阅、没有建立订阅流的订阅者在空闲超时后被清理
+    event_bus
+        .handle_subscription_request(
+            SubscriptionRequest {
+                action: Action::Subscribe as i32,
+                event_types: vec!["idle.test".to_string()],
+                subscriber_id: "idle-subscriber".to_string(),
+                metadata_filter: Default::default(),
+                reliable: false,
+            },
+            OWNER,
+        )
+        .await
+        .expect("Failed to subscribe");
+    let _stream = event_bus
+        .subscribe_event_type("idle.test", "streaming-subscriber")
+        .expect("Failed to subscribe");
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    assert_eq!(event_bus.evict_stale_subscribers().await, 1);
+    assert!(
+        event_bus
+            .get_subscriber_event_types("idle-subscriber")
+            .is_empty()
+    );
+    // 有存活订阅流的订阅者不受空闲超时影响
+    assert_eq!(
+        event_bus.get_subscriber_event_types("streaming-subscriber"),
+        ["idle.test"]
+    );
+}
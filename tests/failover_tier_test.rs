@@ -0,0 +1,165 @@
+mod common;
+
+use std::collections::HashMap;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tokio::sync::mpsc;
+use tonic::Request;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::services::registry::TIER_METADATA_KEY;
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::{MyRegistryService, ServiceHealthStatus};
+
+const TOKEN: &str = "tier-test-token";
+const SERVICE: &str = "TierService";
+
+fn registry_service() -> MyRegistryService {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    MyRegistryService::new(config)
+}
+
+// 以指定层级注册实例，返回实例 ID
+async fn register(
+    registry_service: &MyRegistryService,
+    address: &str,
+    instance_id: &str,
+    tier: u32,
+) -> String {
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: address.to_string(),
+            services: vec![SERVICE.to_string()],
+            metadata: HashMap::from([(TIER_METADATA_KEY.to_string(), tier.to_string())]),
+            instance_id: instance_id.to_string(),
+        }))
+        .await
+        .expect("Failed to register service")
+        .into_inner()
+        .instance_id
+}
+
+async fn call(router: &DynamicRouter) -> String {
+    let request = http::Request::builder()
+        .method("POST")
+        .uri(format!("/tier.{SERVICE}/Call"))
+        .header("content-type", "application/grpc")
+        .body(Full::new(Bytes::new()))
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    response
+        .headers()
+        .get("x-backend")
+        .and_then(|v| v.to_str().ok())
+        .expect("Response did not come from a backend")
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_forward_prefers_lowest_healthy_tier() {
+    let primary_a = common::start_addr_backend().await;
+    let primary_b = common::start_addr_backend().await;
+    let secondary = common::start_addr_backend().await;
+
+    let registry_service = registry_service();
+    let primary_a_id = register(&registry_service, &format!("http://{primary_a}"), "", 0).await;
+    let primary_b_id = register(&registry_service, &format!("http://{primary_b}"), "", 0).await;
+    register(&registry_service, &format!("http://{secondary}"), "", 1).await;
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        Config::default(),
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    // 主层级有健康实例时，次层级不接收任何请求
+    for _ in 0..10 {
+        assert_ne!(call(&router).await, secondary.to_string());
+    }
+
+    // 主层级只剩一个健康实例时仍然只使用主层级
+    assert!(registry_service.set_instance_health(
+        SERVICE,
+        &primary_a_id,
+        ServiceHealthStatus::Unhealthy
+    ));
+    for _ in 0..5 {
+        assert_eq!(call(&router).await, primary_b.to_string());
+    }
+
+    // 主层级全部不健康时溢出到次层级
+    assert!(registry_service.set_instance_health(
+        SERVICE,
+        &primary_b_id,
+        ServiceHealthStatus::Unhealthy
+    ));
+    for _ in 0..5 {
+        assert_eq!(call(&router).await, secondary.to_string());
+    }
+
+    // 主层级恢复后流量回到主层级
+    assert!(registry_service.set_instance_health(
+        SERVICE,
+        &primary_a_id,
+        ServiceHealthStatus::Healthy
+    ));
+    assert_eq!(call(&router).await, primary_a.to_string());
+}
+
+#[tokio::test]
+async fn test_reverse_prefers_lowest_healthy_tier() {
+    let registry_service = registry_service();
+    let manager = registry_service.reverse_connection_manager.clone();
+    let mut receivers = Vec::new();
+    for (connection_id, tier) in [("tier-conn-0a", 0), ("tier-conn-0b", 0), ("tier-conn-1", 1)] {
+        let (request_tx, request_rx) = mpsc::channel(16);
+        manager
+            .register_connection(
+                connection_id.to_string(),
+                vec![SERVICE.to_string()],
+                1,
+                request_tx,
+            )
+            .await
+            .expect("Failed to register connection");
+        register(&registry_service, "http://127.0.0.1:1", connection_id, tier).await;
+        receivers.push(request_rx);
+    }
+
+    for _ in 0..10 {
+        let connection = manager
+            .get_connection_for_service(SERVICE)
+            .expect("Expected a connection");
+        assert_ne!(connection.connection_id, "tier-conn-1");
+    }
+
+    for connection_id in ["tier-conn-0a", "tier-conn-0b"] {
+        assert!(registry_service.set_instance_health(
+            SERVICE,
+            connection_id,
+            ServiceHealthStatus::Unhealthy
+        ));
+    }
+    for _ in 0..5 {
+        let connection = manager
+            .get_connection_for_service(SERVICE)
+            .expect("Secondary tier should take over");
+        assert_eq!(connection.connection_id, "tier-conn-1");
+    }
+
+    assert!(registry_service.set_instance_health(
+        SERVICE,
+        "tier-conn-0b",
+        ServiceHealthStatus::Healthy
+    ));
+    let connection = manager
+        .get_connection_for_service(SERVICE)
+        .expect("Expected a connection");
+    assert_eq!(connection.connection_id, "tier-conn-0b");
+}
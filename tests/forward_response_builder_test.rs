@@ -0,0 +1,158 @@
+use grpc_opizontas::registry::{ResponseStreamInfo, StreamingInfo};
+use grpc_opizontas::services::connection::{
+    ForwardResponseBuilder, ForwardResponseError, ReverseConnectionManager,
+};
+
+#[test]
+fn test_builds_consistent_responses() {
+    let response = ForwardResponseBuilder::new("req-1")
+        .header("content-type", "application/grpc")
+        .payload(vec![1, 2, 3])
+        .build()
+        .expect("Failed to build response");
+    assert_eq!(response.request_id, "req-1");
+    assert_eq!(response.status_code, 200);
+    assert_eq!(response.payload, [1, 2, 3]);
+    assert!(!ReverseConnectionManager::is_streaming_response(&response));
+
+    let streaming_info = StreamingInfo {
+        is_stream_end: true,
+        ..Default::default()
+    };
+    let error = ForwardResponseBuilder::new("req-2")
+        .error(14, "Backend unavailable")
+        .streaming_info(Some(streaming_info))
+        .build()
+        .expect("Failed to build error response");
+    assert_eq!(error.status_code, 14);
+    assert_eq!(error.error_message, "Backend unavailable");
+    assert_eq!(error.streaming_info, Some(streaming_info));
+}
+
+#[test]
+fn test_fixed_error_responses_pass_validation() {
+    let grpc_error =
+        ForwardResponseBuilder::grpc_error("req-4", tonic::Code::ResourceExhausted, "Too large");
+    assert_eq!(grpc_error.status_code, 200);
+    assert_eq!(grpc_error.headers["grpc-status"], "8");
+    assert_eq!(grpc_error.headers["grpc-message"], "Too large");
+    let rebuilt = ForwardResponseBuilder::new("req-4")
+        .headers(grpc_error.headers.clone())
+        .build()
+        .expect("gRPC error response should be valid");
+    assert_eq!(rebuilt, grpc_error);
+
+    let streaming_info = StreamingInfo {
+        is_stream_end: true,
+        ..Default::default()
+    };
+    let internal = ForwardResponseBuilder::internal_error("req-5", "", Some(streaming_info));
+    assert_eq!(internal.status_code, 500);
+    assert!(!internal.error_message.is_empty());
+    assert_eq!(internal.streaming_info, Some(streaming_info));
+    let rebuilt = ForwardResponseBuilder::new("req-5")
+        .error(500, internal.error_message.clone())
+        .streaming_info(Some(streaming_info))
+        .build()
+        .expect("Internal error response should be valid");
+    assert_eq!(rebuilt, internal);
+}
+
+#[test]
+fn test_chunk_is_marked_as_streaming() {
+    let chunk = ForwardResponseBuilder::new("req-3")
+        .payload(vec![0; 16])
+        .chunk(2, true, Some(48))
+        .build()
+        .expect("Failed to build response chunk");
+    assert!(ReverseConnectionManager::is_streaming_response(&chunk));
+    let info = chunk.response_stream_info.unwrap();
+    assert_eq!(info.chunk_index, 2);
+    assert!(info.is_final_chunk);
+    assert_eq!(info.chunk_size, 16);
+    assert_eq!(info.total_size, Some(48));
+
+    assert_eq!(
+        ReverseConnectionManager::create_response_chunk(
+            "req-3".to_string(),
+            vec![],
+            0,
+            false,
+            None
+        )
+        .unwrap()
+        .response_stream_info
+        .map(|info| info.is_streamed),
+        Some(true)
+    );
+}
+
+#[test]
+fn test_rejects_inconsistent_stream_info() {
+    // 未标记 is_streamed 的流式信息会让 is_streaming_response 把数据块当作完整响应
+    let result = ForwardResponseBuilder::new("req-4")
+        .response_stream_info(ResponseStreamInfo {
+            is_streamed: false,
+            chunk_index: 1,
+            ..Default::default()
+        })
+        .build();
+    assert_eq!(result, Err(ForwardResponseError::NotStreamed));
+
+    let result = ForwardResponseBuilder::new("req-4")
+        .payload(vec![0; 8])
+        .response_stream_info(ResponseStreamInfo {
+            is_streamed: true,
+            chunk_size: 4,
+            ..Default::default()
+        })
+        .build();
+    assert_eq!(
+        result,
+        Err(ForwardResponseError::ChunkSizeMismatch {
+            chunk_size: 4,
+            payload_size: 8
+        })
+    );
+
+    assert_eq!(
+        ReverseConnectionManager::create_response_chunk(
+            "req-4".to_string(),
+            vec![],
+            -1,
+            false,
+            None
+        ),
+        Err(ForwardResponseError::InvalidChunkIndex(-1))
+    );
+}
+
+#[test]
+fn test_rejects_inconsistent_error_responses() {
+    assert_eq!(
+        ForwardResponseBuilder::new("req-5").error(500, "").build(),
+        Err(ForwardResponseError::MissingErrorMessage)
+    );
+    assert_eq!(
+        ForwardResponseBuilder::new("req-5")
+            .error(0, "failed")
+            .build(),
+        Err(ForwardResponseError::ErrorWithSuccessStatus(0))
+    );
+    assert_eq!(
+        ForwardResponseBuilder::new("req-5")
+            .error(204, "failed")
+            .build(),
+        Err(ForwardResponseError::ErrorWithSuccessStatus(204))
+    );
+    assert_eq!(
+        ForwardResponseBuilder::new("req-5")
+            .error(42, "failed")
+            .build(),
+        Err(ForwardResponseError::InvalidStatusCode(42))
+    );
+    assert_eq!(
+        ForwardResponseBuilder::new("req-5").status_code(-1).build(),
+        Err(ForwardResponseError::InvalidStatusCode(-1))
+    );
+}
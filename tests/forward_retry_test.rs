@@ -0,0 +1,239 @@
+mod common;
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use http_body_util::{BodyExt, Full};
+use prost::Message;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tonic::service::AxumBody;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::health::{
+    HealthCheckRequest, HealthCheckResponse, health_check_response::ServingStatus,
+};
+use grpc_opizontas::services::client_manager::GrpcClientManager;
+use grpc_opizontas::services::router::load_balance::LoadBalanceStrategy;
+use grpc_opizontas::services::router::{RequestContext, RouterError, forwarder};
+use grpc_opizontas::services::{ServiceHealthStatus, ServiceInfo, ServiceRegistry};
+
+use common::unused_addr;
+
+const SERVICE: &str = "grpc.health.v1.Health";
+const METHOD: &str = "/grpc.health.v1.Health/Check";
+
+fn registry_with(addrs: &[SocketAddr]) -> ServiceRegistry {
+    let instances = Arc::new(DashMap::new());
+    for addr in addrs {
+        let address = format!("http://{addr}");
+        instances.insert(
+            address.clone(),
+            ServiceInfo {
+                address,
+                last_heartbeat: SystemTime::now(),
+                health_status: ServiceHealthStatus::Healthy,
+                metadata: Default::default(),
+            },
+        );
+    }
+
+    let registry: ServiceRegistry = Arc::new(DashMap::new());
+    registry.insert(SERVICE.to_string(), instances);
+    registry
+}
+
+fn health_check_request() -> http::Request<Full<Bytes>> {
+    let message = HealthCheckRequest::default().encode_to_vec();
+    let mut frame = vec![0u8];
+    frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&message);
+
+    http::Request::builder()
+        .method("POST")
+        .uri(format!("/{SERVICE}/Check"))
+        .header("content-type", "application/grpc")
+        .header("te", "trailers")
+        .body(Full::new(Bytes::from(frame)))
+        .expect("Failed to build request")
+}
+
+fn cache_misses(client_manager: &GrpcClientManager) -> u64 {
+    client_manager
+        .get_stats()
+        .get("cache_misses")
+        .copied()
+        .unwrap_or(0)
+}
+
+#[tokio::test]
+async fn test_retries_exhausted_on_dead_instances() {
+    let mut config = Config::default();
+    config.router.retry_attempts = 2;
+
+    let registry = registry_with(&[unused_addr(), unused_addr()]);
+    let client_manager = GrpcClientManager::default();
+
+    let started = Instant::now();
+    let context = RequestContext::new(SERVICE, METHOD);
+    let result = forwarder::forward_request(
+        &registry,
+        &client_manager,
+        &config,
+        &context,
+        health_check_request(),
+    )
+    .await;
+
+    assert!(
+        matches!(result, Err(RouterError::ConnectError(_))),
+        "{result:?}"
+    );
+    // 首次请求 + 2 次重试，退避 50ms + 100ms
+    assert_eq!(cache_misses(&client_manager), 3);
+    assert_eq!(context.attempts(), 3);
+    assert_eq!(context.retries(), 2);
+    assert!(started.elapsed() >= Duration::from_millis(150));
+}
+
+#[tokio::test]
+async fn test_retry_succeeds_on_second_attempt() {
+    let mut config = Config::default();
+    config.router.retry_attempts = 3;
+
+    let addr = unused_addr();
+    let registry = registry_with(&[addr]);
+    let client_manager = GrpcClientManager::default();
+
+    // 后端在首次尝试失败后、退避结束前启动
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .expect("Failed to bind backend address");
+        common::serve_health(listener);
+    });
+
+    let context = RequestContext::new(SERVICE, METHOD);
+    let response = forwarder::forward_request(
+        &registry,
+        &client_manager,
+        &config,
+        &context,
+        health_check_request(),
+    )
+    .await
+    .expect("Request should succeed after retry");
+
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(cache_misses(&client_manager), 2);
+    // 上下文记录成功的那次尝试所用的实例
+    assert_eq!(context.attempts(), 2);
+    assert_eq!(context.backend(), Some(format!("http://{addr}")));
+
+    let collected = response
+        .into_body()
+        .collect()
+        .await
+        .expect("Failed to read response body");
+    let grpc_status = collected
+        .trailers()
+        .and_then(|trailers| trailers.get("grpc-status"))
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    assert_eq!(grpc_status.as_deref(), Some("0"));
+
+    let payload = collected.to_bytes();
+    let health = HealthCheckResponse::decode(&payload[5..]).expect("Invalid response message");
+    assert_eq!(health.status, ServingStatus::Serving as i32);
+}
+
+// 启动记录请求体的后端；slow 为 true 时读完请求体后迟迟不响应，使网关超时
+fn start_recording_backend(listener: TcpListener, slow: bool) -> mpsc::UnboundedReceiver<Bytes> {
+    let (body_tx, body_rx) = mpsc::unbounded_channel();
+    let backend = tower::service_fn(move |req: http::Request<AxumBody>| {
+        let body_tx = body_tx.clone();
+        async move {
+            let body = req.into_body().collect().await.unwrap().to_bytes();
+            let _ = body_tx.send(body);
+            if slow {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            }
+            let response = common::grpc_ok()
+                .body(Full::new(Bytes::new()))
+                .expect("Failed to build response");
+            Ok::<_, Infallible>(response)
+        }
+    });
+
+    common::serve_fallback(listener, backend);
+    body_rx
+}
+
+// 慢实例排在第一位（first_healthy 按地址排序），首次尝试必然在其上超时
+async fn forward_after_timeout(
+    payload: Bytes,
+    retry_buffer_max_size: usize,
+) -> (
+    Result<(), RouterError>,
+    mpsc::UnboundedReceiver<Bytes>,
+    mpsc::UnboundedReceiver<Bytes>,
+) {
+    let mut listeners = [common::bind().await, common::bind().await];
+    listeners.sort_by_key(|(_, addr)| format!("http://{addr}"));
+    let [(slow, slow_addr), (fast, fast_addr)] = listeners;
+    let slow_rx = start_recording_backend(slow, true);
+    let fast_rx = start_recording_backend(fast, false);
+
+    let mut config = Config::default();
+    config.router.request_timeout = 1;
+    config.router.retry_attempts = 1;
+    config.router.load_balance_strategy = LoadBalanceStrategy::FirstHealthy;
+    config.router.buffer_request_for_retry = true;
+    config.router.retry_buffer_max_size = retry_buffer_max_size;
+
+    let registry = registry_with(&[slow_addr, fast_addr]);
+    let request = http::Request::builder()
+        .method("POST")
+        .uri(format!("/{SERVICE}/Check"))
+        .header("content-type", "application/grpc")
+        .header("te", "trailers")
+        .body(Full::new(payload))
+        .expect("Failed to build request");
+    let result = forwarder::forward_request(
+        &registry,
+        &GrpcClientManager::default(),
+        &config,
+        &RequestContext::new(SERVICE, METHOD),
+        request,
+    )
+    .await
+    .map(|_| ());
+    (result, slow_rx, fast_rx)
+}
+
+#[tokio::test]
+async fn test_buffered_request_is_replayed_after_backend_received_it() {
+    let payload = Bytes::from(vec![7u8; 512]);
+    let (result, mut slow_rx, mut fast_rx) = forward_after_timeout(payload.clone(), 1024).await;
+
+    result.expect("Request should succeed on the second instance");
+    // 两个实例都收到了完整的请求体
+    assert_eq!(slow_rx.recv().await, Some(payload.clone()));
+    assert_eq!(fast_rx.recv().await, Some(payload));
+}
+
+#[tokio::test]
+async fn test_request_over_buffer_limit_is_not_retried() {
+    let payload = Bytes::from(vec![7u8; 4096]);
+    let (result, mut slow_rx, mut fast_rx) = forward_after_timeout(payload.clone(), 1024).await;
+
+    assert!(matches!(result, Err(RouterError::Timeout(_))));
+    // 超过上限的请求体按流式转发，发出后不再重放到其他实例
+    assert_eq!(slow_rx.recv().await, Some(payload));
+    assert!(fast_rx.try_recv().is_err());
+}
@@ -0,0 +1,247 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use prost::Message;
+use tokio::sync::{Notify, mpsc};
+use tokio::time::timeout;
+use tokio_stream::StreamExt;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::service::Routes;
+use tonic::{Request, Response, Status, Streaming};
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::health::HealthCheckRequest;
+use grpc_opizontas::registry::registry_service_server::{RegistryService, RegistryServiceServer};
+use grpc_opizontas::registry::{
+    ConnectionMessage, DrainInstanceRequest, DrainInstanceResponse, ForwardResponse,
+    ListServicesRequest, ListServicesResponse, RegisterRequest, RegisterResponse,
+    ResponseStreamInfo, connection_message::MessageType,
+};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::client::{GatewayClientConfig, GatewayClientError};
+use grpc_opizontas::services::gateway_client::GatewayClient;
+
+const TOKEN: &str = "gateway-client-token";
+
+async fn start_gateway() -> (Arc<MyRegistryService>, SocketAddr) {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    let registry_service = Arc::new(MyRegistryService::new(config));
+    let addr = common::serve_registry(registry_service.clone()).await;
+    (registry_service, addr)
+}
+
+async fn client(addr: SocketAddr, api_key: &str) -> GatewayClient {
+    GatewayClient::new(GatewayClientConfig {
+        gateway_address: format!("http://{addr}"),
+        api_key: api_key.to_string(),
+        ..Default::default()
+    })
+    .await
+    .expect("Failed to connect to gateway")
+}
+
+#[tokio::test]
+async fn test_list_healthy_services() {
+    let (registry_service, addr) = start_gateway().await;
+
+    for (service, address) in [
+        ("discovery.UserService", "http://127.0.0.1:50301"),
+        ("discovery.OrderService", "http://127.0.0.1:50302"),
+    ] {
+        registry_service
+            .register(Request::new(RegisterRequest {
+                api_key: TOKEN.to_string(),
+                address: address.to_string(),
+                services: vec![service.to_string()],
+                ..Default::default()
+            }))
+            .await
+            .expect("Failed to register service");
+    }
+
+    let mut client = client(addr, TOKEN).await;
+    let services = client
+        .list_healthy_services()
+        .await
+        .expect("Failed to list services");
+
+    assert_eq!(services.len(), 2);
+    assert_eq!(
+        services.get("discovery.UserService").map(String::as_str),
+        Some("http://127.0.0.1:50301")
+    );
+    assert_eq!(
+        services.get("discovery.OrderService").map(String::as_str),
+        Some("http://127.0.0.1:50302")
+    );
+    assert!(
+        client
+            .is_service_available("discovery.UserService")
+            .await
+            .unwrap()
+    );
+    assert!(
+        !client
+            .is_service_available("discovery.Missing")
+            .await
+            .unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_list_services_requires_valid_token() {
+    let (_registry_service, addr) = start_gateway().await;
+
+    let mut client = client(addr, "wrong-token").await;
+    let result = client.list_healthy_services().await;
+
+    assert!(matches!(
+        result,
+        Err(GatewayClientError::Grpc(status)) if status.code() == tonic::Code::Unauthenticated
+    ));
+}
+
+// 模拟网关：响应发送完后不关闭连接，客户端只能靠请求的最后一个响应判断调用结束
+#[derive(Debug, Default)]
+struct OpenStreamGateway {
+    // 放行 Chunked 调用的最后一个块
+    release_final_chunk: Arc<Notify>,
+}
+
+fn response_message(request_id: &str, value: &str) -> ConnectionMessage {
+    ConnectionMessage {
+        message_type: Some(MessageType::Response(ForwardResponse {
+            request_id: request_id.to_string(),
+            payload: HealthCheckRequest {
+                service: value.to_string(),
+            }
+            .encode_to_vec(),
+            ..Default::default()
+        })),
+    }
+}
+
+fn chunk_message(request_id: &str, index: i64, is_final_chunk: bool) -> ConnectionMessage {
+    let mut message = response_message(request_id, &format!("chunk-{index}"));
+    if let Some(MessageType::Response(response)) = message.message_type.as_mut() {
+        response.response_stream_info = Some(ResponseStreamInfo {
+            is_streamed: true,
+            chunk_index: index,
+            is_final_chunk,
+            ..Default::default()
+        });
+    }
+    message
+}
+
+#[tonic::async_trait]
+impl RegistryService for OpenStreamGateway {
+    async fn register(
+        &self,
+        _request: Request<RegisterRequest>,
+    ) -> Result<Response<RegisterResponse>, Status> {
+        Err(Status::unimplemented("register"))
+    }
+
+    type EstablishConnectionStream = ReceiverStream<Result<ConnectionMessage, Status>>;
+
+    async fn establish_connection(
+        &self,
+        request: Request<Streaming<ConnectionMessage>>,
+    ) -> Result<Response<Self::EstablishConnectionStream>, Status> {
+        let mut inbound = request.into_inner();
+        let release_final_chunk = self.release_final_chunk.clone();
+        let (tx, rx) = mpsc::channel(16);
+        tokio::spawn(async move {
+            while let Some(Ok(message)) = inbound.next().await {
+                let Some(MessageType::Request(request)) = message.message_type else {
+                    continue;
+                };
+                let id = request.request_id.as_str();
+                // 同一连接上其他请求的响应，客户端应忽略
+                let _ = tx
+                    .send(Ok(response_message("other-request", "foreign")))
+                    .await;
+                if request.method_path.ends_with("/Chunked") {
+                    let _ = tx.send(Ok(chunk_message(id, 0, false))).await;
+                    let _ = tx.send(Ok(chunk_message(id, 1, false))).await;
+                    release_final_chunk.notified().await;
+                    let _ = tx.send(Ok(chunk_message(id, 2, true))).await;
+                } else {
+                    // 不带任何流式标记的完整响应
+                    let _ = tx.send(Ok(response_message(id, "single"))).await;
+                }
+            }
+            // 客户端放弃响应流之前保持连接打开
+            tx.closed().await;
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn list_services(
+        &self,
+        _request: Request<ListServicesRequest>,
+    ) -> Result<Response<ListServicesResponse>, Status> {
+        Err(Status::unimplemented("list_services"))
+    }
+
+    async fn drain_instance(
+        &self,
+        _request: Request<DrainInstanceRequest>,
+    ) -> Result<Response<DrainInstanceResponse>, Status> {
+        Err(Status::unimplemented("drain_instance"))
+    }
+}
+
+async fn collect_values(
+    stream: impl tokio_stream::Stream<Item = Result<HealthCheckRequest, GatewayClientError>>,
+) -> Vec<String> {
+    let values = timeout(Duration::from_secs(2), stream.collect::<Vec<_>>())
+        .await
+        .expect("Server stream did not finish while the connection stayed open");
+    values
+        .into_iter()
+        .map(|value| value.expect("Unexpected stream error").service)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_multiplexed_server_streams_complete_independently() {
+    let (listener, addr) = common::bind().await;
+    let gateway = OpenStreamGateway::default();
+    let release_final_chunk = gateway.release_final_chunk.clone();
+    common::serve_routes(listener, Routes::new(RegistryServiceServer::new(gateway)));
+
+    // 两个调用共用同一个 HTTP/2 连接
+    let mut chunked_client = client(addr, TOKEN).await;
+    let mut single_client = chunked_client.clone();
+    let chunked = chunked_client
+        .call_server_stream::<_, HealthCheckRequest>(
+            "stream.Numbers",
+            "/stream.Numbers/Chunked",
+            HealthCheckRequest::default(),
+        )
+        .await
+        .expect("Failed to start chunked stream");
+    let single = single_client
+        .call_server_stream::<_, HealthCheckRequest>(
+            "stream.Numbers",
+            "/stream.Numbers/Single",
+            HealthCheckRequest::default(),
+        )
+        .await
+        .expect("Failed to start single stream");
+
+    // Chunked 调用仍在等待最后一个块时，Single 调用已经结束
+    assert_eq!(collect_values(single).await, vec!["single"]);
+
+    release_final_chunk.notify_one();
+    assert_eq!(
+        collect_values(chunked).await,
+        vec!["chunk-0", "chunk-1", "chunk-2"]
+    );
+}
@@ -0,0 +1,227 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time::timeout;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::client::{
+    ConnectionState, EventClientBuilder, GatewayClientConfig, ReverseConnectionClient,
+    ReverseConnectionOptions,
+};
+use grpc_opizontas::services::gateway_client::GatewayClient;
+
+use common::unused_addr;
+
+const TOKEN: &str = "reconnect-token";
+const SERVICE: &str = "reconnect.OrderService";
+
+async fn start_gateway() -> (Arc<MyRegistryService>, SocketAddr) {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    let registry_service = Arc::new(MyRegistryService::new(config));
+
+    let addr = common::serve_registry(registry_service.clone()).await;
+
+    (registry_service, addr)
+}
+
+#[tokio::test]
+async fn test_reverse_connection_reregisters_after_channel_loss() {
+    let (registry_service, gateway_addr) = start_gateway().await;
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let proxy = common::KillableProxy::start(gateway_addr).await;
+
+    let config = GatewayClientConfig {
+        gateway_address: format!("http://{}", proxy.addr),
+        api_key: TOKEN.to_string(),
+        ..Default::default()
+    };
+    let client = ReverseConnectionClient::spawn(
+        config.clone(),
+        ReverseConnectionOptions {
+            services: vec![SERVICE.to_string()],
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(1),
+            ..Default::default()
+        },
+    );
+    let handle = client.handle();
+
+    let state = timeout(
+        Duration::from_secs(5),
+        handle.wait_for_state(|state| matches!(state, ConnectionState::Connected { .. })),
+    )
+    .await
+    .expect("Timeout waiting for connection");
+    let ConnectionState::Connected { connection_id } = state else {
+        panic!("Unexpected state {state:?}");
+    };
+    assert!(reverse_manager.has_reverse_connection(SERVICE));
+
+    // 通过事件客户端订阅，订阅经由反向连接发送
+    let event_client = EventClientBuilder::new()
+        .with_gateway_client(GatewayClient::new(config).await.unwrap())
+        .with_reverse_connection(handle.clone())
+        .build()
+        .unwrap();
+    event_client
+        .subscribe_events(vec!["order.created"])
+        .await
+        .expect("Failed to subscribe");
+    timeout(Duration::from_secs(5), async {
+        while reverse_manager
+            .event_bus
+            .get_subscriber_event_types(&connection_id)
+            .is_empty()
+        {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Timeout waiting for subscription");
+
+    // 断开底层连接，客户端进入重连状态
+    let mut state_changes = handle.state_changes();
+    proxy.kill_connections();
+    timeout(
+        Duration::from_secs(5),
+        state_changes.wait_for(|state| matches!(state, ConnectionState::Reconnecting { .. })),
+    )
+    .await
+    .expect("Timeout waiting for reconnecting state")
+    .expect("State channel closed");
+
+    // 使用相同的连接 ID 重新注册并恢复订阅
+    let state = timeout(
+        Duration::from_secs(5),
+        state_changes.wait_for(|state| matches!(state, ConnectionState::Connected { .. })),
+    )
+    .await
+    .expect("Timeout waiting for reconnection")
+    .expect("State channel closed")
+    .clone();
+    assert_eq!(
+        state,
+        ConnectionState::Connected {
+            connection_id: connection_id.clone()
+        }
+    );
+    timeout(Duration::from_secs(5), async {
+        while !reverse_manager.has_reverse_connection(SERVICE)
+            || reverse_manager
+                .event_bus
+                .get_subscriber_event_types(&connection_id)
+                != vec!["order.created".to_string()]
+        {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Timeout waiting for re-registration");
+    assert_eq!(handle.subscriptions(), vec!["order.created".to_string()]);
+
+    // 主动关闭后不再重连
+    handle.close();
+    timeout(
+        Duration::from_secs(5),
+        handle.wait_for_state(|state| *state == ConnectionState::Closed),
+    )
+    .await
+    .expect("Timeout waiting for close");
+    timeout(Duration::from_secs(5), async {
+        while reverse_manager.has_reverse_connection(SERVICE) {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Gateway kept the closed connection");
+}
+
+#[tokio::test]
+async fn test_client_falls_back_to_next_gateway_address() {
+    let (registry_service, gateway_addr) = start_gateway().await;
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+
+    // 第一个地址没有监听，连接被拒绝
+    let config = GatewayClientConfig {
+        gateway_address: format!("http://{}", unused_addr()),
+        fallback_addresses: vec![format!("http://{gateway_addr}")],
+        api_key: TOKEN.to_string(),
+        connect_timeout: Duration::from_secs(1),
+        ..Default::default()
+    };
+
+    let gateway_client = GatewayClient::new(config.clone())
+        .await
+        .expect("Client should fall back to the second gateway");
+    assert_eq!(
+        gateway_client.gateway_address(),
+        format!("http://{gateway_addr}")
+    );
+
+    let client = ReverseConnectionClient::spawn(
+        config,
+        ReverseConnectionOptions {
+            services: vec![SERVICE.to_string()],
+            ..Default::default()
+        },
+    );
+    timeout(
+        Duration::from_secs(5),
+        client
+            .handle()
+            .wait_for_state(|state| matches!(state, ConnectionState::Connected { .. })),
+    )
+    .await
+    .expect("Timeout waiting for connection");
+    assert!(reverse_manager.has_reverse_connection(SERVICE));
+}
+
+#[tokio::test]
+async fn test_reverse_connection_rehomes_to_surviving_gateway() {
+    let (first_service, first_addr) = start_gateway().await;
+    let (second_service, second_addr) = start_gateway().await;
+    let first_manager = first_service.reverse_connection_manager.clone();
+    let second_manager = second_service.reverse_connection_manager.clone();
+
+    let client = ReverseConnectionClient::spawn(
+        GatewayClientConfig {
+            gateway_address: format!("http://{first_addr}"),
+            fallback_addresses: vec![format!("http://{second_addr}")],
+            api_key: TOKEN.to_string(),
+            ..Default::default()
+        },
+        ReverseConnectionOptions {
+            services: vec![SERVICE.to_string()],
+            initial_backoff: Duration::from_millis(50),
+            ..Default::default()
+        },
+    );
+    let handle = client.handle();
+    timeout(
+        Duration::from_secs(5),
+        handle.wait_for_state(|state| matches!(state, ConnectionState::Connected { .. })),
+    )
+    .await
+    .expect("Timeout waiting for connection");
+    assert!(first_manager.has_reverse_connection(SERVICE));
+    assert!(!second_manager.has_reverse_connection(SERVICE));
+
+    // 第一个网关停机：断开现有连接并拒绝新的反向连接
+    first_manager.begin_drain();
+    first_manager.close_all_connections();
+
+    timeout(Duration::from_secs(5), async {
+        while !second_manager.has_reverse_connection(SERVICE) {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Reverse connection did not move to the surviving gateway");
+    assert!(!first_manager.has_reverse_connection(SERVICE));
+    assert!(matches!(handle.state(), ConnectionState::Connected { .. }));
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use http_body::Frame;
+use http_body_util::{BodyExt, Full, StreamBody};
+use hyper_util::rt::TokioIo;
+use tokio::sync::oneshot;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_client::RegistryServiceClient;
+
+const TOKEN: &str = "grpc-web-token";
+const ORIGIN: &str = "https://app.example.com";
+
+type EchoBody =
+    StreamBody<tokio_stream::Iter<std::vec::IntoIter<Result<Frame<Bytes>, Infallible>>>>;
+
+// 原样返回请求消息并在 trailers 中带上 grpc-status 的原生 gRPC 后端
+async fn echo<B>(req: http::Request<B>) -> Result<http::Response<EchoBody>, Infallible>
+where
+    B: http_body::Body,
+    B::Error: std::fmt::Debug,
+{
+    let payload = req.into_body().collect().await.unwrap().to_bytes();
+    let mut trailers = http::HeaderMap::new();
+    trailers.insert("grpc-status", "0".parse().unwrap());
+    let frames = vec![Ok(Frame::data(payload)), Ok(Frame::trailers(trailers))];
+    let response = http::Response::builder()
+        .header("content-type", "application/grpc")
+        .body(StreamBody::new(tokio_stream::iter(frames)))
+        .expect("Failed to build response");
+    Ok(response)
+}
+
+async fn start_echo_backend() -> SocketAddr {
+    let (listener, addr) = common::bind().await;
+    common::serve_fallback(listener, tower::service_fn(echo));
+    addr
+}
+
+async fn start_gateway() -> (SocketAddr, oneshot::Sender<()>) {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.grpc_web.cors_enabled = true;
+    config.grpc_web.cors_allowed_origins = vec![ORIGIN.to_string()];
+
+    let (gateway_addr, shutdown_tx, _gateway) = common::spawn_gateway(config).await;
+
+    let backend_addr = start_echo_backend().await;
+    let channel = common::connect(gateway_addr).await;
+    RegistryServiceClient::new(channel)
+        .register(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: format!("http://{backend_addr}"),
+            services: vec!["EchoService".to_string()],
+            ..Default::default()
+        })
+        .await
+        .expect("Failed to register backend");
+
+    (gateway_addr, shutdown_tx)
+}
+
+// 浏览器使用的 HTTP/1.1 连接
+async fn send_http1(
+    addr: SocketAddr,
+    request: http::Request<Full<Bytes>>,
+) -> http::Response<hyper::body::Incoming> {
+    let stream = tokio::net::TcpStream::connect(addr)
+        .await
+        .expect("Failed to connect to gateway");
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+        .await
+        .expect("HTTP/1.1 handshake failed");
+    tokio::spawn(connection);
+    sender
+        .send_request(request)
+        .await
+        .expect("HTTP/1.1 request failed")
+}
+
+fn grpc_frame(flag: u8, payload: &[u8]) -> Bytes {
+    let mut frame = BytesMut::with_capacity(5 + payload.len());
+    frame.put_u8(flag);
+    frame.put_u32(payload.len() as u32);
+    frame.put_slice(payload);
+    frame.freeze()
+}
+
+// 按 gRPC 帧格式拆分响应体，返回 (标志位, 内容)
+fn split_frames(mut body: &[u8]) -> Vec<(u8, Vec<u8>)> {
+    let mut frames = Vec::new();
+    while !body.is_empty() {
+        assert!(body.len() >= 5, "truncated frame header");
+        let flag = body[0];
+        let len = u32::from_be_bytes(body[1..5].try_into().unwrap()) as usize;
+        frames.push((flag, body[5..5 + len].to_vec()));
+        body = &body[5 + len..];
+    }
+    frames
+}
+
+#[tokio::test]
+async fn test_grpc_web_request_is_routed_and_framed() {
+    let (gateway_addr, shutdown_tx) = start_gateway().await;
+
+    let request = http::Request::builder()
+        .method("POST")
+        .uri(format!("http://{gateway_addr}/echo.EchoService/Echo"))
+        .header("host", gateway_addr.to_string())
+        .header("content-type", "application/grpc-web+proto")
+        .header("x-grpc-web", "1")
+        .header("origin", ORIGIN)
+        .body(Full::new(grpc_frame(0, b"hello from browser")))
+        .unwrap();
+    let response = send_http1(gateway_addr, request).await;
+
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/grpc-web+proto"
+    );
+    assert_eq!(
+        response
+            .headers()
+            .get("access-control-allow-origin")
+            .unwrap(),
+        ORIGIN
+    );
+
+    // 数据帧原样返回，trailers 编码为标志位 0x80 的帧
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let frames = split_frames(&body);
+    assert_eq!(frames.len(), 2, "unexpected frames {frames:?}");
+    assert_eq!(frames[0], (0, b"hello from browser".to_vec()));
+    assert_eq!(frames[1].0, 0x80);
+    let trailers = String::from_utf8(frames[1].1.clone()).unwrap();
+    assert!(
+        trailers.to_ascii_lowercase().contains("grpc-status:0"),
+        "unexpected trailers {trailers:?}"
+    );
+
+    let _ = shutdown_tx.send(());
+}
+
+#[tokio::test]
+async fn test_cors_preflight_allows_configured_origin() {
+    let (gateway_addr, shutdown_tx) = start_gateway().await;
+
+    let preflight = |origin: &str| {
+        http::Request::builder()
+            .method("OPTIONS")
+            .uri(format!("http://{gateway_addr}/echo.EchoService/Echo"))
+            .header("host", gateway_addr.to_string())
+            .header("origin", origin)
+            .header("access-control-request-method", "POST")
+            .header("access-control-request-headers", "content-type,x-grpc-web")
+            .body(Full::new(Bytes::new()))
+            .unwrap()
+    };
+
+    let response = send_http1(gateway_addr, preflight(ORIGIN)).await;
+    assert!(response.status().is_success());
+    let headers = response.headers();
+    assert_eq!(headers.get("access-control-allow-origin").unwrap(), ORIGIN);
+    assert!(
+        headers
+            .get("access-control-allow-headers")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.contains("x-grpc-web"))
+    );
+
+    // 未配置的来源不返回允许头
+    let response = send_http1(gateway_addr, preflight("https://evil.example.com")).await;
+    assert!(
+        !response
+            .headers()
+            .contains_key("access-control-allow-origin")
+    );
+
+    let _ = shutdown_tx.send(());
+}
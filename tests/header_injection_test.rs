@@ -0,0 +1,158 @@
+mod common;
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tonic::Request;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::registry::{ForwardResponse, RegisterRequest, connection_message::MessageType};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::router::header_injection::HeaderInjectionPolicy;
+
+const TOKEN: &str = "header-injection-token";
+
+// 为 billing.BillingService 注入内部密钥和租户头
+fn config_with_injected_headers(policy: HeaderInjectionPolicy) -> Config {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.router.injected_headers.insert(
+        "billing.BillingService".to_string(),
+        HashMap::from([
+            ("x-internal-key".to_string(), "internal-secret".to_string()),
+            ("x-tenant".to_string(), "gateway-tenant".to_string()),
+        ]),
+    );
+    config.router.injected_header_policy = policy;
+    config
+}
+
+fn client_request() -> http::Request<Full<Bytes>> {
+    http::Request::builder()
+        .method("POST")
+        .uri("/billing.BillingService/Charge")
+        .header("content-type", "application/grpc")
+        .header("x-tenant", "client-tenant")
+        .body(Full::new(Bytes::new()))
+        .unwrap()
+}
+
+// 启动一个把收到的请求头发回测试的后端
+async fn start_backend() -> (SocketAddr, mpsc::UnboundedReceiver<http::HeaderMap>) {
+    let (listener, addr) = common::bind().await;
+    let (headers_tx, headers_rx) = mpsc::unbounded_channel();
+    let backend = tower::service_fn(move |req: http::Request<_>| {
+        let _ = headers_tx.send(req.headers().clone());
+        async move {
+            let response = common::grpc_ok()
+                .body(Full::new(Bytes::new()))
+                .expect("Failed to build response");
+            Ok::<_, Infallible>(response)
+        }
+    });
+
+    common::serve_fallback(listener, backend);
+    (addr, headers_rx)
+}
+
+#[tokio::test]
+async fn test_injected_headers_reach_forward_backend() {
+    let (backend_addr, mut backend_headers) = start_backend().await;
+
+    let config = config_with_injected_headers(HeaderInjectionPolicy::Override);
+    let registry_service = MyRegistryService::new(config.clone());
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: format!("http://{backend_addr}"),
+            services: vec!["BillingService".to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    let response = router.oneshot(client_request()).await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+
+    // 调用方没有发送 x-internal-key，后端仍然收到；x-tenant 被配置的值覆盖
+    let headers = backend_headers
+        .recv()
+        .await
+        .expect("Backend did not receive a request");
+    assert_eq!(headers.get("x-internal-key").unwrap(), "internal-secret");
+    assert_eq!(headers.get("x-tenant").unwrap(), "gateway-tenant");
+}
+
+#[tokio::test]
+async fn test_injected_headers_supplement_reverse_request() {
+    let config = config_with_injected_headers(HeaderInjectionPolicy::Supplement);
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "billing-conn".to_string(),
+            vec!["BillingService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    let forwarding = tokio::spawn(router.oneshot(client_request()));
+    let message = timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for forwarded request")
+        .expect("Request channel closed");
+    let Some(MessageType::Request(forward_request)) = message.message_type else {
+        panic!("Expected a forwarded request");
+    };
+
+    // 缺少的头被补充，调用方已发送的 x-tenant 保持不变
+    assert_eq!(
+        forward_request
+            .headers
+            .get("x-internal-key")
+            .map(String::as_str),
+        Some("internal-secret")
+    );
+    assert_eq!(
+        forward_request.headers.get("x-tenant").map(String::as_str),
+        Some("client-tenant")
+    );
+
+    reverse_manager
+        .handle_response(ForwardResponse {
+            request_id: forward_request.request_id,
+            status_code: 0,
+            ..Default::default()
+        })
+        .await;
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Timeout waiting for response")
+        .expect("Router task panicked")
+        .expect("Router returned an error");
+    assert_eq!(response.status(), http::StatusCode::OK);
+}
@@ -0,0 +1,160 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time::timeout;
+use tokio_stream::StreamExt;
+use tonic::Request;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::health::{
+    HealthCheckRequest, health_check_response::ServingStatus, health_server::Health,
+};
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::services::health::HealthService;
+use grpc_opizontas::services::{MyRegistryService, ServiceHealthStatus};
+
+const TOKEN: &str = "health-test-token";
+
+async fn setup() -> (Arc<MyRegistryService>, HealthService) {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+
+    let registry_service = Arc::new(MyRegistryService::new(config));
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: "http://127.0.0.1:50100".to_string(),
+            services: vec!["health.TestService".to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+
+    let health = HealthService::new(registry_service.clone());
+    (registry_service, health)
+}
+
+async fn check(health: &HealthService, service: &str) -> i32 {
+    health
+        .check(Request::new(HealthCheckRequest {
+            service: service.to_string(),
+        }))
+        .await
+        .expect("Health check should not fail")
+        .into_inner()
+        .status
+}
+
+#[tokio::test]
+async fn test_health_check_statuses() {
+    let (_registry_service, health) = setup().await;
+
+    assert_eq!(check(&health, "").await, ServingStatus::Serving as i32);
+    assert_eq!(
+        check(&health, "health.TestService").await,
+        ServingStatus::Serving as i32
+    );
+    // 未注册的服务返回 NOT_SERVING 而不是错误
+    assert_eq!(
+        check(&health, "unknown.Service").await,
+        ServingStatus::NotServing as i32
+    );
+}
+
+#[tokio::test]
+async fn test_health_watch_streams_transitions() {
+    let (registry_service, health) = setup().await;
+
+    let mut stream = health
+        .watch(Request::new(HealthCheckRequest {
+            service: "health.TestService".to_string(),
+        }))
+        .await
+        .expect("Failed to start watch")
+        .into_inner();
+
+    let initial = timeout(Duration::from_secs(1), stream.next())
+        .await
+        .expect("Timeout waiting for initial status")
+        .expect("Watch stream ended")
+        .expect("Watch stream error");
+    assert_eq!(initial.status, ServingStatus::Serving as i32);
+
+    assert!(
+        registry_service
+            .update_service_health("health.TestService", ServiceHealthStatus::Unhealthy)
+    );
+
+    let transition = timeout(Duration::from_secs(1), stream.next())
+        .await
+        .expect("Timeout waiting for status transition")
+        .expect("Watch stream ended")
+        .expect("Watch stream error");
+    assert_eq!(transition.status, ServingStatus::NotServing as i32);
+}
+
+async fn next_health(
+    watch: &mut tokio::sync::watch::Receiver<ServiceHealthStatus>,
+) -> ServiceHealthStatus {
+    timeout(Duration::from_secs(1), watch.changed())
+        .await
+        .expect("Timeout waiting for health transition")
+        .expect("Health watch closed");
+    watch.borrow_and_update().clone()
+}
+
+#[tokio::test]
+async fn test_watch_health_observes_transitions() {
+    let (registry_service, _health) = setup().await;
+    let mut watch = registry_service.watch_health("health.TestService");
+    assert_eq!(*watch.borrow_and_update(), ServiceHealthStatus::Healthy);
+
+    assert!(
+        registry_service
+            .update_service_health("health.TestService", ServiceHealthStatus::Unhealthy)
+    );
+    assert_eq!(
+        next_health(&mut watch).await,
+        ServiceHealthStatus::Unhealthy
+    );
+
+    // 状态未变化时不通知
+    registry_service.update_service_health("health.TestService", ServiceHealthStatus::Unhealthy);
+    assert!(!watch.has_changed().unwrap());
+
+    // 重新注册使实例恢复健康
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: "http://127.0.0.1:50100".to_string(),
+            services: vec!["health.TestService".to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to re-register service");
+    assert_eq!(next_health(&mut watch).await, ServiceHealthStatus::Healthy);
+
+    // 服务被移除后推送 Unknown 并关闭通道
+    assert!(registry_service.unregister_service("health.TestService"));
+    assert_eq!(next_health(&mut watch).await, ServiceHealthStatus::Unknown);
+    assert!(watch.changed().await.is_err());
+}
+
+#[tokio::test]
+async fn test_watch_health_before_registration() {
+    let (registry_service, _health) = setup().await;
+    let mut watch = registry_service.watch_health("health.LateService");
+    assert_eq!(*watch.borrow_and_update(), ServiceHealthStatus::Unknown);
+
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: "http://127.0.0.1:50101".to_string(),
+            services: vec!["health.LateService".to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+    assert_eq!(next_health(&mut watch).await, ServiceHealthStatus::Healthy);
+}
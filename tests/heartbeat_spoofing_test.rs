@@ -0,0 +1,138 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tokio_stream::StreamExt;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::Streaming;
+use tonic::transport::Channel;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::registry_service_client::RegistryServiceClient;
+use grpc_opizontas::registry::{
+    ConnectionMessage, ConnectionRegister, ConnectionStatus, Heartbeat,
+    connection_message::MessageType, connection_status::StatusType,
+};
+use grpc_opizontas::services::MyRegistryService;
+
+const TOKEN: &str = "heartbeat-spoofing-token";
+const VICTIM_SERVICE: &str = "VictimService";
+const ATTACKER_SERVICE: &str = "AttackerService";
+const IDLE: Duration = Duration::from_millis(300);
+
+async fn start_gateway() -> (Arc<MyRegistryService>, RegistryServiceClient<Channel>) {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.reverse_connection.ping_interval = 0;
+    let registry_service = Arc::new(MyRegistryService::new(config));
+    let addr = common::serve_registry(registry_service.clone()).await;
+
+    let channel = common::connect(addr).await;
+    (registry_service, RegistryServiceClient::new(channel))
+}
+
+struct Connection {
+    connection_id: String,
+    sender: mpsc::Sender<ConnectionMessage>,
+    inbound: Streaming<ConnectionMessage>,
+}
+
+impl Connection {
+    async fn heartbeat(&self, connection_id: &str) {
+        self.sender
+            .send(ConnectionMessage {
+                message_type: Some(MessageType::Heartbeat(Heartbeat {
+                    timestamp: 0,
+                    connection_id: connection_id.to_string(),
+                })),
+            })
+            .await
+            .expect("Connection stream closed");
+    }
+
+    async fn next_status(&mut self) -> ConnectionStatus {
+        let message = timeout(Duration::from_secs(1), self.inbound.next())
+            .await
+            .expect("Timeout waiting for connection status")
+            .expect("Connection stream closed")
+            .expect("Connection stream failed");
+        let Some(MessageType::Status(status)) = message.message_type else {
+            panic!("Expected a connection status, got {message:?}");
+        };
+        status
+    }
+}
+
+// 建立反向连接并等待连接确认
+async fn connect(client: &mut RegistryServiceClient<Channel>, service: &str) -> Connection {
+    let (sender, reverse_rx) = mpsc::channel(4);
+    sender
+        .send(ConnectionMessage {
+            message_type: Some(MessageType::Register(ConnectionRegister {
+                api_key: TOKEN.to_string(),
+                services: vec![service.to_string()],
+                connection_id: String::new(),
+                weight: 1,
+            })),
+        })
+        .await
+        .unwrap();
+    let inbound = client
+        .establish_connection(ReceiverStream::new(reverse_rx))
+        .await
+        .expect("Failed to establish connection")
+        .into_inner();
+
+    let mut connection = Connection {
+        connection_id: String::new(),
+        sender,
+        inbound,
+    };
+    let status = connection.next_status().await;
+    assert_eq!(status.status, StatusType::Connected as i32);
+    connection.connection_id = status.connection_id;
+    connection
+}
+
+fn heartbeat_age(registry_service: &MyRegistryService, connection_id: &str) -> Duration {
+    registry_service
+        .reverse_connection_manager
+        .get_connection_info(connection_id)
+        .expect("Connection should stay registered")
+        .last_heartbeat_age
+}
+
+#[tokio::test]
+async fn test_heartbeat_with_foreign_connection_id_is_rejected() {
+    let (registry_service, mut client) = start_gateway().await;
+    let victim = connect(&mut client, VICTIM_SERVICE).await;
+    let mut attacker = connect(&mut client, ATTACKER_SERVICE).await;
+    tokio::time::sleep(IDLE).await;
+
+    // 以服务名或其他连接的 ID 发送心跳都会被拒绝，并在发送方的流上收到错误状态
+    for spoofed_id in [VICTIM_SERVICE, victim.connection_id.as_str()] {
+        attacker.heartbeat(spoofed_id).await;
+        let status = attacker.next_status().await;
+        assert_eq!(status.status, StatusType::Error as i32);
+        assert_eq!(status.connection_id, attacker.connection_id);
+        assert!(status.message.contains(spoofed_id), "{}", status.message);
+    }
+
+    // 被冒用的连接和发送方自己的连接都没有被刷新
+    assert!(heartbeat_age(&registry_service, &victim.connection_id) >= IDLE);
+    assert!(heartbeat_age(&registry_service, &attacker.connection_id) >= IDLE);
+
+    // 使用自己的连接 ID 的心跳正常刷新
+    attacker.heartbeat(&attacker.connection_id).await;
+    timeout(Duration::from_secs(1), async {
+        while heartbeat_age(&registry_service, &attacker.connection_id) >= IDLE {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("Heartbeat with the stream's own connection_id was not applied");
+    assert!(heartbeat_age(&registry_service, &victim.connection_id) >= IDLE);
+}
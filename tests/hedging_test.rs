@@ -0,0 +1,146 @@
+mod common;
+
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use http_body_util::Full;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::router::load_balance::LoadBalanceStrategy;
+use grpc_opizontas::services::{
+    MyRegistryService, ServiceHealthStatus, ServiceInfo, ServiceRegistry,
+};
+
+use common::grpc_request;
+
+const SERVICE: &str = "CatalogService";
+const SLOW_DELAY: Duration = Duration::from_millis(800);
+// 空的 gRPC 消息帧
+const EMPTY_MESSAGE: &[u8] = b"\0\0\0\0\0";
+
+// 启动一个等待 delay 后响应的后端，响应头 x-backend 标明实例，收到的请求路径发回测试
+fn start_backend(
+    listener: TcpListener,
+    name: &'static str,
+    delay: Duration,
+) -> mpsc::UnboundedReceiver<String> {
+    let (paths_tx, paths_rx) = mpsc::unbounded_channel();
+    let backend = tower::service_fn(move |req: http::Request<_>| {
+        let _ = paths_tx.send(req.uri().path().to_string());
+        async move {
+            tokio::time::sleep(delay).await;
+            let response = common::grpc_ok()
+                .header("x-backend", name)
+                .body(Full::new(Bytes::new()))
+                .expect("Failed to build response");
+            Ok::<_, Infallible>(response)
+        }
+    });
+
+    common::serve_fallback(listener, backend);
+    paths_rx
+}
+
+fn registry_with(addresses: &[String]) -> ServiceRegistry {
+    let instances = Arc::new(DashMap::new());
+    for address in addresses {
+        instances.insert(
+            address.clone(),
+            ServiceInfo {
+                address: address.clone(),
+                last_heartbeat: SystemTime::now(),
+                health_status: ServiceHealthStatus::Healthy,
+                metadata: Default::default(),
+            },
+        );
+    }
+    let registry: ServiceRegistry = Arc::new(DashMap::new());
+    registry.insert(SERVICE.to_string(), instances);
+    registry
+}
+
+struct Backends {
+    router: DynamicRouter,
+    slow_paths: mpsc::UnboundedReceiver<String>,
+    fast_paths: mpsc::UnboundedReceiver<String>,
+}
+
+// first_healthy 总是先选择地址排序最前的实例，让慢实例成为主请求的目标
+async fn start_backends() -> Backends {
+    let mut listeners = [common::bind().await, common::bind().await];
+    listeners.sort_by_key(|(_, addr)| format!("http://{addr}"));
+    let addrs = listeners.each_ref().map(|(_, addr)| *addr);
+    let [(slow, _), (fast, _)] = listeners;
+    let slow_paths = start_backend(slow, "slow", SLOW_DELAY);
+    let fast_paths = start_backend(fast, "fast", Duration::ZERO);
+
+    let mut config = Config::default();
+    config.router.load_balance_strategy = LoadBalanceStrategy::FirstHealthy;
+    config.router.hedged_methods = vec!["/hedge.CatalogService/Get*".to_string()];
+    config.router.hedge_delay_ms = 50;
+    let registry = registry_with(&addrs.map(|addr| format!("http://{addr}")));
+    let reverse_manager = MyRegistryService::new(config.clone()).reverse_connection_manager;
+    Backends {
+        router: DynamicRouter::new(registry, config, reverse_manager)
+            .expect("Failed to create router"),
+        slow_paths,
+        fast_paths,
+    }
+}
+
+#[tokio::test]
+async fn test_hedged_request_returns_at_fast_instance_latency() {
+    let mut backends = start_backends().await;
+
+    let started = Instant::now();
+    let response = backends
+        .router
+        .oneshot(grpc_request("/hedge.CatalogService/GetItem", EMPTY_MESSAGE))
+        .await
+        .unwrap();
+    let elapsed = started.elapsed();
+
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(response.headers().get("x-backend").unwrap(), "fast");
+    assert!(
+        elapsed < SLOW_DELAY / 2,
+        "Hedged request waited for the slow instance: {elapsed:?}"
+    );
+
+    // 主请求先发往慢实例，对冲请求发往另一个实例
+    assert_eq!(
+        backends.slow_paths.recv().await.as_deref(),
+        Some("/hedge.CatalogService/GetItem")
+    );
+    assert_eq!(
+        backends.fast_paths.recv().await.as_deref(),
+        Some("/hedge.CatalogService/GetItem")
+    );
+}
+
+#[tokio::test]
+async fn test_unlisted_method_is_not_hedged() {
+    let mut backends = start_backends().await;
+
+    let started = Instant::now();
+    let response = backends
+        .router
+        .oneshot(grpc_request(
+            "/hedge.CatalogService/UpdateItem",
+            EMPTY_MESSAGE,
+        ))
+        .await
+        .unwrap();
+
+    // 未列入 hedged_methods 的方法可能有副作用，只发给一个实例
+    assert_eq!(response.headers().get("x-backend").unwrap(), "slow");
+    assert!(started.elapsed() >= SLOW_DELAY);
+    assert!(backends.fast_paths.try_recv().is_err());
+}
@@ -0,0 +1,139 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+use tokio_stream::wrappers::TcpListenerStream;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::health::health_server::HealthServer;
+use grpc_opizontas::server::server_builder;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::client::Http2Settings;
+use grpc_opizontas::services::health::HealthService;
+
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+const FRAME_SETTINGS: u8 = 0x4;
+const FRAME_PING: u8 = 0x6;
+const FRAME_WINDOW_UPDATE: u8 = 0x8;
+const FLAG_ACK: u8 = 0x1;
+const SETTINGS_INITIAL_WINDOW_SIZE: u16 = 0x4;
+const DEFAULT_CONNECTION_WINDOW: u32 = 65_535;
+
+struct Frame {
+    kind: u8,
+    flags: u8,
+    stream_id: u32,
+    payload: Vec<u8>,
+}
+
+async fn read_frame(stream: &mut TcpStream) -> Frame {
+    let mut header = [0u8; 9];
+    stream
+        .read_exact(&mut header)
+        .await
+        .expect("Failed to read frame header");
+    let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+    let mut payload = vec![0u8; len];
+    stream
+        .read_exact(&mut payload)
+        .await
+        .expect("Failed to read frame payload");
+    Frame {
+        kind: header[3],
+        flags: header[4],
+        stream_id: u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fff_ffff,
+        payload,
+    }
+}
+
+fn frame(kind: u8, flags: u8) -> Vec<u8> {
+    vec![0, 0, 0, kind, flags, 0, 0, 0, 0]
+}
+
+// 用配置创建的构建器在随机端口上启动网关健康检查服务
+async fn start_server(config: Config) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind listener");
+    let addr = listener.local_addr().unwrap();
+    let registry_service = Arc::new(MyRegistryService::new(config.clone()));
+    let router = server_builder(&config)
+        .expect("Failed to build server")
+        .add_service(HealthServer::new(HealthService::new(registry_service)));
+    tokio::spawn(async move {
+        router
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .expect("Gateway server failed");
+    });
+    addr
+}
+
+#[tokio::test]
+async fn test_server_builder_applies_http2_settings() {
+    let mut config = Config::default();
+    config.http2.http2_keepalive_interval = 1;
+    config.http2.http2_keepalive_timeout = 5;
+    config.http2.initial_stream_window = 128 * 1024;
+    config.http2.initial_connection_window = 256 * 1024;
+    let addr = start_server(config).await;
+
+    let mut stream = TcpStream::connect(addr).await.expect("Failed to connect");
+    stream.write_all(PREFACE).await.unwrap();
+    stream.write_all(&frame(FRAME_SETTINGS, 0)).await.unwrap();
+
+    let mut stream_window = None;
+    let mut connection_window = None;
+    let mut ping = false;
+    timeout(Duration::from_secs(5), async {
+        while !(ping && stream_window.is_some() && connection_window.is_some()) {
+            let received = read_frame(&mut stream).await;
+            match received.kind {
+                FRAME_SETTINGS if received.flags & FLAG_ACK == 0 => {
+                    for setting in received.payload.chunks_exact(6) {
+                        if u16::from_be_bytes([setting[0], setting[1]])
+                            == SETTINGS_INITIAL_WINDOW_SIZE
+                        {
+                            stream_window = Some(u32::from_be_bytes([
+                                setting[2], setting[3], setting[4], setting[5],
+                            ]));
+                        }
+                    }
+                    stream
+                        .write_all(&frame(FRAME_SETTINGS, FLAG_ACK))
+                        .await
+                        .unwrap();
+                }
+                FRAME_WINDOW_UPDATE if received.stream_id == 0 => {
+                    let increment = u32::from_be_bytes(received.payload[..4].try_into().unwrap());
+                    connection_window = Some(DEFAULT_CONNECTION_WINDOW + increment);
+                }
+                // 连接空闲时服务端按 keepalive 间隔发送 PING
+                FRAME_PING if received.flags & FLAG_ACK == 0 => ping = true,
+                _ => {}
+            }
+        }
+    })
+    .await
+    .expect("Timeout waiting for HTTP/2 settings and keepalive ping");
+
+    assert_eq!(stream_window, Some(128 * 1024));
+    assert_eq!(connection_window, Some(256 * 1024));
+}
+
+#[test]
+fn test_http2_config_maps_zero_to_defaults() {
+    let config = Config::default();
+    assert_eq!(
+        config.http2.settings(),
+        Http2Settings {
+            keepalive_interval: None,
+            keepalive_timeout: Some(Duration::from_secs(20)),
+            initial_stream_window: None,
+            initial_connection_window: None,
+        }
+    );
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::connection_message::MessageType;
+use grpc_opizontas::registry::{ConnectionMessage, Pong};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::client::{
+    ConnectionState, GatewayClientConfig, ReverseConnectionClient, ReverseConnectionOptions,
+};
+use grpc_opizontas::services::connection::{ReverseConnectionConfig, ReverseConnectionManager};
+use grpc_opizontas::services::event::EventConfig;
+
+const TOKEN: &str = "keepalive-token";
+
+async fn register(
+    manager: &ReverseConnectionManager,
+    connection_id: &str,
+) -> mpsc::Receiver<ConnectionMessage> {
+    let (request_tx, request_rx) = mpsc::channel(64);
+    manager
+        .register_connection(
+            connection_id.to_string(),
+            vec![format!("{connection_id}.Service")],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+    request_rx
+}
+
+#[test]
+fn test_ping_interval_stays_below_heartbeat_timeout() {
+    let config = ReverseConnectionConfig {
+        heartbeat_timeout: Duration::from_secs(90),
+        ping_interval: Duration::from_secs(60),
+        ..Default::default()
+    };
+    assert_eq!(
+        config.effective_ping_interval(),
+        Some(Duration::from_secs(30))
+    );
+
+    let config = ReverseConnectionConfig {
+        ping_interval: Duration::from_secs(10),
+        ..Default::default()
+    };
+    assert_eq!(
+        config.effective_ping_interval(),
+        Some(Duration::from_secs(10))
+    );
+
+    let config = ReverseConnectionConfig {
+        ping_interval: Duration::ZERO,
+        ..Default::default()
+    };
+    assert_eq!(config.effective_ping_interval(), None);
+}
+
+#[tokio::test]
+async fn test_pong_keeps_connection_alive_without_heartbeats() {
+    let config = ReverseConnectionConfig {
+        heartbeat_timeout: Duration::from_millis(400),
+        cleanup_interval: Duration::from_millis(50),
+        ping_interval: Duration::from_millis(50),
+        ..Default::default()
+    };
+    let manager = ReverseConnectionManager::new(config, None, EventConfig::default());
+
+    // 响应方对每个 Ping 回复 Pong，但从不发送心跳
+    let mut responsive_rx = register(&manager, "responsive").await;
+    let responder = manager.clone();
+    tokio::spawn(async move {
+        while let Some(message) = responsive_rx.recv().await {
+            if let Some(MessageType::Ping(ping)) = message.message_type {
+                responder
+                    .handle_pong("responsive", Pong { nonce: ping.nonce })
+                    .await;
+            }
+        }
+    });
+
+    // 无响应方能收到 Ping，但不回复
+    let mut silent_rx = register(&manager, "silent").await;
+    let message = timeout(Duration::from_secs(1), silent_rx.recv())
+        .await
+        .expect("Timeout waiting for ping")
+        .expect("Request channel closed");
+    let Some(MessageType::Ping(ping)) = message.message_type else {
+        panic!("Expected a keepalive ping, got {message:?}");
+    };
+    assert!(ping.nonce > 0);
+
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    assert!(manager.has_reverse_connection("responsive.Service"));
+    assert!(!manager.has_reverse_connection("silent.Service"));
+    assert_eq!(manager.get_stats().await.active_connections, 1);
+}
+
+#[tokio::test]
+async fn test_client_answers_gateway_pings() {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.reverse_connection.heartbeat_timeout = 2;
+    config.reverse_connection.cleanup_interval = 1;
+    config.reverse_connection.ping_interval = 1;
+    let registry_service = Arc::new(MyRegistryService::new(config));
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+
+    let addr = common::serve_registry(registry_service.clone()).await;
+
+    // 客户端心跳间隔远大于心跳超时，只靠回复 Ping 保持存活
+    let client = ReverseConnectionClient::spawn(
+        GatewayClientConfig {
+            gateway_address: format!("http://{addr}"),
+            api_key: TOKEN.to_string(),
+            ..Default::default()
+        },
+        ReverseConnectionOptions {
+            services: vec!["keepalive.OrderService".to_string()],
+            heartbeat_interval: Duration::from_secs(3600),
+            ..Default::default()
+        },
+    );
+    let handle = client.handle();
+    timeout(
+        Duration::from_secs(5),
+        handle.wait_for_state(|state| matches!(state, ConnectionState::Connected { .. })),
+    )
+    .await
+    .expect("Timeout waiting for connection");
+
+    tokio::time::sleep(Duration::from_millis(3500)).await;
+    assert!(reverse_manager.has_reverse_connection("keepalive.OrderService"));
+    handle.close();
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tonic::Request;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::router::DynamicRouter;
+
+const TOKEN: &str = "label-test-token";
+const SERVICE: &str = "LabelService";
+
+// 启动在响应头 x-backend-version 中返回自身版本的后端
+async fn start_backend(version: &'static str) -> SocketAddr {
+    let (listener, addr) = common::bind().await;
+    let backend = tower::service_fn(move |_req: http::Request<_>| async move {
+        let response = common::grpc_ok()
+            .header("x-backend-version", version)
+            .body(Full::new(Bytes::new()))
+            .expect("Failed to build response");
+        Ok::<_, Infallible>(response)
+    });
+
+    common::serve_fallback(listener, backend);
+    addr
+}
+
+// 注册 v1 和 v2 两个实例，返回对应的路由器
+async fn setup(label_route_fallback: bool) -> DynamicRouter {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.router.label_route_fallback = label_route_fallback;
+
+    let registry_service = MyRegistryService::new(config.clone());
+    for version in ["v1", "v2"] {
+        let addr = start_backend(version).await;
+        registry_service
+            .register(Request::new(RegisterRequest {
+                api_key: TOKEN.to_string(),
+                address: format!("http://{addr}"),
+                services: vec![SERVICE.to_string()],
+                metadata: HashMap::from([("version".to_string(), version.to_string())]),
+                ..Default::default()
+            }))
+            .await
+            .expect("Failed to register service");
+    }
+
+    let registry = registry_service.registry.clone();
+    let instance = registry.get(SERVICE).expect("Service not registered");
+    assert!(
+        instance
+            .iter()
+            .all(|entry| entry.value().metadata.contains_key("version"))
+    );
+    drop(instance);
+
+    DynamicRouter::new(
+        registry,
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router")
+}
+
+async fn call(router: &DynamicRouter, version: Option<&str>) -> http::Response<impl Sized> {
+    let mut builder = http::Request::builder()
+        .method("POST")
+        .uri(format!("/label.{SERVICE}/Call"))
+        .header("content-type", "application/grpc");
+    if let Some(version) = version {
+        builder = builder.header("x-route-version", version);
+    }
+    let request = builder.body(Full::new(Bytes::new())).unwrap();
+    router.clone().oneshot(request).await.unwrap()
+}
+
+fn header<'a, B>(response: &'a http::Response<B>, name: &str) -> Option<&'a str> {
+    response.headers().get(name).and_then(|v| v.to_str().ok())
+}
+
+#[tokio::test]
+async fn test_route_header_selects_matching_instance() {
+    let router = setup(false).await;
+
+    for version in ["v1", "v2"] {
+        for _ in 0..3 {
+            let response = call(&router, Some(version)).await;
+            assert_eq!(header(&response, "x-backend-version"), Some(version));
+        }
+    }
+
+    // 不带标签时仍可路由到任意健康实例
+    let response = call(&router, None).await;
+    assert!(header(&response, "x-backend-version").is_some());
+}
+
+#[tokio::test]
+async fn test_unmatched_label_returns_not_found_without_fallback() {
+    let router = setup(false).await;
+
+    let response = call(&router, Some("v3")).await;
+    assert_eq!(header(&response, "grpc-status"), Some("5"));
+    assert!(header(&response, "x-backend-version").is_none());
+}
+
+#[tokio::test]
+async fn test_unmatched_label_falls_back_to_any_instance() {
+    let router = setup(true).await;
+
+    let response = call(&router, Some("v3")).await;
+    assert_eq!(header(&response, "grpc-status"), Some("0"));
+    assert!(header(&response, "x-backend-version").is_some());
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+use grpc_opizontas::registry::{
+    ConnectionMessage, ForwardResponse, connection_message::MessageType,
+};
+use grpc_opizontas::services::connection::{ReverseConnectionConfig, ReverseConnectionManager};
+use grpc_opizontas::services::event::EventConfig;
+use grpc_opizontas::services::router::load_balance::{self, InFlight, LoadBalanceStrategy};
+
+const SERVICE: &str = "balance.TestService";
+const METHOD: &str = "/balance.TestService/Call";
+
+// 按策略选择 picks 次，统计每个实例排在第一位的次数
+fn pick_counts(
+    strategy: LoadBalanceStrategy,
+    instances: &[(&str, u32)],
+    in_flight: &InFlight,
+    picks: usize,
+) -> HashMap<String, usize> {
+    let cursor = AtomicUsize::new(0);
+    let mut counts = HashMap::new();
+    for _ in 0..picks {
+        let mut candidates = instances.to_vec();
+        load_balance::order(
+            strategy,
+            &mut candidates,
+            &cursor,
+            in_flight,
+            |&(key, weight)| (key, weight),
+        );
+        *counts.entry(candidates[0].0.to_string()).or_insert(0) += 1;
+    }
+    counts
+}
+
+async fn register(
+    manager: &ReverseConnectionManager,
+    connection_id: &str,
+) -> mpsc::Receiver<ConnectionMessage> {
+    let (request_tx, request_rx) = mpsc::channel(16);
+    manager
+        .register_connection(
+            connection_id.to_string(),
+            vec![SERVICE.to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+    request_rx
+}
+
+#[test]
+fn test_round_robin_distributes_evenly() {
+    let instances = [("a", 1), ("b", 1), ("c", 1)];
+    let counts = pick_counts(
+        LoadBalanceStrategy::RoundRobin,
+        &instances,
+        &InFlight::default(),
+        300,
+    );
+    for (key, _) in instances {
+        assert_eq!(counts.get(key), Some(&100), "{key}: {counts:?}");
+    }
+}
+
+#[test]
+fn test_random_distribution_follows_weights() {
+    let instances = [("a", 1), ("b", 1), ("c", 2)];
+    let counts = pick_counts(
+        LoadBalanceStrategy::Random,
+        &instances,
+        &InFlight::default(),
+        4000,
+    );
+
+    // 权重 1/1/2 对应期望 1000/1000/2000 次，允许 15% 误差
+    for (key, expected) in [("a", 1000), ("b", 1000), ("c", 2000)] {
+        let actual = counts.get(key).copied().unwrap_or(0);
+        assert!(
+            actual.abs_diff(expected) <= expected * 15 / 100,
+            "{key}: expected ~{expected} picks, got {actual}"
+        );
+    }
+}
+
+#[test]
+fn test_least_connections_prefers_idle_instance() {
+    let in_flight = Arc::new(InFlight::default());
+    let instances = [("a", 1), ("b", 1), ("c", 1)];
+
+    let busy_a = in_flight.acquire("a");
+    let _busy_b = in_flight.acquire("b");
+    let _busy_b_again = in_flight.acquire("b");
+    let counts = pick_counts(
+        LoadBalanceStrategy::LeastConnections,
+        &instances,
+        &in_flight,
+        30,
+    );
+    assert_eq!(counts.get("c"), Some(&30), "{counts:?}");
+
+    // 请求结束后计数随守卫释放
+    drop(busy_a);
+    assert_eq!(in_flight.count("a"), 0);
+    assert_eq!(in_flight.count("b"), 2);
+
+    // 进行中请求数相同的实例轮流被选中
+    let _busy_c = in_flight.acquire("c");
+    let _busy_c_again = in_flight.acquire("c");
+    let counts = pick_counts(
+        LoadBalanceStrategy::LeastConnections,
+        &[("a", 1), ("c", 1)],
+        &in_flight,
+        10,
+    );
+    assert_eq!(counts.get("a"), Some(&10), "{counts:?}");
+}
+
+#[test]
+fn test_first_healthy_always_picks_first_instance() {
+    let counts = pick_counts(
+        LoadBalanceStrategy::FirstHealthy,
+        &[("c", 1), ("a", 1), ("b", 1)],
+        &InFlight::default(),
+        20,
+    );
+    assert_eq!(counts.get("a"), Some(&20), "{counts:?}");
+}
+
+#[test]
+fn test_strategy_deserializes_from_snake_case() {
+    let strategy: LoadBalanceStrategy =
+        serde_json::from_str("\"least_connections\"").expect("Failed to parse strategy");
+    assert_eq!(strategy, LoadBalanceStrategy::LeastConnections);
+}
+
+#[tokio::test]
+async fn test_reverse_least_connections_skips_busy_connection() {
+    let config = ReverseConnectionConfig {
+        load_balance_strategy: LoadBalanceStrategy::LeastConnections,
+        request_timeout: Duration::from_secs(5),
+        ..Default::default()
+    };
+    let manager = ReverseConnectionManager::new(config, None, EventConfig::default());
+    let mut rx_a = register(&manager, "conn-a").await;
+    let mut rx_b = register(&manager, "conn-b").await;
+
+    let pending = {
+        let manager = manager.clone();
+        tokio::spawn(async move {
+            manager
+                .send_request(SERVICE, METHOD, HashMap::new(), Vec::new())
+                .await
+        })
+    };
+
+    // 等待请求发往其中一个连接
+    let (busy, idle, message) = tokio::select! {
+        message = rx_a.recv() => ("conn-a", "conn-b", message),
+        message = rx_b.recv() => ("conn-b", "conn-a", message),
+    };
+    let Some(MessageType::Request(request)) = message.and_then(|m| m.message_type) else {
+        panic!("Expected forward request");
+    };
+    timeout(Duration::from_secs(1), async {
+        while manager.in_flight_requests(busy) == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("Request was not counted as in flight");
+    assert_eq!(manager.in_flight_requests(busy), 1);
+    assert_eq!(manager.in_flight_requests(idle), 0);
+
+    for _ in 0..10 {
+        let conn = manager
+            .get_connection_for_service(SERVICE)
+            .expect("Expected an active connection");
+        assert_eq!(conn.connection_id, idle);
+    }
+
+    manager
+        .handle_response(ForwardResponse {
+            request_id: request.request_id,
+            status_code: 200,
+            ..Default::default()
+        })
+        .await;
+    timeout(Duration::from_secs(1), pending)
+        .await
+        .expect("Timeout waiting for response")
+        .expect("Request task panicked")
+        .expect("Request failed");
+    assert_eq!(manager.in_flight_requests(busy), 0);
+}
+
+#[tokio::test]
+async fn test_strategy_can_be_swapped_at_runtime() {
+    let manager = ReverseConnectionManager::default();
+    let _rx_a = register(&manager, "conn-a").await;
+    let _rx_b = register(&manager, "conn-b").await;
+    assert_eq!(
+        manager.load_balance_strategy(),
+        LoadBalanceStrategy::RoundRobin
+    );
+
+    let mut picked: Vec<String> = (0..4)
+        .filter_map(|_| manager.get_connection_for_service(SERVICE))
+        .map(|conn| conn.connection_id)
+        .collect();
+    picked.sort();
+    assert_eq!(picked, ["conn-a", "conn-a", "conn-b", "conn-b"]);
+
+    manager.set_load_balance_strategy(LoadBalanceStrategy::FirstHealthy);
+    for _ in 0..4 {
+        let conn = manager
+            .get_connection_for_service(SERVICE)
+            .expect("Expected an active connection");
+        assert_eq!(conn.connection_id, "conn-a");
+    }
+}
@@ -0,0 +1,75 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use grpc_opizontas::config::{Config, LogFormat, ServerConfig};
+use grpc_opizontas::logging;
+
+// 收集日志输出的缓冲区
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_log_format_defaults_to_text() {
+    let server: ServerConfig = toml::from_str(
+        r#"
+        address = "0.0.0.0:50051"
+        log_level = "info"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(server.log_format, LogFormat::Text);
+
+    let server: ServerConfig = toml::from_str(
+        r#"
+        address = "0.0.0.0:50051"
+        log_level = "info"
+        log_format = "json"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(server.log_format, LogFormat::Json);
+}
+
+#[test]
+fn test_json_subscriber_emits_parseable_json() {
+    let mut config = Config::default();
+    config.server.log_format = LogFormat::Json;
+    config.server.log_level = "info".to_string();
+
+    let buffer = SharedBuffer::default();
+    let writer = buffer.clone();
+    let subscriber = logging::build_subscriber(&config.server, move || writer.clone());
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("forward", service = "post.PostService");
+        let _guard = span.enter();
+        tracing::info!(connection_id = "conn-1", "Request forwarded");
+    });
+
+    let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    let line = output.lines().next().expect("No log line written");
+    let event: serde_json::Value = serde_json::from_str(line).expect("Log line is not JSON");
+
+    assert_eq!(event["level"], "INFO");
+    assert_eq!(event["target"], "logging_test");
+    assert_eq!(event["fields"]["message"], "Request forwarded");
+    assert_eq!(event["fields"]["connection_id"], "conn-1");
+    assert_eq!(event["span"]["service"], "post.PostService");
+    assert_eq!(event["spans"][0]["name"], "forward");
+
+    // RFC3339 格式的 UTC 时间戳
+    let timestamp = event["timestamp"].as_str().expect("Missing timestamp");
+    assert!(timestamp.ends_with('Z'), "unexpected timestamp {timestamp}");
+    assert_eq!(&timestamp[4..5], "-");
+    assert_eq!(&timestamp[10..11], "T");
+}
@@ -0,0 +1,201 @@
+mod common;
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use http_body_util::Empty;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_client::RegistryServiceClient;
+
+use common::unused_addr;
+
+const TOKEN: &str = "metrics-test-token";
+
+// 启动一个对所有请求都在 delay 后返回 OK 的后端
+async fn start_backend(delay: Duration) -> SocketAddr {
+    let (listener, addr) = common::bind().await;
+    let ok = tower::service_fn(move |_req: http::Request<_>| async move {
+        tokio::time::sleep(delay).await;
+        let response = common::grpc_ok()
+            .body(Empty::<bytes::Bytes>::new())
+            .expect("Failed to build response");
+        Ok::<_, Infallible>(response)
+    });
+
+    common::serve_fallback(listener, ok);
+    addr
+}
+
+// 指标端点由网关自己绑定，等待它开始监听
+async fn wait_listening(addr: SocketAddr) {
+    for _ in 0..100 {
+        if TcpStream::connect(addr).await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("Metrics endpoint did not start listening on {addr}");
+}
+
+async fn scrape(addr: SocketAddr, path: &str) -> String {
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .expect("Failed to connect to metrics endpoint");
+    stream
+        .write_all(
+            format!("GET {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\r\n").as_bytes(),
+        )
+        .await
+        .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint_exports_gateway_metrics() {
+    let backend_addr = start_backend(Duration::ZERO).await;
+
+    let metrics_addr = unused_addr();
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.metrics.enabled = true;
+    config.metrics.address = metrics_addr.to_string();
+    // 关闭注册预热，第一次转发才建立连接
+    config.connection_pool.warmup_on_register = false;
+
+    let (gateway_addr, _shutdown_tx, _gateway) = common::spawn_gateway(config).await;
+    wait_listening(metrics_addr).await;
+
+    let channel = common::connect(gateway_addr).await;
+    RegistryServiceClient::new(channel.clone())
+        .register(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: format!("http://{backend_addr}"),
+            services: vec!["MetricsService".to_string()],
+            ..Default::default()
+        })
+        .await
+        .expect("Failed to register backend");
+
+    // 产生一些转发流量
+    for _ in 0..3 {
+        let request = http::Request::builder()
+            .method("POST")
+            .uri(format!("http://{gateway_addr}/metrics.MetricsService/Call"))
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .body(tonic::body::Body::empty())
+            .unwrap();
+        let response = channel.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), http::StatusCode::OK);
+    }
+
+    let response = scrape(metrics_addr, "/metrics").await;
+    assert!(response.starts_with("HTTP/1.1 200"));
+    for name in [
+        "gateway_reverse_connections_active 0",
+        "gateway_pending_requests 0",
+        "gateway_reverse_requests_total{outcome=\"forwarded\"} 0",
+        "gateway_registered_services 1",
+        "gateway_forwarded_requests_total{route=\"direct\",outcome=\"success\"} 3",
+        "gateway_forward_latency_seconds_count{route=\"direct\"} 3",
+        "gateway_forward_latency_seconds_bucket{route=\"direct\",le=\"+Inf\"} 3",
+        "gateway_client_pool_events_total{event=\"cache_hits\"} 2",
+        "gateway_client_pool_events_total{event=\"cache_misses\"} 1",
+        "gateway_client_pool_addresses 1",
+        "gateway_client_pool_channel_uses 3",
+        "gateway_client_pool_connection_age_seconds{stat=\"max\"}",
+        "gateway_client_pool_idle_seconds{stat=\"avg\"}",
+        "gateway_events_published_total",
+        "gateway_event_subscribers",
+    ] {
+        assert!(response.contains(name), "missing `{name}` in:\n{response}");
+    }
+
+    let not_found = scrape(metrics_addr, "/other").await;
+    assert!(not_found.starts_with("HTTP/1.1 404"));
+}
+
+#[tokio::test]
+async fn test_probes_report_not_ready_during_drain() {
+    let backend_addr = start_backend(Duration::from_millis(800)).await;
+
+    let metrics_addr = unused_addr();
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.server.shutdown_grace_period = 5;
+    config.metrics.enabled = true;
+    config.metrics.address = metrics_addr.to_string();
+
+    let (gateway_addr, shutdown_tx, gateway) = common::spawn_gateway(config).await;
+    wait_listening(metrics_addr).await;
+
+    // 正常运行时存活且就绪
+    assert!(
+        scrape(metrics_addr, "/livez")
+            .await
+            .starts_with("HTTP/1.1 200")
+    );
+    assert!(
+        scrape(metrics_addr, "/readyz")
+            .await
+            .starts_with("HTTP/1.1 200")
+    );
+
+    let channel = common::connect(gateway_addr).await;
+    RegistryServiceClient::new(channel.clone())
+        .register(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: format!("http://{backend_addr}"),
+            services: vec!["MetricsService".to_string()],
+            ..Default::default()
+        })
+        .await
+        .expect("Failed to register backend");
+
+    // 保持一个进行中的慢请求，让停机停留在排空阶段
+    let in_flight = tokio::spawn(async move {
+        let request = http::Request::builder()
+            .method("POST")
+            .uri(format!("http://{gateway_addr}/metrics.MetricsService/Slow"))
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .body(tonic::body::Body::empty())
+            .unwrap();
+        channel
+            .oneshot(request)
+            .await
+            .map(|response| response.status())
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    shutdown_tx.send(()).unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // 排空期间仍然存活，但不再就绪
+    assert!(
+        scrape(metrics_addr, "/livez")
+            .await
+            .starts_with("HTTP/1.1 200")
+    );
+    let readyz = scrape(metrics_addr, "/readyz").await;
+    assert!(readyz.starts_with("HTTP/1.1 503"), "{readyz}");
+
+    let status = in_flight
+        .await
+        .unwrap()
+        .expect("In-flight request should complete during drain");
+    assert_eq!(status, http::StatusCode::OK);
+    tokio::time::timeout(Duration::from_secs(5), gateway)
+        .await
+        .expect("Gateway did not stop")
+        .unwrap()
+        .expect("Gateway failed");
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use std::collections::HashMap;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tonic::Request;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::registry::{RegisterRequest, RegisterResponse};
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::{MyRegistryService, ServiceHealthStatus};
+
+const TOKEN: &str = "multi-instance-token";
+const SERVICE: &str = "ReplicaService";
+
+fn registry_service() -> MyRegistryService {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    MyRegistryService::new(config)
+}
+
+async fn register(
+    registry_service: &MyRegistryService,
+    address: &str,
+    instance_id: &str,
+) -> RegisterResponse {
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: address.to_string(),
+            services: vec![SERVICE.to_string()],
+            instance_id: instance_id.to_string(),
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service")
+        .into_inner()
+}
+
+fn instance_count(registry_service: &MyRegistryService) -> usize {
+    registry_service
+        .registry
+        .get(SERVICE)
+        .map(|instances| instances.len())
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn test_requests_are_spread_across_instances() {
+    let addrs = [
+        common::start_addr_backend().await,
+        common::start_addr_backend().await,
+    ];
+
+    let registry_service = registry_service();
+    for addr in addrs {
+        register(&registry_service, &format!("http://{addr}"), "").await;
+    }
+    assert_eq!(instance_count(&registry_service), 2);
+
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        registry_service.config.load().as_ref().clone(),
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    let mut hits: HashMap<String, usize> = HashMap::new();
+    for _ in 0..10 {
+        let request = http::Request::builder()
+            .method("POST")
+            .uri(format!("/replica.{SERVICE}/Call"))
+            .header("content-type", "application/grpc")
+            .body(Full::new(Bytes::new()))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let backend = response
+            .headers()
+            .get("x-backend")
+            .and_then(|v| v.to_str().ok())
+            .expect("Request was not forwarded")
+            .to_string();
+        *hits.entry(backend).or_default() += 1;
+    }
+
+    // 两个实例都收到请求，且大致均分
+    assert_eq!(hits.len(), 2, "{hits:?}");
+    assert!(hits.values().all(|&count| count >= 3), "{hits:?}");
+}
+
+#[tokio::test]
+async fn test_instance_id_identifies_replicas() {
+    let registry_service = registry_service();
+    let address = "http://127.0.0.1:50401";
+
+    // 没有携带实例 ID 时由网关生成，重复注册沿用同一个实例
+    let first = register(&registry_service, address, "").await;
+    assert!(!first.instance_id.is_empty());
+    let heartbeat = register(&registry_service, address, "").await;
+    assert_eq!(heartbeat.instance_id, first.instance_id);
+    assert_eq!(instance_count(&registry_service), 1);
+
+    // 同一地址上的多个副本以实例 ID 区分
+    let replica = register(&registry_service, address, "replica-2").await;
+    assert_eq!(replica.instance_id, "replica-2");
+    assert_eq!(instance_count(&registry_service), 2);
+    let replica = register(&registry_service, address, "replica-2").await;
+    assert_eq!(replica.instance_id, "replica-2");
+    assert_eq!(instance_count(&registry_service), 2);
+
+    let instances = registry_service.registry.get(SERVICE).unwrap().clone();
+    assert!(instances.contains_key(&first.instance_id));
+    assert!(instances.contains_key("replica-2"));
+}
+
+#[tokio::test]
+async fn test_same_service_from_two_addresses_keeps_both_instances() {
+    let registry_service = registry_service();
+    let first_address = "http://127.0.0.1:50411";
+    let second_address = "http://127.0.0.1:50412";
+
+    // 同名服务从不同地址注册时各自成为实例，互不覆盖
+    let first = register(&registry_service, first_address, "").await;
+    let second = register(&registry_service, second_address, "").await;
+    assert_ne!(first.instance_id, second.instance_id);
+    assert_eq!(instance_count(&registry_service), 2);
+
+    // 两个实例的心跳都不会覆盖对方
+    register(&registry_service, first_address, "").await;
+    register(&registry_service, second_address, "").await;
+    assert_eq!(instance_count(&registry_service), 2);
+
+    let instances = registry_service.registry.get(SERVICE).unwrap().clone();
+    for (instance_id, address) in [
+        (&first.instance_id, first_address),
+        (&second.instance_id, second_address),
+    ] {
+        let instance = instances.get(instance_id).expect("Instance was clobbered");
+        assert_eq!(instance.address, address);
+        assert_eq!(instance.health_status, ServiceHealthStatus::Healthy);
+    }
+}
+
+#[tokio::test]
+async fn test_instance_id_keeps_identity_across_address_change() {
+    let registry_service = registry_service();
+
+    // 携带实例 ID 的实例换了地址时更新原实例，而不是新增一个
+    register(
+        &registry_service,
+        "http://127.0.0.1:50421",
+        "moving-replica",
+    )
+    .await;
+    register(
+        &registry_service,
+        "http://127.0.0.1:50422",
+        "moving-replica",
+    )
+    .await;
+    assert_eq!(instance_count(&registry_service), 1);
+
+    let instances = registry_service.registry.get(SERVICE).unwrap().clone();
+    let instance = instances.get("moving-replica").unwrap();
+    assert_eq!(instance.address, "http://127.0.0.1:50422");
+}
@@ -0,0 +1,190 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rcgen::{BasicConstraints, CertificateParams, CertifiedIssuer, IsCa, KeyPair};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::{Identity, Server, ServerTlsConfig};
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::health::health_client::HealthClient;
+use grpc_opizontas::health::health_server::HealthServer;
+use grpc_opizontas::health::{HealthCheckRequest, health_check_response::ServingStatus};
+use grpc_opizontas::registry::registry_service_server::RegistryServiceServer;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::client::{ClientTlsSettings, GatewayClientConfig};
+use grpc_opizontas::services::client_manager::{ConnectionPoolConfig, GrpcClientManager};
+use grpc_opizontas::services::gateway_client::GatewayClient;
+use grpc_opizontas::services::health::HealthService;
+use grpc_opizontas::services::router::DynamicRouter;
+
+const TOKEN: &str = "outbound-tls-token";
+
+// 测试用 CA 及其签发的服务端证书
+struct TestPki {
+    ca_pem: String,
+    server_cert_pem: String,
+    server_key_pem: String,
+}
+
+fn generate_pki() -> TestPki {
+    let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = CertifiedIssuer::self_signed(ca_params, KeyPair::generate().unwrap()).unwrap();
+
+    let server_key = KeyPair::generate().unwrap();
+    let server_cert = CertificateParams::new(vec!["localhost".to_string()])
+        .unwrap()
+        .signed_by(&server_key, &ca)
+        .unwrap();
+
+    TestPki {
+        ca_pem: ca.pem(),
+        server_cert_pem: server_cert.pem(),
+        server_key_pem: server_key.serialize_pem(),
+    }
+}
+
+// 启动带 TLS 的注册服务和健康检查服务
+async fn start_tls_server(pki: &TestPki) -> SocketAddr {
+    let (listener, addr) = common::bind().await;
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    let registry_service = Arc::new(MyRegistryService::new(config));
+
+    let tls = ServerTlsConfig::new().identity(Identity::from_pem(
+        &pki.server_cert_pem,
+        &pki.server_key_pem,
+    ));
+    let server = Server::builder()
+        .tls_config(tls)
+        .expect("Invalid server TLS config")
+        .add_service(RegistryServiceServer::from_arc(registry_service.clone()))
+        .add_service(HealthServer::new(HealthService::new(registry_service)));
+
+    tokio::spawn(async move {
+        server
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .expect("TLS server failed");
+    });
+
+    addr
+}
+
+fn trusting(ca_pem: &str) -> ClientTlsSettings {
+    ClientTlsSettings {
+        ca_certificate: Some(ca_pem.as_bytes().to_vec()),
+        ..Default::default()
+    }
+}
+
+async fn gateway_client(address: String, tls: ClientTlsSettings) -> Result<GatewayClient, String> {
+    GatewayClient::new(GatewayClientConfig {
+        gateway_address: address,
+        api_key: TOKEN.to_string(),
+        connect_timeout: Duration::from_secs(2),
+        tls,
+        ..Default::default()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tokio::test]
+async fn test_gateway_client_tls_handshake() {
+    let pki = generate_pki();
+    let addr = start_tls_server(&pki).await;
+
+    let mut client = gateway_client(
+        format!("https://localhost:{}", addr.port()),
+        trusting(&pki.ca_pem),
+    )
+    .await
+    .expect("TLS handshake should succeed");
+
+    let services = client
+        .list_healthy_services()
+        .await
+        .expect("Request over TLS should succeed");
+    assert!(services.is_empty());
+}
+
+#[tokio::test]
+async fn test_gateway_client_rejects_unknown_ca() {
+    let pki = generate_pki();
+    let other_pki = generate_pki();
+    let addr = start_tls_server(&pki).await;
+
+    let result = gateway_client(
+        format!("https://localhost:{}", addr.port()),
+        trusting(&other_pki.ca_pem),
+    )
+    .await;
+    assert!(result.is_err(), "Handshake with an untrusted CA must fail");
+}
+
+#[tokio::test]
+async fn test_pooled_backend_tls_handshake() {
+    let pki = generate_pki();
+    let other_pki = generate_pki();
+    let addr = start_tls_server(&pki).await;
+
+    // https 地址自动启用 TLS
+    let manager = GrpcClientManager::new(ConnectionPoolConfig {
+        tls: trusting(&pki.ca_pem),
+        ..Default::default()
+    });
+    let channel = manager
+        .get_or_create_client(&format!("https://localhost:{}", addr.port()))
+        .await
+        .expect("Pooled TLS connection should succeed");
+    let status = HealthClient::new(channel)
+        .check(HealthCheckRequest::default())
+        .await
+        .expect("Health check over TLS should succeed")
+        .into_inner()
+        .status;
+    assert_eq!(status, ServingStatus::Serving as i32);
+
+    // 显式启用时 http 地址也使用 TLS
+    let forced = GrpcClientManager::new(ConnectionPoolConfig {
+        tls: ClientTlsSettings {
+            enabled: true,
+            ..trusting(&pki.ca_pem)
+        },
+        ..Default::default()
+    });
+    assert!(
+        forced
+            .get_or_create_client(&format!("http://localhost:{}", addr.port()))
+            .await
+            .is_ok()
+    );
+
+    // 不受信任的 CA 被拒绝
+    let untrusted = GrpcClientManager::new(ConnectionPoolConfig {
+        tls: trusting(&other_pki.ca_pem),
+        ..Default::default()
+    });
+    assert!(
+        untrusted
+            .get_or_create_client(&format!("https://localhost:{}", addr.port()))
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_unreadable_backend_ca_is_rejected() {
+    // 证书无法读取时创建路由器失败，而不是退回到默认的 TLS 设置
+    let mut config = Config::default();
+    config.connection_pool.tls_enabled = true;
+    config.connection_pool.tls_ca_path = Some("/nonexistent/backend-ca.pem".to_string());
+
+    assert!(DynamicRouter::connection_pool_config(&config).is_err());
+    let reverse_manager = MyRegistryService::new(config.clone()).reverse_connection_manager;
+    assert!(DynamicRouter::new(Default::default(), config, reverse_manager).is_err());
+}
@@ -0,0 +1,270 @@
+mod common;
+
+use std::convert::Infallible;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tonic::Request;
+use tonic::service::AxumBody;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::{Config, LogFormat};
+use grpc_opizontas::logging;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::registry::{ForwardResponse, RegisterRequest, connection_message::MessageType};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::router::payload_log::PAYLOAD_LOG_TARGET;
+
+const TOKEN: &str = "payload-token";
+const SECRET: &str = "Bearer very-secret-credential";
+const PREVIEW_BYTES: usize = 8;
+
+// 收集日志输出的缓冲区
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuffer {
+    fn output(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+
+    // target 为 payload 的日志记录
+    fn payload_records(&self) -> Vec<serde_json::Value> {
+        self.output()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .filter(|record| record["target"] == PAYLOAD_LOG_TARGET)
+            .collect()
+    }
+}
+
+// 启动一个读取完请求体后返回 32 字节响应体的后端
+async fn start_backend() -> String {
+    let (listener, addr) = common::bind().await;
+    let backend = tower::service_fn(|req: http::Request<AxumBody>| async move {
+        let _ = req.into_body().collect().await;
+        let response = common::grpc_ok()
+            .header("set-cookie", SECRET)
+            .body(Full::new(Bytes::from(vec![0xab; 32])))
+            .expect("Failed to build response");
+        Ok::<_, Infallible>(response)
+    });
+
+    common::serve_fallback(listener, backend);
+    format!("http://{addr}")
+}
+
+fn config(enabled: bool) -> Config {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.server.log_format = LogFormat::Json;
+    config.server.log_level = "info".to_string();
+    config.payload_log.enabled = enabled;
+    config.payload_log.max_payload_bytes = PREVIEW_BYTES;
+    config
+}
+
+fn request(path: &str) -> http::Request<Full<Bytes>> {
+    http::Request::builder()
+        .method("POST")
+        .uri(path)
+        .header("content-type", "application/grpc")
+        .header("authorization", SECRET)
+        .header("x-trace-tag", "visible")
+        .body(Full::new(Bytes::from(vec![0x01; 64])))
+        .unwrap()
+}
+
+// 检查请求头、响应头和两个方向的消息体日志
+fn assert_logged(buffer: &SharedBuffer, response_size: u64) {
+    let output = buffer.output();
+    assert!(
+        !output.contains(SECRET),
+        "Redacted header value leaked:\n{output}"
+    );
+
+    let records = buffer.payload_records();
+    let find = |message: &str, direction: &str| {
+        records
+            .iter()
+            .find(|r| r["fields"]["message"] == message && r["fields"]["direction"] == direction)
+            .unwrap_or_else(|| panic!("Missing {direction} {message} record:\n{output}"))
+    };
+
+    let request = find("Request", "request");
+    assert_eq!(
+        request["fields"]["method_path"],
+        "/payload.PayloadService/Call"
+    );
+    let headers = request["fields"]["headers"].as_str().unwrap();
+    assert!(headers.contains("authorization: [REDACTED]"), "{headers}");
+    assert!(headers.contains("x-trace-tag: visible"), "{headers}");
+
+    let response = find("Response", "response");
+    assert_eq!(response["fields"]["status"], 200);
+
+    for (direction, size) in [("request", 64), ("response", response_size)] {
+        let payload = find("Payload", direction);
+        assert_eq!(payload["fields"]["size"], size);
+        assert_eq!(payload["fields"]["truncated"], true);
+        assert_eq!(
+            payload["fields"]["preview"].as_str().unwrap().len(),
+            PREVIEW_BYTES * 2,
+            "Hex preview should be truncated to {PREVIEW_BYTES} bytes"
+        );
+    }
+    assert_eq!(
+        find("Payload", "request")["fields"]["preview"],
+        "01".repeat(PREVIEW_BYTES)
+    );
+}
+
+#[tokio::test]
+async fn test_direct_forwarding_logs_redacted_headers_and_truncated_payload() {
+    let backend = start_backend().await;
+    let config = config(true);
+    let buffer = SharedBuffer::default();
+    let writer = buffer.clone();
+    let subscriber = logging::build_subscriber(&config.server, move || writer.clone());
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let registry_service = MyRegistryService::new(config.clone());
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: backend,
+            services: vec!["PayloadService".to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    let response = router
+        .oneshot(request("/payload.PayloadService/Call"))
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body.len(), 32);
+
+    assert_logged(&buffer, 32);
+}
+
+#[tokio::test]
+async fn test_reverse_forwarding_logs_redacted_headers_and_truncated_payload() {
+    let config = config(true);
+    let buffer = SharedBuffer::default();
+    let writer = buffer.clone();
+    let subscriber = logging::build_subscriber(&config.server, move || writer.clone());
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "payload-conn".to_string(),
+            vec!["PayloadService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    let forwarding = tokio::spawn(router.oneshot(request("/payload.PayloadService/Call")));
+    let message = timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for forwarded request")
+        .expect("Request channel closed");
+    let Some(MessageType::Request(forward_request)) = message.message_type else {
+        panic!("Expected a forwarded request");
+    };
+    // 转发给微服务的请求头保持原样，只有日志中被脱敏
+    assert_eq!(
+        forward_request
+            .headers
+            .get("authorization")
+            .map(String::as_str),
+        Some(SECRET)
+    );
+
+    reverse_manager
+        .handle_response(ForwardResponse {
+            request_id: forward_request.request_id,
+            status_code: 0,
+            payload: vec![0xcd; 40],
+            headers: [("set-cookie".to_string(), SECRET.to_string())].into(),
+            ..Default::default()
+        })
+        .await;
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Timeout waiting for response")
+        .expect("Router task panicked")
+        .expect("Router returned an error");
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+
+    assert_logged(&buffer, body.len() as u64);
+}
+
+#[tokio::test]
+async fn test_payload_log_is_disabled_by_default() {
+    let backend = start_backend().await;
+    let config = config(false);
+    let buffer = SharedBuffer::default();
+    let writer = buffer.clone();
+    let subscriber = logging::build_subscriber(&config.server, move || writer.clone());
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let registry_service = MyRegistryService::new(config.clone());
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: backend,
+            services: vec!["PayloadService".to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+    let response = router
+        .oneshot(request("/payload.PayloadService/Call"))
+        .await
+        .unwrap();
+    response.into_body().collect().await.unwrap();
+
+    assert!(buffer.payload_records().is_empty());
+    assert!(!Config::default().payload_log.enabled);
+}
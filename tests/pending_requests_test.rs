@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+use grpc_opizontas::registry::{
+    ConnectionMessage, ForwardResponse, connection_message::MessageType,
+};
+use grpc_opizontas::services::connection::{ReverseConnectionConfig, ReverseConnectionManager};
+use grpc_opizontas::services::event::EventConfig;
+
+const SERVICE: &str = "pending.QueryService";
+const METHOD: &str = "/pending.QueryService/Query";
+
+async fn setup(
+    max_pending_requests: usize,
+) -> (ReverseConnectionManager, mpsc::Receiver<ConnectionMessage>) {
+    let config = ReverseConnectionConfig {
+        max_pending_requests,
+        request_timeout: Duration::from_secs(5),
+        ..Default::default()
+    };
+    let manager = ReverseConnectionManager::new(config, None, EventConfig::default());
+    let (request_tx, request_rx) = mpsc::channel(1024);
+    manager
+        .register_connection(
+            "pending-conn".to_string(),
+            vec![SERVICE.to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+    (manager, request_rx)
+}
+
+async fn next_request_id(request_rx: &mut mpsc::Receiver<ConnectionMessage>) -> String {
+    let message = timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for request")
+        .expect("Request channel closed");
+    let Some(MessageType::Request(request)) = message.message_type else {
+        panic!("Expected forward request");
+    };
+    request.request_id
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_capacity_is_exact_under_concurrent_requests() {
+    let (manager, mut request_rx) = setup(8).await;
+
+    let tasks: Vec<_> = (0..64)
+        .map(|_| {
+            let manager = manager.clone();
+            tokio::spawn(async move {
+                manager
+                    .send_request(SERVICE, METHOD, HashMap::new(), Vec::new())
+                    .await
+            })
+        })
+        .collect();
+
+    // 恰好 8 个请求登记成功并发往微服务
+    let mut request_ids = Vec::new();
+    for _ in 0..8 {
+        request_ids.push(next_request_id(&mut request_rx).await);
+    }
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(request_rx.try_recv().is_err());
+    assert_eq!(manager.pending_request_count().await, 8);
+
+    for request_id in request_ids {
+        manager
+            .handle_response(ForwardResponse {
+                request_id,
+                status_code: 200,
+                ..Default::default()
+            })
+            .await;
+    }
+
+    let mut succeeded = 0;
+    for task in tasks {
+        match task.await.expect("Request task panicked") {
+            Ok(_) => succeeded += 1,
+            Err(e) => assert_eq!(e, "Too many pending requests"),
+        }
+    }
+    assert_eq!(succeeded, 8);
+    assert_eq!(manager.pending_request_count().await, 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_request_response_cycles_leave_no_entries() {
+    let (manager, mut request_rx) = setup(64).await;
+
+    // 模拟微服务：收到请求立即响应
+    let responder = tokio::spawn({
+        let manager = manager.clone();
+        async move {
+            while let Some(message) = request_rx.recv().await {
+                if let Some(MessageType::Request(request)) = message.message_type {
+                    let manager = manager.clone();
+                    tokio::spawn(async move {
+                        manager
+                            .handle_response(ForwardResponse {
+                                request_id: request.request_id,
+                                status_code: 200,
+                                payload: request.payload,
+                                ..Default::default()
+                            })
+                            .await;
+                    });
+                }
+            }
+        }
+    });
+
+    let completed = Arc::new(AtomicUsize::new(0));
+    let workers: Vec<_> = (0..16)
+        .map(|worker| {
+            let manager = manager.clone();
+            let completed = completed.clone();
+            tokio::spawn(async move {
+                for round in 0..100 {
+                    let payload = format!("{worker}-{round}").into_bytes();
+                    let response = manager
+                        .send_request(SERVICE, METHOD, HashMap::new(), payload.clone())
+                        .await
+                        .expect("Request failed");
+                    assert_eq!(response.payload, payload);
+                    completed.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+        })
+        .collect();
+
+    timeout(Duration::from_secs(10), async {
+        for worker in workers {
+            worker.await.expect("Worker panicked");
+        }
+    })
+    .await
+    .expect("Request cycles did not finish in time");
+
+    assert_eq!(completed.load(Ordering::Relaxed), 1600);
+    assert_eq!(manager.pending_request_count().await, 0);
+    assert_eq!(manager.streaming_response_count().await, 0);
+    responder.abort();
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use std::time::Duration;
+
+use tokio::time::timeout;
+use tokio_util::sync::CancellationToken;
+use tonic::Request;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::client_manager::{ConnectionPoolConfig, GrpcClientManager};
+
+use common::unused_addr;
+
+const TOKEN: &str = "warmup-token";
+
+#[tokio::test]
+async fn test_warmed_address_is_a_cache_hit() {
+    let manager = GrpcClientManager::default();
+    let address = format!("http://{}", common::start_health_backend().await);
+
+    assert_eq!(manager.warmup(std::slice::from_ref(&address)).await, 1);
+    // 已缓存的地址不会重复预热
+    assert_eq!(manager.warmup(std::slice::from_ref(&address)).await, 0);
+
+    manager
+        .get_or_create_client(&address)
+        .await
+        .expect("Failed to get warmed client");
+    let stats = manager.get_stats();
+    assert_eq!(stats.get("connections_warmed"), Some(&1));
+    assert_eq!(stats.get("cache_hits"), Some(&1));
+    assert_eq!(stats.get("cache_misses"), None);
+}
+
+#[tokio::test]
+async fn test_warmup_respects_max_connections() {
+    let manager = GrpcClientManager::new(ConnectionPoolConfig {
+        max_connections: 2,
+        ..Default::default()
+    });
+    let existing = format!("http://{}", common::start_health_backend().await);
+    manager
+        .get_or_create_client(&existing)
+        .await
+        .expect("Failed to connect");
+
+    let addresses = vec![
+        format!("http://{}", common::start_health_backend().await),
+        format!("http://{}", common::start_health_backend().await),
+    ];
+    assert_eq!(manager.warmup(&addresses).await, 1);
+    assert_eq!(manager.clients.len(), 2);
+    // 预热不会驱逐已有连接
+    assert!(manager.clients.contains_key(&existing));
+
+    // 连接失败的地址被跳过，也不计入熔断
+    let unreachable = format!("http://{}", unused_addr());
+    let manager = GrpcClientManager::default();
+    assert_eq!(manager.warmup(std::slice::from_ref(&unreachable)).await, 0);
+    assert!(manager.get_breaker_state(&unreachable).is_none());
+}
+
+#[tokio::test]
+async fn test_registration_triggers_warmup() {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    let registry_service = MyRegistryService::new(config);
+    let manager = GrpcClientManager::default();
+    let shutdown = CancellationToken::new();
+    manager.spawn_registration_warmup(registry_service.subscribe_registrations(), shutdown.clone());
+
+    let address = format!("http://{}", common::start_health_backend().await);
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: address.clone(),
+            services: vec!["warmup.OrderService".to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+
+    timeout(Duration::from_secs(2), async {
+        while !manager.clients.contains_key(&address) {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Registered address was not warmed up");
+    shutdown.cancel();
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::router::DynamicRouter;
+
+use common::grpc_status;
+
+const BURST: u32 = 3;
+
+// 请求的服务没有注册，未被限流的请求返回 NOT_FOUND，被限流的返回 RESOURCE_EXHAUSTED
+fn rate_limited_router(requests_per_second: f64) -> DynamicRouter {
+    let mut config = Config::default();
+    config.rate_limit.enabled = true;
+    config.rate_limit.requests_per_second = requests_per_second;
+    config.rate_limit.burst = BURST;
+
+    let registry_service = MyRegistryService::new(config.clone());
+    DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router")
+}
+
+async fn call(router: &DynamicRouter, token: &str) -> http::Response<impl http_body::Body> {
+    let request = http::Request::builder()
+        .method("POST")
+        .uri("/limit.LimitedService/Call")
+        .header("content-type", "application/grpc")
+        .header("x-api-key", token)
+        .body(Full::new(Bytes::new()))
+        .unwrap();
+    // 每次调用都克隆路由器，限流状态在克隆之间共享
+    router.clone().oneshot(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_burst_allowed_then_throttled() {
+    let router = rate_limited_router(1.0);
+
+    for _ in 0..BURST {
+        let response = call(&router, "client-a").await;
+        assert_eq!(grpc_status(&response), Some("5"));
+    }
+
+    let response = call(&router, "client-a").await;
+    assert_eq!(grpc_status(&response), Some("8"));
+    let retry_after = response
+        .headers()
+        .get("retry-after")
+        .and_then(|v| v.to_str().ok())
+        .expect("Throttled response should carry retry-after");
+    assert_eq!(retry_after, "1");
+    assert!(response.headers().contains_key("grpc-retry-pushback-ms"));
+
+    // 其他 token 使用独立的桶
+    let response = call(&router, "client-b").await;
+    assert_eq!(grpc_status(&response), Some("5"));
+}
+
+#[tokio::test]
+async fn test_bucket_refills_over_time() {
+    let router = rate_limited_router(20.0);
+
+    for _ in 0..BURST {
+        call(&router, "client-a").await;
+    }
+    let response = call(&router, "client-a").await;
+    assert_eq!(grpc_status(&response), Some("8"));
+
+    // 每秒补充 20 个令牌，100ms 后至少有一个可用
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let response = call(&router, "client-a").await;
+    assert_eq!(grpc_status(&response), Some("5"));
+}
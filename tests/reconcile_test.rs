@@ -0,0 +1,138 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use dashmap::DashMap;
+use tokio::sync::mpsc;
+use tonic::Request;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::services::connection::{
+    ReconcileReport, ReverseConnectionConfig, ReverseConnectionManager,
+};
+use grpc_opizontas::services::event::EventConfig;
+use grpc_opizontas::services::{
+    MyRegistryService, ServiceHealthStatus, ServiceInfo, ServiceRegistry,
+};
+
+const TOKEN: &str = "reconcile-token";
+const SERVICE: &str = "InventoryService";
+
+async fn register_instance(registry_service: &MyRegistryService, address: &str, instance_id: &str) {
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: address.to_string(),
+            services: vec![SERVICE.to_string()],
+            instance_id: instance_id.to_string(),
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+}
+
+fn instance_ids(registry: &ServiceRegistry) -> Vec<String> {
+    let mut ids: Vec<String> = registry
+        .get(SERVICE)
+        .map(|instances| instances.iter().map(|entry| entry.key().clone()).collect())
+        .unwrap_or_default();
+    ids.sort();
+    ids
+}
+
+#[tokio::test]
+async fn test_reconcile_removes_instance_of_closed_connection() {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.reverse_connection.reconcile_interval = 0;
+    let registry_service = MyRegistryService::new(config);
+    let manager = registry_service.reverse_connection_manager.clone();
+
+    let (request_tx, _request_rx) = mpsc::channel(16);
+    manager
+        .register_connection(
+            "inventory-conn".to_string(),
+            vec![SERVICE.to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+    register_instance(&registry_service, "http://127.0.0.1:1", "inventory-conn").await;
+    register_instance(&registry_service, "http://127.0.0.1:2", "direct-instance").await;
+
+    // 映射一致时没有需要修复的内容
+    assert_eq!(manager.reconcile(), ReconcileReport::default());
+
+    // 连接关闭后，微服务迟到的注册心跳重新登记了以连接 ID 为实例 ID 的实例
+    manager.unregister_connection("inventory-conn").await;
+    register_instance(&registry_service, "http://127.0.0.1:1", "inventory-conn").await;
+    assert_eq!(
+        instance_ids(&registry_service.registry),
+        ["direct-instance", "inventory-conn"]
+    );
+
+    let report = manager.reconcile();
+    assert_eq!(report.removed_registry_instances, 1);
+    assert_eq!(report.detached_pool_connections, 0);
+
+    // 正向注册的实例不受影响
+    assert_eq!(
+        instance_ids(&registry_service.registry),
+        ["direct-instance"]
+    );
+    let stats = manager.get_stats().await;
+    assert_eq!(stats.removed_registry_instances, 1);
+    assert_eq!(stats.detached_pool_connections, 0);
+
+    assert!(manager.reconcile().is_empty());
+    assert_eq!(manager.get_stats().await.removed_registry_instances, 1);
+}
+
+#[tokio::test]
+async fn test_periodic_reconcile_repairs_injected_registry_drift() {
+    let registry: ServiceRegistry = Arc::new(DashMap::new());
+    let config = ReverseConnectionConfig {
+        ping_interval: Duration::ZERO,
+        reconcile_interval: Some(Duration::from_millis(100)),
+        ..Default::default()
+    };
+    let manager =
+        ReverseConnectionManager::new(config, Some(registry.clone()), EventConfig::default());
+
+    let (request_tx, _request_rx) = mpsc::channel(16);
+    manager
+        .register_connection(
+            "stale-conn".to_string(),
+            vec![SERVICE.to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+    manager.unregister_connection("stale-conn").await;
+
+    // 直接写入注册表，制造连接已不存在但实例仍在的不一致
+    let instances = Arc::new(DashMap::new());
+    instances.insert(
+        "stale-conn".to_string(),
+        ServiceInfo {
+            address: "http://127.0.0.1:1".to_string(),
+            last_heartbeat: SystemTime::now(),
+            health_status: ServiceHealthStatus::Healthy,
+            metadata: Default::default(),
+        },
+    );
+    registry.insert(SERVICE.to_string(), instances);
+
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while registry.contains_key(SERVICE) {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Reconciler did not remove the stale registry instance");
+    assert_eq!(manager.get_stats().await.removed_registry_instances, 1);
+    manager.shutdown().await;
+}
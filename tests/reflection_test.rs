@@ -0,0 +1,92 @@
+mod common;
+
+use prost::Message;
+use tokio_stream::StreamExt;
+use tonic::transport::Channel;
+use tonic_reflection::pb::v1::server_reflection_client::ServerReflectionClient;
+use tonic_reflection::pb::v1::server_reflection_request::MessageRequest;
+use tonic_reflection::pb::v1::server_reflection_response::MessageResponse;
+use tonic_reflection::pb::v1::{ServerReflectionRequest, ServerReflectionResponse};
+
+use grpc_opizontas::config::Config;
+
+async fn reflect(
+    client: &mut ServerReflectionClient<Channel>,
+    request: MessageRequest,
+) -> MessageResponse {
+    let request = ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(request),
+    };
+    let mut responses = client
+        .server_reflection_info(tokio_stream::once(request))
+        .await
+        .expect("Reflection call failed")
+        .into_inner();
+    let response: ServerReflectionResponse = responses
+        .next()
+        .await
+        .expect("Reflection stream ended")
+        .expect("Reflection response failed");
+    response
+        .message_response
+        .expect("Reflection response is empty")
+}
+
+#[tokio::test]
+async fn test_reflection_lists_registry_service_methods() {
+    let config = Config::default();
+
+    let (gateway_addr, shutdown_tx, gateway) = common::spawn_gateway(config).await;
+
+    let channel = common::connect(gateway_addr).await;
+    let mut client = ServerReflectionClient::new(channel);
+
+    // 只列出网关自身的服务
+    let MessageResponse::ListServicesResponse(services) =
+        reflect(&mut client, MessageRequest::ListServices(String::new())).await
+    else {
+        panic!("Expected a list services response");
+    };
+    let names: Vec<_> = services.service.into_iter().map(|s| s.name).collect();
+    assert!(names.contains(&"registry.RegistryService".to_string()));
+    assert!(names.contains(&"grpc.health.v1.Health".to_string()));
+
+    let MessageResponse::FileDescriptorResponse(files) = reflect(
+        &mut client,
+        MessageRequest::FileContainingSymbol("registry.RegistryService".to_string()),
+    )
+    .await
+    else {
+        panic!("Expected a file descriptor response");
+    };
+    let methods: Vec<String> = files
+        .file_descriptor_proto
+        .iter()
+        .map(|bytes| {
+            prost_types::FileDescriptorProto::decode(bytes.as_slice())
+                .expect("Invalid file descriptor")
+        })
+        .flat_map(|file| file.service)
+        .filter(|service| service.name() == "RegistryService")
+        .flat_map(|service| service.method)
+        .map(|method| method.name().to_string())
+        .collect();
+    for method in [
+        "Register",
+        "EstablishConnection",
+        "ListServices",
+        "DrainInstance",
+    ] {
+        assert!(
+            methods.iter().any(|m| m == method),
+            "missing {method} in {methods:?}"
+        );
+    }
+
+    let _ = shutdown_tx.send(());
+    gateway
+        .await
+        .expect("Gateway task panicked")
+        .expect("Gateway failed");
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use std::time::Duration;
+
+use tokio::time::timeout;
+use tokio_stream::StreamExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::registry::{SERVICE_REGISTERED_EVENT, SERVICE_UNREGISTERED_EVENT};
+
+const TOKEN: &str = "registry-events-token";
+const ADDRESS: &str = "http://127.0.0.1:50200";
+
+fn registry_service() -> MyRegistryService {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    MyRegistryService::new(config)
+}
+
+#[tokio::test]
+async fn test_registration_publishes_lifecycle_event() {
+    let registry_service = registry_service();
+    let event_bus = registry_service
+        .reverse_connection_manager
+        .event_bus
+        .clone();
+
+    let mut registered = event_bus
+        .subscribe_event_type(SERVICE_REGISTERED_EVENT, "dashboard")
+        .expect("Failed to subscribe");
+    let mut unregistered = event_bus
+        .subscribe_event_type(SERVICE_UNREGISTERED_EVENT, "dashboard")
+        .expect("Failed to subscribe");
+
+    common::register(&registry_service, TOKEN, ADDRESS, &["events.TestService"]).await;
+
+    let event = timeout(Duration::from_secs(1), registered.next())
+        .await
+        .expect("Timeout waiting for registered event")
+        .expect("Stream ended unexpectedly")
+        .expect("Event stream error");
+    assert_eq!(event.event_type, SERVICE_REGISTERED_EVENT);
+    assert_eq!(
+        event.metadata.get("service_name").map(String::as_str),
+        Some("events.TestService")
+    );
+    assert_eq!(
+        event.metadata.get("address").map(String::as_str),
+        Some(ADDRESS)
+    );
+
+    assert!(registry_service.unregister_service("events.TestService"));
+
+    let event = timeout(Duration::from_secs(1), unregistered.next())
+        .await
+        .expect("Timeout waiting for unregistered event")
+        .expect("Stream ended unexpectedly")
+        .expect("Event stream error");
+    assert_eq!(
+        event.metadata.get("service_name").map(String::as_str),
+        Some("events.TestService")
+    );
+}
+
+#[tokio::test]
+async fn test_registration_without_subscribers_succeeds() {
+    let registry_service = registry_service();
+
+    // 没有订阅者时发布失败不影响注册
+    common::register(&registry_service, TOKEN, ADDRESS, &["events.Unobserved"]).await;
+    assert!(
+        registry_service
+            .get_healthy_services()
+            .contains_key("events.Unobserved")
+    );
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::mpsc;
+use tonic::Request;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::registry::{DrainInstanceRequest, RegisterRequest};
+use grpc_opizontas::services::{MyRegistryService, ServiceHealthStatus};
+
+const TOKEN: &str = "snapshot-test-token";
+const ADDRESS: &str = "http://127.0.0.1:50300";
+
+fn config_with_snapshot(path: &std::path::Path) -> Config {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.persistence.snapshot_path = Some(path.to_string_lossy().into_owned());
+    config.persistence.snapshot_grace_period = 10;
+    config
+}
+
+#[tokio::test]
+async fn test_forward_services_restored_from_snapshot() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = dir.path().join("registry.json");
+    let config = config_with_snapshot(&path);
+
+    let registry_service = MyRegistryService::new(config.clone());
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: ADDRESS.to_string(),
+            services: vec!["snap.PostService".to_string(), "snap.Draining".to_string()],
+            metadata: HashMap::from([("version".to_string(), "v2".to_string())]),
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+    registry_service
+        .drain_instance(Request::new(DrainInstanceRequest {
+            api_key: TOKEN.to_string(),
+            service_name: "snap.Draining".to_string(),
+            instance_id: ADDRESS.to_string(),
+            draining: true,
+        }))
+        .await
+        .expect("Failed to drain instance");
+
+    // 反向连接不写入快照
+    let (request_tx, _request_rx) = mpsc::channel(16);
+    registry_service
+        .reverse_connection_manager
+        .register_connection(
+            "snapshot-conn".to_string(),
+            vec!["snap.ReverseService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    registry_service
+        .snapshot()
+        .save(&path)
+        .expect("Failed to write snapshot");
+
+    // 新实例启动时自动加载快照
+    let restored = MyRegistryService::new(config);
+    let info = restored
+        .get_service_info("snap.PostService")
+        .expect("Forward service should be restored");
+    assert_eq!(info.address, ADDRESS);
+    assert_eq!(info.health_status, ServiceHealthStatus::Healthy);
+    assert_eq!(info.metadata.get("version").map(String::as_str), Some("v2"));
+    assert!(
+        restored
+            .get_healthy_services()
+            .contains_key("snap.PostService")
+    );
+
+    // 恢复的实例处于未验证状态，只剩宽限期可用
+    let age = SystemTime::now()
+        .duration_since(info.last_heartbeat)
+        .unwrap_or_default();
+    let remaining = restored
+        .config
+        .load()
+        .heartbeat_timeout()
+        .saturating_sub(age);
+    assert!(remaining <= Duration::from_secs(10));
+
+    assert_eq!(
+        restored
+            .get_service_info("snap.Draining")
+            .expect("Draining instance should be restored")
+            .health_status,
+        ServiceHealthStatus::Draining
+    );
+    assert!(restored.get_service_info("snap.ReverseService").is_none());
+}
+
+#[tokio::test]
+async fn test_missing_snapshot_starts_empty() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let registry_service =
+        MyRegistryService::new(config_with_snapshot(&dir.path().join("missing.json")));
+    assert!(registry_service.registry.is_empty());
+}
+
+#[tokio::test]
+async fn test_snapshot_task_stopped_before_final_save() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = dir.path().join("registry.json");
+    let mut config = config_with_snapshot(&path);
+    config.persistence.snapshot_interval = 1;
+
+    let registry_service = MyRegistryService::new(config);
+    registry_service.stop_snapshot_task().await;
+
+    // 停止后写入的最终快照不会再被定期任务覆盖，也不会留下临时文件
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: ADDRESS.to_string(),
+            services: vec!["snap.Final".to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+    registry_service
+        .snapshot()
+        .save(&path)
+        .expect("Failed to write snapshot");
+    let written = std::fs::read(&path).expect("Failed to read snapshot");
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(
+        std::fs::read(&path).expect("Failed to read snapshot"),
+        written
+    );
+    let entries = std::fs::read_dir(dir.path())
+        .expect("Failed to list snapshot dir")
+        .count();
+    assert_eq!(entries, 1);
+}
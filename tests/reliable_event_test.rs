@@ -0,0 +1,310 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time::timeout;
+use tokio_stream::StreamExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::{
+    EventMessage, SubscriptionRequest, connection_message::MessageType,
+    subscription_request::Action,
+};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::client::{
+    ConnectionState, GatewayClientConfig, ReverseConnectionClient, ReverseConnectionOptions,
+};
+use grpc_opizontas::services::event::{EventBus, EventConfig, EventError};
+
+const TOKEN: &str = "reliable-token";
+const EVENT_TYPE: &str = "order.paid";
+// 进程内订阅时代表调用方的标识，反向连接上为 token 的 subject
+const OWNER: &str = "worker-owner";
+
+fn event(event_id: &str) -> EventMessage {
+    EventMessage {
+        event_id: event_id.to_string(),
+        event_type: EVENT_TYPE.to_string(),
+        publisher_id: "test-publisher".to_string(),
+        payload: event_id.as_bytes().to_vec(),
+        ..Default::default()
+    }
+}
+
+fn reliable_subscription(subscriber_id: &str) -> SubscriptionRequest {
+    SubscriptionRequest {
+        action: Action::Subscribe as i32,
+        event_types: vec![EVENT_TYPE.to_string()],
+        subscriber_id: subscriber_id.to_string(),
+        metadata_filter: Default::default(),
+        reliable: true,
+    }
+}
+
+async fn next_event_id(
+    events: &mut (impl tokio_stream::Stream<Item = EventMessage> + Unpin),
+) -> String {
+    timeout(Duration::from_secs(1), events.next())
+        .await
+        .expect("Timeout waiting for event")
+        .expect("Event stream ended")
+        .event_id
+}
+
+#[tokio::test]
+async fn test_unacked_events_are_redelivered_in_new_session() {
+    let event_bus = EventBus::new(EventConfig {
+        reliable_delivery: true,
+        ..Default::default()
+    });
+    event_bus
+        .handle_subscription_request(reliable_subscription("worker"), OWNER)
+        .await
+        .unwrap();
+
+    // 订阅者不在线时事件同样进入队列
+    event_bus.publish_event(event("e1")).await.unwrap();
+    let mut first = Box::pin(event_bus.reliable_stream("worker", OWNER).unwrap());
+    assert_eq!(next_event_id(&mut first).await, "e1");
+
+    // 新会话开始后旧事件流结束，未确认的 e1 重新投递
+    event_bus.publish_event(event("e2")).await.unwrap();
+    let mut second = Box::pin(event_bus.reliable_stream("worker", OWNER).unwrap());
+    assert!(
+        timeout(Duration::from_secs(1), first.next())
+            .await
+            .expect("Old stream should end")
+            .is_none()
+    );
+    assert_eq!(next_event_id(&mut second).await, "e1");
+    assert_eq!(next_event_id(&mut second).await, "e2");
+
+    // 确认后不再重发
+    assert_eq!(
+        event_bus.ack_events("worker", OWNER, &["e1".to_string()]),
+        1
+    );
+    assert_eq!(event_bus.pending_reliable_events("worker"), 1);
+    let mut third = Box::pin(event_bus.reliable_stream("worker", OWNER).unwrap());
+    assert_eq!(next_event_id(&mut third).await, "e2");
+    assert_eq!(
+        event_bus.ack_events("worker", OWNER, &["e2".to_string()]),
+        1
+    );
+    assert!(
+        timeout(Duration::from_millis(200), third.next())
+            .await
+            .is_err()
+    );
+    assert_eq!(event_bus.get_stats().events_acked, 2);
+
+    // 取消订阅后队列删除，事件流结束
+    event_bus
+        .handle_subscription_request(
+            SubscriptionRequest {
+                action: Action::Unsubscribe as i32,
+                ..reliable_subscription("worker")
+            },
+            OWNER,
+        )
+        .await
+        .unwrap();
+    assert!(
+        timeout(Duration::from_secs(1), third.next())
+            .await
+            .expect("Stream should end after unsubscribe")
+            .is_none()
+    );
+    assert!(matches!(
+        event_bus.publish_event(event("e3")).await,
+        Err(EventError::NoSubscribers { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_reliable_subscriber_cannot_be_taken_over() {
+    let event_bus = EventBus::new(EventConfig {
+        reliable_delivery: true,
+        ..Default::default()
+    });
+    event_bus
+        .handle_subscription_request(reliable_subscription("worker"), OWNER)
+        .await
+        .unwrap();
+    event_bus.publish_event(event("e1")).await.unwrap();
+    let mut events = Box::pin(event_bus.reliable_stream("worker", OWNER).unwrap());
+    assert_eq!(next_event_id(&mut events).await, "e1");
+
+    // 其他调用方不能以同一 ID 重新订阅、接管投递、确认或取消订阅
+    assert!(matches!(
+        event_bus
+            .handle_subscription_request(reliable_subscription("worker"), "intruder")
+            .await,
+        Err(EventError::SubscriberNotOwned { .. })
+    ));
+    assert!(matches!(
+        event_bus.reliable_stream("worker", "intruder"),
+        Err(EventError::SubscriberNotOwned { .. })
+    ));
+    assert_eq!(
+        event_bus.ack_events("worker", "intruder", &["e1".to_string()]),
+        0
+    );
+    assert!(matches!(
+        event_bus
+            .handle_subscription_request(
+                SubscriptionRequest {
+                    action: Action::Unsubscribe as i32,
+                    ..reliable_subscription("worker")
+                },
+                "intruder"
+            )
+            .await,
+        Err(EventError::SubscriberNotOwned { .. })
+    ));
+    assert_eq!(event_bus.pending_reliable_events("worker"), 1);
+
+    // 原订阅者的事件流不受影响
+    event_bus.publish_event(event("e2")).await.unwrap();
+    assert_eq!(next_event_id(&mut events).await, "e2");
+}
+
+#[tokio::test]
+async fn test_reliable_queue_is_bounded() {
+    let event_bus = EventBus::new(EventConfig {
+        reliable_delivery: true,
+        reliable_queue_capacity: 2,
+        ..Default::default()
+    });
+    event_bus
+        .handle_subscription_request(reliable_subscription("worker"), OWNER)
+        .await
+        .unwrap();
+    for id in ["e1", "e2", "e3"] {
+        event_bus.publish_event(event(id)).await.unwrap();
+    }
+
+    // 最早的事件被丢弃并计入投递失败
+    assert_eq!(event_bus.pending_reliable_events("worker"), 2);
+    assert_eq!(event_bus.get_stats().delivery_failures, 1);
+    let mut events = Box::pin(event_bus.reliable_stream("worker", OWNER).unwrap());
+    assert_eq!(next_event_id(&mut events).await, "e2");
+    assert_eq!(next_event_id(&mut events).await, "e3");
+}
+
+#[tokio::test]
+async fn test_reliable_subscription_falls_back_when_disabled() {
+    let event_bus = EventBus::new(EventConfig::default());
+    event_bus
+        .handle_subscription_request(reliable_subscription("worker"), OWNER)
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        event_bus.reliable_stream("worker", OWNER),
+        Err(EventError::ReliableDeliveryDisabled)
+    ));
+    assert_eq!(
+        event_bus.get_subscriber_event_types("worker"),
+        vec![EVENT_TYPE.to_string()]
+    );
+    assert_eq!(event_bus.pending_reliable_events("worker"), 0);
+}
+
+async fn next_event(client: &mut ReverseConnectionClient) -> EventMessage {
+    timeout(Duration::from_secs(5), async {
+        loop {
+            match client.next_message().await {
+                Some(MessageType::Event(event)) => return event,
+                Some(_) => continue,
+                None => panic!("Reverse connection closed"),
+            }
+        }
+    })
+    .await
+    .expect("Timeout waiting for event")
+}
+
+#[tokio::test]
+async fn test_unacked_event_is_redelivered_after_reconnect() {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.event.reliable_delivery = true;
+    let registry_service = Arc::new(MyRegistryService::new(config));
+    let event_bus = registry_service
+        .reverse_connection_manager
+        .event_bus
+        .clone();
+    let gateway_addr = common::serve_registry(registry_service.clone()).await;
+    let proxy = common::KillableProxy::start(gateway_addr).await;
+
+    let mut client = ReverseConnectionClient::spawn(
+        GatewayClientConfig {
+            gateway_address: format!("http://{}", proxy.addr),
+            api_key: TOKEN.to_string(),
+            ..Default::default()
+        },
+        ReverseConnectionOptions {
+            services: vec!["reliable.Worker".to_string()],
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(1),
+            ..Default::default()
+        },
+    );
+    let handle = client.handle();
+    let state = timeout(
+        Duration::from_secs(5),
+        handle.wait_for_state(|state| matches!(state, ConnectionState::Connected { .. })),
+    )
+    .await
+    .expect("Timeout waiting for connection");
+    let ConnectionState::Connected { connection_id } = state else {
+        panic!("Unexpected state {state:?}");
+    };
+
+    handle
+        .subscribe_reliable(vec![EVENT_TYPE.to_string()], Default::default())
+        .await
+        .expect("Failed to subscribe");
+    timeout(Duration::from_secs(5), async {
+        while event_bus.publish_event(event("e1")).await.is_err() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Timeout waiting for reliable subscription");
+    let received = next_event(&mut client).await;
+    assert_eq!(received.event_id, "e1");
+
+    // 不确认就断开连接，事件留在网关的队列中
+    let mut state_changes = handle.state_changes();
+    proxy.kill_connections();
+    timeout(
+        Duration::from_secs(5),
+        state_changes.wait_for(|state| matches!(state, ConnectionState::Reconnecting { .. })),
+    )
+    .await
+    .expect("Timeout waiting for reconnecting state")
+    .expect("State channel closed");
+    assert_eq!(event_bus.pending_reliable_events(&connection_id), 1);
+
+    // 重连后恢复可靠订阅，未确认的事件重新投递
+    let redelivered = next_event(&mut client).await;
+    assert_eq!(redelivered.event_id, "e1");
+    assert_eq!(redelivered.payload, b"e1");
+
+    handle
+        .ack_events(vec![redelivered.event_id])
+        .await
+        .expect("Failed to ack");
+    timeout(Duration::from_secs(5), async {
+        while event_bus.pending_reliable_events(&connection_id) > 0 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Timeout waiting for ack");
+
+    handle.close();
+}
@@ -0,0 +1,192 @@
+mod common;
+
+use std::convert::Infallible;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tonic::Request;
+use tonic::service::AxumBody;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::registry::{ForwardResponse, RegisterRequest, connection_message::MessageType};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::router::context::REQUEST_ID_HEADER;
+
+const TOKEN: &str = "context-token";
+
+// 启动记录请求头 x-request-id 的后端
+async fn start_backend() -> (String, mpsc::UnboundedReceiver<Option<String>>) {
+    let (listener, addr) = common::bind().await;
+    let (id_tx, id_rx) = mpsc::unbounded_channel();
+    let backend = tower::service_fn(move |req: http::Request<AxumBody>| {
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let _ = id_tx.send(request_id);
+        async move {
+            let response = common::grpc_ok()
+                .body(Full::new(Bytes::new()))
+                .expect("Failed to build response");
+            Ok::<_, Infallible>(response)
+        }
+    });
+
+    common::serve_fallback(listener, backend);
+    (format!("http://{addr}"), id_rx)
+}
+
+fn router() -> DynamicRouter {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    let registry_service = MyRegistryService::new(config.clone());
+    DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router")
+}
+
+fn request(path: &str, request_id: Option<&str>) -> http::Request<Full<Bytes>> {
+    let mut builder = http::Request::builder()
+        .method("POST")
+        .uri(path)
+        .header("content-type", "application/grpc");
+    if let Some(request_id) = request_id {
+        builder = builder.header(REQUEST_ID_HEADER, request_id);
+    }
+    builder.body(Full::new(Bytes::new())).unwrap()
+}
+
+fn response_request_id<B>(response: &http::Response<B>) -> Option<String> {
+    response
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+#[tokio::test]
+async fn test_request_id_reaches_direct_backend_and_response() {
+    let (backend, mut backend_ids) = start_backend().await;
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    let registry_service = MyRegistryService::new(config.clone());
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: backend,
+            services: vec!["ContextService".to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    // 调用方传入的请求 ID 原样转发给后端并写回响应头
+    let response = router
+        .clone()
+        .oneshot(request("/context.ContextService/Get", Some("req-123")))
+        .await
+        .unwrap();
+    assert_eq!(response_request_id(&response).as_deref(), Some("req-123"));
+    let received = backend_ids.recv().await.expect("Backend not called");
+    assert_eq!(received.as_deref(), Some("req-123"));
+
+    // 没有传入时由网关生成，后端和调用方看到同一个 ID
+    let response = router
+        .oneshot(request("/context.ContextService/Get", None))
+        .await
+        .unwrap();
+    let generated = response_request_id(&response).expect("Missing generated request id");
+    assert!(!generated.is_empty());
+    let received = backend_ids.recv().await.expect("Backend not called");
+    assert_eq!(received, Some(generated));
+}
+
+#[tokio::test]
+async fn test_request_id_reaches_reverse_connection() {
+    let router = router();
+    let reverse_manager = router.reverse_manager.clone();
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "context-conn".to_string(),
+            vec!["ContextService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    let forwarding =
+        tokio::spawn(router.oneshot(request("/context.ContextService/Get", Some("req-reverse"))));
+    let message = timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for forwarded request")
+        .expect("Request channel closed");
+    let Some(MessageType::Request(forward_request)) = message.message_type else {
+        panic!("Expected a forwarded request");
+    };
+    assert_eq!(
+        forward_request
+            .headers
+            .get(REQUEST_ID_HEADER)
+            .map(String::as_str),
+        Some("req-reverse")
+    );
+
+    reverse_manager
+        .handle_response(ForwardResponse {
+            request_id: forward_request.request_id,
+            status_code: 0,
+            ..Default::default()
+        })
+        .await;
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Timeout waiting for response")
+        .expect("Router task panicked")
+        .expect("Router returned an error");
+    assert_eq!(
+        response_request_id(&response).as_deref(),
+        Some("req-reverse")
+    );
+}
+
+#[tokio::test]
+async fn test_error_response_carries_request_id() {
+    let router = router();
+
+    // 服务未注册，请求在转发前失败
+    let response = router
+        .clone()
+        .oneshot(request("/missing.MissingService/Get", Some("req-error")))
+        .await
+        .unwrap();
+    assert_eq!(
+        response
+            .headers()
+            .get("grpc-status")
+            .and_then(|v| v.to_str().ok()),
+        Some("5")
+    );
+    assert_eq!(response_request_id(&response).as_deref(), Some("req-error"));
+
+    // 无法解析服务名的路径同样返回请求 ID
+    let response = router.oneshot(request("/", None)).await.unwrap();
+    assert!(response_request_id(&response).is_some_and(|id| !id.is_empty()));
+}
@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use tokio::time::timeout;
+use tokio_stream::StreamExt;
+
+use grpc_opizontas::registry::{ForwardResponse, ResponseStreamInfo};
+use grpc_opizontas::services::connection::{REQUEST_DROPPED_EVENT, ReverseConnectionManager};
+
+#[tokio::test]
+async fn test_unknown_response_publishes_dropped_event() {
+    let manager = ReverseConnectionManager::default();
+    let mut dropped = manager
+        .event_bus
+        .subscribe_event_type(REQUEST_DROPPED_EVENT, "operator")
+        .expect("Failed to subscribe");
+
+    manager
+        .handle_response(ForwardResponse {
+            request_id: "ghost-request".to_string(),
+            status_code: 200,
+            ..Default::default()
+        })
+        .await;
+
+    let event = timeout(Duration::from_secs(1), dropped.next())
+        .await
+        .expect("Timeout waiting for dropped event")
+        .expect("Stream ended unexpectedly")
+        .expect("Event stream error");
+    assert_eq!(event.event_type, REQUEST_DROPPED_EVENT);
+    assert_eq!(event.publisher_id, "gateway");
+    assert_eq!(
+        event.metadata.get("request_id").map(String::as_str),
+        Some("ghost-request")
+    );
+    assert!(event.metadata.contains_key("reason"));
+
+    // 未知请求的流式数据块同样发布事件
+    manager
+        .handle_response(ForwardResponse {
+            request_id: "ghost-stream".to_string(),
+            status_code: 200,
+            response_stream_info: Some(ResponseStreamInfo {
+                is_streamed: true,
+                chunk_index: 0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .await;
+
+    let event = timeout(Duration::from_secs(1), dropped.next())
+        .await
+        .expect("Timeout waiting for dropped event")
+        .expect("Stream ended unexpectedly")
+        .expect("Event stream error");
+    assert_eq!(
+        event.metadata.get("request_id").map(String::as_str),
+        Some("ghost-stream")
+    );
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::router::DynamicRouter;
+
+use common::{grpc_request, grpc_status};
+
+#[tokio::test]
+async fn test_gateway_services_are_not_forwarded() {
+    let mut config = Config::default();
+    config.router.reserved_services = vec!["internal.*".to_string()];
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    // 微服务以网关自身服务的短名称和保留服务注册
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "reserved-conn".to_string(),
+            vec![
+                "RegistryService".to_string(),
+                "Health".to_string(),
+                "ServerReflection".to_string(),
+                "SecretService".to_string(),
+            ],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    for path in [
+        "/registry.RegistryService/Register",
+        "/grpc.health.v1.Health/Check",
+        "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+        "/internal.SecretService/Get",
+    ] {
+        let response = router
+            .clone()
+            .oneshot(grpc_request(path, Bytes::new()))
+            .await
+            .unwrap();
+        assert_eq!(grpc_status(&response), Some("12"), "path {path}");
+    }
+    assert!(
+        timeout(Duration::from_millis(100), request_rx.recv())
+            .await
+            .is_err(),
+        "Reserved paths must not be forwarded to the microservice"
+    );
+
+    // 同名服务在其他 package 下照常转发
+    let forwarding = tokio::spawn(router.oneshot(grpc_request(
+        "/other.RegistryService/Register",
+        Bytes::new(),
+    )));
+    timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Non-reserved path should be forwarded")
+        .expect("Request channel closed");
+    forwarding.abort();
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use bytes::Bytes;
+use http_body::Frame;
+use http_body_util::{BodyExt, StreamBody};
+use tokio::sync::oneshot;
+use tonic::transport::Channel;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_client::RegistryServiceClient;
+
+const TOKEN: &str = "response-buffering-token";
+const BUFFER_LIMIT: usize = 1024;
+
+// 启动一个按路径返回不同大小响应体的后端，状态放在 trailers 中
+async fn start_backend() -> SocketAddr {
+    let (listener, addr) = common::bind().await;
+    let backend = tower::service_fn(|req: http::Request<_>| async move {
+        let size = if req.uri().path().ends_with("/Large") {
+            4 * BUFFER_LIMIT
+        } else {
+            100
+        };
+        let mut trailers = http::HeaderMap::new();
+        trailers.insert("grpc-status", http::HeaderValue::from_static("0"));
+        trailers.insert("x-backend-trailer", http::HeaderValue::from_static("kept"));
+        let frames = [
+            Ok::<_, Infallible>(Frame::data(Bytes::from(vec![7u8; size]))),
+            Ok(Frame::trailers(trailers)),
+        ];
+        let response = http::Response::builder()
+            .header("content-type", "application/grpc")
+            .body(StreamBody::new(futures::stream::iter(frames)))
+            .expect("Failed to build response");
+        Ok::<_, Infallible>(response)
+    });
+
+    common::serve_fallback(listener, backend);
+    addr
+}
+
+async fn start_gateway(backend_addr: SocketAddr) -> (Channel, oneshot::Sender<()>) {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.router.buffer_responses = true;
+    config.router.response_buffer_max_size = BUFFER_LIMIT;
+
+    let (gateway_addr, shutdown_tx, _gateway) = common::spawn_gateway(config).await;
+
+    let channel = common::connect(gateway_addr).await;
+    RegistryServiceClient::new(channel.clone())
+        .register(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: format!("http://{backend_addr}"),
+            services: vec!["PayloadService".to_string()],
+            ..Default::default()
+        })
+        .await
+        .expect("Failed to register backend");
+    (channel, shutdown_tx)
+}
+
+// 返回 content-length 头、响应体长度和 trailers
+async fn call(channel: &Channel, method: &str) -> (Option<String>, usize, Option<http::HeaderMap>) {
+    let request = http::Request::builder()
+        .method("POST")
+        .uri(format!("/buffer.PayloadService/{method}"))
+        .header("content-type", "application/grpc")
+        .header("te", "trailers")
+        .body(tonic::body::Body::empty())
+        .unwrap();
+    let response = channel.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    let content_length = response
+        .headers()
+        .get(http::header::CONTENT_LENGTH)
+        .map(|value| value.to_str().unwrap().to_string());
+    let collected = response
+        .into_body()
+        .collect()
+        .await
+        .expect("Failed to read response body");
+    let trailers = collected.trailers().cloned();
+    (content_length, collected.to_bytes().len(), trailers)
+}
+
+fn assert_trailers_forwarded(trailers: Option<http::HeaderMap>) {
+    let trailers = trailers.expect("Missing trailers");
+    assert_eq!(trailers.get("grpc-status").unwrap(), "0");
+    assert_eq!(trailers.get("x-backend-trailer").unwrap(), "kept");
+}
+
+#[tokio::test]
+async fn test_small_response_is_buffered_with_content_length() {
+    let backend_addr = start_backend().await;
+    let (channel, _shutdown) = start_gateway(backend_addr).await;
+
+    let (content_length, body_len, trailers) = call(&channel, "Small").await;
+    assert_eq!(content_length.as_deref(), Some("100"));
+    assert_eq!(body_len, 100);
+    assert_trailers_forwarded(trailers);
+}
+
+#[tokio::test]
+async fn test_large_response_is_streamed() {
+    let backend_addr = start_backend().await;
+    let (channel, _shutdown) = start_gateway(backend_addr).await;
+
+    let (content_length, body_len, trailers) = call(&channel, "Large").await;
+    assert_eq!(content_length, None);
+    assert_eq!(body_len, 4 * BUFFER_LIMIT);
+    assert_trailers_forwarded(trailers);
+}
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body::Frame;
+use http_body_util::StreamBody;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+use grpc_opizontas::registry::{
+    ConnectionMessage, ForwardResponse, connection_message::MessageType, streaming_info::StreamType,
+};
+use grpc_opizontas::services::connection::{ReverseConnectionConfig, ReverseConnectionManager};
+use grpc_opizontas::services::event::EventConfig;
+
+const SERVICE: &str = "stream.UploadService";
+
+async fn setup() -> (ReverseConnectionManager, mpsc::Receiver<ConnectionMessage>) {
+    let manager = ReverseConnectionManager::default();
+    let (request_tx, request_rx) = mpsc::channel(16);
+    manager
+        .register_connection(
+            "stream-conn".to_string(),
+            vec![SERVICE.to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+    (manager, request_rx)
+}
+
+#[tokio::test]
+async fn test_multi_frame_body_is_forwarded_as_ordered_chunks() {
+    let (manager, mut request_rx) = setup().await;
+
+    let frames: Vec<Result<Frame<Bytes>, std::convert::Infallible>> = vec![
+        Ok(Frame::data(Bytes::from_static(b"chunk-0"))),
+        Ok(Frame::data(Bytes::from_static(b"chunk-1"))),
+        Ok(Frame::data(Bytes::from_static(b"chunk-2"))),
+    ];
+    let body = StreamBody::new(futures::stream::iter(frames));
+
+    let forwarding = tokio::spawn({
+        let manager = manager.clone();
+        async move {
+            manager
+                .send_request_stream(
+                    SERVICE,
+                    "/stream.UploadService/Upload",
+                    HashMap::new(),
+                    body,
+                    None,
+                )
+                .await
+        }
+    });
+
+    let mut request_id = None;
+    for expected_sequence in 0..3i64 {
+        let message = timeout(Duration::from_secs(1), request_rx.recv())
+            .await
+            .expect("Timeout waiting for request chunk")
+            .expect("Request channel closed");
+
+        let Some(MessageType::Request(request)) = message.message_type else {
+            panic!("Expected a forward request");
+        };
+        let streaming_info = request.streaming_info.expect("Missing streaming info");
+
+        assert_eq!(streaming_info.sequence_number, expected_sequence);
+        assert_eq!(streaming_info.is_stream_end, expected_sequence == 2);
+        assert_eq!(
+            streaming_info.stream_type,
+            StreamType::ClientStreaming as i32
+        );
+        assert_eq!(
+            request.payload,
+            format!("chunk-{expected_sequence}").as_bytes()
+        );
+
+        // 所有分块共享同一个请求ID
+        let id = request_id.get_or_insert_with(|| request.request_id.clone());
+        assert_eq!(*id, request.request_id);
+    }
+
+    manager
+        .handle_response(ForwardResponse {
+            request_id: request_id.unwrap(),
+            status_code: 200,
+            payload: b"uploaded".to_vec(),
+            ..Default::default()
+        })
+        .await;
+
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Timeout waiting for forwarded response")
+        .expect("Forwarding task panicked")
+        .expect("Forwarding failed");
+    assert_eq!(response.head.payload, b"uploaded");
+    assert!(response.chunks.is_none());
+}
+
+#[tokio::test]
+async fn test_per_service_timeout_override() {
+    let mut config = ReverseConnectionConfig::default();
+    config
+        .service_timeouts
+        .insert("report.SlowService".to_string(), Duration::from_secs(1));
+    let manager = ReverseConnectionManager::new(config, None, EventConfig::default());
+
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    manager
+        .register_connection(
+            "timeout-conn".to_string(),
+            vec![
+                "report.SlowService".to_string(),
+                "report.FastService".to_string(),
+            ],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    let slow = tokio::spawn({
+        let manager = manager.clone();
+        async move {
+            manager
+                .send_request(
+                    "report.SlowService",
+                    "/report.SlowService/Generate",
+                    HashMap::new(),
+                    Vec::new(),
+                )
+                .await
+        }
+    });
+    let fast = tokio::spawn({
+        let manager = manager.clone();
+        async move {
+            manager
+                .send_request(
+                    "report.FastService",
+                    "/report.FastService/Generate",
+                    HashMap::new(),
+                    Vec::new(),
+                )
+                .await
+        }
+    });
+
+    // 两个请求都超过 1 秒才响应，只有配置了 1 秒超时的服务会失败
+    let mut request_ids = Vec::new();
+    for _ in 0..2 {
+        let message = request_rx.recv().await.expect("Request channel closed");
+        if let Some(MessageType::Request(request)) = message.message_type {
+            request_ids.push(request.request_id);
+        }
+    }
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    for request_id in request_ids {
+        manager
+            .handle_response(ForwardResponse {
+                request_id,
+                status_code: 200,
+                ..Default::default()
+            })
+            .await;
+    }
+
+    let slow_result = slow.await.expect("Slow request task panicked");
+    assert_eq!(slow_result.unwrap_err(), "Request timeout");
+
+    let fast_result = fast.await.expect("Fast request task panicked");
+    assert!(fast_result.is_ok());
+}
+
+#[tokio::test]
+async fn test_dropped_request_sends_cancellation() {
+    let (manager, mut request_rx) = setup().await;
+
+    let forwarding = tokio::spawn({
+        let manager = manager.clone();
+        async move {
+            manager
+                .send_request(
+                    SERVICE,
+                    "/stream.UploadService/Upload",
+                    HashMap::new(),
+                    b"payload".to_vec(),
+                )
+                .await
+        }
+    });
+
+    let message = timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for request")
+        .expect("Request channel closed");
+    let Some(MessageType::Request(request)) = message.message_type else {
+        panic!("Expected a forward request");
+    };
+    assert_eq!(manager.pending_request_count().await, 1);
+
+    // 模拟调用方断开：丢弃转发 future
+    forwarding.abort();
+    let _ = forwarding.await;
+
+    let message = timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for cancellation")
+        .expect("Request channel closed");
+    let Some(MessageType::Cancel(cancel)) = message.message_type else {
+        panic!("Expected a cancel message");
+    };
+    assert_eq!(cancel.request_id, request.request_id);
+    assert_eq!(manager.pending_request_count().await, 0);
+}
+
+#[tokio::test]
+async fn test_stats_reflect_connections_and_requests() {
+    let config = ReverseConnectionConfig {
+        request_timeout: Duration::from_millis(200),
+        ..Default::default()
+    };
+    let manager = ReverseConnectionManager::new(config, None, EventConfig::default());
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    manager
+        .register_connection(
+            "stats-conn".to_string(),
+            vec![
+                "stats.QueryService".to_string(),
+                "stats.AdminService".to_string(),
+            ],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    let in_flight = tokio::spawn({
+        let manager = manager.clone();
+        async move {
+            manager
+                .send_request(
+                    "stats.QueryService",
+                    "/stats.QueryService/Query",
+                    HashMap::new(),
+                    Vec::new(),
+                )
+                .await
+        }
+    });
+    timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for request")
+        .expect("Request channel closed");
+
+    // 一个连接提供两个服务，在两个服务池中各计一次
+    let stats = manager.get_stats().await;
+    assert_eq!(stats.active_connections, 1);
+    assert_eq!(stats.registered_services, 2);
+    assert_eq!(stats.pooled_connections, 2);
+    assert_eq!(stats.pending_requests, 1);
+    assert_eq!(stats.forwarded_requests, 1);
+    assert_eq!(stats.timed_out_requests, 0);
+
+    // 微服务不响应，请求超时后计入超时计数
+    let result = in_flight.await.expect("Request task panicked");
+    assert_eq!(result.unwrap_err(), "Request timeout");
+    let stats = manager.get_stats().await;
+    assert_eq!(stats.pending_requests, 0);
+    assert_eq!(stats.timed_out_requests, 1);
+    assert_eq!(stats.failed_requests, 0);
+}
@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tonic::Request;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::registry::{
+    ConnectionMessage, ForwardResponse, RegisterRequest, connection_message::MessageType,
+};
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::{MyRegistryService, ServiceHealthStatus};
+
+const TOKEN: &str = "reverse-health-token";
+const SERVICE: &str = "ReverseOrderService";
+
+fn registry_service() -> MyRegistryService {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    MyRegistryService::new(config)
+}
+
+// 建立反向连接，并以连接ID作为实例ID登记到注册表
+async fn connect(
+    registry_service: &MyRegistryService,
+    connection_id: &str,
+) -> mpsc::Receiver<ConnectionMessage> {
+    let (request_tx, request_rx) = mpsc::channel(16);
+    registry_service
+        .reverse_connection_manager
+        .register_connection(
+            connection_id.to_string(),
+            vec![SERVICE.to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: "http://127.0.0.1:1".to_string(),
+            services: vec![SERVICE.to_string()],
+            instance_id: connection_id.to_string(),
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+    request_rx
+}
+
+fn grpc_request() -> http::Request<Full<Bytes>> {
+    http::Request::builder()
+        .method("POST")
+        .uri(format!("/{SERVICE}/Call"))
+        .header("content-type", "application/grpc")
+        .body(Full::new(Bytes::new()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_unhealthy_reverse_service_is_not_forwarded() {
+    let registry_service = registry_service();
+    let mut request_rx = connect(&registry_service, "health-conn").await;
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        Config::default(),
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    assert!(registry_service.update_service_health(SERVICE, ServiceHealthStatus::Unhealthy));
+    let response = router.clone().oneshot(grpc_request()).await.unwrap();
+    assert_eq!(
+        response
+            .headers()
+            .get("grpc-status")
+            .and_then(|v| v.to_str().ok()),
+        Some("14")
+    );
+    assert!(request_rx.try_recv().is_err());
+
+    // 恢复健康后重新经由反向连接转发
+    assert!(registry_service.update_service_health(SERVICE, ServiceHealthStatus::Healthy));
+    let in_flight = tokio::spawn(router.oneshot(grpc_request()));
+    let message = timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Request was not forwarded after recovery")
+        .expect("Request channel closed");
+    let Some(MessageType::Request(request)) = message.message_type else {
+        panic!("Expected forward request");
+    };
+    registry_service
+        .reverse_connection_manager
+        .handle_response(ForwardResponse {
+            request_id: request.request_id,
+            status_code: 200,
+            ..Default::default()
+        })
+        .await;
+    let response = in_flight.await.unwrap().unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_unhealthy_reverse_instances_are_skipped() {
+    let registry_service = registry_service();
+    let _rx_a = connect(&registry_service, "health-conn-a").await;
+    let _rx_b = connect(&registry_service, "health-conn-b").await;
+    let manager = &registry_service.reverse_connection_manager;
+
+    assert!(registry_service.set_instance_health(
+        SERVICE,
+        "health-conn-a",
+        ServiceHealthStatus::Draining
+    ));
+    for _ in 0..10 {
+        let connection = manager
+            .get_connection_for_service(SERVICE)
+            .expect("Expected a healthy connection");
+        assert_eq!(connection.connection_id, "health-conn-b");
+    }
+
+    // 所有实例都不可用时不再选择任何反向连接
+    assert!(registry_service.set_instance_health(
+        SERVICE,
+        "health-conn-b",
+        ServiceHealthStatus::Unhealthy
+    ));
+    assert!(!manager.has_reverse_connection(SERVICE));
+    assert!(manager.get_connection_for_service(SERVICE).is_none());
+}
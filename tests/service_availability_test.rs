@@ -0,0 +1,75 @@
+use bytes::Bytes;
+use http_body_util::Full;
+use tonic::Request;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::{MyRegistryService, ServiceHealthStatus};
+
+const TOKEN: &str = "availability-token";
+const SERVICE: &str = "OrderService";
+
+async fn setup() -> (MyRegistryService, DynamicRouter) {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+
+    let registry_service = MyRegistryService::new(config.clone());
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: "http://127.0.0.1:1".to_string(),
+            services: vec![SERVICE.to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+    (registry_service, router)
+}
+
+async fn grpc_status(router: &DynamicRouter, service: &str) -> Option<String> {
+    let request = http::Request::builder()
+        .method("POST")
+        .uri(format!("/{service}/Call"))
+        .header("content-type", "application/grpc")
+        .body(Full::new(Bytes::new()))
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    response
+        .headers()
+        .get("grpc-status")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+#[tokio::test]
+async fn test_unregistered_service_returns_not_found() {
+    let (_registry_service, router) = setup().await;
+
+    assert_eq!(
+        grpc_status(&router, "UnknownService").await.as_deref(),
+        Some("5")
+    );
+}
+
+#[tokio::test]
+async fn test_service_without_healthy_instances_returns_unavailable() {
+    let (registry_service, router) = setup().await;
+
+    for status in [
+        ServiceHealthStatus::Unhealthy,
+        ServiceHealthStatus::Draining,
+    ] {
+        assert!(registry_service.update_service_health(SERVICE, status));
+        assert_eq!(grpc_status(&router, SERVICE).await.as_deref(), Some("14"));
+    }
+}
@@ -0,0 +1,254 @@
+mod common;
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tonic::Request;
+use tonic::service::AxumBody;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::registry::{ForwardRequest, RegisterRequest, connection_message::MessageType};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::router::extractor::{extract_service_name, resolve_alias};
+
+const PATH: &str = "/naming.EchoService/Echo";
+
+#[test]
+fn test_extract_short_and_full_names() {
+    assert_eq!(extract_service_name(PATH, false).unwrap(), "EchoService");
+    assert_eq!(
+        extract_service_name(PATH, true).unwrap(),
+        "naming.EchoService"
+    );
+
+    // 没有包名时两种模式结果相同
+    assert_eq!(
+        extract_service_name("/EchoService/Echo", true).unwrap(),
+        "EchoService"
+    );
+    assert!(extract_service_name("/naming.EchoService", true).is_err());
+}
+
+// 以指定模式启动路由器，把反向连接登记为 registered_name，返回请求是否被转发到该连接
+async fn routes_to(use_full_service_name: bool, registered_name: &str) -> bool {
+    let mut config = Config::default();
+    config.router.use_full_service_name = use_full_service_name;
+
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "naming-conn".to_string(),
+            vec![registered_name.to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    let request = http::Request::builder()
+        .method("POST")
+        .uri(PATH)
+        .header("content-type", "application/grpc")
+        .body(Full::new(Bytes::from_static(b"payload")))
+        .unwrap();
+    let _forwarding = tokio::spawn(router.oneshot(request));
+
+    matches!(
+        timeout(Duration::from_millis(500), request_rx.recv()).await,
+        Ok(Some(message)) if matches!(message.message_type, Some(MessageType::Request(_)))
+    )
+}
+
+#[tokio::test]
+async fn test_short_name_mode_matches_short_registration() {
+    assert!(routes_to(false, "EchoService").await);
+    assert!(!routes_to(false, "naming.EchoService").await);
+}
+
+#[tokio::test]
+async fn test_full_name_mode_matches_full_registration() {
+    assert!(routes_to(true, "naming.EchoService").await);
+    assert!(!routes_to(true, "EchoService").await);
+}
+
+#[tokio::test]
+async fn test_full_name_mode_keeps_hierarchical_lookup() {
+    // "naming.EchoService" 回退匹配到登记为 "naming" 的连接
+    assert!(routes_to(true, "naming").await);
+}
+
+const ALIASED_PATH: &str = "/legacy.OldEchoService/Echo";
+
+fn alias_config(rewrite_aliased_path: bool) -> Config {
+    let mut config = Config::default();
+    config.security.tokens = vec!["naming-token".to_string()];
+    config.router.service_aliases = HashMap::from([(
+        "legacy.OldEchoService".to_string(),
+        "naming.EchoService".to_string(),
+    )]);
+    config.router.rewrite_aliased_path = rewrite_aliased_path;
+    config
+}
+
+fn echo_request(path: &str) -> http::Request<Full<Bytes>> {
+    http::Request::builder()
+        .method("POST")
+        .uri(path)
+        .header("content-type", "application/grpc")
+        .body(Full::new(Bytes::from_static(b"payload")))
+        .unwrap()
+}
+
+#[test]
+fn test_resolve_alias() {
+    let aliases = alias_config(true).router.service_aliases;
+    assert_eq!(
+        resolve_alias(ALIASED_PATH, &aliases).as_deref(),
+        Some("/naming.EchoService/Echo")
+    );
+    assert_eq!(resolve_alias(PATH, &aliases), None);
+    assert_eq!(resolve_alias("/legacy.OldEchoService", &aliases), None);
+}
+
+// 通过别名调用，返回反向连接收到的方法路径
+async fn reverse_aliased_path(rewrite_aliased_path: bool) -> String {
+    let config = alias_config(rewrite_aliased_path);
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "naming-conn".to_string(),
+            vec!["EchoService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    let _forwarding = tokio::spawn(router.oneshot(echo_request(ALIASED_PATH)));
+    let message = timeout(Duration::from_millis(500), request_rx.recv())
+        .await
+        .expect("Aliased request was not forwarded")
+        .expect("Request channel closed");
+    let Some(MessageType::Request(request)) = message.message_type else {
+        panic!("Expected a forward request");
+    };
+    request.method_path
+}
+
+#[tokio::test]
+async fn test_alias_resolves_reverse_connection() {
+    assert_eq!(reverse_aliased_path(true).await, "/naming.EchoService/Echo");
+    assert_eq!(reverse_aliased_path(false).await, ALIASED_PATH);
+}
+
+// 启动记录请求路径的后端
+async fn start_backend() -> (String, mpsc::UnboundedReceiver<String>) {
+    let (listener, addr) = common::bind().await;
+    let (path_tx, path_rx) = mpsc::unbounded_channel();
+    let backend = tower::service_fn(move |req: http::Request<AxumBody>| {
+        let _ = path_tx.send(req.uri().path().to_string());
+        async move {
+            let response = common::grpc_ok()
+                .body(Full::new(Bytes::new()))
+                .expect("Failed to build response");
+            Ok::<_, Infallible>(response)
+        }
+    });
+
+    common::serve_fallback(listener, backend);
+    (format!("http://{addr}"), path_rx)
+}
+
+#[tokio::test]
+async fn test_alias_resolves_forward_instance() {
+    for (rewrite_aliased_path, expected_path) in
+        [(true, "/naming.EchoService/Echo"), (false, ALIASED_PATH)]
+    {
+        let (address, mut path_rx) = start_backend().await;
+        let config = alias_config(rewrite_aliased_path);
+        let registry_service = MyRegistryService::new(config.clone());
+        registry_service
+            .register(Request::new(RegisterRequest {
+                api_key: "naming-token".to_string(),
+                address,
+                services: vec!["EchoService".to_string()],
+                ..Default::default()
+            }))
+            .await
+            .expect("Failed to register service");
+        let router = DynamicRouter::new(
+            registry_service.registry.clone(),
+            config,
+            registry_service.reverse_connection_manager.clone(),
+        )
+        .expect("Failed to create router");
+
+        let response = router
+            .oneshot(echo_request(ALIASED_PATH))
+            .await
+            .expect("Router returned an error");
+        assert_eq!(response.headers().get("grpc-status").unwrap(), "0");
+        assert_eq!(path_rx.recv().await.as_deref(), Some(expected_path));
+    }
+}
+
+#[tokio::test]
+async fn test_alias_applies_to_service_requests() {
+    let registry_service = MyRegistryService::new(alias_config(true));
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "naming-conn".to_string(),
+            vec!["EchoService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    // 微服务之间经反向连接发起的请求同样按别名查找
+    let _forwarding = tokio::spawn(MyRegistryService::handle_service_request(
+        Arc::clone(&reverse_manager),
+        ForwardRequest {
+            request_id: "alias-request".to_string(),
+            method_path: ALIASED_PATH.to_string(),
+            ..Default::default()
+        },
+    ));
+    let message = timeout(Duration::from_millis(500), request_rx.recv())
+        .await
+        .expect("Aliased service request was not forwarded")
+        .expect("Request channel closed");
+    let Some(MessageType::Request(request)) = message.message_type else {
+        panic!("Expected a forward request");
+    };
+    assert_eq!(request.method_path, "/naming.EchoService/Echo");
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use tokio::sync::mpsc;
+
+use grpc_opizontas::services::connection::ReverseConnectionManager;
+
+const SERVICE: &str = "weighted.TestService";
+
+async fn register(manager: &ReverseConnectionManager, connection_id: &str, weight: u32) {
+    let (request_tx, _request_rx) = mpsc::channel(16);
+    manager
+        .register_connection(
+            connection_id.to_string(),
+            vec![SERVICE.to_string()],
+            weight,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+}
+
+fn pick_counts(manager: &ReverseConnectionManager, picks: usize) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for _ in 0..picks {
+        let conn = manager
+            .get_connection_for_service(SERVICE)
+            .expect("Expected an active connection");
+        *counts.entry(conn.connection_id).or_insert(0) += 1;
+    }
+    counts
+}
+
+#[tokio::test]
+async fn test_weighted_round_robin_distribution() {
+    let manager = ReverseConnectionManager::default();
+
+    register(&manager, "conn-w1", 1).await;
+    register(&manager, "conn-w2", 2).await;
+    register(&manager, "conn-w3", 3).await;
+
+    let counts = pick_counts(&manager, 600);
+
+    // 权重 1/2/3 对应期望 100/200/300 次，允许 10% 误差
+    for (connection_id, expected) in [("conn-w1", 100), ("conn-w2", 200), ("conn-w3", 300)] {
+        let actual = counts.get(connection_id).copied().unwrap_or(0);
+        let tolerance = expected / 10;
+        assert!(
+            actual.abs_diff(expected) <= tolerance,
+            "{connection_id}: expected ~{expected} picks, got {actual}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_equal_weights_degenerate_to_round_robin() {
+    let manager = ReverseConnectionManager::default();
+
+    // 权重 0 视为 1
+    register(&manager, "conn-a", 0).await;
+    register(&manager, "conn-b", 1).await;
+    register(&manager, "conn-c", 1).await;
+
+    let counts = pick_counts(&manager, 300);
+
+    assert_eq!(counts.len(), 3);
+    assert!(counts.values().all(|&count| count == 100));
+}
@@ -0,0 +1,149 @@
+use std::collections::{HashMap, HashSet};
+
+use tokio::sync::mpsc;
+use tonic::Request;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::client_manager::GrpcClientManager;
+use grpc_opizontas::services::connection::ReverseConnectionManager;
+use grpc_opizontas::services::router::forwarder::{InstanceSelector, select_instance};
+use grpc_opizontas::services::router::load_balance::LoadBalanceStrategy;
+
+const TOKEN: &str = "affinity-token";
+const SERVICE: &str = "SessionService";
+
+fn keys() -> Vec<String> {
+    (0..200).map(|i| format!("session-{i}")).collect()
+}
+
+fn selector(key: &str) -> InstanceSelector<'_> {
+    InstanceSelector {
+        service_name: SERVICE,
+        labels: &[],
+        fallback: true,
+        affinity_key: Some(key),
+        strategy: LoadBalanceStrategy::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_reverse_connection_affinity_is_stable() {
+    let manager = ReverseConnectionManager::default();
+    for id in ["conn-a", "conn-b", "conn-c", "conn-d"] {
+        manager
+            .register_connection(
+                id.to_string(),
+                vec![SERVICE.to_string()],
+                1,
+                mpsc::channel(16).0,
+            )
+            .await
+            .expect("Failed to register connection");
+    }
+
+    let pick = |key: &str| {
+        manager
+            .get_connection_for_key(SERVICE, Some(key))
+            .expect("Expected an active connection")
+            .connection_id
+    };
+
+    // 同一个键多次调用始终选中同一个连接，且键分散到所有连接上
+    let mapping: HashMap<String, String> = keys()
+        .into_iter()
+        .map(|key| {
+            let picked = pick(&key);
+            (key, picked)
+        })
+        .collect();
+    for (key, connection_id) in &mapping {
+        for _ in 0..3 {
+            assert_eq!(&pick(key), connection_id);
+        }
+    }
+    let used: HashSet<_> = mapping.values().collect();
+    assert_eq!(used.len(), 4);
+
+    // 移除一个连接后，原本映射到其他连接的键保持不变
+    manager.unregister_connection("conn-c").await;
+    for (key, connection_id) in &mapping {
+        let current = pick(key);
+        if connection_id == "conn-c" {
+            assert_ne!(current, "conn-c");
+        } else {
+            assert_eq!(&current, connection_id, "key {key} was remapped");
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_forward_instance_affinity_is_stable() {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    let registry_service = MyRegistryService::new(config);
+    let addresses: Vec<String> = (1..=4)
+        .map(|i| format!("http://127.0.0.1:5030{i}"))
+        .collect();
+    for address in &addresses {
+        registry_service
+            .register(Request::new(RegisterRequest {
+                api_key: TOKEN.to_string(),
+                address: address.clone(),
+                services: vec![SERVICE.to_string()],
+                ..Default::default()
+            }))
+            .await
+            .expect("Failed to register service");
+    }
+    let registry = registry_service.registry.clone();
+    let client_manager = GrpcClientManager::default();
+
+    let pick = |key: &str| {
+        select_instance(&registry, &client_manager, &selector(key), &[])
+            .expect("Expected a healthy instance")
+    };
+
+    let mapping: HashMap<String, String> = keys()
+        .into_iter()
+        .map(|key| {
+            let picked = pick(&key);
+            (key, picked)
+        })
+        .collect();
+    for (key, address) in &mapping {
+        assert_eq!(&pick(key), address);
+    }
+    assert_eq!(mapping.values().collect::<HashSet<_>>().len(), 4);
+
+    // 注销一个实例，只有原本映射到该实例的键被重新分配
+    let removed = &addresses[1];
+    registry
+        .get(SERVICE)
+        .unwrap()
+        .retain(|_, info| &info.address != removed);
+    let mut remapped = 0;
+    for (key, address) in &mapping {
+        let current = pick(key);
+        if address == removed {
+            assert_ne!(&current, removed);
+            remapped += 1;
+        } else {
+            assert_eq!(&current, address, "key {key} was remapped");
+        }
+    }
+    assert!(remapped > 0);
+
+    // 重试时排除失败的实例，选择得分次高的实例
+    let (key, address) = mapping.iter().find(|(_, a)| *a != removed).unwrap();
+    let retry = select_instance(
+        &registry,
+        &client_manager,
+        &selector(key),
+        std::slice::from_ref(address),
+    )
+    .expect("Expected a fallback instance");
+    assert_ne!(&retry, address);
+}
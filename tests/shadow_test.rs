@@ -0,0 +1,139 @@
+mod common;
+
+use std::convert::Infallible;
+use std::time::Duration;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use http_body_util::{BodyExt, Full};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tonic::Request;
+use tonic::service::AxumBody;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::{Config, ShadowTarget};
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::router::DynamicRouter;
+
+const TOKEN: &str = "shadow-token";
+const SERVICE: &str = "ShadowOrderService";
+const FULL_SERVICE: &str = "shadow.ShadowOrderService";
+
+fn grpc_frame(message: &[u8]) -> Bytes {
+    let mut frame = BytesMut::with_capacity(5 + message.len());
+    frame.put_u8(0);
+    frame.put_u32(message.len() as u32);
+    frame.put_slice(message);
+    frame.freeze()
+}
+
+// 启动记录请求体的后端，响应头 x-backend 标明自身名称
+async fn start_backend(
+    name: &'static str,
+    grpc_status: &'static str,
+) -> (String, mpsc::UnboundedReceiver<Bytes>) {
+    let (listener, addr) = common::bind().await;
+    let (body_tx, body_rx) = mpsc::unbounded_channel();
+    let backend = tower::service_fn(move |req: http::Request<AxumBody>| {
+        let body_tx = body_tx.clone();
+        async move {
+            let body = req.into_body().collect().await.unwrap().to_bytes();
+            let _ = body_tx.send(body);
+            let response = http::Response::builder()
+                .header("content-type", "application/grpc")
+                .header("grpc-status", grpc_status)
+                .header("x-backend", name)
+                .body(Full::new(Bytes::new()))
+                .expect("Failed to build response");
+            Ok::<_, Infallible>(response)
+        }
+    });
+
+    common::serve_fallback(listener, backend);
+    (format!("http://{addr}"), body_rx)
+}
+
+async fn setup(
+    shadow_status: &'static str,
+) -> (
+    DynamicRouter,
+    mpsc::UnboundedReceiver<Bytes>,
+    mpsc::UnboundedReceiver<Bytes>,
+) {
+    let (primary, primary_rx) = start_backend("primary", "0").await;
+    let (shadow, shadow_rx) = start_backend("shadow", shadow_status).await;
+
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.router.shadow_targets.insert(
+        FULL_SERVICE.to_string(),
+        ShadowTarget {
+            address: shadow,
+            sample_rate: 1.0,
+        },
+    );
+
+    let registry_service = MyRegistryService::new(config.clone());
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: primary,
+            services: vec![SERVICE.to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+    (router, primary_rx, shadow_rx)
+}
+
+async fn call(router: &DynamicRouter, body: Bytes) -> http::Response<impl http_body::Body> {
+    let request = http::Request::builder()
+        .method("POST")
+        .uri(format!("/{FULL_SERVICE}/Create"))
+        .header("content-type", "application/grpc")
+        .body(Full::new(body))
+        .unwrap();
+    router.clone().oneshot(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_unary_request_is_mirrored_to_shadow() {
+    let (router, mut primary_rx, mut shadow_rx) = setup("13").await;
+    let body = grpc_frame(b"order-1");
+
+    // 即使影子返回不同的状态码，调用方仍然收到主实例的响应
+    let response = call(&router, body.clone()).await;
+    assert_eq!(response.headers().get("x-backend").unwrap(), "primary");
+    assert_eq!(response.headers().get("grpc-status").unwrap(), "0");
+    assert_eq!(primary_rx.recv().await.unwrap(), body);
+
+    let mirrored = timeout(Duration::from_secs(2), shadow_rx.recv())
+        .await
+        .expect("Shadow did not receive a copy")
+        .expect("Shadow channel closed");
+    assert_eq!(mirrored, body);
+}
+
+#[tokio::test]
+async fn test_streaming_request_is_not_mirrored() {
+    let (router, mut primary_rx, mut shadow_rx) = setup("0").await;
+    let mut body = BytesMut::new();
+    body.extend_from_slice(&grpc_frame(b"chunk-1"));
+    body.extend_from_slice(&grpc_frame(b"chunk-2"));
+
+    let response = call(&router, body.freeze()).await;
+    assert_eq!(response.headers().get("x-backend").unwrap(), "primary");
+    primary_rx.recv().await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(shadow_rx.try_recv().is_err());
+}
@@ -0,0 +1,252 @@
+mod common;
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use http_body_util::Empty;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::timeout;
+use tokio_stream::StreamExt;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::registry_service_client::RegistryServiceClient;
+use grpc_opizontas::registry::{
+    ConnectionMessage, ConnectionRegister, RegisterRequest, connection_message::MessageType,
+    connection_status::StatusType,
+};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::client_manager::GrpcClientManager;
+
+const TOKEN: &str = "shutdown-test-token";
+const SLOW_RESPONSE: Duration = Duration::from_millis(500);
+
+// 启动一个对所有请求都延迟响应的后端
+async fn start_slow_backend() -> SocketAddr {
+    let (listener, addr) = common::bind().await;
+    let slow = tower::service_fn(|_req: http::Request<_>| async {
+        tokio::time::sleep(SLOW_RESPONSE).await;
+        let response = common::grpc_ok()
+            .body(Empty::<bytes::Bytes>::new())
+            .expect("Failed to build response");
+        Ok::<_, Infallible>(response)
+    });
+
+    common::serve_fallback(listener, slow);
+    addr
+}
+
+#[tokio::test]
+async fn test_graceful_shutdown_completes_in_flight_request() {
+    let backend_addr = start_slow_backend().await;
+
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.server.shutdown_grace_period = 5;
+
+    let (gateway_addr, shutdown_tx, gateway) = common::spawn_gateway(config).await;
+
+    let channel = common::connect(gateway_addr).await;
+    let mut registry_client = RegistryServiceClient::new(channel.clone());
+
+    registry_client
+        .register(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: format!("http://{backend_addr}"),
+            services: vec!["SlowService".to_string()],
+            ..Default::default()
+        })
+        .await
+        .expect("Failed to register backend");
+
+    // 建立一个反向连接，停机时应收到 Disconnected 状态
+    let (reverse_tx, reverse_rx) = mpsc::channel(4);
+    reverse_tx
+        .send(ConnectionMessage {
+            message_type: Some(MessageType::Register(ConnectionRegister {
+                api_key: TOKEN.to_string(),
+                services: vec!["shutdown.ReverseService".to_string()],
+                connection_id: String::new(),
+                weight: 1,
+            })),
+        })
+        .await
+        .unwrap();
+    let mut reverse_inbound = registry_client
+        .establish_connection(ReceiverStream::new(reverse_rx))
+        .await
+        .expect("Failed to establish reverse connection")
+        .into_inner();
+    let connected = reverse_inbound.next().await.unwrap().unwrap();
+    assert!(matches!(
+        connected.message_type,
+        Some(MessageType::Status(status)) if status.status == StatusType::Connected as i32
+    ));
+
+    // 发起慢请求
+    let slow_request = tokio::spawn({
+        let channel = channel.clone();
+        async move {
+            let request = http::Request::builder()
+                .method("POST")
+                .uri(format!("http://{gateway_addr}/slow.SlowService/Call"))
+                .header("content-type", "application/grpc")
+                .header("te", "trailers")
+                .body(tonic::body::Body::empty())
+                .unwrap();
+            channel.oneshot(request).await
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // 请求进行中触发停机
+    shutdown_tx.send(()).unwrap();
+
+    let disconnected = timeout(Duration::from_secs(1), reverse_inbound.next())
+        .await
+        .expect("Timeout waiting for disconnect notice")
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        disconnected.message_type,
+        Some(MessageType::Status(status)) if status.status == StatusType::Disconnected as i32
+    ));
+    drop(reverse_tx);
+    drop(reverse_inbound);
+
+    let response = timeout(Duration::from_secs(2), slow_request)
+        .await
+        .expect("Timeout waiting for in-flight request")
+        .expect("Request task panicked")
+        .expect("In-flight request should complete during drain");
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(
+        response
+            .headers()
+            .get("grpc-status")
+            .and_then(|v| v.to_str().ok()),
+        Some("0")
+    );
+
+    drop(registry_client);
+    drop(channel);
+    timeout(Duration::from_secs(5), gateway)
+        .await
+        .expect("Gateway did not stop within the grace period")
+        .expect("Gateway task panicked")
+        .expect("Gateway returned an error");
+}
+
+#[tokio::test]
+async fn test_shutdown_fails_stuck_reverse_request_after_grace_period() {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.server.shutdown_grace_period = 1;
+    config.reverse_connection.request_timeout = 60;
+
+    let (gateway_addr, shutdown_tx, gateway) = common::spawn_gateway(config).await;
+
+    let channel = common::connect(gateway_addr).await;
+
+    // 反向连接只接收请求，从不返回响应
+    let (reverse_tx, reverse_rx) = mpsc::channel(4);
+    reverse_tx
+        .send(ConnectionMessage {
+            message_type: Some(MessageType::Register(ConnectionRegister {
+                api_key: TOKEN.to_string(),
+                services: vec!["StuckService".to_string()],
+                connection_id: String::new(),
+                weight: 1,
+            })),
+        })
+        .await
+        .unwrap();
+    let mut reverse_inbound = RegistryServiceClient::new(channel.clone())
+        .establish_connection(ReceiverStream::new(reverse_rx))
+        .await
+        .expect("Failed to establish reverse connection")
+        .into_inner();
+    reverse_inbound.next().await.unwrap().unwrap();
+
+    let stuck_request = tokio::spawn({
+        let channel = channel.clone();
+        async move {
+            let request = http::Request::builder()
+                .method("POST")
+                .uri(format!("http://{gateway_addr}/stuck.StuckService/Call"))
+                .header("content-type", "application/grpc")
+                .header("te", "trailers")
+                .body(tonic::body::Body::empty())
+                .unwrap();
+            channel.oneshot(request).await
+        }
+    });
+    let forwarded = timeout(Duration::from_secs(1), reverse_inbound.next())
+        .await
+        .expect("Timeout waiting for forwarded request")
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        forwarded.message_type,
+        Some(MessageType::Request(_))
+    ));
+
+    let shutdown_started = std::time::Instant::now();
+    shutdown_tx.send(()).unwrap();
+
+    // 宽限期结束后请求以 UNAVAILABLE 失败
+    let response = timeout(Duration::from_secs(3), stuck_request)
+        .await
+        .expect("Stuck request was not failed after the grace period")
+        .expect("Request task panicked")
+        .expect("Stuck request should receive an error response");
+    assert_eq!(
+        response
+            .headers()
+            .get("grpc-status")
+            .and_then(|v| v.to_str().ok()),
+        Some("14")
+    );
+
+    timeout(Duration::from_secs(3), gateway)
+        .await
+        .expect("Gateway did not stop after the grace period")
+        .expect("Gateway task panicked")
+        .expect("Gateway returned an error");
+    assert!(
+        shutdown_started.elapsed() < Duration::from_secs(3),
+        "Shutdown took {:?}",
+        shutdown_started.elapsed()
+    );
+    drop(reverse_tx);
+}
+
+#[tokio::test]
+async fn test_manager_shutdown_waits_for_background_tasks() {
+    let registry_service = MyRegistryService::new(Config::default());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let client_manager = GrpcClientManager::default();
+    let (_registrations_tx, registrations) = broadcast::channel(1);
+    client_manager.spawn_registration_warmup(registrations, CancellationToken::new());
+
+    // 清理任务、保活任务和预热任务在创建后一直运行
+    assert!(reverse_manager.background_tasks() > 0);
+    assert_eq!(client_manager.background_tasks(), 2);
+
+    timeout(Duration::from_secs(1), reverse_manager.shutdown())
+        .await
+        .expect("Reverse connection manager shutdown timed out");
+    timeout(Duration::from_secs(1), client_manager.shutdown())
+        .await
+        .expect("Client manager shutdown timed out");
+    assert_eq!(reverse_manager.background_tasks(), 0);
+    assert_eq!(client_manager.background_tasks(), 0);
+
+    // 重复调用立即返回
+    timeout(Duration::from_millis(100), reverse_manager.shutdown())
+        .await
+        .expect("Repeated shutdown should return immediately");
+}
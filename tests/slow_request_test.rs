@@ -0,0 +1,129 @@
+mod common;
+
+use std::convert::Infallible;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tonic::Request;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::metrics::{ForwardRoute, MetricsExporter};
+use grpc_opizontas::services::router::DynamicRouter;
+
+const TOKEN: &str = "slow-request-token";
+
+// 方法名为 Slow 的请求延迟 300ms 返回，其余立即返回
+async fn start_backend() -> SocketAddr {
+    let (listener, addr) = common::bind().await;
+    let backend = tower::service_fn(|req: http::Request<_>| async move {
+        if req.uri().path().ends_with("/Slow") {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+        }
+        let response = common::grpc_ok()
+            .body(Full::new(Bytes::new()))
+            .expect("Failed to build response");
+        Ok::<_, Infallible>(response)
+    });
+
+    common::serve_fallback(listener, backend);
+    addr
+}
+
+// 把日志写入共享缓冲区，供测试检查
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl CapturedLogs {
+    fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+#[tokio::test]
+async fn test_slow_request_is_logged_and_counted() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_max_level(tracing::Level::WARN)
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let backend_addr = start_backend().await;
+
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.router.slow_request_threshold_ms = 100;
+    let registry_service = MyRegistryService::new(config.clone());
+    registry_service
+        .register(Request::new(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: format!("http://{backend_addr}"),
+            services: vec!["LatencyService".to_string()],
+            ..Default::default()
+        }))
+        .await
+        .expect("Failed to register service");
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    for method in ["Fast", "Slow"] {
+        let request = http::Request::builder()
+            .method("POST")
+            .uri(format!("/latency.LatencyService/{method}"))
+            .header("content-type", "application/grpc")
+            .body(Full::new(Bytes::from_static(b"payload")))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), http::StatusCode::OK);
+    }
+
+    // 只有慢请求被计数并输出日志
+    assert_eq!(router.metrics.slow_requests(ForwardRoute::Direct), 1);
+    let logs = logs.contents();
+    assert_eq!(logs.matches("Slow request").count(), 1, "{logs}");
+    assert!(logs.contains("/latency.LatencyService/Slow"), "{logs}");
+    assert!(logs.contains("payload_size=7"), "{logs}");
+
+    let exporter = MetricsExporter {
+        metrics: router.metrics.clone(),
+        registry: router.registry.clone(),
+        client_manager: router.client_manager.clone(),
+        reverse_manager: router.reverse_manager.clone(),
+        readiness: Default::default(),
+    };
+    let rendered = exporter.render().await;
+    for line in [
+        "gateway_slow_requests_total{route=\"direct\"} 1",
+        "gateway_slow_requests_total{route=\"reverse\"} 0",
+        "gateway_request_payload_bytes_bucket{route=\"direct\",le=\"256\"} 2",
+        "gateway_request_payload_bytes_sum{route=\"direct\"} 14",
+        "gateway_request_payload_bytes_count{route=\"direct\"} 2",
+    ] {
+        assert!(rendered.contains(line), "missing `{line}` in:\n{rendered}");
+    }
+}
@@ -0,0 +1,207 @@
+mod common;
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tonic::Request;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::registry::{
+    ConnectionMessage, ForwardResponse, RegisterRequest, connection_message::MessageType,
+};
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::router::sticky::StickyInstances;
+use grpc_opizontas::services::{MyRegistryService, ServiceHealthStatus};
+
+const TOKEN: &str = "sticky-token";
+const CLIENT_HEADER: &str = "x-client-id";
+
+fn sticky_config() -> Config {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.router.stickiness_window_ms = 5_000;
+    config.router.stickiness_header = Some(CLIENT_HEADER.to_string());
+    config
+}
+
+fn request(path: &str, client_id: &str) -> http::Request<Full<Bytes>> {
+    http::Request::builder()
+        .method("POST")
+        .uri(path)
+        .header("content-type", "application/grpc")
+        .header(CLIENT_HEADER, client_id)
+        .body(Full::new(Bytes::new()))
+        .unwrap()
+}
+
+async fn forward_backend(router: &DynamicRouter, client_id: &str) -> String {
+    let response = router
+        .clone()
+        .oneshot(request("/sticky.StickyService/Call", client_id))
+        .await
+        .unwrap();
+    response
+        .headers()
+        .get("x-backend")
+        .and_then(|v| v.to_str().ok())
+        .expect("Request was not forwarded")
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_forward_requests_stick_to_last_instance() {
+    let addrs = [
+        common::start_addr_backend().await,
+        common::start_addr_backend().await,
+    ];
+
+    let config = sticky_config();
+    let registry_service = MyRegistryService::new(config.clone());
+    for addr in addrs {
+        registry_service
+            .register(Request::new(RegisterRequest {
+                api_key: TOKEN.to_string(),
+                address: format!("http://{addr}"),
+                services: vec!["StickyService".to_string()],
+                ..Default::default()
+            }))
+            .await
+            .expect("Failed to register service");
+    }
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    // 窗口内同一客户端的连续请求都落在同一个实例上
+    let first = forward_backend(&router, "client-a").await;
+    for _ in 0..5 {
+        assert_eq!(forward_backend(&router, "client-a").await, first);
+    }
+
+    // 其他客户端仍按负载均衡分摊到各个实例
+    let mut backends = HashSet::new();
+    for client in 0..10 {
+        backends.insert(forward_backend(&router, &format!("client-{client}")).await);
+    }
+    assert_eq!(backends.len(), 2, "{backends:?}");
+
+    // 粘滞的实例不健康时重新选择，之后粘滞在新的实例上
+    registry_service.set_instance_health(
+        "StickyService",
+        &format!("http://{first}"),
+        ServiceHealthStatus::Unhealthy,
+    );
+    let second = forward_backend(&router, "client-a").await;
+    assert_ne!(second, first);
+    registry_service.set_instance_health(
+        "StickyService",
+        &format!("http://{first}"),
+        ServiceHealthStatus::Healthy,
+    );
+    for _ in 0..3 {
+        assert_eq!(forward_backend(&router, "client-a").await, second);
+    }
+}
+
+// 代替微服务应答转发到 connection 上的请求，并报告收到请求的连接
+fn spawn_responder(
+    connection: &'static str,
+    mut requests: mpsc::Receiver<ConnectionMessage>,
+    router: &DynamicRouter,
+    hits: mpsc::UnboundedSender<&'static str>,
+) {
+    let reverse_manager = router.reverse_manager.clone();
+    tokio::spawn(async move {
+        while let Some(message) = requests.recv().await {
+            let Some(MessageType::Request(request)) = message.message_type else {
+                continue;
+            };
+            let _ = hits.send(connection);
+            reverse_manager
+                .handle_response(ForwardResponse {
+                    request_id: request.request_id,
+                    status_code: 0,
+                    ..Default::default()
+                })
+                .await;
+        }
+    });
+}
+
+#[tokio::test]
+async fn test_reverse_requests_stick_to_last_connection() {
+    let config = sticky_config();
+    let registry_service = MyRegistryService::new(config.clone());
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+    let (hits_tx, mut hits_rx) = mpsc::unbounded_channel();
+    for connection in ["sticky-conn-1", "sticky-conn-2"] {
+        let (request_tx, request_rx) = mpsc::channel(16);
+        router
+            .reverse_manager
+            .register_connection(
+                connection.to_string(),
+                vec!["StickyService".to_string()],
+                1,
+                request_tx,
+            )
+            .await
+            .expect("Failed to register connection");
+        spawn_responder(connection, request_rx, &router, hits_tx.clone());
+    }
+
+    let mut call = async |client_id: &str| {
+        router
+            .clone()
+            .oneshot(request("/sticky.StickyService/Call", client_id))
+            .await
+            .unwrap();
+        timeout(Duration::from_secs(1), hits_rx.recv())
+            .await
+            .expect("Timeout waiting for forwarded request")
+            .expect("Responder stopped")
+    };
+
+    // 窗口内同一客户端的连续请求都发往同一个连接
+    let first = call("client-a").await;
+    for _ in 0..5 {
+        assert_eq!(call("client-a").await, first);
+    }
+
+    // 其他客户端仍按负载均衡分摊到各个连接
+    let mut hits: HashMap<&str, usize> = HashMap::new();
+    for client in 0..10 {
+        *hits
+            .entry(call(&format!("client-{client}")).await)
+            .or_default() += 1;
+    }
+    assert_eq!(hits.len(), 2, "{hits:?}");
+}
+
+#[test]
+fn test_sticky_entry_expires_after_window() {
+    let sticky = StickyInstances::default();
+    sticky.record("client", "Service", "instance-1", Duration::from_millis(50));
+    assert_eq!(
+        sticky.get("client", "Service").as_deref(),
+        Some("instance-1")
+    );
+    // 不同服务各自记录
+    assert_eq!(sticky.get("client", "OtherService"), None);
+
+    std::thread::sleep(Duration::from_millis(80));
+    assert_eq!(sticky.get("client", "Service"), None);
+}
@@ -0,0 +1,441 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::{ConnectionMessage, connection_message::MessageType};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::connection::{ReverseConnectionConfig, ReverseConnectionManager};
+use grpc_opizontas::services::event::EventConfig;
+use grpc_opizontas::services::router::DynamicRouter;
+
+const SERVICE: &str = "stream.DownloadService";
+
+async fn setup(
+    config: ReverseConnectionConfig,
+) -> (ReverseConnectionManager, mpsc::Receiver<ConnectionMessage>) {
+    let manager = ReverseConnectionManager::new(config, None, EventConfig::default());
+    let (request_tx, request_rx) = mpsc::channel(16);
+    manager
+        .register_connection(
+            "download-conn".to_string(),
+            vec![SERVICE.to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+    (manager, request_rx)
+}
+
+// 发出请求并返回转发任务和请求 ID
+async fn start_request(
+    manager: &ReverseConnectionManager,
+    request_rx: &mut mpsc::Receiver<ConnectionMessage>,
+) -> (
+    tokio::task::JoinHandle<Result<grpc_opizontas::registry::ForwardResponse, String>>,
+    String,
+) {
+    let forwarding = tokio::spawn({
+        let manager = manager.clone();
+        async move {
+            manager
+                .send_request(
+                    SERVICE,
+                    "/stream.DownloadService/Download",
+                    HashMap::new(),
+                    Vec::new(),
+                )
+                .await
+        }
+    });
+
+    let message = timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for request")
+        .expect("Request channel closed");
+    let Some(MessageType::Request(request)) = message.message_type else {
+        panic!("Expected a forward request");
+    };
+    (forwarding, request.request_id)
+}
+
+#[tokio::test]
+async fn test_stale_stream_is_reaped_and_client_notified() {
+    let config = ReverseConnectionConfig {
+        stream_idle_timeout: Duration::from_millis(200),
+        cleanup_interval: Duration::from_millis(50),
+        ..Default::default()
+    };
+    let (manager, mut request_rx) = setup(config).await;
+    let (forwarding, request_id) = start_request(&manager, &mut request_rx).await;
+
+    // 微服务只发送了部分数据块就停止响应
+    for chunk_index in 0..2 {
+        manager
+            .handle_response(
+                ReverseConnectionManager::create_response_chunk(
+                    request_id.clone(),
+                    vec![chunk_index as u8; 4],
+                    chunk_index,
+                    false,
+                    None,
+                )
+                .expect("Failed to build response chunk"),
+            )
+            .await;
+    }
+    assert_eq!(manager.streaming_response_count().await, 1);
+    assert_eq!(manager.pending_request_count().await, 0);
+
+    let result = timeout(Duration::from_secs(2), forwarding)
+        .await
+        .expect("Client was not notified of the stale stream")
+        .expect("Forwarding task panicked");
+    let error = result.expect_err("Stale stream should fail the request");
+    assert!(error.contains("Streaming response timed out"), "{error}");
+    assert_eq!(manager.streaming_response_count().await, 0);
+}
+
+#[tokio::test]
+async fn test_active_stream_is_not_reaped() {
+    let config = ReverseConnectionConfig {
+        stream_idle_timeout: Duration::from_millis(300),
+        cleanup_interval: Duration::from_millis(50),
+        ..Default::default()
+    };
+    let (manager, mut request_rx) = setup(config).await;
+    let (forwarding, request_id) = start_request(&manager, &mut request_rx).await;
+
+    // 数据块间隔小于空闲超时，总耗时超过空闲超时也不会被清理
+    for chunk_index in 0..4 {
+        manager
+            .handle_response(
+                ReverseConnectionManager::create_response_chunk(
+                    request_id.clone(),
+                    vec![b'a' + chunk_index as u8],
+                    chunk_index,
+                    chunk_index == 3,
+                    None,
+                )
+                .expect("Failed to build response chunk"),
+            )
+            .await;
+        tokio::time::sleep(Duration::from_millis(120)).await;
+    }
+
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Timeout waiting for response")
+        .expect("Forwarding task panicked")
+        .expect("Streaming response should complete");
+    assert_eq!(response.payload, b"abcd");
+}
+
+// 按给定顺序发送数据块，最后一个序号为 final
+async fn deliver_chunks(
+    manager: &ReverseConnectionManager,
+    request_id: &str,
+    order: &[i64],
+    final_index: i64,
+) {
+    for &chunk_index in order {
+        let mut chunk = ReverseConnectionManager::create_response_chunk(
+            request_id.to_string(),
+            format!("[{chunk_index}]").into_bytes(),
+            chunk_index,
+            chunk_index == final_index,
+            None,
+        )
+        .expect("Failed to build response chunk");
+        if chunk_index == final_index {
+            chunk
+                .headers
+                .insert("grpc-status".to_string(), "0".to_string());
+        }
+        manager.handle_response(chunk).await;
+    }
+}
+
+#[tokio::test]
+async fn test_out_of_order_chunks_are_reassembled() {
+    let (manager, mut request_rx) = setup(ReverseConnectionConfig::default()).await;
+    let (forwarding, request_id) = start_request(&manager, &mut request_rx).await;
+
+    deliver_chunks(&manager, &request_id, &[2, 0, 1, 3], 3).await;
+
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Timeout waiting for response")
+        .expect("Forwarding task panicked")
+        .expect("Streaming response should complete");
+    assert_eq!(response.payload, b"[0][1][2][3]");
+    assert!(response.response_stream_info.is_none());
+    assert_eq!(manager.streaming_response_count().await, 0);
+}
+
+#[tokio::test]
+async fn test_oversized_assembled_response_is_rejected() {
+    let config = ReverseConnectionConfig {
+        max_response_size: 8,
+        ..Default::default()
+    };
+    let (manager, mut request_rx) = setup(config).await;
+    let (forwarding, request_id) = start_request(&manager, &mut request_rx).await;
+
+    // 每个数据块 3 字节，缓存到第三个数据块时超过上限
+    deliver_chunks(&manager, &request_id, &[2, 1], 3).await;
+    assert_eq!(manager.streaming_response_count().await, 1);
+    deliver_chunks(&manager, &request_id, &[0], 3).await;
+
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Client was not notified of the oversized response")
+        .expect("Forwarding task panicked")
+        .expect("Oversized response should map to a gRPC status");
+    assert_eq!(
+        response.headers.get("grpc-status").map(String::as_str),
+        Some("8")
+    );
+    assert!(response.payload.is_empty());
+    assert_eq!(manager.streaming_response_count().await, 0);
+
+    // 之后到达的数据块不会重新缓存
+    deliver_chunks(&manager, &request_id, &[3], 3).await;
+    assert_eq!(manager.streaming_response_count().await, 0);
+}
+
+#[tokio::test]
+async fn test_oversized_unary_response_is_rejected() {
+    let config = ReverseConnectionConfig {
+        max_response_size: 8,
+        ..Default::default()
+    };
+    let (manager, mut request_rx) = setup(config).await;
+    let (forwarding, request_id) = start_request(&manager, &mut request_rx).await;
+
+    manager
+        .handle_response(grpc_opizontas::registry::ForwardResponse {
+            request_id,
+            status_code: 200,
+            payload: vec![0; 9],
+            ..Default::default()
+        })
+        .await;
+
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Timeout waiting for response")
+        .expect("Forwarding task panicked")
+        .expect("Oversized response should map to a gRPC status");
+    assert_eq!(
+        response.headers.get("grpc-status").map(String::as_str),
+        Some("8")
+    );
+    assert!(response.payload.is_empty());
+}
+
+#[tokio::test]
+async fn test_final_chunk_before_earlier_chunks() {
+    let (manager, mut request_rx) = setup(ReverseConnectionConfig::default()).await;
+    let (forwarding, request_id) = start_request(&manager, &mut request_rx).await;
+
+    // 最后一个数据块先到，缺口补齐之前不会完成
+    deliver_chunks(&manager, &request_id, &[3, 1], 3).await;
+    assert_eq!(manager.streaming_response_count().await, 1);
+    assert!(!forwarding.is_finished());
+
+    deliver_chunks(&manager, &request_id, &[2, 0], 3).await;
+
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Timeout waiting for response")
+        .expect("Forwarding task panicked")
+        .expect("Streaming response should complete");
+    assert_eq!(response.payload, b"[0][1][2][3]");
+    // 状态与响应头取自最后一个数据块，而不是最后到达的数据块
+    assert_eq!(
+        response.headers.get("grpc-status").map(String::as_str),
+        Some("0")
+    );
+}
+
+// 通过路由器转发到反向连接，返回路由器、反向连接管理器和微服务收到的消息
+async fn setup_router(
+    config: Config,
+) -> (
+    DynamicRouter,
+    std::sync::Arc<ReverseConnectionManager>,
+    mpsc::Receiver<ConnectionMessage>,
+) {
+    let registry_service = MyRegistryService::new(config.clone());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    let (request_tx, request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "download-conn".to_string(),
+            vec!["DownloadService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+    (router, reverse_manager, request_rx)
+}
+
+fn download_request() -> http::Request<Full<Bytes>> {
+    http::Request::builder()
+        .method("POST")
+        .uri("/stream.DownloadService/Download")
+        .header("content-type", "application/grpc")
+        .body(Full::new(Bytes::from_static(b"\0\0\0\0\0")))
+        .unwrap()
+}
+
+async fn next_message(request_rx: &mut mpsc::Receiver<ConnectionMessage>) -> MessageType {
+    timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for message")
+        .expect("Request channel closed")
+        .message_type
+        .expect("Empty message")
+}
+
+async fn next_frame<B>(body: &mut B) -> http_body::Frame<Bytes>
+where
+    B: http_body::Body<Data = Bytes> + Unpin,
+    B::Error: std::fmt::Debug,
+{
+    timeout(Duration::from_secs(1), body.frame())
+        .await
+        .expect("Timeout waiting for frame")
+        .expect("Body ended early")
+        .expect("Body error")
+}
+
+#[tokio::test]
+async fn test_server_stream_is_forwarded_incrementally() {
+    let (router, manager, mut request_rx) = setup_router(Config::default()).await;
+    let forwarding = tokio::spawn(router.oneshot(download_request()));
+
+    let MessageType::Request(request) = next_message(&mut request_rx).await else {
+        panic!("Expected a forward request");
+    };
+    let request_id = request.request_id;
+
+    // 第一个数据块到达后响应即开始返回，不等待整个流结束
+    let mut first = ReverseConnectionManager::create_response_chunk(
+        request_id.clone(),
+        b"[0]".to_vec(),
+        0,
+        false,
+        None,
+    )
+    .expect("Failed to build response chunk");
+    first
+        .headers
+        .insert("content-type".to_string(), "application/grpc".to_string());
+    manager.handle_response(first).await;
+
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Response was not returned after the first chunk")
+        .expect("Router task panicked")
+        .expect("Router returned an error");
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert!(!response.headers().contains_key("grpc-status"));
+    let mut body = response.into_body();
+    assert_eq!(next_frame(&mut body).await.into_data().unwrap(), "[0]");
+
+    // 乱序到达的数据块等缺口补齐后按序发出
+    deliver_chunks(&manager, &request_id, &[2], 3).await;
+    assert!(
+        timeout(Duration::from_millis(100), body.frame())
+            .await
+            .is_err(),
+        "Chunk 2 must not be emitted before chunk 1"
+    );
+    deliver_chunks(&manager, &request_id, &[1], 3).await;
+    assert_eq!(next_frame(&mut body).await.into_data().unwrap(), "[1]");
+    assert_eq!(next_frame(&mut body).await.into_data().unwrap(), "[2]");
+
+    // 最后一个数据块的状态头作为 trailers 发出
+    deliver_chunks(&manager, &request_id, &[3], 3).await;
+    assert_eq!(next_frame(&mut body).await.into_data().unwrap(), "[3]");
+    let trailers = next_frame(&mut body)
+        .await
+        .into_trailers()
+        .expect("Expected trailers");
+    assert_eq!(trailers.get("grpc-status").unwrap(), "0");
+    assert!(body.frame().await.is_none());
+    assert_eq!(manager.streaming_response_count().await, 0);
+}
+
+#[tokio::test]
+async fn test_dropping_stream_body_cancels_request() {
+    let (router, manager, mut request_rx) = setup_router(Config::default()).await;
+    let forwarding = tokio::spawn(router.oneshot(download_request()));
+
+    let MessageType::Request(request) = next_message(&mut request_rx).await else {
+        panic!("Expected a forward request");
+    };
+    deliver_chunks(&manager, &request.request_id, &[0], 3).await;
+
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Timeout waiting for response")
+        .expect("Router task panicked")
+        .expect("Router returned an error");
+
+    // 调用方在流结束前断开，微服务收到取消通知
+    drop(response);
+    let MessageType::Cancel(cancel) = next_message(&mut request_rx).await else {
+        panic!("Expected a cancel message");
+    };
+    assert_eq!(cancel.request_id, request.request_id);
+    assert_eq!(manager.streaming_response_count().await, 0);
+}
+
+#[tokio::test]
+async fn test_oversized_forwarded_stream_ends_with_resource_exhausted() {
+    let mut config = Config::default();
+    config.reverse_connection.max_response_size = 8;
+    let (router, manager, mut request_rx) = setup_router(config).await;
+    let forwarding = tokio::spawn(router.oneshot(download_request()));
+
+    let MessageType::Request(request) = next_message(&mut request_rx).await else {
+        panic!("Expected a forward request");
+    };
+    deliver_chunks(&manager, &request.request_id, &[0, 1], 3).await;
+
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Timeout waiting for response")
+        .expect("Router task panicked")
+        .expect("Router returned an error");
+    let mut body = response.into_body();
+    assert_eq!(next_frame(&mut body).await.into_data().unwrap(), "[0]");
+    assert_eq!(next_frame(&mut body).await.into_data().unwrap(), "[1]");
+
+    // 已经开始返回的响应以 RESOURCE_EXHAUSTED trailers 结束
+    deliver_chunks(&manager, &request.request_id, &[2], 3).await;
+    let trailers = next_frame(&mut body)
+        .await
+        .into_trailers()
+        .expect("Expected trailers");
+    assert_eq!(trailers.get("grpc-status").unwrap(), "8");
+    assert!(body.frame().await.is_none());
+    assert_eq!(manager.streaming_response_count().await, 0);
+}
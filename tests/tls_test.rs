@@ -0,0 +1,131 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::path::Path;
+
+use rcgen::{BasicConstraints, CertificateParams, CertifiedIssuer, IsCa, KeyPair};
+use tokio::sync::oneshot;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
+
+use grpc_opizontas::config::{Config, TlsConfig};
+use grpc_opizontas::health::health_client::HealthClient;
+use grpc_opizontas::health::{HealthCheckRequest, health_check_response::ServingStatus};
+
+// 测试用 CA 及其签发的服务端、客户端证书
+struct TestPki {
+    ca_pem: String,
+    server_cert_pem: String,
+    server_key_pem: String,
+    client_cert_pem: String,
+    client_key_pem: String,
+}
+
+fn generate_pki() -> TestPki {
+    let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = CertifiedIssuer::self_signed(ca_params, KeyPair::generate().unwrap()).unwrap();
+
+    let server_key = KeyPair::generate().unwrap();
+    let server_cert = CertificateParams::new(vec!["localhost".to_string()])
+        .unwrap()
+        .signed_by(&server_key, &ca)
+        .unwrap();
+
+    let client_key = KeyPair::generate().unwrap();
+    let client_cert = CertificateParams::new(vec!["gateway-client".to_string()])
+        .unwrap()
+        .signed_by(&client_key, &ca)
+        .unwrap();
+
+    TestPki {
+        ca_pem: ca.pem(),
+        server_cert_pem: server_cert.pem(),
+        server_key_pem: server_key.serialize_pem(),
+        client_cert_pem: client_cert.pem(),
+        client_key_pem: client_key.serialize_pem(),
+    }
+}
+
+fn write(dir: &Path, name: &str, contents: &str) -> String {
+    let path = dir.join(name);
+    std::fs::write(&path, contents).expect("Failed to write PEM file");
+    path.to_string_lossy().into_owned()
+}
+
+async fn start_tls_gateway(tls: TlsConfig) -> (SocketAddr, oneshot::Sender<()>) {
+    let config = Config {
+        tls: Some(tls),
+        ..Default::default()
+    };
+
+    let (addr, shutdown_tx, _gateway) = common::spawn_gateway(config).await;
+    (addr, shutdown_tx)
+}
+
+async fn health_check(addr: SocketAddr, tls: ClientTlsConfig) -> Result<i32, String> {
+    let channel = Channel::from_shared(format!("https://localhost:{}", addr.port()))
+        .unwrap()
+        .tls_config(tls)
+        .map_err(|e| e.to_string())?
+        .connect()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    HealthClient::new(channel)
+        .check(HealthCheckRequest::default())
+        .await
+        .map(|response| response.into_inner().status)
+        .map_err(|e| e.to_string())
+}
+
+#[tokio::test]
+async fn test_tls_connection() {
+    let pki = generate_pki();
+    let dir = tempfile::tempdir().unwrap();
+
+    let (addr, _shutdown) = start_tls_gateway(TlsConfig {
+        cert_path: write(dir.path(), "server.pem", &pki.server_cert_pem),
+        key_path: write(dir.path(), "server.key", &pki.server_key_pem),
+        client_ca_path: None,
+    })
+    .await;
+
+    let client_tls = ClientTlsConfig::new()
+        .ca_certificate(Certificate::from_pem(&pki.ca_pem))
+        .domain_name("localhost");
+
+    let status = health_check(addr, client_tls)
+        .await
+        .expect("TLS health check should succeed");
+    assert_eq!(status, ServingStatus::Serving as i32);
+}
+
+#[tokio::test]
+async fn test_mtls_requires_client_certificate() {
+    let pki = generate_pki();
+    let dir = tempfile::tempdir().unwrap();
+
+    let (addr, _shutdown) = start_tls_gateway(TlsConfig {
+        cert_path: write(dir.path(), "server.pem", &pki.server_cert_pem),
+        key_path: write(dir.path(), "server.key", &pki.server_key_pem),
+        client_ca_path: Some(write(dir.path(), "client-ca.pem", &pki.ca_pem)),
+    })
+    .await;
+
+    let base_tls = ClientTlsConfig::new()
+        .ca_certificate(Certificate::from_pem(&pki.ca_pem))
+        .domain_name("localhost");
+
+    // 未提供客户端证书时被拒绝
+    assert!(health_check(addr, base_tls.clone()).await.is_err());
+
+    // 提供 CA 签发的客户端证书时正常访问
+    let client_tls = base_tls.identity(Identity::from_pem(
+        &pki.client_cert_pem,
+        &pki.client_key_pem,
+    ));
+    let status = health_check(addr, client_tls)
+        .await
+        .expect("mTLS health check should succeed");
+    assert_eq!(status, ServingStatus::Serving as i32);
+}
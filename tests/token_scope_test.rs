@@ -0,0 +1,185 @@
+mod common;
+
+use std::collections::HashMap;
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Code, Request};
+
+use grpc_opizontas::config::{Config, SecurityConfig};
+use grpc_opizontas::registry::registry_service_client::RegistryServiceClient;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::registry::{
+    ConnectionMessage, ConnectionRegister, DrainInstanceRequest, RegisterRequest,
+    connection_message::MessageType,
+};
+use grpc_opizontas::services::{MyRegistryService, ServiceHealthStatus};
+
+const ADMIN_TOKEN: &str = "admin-token";
+const POST_TOKEN: &str = "post-token";
+
+fn scoped_config() -> Config {
+    let mut config = Config::default();
+    config.security.tokens = vec![ADMIN_TOKEN.to_string()];
+    config.security.scoped_tokens = HashMap::from([(
+        POST_TOKEN.to_string(),
+        vec!["post.*".to_string(), "user.UserService".to_string()],
+    )]);
+    config
+}
+
+fn register_request(token: &str, services: &[&str]) -> Request<RegisterRequest> {
+    Request::new(RegisterRequest {
+        api_key: token.to_string(),
+        address: "http://127.0.0.1:50400".to_string(),
+        services: services.iter().map(|s| s.to_string()).collect(),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_security_config_parses_plain_and_scoped_tokens() {
+    // 旧格式只有 tokens 列表，仍然有效
+    let plain: SecurityConfig = toml::from_str(r#"tokens = ["a", "b"]"#).unwrap();
+    assert_eq!(plain.tokens, vec!["a", "b"]);
+    assert!(plain.scoped_tokens.is_empty());
+
+    let scoped: SecurityConfig = toml::from_str(
+        r#"
+        tokens = []
+
+        [scoped_tokens]
+        "post-token" = ["post.*"]
+        "#,
+    )
+    .unwrap();
+    assert_eq!(scoped.scoped_tokens[POST_TOKEN], vec!["post.*"]);
+}
+
+#[tokio::test]
+async fn test_scoped_token_register_allowed_and_denied() {
+    let config = scoped_config();
+    assert!(config.token_allows_service(POST_TOKEN, "post.PostService"));
+    assert!(!config.token_allows_service(POST_TOKEN, "user.AdminService"));
+    assert!(config.token_allows_service(ADMIN_TOKEN, "anything.AtAll"));
+
+    let registry_service = MyRegistryService::new(config);
+
+    registry_service
+        .register(register_request(
+            POST_TOKEN,
+            &["post.PostService", "user.UserService"],
+        ))
+        .await
+        .expect("In-scope services should register");
+    assert!(
+        registry_service
+            .get_service_info("post.PostService")
+            .is_some()
+    );
+
+    // 任一服务越界则整个注册被拒绝
+    let status = registry_service
+        .register(register_request(
+            POST_TOKEN,
+            &["post.CommentService", "billing.BillingService"],
+        ))
+        .await
+        .expect_err("Out-of-scope service should be rejected");
+    assert_eq!(status.code(), Code::PermissionDenied);
+    assert!(
+        registry_service
+            .get_service_info("post.CommentService")
+            .is_none()
+    );
+    assert!(
+        registry_service
+            .get_service_info("billing.BillingService")
+            .is_none()
+    );
+
+    // 未限定范围的 token 可以注册任意服务
+    registry_service
+        .register(register_request(ADMIN_TOKEN, &["billing.BillingService"]))
+        .await
+        .expect("Unscoped token should register any service");
+
+    let status = registry_service
+        .register(register_request("unknown-token", &["post.PostService"]))
+        .await
+        .expect_err("Unknown token should be rejected");
+    assert_eq!(status.code(), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn test_scoped_token_drain_denied() {
+    let registry_service = MyRegistryService::new(scoped_config());
+    let instance_id = registry_service
+        .register(register_request(ADMIN_TOKEN, &["billing.BillingService"]))
+        .await
+        .expect("Unscoped token should register any service")
+        .into_inner()
+        .instance_id;
+
+    let drain_request = |token: &str, service: &str| {
+        Request::new(DrainInstanceRequest {
+            api_key: token.to_string(),
+            service_name: service.to_string(),
+            instance_id: instance_id.clone(),
+            draining: true,
+        })
+    };
+
+    // 限定范围的 token 不能排空越界服务的实例
+    let status = registry_service
+        .drain_instance(drain_request(POST_TOKEN, "billing.BillingService"))
+        .await
+        .expect_err("Out-of-scope drain should be rejected");
+    assert_eq!(status.code(), Code::PermissionDenied);
+    assert_eq!(
+        registry_service
+            .get_service_info("billing.BillingService")
+            .unwrap()
+            .health_status,
+        ServiceHealthStatus::Healthy
+    );
+
+    registry_service
+        .drain_instance(drain_request(ADMIN_TOKEN, "billing.BillingService"))
+        .await
+        .expect("Unscoped token should drain any service");
+}
+
+#[tokio::test]
+async fn test_scoped_token_reverse_connection_denied() {
+    let config = scoped_config();
+
+    let (gateway_addr, shutdown_tx, gateway) = common::spawn_gateway(config).await;
+
+    let channel = common::connect(gateway_addr).await;
+    let mut registry_client = RegistryServiceClient::new(channel);
+
+    let (reverse_tx, reverse_rx) = mpsc::channel(4);
+    reverse_tx
+        .send(ConnectionMessage {
+            message_type: Some(MessageType::Register(ConnectionRegister {
+                api_key: POST_TOKEN.to_string(),
+                services: vec!["billing.BillingService".to_string()],
+                connection_id: String::new(),
+                weight: 1,
+            })),
+        })
+        .await
+        .unwrap();
+    let status = registry_client
+        .establish_connection(ReceiverStream::new(reverse_rx))
+        .await
+        .expect_err("Out-of-scope reverse connection should be rejected");
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    let _ = shutdown_tx.send(());
+    gateway
+        .await
+        .expect("Gateway task panicked")
+        .expect("Gateway failed");
+}
@@ -0,0 +1,109 @@
+use std::sync::{Arc, Mutex};
+
+use tonic::{Code, Request};
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::registry_service_server::RegistryService;
+use grpc_opizontas::registry::{ListServicesRequest, RegisterRequest};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::registry::{AuthError, TokenClaims, TokenValidator};
+
+const EXTERNAL_TOKEN: &str = "external-token";
+
+// 模拟外部认证服务：只接受 EXTERNAL_TOKEN，并拒绝 forbidden 开头的服务
+#[derive(Debug, Default)]
+struct MockValidator {
+    calls: Mutex<Vec<(String, Option<String>)>>,
+}
+
+#[tonic::async_trait]
+impl TokenValidator for MockValidator {
+    async fn validate(&self, token: &str, service: Option<&str>) -> Result<TokenClaims, AuthError> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push((token.to_string(), service.map(str::to_string)));
+        if token != EXTERNAL_TOKEN {
+            return Err(AuthError::InvalidToken);
+        }
+        if let Some(service) = service.filter(|service| service.starts_with("forbidden")) {
+            return Err(AuthError::ServiceNotAllowed {
+                service: service.to_string(),
+            });
+        }
+        Ok(TokenClaims {
+            subject: "external-subject".to_string(),
+        })
+    }
+}
+
+// 配置中不包含任何 token，校验结果完全取决于注入的校验器
+fn service_with(validator: Arc<MockValidator>) -> MyRegistryService {
+    MyRegistryService::new(Config::default()).with_token_validator(validator)
+}
+
+fn register_request(token: &str, service: &str) -> Request<RegisterRequest> {
+    Request::new(RegisterRequest {
+        api_key: token.to_string(),
+        address: "http://127.0.0.1:50100".to_string(),
+        services: vec![service.to_string()],
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_custom_validator_accepts_token() {
+    let validator = Arc::new(MockValidator::default());
+    let service = service_with(validator.clone());
+
+    service
+        .register(register_request(EXTERNAL_TOKEN, "orders.OrderService"))
+        .await
+        .expect("Token accepted by the validator should register");
+    assert!(service.registry.contains_key("orders.OrderService"));
+
+    let services = service
+        .list_services(Request::new(ListServicesRequest {
+            api_key: EXTERNAL_TOKEN.to_string(),
+        }))
+        .await
+        .expect("Token accepted by the validator should list services")
+        .into_inner()
+        .services;
+    assert_eq!(services.len(), 1);
+
+    // 注册时按服务逐个校验
+    let calls = validator.calls.lock().unwrap().clone();
+    assert!(calls.contains(&(
+        EXTERNAL_TOKEN.to_string(),
+        Some("orders.OrderService".to_string())
+    )));
+}
+
+#[tokio::test]
+async fn test_custom_validator_rejects() {
+    let validator = Arc::new(MockValidator::default());
+    let service = service_with(validator);
+
+    let status = service
+        .register(register_request("unknown-token", "orders.OrderService"))
+        .await
+        .expect_err("Token rejected by the validator should fail");
+    assert_eq!(status.code(), Code::Unauthenticated);
+
+    let status = service
+        .register(register_request(EXTERNAL_TOKEN, "forbidden.AdminService"))
+        .await
+        .expect_err("Service rejected by the validator should fail");
+    assert_eq!(status.code(), Code::PermissionDenied);
+    assert!(status.message().contains("forbidden.AdminService"));
+
+    let status = service
+        .list_services(Request::new(ListServicesRequest {
+            api_key: "unknown-token".to_string(),
+        }))
+        .await
+        .expect_err("Token rejected by the validator should not list services");
+    assert_eq!(status.code(), Code::Unauthenticated);
+    assert!(service.registry.is_empty());
+}
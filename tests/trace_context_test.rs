@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::{ForwardResponse, connection_message::MessageType};
+use grpc_opizontas::services::MyRegistryService;
+use grpc_opizontas::services::router::DynamicRouter;
+
+const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+const TRACESTATE: &str = "vendor=opaque-value";
+
+#[tokio::test]
+async fn test_trace_context_survives_reverse_connection_round_trip() {
+    let registry_service = MyRegistryService::new(Config::default());
+    let reverse_manager = registry_service.reverse_connection_manager.clone();
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        Config::default(),
+        reverse_manager.clone(),
+    )
+    .expect("Failed to create router");
+
+    let (request_tx, mut request_rx) = mpsc::channel(16);
+    reverse_manager
+        .register_connection(
+            "trace-conn".to_string(),
+            vec!["TraceService".to_string()],
+            1,
+            request_tx,
+        )
+        .await
+        .expect("Failed to register connection");
+
+    let request = http::Request::builder()
+        .method("POST")
+        .uri("/trace.TraceService/Call")
+        .header("content-type", "application/grpc")
+        .header("traceparent", TRACEPARENT)
+        .header("tracestate", TRACESTATE)
+        .body(Full::new(Bytes::from_static(b"payload")))
+        .unwrap();
+    let forwarding = tokio::spawn(router.oneshot(request));
+
+    // 微服务侧收到的请求头与入站请求一致
+    let message = timeout(Duration::from_secs(1), request_rx.recv())
+        .await
+        .expect("Timeout waiting for forwarded request")
+        .expect("Request channel closed");
+    let Some(MessageType::Request(forward_request)) = message.message_type else {
+        panic!("Expected a forwarded request");
+    };
+    assert_eq!(
+        forward_request
+            .headers
+            .get("traceparent")
+            .map(String::as_str),
+        Some(TRACEPARENT)
+    );
+    assert_eq!(
+        forward_request
+            .headers
+            .get("tracestate")
+            .map(String::as_str),
+        Some(TRACESTATE)
+    );
+
+    // 微服务响应不带 trace context，网关应写回入站值
+    reverse_manager
+        .handle_response(ForwardResponse {
+            request_id: forward_request.request_id,
+            status_code: 200,
+            payload: b"ok".to_vec(),
+            ..Default::default()
+        })
+        .await;
+
+    let response = timeout(Duration::from_secs(1), forwarding)
+        .await
+        .expect("Timeout waiting for response")
+        .expect("Router task panicked")
+        .expect("Router returned an error");
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(
+        response
+            .headers()
+            .get("traceparent")
+            .and_then(|v| v.to_str().ok()),
+        Some(TRACEPARENT)
+    );
+    assert_eq!(
+        response
+            .headers()
+            .get("tracestate")
+            .and_then(|v| v.to_str().ok()),
+        Some(TRACESTATE)
+    );
+}
@@ -0,0 +1,131 @@
+#![cfg(unix)]
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use hyper_util::rt::TokioIo;
+use tokio::net::UnixStream;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+use tonic::transport::{Channel, Endpoint};
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::registry::RegisterRequest;
+use grpc_opizontas::registry::registry_service_client::RegistryServiceClient;
+use grpc_opizontas::server;
+
+const TOKEN: &str = "uds-test-token";
+
+fn start_gateway(socket: &Path) -> (oneshot::Sender<()>, JoinHandle<Result<(), String>>) {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.server.address = format!("unix:{}", socket.display());
+    config.server.shutdown_grace_period = 1;
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let gateway = tokio::spawn(async move {
+        server::run(config, async move {
+            let _ = shutdown_rx.await;
+        })
+        .await
+        .map_err(|e| e.to_string())
+    });
+    (shutdown_tx, gateway)
+}
+
+// 通过 unix socket 连接网关，URI 只用于填充 :authority
+async fn connect(socket: PathBuf) -> Channel {
+    Endpoint::from_static("http://localhost")
+        .connect_with_connector(tower::service_fn(move |_| {
+            let socket = socket.clone();
+            async move { Ok::<_, std::io::Error>(TokioIo::new(UnixStream::connect(socket).await?)) }
+        }))
+        .await
+        .expect("Failed to connect over unix socket")
+}
+
+async fn stop_gateway(shutdown_tx: oneshot::Sender<()>, gateway: JoinHandle<Result<(), String>>) {
+    let _ = shutdown_tx.send(());
+    timeout(Duration::from_secs(5), gateway)
+        .await
+        .expect("Gateway did not stop")
+        .expect("Gateway task panicked")
+        .expect("Gateway returned an error");
+}
+
+#[tokio::test]
+async fn test_register_over_unix_socket() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("gateway.sock");
+    let (shutdown_tx, gateway) = start_gateway(&socket);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let mut client = RegistryServiceClient::new(connect(socket.clone()).await);
+    let response = client
+        .register(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: "http://127.0.0.1:50600".to_string(),
+            services: vec!["uds.TestService".to_string()],
+            ..Default::default()
+        })
+        .await
+        .expect("Registration over unix socket failed")
+        .into_inner();
+    assert!(response.success);
+    drop(client);
+
+    // 停机后删除 socket 文件
+    stop_gateway(shutdown_tx, gateway).await;
+    assert!(
+        !socket.exists(),
+        "Socket file should be removed on shutdown"
+    );
+}
+
+#[tokio::test]
+async fn test_stale_socket_file_is_replaced() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("gateway.sock");
+    // 模拟上次未正常退出遗留的 socket 文件
+    drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+    assert!(socket.exists());
+
+    let (shutdown_tx, gateway) = start_gateway(&socket);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let mut client = RegistryServiceClient::new(connect(socket.clone()).await);
+    client
+        .register(RegisterRequest {
+            api_key: TOKEN.to_string(),
+            address: "http://127.0.0.1:50601".to_string(),
+            services: vec!["uds.StaleService".to_string()],
+            ..Default::default()
+        })
+        .await
+        .expect("Registration over unix socket failed");
+    drop(client);
+    stop_gateway(shutdown_tx, gateway).await;
+}
+
+#[tokio::test]
+async fn test_socket_in_use_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("gateway.sock");
+    let (shutdown_tx, gateway) = start_gateway(&socket);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // 第二个网关不能抢占正在使用的 socket
+    let (_second_tx, second) = start_gateway(&socket);
+    let error = timeout(Duration::from_secs(2), second)
+        .await
+        .expect("Second gateway should fail immediately")
+        .expect("Gateway task panicked")
+        .expect_err("Second gateway should not start");
+    assert!(error.contains("already in use"), "{error}");
+    assert!(
+        socket.exists(),
+        "Socket of the running gateway must be kept"
+    );
+
+    stop_gateway(shutdown_tx, gateway).await;
+}
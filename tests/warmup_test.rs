@@ -0,0 +1,159 @@
+mod common;
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use tower::ServiceExt;
+
+use grpc_opizontas::config::Config;
+use grpc_opizontas::services::client_manager::GrpcClientManager;
+use grpc_opizontas::services::registry::ActiveHealthChecker;
+use grpc_opizontas::services::router::DynamicRouter;
+use grpc_opizontas::services::{MyRegistryService, ServiceHealthStatus};
+
+const TOKEN: &str = "warmup-test-token";
+const SERVICE: &str = "WarmupService";
+const WARMUP: Duration = Duration::from_millis(300);
+
+// 启动一个对所有请求返回成功的后端，健康检查返回 UNIMPLEMENTED（可达即视为健康）
+async fn start_backend() -> SocketAddr {
+    let (listener, addr) = common::bind().await;
+    let backend = tower::service_fn(|req: http::Request<_>| async move {
+        let status = if req.uri().path().starts_with("/grpc.health.v1.Health/") {
+            "12"
+        } else {
+            "0"
+        };
+        let response = http::Response::builder()
+            .header("content-type", "application/grpc")
+            .header("grpc-status", status)
+            .body(Full::new(Bytes::new()))
+            .expect("Failed to build response");
+        Ok::<_, Infallible>(response)
+    });
+
+    common::serve_fallback(listener, backend);
+    addr
+}
+
+fn warmup_config() -> Config {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    config.router.initial_routing_delay_ms = WARMUP.as_millis() as u64;
+    config
+}
+
+fn instance_status(registry_service: &MyRegistryService) -> ServiceHealthStatus {
+    registry_service
+        .get_service_info(SERVICE)
+        .expect("Service should stay registered")
+        .health_status
+}
+
+// 返回转发结果的 grpc-status
+async fn call(router: &DynamicRouter) -> String {
+    let request = http::Request::builder()
+        .method("POST")
+        .uri(format!("/warmup.{SERVICE}/Call"))
+        .header("content-type", "application/grpc")
+        .body(Full::new(Bytes::new()))
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    response
+        .headers()
+        .get("grpc-status")
+        .and_then(|v| v.to_str().ok())
+        .expect("Missing grpc-status")
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_new_instance_not_routed_until_warmup_elapses() {
+    let backend_addr = start_backend().await;
+
+    let config = warmup_config();
+    let registry_service = MyRegistryService::new(config.clone());
+    let router = DynamicRouter::new(
+        registry_service.registry.clone(),
+        config,
+        registry_service.reverse_connection_manager.clone(),
+    )
+    .expect("Failed to create router");
+    let address = format!("http://{backend_addr}");
+    common::register(&registry_service, TOKEN, &address, &[SERVICE]).await;
+
+    // 预热期内实例不参与路由，服务没有健康实例时返回 UNAVAILABLE
+    assert_eq!(
+        instance_status(&registry_service),
+        ServiceHealthStatus::Warming
+    );
+    assert_eq!(call(&router).await, "14");
+    assert!(
+        !registry_service
+            .get_healthy_services()
+            .contains_key(SERVICE)
+    );
+
+    // 预热期间的心跳不会提前结束预热
+    common::register(&registry_service, TOKEN, &address, &[SERVICE]).await;
+    assert_eq!(
+        instance_status(&registry_service),
+        ServiceHealthStatus::Warming
+    );
+    assert_eq!(call(&router).await, "14");
+
+    tokio::time::sleep(WARMUP + Duration::from_millis(200)).await;
+    assert_eq!(
+        instance_status(&registry_service),
+        ServiceHealthStatus::Healthy
+    );
+    assert_eq!(call(&router).await, "0");
+}
+
+#[tokio::test]
+async fn test_successful_health_check_ends_warmup() {
+    let backend_addr = start_backend().await;
+
+    let mut config = warmup_config();
+    config.router.initial_routing_delay_ms = 60_000;
+    let registry_service = Arc::new(MyRegistryService::new(config.clone()));
+    common::register(
+        &registry_service,
+        TOKEN,
+        &format!("http://{backend_addr}"),
+        &[SERVICE],
+    )
+    .await;
+    assert_eq!(
+        instance_status(&registry_service),
+        ServiceHealthStatus::Warming
+    );
+
+    let checker = ActiveHealthChecker::new(
+        registry_service.clone(),
+        GrpcClientManager::default(),
+        &config,
+    );
+    checker.check_all().await;
+    assert_eq!(
+        instance_status(&registry_service),
+        ServiceHealthStatus::Healthy
+    );
+}
+
+#[tokio::test]
+async fn test_no_warmup_by_default() {
+    let mut config = Config::default();
+    config.security.tokens = vec![TOKEN.to_string()];
+    let registry_service = MyRegistryService::new(config);
+    common::register(&registry_service, TOKEN, "http://127.0.0.1:1", &[SERVICE]).await;
+
+    assert_eq!(
+        instance_status(&registry_service),
+        ServiceHealthStatus::Healthy
+    );
+}